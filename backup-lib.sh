@@ -438,9 +438,10 @@ create_backup_archive() {
     
     report_progress "archiving" 0 "$total_files" '{"total_files":'$total_files'}'
     
-    # Build tar command
-    local tar_cmd="tar -czf"
-    local tar_args=("$output_file")
+    # Build tar command; BACKUP_COMPRESS_PROGRAM (e.g. "zstd -3 -T0") picks
+    # the compressor, matching the output file's extension
+    local tar_cmd="tar"
+    local tar_args=("-I" "${BACKUP_COMPRESS_PROGRAM:-gzip}" "-cf" "$output_file")
     
     # Add exclusions
     if [[ -n "$exclude_file" && -f "$exclude_file" ]]; then
@@ -20,18 +20,28 @@ echo "Output directory: $BACKUP_DIR"
 
 # Create backup directory if it doesn't exist
 mkdir -p "$BACKUP_DIR"
+# tar runs from the home directory, so a relative path is resolved here
+BACKUP_DIR=$(cd "$BACKUP_DIR" && pwd)
 
 # Generate timestamp for the backup
 TIMESTAMP=$(date +%Y%m%d_%H%M%S)
 HOSTNAME=$(hostname)
 
+# Compressor chosen in the UI (gzip unless told otherwise)
+COMPRESSION_EXT="${BACKUP_COMPRESSION_EXT:-gz}"
+COMPRESS_PROGRAM="${BACKUP_COMPRESS_PROGRAM:-gzip -6}"
+if ! command -v "${COMPRESS_PROGRAM%% *}" &>/dev/null; then
+    echo -e "${RED}Error: ${COMPRESS_PROGRAM%% *} is not installed${NC}" >&2
+    exit 1
+fi
+
 # Determine which script to use and the output filename
 if [ "$MODE" = "secure" ]; then
     BACKUP_SCRIPT="./backup-profile-secure.sh"
-    ARCHIVE_NAME="backup_${HOSTNAME}_${TIMESTAMP}_secure.tar.gz"
+    ARCHIVE_NAME="backup_${HOSTNAME}_${TIMESTAMP}_secure.tar.${COMPRESSION_EXT}"
 else
     BACKUP_SCRIPT="./backup-profile-enhanced.sh"
-    ARCHIVE_NAME="backup_${HOSTNAME}_${TIMESTAMP}_complete.tar.gz"
+    ARCHIVE_NAME="backup_${HOSTNAME}_${TIMESTAMP}_complete.tar.${COMPRESSION_EXT}"
 fi
 
 # Check if the backup script exists
@@ -78,6 +88,7 @@ BACKUP_ITEMS=(
     ".config/kitty"
     ".local/share/applications"
     ".local/bin"
+    ".config/systemd/user"
 )
 
 # Additional items for complete mode
@@ -93,25 +104,96 @@ if [ "$MODE" = "complete" ]; then
     echo -e "${YELLOW}Warning: Complete mode includes sensitive files${NC}"
 fi
 
-# Copy files to temp directory
+# Extended attributes (SELinux labels, file capabilities) and POSIX ACLs
+# go into the archive with each file; tar keeps hard links as links
+TAR_METADATA=(--xattrs --xattrs-include='*' --acls --selinux)
+
+# Bytes tar has fed the compressor, about every 10MB, for the UI's
+# progress bar
+TAR_PROGRESS=()
+if [ "${BACKUP_PROGRESS:-}" = "yes" ]; then
+    TAR_PROGRESS=(--checkpoint=1000 "--checkpoint-action=echo=Written %{w}T")
+fi
+
+# Passes the archive on no faster than BACKUP_RATE_LIMIT bytes a second,
+# when the UI caps the write rate
+limit_rate() {
+    if [ -n "${BACKUP_RATE_LIMIT:-}" ]; then
+        pv --quiet --rate-limit "$BACKUP_RATE_LIMIT"
+    else
+        cat
+    fi
+}
+
 cd "$HOME"
-for item in "${BACKUP_ITEMS[@]}"; do
-    if [ -e "$item" ]; then
-        echo "Processing: $item"
-        # Create parent directories in temp
-        parent=$(dirname "$item")
-        if [ "$parent" != "." ]; then
-            mkdir -p "$TEMP_DIR/$parent"
+if [ -n "${BACKUP_FILE_LIST:-}" ] && [ -f "$BACKUP_FILE_LIST" ]; then
+    # Selected items with exclusions already applied by the UI
+    # (NUL-separated, home-relative, every directory listed explicitly),
+    # archived straight from the home directory so no copy can drop metadata
+    echo "Processing: $(tr -cd '\0' < "$BACKUP_FILE_LIST" | wc -c) paths selected in the UI"
+    TAR_SOURCES=(--ignore-failed-read --null --no-recursion -C "$HOME" -T "$BACKUP_FILE_LIST" --recursion)
+
+    # Symbolic links the UI was told to follow: their targets are listed by
+    # absolute path and renamed after the links by these expressions
+    if [ -n "${BACKUP_FILE_TRANSFORMS:-}" ] && [ -f "$BACKUP_FILE_TRANSFORMS" ]; then
+        mapfile -d '' -t FOLLOWED < "$BACKUP_FILE_TRANSFORMS"
+        TAR_SOURCES=("${FOLLOWED[@]/#/--transform=}" "${TAR_SOURCES[@]}")
+    fi
+
+    # Crontab and enabled-services list collected by the UI, stored under
+    # its usual name
+    if [ -n "${BACKUP_SYSTEM_STATE_DIR:-}" ] && [ -d "$BACKUP_SYSTEM_STATE_DIR" ]; then
+        echo "Processing: system state"
+        STATE_NAME=$(basename "$BACKUP_SYSTEM_STATE_DIR")
+        TAR_SOURCES+=(--transform "s,^${STATE_NAME},.backup-system-state," -C "$(dirname "$BACKUP_SYSTEM_STATE_DIR")" "$STATE_NAME")
+    fi
+else
+    for item in "${BACKUP_ITEMS[@]}"; do
+        if [ -e "$item" ]; then
+            echo "Processing: $item"
+            # Create parent directories in temp
+            parent=$(dirname "$item")
+            if [ "$parent" != "." ]; then
+                mkdir -p "$TEMP_DIR/$parent"
+            fi
+            # Copy the item with its attributes, ACLs and hard links
+            cp -a "$item" "$TEMP_DIR/$parent/" 2>/dev/null || true
         fi
-        # Copy the item
-        cp -r "$item" "$TEMP_DIR/$parent/" 2>/dev/null || true
+    done
+
+    if [ -n "${BACKUP_SYSTEM_STATE_DIR:-}" ] && [ -d "$BACKUP_SYSTEM_STATE_DIR" ]; then
+        echo "Processing: system state"
+        cp -r "$BACKUP_SYSTEM_STATE_DIR" "$TEMP_DIR/.backup-system-state"
     fi
-done
+    # Store members as home-relative paths without a leading ./
+    TAR_SOURCES=(-C "$TEMP_DIR" --transform 's,^\./,,' .)
+fi
 
 # Create the archive
 echo "Creating archive: $ARCHIVE_NAME"
-cd "$TEMP_DIR"
-tar czf "$BACKUP_DIR/$ARCHIVE_NAME" .
+# Keep the archive private from the moment it is created
+umask 077
+# tar exits 1 for files that changed while being read, which doesn't fail
+# the backup
+if [ -n "${BACKUP_GPG_RECIPIENTS:-}" ]; then
+    # Encrypt to the public keys chosen in the UI, by fingerprint from the
+    # user's own keyring, so gpg is told to trust them as given
+    RECIPIENT_ARGS=()
+    for fingerprint in $BACKUP_GPG_RECIPIENTS; do
+        RECIPIENT_ARGS+=(--recipient "$fingerprint")
+    done
+    ARCHIVE_NAME="${ARCHIVE_NAME}.gpg"
+    echo "Encrypting to $(( ${#RECIPIENT_ARGS[@]} / 2 )) key(s)"
+    { tar -I "$COMPRESS_PROGRAM" -cf - "${TAR_METADATA[@]}" "${TAR_PROGRESS[@]}" "${TAR_SOURCES[@]}" || [ $? -eq 1 ]; } \
+        | limit_rate \
+        | gpg --batch --yes --quiet --trust-model always --encrypt "${RECIPIENT_ARGS[@]}" \
+            --output "$BACKUP_DIR/$ARCHIVE_NAME"
+elif [ -n "${BACKUP_RATE_LIMIT:-}" ]; then
+    { tar -I "$COMPRESS_PROGRAM" -cf - "${TAR_METADATA[@]}" "${TAR_PROGRESS[@]}" "${TAR_SOURCES[@]}" || [ $? -eq 1 ]; } \
+        | limit_rate > "$BACKUP_DIR/$ARCHIVE_NAME"
+else
+    tar -I "$COMPRESS_PROGRAM" -cf "$BACKUP_DIR/$ARCHIVE_NAME" "${TAR_METADATA[@]}" "${TAR_PROGRESS[@]}" "${TAR_SOURCES[@]}" || [ $? -eq 1 ]
+fi
 
 # Set restrictive permissions on the archive
 chmod 600 "$BACKUP_DIR/$ARCHIVE_NAME"
@@ -123,8 +205,8 @@ echo -e "${GREEN}Backup completed successfully!${NC}"
 echo "Archive: $BACKUP_DIR/$ARCHIVE_NAME"
 echo "Size: $SIZE"
 
-# If in complete mode, remind about security
-if [ "$MODE" = "complete" ]; then
+# If in complete mode and not encrypted, remind about security
+if [ "$MODE" = "complete" ] && [ -z "${BACKUP_GPG_RECIPIENTS:-}" ]; then
     echo -e "${YELLOW}==== SECURITY REMINDER ====${NC}"
     echo "This archive contains sensitive data (credentials, keys, etc.)"
     echo "Please encrypt it or store it securely"
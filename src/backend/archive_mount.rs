@@ -0,0 +1,980 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::ffi::{CString, OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind, Read, Seek, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::extract;
+use crate::core::security::SecurePassword;
+use crate::core::types::Compression;
+
+/// Tar block size; headers are one block and data is padded to whole ones
+const BLOCK: u64 = 512;
+/// The kernel asks again this long after an answer; archives don't change
+const TTL_SECS: u64 = 3600;
+/// Largest GNU long name or PAX header read; tar's own are far smaller,
+/// so anything bigger is a damaged or hostile archive
+const MAX_EXTENSION_HEADER: u64 = 1024 * 1024;
+const ROOT: u64 = 1;
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// A file, directory or link in the archive
+#[derive(Debug)]
+struct Node {
+    parent: u64,
+    /// Permission bits, without any write bits
+    mode: u32,
+    mtime: i64,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Dir(BTreeMap<OsString, u64>),
+    /// Where the data starts in the uncompressed tarball, and its length
+    File { offset: u64, size: u64 },
+    Symlink(Vec<u8>),
+}
+
+/// What the tarball holds, by inode number, with where each file's data
+/// sits so it can be read in place
+#[derive(Debug)]
+pub struct TarIndex {
+    nodes: Vec<Node>,
+    bytes: u64,
+}
+
+/// A header's fields, after any GNU long name or PAX record before it
+struct Header {
+    path: Vec<u8>,
+    link: Vec<u8>,
+    mode: u32,
+    mtime: i64,
+    size: u64,
+    kind: u8,
+}
+
+impl TarIndex {
+    /// Index the uncompressed tarball in `tarball`, from its start
+    pub fn read(mut tarball: &File) -> Result<Self> {
+        tarball.rewind().context("Failed to read the archive")?;
+        let mut file = BufReader::new(tarball);
+        let mut index = Self {
+            nodes: vec![Node { parent: ROOT, mode: 0o555, mtime: 0, kind: Kind::Dir(BTreeMap::new()) }],
+            bytes: 0,
+        };
+        let mut hardlinks = Vec::new();
+        // Extension headers describing the entry after them
+        let mut long_name: Option<Vec<u8>> = None;
+        let mut long_link: Option<Vec<u8>> = None;
+        let mut pax = Vec::new();
+        let mut offset = 0u64;
+        let mut block = [0u8; BLOCK as usize];
+
+        loop {
+            match file.read_exact(&mut block) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e).context("Failed to read the archive"),
+            }
+            offset += BLOCK;
+            // Two zero blocks end the archive
+            if block.iter().all(|&b| b == 0) {
+                break;
+            }
+            let mut header = parse_header(&block)?;
+            let data = offset;
+            let padded = header.size.div_ceil(BLOCK) * BLOCK;
+
+            match header.kind {
+                b'L' | b'K' | b'x' => {
+                    if header.size > MAX_EXTENSION_HEADER {
+                        anyhow::bail!("Tar extension header of {} bytes is too large", header.size);
+                    }
+                    let mut content = vec![0u8; header.size as usize];
+                    file.read_exact(&mut content).context("Truncated archive")?;
+                    file.seek_relative((padded - header.size) as i64)?;
+                    offset += padded;
+                    match header.kind {
+                        b'L' => long_name = Some(until_nul(&content).to_vec()),
+                        b'K' => long_link = Some(until_nul(&content).to_vec()),
+                        _ => pax = pax_records(&content),
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            file.seek_relative(padded as i64)?;
+            offset += padded;
+
+            if let Some(name) = long_name.take() {
+                header.path = name;
+            }
+            if let Some(link) = long_link.take() {
+                header.link = link;
+            }
+            for (key, value) in std::mem::take(&mut pax) {
+                match key.as_slice() {
+                    b"path" => header.path = value,
+                    b"linkpath" => header.link = value,
+                    b"size" => header.size = std::str::from_utf8(&value).ok().and_then(|v| v.parse().ok()).unwrap_or(header.size),
+                    b"mtime" => {
+                        let seconds = std::str::from_utf8(&value).ok().and_then(|v| v.split('.').next()?.parse().ok());
+                        header.mtime = seconds.unwrap_or(header.mtime);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(components) = components_of(&header.path) else {
+                continue;
+            };
+            let mode = header.mode & 0o7555;
+            match header.kind {
+                // `./`, the home directory itself
+                b'5' if components.is_empty() => {
+                    index.nodes[0].mode = mode;
+                    index.nodes[0].mtime = header.mtime;
+                }
+                _ if components.is_empty() => {}
+                b'0' | 0 | b'7' => {
+                    index.bytes += header.size;
+                    index.insert(&components, mode, header.mtime, Kind::File { offset: data, size: header.size });
+                }
+                b'2' => {
+                    index.insert(&components, mode, header.mtime, Kind::Symlink(header.link));
+                }
+                b'5' => {
+                    let dir = index.directory(&components);
+                    index.nodes[dir as usize - 1].mode = mode;
+                    index.nodes[dir as usize - 1].mtime = header.mtime;
+                }
+                b'1' => hardlinks.push((components, header.link, mode, header.mtime)),
+                // Devices, fifos and sparse files aren't offered
+                other => debug!("Skipping {} (type {})", String::from_utf8_lossy(&header.path), other as char),
+            }
+        }
+
+        // A hard link reads the data of the file it names, stored earlier
+        for (components, target, mode, mtime) in hardlinks {
+            let found = components_of(&target).and_then(|target| index.find(&target));
+            match found.map(|ino| &index.nodes[ino as usize - 1].kind) {
+                Some(Kind::File { offset, size }) => {
+                    let kind = Kind::File { offset: *offset, size: *size };
+                    index.insert(&components, mode, mtime, kind);
+                }
+                _ => debug!("Skipping a hard link to {}", String::from_utf8_lossy(&target)),
+            }
+        }
+        Ok(index)
+    }
+
+    /// Entries in the archive, directories included
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn node(&self, ino: u64) -> Option<&Node> {
+        ino.checked_sub(1).and_then(|i| self.nodes.get(i as usize))
+    }
+
+    fn child(&self, parent: u64, name: &OsStr) -> Option<u64> {
+        match &self.node(parent)?.kind {
+            Kind::Dir(children) => children.get(name).copied(),
+            _ => None,
+        }
+    }
+
+    fn find(&self, components: &[OsString]) -> Option<u64> {
+        components.iter().try_fold(ROOT, |ino, name| self.child(ino, name))
+    }
+
+    /// The directory at `components`, created with its parents as needed
+    fn directory(&mut self, components: &[OsString]) -> u64 {
+        let mut ino = ROOT;
+        for name in components {
+            ino = match self.child(ino, name) {
+                Some(child) => child,
+                None => self.add(ino, name, 0o555, 0, Kind::Dir(BTreeMap::new())),
+            };
+        }
+        ino
+    }
+
+    fn insert(&mut self, components: &[OsString], mode: u32, mtime: i64, kind: Kind) {
+        let Some((name, parents)) = components.split_last() else {
+            return;
+        };
+        let parent = self.directory(parents);
+        // A later copy of a path replaces the earlier one, as tar does
+        match self.child(parent, name) {
+            Some(existing) => {
+                let node = &mut self.nodes[existing as usize - 1];
+                if !matches!(node.kind, Kind::Dir(_)) {
+                    *node = Node { parent, mode, mtime, kind };
+                }
+            }
+            None => {
+                self.add(parent, name, mode, mtime, kind);
+            }
+        }
+    }
+
+    fn add(&mut self, parent: u64, name: &OsStr, mode: u32, mtime: i64, kind: Kind) -> u64 {
+        self.nodes.push(Node { parent, mode, mtime, kind });
+        let ino = self.nodes.len() as u64;
+        if let Kind::Dir(children) = &mut self.nodes[parent as usize - 1].kind {
+            children.insert(name.to_os_string(), ino);
+        }
+        ino
+    }
+}
+
+fn parse_header(block: &[u8; BLOCK as usize]) -> Result<Header> {
+    let checksum = octal(&block[148..156]).context("Not a tar archive")?;
+    let sum: u64 = block.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 }).sum();
+    if sum != checksum {
+        anyhow::bail!("Corrupt tar header");
+    }
+    let mut path = until_nul(&block[0..100]).to_vec();
+    // POSIX ustar splits long names into a prefix; GNU tar's own format
+    // keeps other fields there
+    if &block[257..263] == b"ustar\0" {
+        let prefix = until_nul(&block[345..500]);
+        if !prefix.is_empty() {
+            path = [prefix, b"/", &path].concat();
+        }
+    }
+    Ok(Header {
+        path,
+        link: until_nul(&block[157..257]).to_vec(),
+        mode: octal(&block[100..108]).unwrap_or(0o644) as u32,
+        mtime: octal(&block[136..148]).unwrap_or(0) as i64,
+        size: number(&block[124..136]).context("Invalid size in tar header")?,
+        kind: block[156],
+    })
+}
+
+fn until_nul(field: &[u8]) -> &[u8] {
+    field.split(|&b| b == 0).next().unwrap_or_default()
+}
+
+fn octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(until_nul(field)).ok()?.trim();
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// Octal, or GNU's base-256 for sizes of 8 GiB and over
+fn number(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        return Some(field[1..].iter().fold(0u64, |n, &b| (n << 8) | b as u64));
+    }
+    octal(field)
+}
+
+/// `<length> <key>=<value>\n` records of a PAX header
+fn pax_records(content: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut records = Vec::new();
+    let mut rest = content;
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let Some(length) = std::str::from_utf8(&rest[..space]).ok().and_then(|l| l.parse::<usize>().ok()) else {
+            break;
+        };
+        if length <= space + 1 || length > rest.len() {
+            break;
+        }
+        let record = &rest[space + 1..length - 1];
+        if let Some(equals) = record.iter().position(|&b| b == b'=') {
+            records.push((record[..equals].to_vec(), record[equals + 1..].to_vec()));
+        }
+        rest = &rest[length..];
+    }
+    records
+}
+
+/// The parts of a member path, without `.`, leading `/` or `./`; None for
+/// paths climbing out of the archive
+fn components_of(path: &[u8]) -> Option<Vec<OsString>> {
+    let mut parts = Vec::new();
+    for part in path.split(|&b| b == b'/') {
+        match part {
+            b"" | b"." => {}
+            b".." => return None,
+            part => parts.push(OsStr::from_bytes(part).to_os_string()),
+        }
+    }
+    Some(parts)
+}
+
+/// The uncompressed tarball of an archive, decrypted and decompressed
+/// into a file without a name that is shredded when this is dropped, or
+/// the archive itself when it is neither. Having no name, the plaintext
+/// is never left behind by a crash and can't be opened by anyone else.
+pub struct StagedTar {
+    file: File,
+    temporary: bool,
+}
+
+impl StagedTar {
+    pub async fn stage(archive: &Path, password: Option<&SecurePassword>) -> Result<Self> {
+        let encrypted = archive.extension().is_some_and(|e| e == "gpg");
+        let compression = Compression::detect(archive);
+        if !encrypted && compression.is_none() {
+            let file = File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+            return Ok(Self { file, temporary: false });
+        }
+
+        let staged = Self { file: unnamed_file()?, temporary: true };
+        let output = staged.file.try_clone().context("Failed to open the staged archive")?;
+
+        // gpg's output goes to the decompressor, or is the tarball itself
+        let mut gpg = None;
+        let mut input = None;
+        if encrypted {
+            let password = password.context("Archive is encrypted but no password was provided")?;
+            let mut child = extract::spawn_decrypt(archive, password).await?;
+            if compression.is_some() {
+                input = Some(extract::decrypted_stdio(&mut child)?);
+            }
+            gpg = Some(child);
+        } else {
+            input = Some(Stdio::from(File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?));
+        }
+        let decompressed = match (compression, input) {
+            (Some(compression), Some(input)) => Some(
+                TokioCommand::new(compression.as_str())
+                    .arg("-dc")
+                    .stdin(input)
+                    .stdout(output)
+                    .stderr(Stdio::null())
+                    .status()
+                    .await
+                    .with_context(|| format!("Failed to run {}", compression.as_str()))?,
+            ),
+            _ => {
+                let mut stdout = gpg.as_mut().and_then(|gpg| gpg.stdout.take()).context("Failed to read gpg output")?;
+                let mut output = tokio::fs::File::from_std(output);
+                tokio::io::copy(&mut stdout, &mut output).await.context("Failed to write the decrypted archive")?;
+                output.flush().await?;
+                None
+            }
+        };
+        if let Some(gpg) = gpg {
+            if !gpg.wait_with_output().await?.status.success() {
+                anyhow::bail!("Failed to decrypt archive (wrong password?)");
+            }
+        }
+        if let Some(status) = decompressed.filter(|status| !status.success()) {
+            anyhow::bail!("Failed to decompress {} ({})", archive.display(), status);
+        }
+        Ok(staged)
+    }
+
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+impl Drop for StagedTar {
+    fn drop(&mut self) {
+        if self.temporary {
+            shred(&self.file);
+        }
+    }
+}
+
+/// Private directory the plaintext is staged in
+fn staging_dir() -> PathBuf {
+    dirs::data_local_dir().unwrap_or_else(std::env::temp_dir).join("backup-manager").join("mount")
+}
+
+/// A file only this process can reach, in a directory only the user can
+/// enter: an O_TMPFILE one where the filesystem has them, or else one
+/// that is unlinked as soon as it is open
+fn unnamed_file() -> Result<File> {
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+    let dir = staging_dir();
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).with_context(|| format!("Failed to secure {}", dir.display()))?;
+
+    let mut options = fs::OpenOptions::new();
+    options.read(true).write(true).mode(0o600);
+    match options.clone().custom_flags(libc::O_TMPFILE).open(&dir) {
+        Ok(file) => return Ok(file),
+        Err(e) => debug!("No O_TMPFILE in {}: {}", dir.display(), e),
+    }
+    let path = dir.join(format!("staging-{}-{}", std::process::id(), rand::random::<u32>()));
+    let file = options.create_new(true).open(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    fs::remove_file(&path).with_context(|| format!("Failed to unlink {}", path.display()))?;
+    Ok(file)
+}
+
+/// Overwrite a staged tarball before its space is freed, with shred or,
+/// where it is missing, zeroes
+fn shred(file: &File) {
+    let shredded = file
+        .try_clone()
+        .and_then(|output| Command::new("shred").args(["-z", "-"]).stdout(output).stderr(Stdio::null()).status())
+        .is_ok_and(|status| status.success());
+    if shredded {
+        return;
+    }
+    let Ok(length) = file.metadata().map(|metadata| metadata.len()) else {
+        return;
+    };
+    let zeroes = vec![0u8; 1024 * 1024];
+    let mut offset = 0;
+    while offset < length {
+        let chunk = (length - offset).min(zeroes.len() as u64) as usize;
+        if let Err(e) = file.write_all_at(&zeroes[..chunk], offset) {
+            warn!("Failed to wipe the staged archive: {}", e);
+            return;
+        }
+        offset += chunk as u64;
+    }
+    let _ = file.sync_data();
+}
+
+/// How the filesystem was mounted, which decides how it is unmounted
+#[derive(Debug, Clone)]
+pub enum Mount {
+    /// Through the setuid helper of the fuse3 package, for users
+    Helper { program: PathBuf, mountpoint: PathBuf },
+    /// With mount(2) straight away, as root
+    Direct { mountpoint: PathBuf },
+}
+
+impl Mount {
+    /// Detach the filesystem; the session then ends by itself
+    pub fn unmount(&self) -> Result<()> {
+        match self {
+            Mount::Helper { program, mountpoint } => {
+                let status = Command::new(program)
+                    .args(["-u", "-z", "--"])
+                    .arg(mountpoint)
+                    .status()
+                    .with_context(|| format!("Failed to run {}", program.display()))?;
+                if !status.success() {
+                    anyhow::bail!("{} -u {} failed ({})", program.display(), mountpoint.display(), status);
+                }
+            }
+            Mount::Direct { mountpoint } => {
+                let target = CString::new(mountpoint.as_os_str().as_bytes())?;
+                // SAFETY: target is a valid NUL-terminated path
+                if unsafe { libc::umount2(target.as_ptr(), libc::MNT_DETACH) } != 0 {
+                    return Err(std::io::Error::last_os_error()).with_context(|| format!("Failed to unmount {}", mountpoint.display()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mount an empty read-only FUSE filesystem on `mountpoint` and return
+/// the connection to serve it on
+pub fn mount(mountpoint: &Path, name: &str) -> Result<(File, Mount)> {
+    let helper = ["fusermount3", "fusermount"].into_iter().find_map(|program| {
+        std::env::var_os("PATH")
+            .and_then(|path| std::env::split_paths(&path).map(|dir| dir.join(program)).find(|candidate| candidate.is_file()))
+    });
+    match helper {
+        Some(program) => mount_with_helper(&program, mountpoint, name),
+        None => mount_directly(mountpoint, name),
+    }
+}
+
+/// Options without commas or backslashes, which the helper would split on
+fn fsname(name: &str) -> String {
+    name.chars().filter(|c| *c != ',' && *c != '\\').collect()
+}
+
+fn mount_with_helper(program: &Path, mountpoint: &Path, name: &str) -> Result<(File, Mount)> {
+    let mut sockets = [0; 2];
+    // SAFETY: sockets has room for the two descriptors
+    if unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, sockets.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to create a socket pair");
+    }
+    // SAFETY: both descriptors were just created and are owned here
+    let (ours, theirs) = unsafe { (OwnedFd::from_raw_fd(sockets[0]), OwnedFd::from_raw_fd(sockets[1])) };
+
+    // The helper finds its end of the pair by number, so it must stay
+    // open across exec
+    let mut helper = Command::new(program);
+    helper
+        .arg("-o")
+        .arg(format!("ro,nosuid,nodev,default_permissions,subtype=backup-ui,fsname={}", fsname(name)))
+        .arg("--")
+        .arg(mountpoint)
+        .env("_FUSE_COMMFD", theirs.as_raw_fd().to_string());
+    let mut child = helper.spawn().with_context(|| format!("Failed to run {}", program.display()))?;
+    drop(theirs);
+
+    let received = receive_fd(ours.as_raw_fd());
+    let status = child.wait()?;
+    let fd = received.with_context(|| format!("{} did not mount {} ({})", program.display(), mountpoint.display(), status))?;
+    // SAFETY: the helper handed over this descriptor for us to own
+    let device = unsafe { File::from_raw_fd(fd) };
+    Ok((device, Mount::Helper { program: program.to_path_buf(), mountpoint: mountpoint.to_path_buf() }))
+}
+
+/// The descriptor sent over `socket` with SCM_RIGHTS
+fn receive_fd(socket: RawFd) -> Result<RawFd> {
+    let mut byte = [0u8; 1];
+    let mut iov = libc::iovec { iov_base: byte.as_mut_ptr().cast(), iov_len: 1 };
+    // u64s keep the control buffer aligned for cmsghdr
+    // SAFETY: CMSG_SPACE only computes a size
+    let space = unsafe { libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) } as usize;
+    let mut control = vec![0u64; space.div_ceil(8)];
+    // SAFETY: msghdr is plain data for which zeroes are valid
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = space as _;
+
+    // SAFETY: message points at buffers that live until the call returns
+    let received = unsafe { libc::recvmsg(socket, &mut message, 0) };
+    if received < 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to receive the FUSE connection");
+    }
+    // SAFETY: the header and its data are inside control, as recvmsg
+    // filled it in
+    unsafe {
+        let header = libc::CMSG_FIRSTHDR(&message);
+        if received == 0 || header.is_null() || (*header).cmsg_level != libc::SOL_SOCKET || (*header).cmsg_type != libc::SCM_RIGHTS {
+            anyhow::bail!("No FUSE connection was sent");
+        }
+        Ok(std::ptr::read_unaligned(libc::CMSG_DATA(header) as *const RawFd))
+    }
+}
+
+fn mount_directly(mountpoint: &Path, name: &str) -> Result<(File, Mount)> {
+    let device = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/fuse")
+        .context("Failed to open /dev/fuse; is the fuse module loaded?")?;
+    // SAFETY: getuid and getgid can't fail
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let options = format!("fd={},rootmode=40000,user_id={},group_id={},default_permissions", device.as_raw_fd(), uid, gid);
+
+    let source = CString::new(fsname(name))?;
+    let target = CString::new(mountpoint.as_os_str().as_bytes())?;
+    let kind = CString::new("fuse.backup-ui")?;
+    let data = CString::new(options)?;
+    // SAFETY: every pointer is a valid NUL-terminated string
+    let mounted = unsafe {
+        libc::mount(
+            source.as_ptr(),
+            target.as_ptr(),
+            kind.as_ptr(),
+            libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV,
+            data.as_ptr().cast(),
+        )
+    };
+    if mounted != 0 {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::EPERM) {
+            anyhow::bail!("Mounting needs fusermount3 (the fuse3 package) or root");
+        }
+        return Err(error).with_context(|| format!("Failed to mount {}", mountpoint.display()));
+    }
+    Ok((device, Mount::Direct { mountpoint: mountpoint.to_path_buf() }))
+}
+
+// The FUSE requests served; anything else is answered ENOSYS, or EROFS
+// when it would change something
+const LOOKUP: u32 = 1;
+const FORGET: u32 = 2;
+const GETATTR: u32 = 3;
+const READLINK: u32 = 5;
+const OPEN: u32 = 14;
+const READ: u32 = 15;
+const STATFS: u32 = 17;
+const RELEASE: u32 = 18;
+const FLUSH: u32 = 25;
+const INIT: u32 = 26;
+const OPENDIR: u32 = 27;
+const READDIR: u32 = 28;
+const RELEASEDIR: u32 = 29;
+const ACCESS: u32 = 34;
+const INTERRUPT: u32 = 36;
+const DESTROY: u32 = 38;
+const BATCH_FORGET: u32 = 42;
+/// SETATTR, SYMLINK, MKNOD, MKDIR, UNLINK, RMDIR, RENAME, LINK, WRITE,
+/// SETXATTR, REMOVEXATTR, CREATE, FALLOCATE, RENAME2, COPY_FILE_RANGE
+const WRITES: [u32; 15] = [4, 6, 8, 9, 10, 11, 12, 13, 16, 21, 24, 35, 43, 45, 47];
+
+const IN_HEADER: usize = 40;
+const FOPEN_KEEP_CACHE: u32 = 1 << 1;
+const MAX_WRITE: u32 = 128 * 1024;
+
+/// Answer the kernel's requests for `index` on `device` until the
+/// filesystem is unmounted, reading file data from `data`
+pub fn serve(device: File, index: &TarIndex, data: File) -> Result<()> {
+    // SAFETY: getuid and getgid can't fail
+    let owner = unsafe { (libc::getuid(), libc::getgid()) };
+    let session = Session { device, index, data, owner };
+    let mut buffer = vec![0u8; MAX_WRITE as usize + 64 * 1024];
+    loop {
+        let length = match (&session.device).read(&mut buffer) {
+            Ok(length) => length,
+            Err(e) => match e.raw_os_error() {
+                // Unmounted
+                Some(libc::ENODEV) => return Ok(()),
+                // The request was interrupted before it was read
+                Some(libc::ENOENT) | Some(libc::EINTR) | Some(libc::EAGAIN) => continue,
+                _ => return Err(e).context("Failed to read from the FUSE connection"),
+            },
+        };
+        if length < IN_HEADER {
+            continue;
+        }
+        if !session.handle(&buffer[..length])? {
+            return Ok(());
+        }
+    }
+}
+
+struct Session<'a> {
+    device: File,
+    index: &'a TarIndex,
+    data: File,
+    owner: (u32, u32),
+}
+
+impl Session<'_> {
+    /// Answer one request; false once the kernel is done with the
+    /// filesystem
+    fn handle(&self, request: &[u8]) -> Result<bool> {
+        let opcode = u32_at(request, 4);
+        let unique = u64_at(request, 8);
+        let ino = u64_at(request, 16);
+        let body = &request[IN_HEADER..];
+
+        let reply = match opcode {
+            INIT => Ok(self.init(body)),
+            DESTROY => {
+                self.reply(unique, Ok(Vec::new()))?;
+                return Ok(false);
+            }
+            FORGET | BATCH_FORGET | INTERRUPT => return Ok(true),
+            LOOKUP => self.lookup(ino, until_nul(body)),
+            GETATTR => self.index.node(ino).map(|node| {
+                let mut out = Vec::with_capacity(104);
+                out.extend_from_slice(&TTL_SECS.to_ne_bytes());
+                out.extend_from_slice(&[0u8; 8]);
+                out.extend_from_slice(&self.attr(ino, node));
+                out
+            }).ok_or(libc::ENOENT),
+            READLINK => match self.index.node(ino).map(|node| &node.kind) {
+                Some(Kind::Symlink(target)) => Ok(target.clone()),
+                _ => Err(libc::EINVAL),
+            },
+            OPEN => {
+                if u32_at(body, 0) as i32 & libc::O_ACCMODE != libc::O_RDONLY {
+                    Err(libc::EROFS)
+                } else {
+                    Ok(open_out(FOPEN_KEEP_CACHE))
+                }
+            }
+            OPENDIR => Ok(open_out(0)),
+            READ => self.read(ino, u64_at(body, 8), u32_at(body, 16)),
+            READDIR => self.readdir(ino, u64_at(body, 8), u32_at(body, 16)),
+            STATFS => Ok(self.statfs()),
+            RELEASE | RELEASEDIR | FLUSH => Ok(Vec::new()),
+            ACCESS if u32_at(body, 0) as i32 & libc::W_OK != 0 => Err(libc::EROFS),
+            ACCESS => Ok(Vec::new()),
+            opcode if WRITES.contains(&opcode) => Err(libc::EROFS),
+            opcode => {
+                debug!("Unsupported FUSE request {}", opcode);
+                Err(libc::ENOSYS)
+            }
+        };
+        self.reply(unique, reply)?;
+        Ok(true)
+    }
+
+    fn reply(&self, unique: u64, reply: std::result::Result<Vec<u8>, i32>) -> Result<()> {
+        let (error, body) = match reply {
+            Ok(body) => (0, body),
+            Err(errno) => (-errno, Vec::new()),
+        };
+        let mut out = Vec::with_capacity(16 + body.len());
+        out.extend_from_slice(&(16 + body.len() as u32).to_ne_bytes());
+        out.extend_from_slice(&error.to_ne_bytes());
+        out.extend_from_slice(&unique.to_ne_bytes());
+        out.extend_from_slice(&body);
+        match (&self.device).write_all(&out) {
+            Ok(()) => Ok(()),
+            // The request was interrupted and is no longer waited for
+            Err(e) if e.raw_os_error() == Some(libc::ENOENT) => Ok(()),
+            Err(e) => Err(e).context("Failed to answer the kernel"),
+        }
+    }
+
+    fn init(&self, body: &[u8]) -> Vec<u8> {
+        let (major, minor, readahead) = (u32_at(body, 0), u32_at(body, 4), u32_at(body, 8));
+        debug!("FUSE kernel protocol {}.{}", major, minor);
+        let mut out = Vec::with_capacity(64);
+        out.extend_from_slice(&7u32.to_ne_bytes());
+        // A newer major version asks again with ours
+        out.extend_from_slice(&(if major > 7 { 31 } else { minor.min(31) }).to_ne_bytes());
+        out.extend_from_slice(&readahead.to_ne_bytes());
+        out.extend_from_slice(&0u32.to_ne_bytes()); // flags
+        out.extend_from_slice(&16u16.to_ne_bytes()); // max_background
+        out.extend_from_slice(&12u16.to_ne_bytes()); // congestion_threshold
+        out.extend_from_slice(&MAX_WRITE.to_ne_bytes());
+        out.extend_from_slice(&1u32.to_ne_bytes()); // time_gran
+        out.resize(64, 0);
+        out
+    }
+
+    fn lookup(&self, parent: u64, name: &[u8]) -> std::result::Result<Vec<u8>, i32> {
+        let ino = self.index.child(parent, OsStr::from_bytes(name)).ok_or(libc::ENOENT)?;
+        let node = self.index.node(ino).ok_or(libc::ENOENT)?;
+        let mut out = Vec::with_capacity(128);
+        out.extend_from_slice(&ino.to_ne_bytes());
+        out.extend_from_slice(&0u64.to_ne_bytes()); // generation
+        out.extend_from_slice(&TTL_SECS.to_ne_bytes()); // entry_valid
+        out.extend_from_slice(&TTL_SECS.to_ne_bytes()); // attr_valid
+        out.extend_from_slice(&[0u8; 8]);
+        out.extend_from_slice(&self.attr(ino, node));
+        Ok(out)
+    }
+
+    /// fuse_attr; everything belongs to whoever mounted the archive
+    fn attr(&self, ino: u64, node: &Node) -> Vec<u8> {
+        let (kind, size, links) = match &node.kind {
+            Kind::Dir(children) => (S_IFDIR, 0, 2 + children.len() as u32),
+            Kind::File { size, .. } => (S_IFREG, *size, 1),
+            Kind::Symlink(target) => (S_IFLNK, target.len() as u64, 1),
+        };
+        let mode = if kind == S_IFDIR { node.mode | 0o500 } else { node.mode };
+        let mut out = Vec::with_capacity(88);
+        out.extend_from_slice(&ino.to_ne_bytes());
+        out.extend_from_slice(&size.to_ne_bytes());
+        out.extend_from_slice(&size.div_ceil(512).to_ne_bytes());
+        for _ in 0..3 {
+            out.extend_from_slice(&(node.mtime as u64).to_ne_bytes());
+        }
+        out.extend_from_slice(&[0u8; 12]); // nanoseconds
+        out.extend_from_slice(&(kind | (mode & !S_IFMT)).to_ne_bytes());
+        out.extend_from_slice(&links.to_ne_bytes());
+        out.extend_from_slice(&self.owner.0.to_ne_bytes());
+        out.extend_from_slice(&self.owner.1.to_ne_bytes());
+        out.extend_from_slice(&0u32.to_ne_bytes()); // rdev
+        out.extend_from_slice(&4096u32.to_ne_bytes()); // blksize
+        out.extend_from_slice(&0u32.to_ne_bytes()); // flags
+        out
+    }
+
+    fn read(&self, ino: u64, offset: u64, size: u32) -> std::result::Result<Vec<u8>, i32> {
+        let Some(Kind::File { offset: start, size: length }) = self.index.node(ino).map(|node| &node.kind) else {
+            return Err(libc::EISDIR);
+        };
+        let wanted = length.saturating_sub(offset).min(size as u64) as usize;
+        let mut out = vec![0u8; wanted];
+        self.data.read_exact_at(&mut out, start + offset).map_err(|e| {
+            warn!("Failed to read the staged archive: {}", e);
+            libc::EIO
+        })?;
+        Ok(out)
+    }
+
+    fn readdir(&self, ino: u64, offset: u64, size: u32) -> std::result::Result<Vec<u8>, i32> {
+        let node = self.index.node(ino).ok_or(libc::ENOENT)?;
+        let Kind::Dir(children) = &node.kind else {
+            return Err(libc::ENOTDIR);
+        };
+        let entries = [(OsStr::new("."), ino), (OsStr::new(".."), node.parent)]
+            .into_iter()
+            .chain(children.iter().map(|(name, child)| (name.as_os_str(), *child)));
+
+        let mut out = Vec::new();
+        for (position, (name, child)) in entries.enumerate().skip(offset as usize) {
+            let name = name.as_bytes();
+            let entry = (24 + name.len()).div_ceil(8) * 8;
+            if out.len() + entry > size as usize {
+                break;
+            }
+            let kind = match self.index.node(child).map(|node| &node.kind) {
+                Some(Kind::Dir(_)) | None => S_IFDIR,
+                Some(Kind::File { .. }) => S_IFREG,
+                Some(Kind::Symlink(_)) => S_IFLNK,
+            };
+            out.extend_from_slice(&child.to_ne_bytes());
+            out.extend_from_slice(&(position as u64 + 1).to_ne_bytes());
+            out.extend_from_slice(&(name.len() as u32).to_ne_bytes());
+            out.extend_from_slice(&(kind >> 12).to_ne_bytes());
+            out.extend_from_slice(name);
+            out.resize(out.len().next_multiple_of(8), 0);
+        }
+        Ok(out)
+    }
+
+    fn statfs(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(80);
+        out.extend_from_slice(&self.index.bytes.div_ceil(4096).to_ne_bytes()); // blocks
+        out.extend_from_slice(&[0u8; 16]); // bfree, bavail
+        out.extend_from_slice(&(self.index.len() as u64).to_ne_bytes()); // files
+        out.extend_from_slice(&[0u8; 8]); // ffree
+        out.extend_from_slice(&4096u32.to_ne_bytes()); // bsize
+        out.extend_from_slice(&255u32.to_ne_bytes()); // namelen
+        out.extend_from_slice(&4096u32.to_ne_bytes()); // frsize
+        out.resize(80, 0);
+        out
+    }
+}
+
+fn open_out(flags: u32) -> Vec<u8> {
+    let mut out = vec![0u8; 8]; // fh
+    out.extend_from_slice(&flags.to_ne_bytes());
+    out.extend_from_slice(&[0u8; 4]);
+    out
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    bytes.get(at..at + 4).map(|b| u32::from_ne_bytes(b.try_into().unwrap_or_default())).unwrap_or(0)
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    bytes.get(at..at + 8).map(|b| u64::from_ne_bytes(b.try_into().unwrap_or_default())).unwrap_or(0)
+}
+
+/// Mount `archive` read-only on `mountpoint` until `stop` resolves, then
+/// unmount it. Encrypted or compressed archives are unpacked into a
+/// private tarball first, shredded afterwards.
+pub async fn mount_archive(
+    archive: &Path,
+    password: Option<&SecurePassword>,
+    mountpoint: &Path,
+    stop: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    if !mountpoint.is_dir() {
+        anyhow::bail!("{} is not a directory", mountpoint.display());
+    }
+    let staged = StagedTar::stage(archive, password).await?;
+    let tarball = staged.file().try_clone()?;
+    let index = tokio::task::spawn_blocking(move || TarIndex::read(&tarball)).await??;
+    info!("Indexed {} entries of {}", index.len(), archive.display());
+
+    let name = format!("backup-ui:{}", archive.file_name().unwrap_or_default().to_string_lossy());
+    let (device, mounted) = mount(mountpoint, &name)?;
+    let tarball = staged.file().try_clone()?;
+    let mut session = tokio::task::spawn_blocking(move || serve(device, &index, tarball));
+    println!("Mounted {} read-only on {}; press Ctrl-C to unmount", archive.display(), mountpoint.display());
+
+    tokio::select! {
+        served = &mut session => return served?.context("The FUSE session ended"),
+        _ = stop => {}
+    }
+    mounted.unmount()?;
+    session.await??;
+    tokio::task::spawn_blocking(move || drop(staged)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_tarball() {
+        let dir = std::env::temp_dir().join(format!("archive-mount-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let long = "d".repeat(120);
+        fs::create_dir_all(dir.join("home/.config").join(&long)).unwrap();
+        fs::write(dir.join("home/.bashrc"), b"alias ll='ls -l'\n").unwrap();
+        fs::write(dir.join("home/.config").join(&long).join("settings.ini"), b"theme=dark\n").unwrap();
+        std::os::unix::fs::symlink(".bashrc", dir.join("home/.profile")).unwrap();
+        fs::hard_link(dir.join("home/.bashrc"), dir.join("home/.bashrc.bak")).unwrap();
+        let tarball = dir.join("archive.tar");
+        let status = Command::new("tar").arg("-cf").arg(&tarball).arg("-C").arg(dir.join("home")).arg(".").status().unwrap();
+        assert!(status.success());
+
+        let data = File::open(&tarball).unwrap();
+        let index = TarIndex::read(&data).unwrap();
+        let contents = |path: &str| {
+            let ino = index.find(&components_of(path.as_bytes()).unwrap()).unwrap();
+            match &index.node(ino).unwrap().kind {
+                Kind::File { offset, size } => {
+                    let mut out = vec![0u8; *size as usize];
+                    data.read_exact_at(&mut out, *offset).unwrap();
+                    out
+                }
+                Kind::Symlink(target) => target.clone(),
+                Kind::Dir(children) => children.keys().flat_map(|name| name.as_bytes().to_vec()).collect(),
+            }
+        };
+        assert_eq!(contents(".bashrc"), b"alias ll='ls -l'\n");
+        assert_eq!(contents(".bashrc.bak"), b"alias ll='ls -l'\n");
+        assert_eq!(contents(".profile"), b".bashrc");
+        assert_eq!(contents(&format!(".config/{}/settings.ini", long)), b"theme=dark\n");
+        assert!(index.find(&components_of(b"missing").unwrap()).is_none());
+        assert_eq!(components_of(b"../etc/passwd"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stage_encrypted_uncompressed_archive() {
+        let dir = std::env::temp_dir().join(format!("archive-mount-stage-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("home")).unwrap();
+        fs::write(dir.join("home/.bashrc"), b"alias ll='ls -l'\n").unwrap();
+        let tarball = dir.join("archive.tar");
+        let status = Command::new("tar").arg("-cf").arg(&tarball).arg("-C").arg(dir.join("home")).arg(".bashrc").status().unwrap();
+        assert!(status.success());
+        let archive = dir.join("archive.tar.gpg");
+        let mut gpg = Command::new("gpg")
+            .args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0", "--symmetric", "--output"])
+            .arg(&archive)
+            .arg(&tarball)
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        gpg.stdin.take().unwrap().write_all(b"correct horse\n").unwrap();
+        assert!(gpg.wait().unwrap().success());
+
+        let password = SecurePassword::new("correct horse".to_string());
+        let staged = StagedTar::stage(&archive, Some(&password)).await.unwrap();
+        let index = TarIndex::read(staged.file()).unwrap();
+        assert!(index.find(&components_of(b".bashrc").unwrap()).is_some());
+        let wrong = SecurePassword::new("wrong".to_string());
+        drop(staged);
+        assert!(StagedTar::stage(&archive, Some(&wrong)).await.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_oversized_extension_header() {
+        let path = std::env::temp_dir().join(format!("archive-mount-header-test-{}.tar", std::process::id()));
+        let mut block = [0u8; BLOCK as usize];
+        block[..13].copy_from_slice(b"././@LongLink");
+        block[100..108].copy_from_slice(b"0000644\0");
+        block[124..136].copy_from_slice(format!("{:011o}\0", 1u64 << 32).as_bytes());
+        block[156] = b'L';
+        block[148..156].fill(b' ');
+        let sum: u64 = block.iter().map(|&b| b as u64).sum();
+        block[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+        fs::write(&path, block).unwrap();
+
+        let error = TarIndex::read(&File::open(&path).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("too large"), "{}", error);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::backend::catalog;
+use crate::core::exclusions::ExclusionSet;
+use crate::core::types::{BackupItem, CheckResult, CheckStatus};
+
+/// How often a waiting backup looks again, and for how long at most
+const WAIT_POLL: Duration = Duration::from_secs(5);
+const WAIT_LIMIT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Browser {
+    Firefox,
+    Librewolf,
+    Chromium,
+    #[serde(alias = "google-chrome")]
+    Chrome,
+    Brave,
+    Vivaldi,
+    #[serde(alias = "microsoft-edge")]
+    Edge,
+}
+
+impl Browser {
+    pub const ALL: [Browser; 7] = [
+        Browser::Firefox,
+        Browser::Librewolf,
+        Browser::Chromium,
+        Browser::Chrome,
+        Browser::Brave,
+        Browser::Vivaldi,
+        Browser::Edge,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Browser::Firefox => "Firefox",
+            Browser::Librewolf => "LibreWolf",
+            Browser::Chromium => "Chromium",
+            Browser::Chrome => "Google Chrome",
+            Browser::Brave => "Brave",
+            Browser::Vivaldi => "Vivaldi",
+            Browser::Edge => "Microsoft Edge",
+        }
+    }
+
+    /// Where the browser keeps its profiles, home-relative, native first
+    /// and then as a Flatpak
+    pub fn profile_dirs(&self) -> &'static [&'static str] {
+        match self {
+            Browser::Firefox => &[".mozilla/firefox", ".var/app/org.mozilla.firefox/.mozilla/firefox"],
+            Browser::Librewolf => &[".librewolf", ".var/app/io.gitlab.librewolf-community/.librewolf"],
+            Browser::Chromium => &[".config/chromium", ".var/app/org.chromium.Chromium/config/chromium"],
+            Browser::Chrome => &[".config/google-chrome", ".var/app/com.google.Chrome/config/google-chrome"],
+            Browser::Brave => &[
+                ".config/BraveSoftware/Brave-Browser",
+                ".var/app/com.brave.Browser/config/BraveSoftware/Brave-Browser",
+            ],
+            Browser::Vivaldi => &[".config/vivaldi", ".var/app/com.vivaldi.Vivaldi/config/vivaldi"],
+            Browser::Edge => &[".config/microsoft-edge", ".var/app/com.microsoft.Edge/config/microsoft-edge"],
+        }
+    }
+
+    /// Process names as /proc reports them, cut to 15 characters
+    fn processes(&self) -> &'static [&'static str] {
+        match self {
+            Browser::Firefox => &["firefox", "firefox-bin", "firefox-esr"],
+            Browser::Librewolf => &["librewolf", "librewolf-bin"],
+            Browser::Chromium => &["chromium", "chromium-browse"],
+            Browser::Chrome => &["chrome"],
+            Browser::Brave => &["brave"],
+            Browser::Vivaldi => &["vivaldi-bin"],
+            Browser::Edge => &["msedge"],
+        }
+    }
+
+    fn is_firefox(&self) -> bool {
+        matches!(self, Browser::Firefox | Browser::Librewolf)
+    }
+
+    /// Caches the browser rebuilds by itself, as exclusion patterns that
+    /// apply anywhere below a profile directory
+    pub fn cache_exclusions(&self) -> &'static [&'static str] {
+        if self.is_firefox() {
+            &["cache2", "startupCache", "thumbnails", "shader-cache", "safebrowsing", "crashes", "minidumps"]
+        } else {
+            &[
+                "Cache",
+                "Code Cache",
+                "GPUCache",
+                "DawnCache",
+                "DawnGraphiteCache",
+                "DawnWebGPUCache",
+                "GrShaderCache",
+                "GraphiteDawnCache",
+                "ShaderCache",
+                "CacheStorage",
+                "Crashpad",
+                "component_crx_cache",
+            ]
+        }
+    }
+
+    /// The profile directories there are under `home`, or the native one
+    /// when the browser isn't installed
+    pub fn existing_dirs(&self, home: &Path) -> Vec<String> {
+        let existing: Vec<String> =
+            self.profile_dirs().iter().filter(|dir| home.join(dir).is_dir()).map(|dir| dir.to_string()).collect();
+        if existing.is_empty() {
+            vec![self.profile_dirs()[0].to_string()]
+        } else {
+            existing
+        }
+    }
+}
+
+/// What a backup does about a browser that is open
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhenRunning {
+    /// Copy the profile anyway, with a warning
+    #[default]
+    Warn,
+    /// Wait for the browser to close, up to ten minutes, then copy
+    Wait,
+    /// Leave the profile out of this backup
+    Skip,
+}
+
+/// A browser whose profiles an item backs up
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BrowserConfig {
+    pub name: Browser,
+    #[serde(default)]
+    pub when_running: WhenRunning,
+}
+
+/// Why a browser looks open
+#[derive(Debug, Clone, PartialEq)]
+pub enum Running {
+    /// A profile lock held by a live process, or by another machine
+    /// sharing the home directory
+    Lock { path: PathBuf, holder: String },
+    /// A process of the browser, running as this user
+    Process { pid: u32 },
+}
+
+impl Running {
+    pub fn describe(&self) -> String {
+        match self {
+            Running::Lock { path, holder } => format!("{} is locked by {}", path.display(), holder),
+            Running::Process { pid } => format!("running as process {}", pid),
+        }
+    }
+}
+
+/// Whether `browser` has the profiles in `dir` open. Lock files are
+/// checked first as they name the profile; a process alone counts too,
+/// as the browser may use profiles elsewhere.
+pub fn running(browser: Browser, dir: &Path) -> Option<Running> {
+    let locks: Vec<PathBuf> = if browser.is_firefox() {
+        // A `lock` link in each profile, removed when the browser exits
+        fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path().join("lock")).collect())
+            .unwrap_or_default()
+    } else {
+        vec![dir.join("SingletonLock")]
+    };
+    let hostname = catalog::local_hostname();
+    for lock in locks {
+        let Ok(target) = fs::read_link(&lock) else {
+            continue;
+        };
+        let target = target.to_string_lossy().to_string();
+        match lock_holder(browser, &target) {
+            Some((host, pid)) if host.is_empty() || host == hostname => {
+                if Path::new("/proc").join(pid.to_string()).exists() {
+                    return Some(Running::Lock { path: lock, holder: format!("process {}", pid) });
+                }
+                debug!("Ignoring the stale lock {} of process {}", lock.display(), pid);
+            }
+            Some((host, _)) => return Some(Running::Lock { path: lock, holder: host }),
+            None => debug!("Unrecognised lock {} -> {}", lock.display(), target),
+        }
+    }
+    own_process(browser.processes()).map(|pid| Running::Process { pid })
+}
+
+/// Host and process of a lock link: `<address>:+<pid>` for Firefox,
+/// which only names the host by address, or `<hostname>-<pid>` for
+/// Chromium browsers
+fn lock_holder(browser: Browser, target: &str) -> Option<(String, u32)> {
+    if browser.is_firefox() {
+        let (_, pid) = target.rsplit_once(":+")?;
+        Some((String::new(), pid.parse().ok()?))
+    } else {
+        let (host, pid) = target.rsplit_once('-')?;
+        Some((host.to_string(), pid.parse().ok()?))
+    }
+}
+
+/// A process of this user named one of `names`
+fn own_process(names: &[&str]) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = fs::metadata("/proc/self").ok()?.uid();
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        if entry.metadata().ok()?.uid() != uid {
+            return None;
+        }
+        let comm = fs::read_to_string(entry.path().join("comm")).ok()?;
+        names.contains(&comm.trim()).then_some(pid)
+    })
+}
+
+/// A browser profile directory a backup is about to read
+#[derive(Debug, Clone)]
+struct Profile {
+    browser: Browser,
+    /// Home-relative
+    dir: PathBuf,
+    /// The item reading it
+    item: String,
+    when_running: WhenRunning,
+}
+
+/// The profile directories `items` read: those of browser items, and the
+/// usual ones of every browser inside other items, such as `.config`,
+/// which are warned about. Excluded directories aren't read.
+fn profiles(home: &Path, items: &[&BackupItem], exclusions: &ExclusionSet) -> Vec<Profile> {
+    let mut profiles = Vec::new();
+    for item in items {
+        if let Some(browser) = &item.browser {
+            profiles.push(Profile {
+                browser: browser.name,
+                dir: item.path.clone(),
+                item: item.name.clone(),
+                when_running: browser.when_running,
+            });
+            continue;
+        }
+        for browser in Browser::ALL {
+            for dir in browser.profile_dirs() {
+                let dir = Path::new(dir);
+                if dir.starts_with(&item.path) && home.join(dir).is_dir() {
+                    profiles.push(Profile {
+                        browser,
+                        dir: dir.to_path_buf(),
+                        item: item.name.clone(),
+                        when_running: WhenRunning::Warn,
+                    });
+                }
+            }
+        }
+    }
+    profiles.retain(|profile| !exclusions.is_excluded(&profile.dir));
+    profiles
+}
+
+/// Pre-flight check of the browser profiles the backup reads: a warning
+/// for each whose browser is open, saying what the backup will do about it
+pub fn check(home: &Path, items: &[&BackupItem], exclusions: &ExclusionSet) -> Vec<CheckResult> {
+    profiles(home, items, exclusions)
+        .into_iter()
+        .map(|profile| {
+            let name = format!("{} ({})", profile.browser.name(), profile.dir.display());
+            match running(profile.browser, &home.join(&profile.dir)) {
+                Some(running) => {
+                    let then = match profile.when_running {
+                        WhenRunning::Warn => "close it first, or the profile may be copied halfway through a write",
+                        WhenRunning::Wait => "the backup waits up to ten minutes for it to close",
+                        WhenRunning::Skip => "the profile is left out unless it is closed first",
+                    };
+                    CheckResult::new(
+                        "Browsers",
+                        &name,
+                        CheckStatus::Warning,
+                        &format!("Open ({}), read by {}; {}", running.describe(), profile.item, then),
+                    )
+                }
+                None => CheckResult::new("Browsers", &name, CheckStatus::Pass, "Closed"),
+            }
+        })
+        .collect()
+}
+
+/// Deal with open browsers before the profiles `items` read are copied,
+/// as `when_running` says: warn, wait for the browser to close, or skip.
+/// Returns the profile directories to leave out.
+pub async fn settle(home: &Path, items: &[&BackupItem], exclusions: &ExclusionSet) -> Vec<PathBuf> {
+    let started = Instant::now();
+    let mut pending = profiles(home, items, exclusions);
+    let mut skipped = Vec::new();
+    while !pending.is_empty() {
+        let mut waiting = Vec::new();
+        for profile in pending {
+            let Some(running) = running(profile.browser, &home.join(&profile.dir)) else {
+                continue;
+            };
+            let (browser, dir, running) = (profile.browser.name(), profile.dir.display(), running.describe());
+            match profile.when_running {
+                WhenRunning::Warn => warn!("{} is open ({}); copying {} anyway", browser, running, dir),
+                WhenRunning::Skip => {
+                    warn!("{} is open ({}); leaving {} out", browser, running, dir);
+                    skipped.push(profile.dir.clone());
+                }
+                WhenRunning::Wait if started.elapsed() >= WAIT_LIMIT => {
+                    warn!("{} is still open after {} minutes ({}); copying {} anyway", browser, WAIT_LIMIT.as_secs() / 60, running, dir);
+                }
+                WhenRunning::Wait => {
+                    if started.elapsed() < WAIT_POLL {
+                        info!("Waiting for {} to close ({})", browser, running);
+                    }
+                    waiting.push(profile);
+                }
+            }
+        }
+        if waiting.is_empty() {
+            break;
+        }
+        tokio::time::sleep(WAIT_POLL).await;
+        pending = waiting;
+    }
+    skipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_locks() {
+        let home = std::env::temp_dir().join(format!("browser-profiles-test-{}", std::process::id()));
+        let firefox = home.join(".mozilla/firefox");
+        let profile = firefox.join("abcd1234.default-release");
+        fs::create_dir_all(&profile).unwrap();
+        assert_eq!(Browser::Firefox.existing_dirs(&home), vec![".mozilla/firefox".to_string()]);
+
+        // A lock left by a process that has gone is stale
+        std::os::unix::fs::symlink("127.0.1.1:+4194303", profile.join("lock")).unwrap();
+        assert_eq!(lock_holder(Browser::Firefox, "127.0.1.1:+4194303"), Some((String::new(), 4194303)));
+        let stale = running(Browser::Firefox, &firefox);
+        assert!(!matches!(stale, Some(Running::Lock { .. })));
+
+        fs::remove_file(profile.join("lock")).unwrap();
+        std::os::unix::fs::symlink(format!("127.0.1.1:+{}", std::process::id()), profile.join("lock")).unwrap();
+        let held = running(Browser::Firefox, &firefox);
+        assert_eq!(
+            held,
+            Some(Running::Lock { path: profile.join("lock"), holder: format!("process {}", std::process::id()) })
+        );
+
+        // Chromium names the host, which may be another machine
+        let chromium = home.join(".config/chromium");
+        fs::create_dir_all(&chromium).unwrap();
+        std::os::unix::fs::symlink("other-host.lan-1234", chromium.join("SingletonLock")).unwrap();
+        assert_eq!(
+            running(Browser::Chromium, &chromium),
+            Some(Running::Lock { path: chromium.join("SingletonLock"), holder: "other-host.lan".to_string() })
+        );
+
+        // The usual profile directories inside other items are found,
+        // unless excluded
+        let config_dir = BackupItem::new(".config".to_string(), PathBuf::from(".config"), String::new(), String::new());
+        let found = profiles(&home, &[&config_dir], &ExclusionSet::new(&[]).unwrap());
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].browser, found[0].dir.as_path()), (Browser::Chromium, Path::new(".config/chromium")));
+        assert!(profiles(&home, &[&config_dir], &ExclusionSet::new(&[".config/chromium".to_string()]).unwrap()).is_empty());
+
+        let config: BrowserConfig = serde_json::from_str(r#"{"name": "google-chrome"}"#).unwrap();
+        assert_eq!(config, BrowserConfig { name: Browser::Chrome, when_running: WhenRunning::Warn });
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -0,0 +1,931 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::warn;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::checksum::HashAlgorithm;
+use crate::backend::stream;
+use crate::backend::tar_stream::ArchiveReader;
+use crate::backend::text_index::{IndexedText, TextIndexer};
+use crate::core::types::{ArchiveInfo, BackupMode, CatalogMatch, FileVersions, HostSummary, SearchHit, VerificationStatus};
+
+/// File recorded inside a cataloged archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogItem {
+    pub path: String,
+    pub size: u64,
+    /// SHA-256 of the file's contents; recorded for unencrypted archives only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Contents and checksum gathered from an archive before it is cataloged
+#[derive(Debug, Clone)]
+pub struct ArchiveIndex {
+    pub checksum: String,
+    pub size: u64,
+    pub items: Vec<CatalogItem>,
+    /// Text of small text files, when content indexing is enabled
+    pub texts: Vec<IndexedText>,
+}
+
+/// Catalog row in the portable form exchanged between machines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedArchive {
+    pub path: String,
+    pub name: String,
+    pub created: DateTime<Utc>,
+    pub size: u64,
+    pub mode: String,
+    pub encrypted: bool,
+    pub checksum: Option<String>,
+    pub remote: Option<String>,
+    #[serde(default)]
+    pub verified: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub verify_ok: Option<bool>,
+    pub items: Vec<CatalogItem>,
+}
+
+/// SQLite record of every archive created and the files inside it, so
+/// archives can be listed and searched without rescanning directories
+pub struct Catalog {
+    conn: Connection,
+}
+
+impl Catalog {
+    pub fn default_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("backup-manager")
+            .join("catalog.db")
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Self::open(&Self::default_path())
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create catalog directory: {}", parent.display()))?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open catalog: {}", path.display()))?;
+
+        // File names in the catalog reveal what was backed up
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        let catalog = Self { conn };
+        catalog.init_schema()?;
+        Ok(catalog)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        let catalog = Self { conn: Connection::open_in_memory()? };
+        catalog.init_schema()?;
+        Ok(catalog)
+    }
+
+    fn init_schema(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+             CREATE TABLE IF NOT EXISTS archives (
+                 id INTEGER PRIMARY KEY,
+                 path TEXT NOT NULL UNIQUE,
+                 name TEXT NOT NULL,
+                 created TEXT NOT NULL,
+                 size INTEGER NOT NULL,
+                 mode TEXT NOT NULL,
+                 encrypted INTEGER NOT NULL,
+                 checksum TEXT,
+                 remote TEXT,
+                 hostname TEXT,
+                 verified TEXT,
+                 verify_ok INTEGER
+             );
+             CREATE TABLE IF NOT EXISTS items (
+                 id INTEGER PRIMARY KEY,
+                 archive_id INTEGER NOT NULL REFERENCES archives(id) ON DELETE CASCADE,
+                 path TEXT NOT NULL,
+                 size INTEGER NOT NULL,
+                 sha256 TEXT
+             );
+             CREATE INDEX IF NOT EXISTS items_path ON items(path);
+             CREATE VIRTUAL TABLE IF NOT EXISTS item_text USING fts5(
+                 body,
+                 archive_id UNINDEXED,
+                 path UNINDEXED
+             );
+             CREATE TRIGGER IF NOT EXISTS archives_delete_text AFTER DELETE ON archives BEGIN
+                 DELETE FROM item_text WHERE archive_id = old.id;
+             END;",
+        )
+        .context("Failed to initialize catalog schema")?;
+
+        // Catalogs created by older versions lack later columns
+        if self.add_column_if_missing("archives", "hostname", "TEXT")? {
+            self.conn
+                .execute("UPDATE archives SET hostname = ?1", params![local_hostname()])?;
+        }
+        self.add_column_if_missing("archives", "verified", "TEXT")?;
+        self.add_column_if_missing("archives", "verify_ok", "INTEGER")?;
+        self.add_column_if_missing("items", "sha256", "TEXT")?;
+        Ok(())
+    }
+
+    fn add_column_if_missing(&self, table: &str, column: &str, decl: &str) -> Result<bool> {
+        let exists = self
+            .conn
+            .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+            .exists(params![table, column])?;
+        if exists {
+            return Ok(false);
+        }
+        self.conn
+            .execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])
+            .with_context(|| format!("Failed to add {} to catalog", column))?;
+        Ok(true)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM archives", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    /// Insert or replace an archive and its contents
+    pub fn record_archive(&mut self, archive: &ArchiveInfo, index: Option<&ArchiveIndex>) -> Result<i64> {
+        let tx = self.conn.transaction()?;
+
+        let key = archive_key(archive);
+        tx.execute("DELETE FROM archives WHERE path = ?1", params![key])?;
+        tx.execute(
+            "INSERT INTO archives (path, name, created, size, mode, encrypted, checksum, remote, hostname)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                key,
+                archive.name,
+                archive.created.to_rfc3339(),
+                archive.size as i64,
+                archive.mode.as_str(),
+                archive.encrypted,
+                index.map(|i| i.checksum.as_str()),
+                archive.remote,
+                archive.hostname.clone().unwrap_or_else(local_hostname),
+            ],
+        )?;
+        let archive_id = tx.last_insert_rowid();
+
+        if let Some(index) = index {
+            let mut stmt = tx.prepare("INSERT INTO items (archive_id, path, size, sha256) VALUES (?1, ?2, ?3, ?4)")?;
+            for item in &index.items {
+                stmt.execute(params![archive_id, item.path, item.size as i64, item.sha256])?;
+            }
+
+            let mut stmt = tx.prepare("INSERT INTO item_text (body, archive_id, path) VALUES (?1, ?2, ?3)")?;
+            for text in &index.texts {
+                stmt.execute(params![text.text, archive_id, text.path])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(archive_id)
+    }
+
+    /// All cataloged archives, newest first
+    pub fn list_archives(&self) -> Result<Vec<ArchiveInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, path, name, created, size, mode, encrypted, remote, hostname
+             FROM archives ORDER BY created DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        })?;
+
+        let mut archives = Vec::new();
+        for row in rows {
+            let (id, path, name, created, size, mode, encrypted, remote, hostname) = row?;
+            let created = parse_timestamp(&created);
+            archives.push(ArchiveInfo {
+                path: PathBuf::from(path),
+                description: format!("Backup archive from {}", created.format("%Y-%m-%d %H:%M")),
+                name,
+                created,
+                size: size.max(0) as u64,
+                mode: parse_mode(&mode),
+                encrypted,
+                items: self.item_paths(id)?,
+                remote,
+                hostname,
+            });
+        }
+        Ok(archives)
+    }
+
+    fn item_paths(&self, archive_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT path FROM items WHERE archive_id = ?1 ORDER BY path")?;
+        let paths = stmt
+            .query_map(params![archive_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(paths)
+    }
+
+    /// Find files whose path contains `query` across every archive
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<CatalogMatch>> {
+        let pattern = format!("%{}%", query.replace('%', "\\%").replace('_', "\\_"));
+        let mut stmt = self.conn.prepare(
+            "SELECT a.name, a.path, a.created, i.path, i.size, a.hostname, i.sha256
+             FROM items i JOIN archives a ON a.id = i.archive_id
+             WHERE i.path LIKE ?1 ESCAPE '\\'
+             ORDER BY a.created DESC, i.path
+             LIMIT ?2",
+        )?;
+
+        let matches = stmt
+            .query_map(params![pattern, limit as i64], |row| {
+                Ok(CatalogMatch {
+                    archive_name: row.get(0)?,
+                    archive_path: PathBuf::from(row.get::<_, String>(1)?),
+                    archive_created: parse_timestamp(&row.get::<_, String>(2)?),
+                    item_path: row.get(3)?,
+                    size: row.get::<_, i64>(4)?.max(0) as u64,
+                    hostname: row.get(5)?,
+                    sha256: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(matches)
+    }
+
+    /// Files whose indexed text contains every word of `query`, newest
+    /// archive first, each with a snippet around the match
+    pub fn search_text(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let query = fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT a.name, a.path, a.created, t.path,
+                    (SELECT size FROM items i WHERE i.archive_id = a.id AND i.path = t.path),
+                    a.hostname, snippet(item_text, 0, '[', ']', '…', 12),
+                    (SELECT sha256 FROM items i WHERE i.archive_id = a.id AND i.path = t.path)
+             FROM item_text t JOIN archives a ON a.id = t.archive_id
+             WHERE item_text MATCH ?1
+             ORDER BY a.created DESC, t.path
+             LIMIT ?2",
+        )?;
+
+        let hits = stmt
+            .query_map(params![query, limit as i64], |row| {
+                Ok(SearchHit {
+                    file: CatalogMatch {
+                        archive_name: row.get(0)?,
+                        archive_path: PathBuf::from(row.get::<_, String>(1)?),
+                        archive_created: parse_timestamp(&row.get::<_, String>(2)?),
+                        item_path: row.get(3)?,
+                        size: row.get::<_, Option<i64>>(4)?.unwrap_or(0).max(0) as u64,
+                        hostname: row.get(5)?,
+                        sha256: row.get(7)?,
+                    },
+                    snippet: Some(row.get::<_, String>(6)?.replace(['\n', '\r', '\t'], " ")),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+
+    /// Files matching `query` by name, followed by those matching by text
+    pub fn search_all(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let mut hits: Vec<SearchHit> = self
+            .search(query, limit)?
+            .into_iter()
+            .map(|file| SearchHit { file, snippet: None })
+            .collect();
+        hits.extend(self.search_text(query, limit)?);
+        Ok(hits)
+    }
+
+    /// Find every archived copy of a file by path or glob. Patterns match
+    /// either the full path or any trailing path components, so `.bashrc`
+    /// and `*.conf` work without spelling out the directory.
+    pub fn find_file(&self, pattern: &str, limit: usize) -> Result<Vec<FileVersions>> {
+        let pattern = normalize_find_pattern(pattern);
+        if pattern.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT a.name, a.path, a.created, i.path, i.size, a.hostname, i.sha256
+             FROM items i JOIN archives a ON a.id = i.archive_id
+             WHERE i.path GLOB ?1 OR i.path GLOB '*/' || ?1
+             ORDER BY i.path, a.created DESC
+             LIMIT ?2",
+        )?;
+
+        let matches = stmt
+            .query_map(params![pattern, limit as i64], |row| {
+                Ok(CatalogMatch {
+                    archive_name: row.get(0)?,
+                    archive_path: PathBuf::from(row.get::<_, String>(1)?),
+                    archive_created: parse_timestamp(&row.get::<_, String>(2)?),
+                    item_path: row.get(3)?,
+                    size: row.get::<_, i64>(4)?.max(0) as u64,
+                    hostname: row.get(5)?,
+                    sha256: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(group_versions(matches))
+    }
+
+    /// Latest backup of every machine in the catalog
+    pub fn host_summaries(&self) -> Result<Vec<HostSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(hostname, 'unknown'), path, created, size, mode, checksum, remote, verified, verify_ok
+             FROM archives ORDER BY 1, created DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<bool>>(8)?,
+            ))
+        })?;
+
+        let mut hosts: Vec<HostSummary> = Vec::new();
+        for row in rows {
+            let (hostname, path, created, size, mode, checksum, remote, verified, verify_ok) = row?;
+            let size = size.max(0) as u64;
+
+            // Rows are newest first per host, so the first one seen is the latest
+            if let Some(host) = hosts.last_mut().filter(|host| host.hostname == hostname) {
+                host.archive_count += 1;
+                host.total_size += size;
+                continue;
+            }
+
+            let verification = match (&checksum, verified.map(|t| parse_timestamp(&t)), verify_ok) {
+                (None, _, _) => VerificationStatus::NoChecksum,
+                (Some(_), Some(at), Some(true)) => VerificationStatus::Verified(at),
+                (Some(_), Some(at), Some(false)) => VerificationStatus::Failed(at),
+                _ => VerificationStatus::Unverified,
+            };
+            hosts.push(HostSummary {
+                hostname,
+                archive_count: 1,
+                total_size: size,
+                last_backup: parse_timestamp(&created),
+                last_mode: parse_mode(&mode),
+                last_size: size,
+                last_archive: path,
+                last_remote: remote,
+                last_checksum: checksum,
+                verification,
+                overdue: false,
+            });
+        }
+        Ok(hosts)
+    }
+
+    /// Drop an archive and everything recorded about its contents
+    /// SHA-256 recorded for `archive` when it was cataloged
+    pub fn checksum(&self, archive: &ArchiveInfo) -> Result<Option<String>> {
+        let checksum = self
+            .conn
+            .query_row(
+                "SELECT checksum FROM archives WHERE path = ?1",
+                params![archive_key(archive)],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(checksum.flatten())
+    }
+
+    /// Name of the archive cataloged with `checksum`, to recognize an
+    /// archive known only by its contents
+    pub fn archive_with_checksum(&self, checksum: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT name FROM archives WHERE checksum = ?1", params![checksum], |row| row.get(0))
+            .optional()?)
+    }
+
+    pub fn remove_archive(&self, archive_key: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM archives WHERE path = ?1", params![archive_key])?;
+        Ok(())
+    }
+
+    /// Point the entry for `old_key` at where the archive now lives,
+    /// keeping its contents, checksum and verification history
+    pub fn relocate(&self, old_key: &str, moved: &ArchiveInfo) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE archives SET path = ?1, remote = ?2 WHERE path = ?3",
+            params![archive_key(moved), moved.remote, old_key],
+        )?;
+        if updated == 0 {
+            anyhow::bail!("{} is not in the catalog", old_key);
+        }
+        Ok(())
+    }
+
+    /// When `archive` was last checked against its checksum, and whether
+    /// it matched
+    pub fn last_verification(&self, archive: &ArchiveInfo) -> Result<Option<(DateTime<Utc>, bool)>> {
+        let verification = self
+            .conn
+            .query_row(
+                "SELECT verified, verify_ok FROM archives WHERE path = ?1",
+                params![archive_key(archive)],
+                |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<bool>>(1)?)),
+            )
+            .optional()?;
+        Ok(match verification {
+            Some((Some(verified), Some(ok))) => Some((parse_timestamp(&verified), ok)),
+            _ => None,
+        })
+    }
+
+    /// Record the outcome of re-checking an archive against its checksum
+    pub fn record_verification(&self, archive_key: &str, ok: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE archives SET verified = ?1, verify_ok = ?2 WHERE path = ?3",
+            params![Utc::now().to_rfc3339(), ok, archive_key],
+        )?;
+        Ok(())
+    }
+
+    /// Archives created on `hostname`, with their contents, for sharing
+    pub fn export_host(&self, hostname: &str) -> Result<Vec<SyncedArchive>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, path, name, created, size, mode, encrypted, checksum, remote, verified, verify_ok
+             FROM archives WHERE hostname = ?1 ORDER BY created",
+        )?;
+        let rows = stmt
+            .query_map(params![hostname], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    SyncedArchive {
+                        path: row.get(1)?,
+                        name: row.get(2)?,
+                        created: parse_timestamp(&row.get::<_, String>(3)?),
+                        size: row.get::<_, i64>(4)?.max(0) as u64,
+                        mode: row.get(5)?,
+                        encrypted: row.get(6)?,
+                        checksum: row.get(7)?,
+                        remote: row.get(8)?,
+                        verified: row.get::<_, Option<String>>(9)?.map(|t| parse_timestamp(&t)),
+                        verify_ok: row.get(10)?,
+                        items: Vec::new(),
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut item_stmt = self.conn.prepare("SELECT path, size FROM items WHERE archive_id = ?1 ORDER BY path")?;
+        let mut archives = Vec::new();
+        for (id, mut archive) in rows {
+            archive.items = item_stmt
+                .query_map(params![id], |row| {
+                    Ok(CatalogItem {
+                        path: row.get(0)?,
+                        size: row.get::<_, i64>(1)?.max(0) as u64,
+                        // Per-file hashes stay on this machine, like indexed text
+                        sha256: None,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            archives.push(archive);
+        }
+        Ok(archives)
+    }
+
+    /// Replace everything known about another machine's archives. Rows that
+    /// collide with an archive already cataloged here are left alone.
+    pub fn import_host(&mut self, hostname: &str, archives: &[SyncedArchive]) -> Result<usize> {
+        if hostname == local_hostname() {
+            anyhow::bail!("Refusing to overwrite this machine's own catalog entries");
+        }
+
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM archives WHERE hostname = ?1", params![hostname])?;
+
+        let mut imported = 0;
+        for archive in archives {
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO archives
+                     (path, name, created, size, mode, encrypted, checksum, remote, hostname, verified, verify_ok)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    archive.path,
+                    archive.name,
+                    archive.created.to_rfc3339(),
+                    archive.size as i64,
+                    archive.mode,
+                    archive.encrypted,
+                    archive.checksum,
+                    archive.remote,
+                    hostname,
+                    archive.verified.map(|t| t.to_rfc3339()),
+                    archive.verify_ok,
+                ],
+            )?;
+            if inserted == 0 {
+                continue;
+            }
+
+            let archive_id = tx.last_insert_rowid();
+            let mut stmt = tx.prepare("INSERT INTO items (archive_id, path, size, sha256) VALUES (?1, ?2, ?3, ?4)")?;
+            for item in &archive.items {
+                stmt.execute(params![archive_id, item.path, item.size as i64, item.sha256])?;
+            }
+            imported += 1;
+        }
+
+        tx.commit()?;
+        Ok(imported)
+    }
+}
+
+/// Name this machine's archives are tagged with in a shared catalog
+pub fn local_hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .chain(std::env::var("HOSTNAME").ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Catalog paths are relative to the home directory, so accept `~/`, `./`
+/// and absolute home paths as well
+fn normalize_find_pattern(pattern: &str) -> String {
+    let pattern = pattern.trim();
+    let home = dirs::home_dir().map(|h| format!("{}/", h.to_string_lossy()));
+    let pattern = match home.as_deref().and_then(|home| pattern.strip_prefix(home)) {
+        Some(relative) => relative,
+        None => pattern,
+    };
+    pattern
+        .trim_start_matches("~/")
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// FTS5 query requiring every word of `query`, each quoted so operators
+/// and punctuation are matched literally
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Group matches ordered by path into per-file version lists
+fn group_versions(matches: Vec<CatalogMatch>) -> Vec<FileVersions> {
+    let mut files: Vec<FileVersions> = Vec::new();
+    for m in matches {
+        match files.last_mut() {
+            Some(file) if file.path == m.item_path => file.versions.push(m),
+            _ => files.push(FileVersions {
+                path: m.item_path.clone(),
+                versions: vec![m],
+            }),
+        }
+    }
+    files
+}
+
+/// Remote archives are keyed by their rclone path, local ones by file path
+pub fn archive_key(archive: &ArchiveInfo) -> String {
+    archive
+        .remote
+        .clone()
+        .unwrap_or_else(|| archive.path.to_string_lossy().to_string())
+}
+
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn parse_mode(value: &str) -> BackupMode {
+    if value == BackupMode::Secure.as_str() {
+        BackupMode::Secure
+    } else {
+        BackupMode::Complete
+    }
+}
+
+/// List an archive's contents with tar and hash it for the catalog. The
+/// listing is parsed as it streams, never buffered whole. Unencrypted
+/// archives are then read through once to hash each file, so versions can
+/// be told apart in the file history, and with `texts` to gather small
+/// text files for the full-text index. The archive's own checksum uses
+/// `algorithm`, and so do the files inside.
+pub async fn index_archive(path: &Path, texts: Option<TextIndexer>, algorithm: HashAlgorithm) -> Result<ArchiveIndex> {
+    let mut items = Vec::new();
+    stream::for_each_line(TokioCommand::new("tar").arg("-tvf").arg(path), |line| {
+        items.extend(parse_tar_item(line));
+    })
+    .await
+    .context("Failed to run tar to index archive")?
+    .check("Failed to index archive")?;
+
+    let hash_path = path.to_path_buf();
+    let checksum = tokio::task::spawn_blocking(move || algorithm.hash_file(&hash_path)).await??;
+    let size = std::fs::metadata(path)?.len();
+
+    // Hashes of files inside encrypted archives would let anyone holding
+    // the catalog confirm guesses at their contents, so those are skipped
+    let mut texts_found = Vec::new();
+    if path.extension().map(|e| e != "gpg").unwrap_or(true) {
+        let read_path = path.to_path_buf();
+        match tokio::task::spawn_blocking(move || hash_contents(&read_path, texts, algorithm)).await? {
+            Ok((hashes, texts)) => {
+                for item in &mut items {
+                    item.sha256 = hashes.get(&item.path).cloned();
+                }
+                texts_found = texts;
+            }
+            Err(e) => warn!("Reading files in {} failed; they are cataloged by name only: {}", path.display(), e),
+        }
+    }
+
+    Ok(ArchiveIndex { checksum, size, items, texts: texts_found })
+}
+
+/// Checksum of every file in an unencrypted archive, by path, along with
+/// the text `texts` asked for
+pub fn hash_contents(
+    path: &Path,
+    texts: Option<TextIndexer>,
+    algorithm: HashAlgorithm,
+) -> Result<(HashMap<String, String>, Vec<IndexedText>)> {
+    hash_contents_reporting(path, texts, algorithm, |_| {})
+}
+
+/// `hash_contents`, calling `on_file` with each file once it is hashed
+pub fn hash_contents_reporting(
+    path: &Path,
+    mut texts: Option<TextIndexer>,
+    algorithm: HashAlgorithm,
+    mut on_file: impl FnMut(&str),
+) -> Result<(HashMap<String, String>, Vec<IndexedText>)> {
+    let mut hashes = HashMap::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    ArchiveReader::open(path)?.for_each_file(|name, size, content| {
+        let mut hasher = algorithm.hasher();
+        let mut kept = texts.as_ref().filter(|indexer| indexer.wants(name, size)).map(|_| Vec::new());
+        loop {
+            let n = content.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            if let Some(kept) = kept.as_mut() {
+                kept.extend_from_slice(&buf[..n]);
+            }
+        }
+        if let (Some(indexer), Some(data)) = (texts.as_mut(), kept) {
+            indexer.add(name, data);
+        }
+        hashes.insert(name.to_string(), hasher.finish());
+        on_file(name);
+        Ok(())
+    })?;
+    Ok((hashes, texts.map(TextIndexer::into_texts).unwrap_or_default()))
+}
+
+/// Regular file from one line of `tar -tv` output
+fn parse_tar_item(line: &str) -> Option<CatalogItem> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 6 || !fields[0].starts_with('-') {
+        return None;
+    }
+    Some(CatalogItem {
+        path: fields[5..].join(" ").trim_start_matches("./").to_string(),
+        size: fields[2].parse().unwrap_or(0),
+        sha256: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(name: &str) -> ArchiveInfo {
+        ArchiveInfo {
+            path: PathBuf::from(format!("/backups/{}", name)),
+            name: name.to_string(),
+            created: Utc::now(),
+            size: 100,
+            mode: BackupMode::Secure,
+            encrypted: false,
+            description: String::new(),
+            items: Vec::new(),
+            remote: None,
+            hostname: None,
+        }
+    }
+
+    #[test]
+    fn test_record_and_search() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        assert!(catalog.is_empty().unwrap());
+
+        let index = ArchiveIndex {
+            checksum: "abc".to_string(),
+            size: 100,
+            items: vec![
+                CatalogItem { path: ".bashrc".to_string(), size: 10, sha256: None },
+                CatalogItem { path: ".config/nvim/init.lua".to_string(), size: 20, sha256: None },
+            ],
+            texts: Vec::new(),
+        };
+        catalog.record_archive(&archive("one.tar.gz"), Some(&index)).unwrap();
+        // Re-recording the same archive replaces it rather than duplicating
+        catalog.record_archive(&archive("one.tar.gz"), Some(&index)).unwrap();
+
+        let archives = catalog.list_archives().unwrap();
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].items.len(), 2);
+
+        let matches = catalog.search("nvim", 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].item_path, ".config/nvim/init.lua");
+    }
+
+    #[test]
+    fn test_search_text() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let index = ArchiveIndex {
+            checksum: "abc".to_string(),
+            size: 100,
+            items: vec![CatalogItem { path: ".muttrc".to_string(), size: 40, sha256: None }],
+            texts: vec![IndexedText {
+                path: ".muttrc".to_string(),
+                text: "set imap_user = \"jane@example.com\"\nset sort = threads".to_string(),
+            }],
+        };
+        catalog.record_archive(&archive("one.tar.gz"), Some(&index)).unwrap();
+
+        let hits = catalog.search_text("imap_user jane", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file.item_path, ".muttrc");
+        assert_eq!(hits[0].file.size, 40);
+        assert!(hits[0].snippet.as_deref().unwrap().contains("[imap_user]"));
+        // FTS operators in the query are taken literally
+        assert!(catalog.search_text("imap_user OR \"", 10).unwrap().is_empty());
+
+        let hits = catalog.search_all("muttrc", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].snippet.is_none());
+
+        // Replacing the archive drops its old text
+        catalog.record_archive(&archive("one.tar.gz"), None).unwrap();
+        assert!(catalog.search_text("threads", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_file_versions() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+
+        let index = ArchiveIndex {
+            checksum: "abc".to_string(),
+            size: 100,
+            items: vec![
+                CatalogItem { path: ".bashrc".to_string(), size: 10, sha256: Some("5891b5b5".to_string()) },
+                CatalogItem { path: ".config/app/app.conf".to_string(), size: 20, sha256: None },
+            ],
+            texts: Vec::new(),
+        };
+        let mut older = archive("old.tar.gz");
+        older.created = Utc::now() - chrono::Duration::days(1);
+        catalog.record_archive(&older, Some(&index)).unwrap();
+        catalog.record_archive(&archive("new.tar.gz"), Some(&index)).unwrap();
+
+        let files = catalog.find_file("~/.bashrc", 10).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].versions.len(), 2);
+        assert_eq!(files[0].versions[0].archive_name, "new.tar.gz");
+        assert_eq!(files[0].versions[1].sha256.as_deref(), Some("5891b5b5"));
+
+        // Globs match trailing components without the directory
+        let files = catalog.find_file("*.conf", 10).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, ".config/app/app.conf");
+
+        assert!(catalog.find_file("app", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_and_import_host() {
+        let mut source = Catalog::open_in_memory().unwrap();
+        let index = ArchiveIndex {
+            checksum: "abc".to_string(),
+            size: 100,
+            items: vec![CatalogItem { path: ".zshrc".to_string(), size: 10, sha256: None }],
+            texts: Vec::new(),
+        };
+        let mut laptop_archive = archive("laptop.tar.gz");
+        laptop_archive.hostname = Some("laptop".to_string());
+        source.record_archive(&laptop_archive, Some(&index)).unwrap();
+        source.record_archive(&archive("local.tar.gz"), Some(&index)).unwrap();
+
+        let exported = source.export_host("laptop").unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].items.len(), 1);
+
+        let mut target = Catalog::open_in_memory().unwrap();
+        assert_eq!(target.import_host("laptop", &exported).unwrap(), 1);
+        // Re-importing replaces rather than duplicates
+        assert_eq!(target.import_host("laptop", &exported).unwrap(), 1);
+
+        let matches = target.search("zshrc", 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].hostname.as_deref(), Some("laptop"));
+
+        let hosts = target.host_summaries().unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].verification, VerificationStatus::Unverified);
+        target.record_verification(&hosts[0].last_archive, true).unwrap();
+        let hosts = target.host_summaries().unwrap();
+        assert!(matches!(hosts[0].verification, VerificationStatus::Verified(_)));
+        assert!(target.import_host(&local_hostname(), &exported).is_err());
+    }
+
+    #[test]
+    fn test_parse_tar_item() {
+        let listing = "\
+drwxr-xr-x user/user         0 2024-01-01 12:00 ./.config/
+-rw-r--r-- user/user        42 2024-01-01 12:00 ./.config/app.conf
+";
+        let items: Vec<CatalogItem> = listing.lines().filter_map(parse_tar_item).collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, ".config/app.conf");
+        assert_eq!(items[0].size, 42);
+    }
+
+    #[test]
+    fn test_file_hashes_use_the_archive_algorithm() {
+        let dir = std::env::temp_dir().join(format!("catalog-hash-test-{}", std::process::id()));
+        let home = dir.join("home");
+        std::fs::create_dir_all(home.join(".config")).unwrap();
+        std::fs::write(home.join(".bashrc"), "alias ll='ls -l'\n").unwrap();
+        std::fs::write(home.join(".config/app.conf"), "theme = dark\n").unwrap();
+        let archive = dir.join("backup.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf").arg(&archive)
+            .arg("-C").arg(&home)
+            .arg(".bashrc").arg(".config/app.conf")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let (sha256, _) = hash_contents(&archive, None, HashAlgorithm::Sha256).unwrap();
+        let (blake3, _) = hash_contents(&archive, None, HashAlgorithm::Blake3).unwrap();
+        assert_eq!(sha256.len(), 2);
+        assert_eq!(blake3.len(), 2);
+        for (name, content) in [(".bashrc", "alias ll='ls -l'\n"), (".config/app.conf", "theme = dark\n")] {
+            // Plain hex as before, so hashes already in the catalog still compare
+            assert_eq!(sha256[name], HashAlgorithm::Sha256.hash_reader(content.as_bytes()).unwrap());
+            assert_eq!(HashAlgorithm::of(&sha256[name]), HashAlgorithm::Sha256);
+            assert_eq!(blake3[name], HashAlgorithm::Blake3.hash_reader(content.as_bytes()).unwrap());
+            assert_eq!(HashAlgorithm::of(&blake3[name]), HashAlgorithm::Blake3);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::catalog::{local_hostname, Catalog, SyncedArchive};
+
+const HOST_CATALOG_SUFFIX: &str = ".catalog.json";
+
+/// One machine's archives as published to the shared location
+#[derive(Debug, Serialize, Deserialize)]
+struct HostCatalog {
+    hostname: String,
+    exported: DateTime<Utc>,
+    archives: Vec<SyncedArchive>,
+}
+
+/// Outcome of a sync, for status messages
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub hosts: usize,
+    pub archives: usize,
+}
+
+/// Shares the catalog with other machines through a directory every
+/// machine can reach: an NFS mount, or a git checkout that is pulled and
+/// pushed around each sync. Each host writes only its own
+/// `<hostname>.catalog.json` and imports everyone else's.
+///
+/// The shared files list every backed-up path, so the directory should be
+/// readable only by the machines' owner; files are written with mode 0600.
+pub struct CatalogSync {
+    dir: PathBuf,
+}
+
+impl CatalogSync {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub async fn sync(&self, catalog: &mut Catalog) -> Result<SyncSummary> {
+        if !self.dir.is_dir() {
+            anyhow::bail!("Catalog sync location {} is not a directory", self.dir.display());
+        }
+
+        let git = self.dir.join(".git").exists();
+        if git {
+            // Work from the latest published catalogs; an offline pull is
+            // not fatal since the local copy is still usable
+            if let Err(e) = self.git(&["pull", "--rebase", "--quiet"]).await {
+                warn!("Could not pull shared catalog: {}", e);
+            }
+        }
+
+        let hostname = local_hostname();
+        let mut summary = SyncSummary::default();
+
+        for entry in std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read {}", self.dir.display()))?
+            .flatten()
+        {
+            let path = entry.path();
+            let is_host_file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().ends_with(HOST_CATALOG_SUFFIX))
+                .unwrap_or(false);
+            if !is_host_file {
+                continue;
+            }
+
+            let host_catalog = match read_host_catalog(&path) {
+                Ok(host_catalog) => host_catalog,
+                Err(e) => {
+                    warn!("Skipping unreadable shared catalog {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if host_catalog.hostname == hostname {
+                continue;
+            }
+
+            let imported = catalog.import_host(&host_catalog.hostname, &host_catalog.archives)?;
+            debug!("Imported {} archives from {}", imported, host_catalog.hostname);
+            summary.hosts += 1;
+            summary.archives += imported;
+        }
+
+        let own_file = self.dir.join(format!("{}{}", file_safe(&hostname), HOST_CATALOG_SUFFIX));
+        write_host_catalog(
+            &own_file,
+            &HostCatalog {
+                hostname: hostname.clone(),
+                exported: Utc::now(),
+                archives: catalog.export_host(&hostname)?,
+            },
+        )?;
+
+        if git {
+            self.publish(&own_file, &hostname).await?;
+        }
+
+        info!(
+            "Catalog synced with {}: {} archives from {} other machines",
+            self.dir.display(),
+            summary.archives,
+            summary.hosts
+        );
+        Ok(summary)
+    }
+
+    async fn publish(&self, own_file: &Path, hostname: &str) -> Result<()> {
+        self.git(&["add", &own_file.to_string_lossy()]).await?;
+
+        // Nothing to commit when the catalog hasn't changed since last sync
+        let unchanged = TokioCommand::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(["diff", "--cached", "--quiet"])
+            .status()
+            .await
+            .context("Failed to run git")?
+            .success();
+        if unchanged {
+            return Ok(());
+        }
+
+        self.git(&["commit", "--quiet", "-m", &format!("Update backup catalog for {}", hostname)])
+            .await?;
+        if let Err(e) = self.git(&["push", "--quiet"]).await {
+            warn!("Could not push shared catalog, will retry on next sync: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<()> {
+        let output = TokioCommand::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .context("Failed to run git")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+fn read_host_catalog(path: &Path) -> Result<HostCatalog> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_host_catalog(path: &Path, host_catalog: &HostCatalog) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let file = options
+        .open(&tmp_path)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    serde_json::to_writer_pretty(file, host_catalog)?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Hostnames end up in file names on the shared location
+fn file_safe(hostname: &str) -> String {
+    hostname
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect()
+}
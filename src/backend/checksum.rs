@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+/// Recorded BLAKE3 checksums start with this, so verification knows which
+/// algorithm to use; SHA-256 checksums are plain hex as they always were
+const BLAKE3_PREFIX: &str = "blake3:";
+
+/// Algorithm for whole-archive checksums
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// Works with `sha256sum` everywhere
+    #[default]
+    Sha256,
+    /// Several times faster on large archives: hashed on every core from a
+    /// memory-mapped file. Needs `b3sum` to check by hand
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Algorithm a recorded checksum was made with
+    pub fn of(checksum: &str) -> Self {
+        if checksum.starts_with(BLAKE3_PREFIX) {
+            HashAlgorithm::Blake3
+        } else {
+            HashAlgorithm::Sha256
+        }
+    }
+
+    /// Command that checks a file against the hex digest by hand
+    pub fn command(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256sum",
+            HashAlgorithm::Blake3 => "b3sum",
+        }
+    }
+
+    /// Checksum of the file at `path`, in the form the catalog records
+    pub fn hash_file(&self, path: &Path) -> Result<String> {
+        match self {
+            HashAlgorithm::Sha256 => sha256_file(path),
+            HashAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                hasher
+                    .update_mmap_rayon(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                Ok(format!("{}{}", BLAKE3_PREFIX, hasher.finalize().to_hex()))
+            }
+        }
+    }
+
+    /// Checksum of everything `reader` produces, such as an archive
+    /// streamed from a remote, in the form the catalog records
+    pub fn hash_reader(&self, mut reader: impl Read) -> Result<String> {
+        match self {
+            HashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                std::io::copy(&mut reader, &mut hasher)?;
+                Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+            }
+            HashAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                hasher.update_reader(reader)?;
+                Ok(format!("{}{}", BLAKE3_PREFIX, hasher.finalize().to_hex()))
+            }
+        }
+    }
+
+    /// Hasher fed a piece at a time, for data read for other reasons too
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+/// Checksum being built up by `update`
+pub enum Hasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// The checksum, in the form the catalog records
+    pub fn finish(self) -> String {
+        match self {
+            Hasher::Sha256(hasher) => hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect(),
+            Hasher::Blake3(hasher) => format!("{}{}", BLAKE3_PREFIX, hasher.finalize().to_hex()),
+        }
+    }
+}
+
+/// The hex digest of a recorded checksum, without its algorithm prefix,
+/// as `sha256sum` or `b3sum` print it
+pub fn digest(checksum: &str) -> &str {
+    checksum.strip_prefix(BLAKE3_PREFIX).unwrap_or(checksum)
+}
+
+/// Re-hash `path` with the algorithm `expected` was recorded with and
+/// compare
+pub fn verify_file(path: &Path, expected: &str) -> Result<bool> {
+    Ok(HashAlgorithm::of(expected).hash_file(path)? == expected)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn sample_file(name: &str, len: usize) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("checksum-{}-{}", name, std::process::id()));
+        let data: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_checksums_verify_with_their_own_algorithm() {
+        let path = sample_file("verify", 3 * 1024 * 1024 + 17);
+
+        let sha256 = HashAlgorithm::Sha256.hash_file(&path).unwrap();
+        let blake3 = HashAlgorithm::Blake3.hash_file(&path).unwrap();
+        assert_eq!(sha256.len(), 64);
+        assert_eq!(HashAlgorithm::of(&sha256), HashAlgorithm::Sha256);
+        assert_eq!(HashAlgorithm::of(&blake3), HashAlgorithm::Blake3);
+        assert_eq!(digest(&blake3).len(), 64);
+        assert!(verify_file(&path, &sha256).unwrap());
+        assert!(verify_file(&path, &blake3).unwrap());
+
+        std::fs::write(&path, b"tampered").unwrap();
+        assert!(!verify_file(&path, &sha256).unwrap());
+        assert!(!verify_file(&path, &blake3).unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// The reason to pick BLAKE3 at all; `bench_verify_sha256_vs_blake3`
+    /// gives the real figures
+    #[test]
+    fn test_blake3_hashes_faster_than_sha256() {
+        let path = sample_file("speed", 16 * 1024 * 1024);
+        let timed = |algorithm: HashAlgorithm| {
+            let started = Instant::now();
+            algorithm.hash_file(&path).unwrap();
+            started.elapsed()
+        };
+        let (sha256, blake3) = (timed(HashAlgorithm::Sha256), timed(HashAlgorithm::Blake3));
+        std::fs::remove_file(&path).unwrap();
+        assert!(blake3 < sha256, "BLAKE3 took {:?}, SHA-256 {:?}", blake3, sha256);
+    }
+
+    /// Verify times of each algorithm on a 256 MiB file. Run with
+    /// `cargo test --release -- --ignored --nocapture bench_verify`
+    #[test]
+    #[ignore]
+    fn bench_verify_sha256_vs_blake3() {
+        let path = sample_file("bench", 256 * 1024 * 1024);
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            let checksum = algorithm.hash_file(&path).unwrap();
+            let started = Instant::now();
+            assert!(verify_file(&path, &checksum).unwrap());
+            let elapsed = started.elapsed();
+            println!(
+                "{:<7} verified 256 MiB in {:>8.1?} ({:.0} MiB/s)",
+                algorithm.as_str(),
+                elapsed,
+                256.0 / elapsed.as_secs_f64()
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Put `text` on the desktop clipboard with the first clipboard tool that
+/// works here, returning its name
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, args) in tools(|name| std::env::var(name).ok()) {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).with_context(|| format!("Failed to write to {}", program))?;
+        }
+        if child.wait().with_context(|| format!("Failed to run {}", program))?.success() {
+            return Ok(program);
+        }
+    }
+    anyhow::bail!("No clipboard tool worked; install wl-clipboard, xclip or xsel")
+}
+
+/// Clipboard tools to try, in order, for the session the environment
+/// describes; `pbcopy` last for macOS
+fn tools(var: impl Fn(&str) -> Option<String>) -> Vec<(&'static str, &'static [&'static str])> {
+    let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+    let mut tools: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if set("WAYLAND_DISPLAY") {
+        tools.push(("wl-copy", &[]));
+    }
+    if set("DISPLAY") {
+        tools.push(("xclip", &["-selection", "clipboard"]));
+        tools.push(("xsel", &["--clipboard", "--input"]));
+    }
+    tools.push(("pbcopy", &[]));
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tools_follow_the_session() {
+        let names = |vars: &'static [(&'static str, &'static str)]| -> Vec<&str> {
+            tools(move |name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string()))
+                .into_iter()
+                .map(|(program, _)| program)
+                .collect()
+        };
+        assert_eq!(names(&[]), vec!["pbcopy"]);
+        assert_eq!(names(&[("DISPLAY", ":0")]), vec!["xclip", "xsel", "pbcopy"]);
+        // An empty variable doesn't count as set
+        assert_eq!(names(&[("WAYLAND_DISPLAY", "wayland-0"), ("DISPLAY", "")]), vec!["wl-copy", "pbcopy"]);
+    }
+}
@@ -0,0 +1,624 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::backend::catalog::local_hostname;
+use crate::backend::extract;
+use crate::backend::system_state::SYSTEM_STATE_DIR;
+use crate::core::audit::AuditLog;
+use crate::core::security::SecurePassword;
+use crate::core::types::{CheckResult, CheckStatus};
+
+/// Volume snapshots, a tar file each plus a manifest, under the system
+/// state directory
+pub const VOLUMES_DIR: &str = "volumes";
+const MANIFEST_FILE: &str = "volumes.json";
+const MAX_MANIFEST_BYTES: usize = 1024 * 1024;
+/// Largest error body read from the API
+const MAX_ERROR_BYTES: u64 = 64 * 1024;
+
+/// Which container volumes an application entry backs up
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct VolumeConfig {
+    /// Volume names, as `docker volume ls` shows them
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Pause the running containers using a volume while it is copied, so
+    /// a database isn't caught halfway through a write
+    #[serde(default)]
+    pub pause_containers: bool,
+}
+
+/// A container with a volume mounted
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeUser {
+    pub id: String,
+    pub name: String,
+    pub running: bool,
+    /// Where the volume is mounted inside the container
+    pub destination: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerVolume {
+    pub name: String,
+    pub driver: String,
+    /// The volume's directory on the host; only rootless Podman's can be
+    /// read without going through a container
+    pub mountpoint: PathBuf,
+    pub labels: BTreeMap<String, String>,
+    pub containers: Vec<VolumeUser>,
+}
+
+/// A volume as recorded in the archive
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VolumeSnapshot {
+    pub name: String,
+    pub driver: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Containers that used it when the backup was taken
+    #[serde(default)]
+    pub containers: Vec<String>,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct VolumeManifest {
+    pub hostname: String,
+    /// `Docker` or `Podman`
+    pub runtime: String,
+    pub captured: Option<DateTime<Utc>>,
+    pub volumes: Vec<VolumeSnapshot>,
+}
+
+/// A row of the volume selection screen
+#[derive(Debug, Clone)]
+pub struct VolumeChoice {
+    pub name: String,
+    pub driver: String,
+    /// Containers on this machine using the volume
+    pub containers: Vec<VolumeUser>,
+    /// Size in the archive, when restoring
+    pub archived: Option<u64>,
+}
+
+/// Tar member holding a volume's files
+fn member(name: &str) -> String {
+    format!("{}/{}/{}.tar", SYSTEM_STATE_DIR, VOLUMES_DIR, name)
+}
+
+/// The Docker or Podman API, spoken over its Unix socket. Podman serves
+/// the Docker-compatible API, so the same requests work for both.
+#[derive(Debug, Clone)]
+pub struct Runtime {
+    socket: PathBuf,
+}
+
+impl Runtime {
+    /// Find the API socket: the one `DOCKER_HOST` or `CONTAINER_HOST`
+    /// names, then the usual rootless and system locations
+    pub fn find() -> Result<Self> {
+        socket_candidates(
+            std::env::var("DOCKER_HOST").ok().as_deref(),
+            std::env::var("CONTAINER_HOST").ok().as_deref(),
+            std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).as_deref(),
+        )
+        .into_iter()
+        .find(|socket| socket.exists())
+        .map(|socket| Self { socket })
+        .context("No Docker or Podman socket found; start the service or set DOCKER_HOST")
+    }
+
+    pub fn name(&self) -> &'static str {
+        if self.socket.to_string_lossy().contains("podman") {
+            "Podman"
+        } else {
+            "Docker"
+        }
+    }
+
+    /// Every volume, with the containers using it
+    pub fn volumes(&self) -> Result<Vec<ContainerVolume>> {
+        let volumes: ApiVolumes = self.request("GET", "/volumes", None)?.ok("Listing volumes")?.json()?;
+        let containers: Vec<ApiContainer> = self
+            .request("GET", "/containers/json?all=true", None)?
+            .ok("Listing containers")?
+            .json()?;
+        Ok(join_users(volumes.volumes.unwrap_or_default(), containers))
+    }
+
+    /// Write the files of `volume` to `dest` as a tar stream relative to
+    /// the volume's root, through a container using it, or straight from
+    /// its directory when none does. Returns the bytes written.
+    fn snapshot(&self, volume: &ContainerVolume, pause: bool, dest: &Path) -> Result<u64> {
+        let Some(user) = volume.containers.first() else {
+            return tar_directory(&volume.mountpoint, dest);
+        };
+
+        let mut paused = Vec::new();
+        let mut copied = (|| {
+            if pause {
+                for container in volume.containers.iter().filter(|container| container.running) {
+                    self.post(&format!("/containers/{}/pause", container.id), "Pausing the container")?;
+                    debug!("Paused {} while copying {}", container.name, volume.name);
+                    paused.push(container);
+                }
+            }
+            let path = format!("{}/.", user.destination.trim_end_matches('/'));
+            let mut response = self
+                .request("GET", &format!("/containers/{}/archive?path={}", user.id, encode(&path)), None)?
+                .ok("Copying the volume")?;
+            let mut file = create_private(dest)?;
+            std::io::copy(&mut response.body, &mut file).with_context(|| format!("Failed to write {}", dest.display()))
+        })();
+
+        // A container left paused is an outage, so this is reported even
+        // when the copy itself went fine
+        for container in paused {
+            if let Err(e) = self.post(&format!("/containers/{}/unpause", container.id), "Unpausing the container") {
+                error!("{} is still paused: {:#}", container.name, e);
+                copied = Err(e.context(format!("{} is still paused; unpause it by hand", container.name)));
+            }
+        }
+        copied
+    }
+
+    /// Put the files of a snapshot back into its volume, creating the
+    /// volume if it doesn't exist. Says where they went.
+    fn restore(&self, snapshot: &VolumeSnapshot, tar: &Path) -> Result<String> {
+        let volume = match self.volumes()?.into_iter().find(|volume| volume.name == snapshot.name) {
+            Some(volume) => volume,
+            None => {
+                self.create_volume(snapshot)?;
+                self.volumes()?
+                    .into_iter()
+                    .find(|volume| volume.name == snapshot.name)
+                    .with_context(|| format!("{} wasn't created", snapshot.name))?
+            }
+        };
+
+        match volume.containers.first() {
+            Some(user) => {
+                let mut file = File::open(tar).with_context(|| format!("Failed to open {}", tar.display()))?;
+                let length = file.metadata()?.len();
+                let path = format!("/containers/{}/archive?path={}", user.id, encode(&user.destination));
+                self.request("PUT", &path, Some((&mut file, length, "application/x-tar")))?
+                    .ok("Copying into the volume")?;
+                Ok(format!("through {}", user.name))
+            }
+            None => {
+                untar_directory(tar, &volume.mountpoint)?;
+                Ok(format!("into {}", volume.mountpoint.display()))
+            }
+        }
+    }
+
+    fn create_volume(&self, snapshot: &VolumeSnapshot) -> Result<()> {
+        let body = serde_json::to_vec(&serde_json::json!({
+            "Name": snapshot.name,
+            "Driver": snapshot.driver,
+            "Labels": snapshot.labels,
+        }))?;
+        self.request("POST", "/volumes/create", Some((&mut body.as_slice(), body.len() as u64, "application/json")))?
+            .ok("Creating the volume")?;
+        info!("Created volume {}", snapshot.name);
+        Ok(())
+    }
+
+    fn post(&self, path: &str, what: &str) -> Result<()> {
+        self.request("POST", path, None)?.ok(what)?;
+        Ok(())
+    }
+
+    /// Send a request and read the response head. HTTP/1.0, so a body of
+    /// unknown length comes back as is, ended by closing the connection,
+    /// rather than chunked.
+    fn request(&self, method: &str, path: &str, body: Option<(&mut dyn Read, u64, &str)>) -> Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket)
+            .with_context(|| format!("Failed to connect to {}", self.socket.display()))?;
+        let mut head = format!("{} {} HTTP/1.0\r\nHost: localhost\r\n", method, path);
+        if let Some((_, length, content_type)) = &body {
+            head.push_str(&format!("Content-Type: {}\r\nContent-Length: {}\r\n", content_type, length));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if let Some((reader, _, _)) = body {
+            std::io::copy(reader, &mut stream).context("Failed to send the request body")?;
+        }
+
+        let mut reader = BufReader::new(stream);
+        let status = read_head(&mut reader)?;
+        Ok(Response { status, body: reader })
+    }
+}
+
+struct Response {
+    status: u16,
+    body: BufReader<UnixStream>,
+}
+
+impl Response {
+    /// The response, or the API's error message when it didn't succeed
+    fn ok(self, what: &str) -> Result<Self> {
+        if (200..300).contains(&self.status) {
+            return Ok(self);
+        }
+        let status = self.status;
+        let mut body = String::new();
+        let _ = self.body.take(MAX_ERROR_BYTES).read_to_string(&mut body);
+        let message = serde_json::from_str::<ApiError>(&body)
+            .map(|error| error.message)
+            .unwrap_or_else(|_| body.trim().to_string());
+        anyhow::bail!("{} failed ({}): {}", what, status, message)
+    }
+
+    fn json<T: DeserializeOwned>(self) -> Result<T> {
+        serde_json::from_reader(self.body).context("Unexpected response from the container runtime")
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiVolumes {
+    #[serde(default)]
+    volumes: Option<Vec<ApiVolume>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiVolume {
+    name: String,
+    #[serde(default)]
+    driver: String,
+    #[serde(default)]
+    mountpoint: String,
+    #[serde(default)]
+    labels: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainer {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    mounts: Vec<ApiMount>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiMount {
+    #[serde(rename = "Type", default)]
+    kind: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    destination: String,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// Sockets to try, most specific first
+fn socket_candidates(docker_host: Option<&str>, container_host: Option<&str>, runtime_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = [docker_host, container_host]
+        .into_iter()
+        .flatten()
+        .filter_map(|host| host.strip_prefix("unix://"))
+        .map(PathBuf::from)
+        .collect();
+    if let Some(dir) = runtime_dir {
+        candidates.push(dir.join("podman/podman.sock"));
+        candidates.push(dir.join("docker.sock"));
+    }
+    candidates.push(PathBuf::from("/var/run/docker.sock"));
+    candidates.push(PathBuf::from("/run/podman/podman.sock"));
+    candidates
+}
+
+/// Status code of a response, leaving the reader at the start of the body
+fn read_head(reader: &mut impl BufRead) -> Result<u16> {
+    let mut line = String::new();
+    reader.read_line(&mut line).context("No response from the container runtime")?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .with_context(|| format!("Unexpected response from the container runtime: {}", line.trim()))?;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            return Ok(status);
+        }
+    }
+}
+
+fn join_users(volumes: Vec<ApiVolume>, containers: Vec<ApiContainer>) -> Vec<ContainerVolume> {
+    let mut volumes: Vec<ContainerVolume> = volumes
+        .into_iter()
+        .map(|volume| {
+            let containers = containers
+                .iter()
+                .filter_map(|container| {
+                    let mount = container
+                        .mounts
+                        .iter()
+                        .find(|mount| mount.kind == "volume" && mount.name.as_deref() == Some(volume.name.as_str()))?;
+                    let name = match container.names.first() {
+                        Some(name) => name.trim_start_matches('/').to_string(),
+                        None => container.id.chars().take(12).collect(),
+                    };
+                    Some(VolumeUser {
+                        id: container.id.clone(),
+                        name,
+                        running: container.state == "running",
+                        destination: mount.destination.clone(),
+                    })
+                })
+                .collect();
+            ContainerVolume {
+                name: volume.name,
+                driver: volume.driver,
+                mountpoint: PathBuf::from(volume.mountpoint),
+                labels: volume.labels.unwrap_or_default(),
+                containers,
+            }
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.name.cmp(&b.name));
+    volumes
+}
+
+/// Percent-encode a query value, leaving slashes readable
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn create_private(path: &Path) -> Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn tar_directory(dir: &Path, dest: &Path) -> Result<u64> {
+    fs::read_dir(dir).with_context(|| {
+        format!("No container uses the volume and {} can't be read; start a container with it", dir.display())
+    })?;
+    drop(create_private(dest)?);
+    run_tar(Command::new("tar").arg("-C").arg(dir).arg("-cf").arg(dest).arg("."))?;
+    Ok(fs::metadata(dest)?.len())
+}
+
+fn untar_directory(tar: &Path, dir: &Path) -> Result<()> {
+    run_tar(Command::new("tar").arg("-C").arg(dir).arg("-xf").arg(tar)).with_context(|| {
+        format!("No container uses the volume and {} can't be written; start a container with it", dir.display())
+    })
+}
+
+fn run_tar(command: &mut Command) -> Result<()> {
+    let output = command.stdin(Stdio::null()).output().context("Failed to run tar")?;
+    if !output.status.success() {
+        anyhow::bail!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Snapshot the volumes `config` names into `dir`, a tar file each, and
+/// write the manifest. Volumes that are gone or can't be copied are left
+/// out and returned with the reason. Blocking.
+pub fn snapshot_volumes(config: &VolumeConfig, dir: &Path) -> Result<(VolumeManifest, Vec<String>)> {
+    let runtime = Runtime::find()?;
+    let volumes = runtime.volumes()?;
+    let mut manifest = VolumeManifest {
+        hostname: local_hostname(),
+        runtime: runtime.name().to_string(),
+        captured: Some(Utc::now()),
+        volumes: Vec::new(),
+    };
+    let mut failures = Vec::new();
+
+    for name in &config.names {
+        let Some(volume) = volumes.iter().find(|volume| &volume.name == name) else {
+            failures.push(format!("{}: no such volume", name));
+            continue;
+        };
+        let dest = dir.join(format!("{}.tar", name));
+        match runtime.snapshot(volume, config.pause_containers, &dest) {
+            Ok(bytes) => {
+                info!("Copied volume {} ({} bytes)", name, bytes);
+                manifest.volumes.push(VolumeSnapshot {
+                    name: name.clone(),
+                    driver: volume.driver.clone(),
+                    labels: volume.labels.clone(),
+                    containers: volume.containers.iter().map(|container| container.name.clone()).collect(),
+                    bytes,
+                });
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&dest);
+                failures.push(format!("{}: {:#}", name, e));
+            }
+        }
+    }
+
+    let mut file = create_private(&dir.join(MANIFEST_FILE))?;
+    file.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    Ok((manifest, failures))
+}
+
+/// Volumes on this machine to pick from for a backup
+pub async fn backup_choices() -> Result<Vec<VolumeChoice>> {
+    let volumes = tokio::task::spawn_blocking(|| Runtime::find()?.volumes()).await??;
+    Ok(volumes
+        .into_iter()
+        .map(|volume| VolumeChoice {
+            name: volume.name,
+            driver: volume.driver,
+            containers: volume.containers,
+            archived: None,
+        })
+        .collect())
+}
+
+/// Volumes in `archive`, each with the containers here already using a
+/// volume of that name. Without a runtime to ask, none are shown.
+pub async fn restore_choices(archive: &Path, password: Option<&SecurePassword>) -> Result<Vec<VolumeChoice>> {
+    let manifest = read_manifest(archive, password).await?;
+    let live = tokio::task::spawn_blocking(|| Runtime::find()?.volumes())
+        .await?
+        .unwrap_or_else(|e| {
+            warn!("Could not list the volumes here: {:#}", e);
+            Vec::new()
+        });
+    Ok(manifest
+        .volumes
+        .into_iter()
+        .map(|snapshot| VolumeChoice {
+            containers: live
+                .iter()
+                .find(|volume| volume.name == snapshot.name)
+                .map(|volume| volume.containers.clone())
+                .unwrap_or_default(),
+            name: snapshot.name,
+            driver: snapshot.driver,
+            archived: Some(snapshot.bytes),
+        })
+        .collect())
+}
+
+async fn read_manifest(archive: &Path, password: Option<&SecurePassword>) -> Result<VolumeManifest> {
+    let member = format!("{}/{}/{}", SYSTEM_STATE_DIR, VOLUMES_DIR, MANIFEST_FILE);
+    match extract::read_member(archive, &member, password, MAX_MANIFEST_BYTES).await {
+        Ok(content) => serde_json::from_slice(&content).context("Invalid volume list in archive"),
+        Err(e) => anyhow::bail!("This archive has no container volumes ({})", e),
+    }
+}
+
+/// Whether a result came from [`restore_volumes`]
+pub fn is_volume_result(result: &CheckResult) -> bool {
+    result.category == "volumes"
+}
+
+/// Restore the named volumes of `archive`, creating those that don't
+/// exist, with a result per volume
+pub async fn restore_volumes(archive: &Path, password: Option<&SecurePassword>, names: &[String]) -> Vec<CheckResult> {
+    let failed = |name: &str, detail: String| CheckResult::new("volumes", name, CheckStatus::Fail, &detail);
+    let runtime = match Runtime::find() {
+        Ok(runtime) => runtime,
+        Err(e) => return vec![failed("runtime", e.to_string())],
+    };
+    let manifest = match read_manifest(archive, password).await {
+        Ok(manifest) => manifest,
+        Err(e) => return vec![failed("archive", e.to_string())],
+    };
+    let staging = dirs::data_local_dir().unwrap_or_else(std::env::temp_dir).join("backup-manager");
+    if let Err(e) = fs::create_dir_all(&staging) {
+        return vec![failed("staging", format!("Failed to create {}: {}", staging.display(), e))];
+    }
+
+    let mut results = Vec::new();
+    for name in names {
+        let Some(snapshot) = manifest.volumes.iter().find(|snapshot| &snapshot.name == name) else {
+            results.push(failed(name, "not in this archive".to_string()));
+            continue;
+        };
+        let tar = staging.join(format!("volume-{}-{}.tar", std::process::id(), name));
+        let restored = restore_one(&runtime, archive, password, snapshot, &tar).await;
+        let _ = fs::remove_file(&tar);
+        results.push(match restored {
+            Ok(how) => {
+                info!("Restored volume {} {}", name, how);
+                let target = archive.to_string_lossy();
+                if let Err(e) = AuditLog::open_default().record("volume_restore", &target, &format!("{} {}", name, how)) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                CheckResult::new("volumes", name, CheckStatus::Pass, &format!("restored {}", how))
+            }
+            Err(e) => failed(name, format!("{:#}", e)),
+        });
+    }
+    results
+}
+
+async fn restore_one(
+    runtime: &Runtime,
+    archive: &Path,
+    password: Option<&SecurePassword>,
+    snapshot: &VolumeSnapshot,
+    tar: &Path,
+) -> Result<String> {
+    extract::extract_member_to(archive, &member(&snapshot.name), password, tar).await?;
+    let (runtime, snapshot, tar) = (runtime.clone(), snapshot.clone(), tar.to_path_buf());
+    tokio::task::spawn_blocking(move || runtime.restore(&snapshot, &tar)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_api_responses() {
+        let mut response: &[u8] = b"HTTP/1.0 200 OK\r\nApi-Version: 1.45\r\nContent-Type: application/json\r\n\r\n{\"Volumes\":null}";
+        assert_eq!(read_head(&mut response).unwrap(), 200);
+        let volumes: ApiVolumes = serde_json::from_reader(response).unwrap();
+        assert!(volumes.volumes.is_none());
+
+        let volumes: ApiVolumes = serde_json::from_str(
+            r#"{"Volumes": [
+                {"Name": "pgdata", "Driver": "local", "Mountpoint": "/var/lib/docker/volumes/pgdata/_data", "Labels": null},
+                {"Name": "cache", "Driver": "local", "Mountpoint": "/var/lib/docker/volumes/cache/_data", "Labels": {"app": "web"}}
+            ], "Warnings": null}"#,
+        )
+        .unwrap();
+        let containers: Vec<ApiContainer> = serde_json::from_str(
+            r#"[
+                {"Id": "4f1c0a9e8b7d6c5b", "Names": ["/db"], "State": "running",
+                 "Mounts": [{"Type": "volume", "Name": "pgdata", "Destination": "/var/lib/postgresql/data"}]},
+                {"Id": "9a8b7c6d5e4f3a2b", "Names": [], "State": "exited",
+                 "Mounts": [{"Type": "bind", "Source": "/srv", "Destination": "/srv"},
+                            {"Type": "volume", "Name": "pgdata", "Destination": "/backup"}]}
+            ]"#,
+        )
+        .unwrap();
+        let volumes = join_users(volumes.volumes.unwrap(), containers);
+
+        assert_eq!(volumes[0].name, "cache");
+        assert!(volumes[0].containers.is_empty());
+        assert_eq!(volumes[0].labels["app"], "web");
+        let users = &volumes[1].containers;
+        assert_eq!(users.len(), 2);
+        assert_eq!((users[0].name.as_str(), users[0].running), ("db", true));
+        assert_eq!((users[1].name.as_str(), users[1].running), ("9a8b7c6d5e4f", false));
+        assert_eq!(users[1].destination, "/backup");
+
+        assert_eq!(encode("/var/lib/my data/."), "/var/lib/my%20data/.");
+        let candidates = socket_candidates(Some("tcp://10.0.0.2:2375"), Some("unix:///run/user/1000/podman/podman.sock"), None);
+        assert_eq!(candidates[0], PathBuf::from("/run/user/1000/podman/podman.sock"));
+        assert_eq!(candidates.len(), 3);
+    }
+}
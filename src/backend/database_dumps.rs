@@ -0,0 +1,439 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command as TokioCommand;
+use zeroize::Zeroize;
+
+use crate::backend::extract;
+use crate::backend::system_state::{file_stem, SYSTEM_STATE_DIR};
+use crate::core::audit::AuditLog;
+use crate::core::keyring_store;
+use crate::core::security::SecurePassword;
+use crate::core::types::{CheckResult, CheckStatus};
+
+/// Database dumps, a file each plus a manifest, under the system state
+/// directory
+pub const DATABASES_DIR: &str = "databases";
+pub const MANIFEST_FILE: &str = "databases.json";
+const MAX_MANIFEST_BYTES: usize = 1024 * 1024;
+/// How long SQLite waits for a writer to finish before giving up
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Passwords are filed in the keyring under this prefix and the account
+/// the database names, apart from the archive passphrase
+const KEYRING_PREFIX: &str = "database:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseEngine {
+    #[serde(alias = "postgresql")]
+    Postgres,
+    #[serde(alias = "mariadb")]
+    Mysql,
+    Sqlite,
+}
+
+impl DatabaseEngine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatabaseEngine::Postgres => "postgres",
+            DatabaseEngine::Mysql => "mysql",
+            DatabaseEngine::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// A database a profile backs up
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DatabaseConfig {
+    /// Names the item and the dump in the archive
+    pub name: String,
+    pub engine: DatabaseEngine,
+    /// Database name for PostgreSQL and MySQL; the database file for
+    /// SQLite, relative to the home directory unless absolute
+    pub database: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Keyring account holding the password, stored there with
+    /// `backup-ui database-password`. Without one the server's own
+    /// authentication applies: peer, `~/.pgpass` or `~/.my.cnf`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<String>,
+}
+
+impl DatabaseConfig {
+    /// Where the dump goes in the archive, relative to the home directory
+    /// like the other items
+    pub fn dump_path(&self) -> PathBuf {
+        let extension = if self.engine == DatabaseEngine::Sqlite { "sqlite" } else { "sql" };
+        Path::new(SYSTEM_STATE_DIR)
+            .join(DATABASES_DIR)
+            .join(format!("{}.{}", file_stem(&self.name), extension))
+    }
+
+    /// What the dump is restored into, for the replay prompt
+    pub fn target(&self) -> String {
+        match self.engine {
+            DatabaseEngine::Sqlite => self.sqlite_path().display().to_string(),
+            _ => format!(
+                "{} on {}",
+                self.database,
+                match (&self.host, self.port) {
+                    (Some(host), Some(port)) => format!("{}:{}", host, port),
+                    (Some(host), None) => host.clone(),
+                    (None, Some(port)) => format!("localhost:{}", port),
+                    (None, None) => "localhost".to_string(),
+                }
+            ),
+        }
+    }
+
+    fn sqlite_path(&self) -> PathBuf {
+        let path = PathBuf::from(&self.database);
+        if path.is_absolute() {
+            path
+        } else {
+            dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")).join(path)
+        }
+    }
+
+    /// pg_dump or mysqldump style client command with the connection
+    /// options and, when the keyring has one, the password file
+    fn client(&self, program: &str, credentials: Option<&CredentialsFile>) -> TokioCommand {
+        let mut command = TokioCommand::new(program);
+        match self.engine {
+            DatabaseEngine::Postgres => {
+                command.arg("--no-password");
+                if let Some(credentials) = credentials {
+                    command.env("PGPASSFILE", &credentials.path);
+                }
+                if let Some(host) = &self.host {
+                    command.arg("--host").arg(host);
+                }
+                if let Some(port) = self.port {
+                    command.arg("--port").arg(port.to_string());
+                }
+                if let Some(user) = &self.user {
+                    command.arg("--username").arg(user);
+                }
+            }
+            _ => {
+                // Read before every other option, so it has to come first
+                if let Some(credentials) = credentials {
+                    command.arg(format!("--defaults-extra-file={}", credentials.path.display()));
+                }
+                if let Some(host) = &self.host {
+                    command.arg("--host").arg(host);
+                }
+                if let Some(port) = self.port {
+                    command.arg("--port").arg(port.to_string());
+                }
+                if let Some(user) = &self.user {
+                    command.arg("--user").arg(user);
+                }
+            }
+        }
+        command.stdin(Stdio::null());
+        command
+    }
+
+    /// The password file for the client, when the database names a
+    /// keyring account
+    async fn credentials(&self) -> Result<Option<CredentialsFile>> {
+        let Some(account) = self.keyring.clone() else {
+            return Ok(None);
+        };
+        let key = format!("{}{}", KEYRING_PREFIX, account);
+        let password = tokio::task::spawn_blocking(move || keyring_store::load(&key))
+            .await??
+            .with_context(|| {
+                format!("No password for {} in the system keyring; store it with: backup-ui database-password {}", self.name, account)
+            })?;
+        CredentialsFile::write(self.engine, &password).map(Some)
+    }
+}
+
+/// Store the password of a database's keyring account. Blocking.
+pub fn store_password(account: &str, password: &SecurePassword) -> Result<()> {
+    keyring_store::store(&format!("{}{}", KEYRING_PREFIX, account), password)
+}
+
+/// Remove the password of a database's keyring account. Blocking.
+pub fn forget_password(account: &str) -> Result<()> {
+    keyring_store::forget(&format!("{}{}", KEYRING_PREFIX, account))
+}
+
+/// A password handed to the database clients in a file only the user can
+/// read, never in argv or the environment. Removed when dropped.
+struct CredentialsFile {
+    path: PathBuf,
+}
+
+impl CredentialsFile {
+    fn write(engine: DatabaseEngine, password: &SecurePassword) -> Result<Self> {
+        let dir = dirs::data_local_dir().unwrap_or_else(std::env::temp_dir).join("backup-manager");
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("db-credentials-{}-{}", std::process::id(), rand::random::<u32>()));
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let file_guard = Self { path };
+
+        let password = std::str::from_utf8(password.as_bytes()).context("The database password is not valid UTF-8")?;
+        let mut content = credentials_content(engine, password);
+        let written = file.write_all(content.as_bytes());
+        content.zeroize();
+        written.with_context(|| format!("Failed to write {}", file_guard.path.display()))?;
+        Ok(file_guard)
+    }
+}
+
+impl Drop for CredentialsFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A `.pgpass` line matching any server, or a MySQL option file
+fn credentials_content(engine: DatabaseEngine, password: &str) -> String {
+    match engine {
+        DatabaseEngine::Postgres => format!("*:*:*:*:{}\n", password.replace('\\', "\\\\").replace(':', "\\:")),
+        _ => format!("[client]\npassword=\"{}\"\n", password.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+/// Copy a SQLite database consistently: fold its write-ahead log into
+/// the main file, then have SQLite write a snapshot of it, so a database
+/// in use is never copied halfway through a write. Blocking.
+fn copy_sqlite(source: &Path, dest: &Path) -> Result<()> {
+    let connection = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .or_else(|_| Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY))
+        .with_context(|| format!("Failed to open {}", source.display()))?;
+    connection.busy_timeout(SQLITE_BUSY_TIMEOUT)?;
+    // A read-only database or a busy writer leaves the log as it is;
+    // the snapshot below includes it either way
+    if let Err(e) = connection.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(())) {
+        debug!("Could not checkpoint {}: {}", source.display(), e);
+    }
+    connection
+        .execute("VACUUM INTO ?1", [dest.to_string_lossy()])
+        .with_context(|| format!("Failed to copy {}", source.display()))?;
+    Ok(())
+}
+
+/// Run a client, reporting the end of what it printed to stderr if it
+/// fails
+async fn run(mut command: TokioCommand, what: &str) -> Result<()> {
+    let output = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", what))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(3).collect();
+        anyhow::bail!("{} failed ({}): {}", what, output.status, tail.into_iter().rev().collect::<Vec<_>>().join(" "));
+    }
+    Ok(())
+}
+
+/// Dump `config` into `dest`
+pub async fn dump(config: &DatabaseConfig, dest: &Path) -> Result<()> {
+    match config.engine {
+        DatabaseEngine::Sqlite => {
+            let (source, dest) = (config.sqlite_path(), dest.to_path_buf());
+            tokio::task::spawn_blocking(move || copy_sqlite(&source, &dest)).await?
+        }
+        DatabaseEngine::Postgres => {
+            let credentials = config.credentials().await?;
+            let mut command = config.client("pg_dump", credentials.as_ref());
+            // Dropping what exists first lets the dump replay over a
+            // database that is already there
+            command
+                .args(["--format=plain", "--clean", "--if-exists", "--file"])
+                .arg(dest)
+                .arg(&config.database);
+            run(command, "pg_dump").await
+        }
+        DatabaseEngine::Mysql => {
+            let credentials = config.credentials().await?;
+            let mut command = config.client("mysqldump", credentials.as_ref());
+            // One consistent snapshot of InnoDB tables without locking
+            // them; --databases adds the CREATE DATABASE the replay needs
+            command
+                .args(["--single-transaction", "--routines", "--triggers", "--events"])
+                .arg(format!("--result-file={}", dest.display()))
+                .arg("--databases")
+                .arg(&config.database);
+            run(command, "mysqldump").await
+        }
+    }
+}
+
+/// Databases dumped into `archive`
+pub async fn read_manifest(archive: &Path, password: Option<&SecurePassword>) -> Result<Vec<DatabaseConfig>> {
+    let member = format!("{}/{}/{}", SYSTEM_STATE_DIR, DATABASES_DIR, MANIFEST_FILE);
+    match extract::read_member(archive, &member, password, MAX_MANIFEST_BYTES).await {
+        Ok(content) => serde_json::from_slice(&content).context("Invalid database list in archive"),
+        Err(e) => anyhow::bail!("This archive has no database dumps ({})", e),
+    }
+}
+
+/// Whether a result came from [`replay`]
+pub fn is_database_result(result: &CheckResult) -> bool {
+    result.category == "databases"
+}
+
+/// Load the dumps of `databases` from `archive` back into their
+/// databases, with a result for each. PostgreSQL databases that don't
+/// exist are created first; MySQL dumps create their own; a SQLite file
+/// is replaced, keeping the one it replaces next to it.
+pub async fn replay(archive: &Path, password: Option<&SecurePassword>, databases: &[DatabaseConfig]) -> Vec<CheckResult> {
+    let staging = dirs::data_local_dir().unwrap_or_else(std::env::temp_dir).join("backup-manager");
+    if let Err(e) = fs::create_dir_all(&staging) {
+        let detail = format!("Failed to create {}: {}", staging.display(), e);
+        return vec![CheckResult::new("databases", "staging", CheckStatus::Fail, &detail)];
+    }
+
+    let mut results = Vec::new();
+    for database in databases {
+        let dump = staging.join(format!("db-replay-{}-{}", std::process::id(), file_stem(&database.name)));
+        let member = extract::member_name(&database.dump_path());
+        let replayed = match extract::extract_member_to(archive, &member, password, &dump).await {
+            Ok(_) => replay_one(database, &dump).await,
+            Err(e) => Err(e),
+        };
+        let _ = fs::remove_file(&dump);
+
+        results.push(match replayed {
+            Ok(detail) => {
+                info!("Replayed the dump of {} into {}", database.name, database.target());
+                let target = archive.to_string_lossy();
+                let details = format!("{} into {}", database.name, database.target());
+                if let Err(e) = AuditLog::open_default().record("database_replay", &target, &details) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                CheckResult::new("databases", &database.name, CheckStatus::Pass, &detail)
+            }
+            Err(e) => CheckResult::new("databases", &database.name, CheckStatus::Fail, &format!("{:#}", e)),
+        });
+    }
+    results
+}
+
+async fn replay_one(database: &DatabaseConfig, dump: &Path) -> Result<String> {
+    match database.engine {
+        DatabaseEngine::Sqlite => {
+            let target = database.sqlite_path();
+            let kept = replace_sqlite(dump, &target)?;
+            Ok(match kept {
+                Some(kept) => format!("replaced {}, the old file is {}", target.display(), kept.display()),
+                None => format!("restored {}", target.display()),
+            })
+        }
+        DatabaseEngine::Postgres => {
+            let credentials = database.credentials().await?;
+            let mut create = database.client("createdb", credentials.as_ref());
+            create.arg(&database.database);
+            if let Err(e) = run(create, "createdb").await {
+                // Already there is the usual case
+                debug!("{:#}", e);
+            }
+            let mut psql = database.client("psql", credentials.as_ref());
+            psql.args(["--quiet", "--set", "ON_ERROR_STOP=1", "--dbname"])
+                .arg(&database.database)
+                .arg("--file")
+                .arg(dump);
+            run(psql, "psql").await?;
+            Ok(format!("replayed into {}", database.target()))
+        }
+        DatabaseEngine::Mysql => {
+            let credentials = database.credentials().await?;
+            let mut mysql = database.client("mysql", credentials.as_ref());
+            let input = File::open(dump).with_context(|| format!("Failed to open {}", dump.display()))?;
+            mysql.stdin(Stdio::from(input));
+            run(mysql, "mysql").await?;
+            Ok(format!("replayed into {}", database.target()))
+        }
+    }
+}
+
+/// Put a SQLite copy in place of `target`, moving any database there to
+/// `<file>.pre-restore` and dropping its log files, which belong to it
+fn replace_sqlite(dump: &Path, target: &Path) -> Result<Option<PathBuf>> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let kept = if target.exists() {
+        let mut name = target.as_os_str().to_owned();
+        name.push(".pre-restore");
+        let kept = PathBuf::from(name);
+        fs::rename(target, &kept).with_context(|| format!("Failed to move {} aside", target.display()))?;
+        Some(kept)
+    } else {
+        None
+    };
+    for suffix in ["-wal", "-shm"] {
+        let mut name = target.as_os_str().to_owned();
+        name.push(suffix);
+        let _ = fs::remove_file(PathBuf::from(name));
+    }
+    fs::copy(dump, target).with_context(|| format!("Failed to write {}", target.display()))?;
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_copy_and_credentials() {
+        let dir = std::env::temp_dir().join(format!("database-dumps-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // Rows still in the write-ahead log, with the database open
+        let source = dir.join("notes.db");
+        let open = Connection::open(&source).unwrap();
+        open.query_row("PRAGMA journal_mode=WAL", [], |_| Ok(())).unwrap();
+        open.execute_batch("CREATE TABLE notes (body TEXT); INSERT INTO notes VALUES ('one'), ('two');").unwrap();
+        let dump = dir.join("notes.sqlite");
+        copy_sqlite(&source, &dump).unwrap();
+        let copy = Connection::open(&dump).unwrap();
+        let count: i64 = copy.query_row("SELECT count(*) FROM notes", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+
+        drop(open);
+        let kept = replace_sqlite(&dump, &source).unwrap();
+        assert_eq!(kept, Some(dir.join("notes.db.pre-restore")));
+        assert!(!dir.join("notes.db-wal").exists());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(credentials_content(DatabaseEngine::Postgres, r"a:b\c"), "*:*:*:*:a\\:b\\\\c\n");
+        assert_eq!(credentials_content(DatabaseEngine::Mysql, r#"say "hi""#), "[client]\npassword=\"say \\\"hi\\\"\"\n");
+
+        let config: DatabaseConfig = serde_json::from_str(
+            r#"{"name": "Nextcloud DB", "engine": "postgresql", "database": "nextcloud", "host": "db.lan", "keyring": "nextcloud"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.engine, DatabaseEngine::Postgres);
+        assert_eq!(config.dump_path(), Path::new(".backup-system-state/databases/nextcloud-db.sql"));
+        assert_eq!(config.target(), "nextcloud on db.lan");
+    }
+}
@@ -0,0 +1,315 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::extract;
+use crate::backend::system_state::SYSTEM_STATE_DIR;
+use crate::core::security::SecurePassword;
+use crate::core::types::{CheckResult, CheckStatus};
+
+/// `dconf dump /` of the GNOME (and GTK app) settings database
+pub const DCONF_FILE: &str = "dconf.ini";
+/// KDE settings as individual file/group/key entries
+pub const KDE_FILE: &str = "kde-settings.json";
+
+const MAX_SETTINGS_BYTES: usize = 16 * 1024 * 1024;
+
+/// KDE config files exported key by key. Plasma panel layouts
+/// (`plasma-org.kde.plasma.desktop-appletsrc`) are left to the raw config
+/// backup: their numeric containment ids don't carry over between installs.
+const KDE_CONFIG_FILES: &[&str] = &[
+    "kdeglobals",
+    "kwinrc",
+    "kglobalshortcutsrc",
+    "khotkeysrc",
+    "kcminputrc",
+    "kxkbrc",
+    "kscreenlockerrc",
+    "ksmserverrc",
+    "plasmarc",
+    "plasmashellrc",
+    "powermanagementprofilesrc",
+    "dolphinrc",
+    "konsolerc",
+    "katerc",
+];
+
+/// One KDE setting, addressed the way `kwriteconfig` takes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KdeSetting {
+    pub file: String,
+    /// Nested group path, outermost first
+    pub groups: Vec<String>,
+    pub key: String,
+    pub value: String,
+}
+
+/// Export the dconf database, or None when dconf isn't installed or the
+/// database is empty
+pub async fn export_dconf() -> Result<Option<String>> {
+    let output = match TokioCommand::new("dconf")
+        .args(["dump", "/"])
+        .stdin(Stdio::null())
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to run dconf"),
+    };
+
+    if !output.status.success() {
+        anyhow::bail!("dconf dump failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let dump = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(if dump.trim().is_empty() { None } else { Some(dump) })
+}
+
+/// Read the KDE settings worth carrying to another install from
+/// `config_dir` (normally `~/.config`)
+pub fn export_kde(config_dir: &Path) -> Vec<KdeSetting> {
+    let mut settings = Vec::new();
+    for file in KDE_CONFIG_FILES {
+        let path = config_dir.join(file);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => settings.extend(parse_kconfig(file, &content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    settings
+}
+
+/// Parse a KConfig file into settings. Values are unescaped, since
+/// `kwriteconfig` escapes them again when importing.
+fn parse_kconfig(file: &str, content: &str) -> Vec<KdeSetting> {
+    let mut settings = Vec::new();
+    let mut groups: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // "[Group][Nested]" headers; a trailing "[$i]" marks the group immutable
+        if line.starts_with('[') && line.ends_with(']') {
+            groups = line[1..line.len() - 1]
+                .split("][")
+                .filter(|group| !group.starts_with('$'))
+                .map(str::to_string)
+                .collect();
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if groups.is_empty() {
+            continue;
+        }
+        // "Key[$e]" flags expansion of $VARS; kwriteconfig takes the bare key
+        let key = match key.trim_end().find("[$") {
+            Some(flags) => &key[..flags],
+            None => key.trim_end(),
+        };
+
+        settings.push(KdeSetting {
+            file: file.to_string(),
+            groups: groups.clone(),
+            key: key.to_string(),
+            value: unescape_kconfig(value.trim_start()),
+        });
+    }
+
+    settings
+}
+
+fn unescape_kconfig(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Import the desktop settings recorded in an archive through `dconf load`
+/// and `kwriteconfig`, so they land in whatever format the installed
+/// desktop version uses rather than overwriting its config files
+pub async fn import_desktop_settings(archive: &Path, password: Option<&SecurePassword>) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+
+    let dconf_member = format!("{}/{}", SYSTEM_STATE_DIR, DCONF_FILE);
+    if let Ok(dump) = extract::read_member(archive, &dconf_member, password, MAX_SETTINGS_BYTES).await {
+        let sections = String::from_utf8_lossy(&dump).lines().filter(|line| line.starts_with('[')).count();
+        results.push(match dconf_load(&dump).await {
+            Ok(_) => CheckResult::new("gnome", "dconf", CheckStatus::Pass, &format!("loaded {} sections", sections)),
+            Err(e) => CheckResult::new("gnome", "dconf", CheckStatus::Fail, &e.to_string()),
+        });
+    }
+
+    let kde_member = format!("{}/{}", SYSTEM_STATE_DIR, KDE_FILE);
+    if let Ok(content) = extract::read_member(archive, &kde_member, password, MAX_SETTINGS_BYTES).await {
+        match serde_json::from_slice::<Vec<KdeSetting>>(&content) {
+            Ok(settings) => results.extend(import_kde(&settings).await),
+            Err(e) => results.push(CheckResult::new("kde", "settings", CheckStatus::Fail, &format!("invalid settings list: {}", e))),
+        }
+    }
+
+    if results.is_empty() {
+        results.push(CheckResult::new("desktop", "settings", CheckStatus::Pass, "none in archive"));
+    }
+    results
+}
+
+/// Whether a result came from [`import_desktop_settings`]
+pub fn is_desktop_result(result: &CheckResult) -> bool {
+    matches!(result.category.as_str(), "gnome" | "kde" | "desktop")
+}
+
+async fn dconf_load(dump: &[u8]) -> Result<()> {
+    let mut child = TokioCommand::new("dconf")
+        .args(["load", "/"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("dconf is not installed")?;
+    {
+        let mut stdin = child.stdin.take().context("Failed to open dconf stdin")?;
+        stdin.write_all(dump).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Write each setting with kwriteconfig, one result per config file
+async fn import_kde(settings: &[KdeSetting]) -> Vec<CheckResult> {
+    let Some(kwriteconfig) = find_kwriteconfig().await else {
+        return vec![CheckResult::new(
+            "kde",
+            "settings",
+            CheckStatus::Warning,
+            "kwriteconfig6/kwriteconfig5 not found; install KDE before importing its settings",
+        )];
+    };
+
+    let mut results = Vec::new();
+    for file in KDE_CONFIG_FILES {
+        let file_settings: Vec<&KdeSetting> = settings.iter().filter(|s| s.file == *file).collect();
+        if file_settings.is_empty() {
+            continue;
+        }
+
+        let mut failed = 0;
+        let mut last_error = String::new();
+        for setting in &file_settings {
+            if let Err(e) = kwriteconfig_set(kwriteconfig, setting).await {
+                debug!("kwriteconfig failed for {}/{}: {}", setting.file, setting.key, e);
+                failed += 1;
+                last_error = e.to_string();
+            }
+        }
+
+        results.push(if failed == 0 {
+            CheckResult::new("kde", file, CheckStatus::Pass, &format!("{} keys imported", file_settings.len()))
+        } else {
+            CheckResult::new(
+                "kde",
+                file,
+                CheckStatus::Warning,
+                &format!("{} of {} keys failed: {}", failed, file_settings.len(), last_error),
+            )
+        });
+    }
+    results
+}
+
+async fn find_kwriteconfig() -> Option<&'static str> {
+    for tool in ["kwriteconfig6", "kwriteconfig5"] {
+        let found = TokioCommand::new(tool)
+            .arg("--help")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .is_ok();
+        if found {
+            return Some(tool);
+        }
+    }
+    None
+}
+
+async fn kwriteconfig_set(tool: &str, setting: &KdeSetting) -> Result<()> {
+    let mut command = TokioCommand::new(tool);
+    command.arg("--file").arg(&setting.file);
+    for group in &setting.groups {
+        command.arg("--group").arg(group);
+    }
+    // Arguments are passed directly, never through a shell
+    let output = command
+        .arg("--key")
+        .arg(&setting.key)
+        .arg("--")
+        .arg(&setting.value)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run kwriteconfig")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kconfig() {
+        let content = "\
+[General]
+ColorScheme=BreezeDark
+Name[de]=Dunkel
+
+[Containments][1][General]
+Terminal[$e]=$HOME/bin/term\\sa
+
+[KDE][$i]
+SingleClick=false
+";
+        let settings = parse_kconfig("kdeglobals", content);
+        assert_eq!(settings.len(), 4);
+        assert_eq!(settings[1].key, "Name[de]");
+        assert_eq!(settings[2].groups, vec!["Containments", "1", "General"]);
+        assert_eq!(settings[2].key, "Terminal");
+        assert_eq!(settings[2].value, "$HOME/bin/term a");
+        assert_eq!(settings[3].groups, vec!["KDE"]);
+    }
+}
@@ -0,0 +1,553 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::UnboundedSender;
+use log::{debug, info, warn};
+
+use crate::backend::throttle::{self, RateLimited};
+use crate::core::config::ThrottleConfig;
+use crate::core::types::{ArchiveInfo, BackupMode, BackupProgress, ProgressStatus, RestoreProgress};
+
+/// Prefix used on the command line to select an rclone remote, e.g.
+/// `-o rclone:gdrive:backups`
+pub const RCLONE_PREFIX: &str = "rclone:";
+
+/// Where finished archives are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// A directory on the local filesystem
+    Local(PathBuf),
+    /// Any remote configured in rclone (`remote:path`)
+    Rclone(String),
+}
+
+impl Destination {
+    pub fn parse(value: &str) -> Self {
+        match value.strip_prefix(RCLONE_PREFIX) {
+            Some(remote) => Destination::Rclone(remote.to_string()),
+            None => Destination::Local(PathBuf::from(value)),
+        }
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Destination::Local(path) => write!(f, "{}", path.display()),
+            Destination::Rclone(remote) => write!(f, "{}{}", RCLONE_PREFIX, remote),
+        }
+    }
+}
+
+/// Entry returned by `rclone lsjson`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RcloneEntry {
+    name: String,
+    size: i64,
+    mod_time: chrono::DateTime<chrono::Utc>,
+    is_dir: bool,
+}
+
+/// Thin wrapper around the `rclone` binary for streaming archives to and
+/// from a remote without implementing individual cloud providers
+pub struct RcloneRemote {
+    remote: String,
+    throttle: ThrottleConfig,
+}
+
+impl RcloneRemote {
+    pub fn new(remote: impl Into<String>) -> Self {
+        Self { remote: remote.into(), throttle: ThrottleConfig::default() }
+    }
+
+    /// Upload with rclone niced and its bandwidth capped as `throttle` says
+    pub fn with_throttle(mut self, throttle: &ThrottleConfig) -> Self {
+        self.throttle = throttle.clone();
+        self
+    }
+
+    /// Full rclone path for a file inside the configured remote
+    pub fn remote_path(&self, name: &str) -> String {
+        if self.remote.ends_with(':') || self.remote.ends_with('/') {
+            format!("{}{}", self.remote, name)
+        } else {
+            format!("{}/{}", self.remote, name)
+        }
+    }
+
+    /// Stream a local archive to the remote with `rclone rcat`, forwarding
+    /// progress parsed from rclone's `--progress` output
+    pub async fn upload(
+        &self,
+        archive: &Path,
+        progress_tx: Option<&UnboundedSender<BackupProgress>>,
+    ) -> Result<String> {
+        let name = archive
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .context("Archive path has no file name")?;
+        let target = self.remote_path(&name);
+        let size = tokio::fs::metadata(archive).await?.len();
+
+        info!("Uploading {} to {}", archive.display(), target);
+
+        let mut command = TokioCommand::new("rclone");
+        command
+            .arg("rcat")
+            .arg("--size")
+            .arg(size.to_string())
+            .arg("--progress")
+            .arg("--stats-one-line");
+        if let Some(rate) = self.throttle.bytes_per_second() {
+            // rclone reads a bare number as KiB/s
+            command.arg("--bwlimit").arg((rate / 1024).max(1).to_string());
+        }
+        throttle::limit_command(&mut command, &self.throttle);
+        let mut child = command
+            .arg(&target)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start rclone (is it installed and on PATH?)")?;
+
+        let mut stdin = child.stdin.take().context("Failed to open rclone stdin")?;
+        let mut file = tokio::fs::File::open(archive)
+            .await
+            .with_context(|| format!("Failed to open archive: {}", archive.display()))?;
+        let feeder = tokio::spawn(async move {
+            let result = tokio::io::copy(&mut file, &mut stdin).await;
+            // Dropping stdin closes the pipe so rclone sees EOF
+            drop(stdin);
+            result
+        });
+
+        let mut progress = BackupProgress {
+            current_item: format!("Uploading to {}", target),
+            total_items: 1,
+            total_bytes: size,
+            status: ProgressStatus::Processing,
+            ..BackupProgress::default()
+        };
+
+        let (line_tx, mut line_rx) = tokio::sync::mpsc::unbounded_channel();
+        spawn_line_reader(child.stdout.take(), line_tx.clone());
+        spawn_line_reader(child.stderr.take(), line_tx);
+
+        let mut last_error = None;
+        while let Some(line) = line_rx.recv().await {
+            if let Some((transferred, total)) = parse_rclone_progress(&line) {
+                if total > 0 {
+                    progress.total_bytes = total;
+                }
+                progress.record_bytes(transferred);
+                if let Some(tx) = progress_tx {
+                    let _ = tx.send(progress.clone());
+                }
+            } else if line.contains("ERROR") {
+                warn!("rclone: {}", line);
+                last_error = Some(line);
+            } else {
+                debug!("rclone: {}", line);
+            }
+        }
+
+        feeder
+            .await
+            .context("Archive upload task panicked")?
+            .context("Failed to stream archive to rclone")?;
+        let status = child.wait().await?;
+
+        if !status.success() {
+            anyhow::bail!(
+                "rclone upload failed (exit code {:?}): {}",
+                status.code(),
+                last_error.unwrap_or_else(|| "No error details available".to_string())
+            );
+        }
+
+        progress.bytes_processed = size;
+        progress.items_completed = 1;
+        progress.status = ProgressStatus::Completed;
+        if let Some(tx) = progress_tx {
+            let _ = tx.send(progress);
+        }
+
+        info!("Uploaded archive to {}", target);
+        Ok(target)
+    }
+
+    /// Stream a remote archive to a local file with `rclone cat`. Progress
+    /// is counted from the bytes received, against the archive's `size`.
+    /// Only the archive's own path is needed, so archives on any configured
+    /// remote can be fetched, not just the backup destination.
+    pub async fn fetch(
+        remote_path: &str,
+        local_path: &Path,
+        size: u64,
+        progress_tx: Option<&UnboundedSender<RestoreProgress>>,
+    ) -> Result<()> {
+        info!("Fetching {} to {}", remote_path, local_path.display());
+
+        // A fresh private file, never whatever is already at the path: in
+        // a shared temp directory that could be another user's symlink
+        let _ = std::fs::remove_file(local_path);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(local_path)
+            .with_context(|| format!("Failed to create {}", local_path.display()))?;
+
+        let mut child = TokioCommand::new("rclone")
+            .arg("cat")
+            .arg(remote_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start rclone (is it installed and on PATH?)")?;
+
+        let (line_tx, mut line_rx) = tokio::sync::mpsc::unbounded_channel();
+        spawn_line_reader(child.stderr.take(), line_tx);
+
+        let mut progress = RestoreProgress {
+            current_item: format!("Downloading {}", remote_path),
+            total_items: 1,
+            total_bytes: size,
+            status: ProgressStatus::Downloading,
+            ..RestoreProgress::default()
+        };
+        let copied = copy_counting(child.stdout.take(), tokio::fs::File::from_std(file), |received| {
+            progress.bytes_processed = received;
+            if let Some(tx) = progress_tx {
+                let _ = tx.send(progress.clone());
+            }
+        })
+        .await;
+
+        let status = child.wait().await?;
+        let mut last_error = None;
+        while let Some(line) = line_rx.recv().await {
+            warn!("rclone: {}", line);
+            last_error = Some(line);
+        }
+
+        if let Err(e) = copied {
+            let _ = std::fs::remove_file(local_path);
+            return Err(e.context(format!("Failed to download {}", remote_path)));
+        }
+        if !status.success() {
+            let _ = std::fs::remove_file(local_path);
+            anyhow::bail!(
+                "rclone cat failed: {}",
+                last_error.unwrap_or_else(|| "No error details available".to_string())
+            );
+        }
+
+        progress.items_completed = 1;
+        if let Some(tx) = progress_tx {
+            let _ = tx.send(progress);
+        }
+        Ok(())
+    }
+
+    /// List backup archives stored on the remote
+    pub async fn list_archives(&self) -> Result<Vec<ArchiveInfo>> {
+        let output = TokioCommand::new("rclone")
+            .arg("lsjson")
+            .arg(&self.remote)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to start rclone (is it installed and on PATH?)")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "rclone lsjson failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let entries: Vec<RcloneEntry> = serde_json::from_slice(&output.stdout)
+            .context("Failed to parse rclone lsjson output")?;
+
+        let archives = entries
+            .into_iter()
+            .filter(|entry| !entry.is_dir && is_archive_name(&entry.name))
+            .map(|entry| {
+                let remote_path = self.remote_path(&entry.name);
+                let mode = if entry.name.contains("secure") {
+                    BackupMode::Secure
+                } else {
+                    BackupMode::Complete
+                };
+                ArchiveInfo {
+                    path: PathBuf::from(&remote_path),
+                    encrypted: entry.name.contains("encrypted") || entry.name.contains("complete"),
+                    description: format!(
+                        "Remote archive on {} from {}",
+                        self.remote,
+                        entry.mod_time.format("%Y-%m-%d %H:%M")
+                    ),
+                    name: entry.name,
+                    created: entry.mod_time,
+                    size: entry.size.max(0) as u64,
+                    mode,
+                    items: Vec::new(),
+                    hostname: None,
+                    remote: Some(remote_path),
+                }
+            })
+            .collect();
+
+        Ok(archives)
+    }
+}
+
+/// Put a copy of a finished archive at `destination` and return where it
+/// went. A local directory must already exist, so an unmounted drive or
+/// share is reported instead of filling the empty mount point. The copy
+/// or upload is capped at `throttle`'s rate.
+pub async fn copy_archive(archive: &Path, destination: &Destination, throttle: &ThrottleConfig) -> Result<String> {
+    let dir = match destination {
+        Destination::Local(dir) => dir,
+        Destination::Rclone(remote) => {
+            return RcloneRemote::new(remote.clone()).with_throttle(throttle).upload(archive, None).await;
+        }
+    };
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not available", dir.display());
+    }
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .context("Archive path has no file name")?;
+    let target = dir.join(&name);
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    let partial = dir.join(format!(".partial-{}", name));
+
+    let (source, dest, rate) = (archive.to_path_buf(), partial.clone(), throttle.bytes_per_second());
+    let copied = tokio::task::spawn_blocking(move || -> Result<()> {
+        copy_private_limited(&source, &dest, rate)?;
+        if std::fs::metadata(&dest)?.len() != std::fs::metadata(&source)?.len() {
+            anyhow::bail!("The copy is incomplete");
+        }
+        Ok(())
+    })
+    .await?;
+    if let Err(e) = copied {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, &target)
+        .with_context(|| format!("Failed to move the copy to {}", target.display()))?;
+    Ok(target.display().to_string())
+}
+
+/// Copy `source` to a new file at `dest`, private to the user
+pub fn copy_private(source: &Path, dest: &Path) -> Result<()> {
+    copy_private_limited(source, dest, None)
+}
+
+/// [`copy_private`], writing no faster than `bytes_per_second` when given
+pub fn copy_private_limited(source: &Path, dest: &Path, bytes_per_second: Option<u64>) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut output = options
+        .open(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+    let mut input = std::fs::File::open(source).with_context(|| format!("Failed to open {}", source.display()))?;
+    let copied = match bytes_per_second {
+        Some(rate) => std::io::copy(&mut input, &mut RateLimited::new(&mut output, rate)),
+        None => std::io::copy(&mut input, &mut output),
+    };
+    copied.with_context(|| format!("Failed to copy to {}", dest.display()))?;
+    output.sync_all()?;
+    Ok(())
+}
+
+/// Whether a file name looks like one of our backup archives
+pub fn is_archive_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    [".gz", ".xz", ".bz2", ".zst", ".tar", ".gpg"].iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Forward lines from a child stream, splitting on both `\n` and `\r` since
+/// rclone redraws its progress line in place
+fn spawn_line_reader<R>(stream: Option<R>, tx: UnboundedSender<String>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let Some(mut stream) = stream else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut buf = [0u8; 4096];
+        let mut current = Vec::new();
+        while let Ok(n) = stream.read(&mut buf).await {
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                if byte == b'\n' || byte == b'\r' {
+                    if !current.is_empty() {
+                        let _ = tx.send(String::from_utf8_lossy(&current).trim().to_string());
+                        current.clear();
+                    }
+                } else {
+                    current.push(byte);
+                }
+            }
+        }
+        if !current.is_empty() {
+            let _ = tx.send(String::from_utf8_lossy(&current).trim().to_string());
+        }
+    });
+}
+
+/// Copy `reader` into `file`, calling `on_progress` with the running total
+/// at most a few times a second and once at the end
+async fn copy_counting<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    mut file: tokio::fs::File,
+    mut on_progress: impl FnMut(u64),
+) -> Result<u64> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut reader = reader.context("Failed to read rclone output")?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut received = 0u64;
+    let mut reported = std::time::Instant::now();
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read]).await?;
+        received += read as u64;
+        if reported.elapsed() >= std::time::Duration::from_millis(250) {
+            on_progress(received);
+            reported = std::time::Instant::now();
+        }
+    }
+    file.sync_all().await?;
+    on_progress(received);
+    Ok(received)
+}
+
+/// Parse the transferred/total byte counts out of an rclone stats line, e.g.
+/// `Transferred:   1.500 MiB / 10 MiB, 15%, 512 KiB/s, ETA 16s`
+pub fn parse_rclone_progress(line: &str) -> Option<(u64, u64)> {
+    let stats = line.rsplit("Transferred:").next()?.trim();
+    let stats = stats.rsplit(" : ").next()?.trim();
+    let counts = stats.split(',').next()?;
+    let (done, total) = counts.split_once('/')?;
+    Some((parse_rclone_size(done)?, parse_rclone_size(total)?))
+}
+
+fn parse_rclone_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number: f64 = parts.next()?.parse().ok()?;
+    let multiplier: f64 = match parts.next()? {
+        "B" | "Bytes" => 1.0,
+        "KiB" | "k" => 1024.0,
+        "MiB" | "M" => 1024.0 * 1024.0,
+        "GiB" | "G" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" | "T" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destination_parse() {
+        assert_eq!(
+            Destination::parse("rclone:gdrive:backups"),
+            Destination::Rclone("gdrive:backups".to_string())
+        );
+        assert_eq!(
+            Destination::parse("/mnt/backups"),
+            Destination::Local(PathBuf::from("/mnt/backups"))
+        );
+    }
+
+    #[test]
+    fn test_remote_path() {
+        assert_eq!(RcloneRemote::new("gdrive:").remote_path("a.tar.gz"), "gdrive:a.tar.gz");
+        assert_eq!(RcloneRemote::new("s3:bucket/dir").remote_path("a.tar.gz"), "s3:bucket/dir/a.tar.gz");
+    }
+
+    #[test]
+    fn test_parse_rclone_progress() {
+        assert_eq!(
+            parse_rclone_progress("Transferred:   \t    1 MiB / 4 MiB, 25%, 512 KiB/s, ETA 6s"),
+            Some((1024 * 1024, 4 * 1024 * 1024))
+        );
+        assert_eq!(
+            parse_rclone_progress("2024/01/01 12:00:00 INFO  :      512 B / 1 KiB, 50%, 0 B/s, ETA -"),
+            Some((512, 1024))
+        );
+        // File-count lines carry no units and must be ignored
+        assert_eq!(parse_rclone_progress("Transferred:            0 / 1, 0%"), None);
+    }
+
+    #[tokio::test]
+    async fn test_copy_archive_to_local_destinations() {
+        let root = std::env::temp_dir().join(format!("copy-archive-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("nas")).unwrap();
+        let archive = root.join("a.tar.gz");
+        std::fs::write(&archive, b"archive").unwrap();
+
+        let copied = copy_archive(&archive, &Destination::Local(root.join("nas")), &ThrottleConfig::default()).await.unwrap();
+        assert_eq!(std::fs::read(&copied).unwrap(), b"archive");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&copied).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        // An unmounted share, and a copy that is already there
+        assert!(copy_archive(&archive, &Destination::Local(root.join("usb")), &ThrottleConfig::default()).await.is_err());
+        assert!(copy_archive(&archive, &Destination::Local(root.join("nas")), &ThrottleConfig::default()).await.is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_copy_counting() {
+        let path = std::env::temp_dir().join(format!("fetch-test-{}", std::process::id()));
+        let file = tokio::fs::File::create(&path).await.unwrap();
+        let data = vec![7u8; 200 * 1024];
+
+        let mut reports = Vec::new();
+        let copied = copy_counting(Some(data.as_slice()), file, |received| reports.push(received)).await.unwrap();
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(written, data);
+        assert_eq!(reports.last(), Some(&copied));
+    }
+}
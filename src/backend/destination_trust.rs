@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::destination::Destination;
+
+/// What identifies a destination: filesystem UUID and mount point for a
+/// local directory, remote type, host, host key and bucket for rclone
+pub type Fingerprint = BTreeMap<String, String>;
+
+/// rclone remote settings that identify where data goes. Everything else
+/// in the rclone config (tokens, keys, passwords) is dropped, never stored
+/// or logged.
+const RCLONE_IDENTITY_KEYS: &[&str] = &["type", "provider", "host", "port", "user", "endpoint", "region", "remote"];
+
+/// Remote types whose first path component is a bucket or container
+const BUCKET_TYPES: &[&str] = &["s3", "gcs", "b2", "azureblob", "swift", "oos", "qingstor"];
+
+/// Fingerprint last seen for a destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedDestination {
+    pub fingerprint: Fingerprint,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// One field that differs from the trusted fingerprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerprintChange {
+    pub field: String,
+    pub trusted: Option<String>,
+    pub current: Option<String>,
+}
+
+/// Destination whose fingerprint no longer matches, waiting for the user
+/// to trust it or cancel the backup
+#[derive(Debug, Clone)]
+pub struct DestinationChange {
+    pub destination: Destination,
+    pub fingerprint: Fingerprint,
+    pub changes: Vec<FingerprintChange>,
+}
+
+/// How a destination's current fingerprint compares with the stored one
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrustCheck {
+    /// Never written to before; the fingerprint is trusted from now on
+    New,
+    Trusted,
+    Changed(Vec<FingerprintChange>),
+}
+
+/// Fingerprints of the destinations complete archives have been written
+/// to, trusted on first use
+pub struct TrustStore {
+    path: PathBuf,
+}
+
+impl TrustStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Default location under the user's data directory
+    pub fn open_default() -> Self {
+        Self::new(
+            dirs::data_local_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("backup-manager")
+                .join("trusted-destinations.json"),
+        )
+    }
+
+    fn load(&self) -> Result<HashMap<String, TrustedDestination>> {
+        match fs::read(&self.path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse {}", self.path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", self.path.display())),
+        }
+    }
+
+    /// Compare `fingerprint` with what was trusted for `destination`,
+    /// recording it if the destination is new
+    pub fn check(&self, destination: &Destination, fingerprint: &Fingerprint) -> Result<TrustCheck> {
+        let trusted = self.load()?;
+        match trusted.get(&destination.to_string()) {
+            None => {
+                self.trust(destination, fingerprint)?;
+                Ok(TrustCheck::New)
+            }
+            Some(known) => {
+                let changes = compare(&known.fingerprint, fingerprint);
+                if changes.is_empty() {
+                    Ok(TrustCheck::Trusted)
+                } else {
+                    Ok(TrustCheck::Changed(changes))
+                }
+            }
+        }
+    }
+
+    /// Trust `fingerprint` for `destination` from now on
+    pub fn trust(&self, destination: &Destination, fingerprint: &Fingerprint) -> Result<()> {
+        let mut trusted = self.load()?;
+        trusted.insert(
+            destination.to_string(),
+            TrustedDestination {
+                fingerprint: fingerprint.clone(),
+                first_seen: Utc::now(),
+            },
+        );
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = self.path.with_extension("json.partial");
+        write_private(&partial, &serde_json::to_vec_pretty(&trusted)?)?;
+        fs::rename(&partial, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// Fields that differ between two fingerprints
+pub fn compare(trusted: &Fingerprint, current: &Fingerprint) -> Vec<FingerprintChange> {
+    let mut fields: Vec<&String> = trusted.keys().chain(current.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| trusted.get(*field) != current.get(*field))
+        .map(|field| FingerprintChange {
+            field: field.clone(),
+            trusted: trusted.get(field).cloned(),
+            current: current.get(field).cloned(),
+        })
+        .collect()
+}
+
+/// Fingerprint `destination` as it is right now
+pub async fn fingerprint(destination: &Destination) -> Result<Fingerprint> {
+    match destination {
+        Destination::Local(path) => local_fingerprint(path).await,
+        Destination::Rclone(remote) => rclone_fingerprint(remote).await,
+    }
+}
+
+/// Filesystem holding `path`, found through its nearest existing parent so
+/// a backup directory that is yet to be created still resolves
+async fn local_fingerprint(path: &Path) -> Result<Fingerprint> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .context("Destination has no existing parent directory")?;
+
+    let output = TokioCommand::new("findmnt")
+        .args(["-n", "-P", "-o", "UUID,FSTYPE,SOURCE,TARGET", "-T"])
+        .arg(existing)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .context("Failed to run findmnt")?;
+    if !output.status.success() {
+        anyhow::bail!("findmnt failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let fields = parse_pairs(&String::from_utf8_lossy(&output.stdout));
+    let mut fingerprint = Fingerprint::new();
+    for (key, value) in fields {
+        if value.is_empty() {
+            continue;
+        }
+        fingerprint.insert(key.to_lowercase(), value);
+    }
+    // Device names move around between plugs; only fall back to them for
+    // filesystems without a UUID
+    if fingerprint.contains_key("uuid") {
+        fingerprint.remove("source");
+    }
+    Ok(fingerprint)
+}
+
+/// Identity settings of the rclone remote, the bucket, and for SFTP the
+/// host keys the server presents now
+async fn rclone_fingerprint(remote: &str) -> Result<Fingerprint> {
+    let (name, path) = remote.split_once(':').context("rclone destination must be <remote>:<path>")?;
+
+    let output = TokioCommand::new("rclone")
+        .args(["config", "dump"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .context("Failed to start rclone (is it installed and on PATH?)")?;
+    if !output.status.success() {
+        anyhow::bail!("rclone config dump failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let config: HashMap<String, HashMap<String, serde_json::Value>> =
+        serde_json::from_slice(&output.stdout).context("Failed to parse rclone config")?;
+    let settings = config
+        .get(name)
+        .with_context(|| format!("rclone has no remote named {}", name))?;
+
+    let mut fingerprint: Fingerprint = settings
+        .iter()
+        .filter(|(key, _)| RCLONE_IDENTITY_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())))
+        .collect();
+
+    let remote_type = fingerprint.get("type").cloned().unwrap_or_default();
+    if BUCKET_TYPES.contains(&remote_type.as_str()) {
+        if let Some(bucket) = path.trim_start_matches('/').split('/').next().filter(|b| !b.is_empty()) {
+            fingerprint.insert("bucket".to_string(), bucket.to_string());
+        }
+    }
+    if remote_type == "sftp" {
+        let host = fingerprint.get("host").cloned().context("sftp remote has no host")?;
+        let port = fingerprint.get("port").cloned().unwrap_or_else(|| "22".to_string());
+        fingerprint.insert("host_key".to_string(), host_key(&host, &port).await?);
+    }
+    Ok(fingerprint)
+}
+
+/// SHA-256 over the host keys an SSH server presents
+async fn host_key(host: &str, port: &str) -> Result<String> {
+    let output = TokioCommand::new("ssh-keyscan")
+        .args(["-T", "10", "-p", port, "--", host])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run ssh-keyscan")?;
+
+    // "host type key" lines; only the key type and key identify the server
+    let mut keys: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace().skip(1);
+            Some(format!("{} {}", parts.next()?, parts.next()?))
+        })
+        .collect();
+    if keys.is_empty() {
+        anyhow::bail!("{} did not present any SSH host keys", host);
+    }
+    keys.sort();
+    let digest = Sha256::digest(keys.join("\n").as_bytes());
+    Ok(format!("SHA256:{}", digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>()))
+}
+
+/// `KEY="value" KEY2="value"` pairs as printed by `findmnt -P`
+fn parse_pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = line.trim();
+    while let Some((key, after)) = rest.split_once("=\"") {
+        let Some(end) = after.find('"') else {
+            break;
+        };
+        pairs.push((key.trim().to_string(), after[..end].to_string()));
+        rest = &after[end + 1..];
+    }
+    pairs
+}
+
+fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trust_on_first_use() {
+        let path = std::env::temp_dir().join(format!("trusted-destinations-{}.json", std::process::id()));
+        let store = TrustStore::new(path.clone());
+        let destination = Destination::parse("/mnt/usb/backups");
+
+        let pairs = parse_pairs(r#"UUID="1234-ABCD" FSTYPE="vfat" SOURCE="/dev/sdb1" TARGET="/mnt/usb""#);
+        let usb: Fingerprint = pairs.into_iter().map(|(k, v)| (k.to_lowercase(), v)).collect();
+        assert_eq!(store.check(&destination, &usb).unwrap(), TrustCheck::New);
+        assert_eq!(store.check(&destination, &usb).unwrap(), TrustCheck::Trusted);
+
+        // Drive not mounted: the directory is now on the root filesystem
+        let mut other = usb.clone();
+        other.insert("uuid".to_string(), "9f0e-root".to_string());
+        other.insert("target".to_string(), "/".to_string());
+        other.remove("source");
+        let TrustCheck::Changed(changes) = store.check(&destination, &other).unwrap() else {
+            panic!("fingerprint change not detected");
+        };
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["source", "target", "uuid"]);
+        assert_eq!(changes[2].trusted.as_deref(), Some("1234-ABCD"));
+
+        store.trust(&destination, &other).unwrap();
+        assert_eq!(store.check(&destination, &other).unwrap(), TrustCheck::Trusted);
+        fs::remove_file(&path).unwrap();
+    }
+}
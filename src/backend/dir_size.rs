@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Size of one path, sent as soon as its walk finishes
+#[derive(Debug)]
+pub struct SizeUpdate {
+    /// The path as it was passed to [`spawn_size_scan`]
+    pub path: PathBuf,
+    pub size: Option<u64>,
+}
+
+/// Compute the size of each `(key, full_path)` pair on tokio's blocking
+/// pool, one task per path, so several directories are walked in parallel
+/// and small items report long before a large one finishes. Dropping the
+/// receiver stops tasks that haven't started yet.
+pub fn spawn_size_scan(paths: Vec<(PathBuf, PathBuf)>) -> UnboundedReceiver<SizeUpdate> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    for (key, full_path) in paths {
+        let tx = tx.clone();
+        tokio::task::spawn_blocking(move || {
+            if tx.is_closed() {
+                return;
+            }
+            let size = match path_size(&full_path) {
+                Ok(size) => Some(size),
+                Err(e) => {
+                    debug!("Failed to size {}: {}", full_path.display(), e);
+                    None
+                }
+            };
+            let _ = tx.send(SizeUpdate { path: key, size });
+        });
+    }
+    rx
+}
+
+/// Total size of the files under `path`. The top-level path is followed if
+/// it is a symlink; links inside directories are not, so link loops can't
+/// recurse forever and linked trees aren't counted twice.
+pub fn path_size(path: &Path) -> Result<u64> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(if metadata.is_file() { metadata.len() } else { 0 });
+    }
+
+    let mut total_size = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Unreadable subdirectories are skipped rather than failing the item
+            Err(e) if dir != path => {
+                debug!("Skipping {}: {}", dir.display(), e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+    Ok(total_size)
+}
+
+/// Capacity and free space in bytes of the filesystem holding `path`
+pub async fn disk_space(path: &Path) -> Result<(u64, u64)> {
+    let output = TokioCommand::new("df")
+        .args(["-B1", "--output=size,avail", "--"])
+        .arg(path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .context("Failed to run df")?;
+    if !output.status.success() {
+        anyhow::bail!("df failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout)).context("Unexpected df output")
+}
+
+fn parse_df(content: &str) -> Option<(u64, u64)> {
+    let mut fields = content.lines().nth(1)?.split_whitespace();
+    Some((fields.next()?.parse().ok()?, fields.next()?.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_size_scan_reports_each_path() {
+        let dir = std::env::temp_dir().join(format!("dir-size-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("tree/nested")).unwrap();
+        fs::write(dir.join("tree/a"), vec![0u8; 100]).unwrap();
+        fs::write(dir.join("tree/nested/b"), vec![0u8; 50]).unwrap();
+        fs::write(dir.join("file"), vec![0u8; 7]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("tree"), dir.join("tree/nested/loop")).unwrap();
+
+        let mut rx = spawn_size_scan(vec![
+            (PathBuf::from("tree"), dir.join("tree")),
+            (PathBuf::from("file"), dir.join("file")),
+            (PathBuf::from("missing"), dir.join("missing")),
+        ]);
+        let mut updates = Vec::new();
+        while let Some(update) = rx.recv().await {
+            updates.push((update.path.to_string_lossy().to_string(), update.size));
+        }
+        updates.sort();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            updates,
+            vec![
+                ("file".to_string(), Some(7)),
+                ("missing".to_string(), None),
+                ("tree".to_string(), Some(150)),
+            ]
+        );
+
+        assert_eq!(parse_df("     1B-blocks       Avail\n 32000000000 12000000000\n"), Some((32_000_000_000, 12_000_000_000)));
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use log::warn;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as TokioCommand;
+use zeroize::Zeroize;
+
+use crate::backend::catalog::{local_hostname, Catalog};
+use crate::backend::checksum::{self, HashAlgorithm};
+use crate::core::security::SecurePassword;
+use crate::core::types::Compression;
+use crate::ui::terminal::format_bytes;
+
+/// Archives listed on the sheet, newest first
+const SHEET_ARCHIVE_LIMIT: usize = 10;
+
+/// Archive as listed on the sheet
+#[derive(Debug, Clone)]
+pub struct SheetArchive {
+    pub name: String,
+    /// Local path, or the rclone path for archives kept on a remote
+    pub location: String,
+    pub remote: bool,
+    pub created: DateTime<Utc>,
+    pub size: u64,
+    pub mode: String,
+    pub encrypted: bool,
+    pub checksum: Option<String>,
+}
+
+/// GPG secret key that may be needed to decrypt archives
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetKey {
+    pub fingerprint: String,
+    pub user_id: String,
+}
+
+/// Everything needed to get this machine's data back after losing the
+/// machine: where the archives are, how to check and decrypt them and the
+/// restore steps. Meant to be kept in a password manager or printed, so it
+/// never lives only inside the backups it describes.
+pub struct EmergencySheet {
+    pub generated: DateTime<Local>,
+    pub hostname: String,
+    pub archives: Vec<SheetArchive>,
+    pub keys: Vec<SheetKey>,
+    pub catalog_sync: Option<PathBuf>,
+}
+
+impl EmergencySheet {
+    pub async fn gather(catalog: &Catalog, catalog_sync: Option<PathBuf>) -> Result<Self> {
+        let hostname = local_hostname();
+        let mut archives: Vec<SheetArchive> = catalog
+            .export_host(&hostname)?
+            .into_iter()
+            .map(|archive| SheetArchive {
+                location: archive.remote.clone().unwrap_or_else(|| archive.path.clone()),
+                remote: archive.remote.is_some(),
+                name: archive.name,
+                created: archive.created,
+                size: archive.size,
+                mode: archive.mode,
+                encrypted: archive.encrypted,
+                checksum: archive.checksum,
+            })
+            .collect();
+        archives.sort_by_key(|archive| std::cmp::Reverse(archive.created));
+        archives.truncate(SHEET_ARCHIVE_LIMIT);
+
+        let keys = match secret_keys().await {
+            Ok(keys) => keys,
+            Err(e) => {
+                warn!("Failed to list GPG keys for the emergency sheet: {}", e);
+                Vec::new()
+            }
+        };
+
+        Ok(Self {
+            generated: Local::now(),
+            hostname,
+            archives,
+            keys,
+            catalog_sync,
+        })
+    }
+
+    /// Directories holding the listed local archives; the sheet must not
+    /// be stored in any of them
+    pub fn archive_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self.archives
+            .iter()
+            .filter(|archive| !archive.remote)
+            .filter_map(|archive| Path::new(&archive.location).parent().map(Path::to_path_buf))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    pub fn render(&self) -> String {
+        let mut sheet = String::new();
+        let _ = writeln!(sheet, "BACKUP EMERGENCY SHEET - {}", self.hostname);
+        let _ = writeln!(sheet, "Generated {}", self.generated.format("%Y-%m-%d %H:%M %Z"));
+        let _ = writeln!(sheet);
+
+        let _ = writeln!(sheet, "ARCHIVES (newest first)");
+        if self.archives.is_empty() {
+            let _ = writeln!(sheet, "  None cataloged yet. Run a backup, then regenerate this sheet.");
+        }
+        for archive in &self.archives {
+            let _ = writeln!(
+                sheet,
+                "  {}  {}  {} mode  {}{}",
+                archive.created.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                archive.name,
+                archive.mode,
+                format_bytes(archive.size),
+                if archive.encrypted { "  encrypted" } else { "" },
+            );
+            let _ = writeln!(sheet, "    location: {}", archive.location);
+            match archive.checksum.as_deref() {
+                Some(recorded) => {
+                    let algorithm = HashAlgorithm::of(recorded).as_str();
+                    let _ = writeln!(sheet, "    {:<9} {}", format!("{}:", algorithm), checksum::digest(recorded));
+                }
+                None => {
+                    let _ = writeln!(sheet, "    checksum: not recorded");
+                }
+            }
+        }
+        let _ = writeln!(sheet);
+
+        let _ = writeln!(sheet, "GPG KEYS ON THIS MACHINE");
+        if self.keys.is_empty() {
+            let _ = writeln!(sheet, "  None. Encrypted archives use a passphrase only.");
+        }
+        for key in &self.keys {
+            let _ = writeln!(sheet, "  {}  {}", key.fingerprint, key.user_id);
+        }
+        let _ = writeln!(sheet, "  Archive passphrases are NOT on this sheet; keep them in your password manager.");
+        let _ = writeln!(sheet);
+
+        if let Some(sync) = &self.catalog_sync {
+            let _ = writeln!(sheet, "SHARED CATALOG");
+            let _ = writeln!(sheet, "  {}", sync.display());
+            let _ = writeln!(sheet);
+        }
+
+        let _ = writeln!(sheet, "RESTORE STEPS");
+        let example = self.archives.first();
+        let name = example.map(|archive| archive.name.as_str()).unwrap_or("ARCHIVE");
+        let _ = writeln!(sheet, "  1. Copy the newest archive to the new machine");
+        if let Some(archive) = example.filter(|archive| archive.remote) {
+            let _ = writeln!(sheet, "       rclone copy '{}' .", archive.location);
+        }
+        let algorithm = example
+            .and_then(|archive| archive.checksum.as_deref())
+            .map(HashAlgorithm::of)
+            .unwrap_or_default();
+        let _ = writeln!(sheet, "  2. Check it against the {} above", algorithm.as_str());
+        let _ = writeln!(sheet, "       {} {}", algorithm.command(), name);
+        let tar_option = Compression::detect(Path::new(name))
+            .map(|compression| format!(" {}", compression.tar_option()))
+            .unwrap_or_default();
+        let _ = writeln!(sheet, "  3. Extract into your home directory");
+        if name.ends_with(".gpg") {
+            let _ = writeln!(sheet, "       gpg --decrypt {} | tar -xpf -{} -C ~", name, tar_option);
+        } else {
+            let _ = writeln!(sheet, "       tar -xpf {}{} -C ~", name, tar_option);
+        }
+        let _ = writeln!(sheet, "     or install backup-ui and choose Restore to pick items and keep a rollback snapshot");
+        let _ = writeln!(sheet, "  4. Re-enable services and desktop settings from the restore results screen (E, D)");
+        let _ = writeln!(sheet, "  5. Fix key permissions: chmod 700 ~/.ssh ~/.gnupg; chmod 600 ~/.ssh/id_*");
+        sheet
+    }
+}
+
+/// ASCII-armored copy of `sheet` encrypted with `password`, safe to paste
+/// into a password manager note or store on separate media
+pub async fn encrypt(sheet: &str, password: &SecurePassword) -> Result<Vec<u8>> {
+    let mut child = TokioCommand::new("gpg")
+        .args([
+            "--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0",
+            "--symmetric", "--cipher-algo", "AES256", "--armor", "--output", "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run gpg")?;
+
+    // gpg reads the passphrase line from stdin and the plaintext after it,
+    // so neither touches argv, the environment or the disk
+    let mut input = password.as_bytes().to_vec();
+    input.push(b'\n');
+    input.extend_from_slice(sheet.as_bytes());
+    let mut stdin = child.stdin.take().context("Failed to open gpg stdin")?;
+    let written = stdin.write_all(&input).await;
+    input.zeroize();
+    written.context("Failed to pass the sheet to gpg")?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!("gpg failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+async fn secret_keys() -> Result<Vec<SheetKey>> {
+    let output = TokioCommand::new("gpg")
+        .args(["--batch", "--list-secret-keys", "--with-colons"])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run gpg")?;
+    if !output.status.success() {
+        anyhow::bail!("gpg failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(parse_secret_keys(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Primary keys from `gpg --list-secret-keys --with-colons`, each with its
+/// first user id
+fn parse_secret_keys(listing: &str) -> Vec<SheetKey> {
+    let mut keys: Vec<SheetKey> = Vec::new();
+    // Fingerprint and uid records that follow a "sec" record belong to it;
+    // those after an "ssb" (subkey) record don't
+    let mut in_primary = false;
+    for line in listing.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first().copied() {
+            Some("sec") => {
+                in_primary = true;
+                keys.push(SheetKey { fingerprint: String::new(), user_id: String::new() });
+            }
+            Some("ssb") => in_primary = false,
+            Some("fpr") if in_primary => {
+                if let (Some(key), Some(fpr)) = (keys.last_mut(), fields.get(9)) {
+                    if key.fingerprint.is_empty() {
+                        key.fingerprint = fpr.to_string();
+                    }
+                }
+            }
+            Some("uid") => {
+                if let (Some(key), Some(uid)) = (keys.last_mut(), fields.get(9)) {
+                    if key.user_id.is_empty() {
+                        key.user_id = uid.to_string();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    keys.retain(|key| !key.fingerprint.is_empty());
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_lists_keys_and_restore_steps() {
+        let listing = "\
+sec:u:255:22:ABCDEF0123456789:1700000000:::u:::scESC:::+:::ed25519:::0:
+fpr:::::::::0123456789ABCDEF0123456789ABCDEF01234567:
+grp:::::::::AAAA:
+uid:u::::1700000000::HASH::Jane Doe <jane@example.com>::::::::::0:
+ssb:u:255:18:1111222233334444:1700000000::::::e:::+:::cv25519::
+fpr:::::::::FFFFEEEEDDDDCCCCBBBBAAAA9999888877776666:
+";
+        let keys = parse_secret_keys(listing);
+        assert_eq!(
+            keys,
+            vec![SheetKey {
+                fingerprint: "0123456789ABCDEF0123456789ABCDEF01234567".to_string(),
+                user_id: "Jane Doe <jane@example.com>".to_string(),
+            }]
+        );
+
+        let sheet = EmergencySheet {
+            generated: Local::now(),
+            hostname: "laptop".to_string(),
+            archives: vec![SheetArchive {
+                name: "backup_laptop_20250101_secure.tar.zst.gpg".to_string(),
+                location: "/mnt/backup/backup_laptop_20250101_secure.tar.zst.gpg".to_string(),
+                remote: false,
+                created: Utc::now(),
+                size: 1024,
+                mode: "secure".to_string(),
+                encrypted: true,
+                checksum: Some("deadbeef".to_string()),
+            }],
+            keys,
+            catalog_sync: None,
+        };
+        let text = sheet.render();
+        assert!(text.contains("sha256:   deadbeef"));
+        assert!(text.contains("gpg --decrypt backup_laptop_20250101_secure.tar.zst.gpg | tar -xpf - --zstd -C ~"));
+        assert!(text.contains("Jane Doe"));
+        assert_eq!(sheet.archive_dirs(), vec![PathBuf::from("/mnt/backup")]);
+    }
+}
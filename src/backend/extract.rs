@@ -0,0 +1,477 @@
+use anyhow::{Context, Result};
+use log::warn;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command as TokioCommand};
+use zeroize::Zeroize;
+
+use crate::backend::member_index::{IndexedMember, MemberIndex, MemberKind, MemberReader};
+use crate::core::diff::{collapse_context, diff_lines, DiffLine};
+use crate::core::security::SecurePassword;
+use crate::core::types::{Compression, RestoreItem, RestoreMetadata};
+
+/// Largest file either side of a diff may be
+const MAX_DIFF_BYTES: usize = 1024 * 1024;
+
+/// Tar member name for a restore item. Archives are created with
+/// `tar -C $HOME`, so members are home-relative without a leading `./`.
+pub fn member_name(original_path: &Path) -> String {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+    let relative = original_path.strip_prefix(&home).unwrap_or(original_path);
+    relative
+        .to_string_lossy()
+        .trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Read a single file out of an archive without extracting anything to
+/// disk, decrypting GPG archives on the fly. Reads at most `max_bytes`;
+/// larger members are reported as an error.
+pub async fn read_member(
+    archive: &Path,
+    member: &str,
+    password: Option<&SecurePassword>,
+    max_bytes: usize,
+) -> Result<Vec<u8>> {
+    if let Some(indexed) = indexed_file(archive, member).await {
+        if indexed.size > max_bytes as u64 {
+            anyhow::bail!("{} is larger than {} bytes", member, max_bytes);
+        }
+        let archive = archive.to_path_buf();
+        return tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+            use std::io::Read;
+            let mut content = Vec::with_capacity(indexed.size as usize);
+            MemberReader::open(&archive, &indexed)?.read_to_end(&mut content)?;
+            Ok(content)
+        })
+        .await?;
+    }
+
+    let (mut child, gpg) = spawn_member(archive, member, password).await?;
+    let mut stdout = child.stdout.take().context("Failed to read tar output")?;
+
+    let mut content = Vec::new();
+    (&mut stdout)
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut content)
+        .await?;
+    if content.len() > max_bytes {
+        let _ = child.kill().await;
+        if let Some(mut gpg) = gpg {
+            let _ = gpg.kill().await;
+        }
+        anyhow::bail!("{} is larger than {} bytes", member, max_bytes);
+    }
+
+    finish_member(child, gpg, member).await?;
+    Ok(content)
+}
+
+/// Copy a single file out of an archive into `dest`, readable only by the
+/// user, for members too large to hold in memory. Returns its size.
+pub async fn extract_member_to(
+    archive: &Path,
+    member: &str,
+    password: Option<&SecurePassword>,
+    dest: &Path,
+) -> Result<u64> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options
+        .open(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+
+    if let Some(indexed) = indexed_file(archive, member).await {
+        let (archive, dest) = (archive.to_path_buf(), dest.to_path_buf());
+        return tokio::task::spawn_blocking(move || -> Result<u64> {
+            let mut file = file;
+            std::io::copy(&mut MemberReader::open(&archive, &indexed)?, &mut file)
+                .with_context(|| format!("Failed to write {}", dest.display()))
+        })
+        .await?;
+    }
+    let mut file = tokio::fs::File::from_std(file);
+
+    let (mut child, gpg) = spawn_member(archive, member, password).await?;
+    let mut stdout = child.stdout.take().context("Failed to read tar output")?;
+    let copied = tokio::io::copy(&mut stdout, &mut file).await;
+    let finished = finish_member(child, gpg, member).await;
+    let bytes = copied.with_context(|| format!("Failed to write {}", dest.display()))?;
+    finished?;
+    file.flush().await?;
+    Ok(bytes)
+}
+
+/// `member` as listed in the archive's index, when it has one and the
+/// member is a regular file that can be read straight from the archive
+async fn indexed_file(archive: &Path, member: &str) -> Option<IndexedMember> {
+    let (archive, member) = (archive.to_path_buf(), member.to_string());
+    match tokio::task::spawn_blocking(move || MemberIndex::find(&archive, &member)).await {
+        Ok(Ok(found)) => found.filter(|indexed| indexed.kind == MemberKind::File),
+        Ok(Err(e)) => {
+            warn!("Ignoring archive index: {}", e);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Start tar writing `member` to its stdout, behind gpg for encrypted
+/// archives
+async fn spawn_member(archive: &Path, member: &str, password: Option<&SecurePassword>) -> Result<(Child, Option<Child>)> {
+    let mut tar = tar_reading(archive, "-xO");
+    tar.arg("--").arg(member)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    spawn_tar(tar, archive, password).await
+}
+
+/// Tar running `operation` on `archive`, reading it from stdin when it is
+/// encrypted
+fn tar_reading(archive: &Path, operation: &str) -> TokioCommand {
+    let mut tar = TokioCommand::new("tar");
+    tar.arg(operation);
+    if is_encrypted(archive) {
+        tar.arg("-f").arg("-");
+        if let Some(compression) = Compression::detect(archive) {
+            tar.arg(compression.tar_option());
+        }
+    } else {
+        tar.arg("-f").arg(archive);
+    }
+    tar
+}
+
+/// Start a [`tar_reading`] command, with gpg feeding it for encrypted
+/// archives
+async fn spawn_tar(mut tar: TokioCommand, archive: &Path, password: Option<&SecurePassword>) -> Result<(Child, Option<Child>)> {
+    let mut gpg = None;
+    if is_encrypted(archive) {
+        let password = password.context("Archive is encrypted but no password was provided")?;
+        let mut child = spawn_decrypt(archive, password).await?;
+        tar.stdin(decrypted_stdio(&mut child)?);
+        gpg = Some(child);
+    }
+
+    let child = tar.spawn().context("Failed to run tar")?;
+    Ok((child, gpg))
+}
+
+fn is_encrypted(archive: &Path) -> bool {
+    archive.extension().map(|e| e == "gpg").unwrap_or(false)
+}
+
+/// Extract one item, a file or a whole directory, to `dest` without going
+/// through a restore. `dest` must not exist yet. The item is unpacked into
+/// a private directory beside `dest` and moved into place once tar has
+/// finished, so a failed extraction leaves nothing behind. Owners are not
+/// kept and setuid/setgid bits are cleared.
+pub async fn extract_item_to(
+    archive: &Path,
+    member: &str,
+    password: Option<&SecurePassword>,
+    dest: &Path,
+) -> Result<()> {
+    if dest.symlink_metadata().is_ok() {
+        anyhow::bail!("{} already exists", dest.display());
+    }
+    let parent = dest
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+    let staging = parent.join(format!(".backup-ui-extract-{}", std::process::id()));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+
+    let extracted = extract_into(archive, member, password, &staging, dest).await;
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+    extracted
+}
+
+async fn extract_into(
+    archive: &Path,
+    member: &str,
+    password: Option<&SecurePassword>,
+    staging: &Path,
+    dest: &Path,
+) -> Result<()> {
+    let mut tar = tar_reading(archive, "-x");
+    tar.arg("-C").arg(staging)
+        .arg("--no-same-owner")
+        .arg("--").arg(member)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let (child, gpg) = spawn_tar(tar, archive, password).await?;
+    finish_member(child, gpg, member).await?;
+
+    let extracted = staging.join(member);
+    clear_privileged_bits(&extracted)?;
+    std::fs::rename(&extracted, dest)
+        .with_context(|| format!("Failed to move {} into place", dest.display()))
+}
+
+/// Drop setuid/setgid bits from `path` and everything under it
+fn clear_privileged_bits(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = path
+            .symlink_metadata()
+            .with_context(|| format!("Failed to stat {}", path.display()))?;
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                clear_privileged_bits(&entry?.path())?;
+            }
+        }
+        let mode = metadata.permissions().mode();
+        if !metadata.file_type().is_symlink() && mode & 0o6000 != 0 {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & !0o6000))?;
+        }
+    }
+    Ok(())
+}
+
+/// Restore exactly `items` from `archive`, each to its restore path,
+/// with a tar run per directory they land in that names their members.
+/// An item inside another selected item is left to the outer one.
+pub async fn restore_items(
+    archive: &Path,
+    items: &[&RestoreItem],
+    password: Option<&SecurePassword>,
+    metadata: RestoreMetadata,
+) -> Result<()> {
+    for (base, members) in restore_groups(items)? {
+        std::fs::create_dir_all(&base)
+            .with_context(|| format!("Failed to create {}", base.display()))?;
+        let mut tar = tar_reading(archive, "-x");
+        // Member names are literal, so `*` or `?` in a file name can't
+        // pull in anything else
+        tar.arg("-p")
+            .args(metadata.tar_options())
+            .arg("-C").arg(&base)
+            .arg("--no-wildcards")
+            .arg("--").args(&members)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        let (child, gpg) = spawn_tar(tar, archive, password).await?;
+        finish_member(child, gpg, &members.join(", ")).await?;
+    }
+    Ok(())
+}
+
+/// Selected items as (directory to extract in, member names), dropping
+/// items nested in another selected one
+fn restore_groups(items: &[&RestoreItem]) -> Result<Vec<(PathBuf, Vec<String>)>> {
+    let members: Vec<String> = items.iter().map(|item| member_name(&item.original_path)).collect();
+    let mut groups: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    for (item, member) in items.iter().zip(&members) {
+        let nested = members
+            .iter()
+            .any(|outer| member.strip_prefix(outer.as_str()).is_some_and(|rest| rest.starts_with('/')));
+        if nested || member.is_empty() {
+            continue;
+        }
+
+        let mut base = item.restore_path.clone();
+        for _ in Path::new(member).components() {
+            base.pop();
+        }
+        if base.join(member) != item.restore_path {
+            anyhow::bail!("Cannot restore {} to {}", member, item.restore_path.display());
+        }
+        match groups.iter_mut().find(|(existing, _)| *existing == base) {
+            Some((_, group)) if !group.contains(member) => group.push(member.clone()),
+            Some(_) => {}
+            None => groups.push((base, vec![member.clone()])),
+        }
+    }
+    Ok(groups)
+}
+
+/// Wait for the processes of [`spawn_member`] once its output is read
+async fn finish_member(child: Child, gpg: Option<Child>, member: &str) -> Result<()> {
+    let output = child.wait_with_output().await?;
+    if let Some(gpg) = gpg {
+        let status = gpg.wait_with_output().await?.status;
+        if !status.success() {
+            anyhow::bail!("Failed to decrypt archive (wrong password?)");
+        }
+    }
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to read {} from archive: {}",
+            member,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Start decrypting a GPG archive; the plaintext tarball is on the child's
+/// stdout
+pub async fn spawn_decrypt(archive: &Path, password: &SecurePassword) -> Result<Child> {
+    let mut child = TokioCommand::new("gpg")
+        .args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0", "--decrypt"])
+        .arg(archive)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run gpg")?;
+
+    // The passphrase goes over a pipe, never argv or the environment
+    let mut passphrase = password.as_bytes().to_vec();
+    passphrase.push(b'\n');
+    let mut stdin = child.stdin.take().context("Failed to open gpg stdin")?;
+    let written = stdin.write_all(&passphrase).await;
+    passphrase.zeroize();
+    written.context("Failed to pass passphrase to gpg")?;
+    drop(stdin);
+
+    Ok(child)
+}
+
+/// Hand a decrypting child's output to the next process in a pipeline
+pub fn decrypted_stdio(child: &mut Child) -> Result<Stdio> {
+    child
+        .stdout
+        .take()
+        .context("Failed to read gpg output")?
+        .try_into()
+        .context("Failed to pipe gpg output")
+}
+
+/// Unified diff from the file on disk to the archived version a restore
+/// would write over it
+pub async fn diff_with_disk(
+    archive: &Path,
+    item: &RestoreItem,
+    password: Option<&SecurePassword>,
+) -> Result<Vec<DiffLine>> {
+    let metadata = std::fs::metadata(&item.restore_path)
+        .with_context(|| format!("{} does not exist on disk", item.restore_path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("Only single files can be diffed; {} is a directory", item.restore_path.display());
+    }
+    if metadata.len() > MAX_DIFF_BYTES as u64 {
+        anyhow::bail!("{} is too large to diff", item.restore_path.display());
+    }
+
+    let on_disk = std::fs::read(&item.restore_path)
+        .with_context(|| format!("Failed to read {}", item.restore_path.display()))?;
+    let archived = read_member(archive, &member_name(&item.original_path), password, MAX_DIFF_BYTES).await?;
+
+    let (Some(on_disk), Some(archived)) = (as_text(&on_disk), as_text(&archived)) else {
+        anyhow::bail!("Binary files can't be shown as a diff");
+    };
+    Ok(collapse_context(diff_lines(on_disk, archived), 3))
+}
+
+fn as_text(content: &[u8]) -> Option<&str> {
+    if content.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[tokio::test]
+    async fn test_extract_item_to() {
+        let dir = std::env::temp_dir().join(format!("extract-item-test-{}", std::process::id()));
+        let home = dir.join("home");
+        std::fs::create_dir_all(home.join(".config/app")).unwrap();
+        std::fs::write(home.join(".config/app/settings.toml"), "theme = \"dark\"\n").unwrap();
+        std::fs::write(home.join(".config/app/helper"), "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(home.join(".config/app/helper"), std::fs::Permissions::from_mode(0o4755)).unwrap();
+        let archive = dir.join("backup.tar");
+        let status = std::process::Command::new("tar")
+            .arg("-cf").arg(&archive)
+            .arg("-C").arg(&home)
+            .arg(".config")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let file = dir.join("out/settings.toml");
+        extract_item_to(&archive, ".config/app/settings.toml", None, &file).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "theme = \"dark\"\n");
+        assert!(extract_item_to(&archive, ".config/app/settings.toml", None, &file).await.is_err());
+
+        let tree = dir.join("out/app");
+        extract_item_to(&archive, ".config/app", None, &tree).await.unwrap();
+        let mode = std::fs::metadata(tree.join("helper")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o6000, 0);
+        assert!(extract_item_to(&archive, ".config/missing", None, &dir.join("out/missing")).await.is_err());
+        assert_eq!(std::fs::read_dir(dir.join("out")).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_only_selected_items() {
+        let dir = std::env::temp_dir().join(format!("restore-items-test-{}", std::process::id()));
+        let home = dir.join("home");
+        for path in [".config/app/themes/dark", ".config/app-old/settings", ".config/other/settings", ".bashrc", "notes*"] {
+            let path = home.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, path.to_string_lossy().as_bytes()).unwrap();
+        }
+        let archive = dir.join("backup.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf").arg(&archive)
+            .arg("-C").arg(&home)
+            .args([".config", ".bashrc", "notes*"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let target = dir.join("target");
+        let item = |path: &str| RestoreItem {
+            name: path.to_string(),
+            original_path: PathBuf::from(path),
+            restore_path: target.join(path),
+            size: 0,
+            selected: true,
+            conflicts: false,
+            privileged_entries: Vec::new(),
+        };
+        let items = [item(".config/app"), item(".config/app/themes"), item("notes*")];
+        let refs: Vec<&RestoreItem> = items.iter().collect();
+        assert_eq!(
+            restore_groups(&refs).unwrap(),
+            vec![(target.clone(), vec![".config/app".to_string(), "notes*".to_string()])]
+        );
+        restore_items(&archive, &refs, None, RestoreMetadata::Skip).await.unwrap();
+
+        assert!(target.join(".config/app/themes/dark").is_file());
+        assert!(target.join("notes*").is_file());
+        assert!(!target.join(".config/app-old").exists());
+        assert!(!target.join(".config/other").exists());
+        assert!(!target.join(".bashrc").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
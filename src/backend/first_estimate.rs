@@ -0,0 +1,288 @@
+use log::{debug, info, warn};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::backend::dir_size;
+use crate::backend::removable::{self, RemovableDrive};
+use crate::core::config::BackupConfig;
+use crate::core::exclusions::ExclusionSet;
+use crate::core::types::{BackupMode, Compression};
+
+/// Most data read to time the disk; a first backup reads everything once,
+/// so this only has to be enough to average out small files
+const READ_SAMPLE_BYTES: u64 = 64 * 1024 * 1024;
+const READ_SAMPLE_TIME: Duration = Duration::from_secs(3);
+/// Of the data read, how much each compressor is timed on
+const COMPRESS_SAMPLE_BYTES: usize = 8 * 1024 * 1024;
+/// A compressor within this much of the smallest output counts as just
+/// as good, and the fastest of those is recommended
+const RATIO_TOLERANCE: f64 = 1.1;
+
+/// How much one backup mode would archive
+#[derive(Debug, Clone)]
+pub struct ModeVolume {
+    pub mode: BackupMode,
+    pub bytes: u64,
+    pub files: usize,
+}
+
+/// How a compressor did on the sample
+#[derive(Debug, Clone)]
+pub struct CompressorSample {
+    pub compression: Compression,
+    pub level: u32,
+    /// Input bytes compressed per second
+    pub speed: f64,
+    /// Output size as a fraction of the input
+    pub ratio: f64,
+}
+
+/// What a first backup on this machine would take
+#[derive(Debug, Clone, Default)]
+pub struct FirstBackupEstimate {
+    pub volumes: Vec<ModeVolume>,
+    /// Bytes read per second from the selected files, opening included;
+    /// None when nothing could be read
+    pub read_speed: Option<f64>,
+    /// Installed compressors, fastest first
+    pub compressors: Vec<CompressorSample>,
+    /// Free space on the filesystem holding the home directory
+    pub home_free: Option<u64>,
+    pub drives: Vec<RemovableDrive>,
+}
+
+impl FirstBackupEstimate {
+    /// Compressor giving nearly the smallest archive in the least time
+    pub fn recommended(&self) -> Option<&CompressorSample> {
+        let smallest = self.compressors.iter().map(|sample| sample.ratio).fold(f64::INFINITY, f64::min);
+        self.compressors
+            .iter()
+            .filter(|sample| sample.ratio <= smallest * RATIO_TOLERANCE)
+            .max_by(|a, b| a.speed.total_cmp(&b.speed))
+    }
+
+    /// Time to archive `volume` with `compressor`: reading and compressing
+    /// run side by side, so the slower of the two sets the pace
+    pub fn duration(&self, volume: &ModeVolume, compressor: &CompressorSample) -> Option<Duration> {
+        let speed = self.read_speed?.min(compressor.speed);
+        (speed > 0.0).then(|| Duration::from_secs_f64(volume.bytes as f64 / speed))
+    }
+
+    /// Archive size of `volume` going by the sample's ratio
+    pub fn archive_size(&self, volume: &ModeVolume, compressor: &CompressorSample) -> u64 {
+        (volume.bytes as f64 * compressor.ratio) as u64
+    }
+
+    /// Removable drives with room for an archive of `bytes`
+    pub fn drives_with_room(&self, bytes: u64) -> impl Iterator<Item = &RemovableDrive> {
+        self.drives.iter().filter(move |drive| drive.has_room_for(bytes) == Some(true))
+    }
+}
+
+/// Analyze a first backup of `config` on the blocking pool, then look up
+/// free space at home and the removable drives mounted
+pub async fn run(config: BackupConfig, home: PathBuf) -> FirstBackupEstimate {
+    let free_at = home.clone();
+    let mut estimate = tokio::task::spawn_blocking(move || analyze(&config, &home)).await.unwrap_or_default();
+    estimate.home_free = dir_size::disk_space(&free_at).await.ok().map(|(_, free)| free);
+    estimate.drives = removable::list_drives().await.unwrap_or_else(|e| {
+        debug!("No removable drives listed: {}", e);
+        Vec::new()
+    });
+    estimate
+}
+
+/// Count what each mode in `config` would back up, time reading a sample of
+/// it and compressing part of that with each supported compressor that is
+/// installed. Files read recently come from the page cache, so the read
+/// speed is on the optimistic side. Blocking.
+pub fn analyze(config: &BackupConfig, home: &Path) -> FirstBackupEstimate {
+    let mut estimate = FirstBackupEstimate::default();
+    let mut sample = Sample::default();
+    // Complete mode usually covers secure mode's files, so it is sampled:
+    // walking secure mode afterwards only counts, as its files are cached
+    for mode in [BackupMode::Complete, BackupMode::Secure] {
+        let exclusions = match ExclusionSet::for_mode(config, &mode) {
+            Ok(exclusions) => exclusions,
+            // A backup in this mode won't start either until the pattern is fixed
+            Err(e) => {
+                warn!("Not estimating {} mode: {}", mode.as_str(), e);
+                continue;
+            }
+        };
+        let roots: Vec<PathBuf> = config.get_items_for_mode(&mode).into_iter().map(|item| item.path).collect();
+        let sampling = estimate.volumes.is_empty();
+        let (bytes, files) = walk(&exclusions, home, &roots, sampling.then_some(&mut sample));
+        info!("First {} backup: {} files, {} bytes", mode.as_str(), files, bytes);
+        estimate.volumes.insert(0, ModeVolume { mode, bytes, files });
+    }
+
+    estimate.read_speed = sample.speed();
+    estimate.compressors = Compression::ALL
+        .into_iter()
+        .filter(|compression| config.validation.supported_compression.iter().any(|name| name == compression.as_str()))
+        .filter_map(|compression| time_compressor(compression, config.compression_threads(), &sample.data))
+        .collect();
+    estimate.compressors.sort_by(|a, b| b.speed.total_cmp(&a.speed));
+    estimate
+}
+
+/// Data read while walking, and how long reading it took
+#[derive(Default)]
+struct Sample {
+    data: Vec<u8>,
+    bytes: u64,
+    elapsed: Duration,
+}
+
+impl Sample {
+    fn full(&self) -> bool {
+        self.bytes >= READ_SAMPLE_BYTES || self.elapsed >= READ_SAMPLE_TIME
+    }
+
+    /// Read `path` whole, keeping the start of what was read for the
+    /// compressors
+    fn read(&mut self, path: &Path) {
+        let started = Instant::now();
+        let Ok(mut file) = fs::File::open(path) else {
+            return;
+        };
+        let mut buf = vec![0u8; 64 * 1024];
+        while let Ok(n) = file.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let room = COMPRESS_SAMPLE_BYTES.saturating_sub(self.data.len());
+            self.data.extend_from_slice(&buf[..n.min(room)]);
+            self.bytes += n as u64;
+            if self.bytes >= READ_SAMPLE_BYTES {
+                break;
+            }
+        }
+        self.elapsed += started.elapsed();
+    }
+
+    fn speed(&self) -> Option<f64> {
+        (self.bytes > 0).then(|| self.bytes as f64 / self.elapsed.as_secs_f64().max(0.001))
+    }
+}
+
+/// Bytes and files under `roots` that aren't excluded, reading files into
+/// `sample`, if given, until it is full. Links aren't followed.
+fn walk(exclusions: &ExclusionSet, home: &Path, roots: &[PathBuf], mut sample: Option<&mut Sample>) -> (u64, usize) {
+    let (mut bytes, mut files) = (0, 0);
+    let mut pending: Vec<PathBuf> = roots.iter().filter(|root| !exclusions.is_excluded(root)).cloned().collect();
+    while let Some(rel) = pending.pop() {
+        let full = home.join(&rel);
+        let Ok(metadata) = full.symlink_metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            let Ok(entries) = fs::read_dir(&full) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let child = rel.join(entry.file_name());
+                if !exclusions.is_excluded(&child) {
+                    pending.push(child);
+                }
+            }
+        } else if metadata.is_file() {
+            bytes += metadata.len();
+            files += 1;
+            if let Some(sample) = sample.as_deref_mut().filter(|sample| !sample.full()) {
+                sample.read(&full);
+            }
+        }
+    }
+    (bytes, files)
+}
+
+/// Compress `data` with `compression` at its default level, as tar would
+/// run it. None when the compressor isn't installed or fails.
+fn time_compressor(compression: Compression, threads: u32, data: &[u8]) -> Option<CompressorSample> {
+    if data.is_empty() {
+        return None;
+    }
+    let level = compression.default_level();
+    let started = Instant::now();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(compression.program(level, threads))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut stdin = child.stdin.take()?;
+    let mut stdout = child.stdout.take()?;
+    let output = thread::scope(|scope| {
+        scope.spawn(move || {
+            let _ = stdin.write_all(data);
+        });
+        let mut output = 0u64;
+        let mut buf = vec![0u8; 64 * 1024];
+        while let Ok(n) = stdout.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            output += n as u64;
+        }
+        output
+    });
+    let status = child.wait().ok()?;
+    if !status.success() {
+        debug!("{} is not available: {}", compression.as_str(), status);
+        return None;
+    }
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+    Some(CompressorSample {
+        compression,
+        level,
+        speed: data.len() as f64 / elapsed,
+        ratio: output as f64 / data.len() as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_counts_and_recommends_a_compressor() {
+        let home = std::env::temp_dir().join(format!("first-estimate-test-{}", std::process::id()));
+        fs::create_dir_all(home.join(".config/app/cache")).unwrap();
+        fs::write(home.join(".config/app/settings.ini"), "theme=dark\n".repeat(1000)).unwrap();
+        fs::write(home.join(".config/app/cache/blob"), vec![0u8; 5000]).unwrap();
+        fs::write(home.join(".bashrc"), b"alias ll='ls -l'").unwrap();
+
+        let exclusions = ExclusionSet::new(&[".config/*/cache".to_string()]).unwrap();
+        let mut sample = Sample::default();
+        let roots = [PathBuf::from(".config"), PathBuf::from(".bashrc"), PathBuf::from(".missing")];
+        assert_eq!(walk(&exclusions, &home, &roots, Some(&mut sample)), (11_016, 2));
+        assert_eq!(sample.bytes, 11_016);
+        fs::remove_dir_all(&home).unwrap();
+
+        let gzip = time_compressor(Compression::Gzip, 0, &sample.data).unwrap();
+        assert!(gzip.ratio < 0.1);
+        let estimate = FirstBackupEstimate {
+            volumes: vec![ModeVolume { mode: BackupMode::Secure, bytes: 1_000_000, files: 10 }],
+            read_speed: Some(100_000.0),
+            compressors: vec![
+                CompressorSample { compression: Compression::Zstd, level: 3, speed: 400_000.0, ratio: 0.32 },
+                CompressorSample { compression: Compression::Gzip, level: 6, speed: 50_000.0, ratio: 0.30 },
+                CompressorSample { compression: Compression::Xz, level: 6, speed: 10_000.0, ratio: 0.20 },
+            ],
+            ..Default::default()
+        };
+        // xz is far smaller, so the faster ones don't count as just as good
+        assert_eq!(estimate.recommended().unwrap().compression, Compression::Xz);
+        let zstd = &estimate.compressors[0];
+        assert_eq!(estimate.duration(&estimate.volumes[0], zstd), Some(Duration::from_secs(10)));
+        assert_eq!(estimate.archive_size(&estimate.volumes[0], zstd), 320_000);
+    }
+}
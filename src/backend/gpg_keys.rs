@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+/// Where the private half of a key's encryption subkey is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretKey {
+    /// Not on this machine: only the key's owner can decrypt
+    Missing,
+    /// In the local keyring
+    Local,
+    /// On a smartcard or token, by serial number
+    Card(String),
+}
+
+/// Public key in the local keyring that archives can be encrypted to
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpgKey {
+    pub fingerprint: String,
+    pub user_ids: Vec<String>,
+    pub expires: Option<DateTime<Utc>>,
+    pub secret: SecretKey,
+}
+
+impl GpgKey {
+    /// First user ID, or the fingerprint for a key without one
+    pub fn name(&self) -> &str {
+        self.user_ids.first().map(String::as_str).unwrap_or(&self.fingerprint)
+    }
+
+    /// Last 16 hex digits, as gpg shows long key IDs
+    pub fn short_id(&self) -> &str {
+        &self.fingerprint[self.fingerprint.len().saturating_sub(16)..]
+    }
+
+    /// Whether `id`, a fingerprint or key ID from the config, names this key
+    pub fn matches(&self, id: &str) -> bool {
+        let id = id.trim_start_matches("0x").replace(' ', "").to_uppercase();
+        id.len() >= 16 && self.fingerprint.ends_with(&id)
+    }
+}
+
+/// Keys in the local keyring that can encrypt, excluding revoked, expired
+/// and disabled ones, in keyring order
+pub async fn list_encryption_keys() -> Result<Vec<GpgKey>> {
+    let public = list("--list-keys").await?;
+    let secret = list("--list-secret-keys").await?;
+    Ok(parse_keys(&public, &secret))
+}
+
+async fn list(command: &str) -> Result<String> {
+    let output = TokioCommand::new("gpg")
+        .args(["--batch", "--with-colons", "--fixed-list-mode", command])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run gpg")?;
+    if !output.status.success() {
+        anyhow::bail!("gpg {} failed: {}", command, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Keys from `--with-colons` listings of the public and secret keyrings
+fn parse_keys(public: &str, secret: &str) -> Vec<GpgKey> {
+    let secrets = parse_secrets(secret);
+    let mut keys = Vec::new();
+    // The key being read, and whether it can encrypt
+    let mut current: Option<(GpgKey, bool)> = None;
+    let mut expect_fingerprint = false;
+    for line in public.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("");
+        match field(0) {
+            "pub" => {
+                keys.extend(current.take().filter(|(_, usable)| *usable).map(|(key, _)| key));
+                let usable = !matches!(field(1), "r" | "e" | "d" | "i") && field(11).contains('E');
+                let expires = field(6).parse().ok().and_then(|seconds| DateTime::from_timestamp(seconds, 0));
+                let key = GpgKey { fingerprint: String::new(), user_ids: Vec::new(), expires, secret: SecretKey::Missing };
+                current = Some((key, usable));
+                expect_fingerprint = true;
+            }
+            "fpr" if expect_fingerprint => {
+                if let Some((key, _)) = &mut current {
+                    key.fingerprint = field(9).to_string();
+                    key.secret = secrets.get(field(9)).cloned().unwrap_or(SecretKey::Missing);
+                }
+                expect_fingerprint = false;
+            }
+            "uid" if field(1) != "r" => {
+                if let Some((key, _)) = &mut current {
+                    key.user_ids.push(field(9).to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    keys.extend(current.filter(|(_, usable)| *usable).map(|(key, _)| key));
+    keys
+}
+
+/// Where each primary key's encryption secret is, by primary fingerprint
+fn parse_secrets(listing: &str) -> HashMap<String, SecretKey> {
+    let mut secrets = HashMap::new();
+    let mut primary: Option<String> = None;
+    // Location of the primary key, until the fingerprint line names it
+    let mut pending: Option<SecretKey> = None;
+    for line in listing.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("");
+        let location = match field(14) {
+            "#" => SecretKey::Missing,
+            "" | "+" => SecretKey::Local,
+            serial => SecretKey::Card(serial.to_string()),
+        };
+        match field(0) {
+            "sec" => {
+                primary = None;
+                pending = Some(location).filter(|_| field(11).contains('e'));
+            }
+            "fpr" if primary.is_none() => {
+                primary = Some(field(9).to_string());
+                secrets.insert(field(9).to_string(), pending.take().unwrap_or(SecretKey::Missing));
+            }
+            // The first encryption subkey whose secret is here decides
+            "ssb" if field(11).contains('e') && location != SecretKey::Missing => {
+                if let Some(primary) = &primary {
+                    secrets
+                        .entry(primary.clone())
+                        .and_modify(|secret| {
+                            if *secret == SecretKey::Missing {
+                                *secret = location.clone();
+                            }
+                        })
+                        .or_insert(location);
+                }
+            }
+            _ => {}
+        }
+    }
+    secrets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys_keeps_encryption_keys_and_their_secrets() {
+        let public = "\
+tru:o:1:1792199088:1:3:1:5
+pub:u:2048:1:684117187E401454:1792199087:1823735087::u:::scESC::::::23::0:
+fpr:::::::::4E65F9FBCF28BC30EA113211684117187E401454:
+uid:u::::1792199087::E275::Alice Example <alice@example.com>::::::::::0:
+uid:r::::1792199087::E276::Alice Old <alice@old.example.com>::::::::::0:
+sub:u:2048:1:81015403824770BC:1792199092:1823735092:::::e::::::23:
+fpr:::::::::069886F41EA8114C2F3884D681015403824770BC:
+pub:u:255:22:75F8FAA5C50A959E:1792199088:::u:::scSC:::::ed25519:::0:
+fpr:::::::::462856A804B1D554C8CB9DEB75F8FAA5C50A959E:
+uid:u::::1792199088::FE5E::Sign Only <s@example.com>::::::::::0:
+pub:r:4096:1:1111111111111111:1600000000:::u:::scESC::::::23::0:
+fpr:::::::::AAAAAAAAAAAAAAAAAAAAAAAA1111111111111111:
+uid:r::::1600000000::AAAA::Revoked <r@example.com>::::::::::0:
+pub:u:4096:1:2222222222222222:1700000000:::f:::scESC::::::23::0:
+fpr:::::::::BBBBBBBBBBBBBBBBBBBBBBBB2222222222222222:
+uid:f::::1700000000::BBBB::Bob Card <bob@example.com>::::::::::0:
+sub:f:4096:1:3333333333333333:1700000000::::::e::::::23:
+fpr:::::::::CCCCCCCCCCCCCCCCCCCCCCCC3333333333333333:
+";
+        let secret = "\
+sec:u:2048:1:684117187E401454:1792199087:1823735087::u:::scESC:::+:::23::0:
+fpr:::::::::4E65F9FBCF28BC30EA113211684117187E401454:
+ssb:u:2048:1:81015403824770BC:1792199092:1823735092:::::e:::+:::23:
+fpr:::::::::069886F41EA8114C2F3884D681015403824770BC:
+sec:u:4096:1:2222222222222222:1700000000:::u:::scESC:::#:::23::0:
+fpr:::::::::BBBBBBBBBBBBBBBBBBBBBBBB2222222222222222:
+ssb:u:4096:1:3333333333333333:1700000000::::::e:::D2760001240100000006123456780000:::23:
+fpr:::::::::CCCCCCCCCCCCCCCCCCCCCCCC3333333333333333:
+";
+        let keys = parse_keys(public, secret);
+        assert_eq!(keys.len(), 2);
+
+        assert_eq!(keys[0].fingerprint, "4E65F9FBCF28BC30EA113211684117187E401454");
+        assert_eq!(keys[0].user_ids, vec!["Alice Example <alice@example.com>"]);
+        assert_eq!(keys[0].expires, DateTime::from_timestamp(1823735087, 0));
+        assert_eq!(keys[0].secret, SecretKey::Local);
+        assert_eq!(keys[0].short_id(), "684117187E401454");
+        assert!(keys[0].matches("0x684117187e401454"));
+        assert!(!keys[0].matches("7E401454"));
+
+        assert_eq!(keys[1].name(), "Bob Card <bob@example.com>");
+        assert_eq!(keys[1].expires, None);
+        assert_eq!(keys[1].secret, SecretKey::Card("D2760001240100000006123456780000".to_string()));
+
+        assert_eq!(parse_keys(public, "")[0].secret, SecretKey::Missing);
+    }
+}
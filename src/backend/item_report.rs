@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use log::{debug, info};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::core::types::{BackupMode, ItemReport};
+
+/// Reports kept; older ones are removed as new ones are written
+pub const ITEM_REPORT_LIMIT: usize = 20;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Default location under the user's data directory
+pub fn default_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("backup-manager")
+        .join("reports")
+}
+
+/// Write what became of each item in the backup that made `archive` to a
+/// new report in `dir`, and return its path. Tab-separated, one line per
+/// item and one per unreadable path left out of an item. The report names
+/// files in the backup, so only the user can read it.
+pub fn write(dir: &Path, archive: &str, mode: &BackupMode, reports: &[ItemReport]) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let now = Local::now();
+    let path = dir.join(format!("backup-items-{}.tsv", now.format(TIMESTAMP_FORMAT)));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    writeln!(file, "# {} backup {} at {}", mode.as_str(), archive, now.to_rfc3339())?;
+    file.write_all(render(reports).as_bytes())?;
+    info!("Item report for {} saved to {}", archive, path.display());
+
+    prune(dir, ITEM_REPORT_LIMIT)?;
+    Ok(path)
+}
+
+fn render(reports: &[ItemReport]) -> String {
+    let mut out = String::from("status\tpath\tfiles\tbytes\n");
+    for report in reports {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            report.outcome.label(),
+            report.path.display(),
+            report.files,
+            report.bytes
+        ));
+        for path in &report.unreadable {
+            out.push_str(&format!("unreadable\t{}\t\t\n", path.display()));
+        }
+    }
+    out
+}
+
+/// Remove all but the newest `keep` reports; their names sort by time
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("backup-items-")))
+        .collect();
+    reports.sort();
+    let excess = reports.len().saturating_sub(keep);
+    for path in &reports[..excess] {
+        debug!("Removing old item report {}", path.display());
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::ItemOutcome;
+
+    #[test]
+    fn test_report_lists_items_and_what_they_left_out() {
+        let reports = vec![
+            ItemReport {
+                path: PathBuf::from(".config"),
+                outcome: ItemOutcome::BackedUp,
+                files: 2,
+                bytes: 120,
+                unreadable: vec![PathBuf::from(".config/app/secret")],
+            },
+            ItemReport {
+                path: PathBuf::from(".vimrc"),
+                outcome: ItemOutcome::Missing,
+                files: 0,
+                bytes: 0,
+                unreadable: Vec::new(),
+            },
+        ];
+        assert_eq!(
+            render(&reports),
+            "status\tpath\tfiles\tbytes\n\
+             backed up\t.config\t2\t120\n\
+             unreadable\t.config/app/secret\t\t\n\
+             missing\t.vimrc\t0\t0\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("item-report-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for stamp in ["20240101-000000", "20240102-000000", "20240103-000000"] {
+            fs::write(dir.join(format!("backup-items-{}.tsv", stamp)), "").unwrap();
+        }
+        prune(&dir, 2).unwrap();
+        assert!(!dir.join("backup-items-20240101-000000.tsv").exists());
+        assert!(dir.join("backup-items-20240103-000000.tsv").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::backend::extract;
+use crate::backend::member_index::{IndexedMember, MemberIndex, MemberKind};
+use crate::core::exclusions::ExclusionSet;
+use crate::core::security::SecurePassword;
+use crate::core::types::Compression;
+use crate::ui::terminal::format_bytes;
+
+/// How a path on disk differs from its archived copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Modified,
+    Deleted,
+    New,
+}
+
+impl Change {
+    pub fn marker(self) -> char {
+        match self {
+            Change::Modified => 'M',
+            Change::Deleted => 'D',
+            Change::New => 'N',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedPath {
+    pub change: Change,
+    /// Home-relative path; a deleted or new directory is listed once,
+    /// without what is under it
+    pub path: String,
+    pub detail: String,
+}
+
+/// What changed under an archive's items since it was made
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LiveChanges {
+    pub changed: Vec<ChangedPath>,
+    pub unchanged: usize,
+}
+
+impl LiveChanges {
+    pub fn count(&self, change: Change) -> usize {
+        self.changed.iter().filter(|path| path.change == change).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} modified, {} deleted, {} new, {} unchanged",
+            self.count(Change::Modified),
+            self.count(Change::Deleted),
+            self.count(Change::New),
+            self.unchanged,
+        )
+    }
+}
+
+/// Members of `archive`, from its index when it has one, or else as
+/// [`read_members`] finds them
+pub async fn members(archive: &Path, password: Option<&SecurePassword>) -> Result<Vec<IndexedMember>> {
+    if let Some(index) = MemberIndex::load(archive)? {
+        return Ok(index.members);
+    }
+    read_members(archive, password).await
+}
+
+/// Members of `archive`, read through once and decrypted with `password`
+/// if needed; nothing is written to disk
+pub async fn read_members(archive: &Path, password: Option<&SecurePassword>) -> Result<Vec<IndexedMember>> {
+    if archive.extension().is_none_or(|e| e != "gpg") {
+        let archive = archive.to_path_buf();
+        return Ok(tokio::task::spawn_blocking(move || MemberIndex::build(&archive)).await??.members);
+    }
+
+    let password = password.context("Archive is encrypted but no password was provided")?;
+    let mut gpg = extract::spawn_decrypt(archive, password).await?;
+    let (decompressor, mut reader): (_, Box<dyn std::io::Read + Send>) = match Compression::detect(archive) {
+        Some(compression) => {
+            let mut child = Command::new(compression.as_str())
+                .arg("-dc")
+                .stdin(extract::decrypted_stdio(&mut gpg)?)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .with_context(|| format!("Failed to run {}", compression.as_str()))?;
+            let stdout = child.stdout.take().context("Failed to read decompressor output")?;
+            (Some(child), Box::new(stdout))
+        }
+        None => {
+            let stdout = gpg.stdout.take().context("Failed to read gpg output")?;
+            (None, Box::new(File::from(stdout.into_owned_fd()?)))
+        }
+    };
+
+    // The reader is dropped once the walk ends, so gpg and the
+    // decompressor exit even if the archive couldn't be read to its end
+    let read = tokio::task::spawn_blocking(move || MemberIndex::read_to_end(&mut reader)).await?;
+    let decompressed = match decompressor {
+        Some(mut child) if read.is_err() => {
+            let _ = child.kill();
+            let _ = child.wait();
+            true
+        }
+        Some(mut child) => child.wait()?.success(),
+        None => true,
+    };
+    let decrypted = gpg.wait().await?.success();
+    // gpg also fails once the decompressor has given up on its output, so
+    // both failing doesn't tell a wrong password from a damaged archive
+    match read {
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", archive.display())),
+        Ok(index) if decrypted && decompressed => Ok(index.members),
+        Ok(_) if decompressed => anyhow::bail!("Failed to decrypt archive (wrong password?)"),
+        Ok(_) if decrypted => anyhow::bail!("Failed to decompress {}", archive.display()),
+        Ok(_) => anyhow::bail!("Failed to decrypt and decompress archive (wrong password, or is it damaged?)"),
+    }
+}
+
+/// Compare archived `members` with what is under `home_dir` now. Files
+/// count as modified when their size, modification time or type differ.
+/// New paths are looked for in the archived directories only, leaving out
+/// those `exclusions` kept out of the backup. Blocking.
+pub fn compare(members: &[IndexedMember], home_dir: &Path, exclusions: &ExclusionSet) -> LiveChanges {
+    let archived: HashSet<&str> = members.iter().map(|member| member.path.as_str()).collect();
+    let mut changes = LiveChanges::default();
+    let mut gone: HashSet<&str> = HashSet::new();
+
+    for member in members {
+        if member.path.is_empty() {
+            continue;
+        }
+        // Under a directory already reported deleted
+        if member.path.rsplit_once('/').is_some_and(|(parent, _)| gone.contains(parent)) {
+            gone.insert(&member.path);
+            continue;
+        }
+
+        let path = home_dir.join(&member.path);
+        let Ok(metadata) = path.symlink_metadata() else {
+            gone.insert(&member.path);
+            changes.changed.push(ChangedPath {
+                change: Change::Deleted,
+                path: member.path.clone(),
+                detail: "no longer on disk".to_string(),
+            });
+            continue;
+        };
+
+        let detail = match member.kind {
+            MemberKind::File if !metadata.is_file() => Some(format!("now {}", describe_type(&metadata))),
+            MemberKind::File if metadata.len() != member.size => Some(format!("size {} → {}", format_bytes(member.size), format_bytes(metadata.len()))),
+            MemberKind::File if metadata.mtime() != member.mtime => Some(format!(
+                "modified {}",
+                chrono::DateTime::from_timestamp(metadata.mtime(), 0)
+                    .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default()
+            )),
+            MemberKind::Dir if !metadata.is_dir() => Some(format!("now {}", describe_type(&metadata))),
+            MemberKind::Symlink if !metadata.is_symlink() => Some(format!("now {}", describe_type(&metadata))),
+            _ => None,
+        };
+        match detail {
+            Some(detail) => changes.changed.push(ChangedPath { change: Change::Modified, path: member.path.clone(), detail }),
+            None => changes.unchanged += 1,
+        }
+
+        if member.kind == MemberKind::Dir && metadata.is_dir() {
+            let Ok(entries) = fs::read_dir(&path) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let relative = format!("{}/{}", member.path, entry.file_name().to_string_lossy());
+                if archived.contains(relative.as_str()) || exclusions.is_excluded(Path::new(&relative)) {
+                    continue;
+                }
+                let detail = match entry.metadata() {
+                    Ok(metadata) if metadata.is_file() => format_bytes(metadata.len()),
+                    Ok(metadata) => describe_type(&metadata).to_string(),
+                    Err(_) => String::new(),
+                };
+                changes.changed.push(ChangedPath { change: Change::New, path: relative, detail });
+            }
+        }
+    }
+
+    changes.changed.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn describe_type(metadata: &fs::Metadata) -> &'static str {
+    if metadata.is_symlink() {
+        "a symbolic link"
+    } else if metadata.is_dir() {
+        "a directory"
+    } else if metadata.is_file() {
+        "a file"
+    } else {
+        "a special file"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_with_disk() {
+        let dir = std::env::temp_dir().join(format!("live-changes-test-{}", std::process::id()));
+        let home = dir.join("home");
+        fs::create_dir_all(home.join(".config/app/cache")).unwrap();
+        fs::create_dir_all(home.join(".config/old")).unwrap();
+        fs::write(home.join(".config/app/settings.toml"), "theme = \"dark\"\n").unwrap();
+        fs::write(home.join(".config/app/keys"), "a").unwrap();
+        fs::write(home.join(".config/old/file"), "x").unwrap();
+        fs::write(home.join(".bashrc"), "alias ll='ls -l'\n").unwrap();
+        let status = Command::new("tar")
+            .arg("-cf").arg(dir.join("backup.tar"))
+            .arg("-C").arg(&home)
+            .args(["--exclude=cache", ".config", ".bashrc"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let members = MemberIndex::build(&dir.join("backup.tar")).unwrap().members;
+
+        fs::write(home.join(".config/app/settings.toml"), "theme = \"light\"\n").unwrap();
+        fs::remove_dir_all(home.join(".config/old")).unwrap();
+        fs::write(home.join(".config/app/notes"), "new").unwrap();
+        fs::write(home.join(".config/app/cache/blob"), "cached").unwrap();
+
+        let exclusions = ExclusionSet::new(&["cache".to_string()]).unwrap();
+        let changes = compare(&members, &home, &exclusions);
+        let found: Vec<(Change, &str)> = changes.changed.iter().map(|path| (path.change, path.path.as_str())).collect();
+        assert_eq!(found, vec![
+            (Change::New, ".config/app/notes"),
+            (Change::Modified, ".config/app/settings.toml"),
+            (Change::Deleted, ".config/old"),
+        ]);
+        assert_eq!(changes.summary(), "1 modified, 1 deleted, 1 new, 4 unchanged");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
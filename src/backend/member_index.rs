@@ -0,0 +1,439 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+
+use crate::backend::tar_stream;
+use crate::core::types::{Compression, PrivilegedEntry, RestoreItem};
+
+/// First line of every index, naming the format version
+const HEADER: &str = "backup-ui-index 1";
+
+/// What kind of entry a member is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    File,
+    Dir,
+    Symlink,
+    Hardlink,
+    Other,
+}
+
+impl MemberKind {
+    fn from_typeflag(typeflag: u8) -> Self {
+        match typeflag {
+            b'0' | 0 | b'7' => MemberKind::File,
+            b'5' => MemberKind::Dir,
+            b'2' => MemberKind::Symlink,
+            b'1' => MemberKind::Hardlink,
+            _ => MemberKind::Other,
+        }
+    }
+
+    fn as_char(self) -> char {
+        match self {
+            MemberKind::File => 'f',
+            MemberKind::Dir => 'd',
+            MemberKind::Symlink => 'l',
+            MemberKind::Hardlink => 'h',
+            MemberKind::Other => 'o',
+        }
+    }
+
+    fn from_char(c: &str) -> Option<Self> {
+        Some(match c {
+            "f" => MemberKind::File,
+            "d" => MemberKind::Dir,
+            "l" => MemberKind::Symlink,
+            "h" => MemberKind::Hardlink,
+            "o" => MemberKind::Other,
+            _ => return None,
+        })
+    }
+}
+
+/// One entry of an archive, with where its data starts in the plain tar
+/// stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedMember {
+    /// Home-relative path, without a leading `./` or trailing `/`
+    pub path: String,
+    pub kind: MemberKind,
+    pub offset: u64,
+    pub size: u64,
+    pub mode: u32,
+    pub mtime: i64,
+    pub capabilities: bool,
+}
+
+/// Listing of an unencrypted archive kept in a file beside it
+/// (`<archive>.idx`), so its contents can be shown, and single files read
+/// out of it, without reading the archive through. One line per member:
+/// kind, data offset, size, octal mode, mtime, `c` for file capabilities
+/// (else `-`) and the path, with backslashes and newlines escaped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemberIndex {
+    pub members: Vec<IndexedMember>,
+}
+
+impl MemberIndex {
+    /// Index `archive` by reading it through once. Blocking; run it on the
+    /// blocking pool.
+    pub fn build(archive: &Path) -> Result<Self> {
+        let (mut reader, decompressor) = tar_stream::decompressed(archive)?;
+        let read = Self::read_to_end(&mut reader);
+        drop(reader);
+        let Ok(index) = read else {
+            finish(decompressor);
+            return read.with_context(|| format!("Failed to index {}", archive.display()));
+        };
+        // A damaged archive can decompress to nothing at all, which reads
+        // as an empty tar stream; only the decompressor knows
+        if let Some(mut child) = decompressor {
+            if !child.wait()?.success() {
+                anyhow::bail!("Failed to decompress {}", archive.display());
+            }
+        }
+        Ok(index)
+    }
+
+    /// Index a plain tar stream, such as a decrypted archive
+    pub fn read(reader: &mut impl Read) -> Result<Self> {
+        let mut members = Vec::new();
+        tar_stream::walk_entries(reader, |entry, _| {
+            members.push(IndexedMember {
+                path: entry.name.trim_end_matches('/').to_string(),
+                kind: MemberKind::from_typeflag(entry.typeflag),
+                offset: entry.offset,
+                size: entry.size,
+                mode: entry.mode,
+                mtime: entry.mtime,
+                capabilities: entry.capabilities,
+            });
+            Ok(())
+        })?;
+        Ok(Self { members })
+    }
+
+    /// Index a plain tar stream as [`MemberIndex::read`] does, then read
+    /// what follows the end-of-archive marker so whatever writes the
+    /// stream finishes by itself and its exit status can be trusted
+    pub fn read_to_end(reader: &mut impl Read) -> Result<Self> {
+        let index = Self::read(reader)?;
+        std::io::copy(reader, &mut std::io::sink()).context("Failed to read the end of the archive")?;
+        Ok(index)
+    }
+
+    /// Where the index of `archive` is kept
+    pub fn path_for(archive: &Path) -> PathBuf {
+        let mut name = archive.as_os_str().to_os_string();
+        name.push(".idx");
+        PathBuf::from(name)
+    }
+
+    /// Write the index beside `archive`, readable only by the user. The
+    /// archive's size is recorded so an index left behind by an archive
+    /// that was since replaced is ignored.
+    pub fn write_beside(&self, archive: &Path) -> Result<()> {
+        let archive_size = fs::metadata(archive)
+            .with_context(|| format!("Failed to read {}", archive.display()))?
+            .len();
+        let path = Self::path_for(archive);
+        let partial = path.with_extension("idx.partial");
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(&partial)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        let mut out = BufWriter::new(file);
+        writeln!(out, "{} {}", HEADER, archive_size)?;
+        for member in &self.members {
+            writeln!(
+                out,
+                "{} {} {} {:o} {} {} {}",
+                member.kind.as_char(),
+                member.offset,
+                member.size,
+                member.mode,
+                member.mtime,
+                if member.capabilities { 'c' } else { '-' },
+                escape(&member.path),
+            )?;
+        }
+        out.into_inner()?.sync_all()?;
+        fs::rename(&partial, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The index beside `archive`, or `None` when there is none or it was
+    /// written for a different archive
+    pub fn load(archive: &Path) -> Result<Option<Self>> {
+        let Some(lines) = open_lines(archive)? else {
+            return Ok(None);
+        };
+        let mut members = Vec::new();
+        for line in lines {
+            members.push(parse_line(&line?)?);
+        }
+        Ok(Some(Self { members }))
+    }
+
+    /// The member named `name` in the index beside `archive`, read without
+    /// loading the whole index
+    pub fn find(archive: &Path, name: &str) -> Result<Option<IndexedMember>> {
+        let Some(lines) = open_lines(archive)? else {
+            return Ok(None);
+        };
+        let name = name.trim_start_matches("./").trim_end_matches('/');
+        for line in lines {
+            let member = parse_line(&line?)?;
+            if member.path == name {
+                return Ok(Some(member));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Restore items for the archive: each path given to tar when it was
+    /// created, found as a member whose parent directory isn't in the
+    /// archive, with the total size of everything under it
+    pub fn restore_items(&self, home_dir: &Path) -> Vec<RestoreItem> {
+        let paths: HashSet<&str> = self.members.iter().map(|member| member.path.as_str()).collect();
+        let mut roots: Vec<&str> = Vec::new();
+        let mut sizes: HashMap<&str, u64> = HashMap::new();
+
+        for member in &self.members {
+            // The outermost ancestor still in the archive
+            let mut root = member.path.as_str();
+            while let Some((parent, _)) = root.rsplit_once('/') {
+                if !paths.contains(parent) {
+                    break;
+                }
+                root = parent;
+            }
+            match sizes.get_mut(root) {
+                Some(size) => *size += member.size,
+                None => {
+                    roots.push(root);
+                    sizes.insert(root, member.size);
+                }
+            }
+        }
+
+        roots
+            .into_iter()
+            .filter(|root| !root.is_empty())
+            .map(|root| {
+                let restore_path = home_dir.join(root);
+                RestoreItem {
+                    name: root.to_string(),
+                    original_path: PathBuf::from(root),
+                    conflicts: restore_path.exists(),
+                    restore_path,
+                    size: sizes[root],
+                    selected: false,
+                    privileged_entries: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Members that would be restored setuid, setgid or with capabilities
+    pub fn privileged_entries(&self) -> Vec<PrivilegedEntry> {
+        self.members
+            .iter()
+            .filter(|member| member.kind == MemberKind::File)
+            .filter(|member| member.mode & 0o6000 != 0 || member.capabilities)
+            .map(|member| PrivilegedEntry {
+                path: member.path.clone(),
+                setuid: member.mode & 0o4000 != 0,
+                setgid: member.mode & 0o2000 != 0,
+                capabilities: member.capabilities,
+            })
+            .collect()
+    }
+}
+
+/// Copy the index of `from` to sit beside `to`, a copy of the same archive
+pub fn copy_beside(from: &Path, to: &Path) -> Result<()> {
+    if let Some(index) = MemberIndex::load(from)? {
+        index.write_beside(to)?;
+    }
+    Ok(())
+}
+
+/// Delete the index beside `archive`, if there is one
+pub fn remove_beside(archive: &Path) {
+    let path = MemberIndex::path_for(archive);
+    if let Err(e) = fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            debug!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Contents of one indexed file. A plain tarball is read in place; a
+/// compressed one is decompressed only as far as the end of the file.
+/// Blocking.
+pub struct MemberReader {
+    reader: Box<dyn Read + Send>,
+    decompressor: Option<Child>,
+}
+
+impl MemberReader {
+    pub fn open(archive: &Path, member: &IndexedMember) -> Result<Self> {
+        if member.kind != MemberKind::File {
+            anyhow::bail!("{} is not a regular file", member.path);
+        }
+        if Compression::detect(archive).is_none() {
+            let mut file = File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+            file.seek(SeekFrom::Start(member.offset))?;
+            return Ok(Self { reader: Box::new(file.take(member.size)), decompressor: None });
+        }
+
+        let (mut reader, decompressor) = tar_stream::decompressed(archive)?;
+        let skipped = std::io::copy(&mut (&mut reader).take(member.offset), &mut std::io::sink())?;
+        if skipped != member.offset {
+            anyhow::bail!("{} ends before {}; its index is out of date", archive.display(), member.path);
+        }
+        Ok(Self { reader: Box::new(reader.take(member.size)), decompressor })
+    }
+}
+
+impl Read for MemberReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Drop for MemberReader {
+    fn drop(&mut self) {
+        finish(self.decompressor.take());
+    }
+}
+
+/// Stop a decompressor that may still be writing the rest of the archive
+fn finish(decompressor: Option<Child>) {
+    if let Some(mut child) = decompressor {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Member lines of the index beside `archive`, after checking its header
+fn open_lines(archive: &Path) -> Result<Option<impl Iterator<Item = std::io::Result<String>>>> {
+    let path = MemberIndex::path_for(archive);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+    };
+    let mut lines = BufReader::new(file).lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    let Some(size) = header.strip_prefix(HEADER).map(str::trim) else {
+        debug!("Ignoring {}: not an index this version can read", path.display());
+        return Ok(None);
+    };
+    let archive_size = fs::metadata(archive)
+        .with_context(|| format!("Failed to read {}", archive.display()))?
+        .len();
+    if size.parse::<u64>().ok() != Some(archive_size) {
+        debug!("Ignoring {}: it was written for a different archive", path.display());
+        return Ok(None);
+    }
+    Ok(Some(lines))
+}
+
+fn parse_line(line: &str) -> Result<IndexedMember> {
+    let fields: Vec<&str> = line.splitn(7, ' ').collect();
+    let [kind, offset, size, mode, mtime, capabilities, path] = fields[..] else {
+        anyhow::bail!("Malformed archive index line: {}", line);
+    };
+    Ok(IndexedMember {
+        path: unescape(path),
+        kind: MemberKind::from_char(kind).with_context(|| format!("Unknown member kind {}", kind))?,
+        offset: offset.parse().context("Invalid offset in archive index")?,
+        size: size.parse().context("Invalid size in archive index")?,
+        mode: u32::from_str_radix(mode, 8).context("Invalid mode in archive index")?,
+        mtime: mtime.parse().context("Invalid mtime in archive index")?,
+        capabilities: capabilities == "c",
+    })
+}
+
+fn escape(path: &str) -> String {
+    path.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next() {
+            Some('n') => '\n',
+            Some(other) => other,
+            None => '\\',
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_and_read_members() {
+        let dir = std::env::temp_dir().join(format!("member-index-test-{}", std::process::id()));
+        let home = dir.join("home");
+        fs::create_dir_all(home.join(".config/app")).unwrap();
+        fs::create_dir_all(home.join(".ssh")).unwrap();
+        fs::write(home.join(".config/app/settings.toml"), "theme = \"dark\"\n").unwrap();
+        fs::write(home.join(".config/app/odd\nname"), "x").unwrap();
+        fs::write(home.join(".ssh/config"), "Host *\n").unwrap();
+        fs::write(home.join(".bashrc"), "alias ll='ls -l'\n").unwrap();
+        let status = std::process::Command::new("tar")
+            .arg("-czf").arg(dir.join("backup.tar.gz"))
+            .arg("-C").arg(&home)
+            .args([".config/app", ".ssh", ".bashrc"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let archive = dir.join("backup.tar.gz");
+        assert_eq!(MemberIndex::load(&archive).unwrap(), None);
+        let index = MemberIndex::build(&archive).unwrap();
+        index.write_beside(&archive).unwrap();
+        assert_eq!(MemberIndex::load(&archive).unwrap().as_ref(), Some(&index));
+
+        let items = index.restore_items(&home);
+        let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec![".config/app", ".ssh", ".bashrc"]);
+        assert_eq!(items[0].size, 16);
+        assert!(items[2].conflicts);
+
+        let member = MemberIndex::find(&archive, ".config/app/settings.toml").unwrap().unwrap();
+        let mut content = String::new();
+        MemberReader::open(&archive, &member).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "theme = \"dark\"\n");
+        assert!(MemberIndex::find(&archive, ".config/app/odd\nname").unwrap().is_some());
+
+        // An index outlived by its archive is ignored
+        fs::write(&archive, b"replaced").unwrap();
+        assert_eq!(MemberIndex::find(&archive, ".ssh/config").unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
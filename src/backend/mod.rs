@@ -1,17 +1,91 @@
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::process::Command as TokioCommand;
 use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc::UnboundedSender;
 use log::{debug, error, info, warn};
 
 use crate::core::types::{
-    ArchiveInfo, BackupItem, BackupMode, RestoreItem
+    ArchiveInfo, BackupItem, BackupMode, BackupProgress, Compression, ItemReport, ProgressStatus, RestoreItem,
+    RestoreMetadata
 };
+use crate::core::config::ThrottleConfig;
+use crate::core::exclusions::{ExclusionSet, FileList, ScanProgress};
 use crate::core::security::SecurePassword;
-
+use catalog::{ArchiveIndex, Catalog};
+use checksum::HashAlgorithm;
+use member_index::MemberIndex;
+
+pub mod archive_mount;
+pub mod browser_profiles;
+pub mod catalog;
+pub mod catalog_sync;
+pub mod checksum;
+pub mod clipboard;
+pub mod container_volumes;
+pub mod credential_checks;
+pub mod database_dumps;
+pub mod desktop_settings;
+pub mod destination;
+pub mod destination_trust;
+pub mod dir_size;
+pub mod emergency_sheet;
+pub mod extract;
+pub mod first_estimate;
+pub mod gpg_keys;
+pub mod item_report;
+pub mod live_changes;
+pub mod member_index;
+pub mod mount_health;
+pub mod nfs_sync;
+pub mod packages;
+pub mod parity;
+pub mod partial_run;
+pub mod piped_restore;
+pub mod preflight;
+pub mod privileged;
+pub mod recovery_kit;
+pub mod remote_cache;
+pub mod remote_restore;
+pub mod removable;
+pub mod repack;
+pub mod report;
+pub mod restore_plan;
+pub mod restore_snapshot;
+pub mod scrub;
+pub mod secret_scan;
+pub mod stdout_backup;
+pub mod stream;
+pub mod system_snapshot;
+pub mod system_state;
+pub mod tar_stream;
+pub mod thread_usage;
+pub mod throttle;
+pub mod text_index;
+pub mod tiering;
+pub mod vault;
+
+/// Lines of script stderr kept for the error shown when a backup or
+/// restore fails
+const BACKUP_ERROR_LINES: usize = 20;
+
+#[derive(Clone)]
 pub struct BackupEngine {
     backup_lib_path: PathBuf,
+    throttle: ThrottleConfig,
+}
+
+/// What a finished backup wrote
+#[derive(Debug, Default)]
+pub struct BackupOutput {
+    /// The archive, as reported by the script
+    pub archive: Option<PathBuf>,
+    /// One for each item backed up, in order; empty when the script lists
+    /// the files itself
+    pub items: Vec<ItemReport>,
 }
 
 impl BackupEngine {
@@ -55,16 +129,30 @@ impl BackupEngine {
             }
         }
 
-        Ok(Self { backup_lib_path })
+        Ok(Self { backup_lib_path, throttle: ThrottleConfig::default() })
     }
 
+    /// Run backups with the script's processes niced and their writes
+    /// capped as `throttle` says
+    pub fn with_throttle(mut self, throttle: ThrottleConfig) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn start_backup(
         &self,
         items: Vec<&BackupItem>,
         mode: &BackupMode,
+        exclusions: &ExclusionSet,
+        compression: Compression,
+        level: u32,
+        threads: u32,
         password: Option<&SecurePassword>,
+        recipients: &[String],
         output_path: Option<&PathBuf>,
-    ) -> Result<()> {
+        progress_tx: Option<&UnboundedSender<BackupProgress>>,
+    ) -> Result<BackupOutput> {
         info!("Starting backup operation in {} mode", mode.as_str());
         debug!("Backing up {} items", items.len());
 
@@ -122,6 +210,90 @@ impl BackupEngine {
             command.env("BACKUP_DIR", ".");
         }
 
+        // The wrapper names the archive and runs tar's compressor from these;
+        // the original scripts always write .tar.gz
+        let program = compression.program(level, threads);
+        if using_wrapper {
+            command.env("BACKUP_COMPRESSION_EXT", compression.extension());
+            command.env("BACKUP_COMPRESS_PROGRAM", &program);
+        } else if compression != Compression::Gzip {
+            warn!("{} compression needs backup-noninteractive.sh, falling back to gzip", compression.as_str());
+        }
+
+        // The wrapper archives exactly the selected items minus exclusions,
+        // listed here so the UI's glob engine is the only matcher involved.
+        // Exclusions can keep credentials out of secure-mode archives, so a
+        // backup never goes ahead without them.
+        let (commands, files): (Vec<&BackupItem>, Vec<&BackupItem>) = items.iter().partition(|item| item.is_generated());
+        let scan = Arc::new(ScanProgress::default());
+        let file_list = if using_wrapper {
+            let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+            let mut roots: Vec<PathBuf> = files.iter().map(|item| item.path.clone()).collect();
+            // User units, so the services recorded below can be re-enabled
+            roots.push(PathBuf::from(".config/systemd/user"));
+            let symlinks: Vec<_> = files.iter().map(|item| (item.path.clone(), item.symlinks)).collect();
+            // Browsers copied while open can leave a corrupt profile
+            let skipped = browser_profiles::settle(&home_dir, &files, exclusions).await;
+            let exclusions = exclusions.with_paths(&skipped)?;
+            // Writing the list is the first pass over the files; until it
+            // is done, what it has counted so far stands in for progress
+            let counter = progress_tx.cloned().map(|tx| {
+                let scan = scan.clone();
+                tokio::spawn(async move {
+                    let mut ticks = tokio::time::interval(Duration::from_millis(200));
+                    loop {
+                        ticks.tick().await;
+                        let found = scan.get();
+                        let progress = BackupProgress {
+                            current_item: "Counting files".to_string(),
+                            total_files: found.files,
+                            total_bytes: found.bytes,
+                            status: ProgressStatus::Preparing,
+                            ..BackupProgress::default()
+                        };
+                        if tx.send(progress).is_err() {
+                            break;
+                        }
+                    }
+                })
+            });
+            let list_scan = scan.clone();
+            let listed = tokio::task::spawn_blocking(move || FileList::write(&exclusions, &home_dir, &roots, &symlinks, Some(&list_scan))).await;
+            if let Some(counter) = counter {
+                counter.abort();
+            }
+            let list = listed?.context("Failed to list files for backup")?;
+            info!("Backing up {} paths after exclusions", list.entries);
+            command.env("BACKUP_FILE_LIST", list.path());
+            if let Some(transforms) = list.transforms_path() {
+                command.env("BACKUP_FILE_TRANSFORMS", transforms);
+            }
+            Some(list)
+        } else {
+            warn!("Exclusion patterns are only applied by backup-noninteractive.sh");
+            None
+        };
+
+        // Crontab and enabled services go into the archive alongside the
+        // files; the staging directory is removed when this goes out of scope
+        let system_state = if using_wrapper {
+            match system_state::SystemStateDir::collect(&commands).await {
+                Ok(dir) => {
+                    command.env("BACKUP_SYSTEM_STATE_DIR", dir.path());
+                    Some(dir)
+                }
+                Err(e) => {
+                    warn!("Failed to collect system state: {}", e);
+                    None
+                }
+            }
+        } else {
+            if !commands.is_empty() {
+                warn!("Item commands are only run with backup-noninteractive.sh");
+            }
+            None
+        };
+
         // Handle encryption - the scripts prompt for GPG encryption
         // For now, we'll set an environment variable to indicate if encryption is desired
         if password.is_some() {
@@ -130,6 +302,16 @@ impl BackupEngine {
             // This would need to be adapted to work with GPG key selection
         }
 
+        // The wrapper encrypts to these public keys; only their private
+        // keys, such as one on a smartcard, can open the archive
+        if !recipients.is_empty() {
+            if using_wrapper {
+                command.env("BACKUP_GPG_RECIPIENTS", recipients.join(" "));
+            } else {
+                anyhow::bail!("Encrypting to GPG keys needs backup-noninteractive.sh");
+            }
+        }
+
         debug!("Executing backup script");
 
         // For now, we need to run the scripts in non-interactive mode
@@ -137,65 +319,115 @@ impl BackupEngine {
         // TODO: Implement proper GPG key handling
         command.env("BACKUP_NONINTERACTIVE", "yes");
         command.env("SKIP_GPG", "yes");
+        command.env("BACKUP_PROGRESS", "yes");
+
+        // tar, the compressor and gpg inherit the script's priority; the
+        // wrapper passes the archive through pv to cap its write rate
+        throttle::limit_command(&mut command, &self.throttle);
+        if let Some(rate) = self.throttle.bytes_per_second() {
+            if !using_wrapper {
+                warn!("A write rate cap needs backup-noninteractive.sh");
+            } else if !self.check_tool_available("pv").await {
+                warn!("pv is not installed, so the archive is written without a rate cap");
+            } else {
+                command.env("BACKUP_RATE_LIMIT", rate.to_string());
+            }
+        }
 
         let mut child = command.spawn()
             .context("Failed to start backup process")?;
+        let compressor_threads = child
+            .id()
+            .zip(program.split_whitespace().next())
+            .and_then(|(pid, compressor)| thread_usage::CompressorThreads::watch(pid, compressor));
 
-        // Capture both stdout and stderr
+        // Drain both stdout and stderr, keeping only what is reported back:
+        // tar can be very chatty on large trees
         let stdout_handle = if let Some(stdout) = child.stdout.take() {
             let reader = BufReader::new(stdout);
             Some(tokio::spawn(async move {
                 let mut lines = reader.lines();
-                let mut output = Vec::new();
+                let mut archive_path = None;
+                let mut last_line = None;
                 while let Ok(Some(line)) = lines.next_line().await {
                     debug!("Backup stdout: {}", line);
-                    output.push(line);
+                    // The wrapper script reports the archive it wrote as "Archive: <path>"
+                    if let Some(path) = line.strip_prefix("Archive: ") {
+                        archive_path = Some(PathBuf::from(path.trim()));
+                    }
+                    last_line = Some(line);
                 }
-                output
+                (archive_path, last_line)
             }))
         } else {
             None
         };
 
-        let stderr_handle = if let Some(stderr) = child.stderr.take() {
-            let reader = BufReader::new(stderr);
-            Some(tokio::spawn(async move {
-                let mut lines = reader.lines();
-                let mut errors = Vec::new();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    warn!("Backup stderr: {}", line);
-                    errors.push(line);
+        // tar reports the bytes it has written on stderr; against the
+        // total counted while listing, or the selected items' sizes without
+        // a list, they drive the progress bar
+        let scanned = scan.get();
+        let mut progress = BackupProgress {
+            current_item: if scanned.files > 0 { format!("Archiving {} files", scanned.files) } else { "Archiving".to_string() },
+            total_items: files.len(),
+            total_files: scanned.files,
+            total_bytes: if file_list.is_some() { scanned.bytes } else { files.iter().filter_map(|item| item.size).sum() },
+            status: ProgressStatus::Processing,
+            ..BackupProgress::default()
+        };
+        let progress_tx = progress_tx.cloned();
+        if let Some(tx) = &progress_tx {
+            let _ = tx.send(progress.clone());
+        }
+        let stderr_handle = child.stderr.take().map(|stderr| {
+            tokio::spawn(stream::tail_lines_except(stderr, BACKUP_ERROR_LINES, move |line| {
+                let Some(written) = parse_tar_checkpoint(line) else {
+                    return false;
+                };
+                progress.record_bytes(written);
+                if let Some(tx) = &progress_tx {
+                    let _ = tx.send(progress.clone());
                 }
-                errors
+                true
             }))
-        } else {
-            None
-        };
+        });
 
         // Wait for the process to complete
         let exit_status = child.wait().await?;
+        drop(compressor_threads);
+        // The unit directory added above isn't one of the selected items
+        let mut item_reports: Vec<ItemReport> = file_list
+            .map(|list| list.items.iter().take(files.len()).cloned().collect())
+            .unwrap_or_default();
+        if let Some(state) = system_state {
+            item_reports.extend(state.command_reports.iter().cloned());
+        }
+        let incomplete = item_reports.iter().filter(|report| report.is_failure()).count();
+        if incomplete > 0 {
+            warn!("{} of {} items were not backed up in full", incomplete, item_reports.len());
+        }
 
         // Collect output
-        let stdout_lines = if let Some(handle) = stdout_handle {
+        let (archive_path, last_stdout_line) = if let Some(handle) = stdout_handle {
             handle.await.unwrap_or_default()
         } else {
-            Vec::new()
+            (None, None)
         };
 
-        let stderr_lines = if let Some(handle) = stderr_handle {
+        let stderr_tail = if let Some(handle) = stderr_handle {
             handle.await.unwrap_or_default()
         } else {
-            Vec::new()
+            String::new()
         };
 
         if exit_status.success() {
             info!("Backup completed successfully");
-            Ok(())
+            Ok(BackupOutput { archive: archive_path, items: item_reports })
         } else {
-            let error_details = if !stderr_lines.is_empty() {
-                stderr_lines.join("\n")
-            } else if !stdout_lines.is_empty() {
-                stdout_lines.last().unwrap_or(&"Unknown error".to_string()).clone()
+            let error_details = if !stderr_tail.is_empty() {
+                stderr_tail
+            } else if let Some(line) = last_stdout_line {
+                line
             } else {
                 "No error details available".to_string()
             };
@@ -212,79 +444,68 @@ impl BackupEngine {
         archive: &ArchiveInfo,
         items: Vec<&RestoreItem>,
         password: Option<&SecurePassword>,
+        metadata: RestoreMetadata,
     ) -> Result<()> {
         info!("Starting restore operation from archive: {}", archive.name);
         debug!("Restoring {} items", items.len());
 
-        // Prepare arguments for the restore script
-        let mut args = vec![
-            "bash".to_string(),
-            self.backup_lib_path.to_string_lossy().to_string(),
-            "restore_backup".to_string(),
-            archive.path.to_string_lossy().to_string(),
-        ];
-
-        // Add decryption flag if password is provided
-        if password.is_some() {
-            args.push("--decrypt".to_string());
-        }
-
-        // Add selective restore items
-        for item in &items {
-            args.push("--item".to_string());
-            args.push(item.name.clone());
+        // Only the checked items are extracted; an empty selection would
+        // otherwise mean the whole archive to tar
+        if items.is_empty() {
+            anyhow::bail!("No items selected for restore");
         }
-
-        debug!("Executing restore command with {} arguments", args.len());
-
-        // Execute the restore command
-        let mut command = TokioCommand::new(&args[0]);
-        command
-            .args(&args[1..])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        // Set password via environment variable if provided
-        if let Some(pwd) = password {
-            command.env("RESTORE_PASSWORD", String::from_utf8_lossy(pwd.as_bytes()).as_ref());
+        match extract::restore_items(&archive.path, &items, password, metadata).await {
+            Ok(()) => {
+                info!("Restore completed successfully");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Restore failed: {}", e);
+                Err(e)
+            }
         }
+    }
 
-        let mut child = command.spawn()
-            .context("Failed to start restore process")?;
-
-        // Monitor the process output
-        if let Some(stdout) = child.stdout.take() {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-
-            while let Some(line) = lines.next_line().await? {
-                debug!("Restore output: {}", line);
-                
-                // Parse progress information from the output
-                if line.contains("Restoring:") {
-                    // Update progress based on script output
-                }
+    pub async fn list_archives(&self) -> Result<Vec<ArchiveInfo>> {
+        info!("Reading available backup archives from catalog");
+
+        let mut catalog = Catalog::open_default()?;
+
+        // Seed an empty catalog from archives already on disk
+        if catalog.is_empty()? {
+            for archive in self.scan_archive_directories() {
+                let index = match catalog::index_archive(&archive.path, None, HashAlgorithm::Sha256).await {
+                    Ok(index) => Some(index),
+                    Err(e) => {
+                        debug!("Could not index {}: {}", archive.name, e);
+                        None
+                    }
+                };
+                catalog.record_archive(&archive, index.as_ref())?;
             }
         }
 
-        // Wait for the process to complete
-        let exit_status = child.wait().await?;
+        // Local archives that have since been deleted can't be restored
+        let archives: Vec<ArchiveInfo> = catalog
+            .list_archives()?
+            .into_iter()
+            .filter(|archive| archive.remote.is_some() || archive.path.exists())
+            .collect();
 
-        if exit_status.success() {
-            info!("Restore completed successfully");
-            Ok(())
-        } else {
-            let error_msg = format!("Restore process failed with exit code: {:?}", exit_status.code());
-            error!("{}", error_msg);
-            Err(anyhow::anyhow!(error_msg))
-        }
+        info!("Found {} backup archives", archives.len());
+        Ok(archives)
     }
 
-    pub async fn list_archives(&self) -> Result<Vec<ArchiveInfo>> {
+    /// Record a newly created archive and its contents in the catalog
+    pub async fn catalog_archive(&self, archive: &ArchiveInfo, index: Option<&ArchiveIndex>) -> Result<()> {
+        let mut catalog = Catalog::open_default()?;
+        catalog.record_archive(archive, index)?;
+        Ok(())
+    }
+
+    fn scan_archive_directories(&self) -> Vec<ArchiveInfo> {
         info!("Scanning for available backup archives");
 
-        // This would typically scan a backup directory for archive files
-        // For now, we'll return a mock list to demonstrate functionality
         let mut archives = Vec::new();
 
         // Look for backup files in common locations
@@ -301,7 +522,7 @@ impl BackupEngine {
                         let path = entry.path();
                         if let Some(extension) = path.extension() {
                             let ext = extension.to_string_lossy().to_lowercase();
-                            if ext == "gz" || ext == "xz" || ext == "tar" {
+                            if matches!(ext.as_str(), "gz" | "xz" | "bz2" | "zst" | "tar") {
                                 if let Some(file_name) = path.file_name() {
                                     let name = file_name.to_string_lossy().to_string();
                                     
@@ -334,6 +555,8 @@ impl BackupEngine {
                                         description: format!("Backup archive from {}", 
                                             created.format("%Y-%m-%d %H:%M")),
                                         items: Vec::new(), // Would be populated by inspecting the archive
+                                        remote: None,
+                                        hostname: Some(catalog::local_hostname()),
                                     };
 
                                     archives.push(archive);
@@ -348,8 +571,7 @@ impl BackupEngine {
         // Sort archives by creation date (newest first)
         archives.sort_by(|a, b| b.created.cmp(&a.created));
 
-        info!("Found {} backup archives", archives.len());
-        Ok(archives)
+        archives
     }
 
     pub async fn list_archive_contents(
@@ -359,76 +581,34 @@ impl BackupEngine {
     ) -> Result<Vec<RestoreItem>> {
         info!("Listing contents of archive: {}", archive.name);
 
-        // Prepare arguments to list archive contents
-        let mut args = vec![
-            "bash".to_string(),
-            self.backup_lib_path.to_string_lossy().to_string(),
-            "list_archive".to_string(),
-            archive.path.to_string_lossy().to_string(),
-        ];
-
-        if password.is_some() {
-            args.push("--decrypt".to_string());
-        }
-
-        let mut command = TokioCommand::new(&args[0]);
-        command
-            .args(&args[1..])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        // Set password via environment variable if provided
-        if let Some(pwd) = password {
-            command.env("LIST_PASSWORD", String::from_utf8_lossy(pwd.as_bytes()).as_ref());
-        }
-
-        let output = command.output().await
-            .context("Failed to list archive contents")?;
-
-        if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("Failed to list archive contents: {}", error));
-        }
-
-        // Parse the output to create RestoreItem list
-        let contents = String::from_utf8_lossy(&output.stdout);
-        let mut items = Vec::new();
-
-        for line in contents.lines() {
-            if !line.trim().is_empty() {
-                // Parse each line to extract file information
-                // Format: "path|size|original_path"
-                let parts: Vec<&str> = line.split('|').collect();
-                if parts.len() >= 3 {
-                    let name = parts[0].to_string();
-                    let size = parts[1].parse::<u64>().unwrap_or(0);
-                    let original_path = PathBuf::from(parts[2]);
-                    
-                    // Determine restore path (usually the same as original)
-                    let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
-                    let restore_path = if original_path.is_absolute() {
-                        original_path.clone()
-                    } else {
-                        home_dir.join(&original_path)
-                    };
-
-                    // Check for conflicts (file already exists)
-                    let conflicts = restore_path.exists();
-
-                    let item = RestoreItem {
-                        name,
-                        original_path,
-                        restore_path,
-                        size,
-                        selected: false,
-                        conflicts,
-                    };
-
-                    items.push(item);
-                }
+        // Archives made since the index was introduced list from it at once
+        let path = archive.path.clone();
+        let index = match tokio::task::spawn_blocking(move || MemberIndex::load(&path)).await? {
+            Ok(Some(index)) => Some(index),
+            Ok(None) => {
+                debug!("{} has no index; reading the archive", archive.name);
+                None
             }
-        }
+            Err(e) => {
+                warn!("Ignoring the index of {}: {}", archive.name, e);
+                None
+            }
+        };
+        // Others, encrypted ones included, are read through once; the same
+        // walk finds setuid, setgid and capability files, and an archive
+        // it can't finish can't be restored
+        let index = match index {
+            Some(index) => index,
+            None => MemberIndex {
+                members: live_changes::read_members(&archive.path, password)
+                    .await
+                    .context("Failed to list archive contents")?,
+            },
+        };
 
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let mut items = index.restore_items(&home_dir);
+        privileged::annotate_items(&mut items, &index.privileged_entries());
         info!("Found {} items in archive", items.len());
         Ok(items)
     }
@@ -436,7 +616,7 @@ impl BackupEngine {
     pub async fn validate_tools(&self) -> Result<Vec<String>> {
         let mut missing_tools = Vec::new();
         let required_tools = vec!["tar", "gzip", "sha256sum", "find"];
-        let optional_tools = vec!["gpg", "pv", "xz"];
+        let optional_tools = vec!["gpg", "pv", "xz", "zstd"];
 
         for tool in required_tools {
             if !self.check_tool_available(tool).await {
@@ -465,10 +645,97 @@ impl BackupEngine {
     }
 }
 
+/// Bytes written so far from a tar checkpoint line, as printed by
+/// `--checkpoint-action=echo=Written %{w}T`: `tar: Written 1013760 (990KiB, 22MiB/s)`
+fn parse_tar_checkpoint(line: &str) -> Option<u64> {
+    line.strip_prefix("tar: Written ")?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_tar_checkpoint() {
+        assert_eq!(parse_tar_checkpoint("tar: Written 1013760 (990KiB, 22MiB/s)"), Some(1013760));
+        assert_eq!(parse_tar_checkpoint("tar: .cache/x: file changed as we read it"), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_encrypted_archive() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("list-archive-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let home = dir.join("home");
+        std::fs::create_dir_all(home.join(".local/bin")).unwrap();
+        std::fs::write(home.join(".bashrc"), "alias ll='ls -l'\n").unwrap();
+        std::fs::write(home.join(".local/bin/helper"), "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(home.join(".local/bin/helper"), std::fs::Permissions::from_mode(0o4755)).unwrap();
+        let tarball = dir.join("backup.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf").arg(&tarball)
+            .arg("-C").arg(&home)
+            .args([".bashrc", ".local"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let encrypt = |plain: &std::path::Path, archive: &std::path::Path| {
+            let mut gpg = std::process::Command::new("gpg")
+                .args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0", "--symmetric", "--output"])
+                .arg(archive)
+                .arg(plain)
+                .stdin(Stdio::piped())
+                .spawn()
+                .unwrap();
+            std::io::Write::write_all(&mut gpg.stdin.take().unwrap(), b"correct horse\n").unwrap();
+            assert!(gpg.wait().unwrap().success());
+        };
+        let info = |path: PathBuf| ArchiveInfo {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            path,
+            created: chrono::Utc::now(),
+            size: 0,
+            mode: BackupMode::Complete,
+            encrypted: true,
+            description: String::new(),
+            items: Vec::new(),
+            remote: None,
+            hostname: None,
+        };
+        let engine = BackupEngine::new().unwrap();
+        let password = SecurePassword::new("correct horse".to_string());
+
+        let archive = info(dir.join("backup.tar.gz.gpg"));
+        encrypt(&tarball, &archive.path);
+        let mut items = engine.list_archive_contents(&archive, Some(&password)).await.unwrap();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(items.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), vec![".bashrc", ".local"]);
+        assert_eq!(items[1].privileged_entries.len(), 1);
+        assert!(items[1].privileged_entries[0].setuid);
+
+        let wrong = SecurePassword::new("wrong".to_string());
+        assert!(engine.list_archive_contents(&archive, Some(&wrong)).await.is_err());
+        assert!(engine.list_archive_contents(&archive, None).await.is_err());
+
+        // A damaged archive is an error, not an empty listing, even when
+        // gpg has far more to write than is read before giving up
+        let garbage = dir.join("garbage");
+        std::fs::write(&garbage, (0..4 * 1024 * 1024u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect::<Vec<_>>()).unwrap();
+        let damaged = info(dir.join("damaged.tar.gz.gpg"));
+        encrypt(&garbage, &damaged.path);
+        let listed = tokio::time::timeout(Duration::from_secs(30), engine.list_archive_contents(&damaged, Some(&password))).await;
+        assert!(listed.expect("listing hung").is_err());
+        let damaged = info(dir.join("damaged.tar.gz"));
+        std::fs::copy(&garbage, &damaged.path).unwrap();
+        assert!(engine.list_archive_contents(&damaged, None).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[tokio::test]
     async fn test_backup_engine_creation() {
         // This test would need the backup-lib.sh file to exist
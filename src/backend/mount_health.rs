@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::backend::removable::unescape;
+
+/// Filesystems served over the network, checked by the mounts screen
+const NETWORK_FILESYSTEMS: &[&str] = &["nfs", "nfs4", "cifs", "smb3"];
+/// How long a mount point may take to answer before it counts as hung
+const STAT_TIMEOUT: Duration = Duration::from_secs(3);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// errno for a stale NFS file handle
+const ESTALE: i32 = 116;
+/// errno for permission denied, which the server answering with is fine
+const EACCES: i32 = 13;
+
+/// A network share from /proc/mounts, or from /etc/fstab when it isn't
+/// mounted
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkMount {
+    /// `server:/export` for NFS, `//server/share` for SMB
+    pub source: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub mounted: bool,
+}
+
+impl NetworkMount {
+    /// Host serving the share
+    pub fn server(&self) -> &str {
+        match self.source.strip_prefix("//") {
+            Some(rest) => rest.split('/').next().unwrap_or(rest),
+            None => self.source.split(':').next().unwrap_or(&self.source),
+        }
+    }
+
+    fn is_nfs(&self) -> bool {
+        self.fs_type.starts_with("nfs")
+    }
+
+    fn port(&self) -> u16 {
+        if self.is_nfs() { 2049 } else { 445 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MountHealth {
+    Healthy,
+    /// The server answers but the export is gone or not offered to us
+    ExportMissing,
+    /// Mounted, but the server no longer knows the file handle
+    Stale,
+    /// Mounted, but reading the mount point doesn't return
+    Hung,
+    ServerUnreachable,
+    NotMounted,
+}
+
+impl MountHealth {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MountHealth::Healthy => "healthy",
+            MountHealth::ExportMissing => "export missing",
+            MountHealth::Stale => "stale handle",
+            MountHealth::Hung => "not responding",
+            MountHealth::ServerUnreachable => "server unreachable",
+            MountHealth::NotMounted => "not mounted",
+        }
+    }
+
+    /// Whether unmounting and mounting again may help
+    pub fn needs_remount(&self) -> bool {
+        matches!(self, MountHealth::Stale | MountHealth::Hung | MountHealth::NotMounted)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MountCheck {
+    pub mount: NetworkMount,
+    pub health: MountHealth,
+    pub detail: String,
+}
+
+/// Whether a disaster recovery menu command opens the mounts screen:
+/// `backup-ui mounts`, or the script it replaces
+pub fn is_mounts_command(command: &str) -> bool {
+    let command = command.trim();
+    command.ends_with("check-and-mount-nfs.sh") || command.split_whitespace().collect::<Vec<_>>() == ["backup-ui", "mounts"]
+}
+
+/// Network shares that are mounted, then those in /etc/fstab that aren't
+pub fn network_mounts() -> Vec<NetworkMount> {
+    let mounted = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let fstab = fs::read_to_string("/etc/fstab").unwrap_or_default();
+    merge_mounts(parse_network_mounts(&mounted, true), parse_network_mounts(&fstab, false))
+}
+
+fn merge_mounts(mut mounted: Vec<NetworkMount>, configured: Vec<NetworkMount>) -> Vec<NetworkMount> {
+    for mount in configured {
+        if !mounted.iter().any(|m| m.mount_point == mount.mount_point) {
+            mounted.push(mount);
+        }
+    }
+    mounted
+}
+
+/// Network filesystem lines of /proc/mounts or fstab, which share a format
+fn parse_network_mounts(content: &str, mounted: bool) -> Vec<NetworkMount> {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (source, mount_point, fs_type) = (fields.next()?, fields.next()?, fields.next()?);
+            NETWORK_FILESYSTEMS.contains(&fs_type).then(|| NetworkMount {
+                source: unescape(source),
+                mount_point: PathBuf::from(unescape(mount_point)),
+                fs_type: fs_type.to_string(),
+                mounted,
+            })
+        })
+        .collect()
+}
+
+/// Check every network share
+pub fn check_all() -> Vec<MountCheck> {
+    network_mounts().into_iter().map(check).collect()
+}
+
+/// How a share is doing: whether its server answers, whether it still
+/// offers the export, and whether the mount point itself responds
+pub fn check(mount: NetworkMount) -> MountCheck {
+    let result = |health, detail: String| MountCheck { mount: mount.clone(), health, detail };
+
+    if mount.mounted {
+        match stat_with_timeout(&mount.mount_point) {
+            Ok(None) | Ok(Some(EACCES)) => {}
+            Ok(Some(ESTALE)) => {
+                return result(MountHealth::Stale, "the server no longer recognises this mount; remount it".to_string())
+            }
+            Ok(Some(errno)) => {
+                let error = std::io::Error::from_raw_os_error(errno);
+                return result(MountHealth::Hung, format!("mount point can't be read: {}", error));
+            }
+            Err(_) => {
+                return result(
+                    MountHealth::Hung,
+                    format!("no answer within {}s; the server may be down", STAT_TIMEOUT.as_secs()),
+                )
+            }
+        }
+    }
+
+    if let Err(e) = reach(mount.server(), mount.port()) {
+        return result(MountHealth::ServerUnreachable, format!("{} port {}: {}", mount.server(), mount.port(), e));
+    }
+    if mount.is_nfs() && export_offered(&mount) == Some(false) {
+        return result(MountHealth::ExportMissing, format!("{} doesn't offer this export to this machine", mount.server()));
+    }
+    if !mount.mounted {
+        return result(MountHealth::NotMounted, "in /etc/fstab; the server is reachable".to_string());
+    }
+    result(MountHealth::Healthy, "server reachable, mount responding".to_string())
+}
+
+/// Stat `path` on a thread, since a dead NFS server makes it block. Ok with
+/// the errno if it failed, Err if it didn't return in time; the thread of a
+/// hung stat is left behind.
+fn stat_with_timeout(path: &Path) -> std::result::Result<Option<i32>, mpsc::RecvTimeoutError> {
+    let (sender, receiver) = mpsc::channel();
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        let errno = fs::read_dir(&path).err().map(|e| e.raw_os_error().unwrap_or(0));
+        let _ = sender.send(errno);
+    });
+    receiver.recv_timeout(STAT_TIMEOUT)
+}
+
+fn reach(host: &str, port: u16) -> Result<()> {
+    let address = (host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("can't resolve {}", host))?
+        .next()
+        .context("no address")?;
+    TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+    Ok(())
+}
+
+/// Whether the server lists the export in `showmount -e`; None when that
+/// can't tell, as with NFSv4-only servers or without showmount
+fn export_offered(mount: &NetworkMount) -> Option<bool> {
+    let export = mount.source.split_once(':')?.1;
+    let output = Command::new("showmount")
+        .args(["-e", "--no-headers", mount.server()])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let exports = String::from_utf8_lossy(&output.stdout);
+    // NFSv4 mounts can name a path below the exported directory
+    Some(exports.lines().filter_map(|line| line.split_whitespace().next()).any(|exported| {
+        export == exported || export.strip_prefix(exported).is_some_and(|rest| rest.starts_with('/'))
+    }))
+}
+
+/// Unmount the share if it is mounted (lazily, so a hung or stale mount
+/// lets go) and mount it again from /etc/fstab. Runs `sudo`, so call it
+/// with the terminal free for a password prompt.
+pub fn remount(mount: &NetworkMount) -> Result<()> {
+    let point = mount.mount_point.to_string_lossy().to_string();
+    if mount.mounted {
+        debug!("Unmounting {}", point);
+        sudo(&["umount", "-l", &point])?;
+    }
+    sudo(&["mount", &point])?;
+    info!("Remounted {} on {}", mount.source, point);
+    Ok(())
+}
+
+fn sudo(args: &[&str]) -> Result<()> {
+    let status = Command::new("sudo")
+        .args(args)
+        .status()
+        .context("Failed to run sudo")?;
+    if !status.success() {
+        anyhow::bail!("sudo {} failed ({})", args.join(" "), status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_mounts() {
+        let mounted = parse_network_mounts(
+            "/dev/nvme0n1p2 / ext4 rw 0 0\n\
+             nas:/export/projects /mnt/projects-share nfs4 rw,relatime,vers=4.2 0 0\n",
+            true,
+        );
+        let configured = parse_network_mounts(
+            "# nas:/old /mnt/old nfs defaults 0 0\n\
+             nas:/export/projects /mnt/projects-share nfs4 defaults,_netdev 0 0\n\
+             //files/Media\\040Library /mnt/media cifs credentials=/etc/smb-creds 0 0\n",
+            false,
+        );
+        let mounts = merge_mounts(mounted, configured);
+
+        assert_eq!(mounts.len(), 2);
+        assert!(mounts[0].mounted);
+        assert_eq!(mounts[0].server(), "nas");
+        assert_eq!(mounts[0].port(), 2049);
+        assert_eq!(mounts[1].source, "//files/Media Library");
+        assert_eq!(mounts[1].server(), "files");
+        assert!(!mounts[1].mounted);
+
+        assert!(is_mounts_command("~/check-and-mount-nfs.sh"));
+        assert!(is_mounts_command("backup-ui mounts"));
+        assert!(!is_mounts_command("backup-ui sync status"));
+        assert!(MountHealth::Stale.needs_remount() && !MountHealth::ServerUnreachable.needs_remount());
+    }
+}
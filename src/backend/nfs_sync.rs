@@ -0,0 +1,433 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::backend::catalog::local_hostname;
+use crate::core::audit::AuditLog;
+use crate::core::config::NfsSyncConfig;
+use crate::ui::terminal::format_bytes;
+
+/// rsync's exit code when files vanished while it was copying them
+const RSYNC_VANISHED: i32 = 24;
+const STATE_FILE: &str = "nfs-sync.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOperation {
+    Push,
+    Status,
+    Pull,
+}
+
+impl SyncOperation {
+    /// The operation a disaster recovery menu command stands for:
+    /// `backup-ui sync <operation>`, or the NFS scripts this replaces, so
+    /// menus saved before keep working without them
+    pub fn from_command(command: &str) -> Option<Self> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        match words.as_slice() {
+            [program, "sync", operation] if program.ends_with("backup-ui") => match *operation {
+                "push" => Some(Self::Push),
+                "status" => Some(Self::Status),
+                "pull" => Some(Self::Pull),
+                _ => None,
+            },
+            [script, "sync"] if script.ends_with("sync-to-nfs-backup.sh") => Some(Self::Push),
+            [script, "status"] if script.ends_with("sync-to-nfs-backup.sh") => Some(Self::Status),
+            [script, "pull"] if script.ends_with("sync-from-nfs-restore.sh") => Some(Self::Pull),
+            [script, "check"] if script.ends_with("sync-from-nfs-restore.sh") => Some(Self::Status),
+            _ => None,
+        }
+    }
+}
+
+/// Overall progress of a transfer, from `rsync --info=progress2`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncProgress {
+    pub bytes: u64,
+    pub percent: u8,
+    /// Transfer rate as rsync prints it, e.g. `10.50MB/s`
+    pub rate: String,
+    /// Time left as rsync prints it, e.g. `0:01:12`
+    pub eta: String,
+}
+
+/// What a transfer copied, or would copy, from `rsync --stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SyncStats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// When this machine last pushed to and pulled from the share
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    pub last_push: Option<DateTime<Local>>,
+    pub last_pull: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncStatus {
+    pub target: PathBuf,
+    pub mounted: bool,
+    pub last_push: Option<DateTime<Local>>,
+    pub last_pull: Option<DateTime<Local>>,
+    /// What a push would copy now; None when the share isn't mounted
+    pub pending: Option<SyncStats>,
+}
+
+/// Mirrors chosen home-relative paths into a directory on an NFS share
+/// with rsync, and pulls them back onto a new machine. Only changed files
+/// are copied; paths keep their place relative to the home directory.
+pub struct NfsSync {
+    home: PathBuf,
+    mount: PathBuf,
+    target: PathBuf,
+    paths: Vec<String>,
+    exclusions: Vec<String>,
+    delete: bool,
+}
+
+impl NfsSync {
+    pub fn from_config(config: &NfsSyncConfig) -> Result<Self> {
+        let home = dirs::home_dir().context("Could not find the home directory")?;
+        let mount = PathBuf::from(&config.mount);
+        let target = mount.join(config.dir.clone().unwrap_or_else(local_hostname));
+        Ok(Self {
+            home,
+            mount,
+            target,
+            paths: config.paths.clone(),
+            exclusions: config.exclusions.clone(),
+            delete: config.delete,
+        })
+    }
+
+    /// Whether something is mounted at the share's mount point. An empty
+    /// mount point is a directory on the local disk, which a sync would
+    /// fill instead of the share.
+    pub fn is_mounted(&self) -> bool {
+        is_mount_point(&self.mount)
+    }
+
+    fn check_mounted(&self) -> Result<()> {
+        if !self.is_mounted() {
+            anyhow::bail!("{} is not mounted; mount the share and try again", self.mount.display());
+        }
+        Ok(())
+    }
+
+    /// Copy local changes to the share
+    pub fn push(&self, progress: impl FnMut(&SyncProgress)) -> Result<SyncStats> {
+        self.check_mounted()?;
+        fs::create_dir_all(&self.target).with_context(|| format!("Failed to create {}", self.target.display()))?;
+        let stats = run_rsync(self.push_args(false)?, progress)?;
+        self.record(|state| state.last_push = Some(Local::now()));
+        self.audit("nfs_push", &stats);
+        info!("Pushed {} files ({} bytes) to {}", stats.files, stats.bytes, self.target.display());
+        Ok(stats)
+    }
+
+    /// Copy the share's files into the home directory. Local files newer
+    /// than the share's copy are left alone, and nothing local is deleted.
+    pub fn pull(&self, progress: impl FnMut(&SyncProgress)) -> Result<SyncStats> {
+        self.check_mounted()?;
+        if !self.target.is_dir() {
+            anyhow::bail!("Nothing has been synced to {} yet", self.target.display());
+        }
+        let sources: Vec<String> = self
+            .paths
+            .iter()
+            .filter(|path| self.target.join(path).exists())
+            .map(|path| format!("{}/./{}", self.target.display(), path))
+            .collect();
+        if sources.is_empty() {
+            anyhow::bail!("None of the synced paths are on {}", self.target.display());
+        }
+        let mut args = self.common_args();
+        args.push("--update".to_string());
+        args.push("--".to_string());
+        args.extend(sources);
+        args.push(format!("{}/", self.home.display()));
+        let stats = run_rsync(args, progress)?;
+        self.record(|state| state.last_pull = Some(Local::now()));
+        self.audit("nfs_pull", &stats);
+        info!("Pulled {} files ({} bytes) from {}", stats.files, stats.bytes, self.target.display());
+        Ok(stats)
+    }
+
+    /// When the share was last synced and what a push would copy now
+    pub fn status(&self) -> Result<SyncStatus> {
+        let state = self.state();
+        let mounted = self.is_mounted();
+        let pending = if mounted && self.target.is_dir() {
+            Some(run_rsync(self.push_args(true)?, |_| {})?)
+        } else {
+            None
+        };
+        Ok(SyncStatus {
+            target: self.target.clone(),
+            mounted,
+            last_push: state.last_push,
+            last_pull: state.last_pull,
+            pending,
+        })
+    }
+
+    /// Run `operation` with progress redrawn on stderr, returning the lines
+    /// summing it up
+    pub fn run_in_terminal(&self, operation: SyncOperation) -> Result<Vec<String>> {
+        use std::io::Write;
+
+        let show = |update: &SyncProgress| {
+            eprint!("\r{:>10} {:>3}% {:>12} {:>9} left ", format_bytes(update.bytes), update.percent, update.rate, update.eta);
+            let _ = std::io::stderr().flush();
+        };
+        let lines = match operation {
+            SyncOperation::Push => {
+                let stats = self.push(show)?;
+                eprintln!();
+                vec![format!("Pushed {} files ({}) to {}", stats.files, format_bytes(stats.bytes), self.target.display())]
+            }
+            SyncOperation::Pull => {
+                let stats = self.pull(show)?;
+                eprintln!();
+                vec![format!("Pulled {} files ({}) from {}", stats.files, format_bytes(stats.bytes), self.target.display())]
+            }
+            SyncOperation::Status => {
+                let status = self.status()?;
+                let when = |time: Option<DateTime<Local>>| time.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "never".to_string());
+                vec![
+                    format!("Share:     {} ({})", status.target.display(), if status.mounted { "mounted" } else { "not mounted" }),
+                    format!("Last push: {}", when(status.last_push)),
+                    format!("Last pull: {}", when(status.last_pull)),
+                    match status.pending {
+                        Some(pending) => format!("Pending:   {} files ({})", pending.files, format_bytes(pending.bytes)),
+                        None => "Pending:   unknown until the share is mounted and pushed to".to_string(),
+                    },
+                ]
+            }
+        };
+        Ok(lines)
+    }
+
+    fn common_args(&self) -> Vec<String> {
+        // No ACLs or extended attributes: many NFS exports support neither
+        let mut args: Vec<String> = ["-aH", "--relative", "--info=progress2", "--no-inc-recursive", "--stats"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        args.extend(self.exclusions.iter().map(|pattern| format!("--exclude={}", pattern)));
+        args
+    }
+
+    fn push_args(&self, dry_run: bool) -> Result<Vec<String>> {
+        // `./` marks where the path kept on the share starts
+        let sources: Vec<String> = self
+            .paths
+            .iter()
+            .filter(|path| self.home.join(path).exists())
+            .map(|path| format!("{}/./{}", self.home.display(), path))
+            .collect();
+        if sources.is_empty() {
+            anyhow::bail!("None of the paths to sync exist in {}", self.home.display());
+        }
+
+        let mut args = self.common_args();
+        if dry_run {
+            args.push("--dry-run".to_string());
+        }
+        if self.delete {
+            args.push("--delete".to_string());
+        }
+        args.push("--".to_string());
+        args.extend(sources);
+        args.push(format!("{}/", self.target.display()));
+        Ok(args)
+    }
+
+    fn state_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("backup-manager")
+            .join(STATE_FILE)
+    }
+
+    fn state(&self) -> SyncState {
+        fs::read(Self::state_path())
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn audit(&self, action: &str, stats: &SyncStats) {
+        let details = format!("{} files, {} bytes", stats.files, stats.bytes);
+        if let Err(e) = AuditLog::open_default().record(action, &self.target.display().to_string(), &details) {
+            warn!("Failed to write audit log: {}", e);
+        }
+    }
+
+    fn record(&self, update: impl FnOnce(&mut SyncState)) {
+        let mut state = self.state();
+        update(&mut state);
+        let path = Self::state_path();
+        let written = path
+            .parent()
+            .map(fs::create_dir_all)
+            .transpose()
+            .and_then(|_| fs::write(&path, serde_json::to_vec_pretty(&state).unwrap_or_default()));
+        if let Err(e) = written {
+            warn!("Failed to record sync time in {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Run rsync with `args`, reporting progress as it goes, and return the
+/// totals from its `--stats` summary
+fn run_rsync(args: Vec<String>, mut progress: impl FnMut(&SyncProgress)) -> Result<SyncStats> {
+    debug!("rsync {}", args.join(" "));
+    let mut child = Command::new("rsync")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run rsync; is it installed?")?;
+
+    let mut stderr = child.stderr.take().context("Failed to read rsync errors")?;
+    let errors = std::thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+
+    let mut stats = SyncStats::default();
+    let mut stdout = BufReader::new(child.stdout.take().context("Failed to read rsync output")?);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        // Progress lines are redrawn with a carriage return
+        let read = read_until_any(&mut stdout, &mut line)?;
+        if read == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        if let Some(update) = parse_progress(&text) {
+            progress(&update);
+        } else {
+            parse_stat(&text, &mut stats);
+        }
+    }
+
+    let status = child.wait().context("Failed to wait for rsync")?;
+    let errors = errors.join().unwrap_or_default();
+    match status.code() {
+        Some(0) => {}
+        Some(RSYNC_VANISHED) => warn!("Some files vanished while being synced"),
+        _ => {
+            let tail: Vec<&str> = errors.lines().rev().take(5).collect::<Vec<_>>().into_iter().rev().collect();
+            anyhow::bail!("rsync failed ({}): {}", status, tail.join("; "));
+        }
+    }
+    Ok(stats)
+}
+
+/// Read up to and including the next `\n` or `\r`
+fn read_until_any(reader: &mut impl BufRead, line: &mut Vec<u8>) -> std::io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(read);
+        }
+        match buffer.iter().position(|&byte| byte == b'\n' || byte == b'\r') {
+            Some(end) => {
+                line.extend_from_slice(&buffer[..=end]);
+                reader.consume(end + 1);
+                return Ok(read + end + 1);
+            }
+            None => {
+                let length = buffer.len();
+                line.extend_from_slice(buffer);
+                reader.consume(length);
+                read += length;
+            }
+        }
+    }
+}
+
+/// `      1,234,567  45%   10.50MB/s    0:00:12 (xfr#3, to-chk=10/20)`
+fn parse_progress(line: &str) -> Option<SyncProgress> {
+    let mut fields = line.split_whitespace();
+    let bytes = fields.next()?.replace(',', "").parse().ok()?;
+    let percent = fields.next()?.strip_suffix('%')?.parse().ok()?;
+    Some(SyncProgress {
+        bytes,
+        percent,
+        rate: fields.next().unwrap_or_default().to_string(),
+        eta: fields.next().unwrap_or_default().to_string(),
+    })
+}
+
+fn parse_stat(line: &str, stats: &mut SyncStats) {
+    let number = |value: &str| value.split_whitespace().next().unwrap_or_default().replace(',', "").parse().unwrap_or(0);
+    if let Some(value) = line.trim().strip_prefix("Number of regular files transferred:") {
+        stats.files = number(value);
+    } else if let Some(value) = line.trim().strip_prefix("Total transferred file size:") {
+        stats.bytes = number(value);
+    }
+}
+
+/// A directory is a mount point when it sits on another filesystem than
+/// its parent
+fn is_mount_point(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let parent = path.parent().unwrap_or(Path::new("/"));
+        match (fs::metadata(path), fs::metadata(parent)) {
+            (Ok(dir), Ok(parent)) => dir.is_dir() && (dir.dev() != parent.dev() || dir.ino() == parent.ino()),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_dir()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rsync_output() {
+        let output = b"sending incremental file list\n      32,768   3%    1.20MB/s    0:00:08\r   1,048,576 100%   10.50MB/s    0:00:00 (xfr#3, to-chk=0/20)\n\nNumber of files: 20 (reg: 17, dir: 3)\nNumber of regular files transferred: 3\nTotal file size: 9,999,999 bytes\nTotal transferred file size: 1,048,576 bytes\n";
+        let mut reader = BufReader::new(&output[..]);
+        let (mut updates, mut stats, mut line) = (Vec::new(), SyncStats::default(), Vec::new());
+        while read_until_any(&mut reader, &mut line).unwrap() > 0 {
+            let text = String::from_utf8_lossy(&line).to_string();
+            match parse_progress(&text) {
+                Some(update) => updates.push(update),
+                None => parse_stat(&text, &mut stats),
+            }
+            line.clear();
+        }
+
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1], SyncProgress { bytes: 1_048_576, percent: 100, rate: "10.50MB/s".to_string(), eta: "0:00:00".to_string() });
+        assert_eq!(stats, SyncStats { files: 3, bytes: 1_048_576 });
+        assert!(parse_progress("Number of files: 20").is_none());
+
+        assert_eq!(SyncOperation::from_command("~/sync-to-nfs-backup.sh sync"), Some(SyncOperation::Push));
+        assert_eq!(SyncOperation::from_command("backup-ui sync pull"), Some(SyncOperation::Pull));
+        assert_eq!(SyncOperation::from_command("chezmoi status"), None);
+
+        assert!(is_mount_point(Path::new("/")));
+        assert!(!is_mount_point(&std::env::temp_dir().join("no-such-share")));
+    }
+}
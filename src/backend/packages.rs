@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::catalog::local_hostname;
+use crate::backend::extract;
+use crate::backend::item_report;
+use crate::backend::remote_restore::shell_quote;
+use crate::backend::system_state::SYSTEM_STATE_DIR;
+use crate::core::security::SecurePassword;
+use crate::core::types::{CheckResult, CheckStatus};
+
+/// Package lists, under the system state directory
+pub const PACKAGES_FILE: &str = "packages.json";
+
+const MAX_MANIFEST_BYTES: usize = 4 * 1024 * 1024;
+
+/// Package managers whose explicitly installed packages can be listed
+/// and installed again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Pacman,
+    Flatpak,
+    Cargo,
+    Pip,
+    Npm,
+}
+
+impl PackageManager {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageManager::Pacman => "pacman",
+            PackageManager::Flatpak => "flatpak",
+            PackageManager::Cargo => "cargo",
+            PackageManager::Pip => "pip",
+            PackageManager::Npm => "npm",
+        }
+    }
+
+    /// Command listing the packages installed on purpose, not those pulled
+    /// in as dependencies. Pacman lists repository packages only; AUR and
+    /// locally built ones can't be installed again by pacman.
+    fn list_command(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            PackageManager::Pacman => ("pacman", &["-Qqen"]),
+            PackageManager::Flatpak => ("flatpak", &["list", "--app", "--columns=application"]),
+            PackageManager::Cargo => ("cargo", &["install", "--list"]),
+            PackageManager::Pip => ("python3", &["-m", "pip", "list", "--user", "--not-required", "--format=freeze"]),
+            PackageManager::Npm => ("npm", &["ls", "--global", "--depth=0", "--json"]),
+        }
+    }
+
+    /// Package names from the list command's output
+    fn parse(&self, output: &str) -> Vec<String> {
+        let mut packages: Vec<String> = match self {
+            PackageManager::Pacman | PackageManager::Flatpak => {
+                output.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect()
+            }
+            // `name v1.2.3:` followed by the binaries, indented
+            PackageManager::Cargo => output
+                .lines()
+                .filter(|line| !line.starts_with(char::is_whitespace))
+                .filter_map(|line| line.split_whitespace().next())
+                .map(str::to_string)
+                .collect(),
+            PackageManager::Pip => output
+                .lines()
+                .filter_map(|line| line.split("==").next())
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+            // npm itself and corepack come with node
+            PackageManager::Npm => serde_json::from_str::<serde_json::Value>(output)
+                .ok()
+                .and_then(|tree| tree.get("dependencies")?.as_object().map(|deps| deps.keys().cloned().collect::<Vec<_>>()))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|name| name != "npm" && name != "corepack")
+                .collect(),
+        };
+        packages.sort();
+        packages.dedup();
+        packages
+    }
+
+    /// Shell command installing `packages` again, skipping those already
+    /// there where the manager can
+    pub fn install_command(&self, packages: &[String]) -> String {
+        let names: Vec<String> = packages.iter().map(|name| shell_quote(name)).collect();
+        let names = names.join(" ");
+        match self {
+            PackageManager::Pacman => format!("sudo pacman -S --needed {}", names),
+            PackageManager::Flatpak => format!("flatpak install --user --noninteractive flathub {}", names),
+            PackageManager::Cargo => format!("cargo install {}", names),
+            PackageManager::Pip => format!("python3 -m pip install --user {}", names),
+            PackageManager::Npm => format!("npm install --global {}", names),
+        }
+    }
+}
+
+/// What a backup recorded, stored in the archive as [`PACKAGES_FILE`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub hostname: String,
+    pub captured: Option<DateTime<Local>>,
+    /// Explicitly installed packages of each manager found on the machine
+    pub packages: BTreeMap<PackageManager, Vec<String>>,
+}
+
+impl PackageManifest {
+    /// List the packages of each of `managers`. Managers that aren't
+    /// installed are skipped; one that fails is logged and skipped.
+    pub async fn capture(managers: &[PackageManager]) -> Self {
+        let mut manifest = Self { hostname: local_hostname(), captured: Some(Local::now()), packages: BTreeMap::new() };
+        for manager in managers {
+            let (program, args) = manager.list_command();
+            let output = match TokioCommand::new(program).args(args).stdin(Stdio::null()).output().await {
+                Ok(output) => output,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    debug!("{} is not installed", manager.as_str());
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Skipping {} packages: {}", manager.as_str(), e);
+                    continue;
+                }
+            };
+            // npm ls exits 1 over problems in the tree it still lists
+            if !output.status.success() && *manager != PackageManager::Npm {
+                warn!("Skipping {} packages: {}", manager.as_str(), String::from_utf8_lossy(&output.stderr).trim());
+                continue;
+            }
+            let packages = manager.parse(&String::from_utf8_lossy(&output.stdout));
+            debug!("{} explicitly installed {} packages", packages.len(), manager.as_str());
+            if !packages.is_empty() {
+                manifest.packages.insert(*manager, packages);
+            }
+        }
+        manifest
+    }
+
+    /// Shell script installing every recorded package again, one manager
+    /// per line so a missing manager fails on its own
+    pub fn reinstall_script(&self, archive: &str) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        let captured = self.captured.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+        let _ = writeln!(script, "# Packages installed on {} when {} was made ({})", self.hostname, archive, captured);
+        let _ = writeln!(script, "# Read it through and drop anything no longer wanted before running it");
+        for (manager, packages) in &self.packages {
+            let _ = writeln!(script);
+            let _ = writeln!(script, "# {}: {} packages", manager.as_str(), packages.len());
+            let _ = writeln!(script, "{}", manager.install_command(packages));
+        }
+        script
+    }
+}
+
+/// Whether a result came from [`write_reinstall_script`]
+pub fn is_package_result(result: &CheckResult) -> bool {
+    result.category == "packages"
+}
+
+/// Write a script reinstalling the packages recorded in `archive` to the
+/// reports directory, returning where it went and a result per manager
+pub async fn write_reinstall_script(archive: &Path, password: Option<&SecurePassword>) -> Result<(PathBuf, Vec<CheckResult>)> {
+    let member = format!("{}/{}", SYSTEM_STATE_DIR, PACKAGES_FILE);
+    let manifest: PackageManifest = match extract::read_member(archive, &member, password, MAX_MANIFEST_BYTES).await {
+        Ok(content) => serde_json::from_slice(&content).context("Invalid package lists in archive")?,
+        Err(e) => anyhow::bail!("This archive has no package lists ({})", e),
+    };
+    if manifest.packages.is_empty() {
+        anyhow::bail!("No package manager listed any packages when the archive was made");
+    }
+
+    let name = archive.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let dir = item_report::default_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("reinstall-packages-{}.sh", Local::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&path, manifest.reinstall_script(&name)).with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let results = manifest
+        .packages
+        .iter()
+        .map(|(manager, packages)| CheckResult::new("packages", manager.as_str(), CheckStatus::Pass, &format!("{} packages", packages.len())))
+        .chain(std::iter::once(CheckResult::new(
+            "packages",
+            "script",
+            CheckStatus::Warning,
+            &format!("review, then run: sh {}", path.display()),
+        )))
+        .collect();
+    Ok((path, results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_lists_and_write_script() {
+        let cargo = "bat v0.24.0:\n    bat\nripgrep v14.1.0 (/home/me/src/ripgrep):\n    rg\n";
+        assert_eq!(PackageManager::Cargo.parse(cargo), ["bat", "ripgrep"]);
+        assert_eq!(PackageManager::Pip.parse("httpie==3.2.2\nblack==24.1.0\n"), ["black", "httpie"]);
+        let npm = r#"{"name": "lib", "dependencies": {"npm": {"version": "10.2.4"}, "typescript": {"version": "5.3.3"}}}"#;
+        assert_eq!(PackageManager::Npm.parse(npm), ["typescript"]);
+        assert_eq!(PackageManager::Flatpak.parse("org.mozilla.firefox\n\norg.gimp.GIMP\n"), ["org.gimp.GIMP", "org.mozilla.firefox"]);
+
+        let mut manifest = PackageManifest { hostname: "laptop".to_string(), ..Default::default() };
+        manifest.packages.insert(PackageManager::Pacman, vec!["base".to_string(), "neovim".to_string()]);
+        manifest.packages.insert(PackageManager::Npm, vec!["it's".to_string()]);
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert!(json.contains(r#""pacman":["base","neovim"]"#), "{}", json);
+        assert_eq!(serde_json::from_str::<PackageManifest>(&json).unwrap(), manifest);
+
+        let script = manifest.reinstall_script("complete-20260101.tar.gz");
+        assert!(script.starts_with("#!/bin/sh\n# Packages installed on laptop when complete-20260101.tar.gz was made"));
+        assert!(script.contains("\n# pacman: 2 packages\nsudo pacman -S --needed 'base' 'neovim'\n"));
+        assert!(script.contains("\nnpm install --global 'it'\\''s'\n"));
+    }
+}
@@ -0,0 +1,402 @@
+use anyhow::{Context, Result};
+use log::{debug, info};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+use crate::backend::destination::copy_private;
+
+/// Data and parity blocks together can't exceed the field size of GF(2^8)
+const MAX_BLOCKS: usize = 256;
+/// Smallest block an archive is cut into; small archives get fewer blocks
+const MIN_BLOCK_SIZE: u64 = 64 * 1024;
+/// Bytes of each block encoded at a time, so memory use doesn't grow with
+/// the archive
+const CHUNK_SIZE: u64 = 1024 * 1024;
+const FORMAT: &str = "backup-ui-parity";
+
+/// Layout and checksums of an archive's parity file. The file holds the
+/// parity blocks, then this header as JSON, then the header's length as
+/// 8 big-endian bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParityHeader {
+    pub format: String,
+    pub version: u32,
+    pub archive_size: u64,
+    pub block_size: u64,
+    pub redundancy: u32,
+    /// BLAKE3 of each data block, zero-padded to `block_size`
+    pub data_hashes: Vec<String>,
+    pub parity_hashes: Vec<String>,
+}
+
+impl ParityHeader {
+    pub fn data_blocks(&self) -> usize {
+        self.data_hashes.len()
+    }
+
+    pub fn parity_blocks(&self) -> usize {
+        self.parity_hashes.len()
+    }
+
+    /// One line for archive details, e.g. "10%, rebuilds 20 of 200 blocks"
+    pub fn describe(&self) -> String {
+        format!(
+            "{}%, rebuilds up to {} of {} blocks",
+            self.redundancy,
+            self.parity_blocks(),
+            self.data_blocks(),
+        )
+    }
+}
+
+/// What checking an archive against its parity found
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParityCheck {
+    pub header: ParityHeader,
+    /// Data blocks that no longer match their checksum
+    pub damaged_data: Vec<usize>,
+    pub damaged_parity: Vec<usize>,
+}
+
+impl ParityCheck {
+    pub fn is_intact(&self) -> bool {
+        self.damaged_data.is_empty() && self.damaged_parity.is_empty()
+    }
+
+    /// Enough blocks survive to rebuild the damaged ones
+    pub fn is_repairable(&self) -> bool {
+        self.damaged_data.len() + self.damaged_parity.len() <= self.header.parity_blocks()
+    }
+
+    pub fn describe(&self) -> String {
+        if self.is_intact() {
+            return format!("intact ({} data blocks, {} parity blocks)", self.header.data_blocks(), self.header.parity_blocks());
+        }
+        let lost = self.damaged_data.len() + self.damaged_parity.len();
+        format!(
+            "{} of {} data blocks and {} of {} parity blocks damaged; {}",
+            self.damaged_data.len(),
+            self.header.data_blocks(),
+            self.damaged_parity.len(),
+            self.header.parity_blocks(),
+            if self.is_repairable() {
+                "repairable".to_string()
+            } else {
+                format!("too many to repair ({} lost, at most {} can be rebuilt)", lost, self.header.parity_blocks())
+            }
+        )
+    }
+}
+
+/// Where the parity of `archive` is kept
+pub fn path_for(archive: &Path) -> PathBuf {
+    let mut name = archive.as_os_str().to_os_string();
+    name.push(".par");
+    PathBuf::from(name)
+}
+
+/// Copy the parity of `from` to sit beside `to`, a copy of the same archive
+pub fn copy_beside(from: &Path, to: &Path) -> Result<()> {
+    let source = path_for(from);
+    if source.exists() {
+        copy_private(&source, &path_for(to))?;
+    }
+    Ok(())
+}
+
+/// Delete the parity beside `archive`, if there is any
+pub fn remove_beside(archive: &Path) {
+    let path = path_for(archive);
+    if let Err(e) = fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            debug!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Write Reed-Solomon parity for `archive` beside it, able to rebuild
+/// `redundancy` percent of its blocks. Blocking; run it on the blocking
+/// pool.
+pub fn create(archive: &Path, redundancy: u32) -> Result<ParityHeader> {
+    if !(1..=100).contains(&redundancy) {
+        anyhow::bail!("Parity redundancy must be between 1 and 100 percent, not {}", redundancy);
+    }
+    let input = File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let archive_size = input.metadata()?.len();
+
+    let path = path_for(archive);
+    let partial = path.with_extension("par.partial");
+    let output = create_private(&partial)?;
+    match encode(&input, &output, archive_size, redundancy) {
+        Ok(header) => {
+            fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+            info!("Wrote parity for {} ({})", archive.display(), header.describe());
+            Ok(header)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            Err(e.context(format!("Failed to write parity for {}", archive.display())))
+        }
+    }
+}
+
+/// Write the parity blocks of `input` to `output`, followed by the header
+fn encode(input: &File, mut output: &File, archive_size: u64, redundancy: u32) -> Result<ParityHeader> {
+    let (data_blocks, parity_blocks, block_size) = layout(archive_size, redundancy);
+    let codec = ReedSolomon::new(data_blocks, parity_blocks)?;
+    let mut data_hashers = vec![blake3::Hasher::new(); data_blocks];
+    let mut parity_hashers = vec![blake3::Hasher::new(); parity_blocks];
+    let mut data = vec![Vec::new(); data_blocks];
+    let mut parity = vec![Vec::new(); parity_blocks];
+
+    // Block by block would hold whole blocks in memory; the code works on
+    // each byte position across the blocks, so a slice at a time will do
+    for start in (0..block_size).step_by(CHUNK_SIZE as usize) {
+        let len = CHUNK_SIZE.min(block_size - start) as usize;
+        for (block, chunk) in data.iter_mut().enumerate() {
+            read_padded(input, block as u64 * block_size + start, len, chunk)?;
+            data_hashers[block].update(chunk);
+        }
+        for chunk in &mut parity {
+            chunk.resize(len, 0);
+        }
+        codec.encode_sep(&data, &mut parity)?;
+        for (block, chunk) in parity.iter().enumerate() {
+            parity_hashers[block].update(chunk);
+            output.write_all_at(chunk, block as u64 * block_size + start)?;
+        }
+    }
+
+    let header = ParityHeader {
+        format: FORMAT.to_string(),
+        version: 1,
+        archive_size,
+        block_size,
+        redundancy,
+        data_hashes: data_hashers.iter().map(|h| h.finalize().to_hex().to_string()).collect(),
+        parity_hashes: parity_hashers.iter().map(|h| h.finalize().to_hex().to_string()).collect(),
+    };
+    let json = serde_json::to_vec(&header)?;
+    output.seek(SeekFrom::Start(parity_blocks as u64 * block_size))?;
+    output.write_all(&json)?;
+    output.write_all(&(json.len() as u64).to_be_bytes())?;
+    output.sync_all()?;
+    Ok(header)
+}
+
+/// The header of the parity beside `archive`, if it has any
+pub fn read_header(archive: &Path) -> Result<Option<ParityHeader>> {
+    let path = path_for(archive);
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+    };
+    let file_size = file.metadata()?.len();
+    let mut len = [0u8; 8];
+    file.seek(SeekFrom::End(-8)).with_context(|| format!("{} is too short", path.display()))?;
+    file.read_exact(&mut len)?;
+    let len = u64::from_be_bytes(len);
+    if len > file_size - 8 {
+        anyhow::bail!("{} is damaged: its header is missing", path.display());
+    }
+    let mut json = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(file_size - 8 - len))?;
+    file.read_exact(&mut json)?;
+    let header: ParityHeader = serde_json::from_slice(&json)
+        .with_context(|| format!("{} is damaged: its header can't be read", path.display()))?;
+    if header.format != FORMAT || header.version != 1 {
+        anyhow::bail!("{} is not parity this version can read", path.display());
+    }
+    Ok(Some(header))
+}
+
+/// Check every block of `archive` and its parity against their checksums.
+/// Blocking.
+pub fn check(archive: &Path) -> Result<ParityCheck> {
+    let header = read_header(archive)?.with_context(|| format!("{} has no parity data", archive.display()))?;
+    let input = File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let parity = File::open(path_for(archive))?;
+    Ok(ParityCheck {
+        damaged_data: damaged_blocks(&input, &header.data_hashes, header.block_size, header.archive_size)?,
+        damaged_parity: damaged_blocks(&parity, &header.parity_hashes, header.block_size, u64::MAX)?,
+        header,
+    })
+}
+
+/// Rebuild the damaged blocks of `archive`, and of its parity, from the
+/// blocks that survive. Only damaged blocks are written, and a truncated
+/// archive is restored to its full length. Blocking.
+pub fn repair(archive: &Path) -> Result<ParityCheck> {
+    let found = check(archive)?;
+    if found.is_intact() {
+        return Ok(found);
+    }
+    if !found.is_repairable() {
+        anyhow::bail!("{}: {}", archive.display(), found.describe());
+    }
+
+    let header = &found.header;
+    let (data_blocks, block_size) = (header.data_blocks(), header.block_size);
+    let codec = ReedSolomon::new(data_blocks, header.parity_blocks())?;
+    let output = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(archive)
+        .with_context(|| format!("Failed to open {} for writing", archive.display()))?;
+    let parity = fs::OpenOptions::new().read(true).write(true).open(path_for(archive))?;
+    let damaged = |block: usize| {
+        if block < data_blocks {
+            found.damaged_data.contains(&block)
+        } else {
+            found.damaged_parity.contains(&(block - data_blocks))
+        }
+    };
+
+    for start in (0..block_size).step_by(CHUNK_SIZE as usize) {
+        let len = CHUNK_SIZE.min(block_size - start) as usize;
+        let mut shards: Vec<Option<Vec<u8>>> = Vec::with_capacity(data_blocks + header.parity_blocks());
+        for block in 0..data_blocks + header.parity_blocks() {
+            if damaged(block) {
+                shards.push(None);
+                continue;
+            }
+            let mut chunk = Vec::new();
+            match block.checked_sub(data_blocks) {
+                None => read_padded(&output, block as u64 * block_size + start, len, &mut chunk)?,
+                Some(p) => read_padded(&parity, p as u64 * block_size + start, len, &mut chunk)?,
+            }
+            shards.push(Some(chunk));
+        }
+        codec.reconstruct(&mut shards)?;
+
+        for (block, shard) in shards.iter().enumerate().filter(|(block, _)| damaged(*block)) {
+            let chunk = shard.as_ref().context("Block was not rebuilt")?;
+            match block.checked_sub(data_blocks) {
+                None => {
+                    // The last block is padded past the end of the archive
+                    let at = block as u64 * block_size + start;
+                    let keep = header.archive_size.saturating_sub(at).min(len as u64) as usize;
+                    output.write_all_at(&chunk[..keep], at)?;
+                }
+                Some(p) => parity.write_all_at(chunk, p as u64 * block_size + start)?,
+            }
+        }
+    }
+    output.set_len(header.archive_size)?;
+    output.sync_all()?;
+    parity.sync_all()?;
+
+    let after = check(archive)?;
+    if !after.is_intact() {
+        anyhow::bail!("{} still has damaged blocks after repair: {}", archive.display(), after.describe());
+    }
+    info!("Repaired {}: {}", archive.display(), found.describe());
+    Ok(found)
+}
+
+/// Number of data and parity blocks, and their size, for an archive of
+/// `size` bytes
+fn layout(size: u64, redundancy: u32) -> (usize, usize, u64) {
+    let most_data = MAX_BLOCKS * 100 / (100 + redundancy as usize);
+    let data_blocks = (size.div_ceil(MIN_BLOCK_SIZE) as usize).clamp(1, most_data);
+    let parity_blocks = (data_blocks * redundancy as usize).div_ceil(100).max(1);
+    let block_size = size.div_ceil(data_blocks as u64).max(1);
+    (data_blocks, parity_blocks, block_size)
+}
+
+/// Blocks of `file` whose BLAKE3 no longer matches. Reads past `limit`,
+/// the length the blocks were hashed at, count as zeros.
+fn damaged_blocks(file: &File, hashes: &[String], block_size: u64, limit: u64) -> Result<Vec<usize>> {
+    let mut damaged = Vec::new();
+    let mut chunk = Vec::new();
+    for (block, expected) in hashes.iter().enumerate() {
+        let mut hasher = blake3::Hasher::new();
+        for start in (0..block_size).step_by(CHUNK_SIZE as usize) {
+            let at = block as u64 * block_size + start;
+            let len = CHUNK_SIZE.min(block_size - start) as usize;
+            read_padded(file, at, len, &mut chunk)?;
+            let valid = limit.saturating_sub(at).min(len as u64) as usize;
+            chunk[valid..].fill(0);
+            hasher.update(&chunk);
+        }
+        if hasher.finalize().to_hex().as_str() != expected {
+            damaged.push(block);
+        }
+    }
+    Ok(damaged)
+}
+
+/// Read `len` bytes at `offset` into `buf`, with zeros past the end of
+/// the file
+fn read_padded(file: &File, offset: u64, len: usize, buf: &mut Vec<u8>) -> Result<()> {
+    buf.clear();
+    buf.resize(len, 0);
+    let mut filled = 0;
+    while filled < len {
+        match file.read_at(&mut buf[filled..], offset + filled as u64)? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(())
+}
+
+fn create_private(path: &Path) -> Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.read(true).write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path).with_context(|| format!("Failed to create {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parity_repairs_damage() {
+        let dir = std::env::temp_dir().join(format!("parity-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("backup.tar.gz");
+        let original: Vec<u8> = (0..3_000_000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+        fs::write(&archive, &original).unwrap();
+
+        let header = create(&archive, 10).unwrap();
+        assert_eq!((header.data_blocks(), header.parity_blocks()), (46, 5));
+        assert_eq!(read_header(&archive).unwrap(), Some(header.clone()));
+        assert!(check(&archive).unwrap().is_intact());
+
+        // Flip bits in two blocks and cut off the end of the archive
+        let mut damaged = original.clone();
+        damaged[10] ^= 0x40;
+        damaged[1_500_000] ^= 0x01;
+        damaged.truncate(2_990_000);
+        fs::write(&archive, &damaged).unwrap();
+        let found = check(&archive).unwrap();
+        assert_eq!(found.damaged_data, vec![0, 22, 45]);
+        assert!(found.is_repairable());
+
+        repair(&archive).unwrap();
+        assert_eq!(fs::read(&archive).unwrap(), original);
+
+        // More damage than the parity covers is refused, leaving the file
+        let mut ruined = original.clone();
+        for block in 0..6 {
+            ruined[block * header.block_size as usize] ^= 0xff;
+        }
+        fs::write(&archive, &ruined).unwrap();
+        assert!(!check(&archive).unwrap().is_repairable());
+        assert!(repair(&archive).is_err());
+        assert_eq!(fs::read(&archive).unwrap(), ruined);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A backup that stopped at the end of its window, so the next run in
+/// the next window backs up only what it didn't reach
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialRun {
+    /// Mode and profile the run was for; a run of anything else starts
+    /// afresh and leaves this alone
+    pub key: String,
+    pub started: DateTime<Utc>,
+    /// Top-level home-relative roots already archived by earlier parts
+    pub done: Vec<PathBuf>,
+    /// Archives written so far
+    pub parts: usize,
+}
+
+impl PartialRun {
+    /// `mode`, and the profile when there is one
+    pub fn key(mode: &str, profile: Option<&str>) -> String {
+        match profile {
+            Some(profile) => format!("{}/{}", mode, profile),
+            None => mode.to_string(),
+        }
+    }
+
+    /// Whether an earlier part already archived `path`
+    pub fn covers(&self, path: &Path) -> bool {
+        self.done.iter().any(|root| path.starts_with(root))
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(serde_json::from_str(&text).with_context(|| format!("Failed to read {}", path.display()))?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Saved private to the user, since it names backed-up paths
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn clear(path: &Path) -> Result<()> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Default location under the user's data directory
+pub fn default_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("backup-manager")
+        .join("partial-backup.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_run_round_trip() {
+        let path = std::env::temp_dir().join(format!("partial-run-test-{}/partial-backup.json", std::process::id()));
+        assert_eq!(PartialRun::load(&path).unwrap(), None);
+
+        let run = PartialRun {
+            key: PartialRun::key("secure", Some("laptop")),
+            started: Utc::now(),
+            done: vec![PathBuf::from(".config"), PathBuf::from(".local/share")],
+            parts: 1,
+        };
+        assert_eq!(run.key, "secure/laptop");
+        assert!(run.covers(Path::new(".config/gh")));
+        assert!(!run.covers(Path::new(".local")));
+        assert!(!run.covers(Path::new(".configs")));
+
+        run.save(&path).unwrap();
+        assert_eq!(PartialRun::load(&path).unwrap(), Some(run));
+        PartialRun::clear(&path).unwrap();
+        PartialRun::clear(&path).unwrap();
+        assert_eq!(PartialRun::load(&path).unwrap(), None);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use zeroize::Zeroize;
+
+use crate::backend::tar_stream;
+use crate::core::security::SecurePassword;
+use crate::core::types::{Compression, PrivilegedEntry, RestoreMetadata};
+
+/// Bytes needed to tell the formats apart
+const MAGIC_LEN: usize = 32;
+
+/// What a piped archive is, judged from its first bytes since there is no
+/// file name to go by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    Encrypted,
+    Compressed(Compression),
+    Tar,
+}
+
+impl StreamFormat {
+    pub fn detect(head: &[u8]) -> Self {
+        const MAGIC: [(&[u8], Compression); 4] = [
+            (&[0x1f, 0x8b], Compression::Gzip),
+            (&[0xfd, b'7', b'z', b'X', b'Z', 0x00], Compression::Xz),
+            (b"BZh", Compression::Bzip2),
+            (&[0x28, 0xb5, 0x2f, 0xfd], Compression::Zstd),
+        ];
+        if let Some((_, compression)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+            return StreamFormat::Compressed(*compression);
+        }
+        if head.starts_with(b"-----BEGIN PGP MESSAGE") {
+            return StreamFormat::Encrypted;
+        }
+        // OpenPGP messages open with a session key packet: symmetric (tag
+        // 3) or public-key (tag 1). Tar names are text, so never match.
+        let tag = match head.first() {
+            Some(byte) if byte & 0xc0 == 0xc0 => Some(byte & 0x3f),
+            Some(byte) if byte & 0x80 != 0 => Some((byte >> 2) & 0x0f),
+            _ => None,
+        };
+        if matches!(tag, Some(1 | 3)) {
+            return StreamFormat::Encrypted;
+        }
+        StreamFormat::Tar
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            StreamFormat::Encrypted => "GPG-encrypted",
+            StreamFormat::Compressed(compression) => compression.as_str(),
+            StreamFormat::Tar => "uncompressed tar",
+        }
+    }
+}
+
+/// What to do with the files in a piped archive
+#[derive(Debug, Clone)]
+pub struct PipedRestore {
+    /// Directory the archive's home-relative paths are restored under
+    pub target: PathBuf,
+    /// Home-relative paths to restore; all of them when empty
+    pub items: Vec<String>,
+    /// Replace files that already exist, keeping each one beside it as
+    /// `<name>.~1~`; otherwise existing files are left alone
+    pub overwrite: bool,
+    /// Read the archive through without writing anything
+    pub list_only: bool,
+    pub metadata: RestoreMetadata,
+    /// Restore setuid/setgid bits and file capabilities as archived;
+    /// otherwise tar clears them, even when running as root
+    pub allow_privileged: bool,
+}
+
+/// Outcome of reading a piped archive
+#[derive(Debug, Default)]
+pub struct PipedSummary {
+    pub format: Option<StreamFormat>,
+    pub files: usize,
+    pub bytes: u64,
+    /// Files already on disk that were left as they were
+    pub kept: Vec<String>,
+    /// Files replaced, each with its previous version kept beside it
+    pub replaced: usize,
+    /// First path component of each file, for the audit log
+    pub top_level: BTreeSet<String>,
+    /// Files archived with setuid/setgid bits or capabilities
+    pub privileged: Vec<PrivilegedEntry>,
+    /// Size and checksums of the stream as it arrived, before decryption
+    /// and decompression, in the forms the catalog records
+    pub archive_bytes: u64,
+    pub sha256: String,
+    pub blake3: String,
+}
+
+/// Restore from an archive read once, front to back, from `input`. It is
+/// decrypted and decompressed as it arrives and each tar header is read
+/// before its contents, so `on_file` reports files as they go by and
+/// nothing is buffered on disk. `password` is asked for only if the
+/// archive turns out to be encrypted. Blocking.
+pub fn restore(
+    input: Box<dyn Read + Send>,
+    options: &PipedRestore,
+    password: impl FnOnce() -> Result<SecurePassword>,
+    mut on_file: impl FnMut(&str, u64),
+) -> Result<PipedSummary> {
+    let hashes = Arc::new(Mutex::new(Hashes::default()));
+    let input = Box::new(Hashing { inner: input, hashes: hashes.clone() });
+    let mut stages = Vec::new();
+
+    let read = read_archive(input, options, password, &mut on_file, &mut stages);
+    let summary = match read {
+        Ok(summary) => finish(stages).map(|_| summary)?,
+        Err(e) => return Err(abandon(stages).unwrap_or(e)),
+    };
+
+    let hashes = Arc::try_unwrap(hashes).ok().context("Archive stream still in use")?.into_inner().unwrap_or_default();
+    Ok(PipedSummary {
+        archive_bytes: hashes.bytes,
+        sha256: hashes.sha256.finalize().iter().map(|b| format!("{:02x}", b)).collect(),
+        blake3: format!("blake3:{}", hashes.blake3.finalize().to_hex()),
+        ..summary
+    })
+}
+
+fn read_archive(
+    input: Box<dyn Read + Send>,
+    options: &PipedRestore,
+    password: impl FnOnce() -> Result<SecurePassword>,
+    on_file: &mut impl FnMut(&str, u64),
+    stages: &mut Vec<Stage>,
+) -> Result<PipedSummary> {
+    let (head, mut stream) = peek(input)?;
+    if head.is_empty() {
+        anyhow::bail!("Nothing was piped in");
+    }
+    let format = StreamFormat::detect(&head);
+
+    if format == StreamFormat::Encrypted {
+        // The passphrase goes first on gpg's stdin, then the archive
+        let mut passphrase = password()?.as_bytes().to_vec();
+        passphrase.push(b'\n');
+        let mut gpg = Command::new("gpg");
+        gpg.args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0", "--decrypt"]);
+        stream = Box::new(spawn_stage(&mut gpg, "gpg", stream, passphrase, stages)?);
+    }
+
+    let (head, decrypted) = peek(stream)?;
+    stream = decrypted;
+    match StreamFormat::detect(&head) {
+        StreamFormat::Compressed(compression) => {
+            let mut decompressor = Command::new(compression.as_str());
+            decompressor.arg("-dc");
+            stream = Box::new(spawn_stage(&mut decompressor, compression.as_str(), stream, Vec::new(), stages)?);
+        }
+        StreamFormat::Tar if !head.is_empty() => {}
+        _ => anyhow::bail!("The piped data is not an archive"),
+    }
+
+    let mut tar = if options.list_only { None } else { Some(spawn_tar(options)?) };
+    let mut reader: Box<dyn Read> = match tar.as_mut() {
+        Some((child, _)) => Box::new(Tee {
+            inner: stream,
+            copy: Some(child.stdin.take().context("Failed to open tar stdin")?),
+        }),
+        None => stream,
+    };
+
+    let mut summary = PipedSummary { format: Some(format), ..Default::default() };
+    let walked = tar_stream::walk_entries(&mut reader, |entry, _| {
+        if !matches!(entry.typeflag, b'0' | 0) {
+            return Ok(());
+        }
+        let (name, size) = (entry.name.as_str(), entry.size);
+        let path = Path::new(name);
+        if path.components().any(|part| matches!(part, Component::RootDir | Component::ParentDir)) {
+            anyhow::bail!("Refusing to restore {}: it points outside the target directory", name);
+        }
+        if !is_selected(name, &options.items) {
+            return Ok(());
+        }
+        if !options.list_only && options.target.join(path).symlink_metadata().is_ok() {
+            if options.overwrite {
+                summary.replaced += 1;
+            } else {
+                summary.kept.push(name.to_string());
+            }
+        }
+        if entry.mode & 0o6000 != 0 || entry.capabilities {
+            summary.privileged.push(PrivilegedEntry {
+                path: name.to_string(),
+                setuid: entry.mode & 0o4000 != 0,
+                setgid: entry.mode & 0o2000 != 0,
+                capabilities: entry.capabilities,
+            });
+        }
+        summary.files += 1;
+        summary.bytes += size;
+        if let Some(Component::Normal(first)) = path.components().next() {
+            summary.top_level.insert(first.to_string_lossy().to_string());
+        }
+        on_file(name, size);
+        Ok(())
+    });
+    // Read on past the end-of-archive marker so every byte is hashed and
+    // tar sees a complete archive
+    let walked = walked.and_then(|_| Ok(io::copy(&mut reader, &mut io::sink())?));
+    drop(reader);
+
+    // tar stops at a file it refuses, reporting why, or at the end of
+    // the input when reading stopped first
+    if let Some((mut child, stderr)) = tar {
+        let status = child.wait()?;
+        let stderr = stderr.join().unwrap_or_default();
+        if walked.is_ok() && !status.success() {
+            anyhow::bail!("tar failed: {}", stderr.trim());
+        }
+    }
+    walked?;
+    Ok(summary)
+}
+
+/// Whether `name` is one of `items` or inside one of them
+fn is_selected(name: &str, items: &[String]) -> bool {
+    items.is_empty()
+        || items.iter().any(|item| {
+            let item = item.trim_start_matches("./").trim_end_matches('/');
+            name == item || name.strip_prefix(item).is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// GNU tar extracting a plain tar stream from its stdin, with a thread
+/// collecting what it writes to stderr
+fn spawn_tar(options: &PipedRestore) -> Result<(Child, JoinHandle<String>)> {
+    let mut tar = Command::new("tar");
+    tar.args(["-x", "-f", "-"]).args(options.metadata.tar_options()).arg("-C").arg(&options.target);
+    // tar running as root would otherwise restore setuid/setgid bits and
+    // capabilities unasked, and as a user never restore them
+    if options.allow_privileged {
+        tar.arg("--same-permissions");
+    } else {
+        tar.arg("--no-same-permissions");
+        // Naming an xattr pattern turns xattrs on, so only when they are
+        // restored at all
+        if options.metadata == RestoreMetadata::Apply {
+            tar.arg("--xattrs-exclude=security.capability");
+        }
+    }
+    // Either way, existing directories such as the home directory keep
+    // their owner and permissions
+    if options.overwrite {
+        tar.args(["--backup=numbered", "--no-overwrite-dir"]);
+    } else {
+        tar.arg("--skip-old-files");
+    }
+    let items = options.items.iter().map(|item| item.trim_start_matches("./").trim_end_matches('/'));
+    tar.arg("--").args(items);
+
+    let mut child = tar
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run tar")?;
+    let mut stderr = child.stderr.take().context("Failed to read tar errors")?;
+    let collected = thread::spawn(move || {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text);
+        text
+    });
+    Ok((child, collected))
+}
+
+/// A decrypting or decompressing process in the pipeline, with the thread
+/// feeding it
+struct Stage {
+    name: &'static str,
+    child: Child,
+    feeder: JoinHandle<io::Result<()>>,
+}
+
+/// Start `command` reading `input`, after `prefix`, and return its output
+fn spawn_stage(
+    command: &mut Command,
+    name: &'static str,
+    mut input: Box<dyn Read + Send>,
+    mut prefix: Vec<u8>,
+    stages: &mut Vec<Stage>,
+) -> Result<ChildStdout> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", name))?;
+    let mut stdin = child.stdin.take().with_context(|| format!("Failed to open {} stdin", name))?;
+    let stdout = child.stdout.take().with_context(|| format!("Failed to read {} output", name))?;
+    let feeder = thread::spawn(move || {
+        let written = stdin.write_all(&prefix);
+        prefix.zeroize();
+        written?;
+        io::copy(&mut input, &mut stdin)?;
+        Ok(())
+    });
+    stages.push(Stage { name, child, feeder });
+    Ok(stdout)
+}
+
+/// Wait for every stage after the archive was read through
+fn finish(stages: Vec<Stage>) -> Result<()> {
+    for mut stage in stages {
+        let status = stage.child.wait()?;
+        let fed = stage.feeder.join().unwrap_or_else(|_| Err(io::Error::other("feeder panicked")));
+        if !status.success() {
+            return Err(stage_error(stage.name));
+        }
+        fed.with_context(|| format!("Failed to pass the archive to {}", stage.name))?;
+    }
+    Ok(())
+}
+
+/// Stop every stage after reading failed. A stage that had already failed
+/// on its own, such as gpg given the wrong passphrase, explains the
+/// failure better than the reading error does.
+fn abandon(stages: Vec<Stage>) -> Option<anyhow::Error> {
+    let mut cause = None;
+    for mut stage in stages {
+        // A stage that closed its output is usually about to exit
+        let mut exited = None;
+        for _ in 0..10 {
+            exited = stage.child.try_wait().ok().flatten();
+            if exited.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        if exited.is_none() {
+            let _ = stage.child.kill();
+        }
+        let _ = stage.child.wait();
+        // The feeder may still be blocked reading the pipe; it ends with
+        // the process
+        if exited.is_some_and(|status| !status.success()) && cause.is_none() {
+            cause = Some(stage_error(stage.name));
+        }
+    }
+    cause
+}
+
+fn stage_error(name: &str) -> anyhow::Error {
+    match name {
+        "gpg" => anyhow::anyhow!("Failed to decrypt the archive (wrong passphrase?)"),
+        name => anyhow::anyhow!("{} could not decompress the archive; it may be truncated or corrupt", name),
+    }
+}
+
+/// The first bytes of `reader`, and a reader that still starts with them
+fn peek(mut reader: Box<dyn Read + Send>) -> Result<(Vec<u8>, Box<dyn Read + Send>)> {
+    let mut head = Vec::with_capacity(MAGIC_LEN);
+    (&mut reader).take(MAGIC_LEN as u64).read_to_end(&mut head)?;
+    Ok((head.clone(), Box::new(io::Cursor::new(head).chain(reader))))
+}
+
+#[derive(Default)]
+struct Hashes {
+    sha256: Sha256,
+    blake3: blake3::Hasher,
+    bytes: u64,
+}
+
+/// Hashes everything read through it
+struct Hashing {
+    inner: Box<dyn Read + Send>,
+    hashes: Arc<Mutex<Hashes>>,
+}
+
+impl Read for Hashing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Ok(mut hashes) = self.hashes.lock() {
+            hashes.sha256.update(&buf[..n]);
+            hashes.blake3.update(&buf[..n]);
+            hashes.bytes += n as u64;
+        }
+        Ok(n)
+    }
+}
+
+/// Passes everything read through it on to `copy`, until `copy` closes
+struct Tee {
+    inner: Box<dyn Read + Send>,
+    copy: Option<ChildStdin>,
+}
+
+impl Read for Tee {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(copy) = self.copy.as_mut() {
+            match copy.write_all(&buf[..n]) {
+                // tar has exited; its status says why
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => self.copy = None,
+                written => written?,
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_restore_from_a_piped_compressed_archive() {
+        let root = std::env::temp_dir().join(format!("piped-restore-test-{}", std::process::id()));
+        let (source, target) = (root.join("source"), root.join("target"));
+        fs::create_dir_all(source.join(".config/app")).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        fs::write(source.join(".bashrc"), b"archived").unwrap();
+        fs::write(source.join(".config/app/settings.ini"), b"theme=dark").unwrap();
+        fs::write(target.join(".bashrc"), b"current").unwrap();
+
+        let archive = Command::new("tar").args(["-czf", "-", "-C"]).arg(&source).args([".bashrc", ".config"]).output().unwrap();
+        assert_eq!(StreamFormat::detect(&archive.stdout), StreamFormat::Compressed(Compression::Gzip));
+        assert_eq!(StreamFormat::detect(&[0x8c, 0x0d, 0x04, 0x09]), StreamFormat::Encrypted);
+        assert_eq!(StreamFormat::detect(b".bashrc\0"), StreamFormat::Tar);
+
+        let options = PipedRestore { target: target.clone(), items: Vec::new(), overwrite: false, list_only: false, metadata: RestoreMetadata::Apply, allow_privileged: false };
+        let mut seen = Vec::new();
+        let summary = restore(
+            Box::new(io::Cursor::new(archive.stdout.clone())),
+            &options,
+            || anyhow::bail!("not encrypted"),
+            |name, _| seen.push(name.to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(seen, [".bashrc", ".config/app/settings.ini"]);
+        assert_eq!(summary.kept, [".bashrc"]);
+        assert_eq!(fs::read(target.join(".bashrc")).unwrap(), b"current");
+        assert_eq!(fs::read(target.join(".config/app/settings.ini")).unwrap(), b"theme=dark");
+        assert_eq!(summary.archive_bytes, archive.stdout.len() as u64);
+        let expected: String = Sha256::digest(&archive.stdout).iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(summary.sha256, expected);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_privileged_bits_need_allowing() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = std::env::temp_dir().join(format!("piped-restore-privileged-test-{}", std::process::id()));
+        let source = root.join("source");
+        fs::create_dir_all(source.join(".local/bin")).unwrap();
+        fs::write(source.join(".local/bin/helper"), b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(source.join(".local/bin/helper"), fs::Permissions::from_mode(0o4755)).unwrap();
+        fs::write(source.join(".bashrc"), b"archived").unwrap();
+        let archive = Command::new("tar").args(["-cf", "-", "-C"]).arg(&source).args([".bashrc", ".local"]).output().unwrap();
+
+        let restore_into = |target: &Path, allow_privileged: bool| {
+            fs::create_dir_all(target).unwrap();
+            let options = PipedRestore {
+                target: target.to_path_buf(),
+                items: Vec::new(),
+                overwrite: false,
+                list_only: false,
+                metadata: RestoreMetadata::Apply,
+                allow_privileged,
+            };
+            let summary = restore(Box::new(io::Cursor::new(archive.stdout.clone())), &options, || anyhow::bail!("not encrypted"), |_, _| {}).unwrap();
+            let mode = fs::metadata(target.join(".local/bin/helper")).unwrap().permissions().mode();
+            (summary, mode & 0o7777)
+        };
+
+        let (summary, mode) = restore_into(&root.join("cleared"), false);
+        assert_eq!(summary.privileged.len(), 1);
+        assert_eq!(summary.privileged[0].path, ".local/bin/helper");
+        assert!(summary.privileged[0].setuid && !summary.privileged[0].setgid);
+        assert_eq!(mode & 0o6000, 0);
+        let (_, mode) = restore_into(&root.join("allowed"), true);
+        assert_eq!(mode & 0o4000, 0o4000);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_metadata_is_restored_unless_skipped() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::MetadataExt;
+        use crate::core::types::TAR_METADATA_OPTIONS;
+
+        let c_path = |path: &Path| CString::new(path.as_os_str().as_bytes()).unwrap();
+        let note = |path: &Path| -> Option<Vec<u8>> {
+            let mut value = [0u8; 64];
+            let name = CString::new("user.note").unwrap();
+            let len = unsafe { libc::getxattr(c_path(path).as_ptr(), name.as_ptr(), value.as_mut_ptr().cast(), value.len()) };
+            (len >= 0).then(|| value[..len as usize].to_vec())
+        };
+
+        let root = std::env::temp_dir().join(format!("piped-restore-metadata-test-{}", std::process::id()));
+        let source = root.join("source");
+        fs::create_dir_all(source.join(".config")).unwrap();
+        fs::write(source.join(".config/app.conf"), b"theme=dark").unwrap();
+        fs::hard_link(source.join(".config/app.conf"), source.join(".config/app.conf.link")).unwrap();
+        let name = CString::new("user.note").unwrap();
+        let set = unsafe { libc::setxattr(c_path(&source.join(".config/app.conf")).as_ptr(), name.as_ptr(), b"kept".as_ptr().cast(), 4, 0) };
+        assert_eq!(set, 0, "the temporary directory needs user xattrs");
+        let archive = Command::new("tar").args(TAR_METADATA_OPTIONS).args(["-cf", "-", "-C"]).arg(&source).arg(".config").output().unwrap();
+        assert!(archive.status.success());
+
+        for (metadata, expected) in [(RestoreMetadata::Apply, Some(b"kept".to_vec())), (RestoreMetadata::Skip, None)] {
+            let target = root.join(metadata.as_str());
+            fs::create_dir_all(&target).unwrap();
+            let options = PipedRestore { target: target.clone(), items: Vec::new(), overwrite: false, list_only: false, metadata, allow_privileged: false };
+            restore(Box::new(io::Cursor::new(archive.stdout.clone())), &options, || anyhow::bail!("not encrypted"), |_, _| {}).unwrap();
+
+            assert_eq!(note(&target.join(".config/app.conf")), expected, "{}", metadata.as_str());
+            // Hard links stay links either way
+            let inode = |name: &str| fs::metadata(target.join(name)).unwrap().ino();
+            assert_eq!(inode(".config/app.conf"), inode(".config/app.conf.link"));
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use log::{debug, info};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::backend::dir_size;
+use crate::core::exclusions::ExclusionSet;
+use crate::core::types::{CheckResult, CheckStatus};
+use crate::ui::terminal::format_bytes;
+
+/// Unreadable paths named in the check's detail; the rest are counted
+const UNREADABLE_SHOWN: usize = 3;
+
+/// What a backup is about to read and write
+#[derive(Debug, Clone)]
+pub struct PreflightPlan {
+    pub home: PathBuf,
+    /// Selected items, relative to `home`
+    pub items: Vec<PathBuf>,
+    pub exclusions: ExclusionSet,
+    /// Total of the selected items' sizes, before compression
+    pub estimate: u64,
+    /// Items whose size is still being computed
+    pub sizes_pending: usize,
+    /// Where the archive is written first, then each local directory it is
+    /// copied to
+    pub local_dirs: Vec<PathBuf>,
+    /// rclone destinations, reached through a local staging directory
+    pub remotes: Vec<String>,
+    /// `validation.minimum_disk_space`: less free than this fails the check
+    pub minimum_free: Option<u64>,
+}
+
+/// Check the destinations have room and can be written, and that the
+/// selected files can be read, so a backup doesn't stop halfway through
+pub async fn run_checks(plan: &PreflightPlan) -> Vec<CheckResult> {
+    info!("Running pre-flight checks for {} items", plan.items.len());
+    let mut results = Vec::new();
+
+    let estimate = format_bytes(plan.estimate);
+    results.push(if plan.sizes_pending > 0 {
+        CheckResult::new(
+            "Space",
+            "Estimated size",
+            CheckStatus::Warning,
+            &format!("{} so far; {} items are still being sized", estimate, plan.sizes_pending),
+        )
+    } else {
+        CheckResult::new("Space", "Estimated size", CheckStatus::Pass, &format!("{} before compression", estimate))
+    });
+
+    for dir in &plan.local_dirs {
+        let name = dir.display().to_string();
+        // A destination that doesn't exist yet is created on its parent
+        let Some(existing) = dir.ancestors().find(|ancestor| ancestor.is_dir()) else {
+            results.push(CheckResult::new("Destination", &name, CheckStatus::Fail, "No part of this path exists"));
+            continue;
+        };
+
+        results.push(match probe_write(existing) {
+            Ok(()) => CheckResult::new("Destination", &name, CheckStatus::Pass, "Writable"),
+            Err(e) => CheckResult::new(
+                "Destination",
+                &name,
+                CheckStatus::Fail,
+                &format!("Cannot write to {}: {}", existing.display(), e),
+            ),
+        });
+
+        results.push(match dir_size::disk_space(existing).await {
+            Ok((_, free)) => space_check(&name, free, plan.estimate, plan.minimum_free),
+            Err(e) => CheckResult::new(
+                "Space",
+                &name,
+                CheckStatus::Warning,
+                &format!("Could not read free space: {}", e),
+            ),
+        });
+    }
+
+    for remote in &plan.remotes {
+        results.push(CheckResult::new(
+            "Destination",
+            remote,
+            CheckStatus::Pass,
+            "Uploaded after the archive is staged; space on the remote is not checked",
+        ));
+    }
+
+    let (home, items, exclusions) = (plan.home.clone(), plan.items.clone(), plan.exclusions.clone());
+    let readable = tokio::task::spawn_blocking(move || unreadable_paths(&exclusions, &home, &items)).await;
+    results.push(match readable {
+        Ok((checked, unreadable)) if unreadable.is_empty() => CheckResult::new(
+            "Files",
+            "Readable",
+            CheckStatus::Pass,
+            &format!("All {} files and directories can be read", checked),
+        ),
+        Ok((_, unreadable)) => {
+            let mut shown: Vec<String> = unreadable.iter().take(UNREADABLE_SHOWN).map(|p| p.display().to_string()).collect();
+            if unreadable.len() > UNREADABLE_SHOWN {
+                shown.push(format!("and {} more", unreadable.len() - UNREADABLE_SHOWN));
+            }
+            CheckResult::new(
+                "Files",
+                "Readable",
+                CheckStatus::Warning,
+                &format!("{} can't be read and will be left out: {}", unreadable.len(), shown.join(", ")),
+            )
+        }
+        Err(e) => CheckResult::new("Files", "Readable", CheckStatus::Warning, &format!("Could not check: {}", e)),
+    });
+    results
+}
+
+/// Whether any check stops the backup from going ahead
+pub fn has_failures(results: &[CheckResult]) -> bool {
+    results.iter().any(|result| result.status == CheckStatus::Fail)
+}
+
+fn space_check(name: &str, free: u64, estimate: u64, minimum_free: Option<u64>) -> CheckResult {
+    match minimum_free {
+        Some(minimum) if free < minimum => CheckResult::new(
+            "Space",
+            name,
+            CheckStatus::Fail,
+            &format!("{} free, below the configured minimum of {}", format_bytes(free), format_bytes(minimum)),
+        ),
+        // Compression usually shrinks the archive well below the estimate
+        _ if free < estimate => CheckResult::new(
+            "Space",
+            name,
+            CheckStatus::Warning,
+            &format!(
+                "{} free; the backup may need up to {} before compression",
+                format_bytes(free),
+                format_bytes(estimate)
+            ),
+        ),
+        _ => CheckResult::new("Space", name, CheckStatus::Pass, &format!("{} free", format_bytes(free))),
+    }
+}
+
+/// Create and remove a private file in `dir`, the way the archive will be
+fn probe_write(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".backup-ui-preflight-{}", std::process::id()));
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(&probe)?;
+    fs::remove_file(&probe)
+}
+
+/// Walk the selected items as the backup will, skipping exclusions, and
+/// return how many entries were checked and the home-relative paths that
+/// couldn't be opened. Symlinks are archived as links, so aren't followed.
+fn unreadable_paths(exclusions: &ExclusionSet, home: &Path, roots: &[PathBuf]) -> (usize, Vec<PathBuf>) {
+    let mut checked = 0;
+    let mut unreadable = Vec::new();
+    let mut pending: Vec<PathBuf> = roots.iter().filter(|root| !exclusions.is_excluded(root)).cloned().collect();
+    while let Some(rel) = pending.pop() {
+        let full = home.join(&rel);
+        let Ok(metadata) = full.symlink_metadata() else {
+            continue;
+        };
+        checked += 1;
+        let opened = if metadata.is_dir() {
+            fs::read_dir(&full).map(|entries| {
+                for entry in entries.flatten() {
+                    let child = rel.join(entry.file_name());
+                    if !exclusions.is_excluded(&child) {
+                        pending.push(child);
+                    }
+                }
+            })
+        } else if metadata.is_file() {
+            fs::File::open(&full).map(drop)
+        } else {
+            Ok(())
+        };
+        match opened {
+            Ok(()) => {}
+            // Removed since it was listed
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                debug!("Cannot read {}: {}", full.display(), e);
+                unreadable.push(rel);
+            }
+        }
+    }
+    unreadable.sort();
+    (checked, unreadable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_preflight_checks_space_access_and_readability() {
+        let home = std::env::temp_dir().join(format!("preflight-test-{}", std::process::id()));
+        fs::create_dir_all(home.join(".config/app/cache")).unwrap();
+        fs::write(home.join(".config/app/settings.ini"), b"theme=dark").unwrap();
+        fs::write(home.join(".config/app/cache/blob"), b"cached").unwrap();
+        fs::write(home.join(".config/app/secret"), b"hidden").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(home.join(".config/app/secret"), fs::Permissions::from_mode(0o000)).unwrap();
+        }
+        // Root reads the file regardless of its mode
+        let privileged = fs::File::open(home.join(".config/app/secret")).is_ok();
+
+        let plan = PreflightPlan {
+            home: home.clone(),
+            items: vec![PathBuf::from(".config/app")],
+            exclusions: ExclusionSet::new(&[".config/*/cache".to_string()]).unwrap(),
+            estimate: 16,
+            sizes_pending: 0,
+            local_dirs: vec![home.join("backups/not-yet-created")],
+            remotes: Vec::new(),
+            minimum_free: None,
+        };
+        let results = run_checks(&plan).await;
+        let status = |category: &str| results.iter().find(|r| r.category == category && r.name != "Estimated size").unwrap();
+
+        assert_eq!(status("Destination").status, CheckStatus::Pass);
+        assert_eq!(status("Space").status, CheckStatus::Pass);
+        assert!(!home.join("backups").exists());
+        let files = status("Files");
+        if privileged {
+            assert_eq!(files.status, CheckStatus::Pass);
+            assert_eq!(files.detail, "All 3 files and directories can be read");
+        } else {
+            assert_eq!(files.status, CheckStatus::Warning);
+            assert!(files.detail.contains(".config/app/secret"));
+        }
+        assert!(!has_failures(&results));
+
+        assert_eq!(space_check("usb", 100, 50, Some(200)).status, CheckStatus::Fail);
+        assert_eq!(space_check("usb", 100, 150, Some(50)).status, CheckStatus::Warning);
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
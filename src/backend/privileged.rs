@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+use log::{debug, warn};
+
+use crate::core::types::{PrivilegedEntry, RestoreItem};
+
+fn normalize_entry_path(path: &str) -> String {
+    path.trim_start_matches("./").trim_end_matches('/').to_string()
+}
+
+/// Attach privileged entries to the restore items that contain them
+pub fn annotate_items(items: &mut [RestoreItem], entries: &[PrivilegedEntry]) {
+    for item in items.iter_mut() {
+        let prefix = normalize_entry_path(&item.original_path.to_string_lossy());
+        item.privileged_entries = entries
+            .iter()
+            .filter(|entry| entry.path == prefix || entry.path.starts_with(&format!("{}/", prefix)))
+            .cloned()
+            .collect();
+    }
+}
+
+/// Remove setuid/setgid bits and file capabilities from a restored file
+pub async fn strip_privileges(path: &PathBuf) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to stat {}", path.display()))?;
+        let mode = metadata.permissions().mode() & !0o6000;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+
+    // setcap may not be installed; a missing capability is not an error
+    match TokioCommand::new("setcap")
+        .arg("-r")
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+    {
+        Ok(status) => debug!("setcap -r {} exited with {:?}", path.display(), status.code()),
+        Err(e) => warn!("Could not run setcap to clear capabilities on {}: {}", path.display(), e),
+    }
+
+    Ok(())
+}
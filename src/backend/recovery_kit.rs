@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use qrcode::render::unicode;
+use qrcode::QrCode;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::backend::catalog::local_hostname;
+use crate::backend::checksum::{self, HashAlgorithm};
+use crate::backend::item_report;
+use crate::core::types::Compression;
+use crate::ui::terminal::format_bytes;
+
+/// GPG key an archive was encrypted to, as listed on the kit
+#[derive(Debug, Clone, PartialEq)]
+pub struct KitKey {
+    pub fingerprint: String,
+    /// First user ID, when the key was in the keyring
+    pub name: Option<String>,
+}
+
+/// One printable page for getting a single archive back onto a bare
+/// machine: what the archive is, where its copies went, the checksum and
+/// key fingerprints as QR codes to check against, and the restore steps
+#[derive(Debug, Clone)]
+pub struct RecoveryKit {
+    pub hostname: String,
+    pub created: DateTime<Local>,
+    pub archive: String,
+    pub mode: String,
+    pub size: Option<u64>,
+    /// As the catalog records it, algorithm first
+    pub checksum: Option<String>,
+    /// Where each copy of the archive was written
+    pub locations: Vec<String>,
+    /// Encrypted to a passphrase, rather than (or besides) `keys`
+    pub passphrase: bool,
+    pub keys: Vec<KitKey>,
+}
+
+impl RecoveryKit {
+    pub fn new(archive: &str, mode: &str) -> Self {
+        Self {
+            hostname: local_hostname(),
+            created: Local::now(),
+            archive: archive.to_string(),
+            mode: mode.to_string(),
+            size: None,
+            checksum: None,
+            locations: Vec::new(),
+            passphrase: false,
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn render(&self) -> Result<String> {
+        let mut kit = String::new();
+        let _ = writeln!(kit, "BACKUP RECOVERY KIT - {}", self.hostname);
+        let _ = writeln!(kit, "Made {} for one archive. Print it and keep it away from the machine", self.created.format("%Y-%m-%d %H:%M %Z"));
+        let _ = writeln!(kit, "and from the backups.");
+        let _ = writeln!(kit);
+
+        let _ = writeln!(kit, "ARCHIVE");
+        let _ = writeln!(kit, "  {}  {} mode{}", self.archive, self.mode, self.size.map(|size| format!("  {}", format_bytes(size))).unwrap_or_default());
+        for location in &self.locations {
+            let _ = writeln!(kit, "  copy: {}", location);
+        }
+        let algorithm = self.checksum.as_deref().map(HashAlgorithm::of).unwrap_or_default();
+        match self.checksum.as_deref() {
+            Some(recorded) => {
+                let _ = writeln!(kit, "  {:<9} {}", format!("{}:", algorithm.as_str()), checksum::digest(recorded));
+                let _ = writeln!(kit);
+                kit.push_str(&qr_code(recorded)?);
+            }
+            None => {
+                let _ = writeln!(kit, "  checksum: not recorded");
+            }
+        }
+        let _ = writeln!(kit);
+
+        let _ = writeln!(kit, "ENCRYPTION");
+        if self.keys.is_empty() && !self.passphrase {
+            let _ = writeln!(kit, "  None. Anyone holding a copy of the archive can read it.");
+        }
+        if self.passphrase {
+            let _ = writeln!(kit, "  Passphrase. It is NOT on this kit; keep it in your password manager,");
+            let _ = writeln!(kit, "  or split it with `backup-ui shares split`.");
+        }
+        for key in &self.keys {
+            let _ = writeln!(kit, "  GPG key {}", key.fingerprint);
+            if let Some(name) = &key.name {
+                let _ = writeln!(kit, "    {}", name);
+            }
+            let _ = writeln!(kit);
+            kit.push_str(&qr_code(&format!("OPENPGP4FPR:{}", key.fingerprint))?);
+        }
+        if !self.keys.is_empty() {
+            let _ = writeln!(kit, "  Only these keys' private halves can decrypt the archive. Keep one on a");
+            let _ = writeln!(kit, "  smartcard, or on paper: gpg --export-secret-keys <fingerprint> | paperkey");
+        }
+        let _ = writeln!(kit);
+
+        let _ = writeln!(kit, "RESTORE STEPS ON A NEW MACHINE");
+        let _ = writeln!(kit, "  1. Install an operating system with gnupg and tar, and create your user");
+        if !self.keys.is_empty() {
+            let _ = writeln!(kit, "  2. Bring back the private key: plug in the smartcard and run");
+            let _ = writeln!(kit, "       gpg --card-status; gpg --card-edit (then: fetch)");
+            let _ = writeln!(kit, "     or rebuild it from paper: paperkey --pubring public.gpg --secrets key.txt | gpg --import");
+            let _ = writeln!(kit, "     Check its fingerprint against the one above: gpg --fingerprint");
+        } else {
+            let _ = writeln!(kit, "  2. Have the archive's passphrase at hand, if it is encrypted");
+        }
+        let _ = writeln!(kit, "  3. Copy the archive to the machine from any copy listed above");
+        if let Some(remote) = self.locations.iter().find(|location| location.contains(':') && !location.starts_with('/')) {
+            let _ = writeln!(kit, "       rclone copy '{}' .", remote);
+        }
+        let _ = writeln!(kit, "  4. Check it against the {} above (or scan the first QR code)", algorithm.as_str());
+        let _ = writeln!(kit, "       {} {}", algorithm.command(), self.archive);
+        let tar_option = Compression::detect(Path::new(&self.archive))
+            .map(|compression| format!(" {}", compression.tar_option()))
+            .unwrap_or_default();
+        let _ = writeln!(kit, "  5. Extract into your home directory");
+        if self.archive.ends_with(".gpg") {
+            let _ = writeln!(kit, "       gpg --decrypt {} | tar -xpf -{} -C ~", self.archive, tar_option);
+        } else {
+            let _ = writeln!(kit, "       tar -xpf {}{} -C ~", self.archive, tar_option);
+        }
+        let _ = writeln!(kit, "     or install backup-ui and choose Restore to pick items and keep a rollback snapshot");
+        let _ = writeln!(kit, "  6. Fix key permissions: chmod 700 ~/.ssh ~/.gnupg; chmod 600 ~/.ssh/id_*");
+        Ok(kit)
+    }
+
+    /// Write the kit to a new file in the reports directory, readable only
+    /// by the user since it names where the backups are
+    pub fn save(&self) -> Result<PathBuf> {
+        let dir = item_report::default_dir();
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("recovery-kit-{}.txt", self.created.format("%Y%m%d-%H%M%S")));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.write_all(self.render()?.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// `text` as an indented QR code of block characters, dark on light as
+/// printed
+fn qr_code(text: &str) -> Result<String> {
+    let code = QrCode::new(text.as_bytes()).context("Failed to make a QR code")?;
+    let rendered = code.render::<unicode::Dense1x2>().build();
+    Ok(rendered.lines().map(|line| format!("  {}\n", line)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery_kit_lists_archive_keys_and_steps() {
+        let mut kit = RecoveryKit::new("complete-20260101.tar.zst.gpg", "complete");
+        kit.size = Some(4096);
+        kit.checksum = Some(format!("blake3:{}", "ab".repeat(32)));
+        kit.locations = vec!["/mnt/usb/complete-20260101.tar.zst.gpg".to_string(), "nas:backups/complete-20260101.tar.zst.gpg".to_string()];
+        kit.keys = vec![KitKey {
+            fingerprint: "4E65F9FBCF28BC30EA113211684117187E401454".to_string(),
+            name: Some("Alice Example <alice@example.com>".to_string()),
+        }];
+
+        let text = kit.render().unwrap();
+        assert!(text.contains(&format!("  blake3:   {}", "ab".repeat(32))));
+        assert!(text.contains("  GPG key 4E65F9FBCF28BC30EA113211684117187E401454\n    Alice Example"));
+        assert!(text.contains("rclone copy 'nas:backups/complete-20260101.tar.zst.gpg' ."));
+        assert!(text.contains("b3sum complete-20260101.tar.zst.gpg"));
+        assert!(text.contains("gpg --decrypt complete-20260101.tar.zst.gpg | tar -xpf - --zstd -C ~"));
+        assert!(!text.contains("Passphrase."));
+        // A QR code for the checksum and one for the key
+        assert!(text.contains(&qr_code(kit.checksum.as_deref().unwrap()).unwrap()));
+        assert!(text.contains(&qr_code("OPENPGP4FPR:4E65F9FBCF28BC30EA113211684117187E401454").unwrap()));
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::core::types::{ArchiveInfo, PrivilegedEntry, RestoreItem};
+
+/// Space the cache may use before the least recently read listings go
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Contents listing of a remote archive, as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedListing {
+    remote: String,
+    size: u64,
+    items: Vec<CachedItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedItem {
+    name: String,
+    original_path: PathBuf,
+    restore_path: PathBuf,
+    size: u64,
+    privileged_entries: Vec<PrivilegedEntry>,
+}
+
+/// Read-through cache of what remote archives contain, so browsing one
+/// again doesn't download it. Only unencrypted archives are cached: the
+/// listing of an encrypted one would put its file names in the clear.
+pub struct RemoteCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl RemoteCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Cache under the user's cache directory
+    pub fn open_default() -> Self {
+        let dir = dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from(".cache"))
+            .join("backup-manager")
+            .join("remote");
+        Self::new(dir, DEFAULT_MAX_BYTES)
+    }
+
+    /// Whether `archive` can be cached at all
+    pub fn caches(archive: &ArchiveInfo) -> bool {
+        archive.remote.is_some() && !archive.encrypted
+    }
+
+    /// Cached contents of `archive`, with conflicts checked against the
+    /// disk as it is now
+    pub fn items(&self, archive: &ArchiveInfo) -> Option<Vec<RestoreItem>> {
+        let path = self.entry_path(archive)?;
+        let content = fs::read(&path).ok()?;
+        let listing: CachedListing = match serde_json::from_slice(&content) {
+            Ok(listing) => listing,
+            Err(e) => {
+                warn!("Dropping unreadable cache entry {}: {}", path.display(), e);
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+        };
+        if Some(&listing.remote) != archive.remote.as_ref() || listing.size != archive.size {
+            return None;
+        }
+
+        // Reading an entry makes it the last to be evicted
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        debug!("Using cached listing of {}", archive.name);
+
+        Some(
+            listing.items
+                .into_iter()
+                .map(|item| RestoreItem {
+                    name: item.name,
+                    conflicts: item.restore_path.exists(),
+                    original_path: item.original_path,
+                    restore_path: item.restore_path,
+                    size: item.size,
+                    selected: false,
+                    privileged_entries: item.privileged_entries,
+                })
+                .collect(),
+        )
+    }
+
+    /// Remember the contents of `archive`, then trim the cache to size
+    pub fn store(&self, archive: &ArchiveInfo, items: &[RestoreItem]) -> Result<()> {
+        let (Some(path), Some(remote)) = (self.entry_path(archive), archive.remote.clone()) else {
+            return Ok(());
+        };
+        let listing = CachedListing {
+            remote,
+            size: archive.size,
+            items: items
+                .iter()
+                .map(|item| CachedItem {
+                    name: item.name.clone(),
+                    original_path: item.original_path.clone(),
+                    restore_path: item.restore_path.clone(),
+                    size: item.size,
+                    privileged_entries: item.privileged_entries.clone(),
+                })
+                .collect(),
+        };
+
+        create_private_dir(&self.dir)?;
+        let partial = path.with_extension("partial");
+        write_private(&partial, &serde_json::to_vec(&listing)?)?;
+        fs::rename(&partial, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        self.evict()
+    }
+
+    /// Delete the least recently read entries until the cache fits
+    fn evict(&self) -> Result<()> {
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+            })
+            .collect();
+        entries.sort();
+
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        for (_, size, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            debug!("Evicting {} from the remote cache", path.display());
+            fs::remove_file(&path)?;
+            total -= size;
+        }
+        Ok(())
+    }
+
+    /// One file per remote archive; replacing the archive changes its size
+    /// and so invalidates the entry
+    fn entry_path(&self, archive: &ArchiveInfo) -> Option<PathBuf> {
+        if !Self::caches(archive) {
+            return None;
+        }
+        let remote = archive.remote.as_deref()?;
+        let digest = Sha256::digest(remote.as_bytes());
+        let name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        Some(self.dir.join(format!("{}.json", name)))
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    std::io::Write::write_all(&mut file, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::BackupMode;
+
+    fn remote_archive(name: &str, size: u64) -> ArchiveInfo {
+        ArchiveInfo {
+            path: PathBuf::from(format!("gdrive:backups/{}", name)),
+            name: name.to_string(),
+            created: chrono::Utc::now(),
+            size,
+            mode: BackupMode::Secure,
+            encrypted: false,
+            description: String::new(),
+            items: Vec::new(),
+            hostname: None,
+            remote: Some(format!("gdrive:backups/{}", name)),
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip_and_eviction() {
+        let dir = std::env::temp_dir().join(format!("remote-cache-test-{}", std::process::id()));
+        let item = RestoreItem {
+            name: ".bashrc".to_string(),
+            original_path: PathBuf::from(".bashrc"),
+            restore_path: dir.join("missing/.bashrc"),
+            size: 42,
+            selected: true,
+            conflicts: true,
+            privileged_entries: Vec::new(),
+        };
+
+        // Room for about one listing
+        let cache = RemoteCache::new(dir.clone(), 400);
+        let first = remote_archive("backup_a_secure.tar.gz", 1000);
+        cache.store(&first, std::slice::from_ref(&item)).unwrap();
+        let items = cache.items(&first).unwrap();
+        assert_eq!(items[0].name, ".bashrc");
+        assert!(!items[0].conflicts && !items[0].selected);
+
+        // Same name, different size: the archive was replaced
+        assert!(cache.items(&remote_archive("backup_a_secure.tar.gz", 1001)).is_none());
+
+        let mut encrypted = remote_archive("backup_b_complete.tar.gz.gpg", 10);
+        encrypted.encrypted = true;
+        cache.store(&encrypted, std::slice::from_ref(&item)).unwrap();
+        assert!(cache.items(&encrypted).is_none());
+
+        let second = remote_archive("backup_c_secure.tar.gz", 2000);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.store(&second, std::slice::from_ref(&item)).unwrap();
+        assert!(cache.items(&second).is_some());
+        assert!(cache.items(&first).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
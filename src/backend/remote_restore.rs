@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::path::Path;
+use std::process::{Output, Stdio};
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::extract::{self, member_name};
+use crate::core::security::SecurePassword;
+use crate::core::types::{ArchiveInfo, Compression, RestoreItem, RestoreMetadata};
+
+/// Restores an archive straight onto another machine by streaming it
+/// through `ssh host tar -x`, so nothing is staged on either side. GPG
+/// archives are decrypted locally and only the plaintext tarball crosses
+/// the (encrypted) SSH channel.
+///
+/// SSH runs in batch mode: key-based authentication is required and the
+/// remote host key must already be in `known_hosts`, so a first connection
+/// can't silently trust an unknown machine.
+pub struct RemoteRestore {
+    host: String,
+}
+
+impl RemoteRestore {
+    /// `host` is anything `ssh` accepts as a destination: `host`,
+    /// `user@host`, an alias from `~/.ssh/config` or `ssh://user@host:port`
+    pub fn new(host: &str) -> Result<Self> {
+        let host = host.trim();
+        if host.is_empty() {
+            anyhow::bail!("No host given");
+        }
+        if host.starts_with('-') || host.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            anyhow::bail!("'{}' is not a valid SSH destination", host);
+        }
+        Ok(Self { host: host.to_string() })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Extract `items` (or the whole archive when every item is selected)
+    /// into the remote user's home directory
+    pub async fn push(
+        &self,
+        archive: &ArchiveInfo,
+        items: &[&RestoreItem],
+        all_selected: bool,
+        password: Option<&SecurePassword>,
+        metadata: RestoreMetadata,
+    ) -> Result<()> {
+        if archive.remote.is_some() {
+            anyhow::bail!("{} is stored on a remote; download it before pushing it to another host", archive.name);
+        }
+
+        let compression = Compression::detect(&archive.path);
+        let members: Vec<String> = if all_selected {
+            Vec::new()
+        } else {
+            items.iter().map(|item| member_name(&item.original_path)).collect()
+        };
+        let remote_command = remote_tar_command(compression, metadata, &members);
+        debug!("Pushing {} to {}: {}", archive.name, self.host, remote_command);
+
+        let mut ssh = TokioCommand::new("ssh");
+        ssh.args(["-o", "BatchMode=yes", "--"])
+            .arg(&self.host)
+            .arg(&remote_command)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        let output = feed(&archive.path, password, ssh).await?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Restore on {} failed: {}",
+                self.host,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        info!("Restored {} onto {}", archive.name, self.host);
+        Ok(())
+    }
+}
+
+/// Run `consumer` with the archive, decrypted if need be, on its stdin and
+/// return its output. A consumer that fails leaves gpg writing into a pipe
+/// nobody reads, so gpg is then stopped rather than waited for.
+async fn feed(archive: &Path, password: Option<&SecurePassword>, mut consumer: TokioCommand) -> Result<Output> {
+    let mut gpg = None;
+    if archive.extension().map(|e| e == "gpg").unwrap_or(false) {
+        let password = password.context("Archive is encrypted but no password was provided")?;
+        let mut child = extract::spawn_decrypt(archive, password).await?;
+        consumer.stdin(extract::decrypted_stdio(&mut child)?);
+        gpg = Some(child);
+    } else {
+        let file = std::fs::File::open(archive)
+            .with_context(|| format!("Failed to open {}", archive.display()))?;
+        consumer.stdin(Stdio::from(file));
+    }
+
+    let child = consumer.spawn();
+    // The command keeps its copy of gpg's output open until dropped
+    drop(consumer);
+    let output = match child {
+        Ok(child) => child.wait_with_output().await,
+        Err(e) => Err(e),
+    };
+    if let Some(mut gpg) = gpg {
+        if !output.as_ref().is_ok_and(|output| output.status.success()) {
+            let _ = gpg.kill().await;
+        } else if !gpg.wait().await?.success() {
+            anyhow::bail!("Failed to decrypt archive (wrong password?)");
+        }
+    }
+    output.context("Failed to run ssh")
+}
+
+/// Command line run by the remote shell. tar can't detect compression on
+/// a pipe, so it is named explicitly. Member names come from the archive,
+/// so each is quoted to keep it a single literal argument.
+fn remote_tar_command(compression: Option<Compression>, metadata: RestoreMetadata, members: &[String]) -> String {
+    let mut command = "tar -xpf -".to_string();
+    if let Some(compression) = compression {
+        command.push(' ');
+        command.push_str(compression.tar_option());
+    }
+    for option in metadata.tar_options() {
+        command.push(' ');
+        command.push_str(&shell_quote(option));
+    }
+    command.push_str(" -C \"$HOME\"");
+    if !members.is_empty() {
+        command.push_str(" --");
+        for member in members {
+            command.push(' ');
+            command.push_str(&shell_quote(member));
+        }
+    }
+    command
+}
+
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_command_quotes_members() {
+        let members = vec![".config/app".to_string(), "it's; rm -rf ~".to_string()];
+        assert_eq!(
+            remote_tar_command(Some(Compression::Gzip), RestoreMetadata::Skip, &members),
+            "tar -xpf - --gzip '--no-same-owner' '--no-xattrs' '--no-acls' '--no-selinux' -C \"$HOME\" -- '.config/app' 'it'\\''s; rm -rf ~'"
+        );
+        // The include pattern must reach tar unexpanded
+        assert!(remote_tar_command(None, RestoreMetadata::Apply, &[]).contains(" '--xattrs-include=*' "));
+        assert_eq!(Compression::detect(Path::new("a.tar.xz")), Some(Compression::Xz));
+        assert_eq!(Compression::detect(Path::new("a.tar.zst.gpg")), Some(Compression::Zstd));
+        assert_eq!(Compression::detect(Path::new("legacy.gpg")), Some(Compression::Gzip));
+        assert_eq!(Compression::detect(Path::new("a.tar")), None);
+        assert!(RemoteRestore::new("-oProxyCommand=x").is_err());
+        assert!(RemoteRestore::new("user@new-box").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_feed_stops_gpg_when_the_consumer_fails() {
+        let dir = std::env::temp_dir().join(format!("remote-restore-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // Far more than a pipe holds, so gpg is still writing when the
+        // consumer gives up
+        let plain = dir.join("archive.tar");
+        std::fs::write(&plain, (0..4 * 1024 * 1024u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect::<Vec<_>>()).unwrap();
+        let archive = dir.join("archive.tar.gpg");
+        let mut gpg = std::process::Command::new("gpg")
+            .args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0", "--compress-algo", "none", "--symmetric", "--output"])
+            .arg(&archive)
+            .arg(&plain)
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        std::io::Write::write_all(&mut gpg.stdin.take().unwrap(), b"correct horse\n").unwrap();
+        assert!(gpg.wait().unwrap().success());
+        let password = SecurePassword::new("correct horse".to_string());
+
+        let mut consumer = TokioCommand::new("sh");
+        consumer.args(["-c", "head -c 10 >/dev/null; echo refused >&2; exit 3"]).stderr(Stdio::piped());
+        let fed = tokio::time::timeout(std::time::Duration::from_secs(30), feed(&archive, Some(&password), consumer)).await;
+        let output = fed.expect("feeding the archive hung").unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&output.stderr).trim(), "refused");
+
+        let mut consumer = TokioCommand::new("wc");
+        consumer.arg("-c").stdout(Stdio::piped());
+        let output = feed(&archive, Some(&password), consumer).await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), (4 * 1024 * 1024).to_string());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::dir_size;
+
+/// Filesystems that don't keep Unix owners and permissions, so archives on
+/// them can't be made private to the user
+const NO_PERMISSION_FILESYSTEMS: &[&str] = &["vfat", "exfat", "ntfs", "ntfs3", "fuseblk", "msdos"];
+
+/// Mounted drive that can be unplugged: USB sticks and disks, SD cards
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovableDrive {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    /// Filesystem label, or the vendor and model when it has none
+    pub label: Option<String>,
+    /// Capacity and free space, in bytes; None when `df` couldn't tell
+    pub size: Option<u64>,
+    pub free: Option<u64>,
+}
+
+impl RemovableDrive {
+    /// Label and mount point, e.g. `BACKUP (/media/me/BACKUP)`
+    pub fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{} ({})", label, self.mount_point.display()),
+            None => self.mount_point.display().to_string(),
+        }
+    }
+
+    /// Whether `bytes` fit in the free space; None when it is unknown
+    pub fn has_room_for(&self, bytes: u64) -> Option<bool> {
+        self.free.map(|free| free >= bytes)
+    }
+
+    /// Whether files written here keep the 0600 permissions archives get
+    pub fn keeps_permissions(&self) -> bool {
+        !NO_PERMISSION_FILESYSTEMS.contains(&self.fs_type.as_str())
+    }
+}
+
+/// Removable drives mounted right now, from /proc/mounts, with udev
+/// telling which devices are removable. Read-only mounts are left out.
+pub async fn list_drives() -> Result<Vec<RemovableDrive>> {
+    let mounts = fs::read_to_string("/proc/mounts").context("Failed to read /proc/mounts")?;
+
+    let mut drives: Vec<RemovableDrive> = Vec::new();
+    for mount in parse_mounts(&mounts) {
+        if !mount.writable || drives.iter().any(|drive| drive.device == mount.device) {
+            continue;
+        }
+        let properties = udev_properties(&mount.device).await.unwrap_or_else(|e| {
+            debug!("No udev information for {}: {}", mount.device, e);
+            HashMap::new()
+        });
+        if !is_removable(&properties, sysfs_removable(&mount.device)) {
+            continue;
+        }
+
+        let model = [properties.get("ID_VENDOR"), properties.get("ID_MODEL")]
+            .into_iter()
+            .flatten()
+            .map(|part| part.replace('_', " "))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let label = properties
+            .get("ID_FS_LABEL")
+            .cloned()
+            .or_else(|| Some(model).filter(|model| !model.is_empty()));
+        drives.push(RemovableDrive {
+            device: mount.device,
+            mount_point: mount.mount_point,
+            fs_type: mount.fs_type,
+            label,
+            size: None,
+            free: None,
+        });
+    }
+
+    for drive in &mut drives {
+        match dir_size::disk_space(&drive.mount_point).await {
+            Ok((size, free)) => (drive.size, drive.free) = (Some(size), Some(free)),
+            Err(e) => debug!("Could not read free space on {}: {}", drive.mount_point.display(), e),
+        }
+    }
+    Ok(drives)
+}
+
+/// One line of /proc/mounts backed by a device node
+#[derive(Debug, PartialEq)]
+struct Mount {
+    device: String,
+    mount_point: PathBuf,
+    fs_type: String,
+    writable: bool,
+}
+
+/// Mounts of `/dev` devices in /proc/mounts, which escapes spaces and
+/// other whitespace in paths as octal, e.g. `\040`
+fn parse_mounts(content: &str) -> Vec<Mount> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (device, mount_point, fs_type, options) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+            if !device.starts_with("/dev/") {
+                return None;
+            }
+            Some(Mount {
+                device: unescape(device),
+                mount_point: PathBuf::from(unescape(mount_point)),
+                fs_type: fs_type.to_string(),
+                writable: options.split(',').any(|option| option == "rw"),
+            })
+        })
+        .collect()
+}
+
+/// Undo the octal escapes of /proc/mounts and fstab fields
+pub fn unescape(field: &str) -> String {
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut rest = field.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let octal = after.get(..3).and_then(|digits| std::str::from_utf8(digits).ok()).and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match octal {
+            Some(value) if byte == b'\\' => {
+                bytes.push(value);
+                rest = &after[3..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// `KEY=value` lines from `udevadm info --query=property`
+async fn udev_properties(device: &str) -> Result<HashMap<String, String>> {
+    let output = TokioCommand::new("udevadm")
+        .args(["info", "--query=property", "--name"])
+        .arg(device)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .context("Failed to run udevadm")?;
+    if !output.status.success() {
+        anyhow::bail!("udevadm failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(parse_properties(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_properties(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// The kernel's removable flag for the disk holding `device`; a partition
+/// reads it from its parent disk
+fn sysfs_removable(device: &str) -> bool {
+    let Some(name) = Path::new(device).file_name() else {
+        return false;
+    };
+    let Ok(dir) = Path::new("/sys/class/block").join(name).canonicalize() else {
+        return false;
+    };
+    [dir.join("removable"), dir.join("../removable")]
+        .iter()
+        .any(|flag| fs::read_to_string(flag).is_ok_and(|value| value.trim() == "1"))
+}
+
+/// USB and memory card devices count, as do disks the kernel flags
+/// removable. Optical drives are left out: they can't be written like this.
+fn is_removable(properties: &HashMap<String, String>, sysfs_removable: bool) -> bool {
+    let flag = |key: &str| properties.get(key).is_some_and(|value| value == "1");
+    if flag("ID_CDROM") {
+        return false;
+    }
+    properties.get("ID_BUS").is_some_and(|bus| bus == "usb")
+        || flag("ID_DRIVE_FLASH_SD")
+        || flag("ID_DRIVE_MEDIA_FLASH_SD")
+        || sysfs_removable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mounts_and_udev_properties() {
+        let mounts = parse_mounts(
+            "proc /proc proc rw,nosuid 0 0\n\
+             /dev/nvme0n1p2 / ext4 rw,relatime 0 0\n\
+             /dev/sdb1 /media/me/My\\040Backups vfat rw,nosuid,uid=1000 0 0\n\
+             /dev/sr0 /media/me/CDROM iso9660 ro,nosuid 0 0\n",
+        );
+        assert_eq!(mounts.len(), 3);
+        assert_eq!(mounts[1].mount_point, PathBuf::from("/media/me/My Backups"));
+        assert!(mounts[1].writable);
+        assert!(!mounts[2].writable);
+
+        let usb = parse_properties("DEVNAME=/dev/sdb1\nID_BUS=usb\nID_FS_LABEL=BACKUPS\nID_FS_TYPE=vfat\n");
+        assert!(is_removable(&usb, false));
+        assert!(!is_removable(&parse_properties("ID_BUS=ata\n"), false));
+        assert!(is_removable(&parse_properties("ID_BUS=ata\n"), true));
+        assert!(!is_removable(&parse_properties("ID_BUS=usb\nID_CDROM=1\n"), true));
+
+        let drive = RemovableDrive {
+            device: "/dev/sdb1".to_string(),
+            mount_point: mounts[1].mount_point.clone(),
+            fs_type: mounts[1].fs_type.clone(),
+            label: usb.get("ID_FS_LABEL").cloned(),
+            size: None,
+            free: None,
+        };
+        assert_eq!(drive.name(), "BACKUPS (/media/me/My Backups)");
+        assert!(!drive.keeps_permissions());
+        assert_eq!(drive.has_room_for(1), None);
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::backend::catalog::{self, Catalog};
+use crate::backend::checksum::HashAlgorithm;
+use crate::backend::member_index;
+use crate::backend::parity;
+use crate::backend::tar_stream;
+use crate::backend::text_index::TextIndexer;
+use crate::core::types::{ArchiveInfo, Compression};
+
+/// Outcome of rewriting an archive with another compressor
+#[derive(Debug, Clone)]
+pub struct RepackResult {
+    pub archive: PathBuf,
+    pub files: usize,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub original_removed: bool,
+}
+
+/// Rewrite a cataloged local archive with `compression`. The new archive
+/// replaces the old one in the catalog only once every file in it has been
+/// read back and matched against the original by hash; the original is
+/// then deleted unless `keep_original` is set. `on_file` hears of each file
+/// as it is checked, with how many are done and how many there are.
+#[allow(clippy::too_many_arguments)]
+pub async fn repack(
+    archive: &Path,
+    compression: Compression,
+    level: u32,
+    threads: u32,
+    keep_original: bool,
+    texts: Option<TextIndexer>,
+    algorithm: HashAlgorithm,
+    mut on_file: impl FnMut(&str, usize, usize) + Send + 'static,
+) -> Result<RepackResult> {
+    let archive = archive
+        .canonicalize()
+        .with_context(|| format!("Failed to find {}", archive.display()))?;
+    let mut catalog = Catalog::open_default()?;
+    let original: ArchiveInfo = catalog
+        .list_archives()?
+        .into_iter()
+        .find(|info| info.remote.is_none() && info.path.canonicalize().ok().as_deref() == Some(archive.as_path()))
+        .with_context(|| format!("{} is not in the catalog; only archives made by backup-ui can be repacked", archive.display()))?;
+
+    // Rewriting these would mean decrypting them and asking for the
+    // passphrase again; they are left to the backup that made them
+    if original.encrypted {
+        anyhow::bail!("{} is encrypted; encrypted archives are not repacked", original.name);
+    }
+    if Compression::detect(&archive) == Some(compression) {
+        anyhow::bail!("{} is already compressed with {}", original.name, compression.as_str());
+    }
+
+    let target = repacked_path(&archive, compression)?;
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    // Keeps the extension, so the copy can be read back before it's renamed
+    let partial = target.with_file_name(format!(
+        ".partial-{}",
+        target.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+    ));
+
+    info!("Repacking {} with {} -{}", original.name, compression.as_str(), level);
+    let (source, dest) = (archive.clone(), partial.clone());
+    let written = tokio::task::spawn_blocking(move || recompress(&source, &dest, compression, level, threads)).await?;
+    if let Err(e) = written {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    // Every file must read back identical before the original can go
+    let (source, dest) = (archive.clone(), partial.clone());
+    let compared = tokio::task::spawn_blocking(move || -> Result<usize> {
+        let (old, _) = catalog::hash_contents(&source, None, algorithm)?;
+        let mut done = 0;
+        let (new, _) = catalog::hash_contents_reporting(&dest, None, algorithm, |name| {
+            done += 1;
+            on_file(name, done, old.len());
+        })?;
+        if old != new {
+            anyhow::bail!("Repacked archive does not match the original");
+        }
+        Ok(old.len())
+    })
+    .await?;
+    let files = match compared {
+        Ok(files) => files,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e.context(format!("{} was left as it was", original.name)));
+        }
+    };
+
+    fs::rename(&partial, &target)
+        .with_context(|| format!("Failed to move the repacked archive to {}", target.display()))?;
+
+    let index = catalog::index_archive(&target, texts, algorithm).await?;
+    let repacked = ArchiveInfo {
+        path: target.clone(),
+        name: target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size: index.size,
+        ..original.clone()
+    };
+    catalog.record_archive(&repacked, Some(&index))?;
+    // The tar stream inside is unchanged, so member offsets still hold
+    if let Err(e) = member_index::copy_beside(&archive, &target) {
+        warn!("Failed to index {}: {}", target.display(), e);
+    }
+
+    let mut original_removed = false;
+    if !keep_original {
+        catalog.remove_archive(&original.path.to_string_lossy())?;
+        match fs::remove_file(&archive) {
+            Ok(()) => {
+                member_index::remove_beside(&archive);
+                parity::remove_beside(&archive);
+                original_removed = true;
+            }
+            Err(e) => warn!("Failed to delete {}: {}", archive.display(), e),
+        }
+    }
+
+    Ok(RepackResult {
+        archive: target,
+        files,
+        old_size: original.size,
+        new_size: index.size,
+        original_removed,
+    })
+}
+
+/// `archive` renamed for `compression`, keeping everything before `.tar`
+fn repacked_path(archive: &Path, compression: Compression) -> Result<PathBuf> {
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .context("Archive path has no file name")?;
+    let lower = name.to_ascii_lowercase();
+    let stem_len = Compression::detect(archive)
+        .and_then(|old| lower.strip_suffix(&format!(".tar.{}", old.extension())))
+        .or_else(|| lower.strip_suffix(".tar"))
+        .map(str::len)
+        .with_context(|| format!("{} is not a tar archive", name))?;
+    Ok(archive.with_file_name(format!("{}.tar.{}", &name[..stem_len], compression.extension())))
+}
+
+/// Stream the tar inside `source` through the new compressor into
+/// `dest`, created private to the user
+fn recompress(source: &Path, dest: &Path, compression: Compression, level: u32, threads: u32) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let out = options
+        .open(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+
+    let program = compression.program(level, threads);
+    let mut args = program.split_whitespace();
+    let mut compressor = Command::new(args.next().unwrap_or(compression.as_str()))
+        .args(args)
+        .arg("-c")
+        .stdin(Stdio::piped())
+        .stdout(out)
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", compression.as_str()))?;
+
+    let (mut input, decompressor) = tar_stream::decompressed(source)?;
+    let mut stdin = compressor.stdin.take().context("Failed to open compressor input")?;
+    let copied = std::io::copy(&mut input, &mut stdin);
+    drop(stdin);
+
+    let output = compressor.wait_with_output()?;
+    if let Some(mut decompressor) = decompressor {
+        if !decompressor.wait()?.success() {
+            anyhow::bail!("Failed to decompress {}", source.display());
+        }
+    }
+    copied.context("Failed to recompress archive")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            compression.as_str(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    File::open(dest)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repacked_path() {
+        let zst = repacked_path(Path::new("/b/backup_host_20250101_secure.tar.gz"), Compression::Zstd).unwrap();
+        assert_eq!(zst, PathBuf::from("/b/backup_host_20250101_secure.tar.zst"));
+        let xz = repacked_path(Path::new("/b/old.TAR"), Compression::Xz).unwrap();
+        assert_eq!(xz, PathBuf::from("/b/old.tar.xz"));
+        assert!(repacked_path(Path::new("/b/notes.txt"), Compression::Xz).is_err());
+    }
+}
@@ -0,0 +1,325 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::backend::catalog::local_hostname;
+use crate::backend::item_report;
+use crate::core::types::{DestinationResult, ItemOutcome, ItemReport};
+use crate::ui::terminal::{format_bytes, format_duration};
+
+/// Format a report is saved in, chosen by the file's extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("md" | "markdown") => Ok(Self::Markdown),
+            Some("html" | "htm") => Ok(Self::Html),
+            _ => anyhow::bail!("Name the report .md or .html to choose its format: {}", path.display()),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Row of a report's item table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportItem {
+    pub path: String,
+    pub status: String,
+    pub files: Option<usize>,
+    pub bytes: Option<u64>,
+}
+
+impl From<&ItemReport> for ReportItem {
+    fn from(report: &ItemReport) -> Self {
+        Self {
+            path: report.path.display().to_string(),
+            status: report.status(),
+            files: Some(report.files),
+            bytes: Some(report.bytes),
+        }
+    }
+}
+
+/// A finished backup or restore written out for record keeping or to
+/// attach to a ticket
+#[derive(Debug, Clone)]
+pub struct OperationReport {
+    /// "Backup" or "Restore"
+    pub operation: &'static str,
+    pub hostname: String,
+    pub finished: DateTime<Local>,
+    pub mode: String,
+    /// Why the operation failed, if it did
+    pub error: Option<String>,
+    pub duration: Option<Duration>,
+    pub archive: Option<String>,
+    pub archive_size: Option<u64>,
+    pub encrypted: bool,
+    /// As the catalog records it, algorithm first
+    pub checksum: Option<String>,
+    /// Where the archive went, or where a restore wrote the files
+    pub destinations: Vec<DestinationResult>,
+    pub items: Vec<ReportItem>,
+    pub warnings: Vec<String>,
+}
+
+impl OperationReport {
+    pub fn new(operation: &'static str, mode: &str) -> Self {
+        Self {
+            operation,
+            hostname: local_hostname(),
+            finished: Local::now(),
+            mode: mode.to_string(),
+            error: None,
+            duration: None,
+            archive: None,
+            archive_size: None,
+            encrypted: false,
+            checksum: None,
+            destinations: Vec::new(),
+            items: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Items from a backup's item reports, with a warning for each path
+    /// that was left out
+    pub fn with_item_reports(mut self, reports: &[ItemReport]) -> Self {
+        self.items = reports.iter().map(ReportItem::from).collect();
+        for report in reports {
+            if report.outcome != ItemOutcome::BackedUp {
+                self.warnings.push(format!("{}: {}", report.path.display(), report.outcome.label()));
+            }
+            for path in &report.unreadable {
+                self.warnings.push(format!("{}: could not be read, left out", path.display()));
+            }
+        }
+        self
+    }
+
+    /// Label and value of each summary field that is known
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("Machine", self.hostname.clone()),
+            ("Finished", self.finished.format("%Y-%m-%d %H:%M:%S %Z").to_string()),
+            ("Mode", self.mode.clone()),
+            ("Result", self.error.as_ref().map(|e| format!("FAILED, {}", e)).unwrap_or_else(|| "succeeded".to_string())),
+        ];
+        if let Some(duration) = self.duration {
+            fields.push(("Duration", format_duration(duration)));
+        }
+        if let Some(archive) = &self.archive {
+            fields.push(("Archive", archive.clone()));
+        }
+        if let Some(size) = self.archive_size {
+            fields.push(("Archive size", format_bytes(size)));
+        }
+        fields.push(("Encrypted", if self.encrypted { "yes" } else { "no" }.to_string()));
+        fields.push(("Checksum", self.checksum.clone().unwrap_or_else(|| "not recorded".to_string())));
+        fields
+    }
+
+    fn destination_lines(&self) -> Vec<String> {
+        self.destinations
+            .iter()
+            .map(|result| match &result.outcome {
+                Ok(location) => format!("{}: {}", result.destination, location),
+                Err(error) => format!("{}: FAILED, {}", result.destination, error),
+            })
+            .collect()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.markdown(),
+            ReportFormat::Html => self.html(),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let cell = |text: &str| text.replace('|', "\\|");
+        let mut out = String::new();
+        let _ = writeln!(out, "# {} report: {}\n", self.operation, self.hostname);
+        for (label, value) in self.fields() {
+            let _ = writeln!(out, "- **{}:** {}", label, value);
+        }
+
+        if !self.destinations.is_empty() {
+            let _ = writeln!(out, "\n## Destinations\n");
+            for line in self.destination_lines() {
+                let _ = writeln!(out, "- {}", line);
+            }
+        }
+
+        let _ = writeln!(out, "\n## Warnings\n");
+        if self.warnings.is_empty() {
+            let _ = writeln!(out, "None.");
+        }
+        for warning in &self.warnings {
+            let _ = writeln!(out, "- {}", warning);
+        }
+
+        let _ = writeln!(out, "\n## Items ({})\n", self.items.len());
+        let _ = writeln!(out, "| Item | Status | Files | Size |");
+        let _ = writeln!(out, "|---|---|---:|---:|");
+        for item in &self.items {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                cell(&item.path),
+                cell(&item.status),
+                item.files.map(|files| files.to_string()).unwrap_or_default(),
+                item.bytes.map(format_bytes).unwrap_or_default(),
+            );
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::new();
+        let title = format!("{} report: {}", self.operation, escape(&self.hostname));
+        let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>", title);
+        let _ = writeln!(
+            out,
+            "<style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}\
+             td,th{{border:1px solid #ccc;padding:2px 8px;text-align:left}}.failed{{color:#b00}}</style>"
+        );
+        let _ = writeln!(out, "</head>\n<body>\n<h1>{}</h1>\n<table>", title);
+        for (label, value) in self.fields() {
+            let _ = writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", label, escape(&value));
+        }
+        let _ = writeln!(out, "</table>");
+
+        if !self.destinations.is_empty() {
+            let _ = writeln!(out, "<h2>Destinations</h2>\n<ul>");
+            for (result, line) in self.destinations.iter().zip(self.destination_lines()) {
+                let class = if result.outcome.is_err() { " class=\"failed\"" } else { "" };
+                let _ = writeln!(out, "<li{}>{}</li>", class, escape(&line));
+            }
+            let _ = writeln!(out, "</ul>");
+        }
+
+        let _ = writeln!(out, "<h2>Warnings</h2>");
+        if self.warnings.is_empty() {
+            let _ = writeln!(out, "<p>None.</p>");
+        } else {
+            let _ = writeln!(out, "<ul>");
+            for warning in &self.warnings {
+                let _ = writeln!(out, "<li>{}</li>", escape(warning));
+            }
+            let _ = writeln!(out, "</ul>");
+        }
+
+        let _ = writeln!(out, "<h2>Items ({})</h2>", self.items.len());
+        let _ = writeln!(out, "<table>\n<tr><th>Item</th><th>Status</th><th>Files</th><th>Size</th></tr>");
+        for item in &self.items {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&item.path),
+                escape(&item.status),
+                item.files.map(|files| files.to_string()).unwrap_or_default(),
+                item.bytes.map(format_bytes).unwrap_or_default(),
+            );
+        }
+        let _ = writeln!(out, "</table>\n</body>\n</html>");
+        out
+    }
+
+    /// Write the report to `path` in the format its extension names. It
+    /// lists files in the backup, so only the user can read it.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let format = ReportFormat::from_path(path)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.write_all(self.render(format).as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// New file for a report saved from the screens, in the reports directory
+pub fn default_path(operation: &str, format: ReportFormat) -> Result<PathBuf> {
+    let dir = item_report::default_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    Ok(dir.join(format!(
+        "{}-report-{}.{}",
+        operation.to_lowercase(),
+        Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    )))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_renders_items_warnings_and_destinations() {
+        let mut report = OperationReport::new("Backup", "secure").with_item_reports(&[
+            ItemReport {
+                path: PathBuf::from(".config"),
+                outcome: ItemOutcome::BackedUp,
+                files: 3,
+                bytes: 2048,
+                unreadable: vec![PathBuf::from(".config/a|b")],
+            },
+            ItemReport {
+                path: PathBuf::from(".vimrc"),
+                outcome: ItemOutcome::Missing,
+                files: 0,
+                bytes: 0,
+                unreadable: Vec::new(),
+            },
+        ]);
+        report.checksum = Some("sha256:abc".to_string());
+        report.destinations = vec![
+            DestinationResult { destination: "/mnt/usb".to_string(), outcome: Ok("/mnt/usb/b.tar.gz".to_string()) },
+            DestinationResult { destination: "nas:backups".to_string(), outcome: Err("<timeout>".to_string()) },
+        ];
+        assert_eq!(report.warnings, vec![".config/a|b: could not be read, left out", ".vimrc: missing"]);
+
+        let markdown = report.render(ReportFormat::Markdown);
+        assert!(markdown.contains("- **Checksum:** sha256:abc"));
+        assert!(markdown.contains("| .config | backed up, 1 unreadable | 3 | "));
+        assert!(markdown.contains("- .config/a|b: could not be read"));
+        assert!(markdown.contains("- nas:backups: FAILED, <timeout>"));
+
+        let html = report.render(ReportFormat::Html);
+        assert!(html.contains("<li class=\"failed\">nas:backups: FAILED, &lt;timeout&gt;</li>"));
+        assert!(!html.contains("<timeout>"));
+
+        assert_eq!(ReportFormat::from_path(Path::new("r.HTML")).unwrap(), ReportFormat::Html);
+        assert!(ReportFormat::from_path(Path::new("r.txt")).is_err());
+    }
+}
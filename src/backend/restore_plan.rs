@@ -0,0 +1,152 @@
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+use crate::backend::extract;
+use crate::backend::member_index::{IndexedMember, MemberKind};
+use crate::core::types::RestoreItem;
+use crate::ui::terminal::format_bytes;
+
+/// What restoring one archive entry would do to the disk
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedChange {
+    CreateFile,
+    CreateDir,
+    /// Replace a file, with the size and mtime it has now
+    Overwrite { old_size: u64, old_mtime: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedPath {
+    pub target: PathBuf,
+    pub change: PlannedChange,
+    /// Size and mtime of the archived entry
+    pub size: u64,
+    pub mtime: i64,
+}
+
+impl PlannedPath {
+    pub fn describe(&self) -> String {
+        match &self.change {
+            PlannedChange::CreateFile => format!("create {} ({})", self.target.display(), format_bytes(self.size)),
+            PlannedChange::CreateDir => format!("create directory {}", self.target.display()),
+            PlannedChange::Overwrite { old_size, old_mtime } => format!(
+                "overwrite {} ({}, {} → {}, {})",
+                self.target.display(),
+                format_bytes(*old_size),
+                format_time(*old_mtime),
+                format_bytes(self.size),
+                format_time(self.mtime),
+            ),
+        }
+    }
+}
+
+/// Everything a restore of the selected items would write, worked out
+/// without writing anything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestorePlan {
+    pub paths: Vec<PlannedPath>,
+    /// Directories that already exist and would be left as they are
+    pub existing_dirs: usize,
+}
+
+impl RestorePlan {
+    pub fn count(&self, matches: impl Fn(&PlannedChange) -> bool) -> usize {
+        self.paths.iter().filter(|path| matches(&path.change)).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} files created, {} overwritten, {} directories created",
+            self.count(|change| *change == PlannedChange::CreateFile),
+            self.count(|change| matches!(change, PlannedChange::Overwrite { .. })),
+            self.count(|change| *change == PlannedChange::CreateDir),
+        )
+    }
+}
+
+/// Simulate restoring `items` from an archive holding `members`: each
+/// entry under an item is placed at the item's restore path and checked
+/// against what is there now. Overwrites come first, as those are what
+/// to look at. Blocking.
+pub fn plan(members: &[IndexedMember], items: &[&RestoreItem]) -> RestorePlan {
+    let mut plan = RestorePlan::default();
+    for item in items {
+        let root = extract::member_name(&item.original_path);
+        for member in members {
+            let target = if member.path == root {
+                item.restore_path.clone()
+            } else {
+                match member.path.strip_prefix(&root).and_then(|rest| rest.strip_prefix('/')) {
+                    Some(rest) => item.restore_path.join(rest),
+                    None => continue,
+                }
+            };
+
+            let change = match (fs::symlink_metadata(&target), member.kind) {
+                (Ok(metadata), MemberKind::Dir) if metadata.is_dir() => {
+                    plan.existing_dirs += 1;
+                    continue;
+                }
+                (Ok(metadata), _) => PlannedChange::Overwrite { old_size: metadata.len(), old_mtime: metadata.mtime() },
+                (Err(_), MemberKind::Dir) => PlannedChange::CreateDir,
+                (Err(_), _) => PlannedChange::CreateFile,
+            };
+            plan.paths.push(PlannedPath { target, change, size: member.size, mtime: member.mtime });
+        }
+    }
+    plan.paths.sort_by_key(|path| !matches!(path.change, PlannedChange::Overwrite { .. }));
+    plan
+}
+
+fn format_time(mtime: i64) -> String {
+    chrono::DateTime::from_timestamp(mtime, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::member_index::MemberIndex;
+    use std::process::Command;
+
+    #[test]
+    fn test_plan_restore() {
+        let dir = std::env::temp_dir().join(format!("restore-plan-test-{}", std::process::id()));
+        let home = dir.join("home");
+        fs::create_dir_all(home.join(".config/app/themes")).unwrap();
+        fs::write(home.join(".config/app/settings.toml"), "theme = \"dark\"\n").unwrap();
+        fs::write(home.join(".config/app/themes/dark"), "bg = black\n").unwrap();
+        let status = Command::new("tar")
+            .arg("-cf").arg(dir.join("backup.tar"))
+            .arg("-C").arg(&home)
+            .arg(".config/app")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let members = MemberIndex::build(&dir.join("backup.tar")).unwrap().members;
+
+        fs::remove_dir_all(home.join(".config/app/themes")).unwrap();
+        fs::write(home.join(".config/app/settings.toml"), "theme = \"light\"\n").unwrap();
+        let item = RestoreItem {
+            name: ".config/app".to_string(),
+            original_path: PathBuf::from(".config/app"),
+            restore_path: home.join(".config/app"),
+            size: 0,
+            selected: true,
+            conflicts: true,
+            privileged_entries: Vec::new(),
+        };
+
+        let plan = plan(&members, &[&item]);
+        assert_eq!(plan.existing_dirs, 1);
+        assert_eq!(plan.summary(), "1 files created, 1 overwritten, 1 directories created");
+        assert_eq!(plan.paths[0].target, home.join(".config/app/settings.toml"));
+        assert!(matches!(plan.paths[0].change, PlannedChange::Overwrite { old_size: 16, .. }));
+        assert_eq!(plan.paths[0].size, 15);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
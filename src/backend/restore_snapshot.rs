@@ -0,0 +1,297 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+use crate::core::types::{RestoreItem, RestoreMetadata, TAR_METADATA_OPTIONS};
+
+/// Number of pre-restore snapshots kept; older ones are deleted
+pub const PRE_RESTORE_SNAPSHOT_LIMIT: usize = 5;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// One path a restore was about to write, and whether something was
+/// already there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub path: PathBuf,
+    pub existed: bool,
+}
+
+/// Copy of everything a restore overwrote, taken just before it ran.
+/// Rolling back puts the copied files back and removes paths the restore
+/// created, leaving the system as it was before the restore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreRestoreSnapshot {
+    pub taken: DateTime<Local>,
+    /// Name of the archive that was being restored
+    pub archive: String,
+    pub entries: Vec<SnapshotEntry>,
+    #[serde(skip)]
+    manifest_path: PathBuf,
+}
+
+impl PreRestoreSnapshot {
+    /// Default location under the user's data directory
+    pub fn default_dir() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("backup-manager")
+            .join("pre-restore")
+    }
+
+    /// Archive the current contents of every path `items` will write to
+    pub async fn create(dir: &Path, archive: &str, items: &[&RestoreItem]) -> Result<Self> {
+        create_private_dir(dir)?;
+
+        let taken = Local::now();
+        let stamp = taken.format(TIMESTAMP_FORMAT).to_string();
+        let entries: Vec<SnapshotEntry> = items
+            .iter()
+            .map(|item| SnapshotEntry {
+                path: item.restore_path.clone(),
+                existed: item.restore_path.symlink_metadata().is_ok(),
+            })
+            .collect();
+
+        let snapshot = Self {
+            taken,
+            archive: archive.to_string(),
+            entries,
+            manifest_path: dir.join(format!("pre-restore-{}.json", stamp)),
+        };
+
+        let existing: Vec<String> = snapshot.entries
+            .iter()
+            .filter(|entry| entry.existed)
+            .map(|entry| relative_to_root(&entry.path))
+            .collect();
+        if !existing.is_empty() {
+            let tarball = snapshot.tarball_path();
+            // Created up front so tar never writes the copies world-readable
+            private_file(&tarball)?;
+            run_tar(
+                TokioCommand::new("tar")
+                    .arg("-czf")
+                    .arg(&tarball)
+                    .args(TAR_METADATA_OPTIONS)
+                    .args(["-C", "/", "--"])
+                    .args(&existing),
+            )
+            .await
+            .context("Failed to snapshot files before restore")?;
+        }
+
+        let mut file = private_file(&snapshot.manifest_path)?;
+        serde_json::to_writer_pretty(&mut file, &snapshot)?;
+        info!(
+            "Pre-restore snapshot {} saved {} existing paths",
+            snapshot.manifest_path.display(),
+            existing.len()
+        );
+
+        if let Err(e) = prune(dir, PRE_RESTORE_SNAPSHOT_LIMIT) {
+            warn!("Failed to prune old pre-restore snapshots: {}", e);
+        }
+        Ok(snapshot)
+    }
+
+    /// Most recent snapshot in `dir`, if any
+    pub fn latest(dir: &Path) -> Result<Option<Self>> {
+        Ok(list(dir)?.into_iter().next())
+    }
+
+    /// Undo the restore this snapshot was taken for. The snapshot is
+    /// deleted afterwards so the same rollback can't be applied twice.
+    pub async fn rollback(&self) -> Result<()> {
+        self.rollback_reporting(|_| {}).await
+    }
+
+    /// `rollback`, calling `on_path` with each path once it is back to how
+    /// it was before the restore
+    pub async fn rollback_reporting(&self, mut on_path: impl FnMut(&Path)) -> Result<()> {
+        for entry in self.entries.iter().filter(|entry| !entry.existed) {
+            let removed = match entry.path.symlink_metadata() {
+                Ok(meta) if meta.is_dir() => fs::remove_dir_all(&entry.path),
+                Ok(_) => fs::remove_file(&entry.path),
+                // Already gone, which is how it was before
+                Err(_) => {
+                    on_path(&entry.path);
+                    continue;
+                }
+            };
+            removed.with_context(|| format!("Failed to remove restored {}", entry.path.display()))?;
+            debug!("Removed {} created by the restore", entry.path.display());
+            on_path(&entry.path);
+        }
+
+        let tarball = self.tarball_path();
+        if tarball.exists() {
+            // Replace whole directories so files the restore added to them go too
+            for entry in self.entries.iter().filter(|entry| entry.existed && entry.path.is_dir()) {
+                fs::remove_dir_all(&entry.path)
+                    .with_context(|| format!("Failed to clear {}", entry.path.display()))?;
+            }
+            run_tar(
+                TokioCommand::new("tar")
+                    .arg("-xzpf")
+                    .arg(&tarball)
+                    .args(RestoreMetadata::Apply.tar_options())
+                    .args(["-C", "/"]),
+            )
+            .await
+            .context("Failed to put back files from the pre-restore snapshot")?;
+        }
+        for entry in self.entries.iter().filter(|entry| entry.existed) {
+            on_path(&entry.path);
+        }
+
+        info!("Rolled back restore of {} from {}", self.archive, self.manifest_path.display());
+        self.remove()
+    }
+
+    /// Delete the snapshot files
+    pub fn remove(&self) -> Result<()> {
+        let tarball = self.tarball_path();
+        if tarball.exists() {
+            fs::remove_file(&tarball)
+                .with_context(|| format!("Failed to remove {}", tarball.display()))?;
+        }
+        fs::remove_file(&self.manifest_path)
+            .with_context(|| format!("Failed to remove {}", self.manifest_path.display()))
+    }
+
+    fn tarball_path(&self) -> PathBuf {
+        self.manifest_path.with_extension("tar.gz")
+    }
+}
+
+/// Snapshots in `dir`, newest first
+fn list(dir: &Path) -> Result<Vec<PreRestoreSnapshot>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+    {
+        let path = entry.path();
+        if path.extension().map(|e| e != "json").unwrap_or(true) {
+            continue;
+        }
+        let snapshot = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str::<PreRestoreSnapshot>(&content)?));
+        match snapshot {
+            Ok(mut snapshot) => {
+                snapshot.manifest_path = path;
+                snapshots.push(snapshot);
+            }
+            Err(e) => warn!("Skipping unreadable pre-restore snapshot {}: {}", path.display(), e),
+        }
+    }
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.taken));
+    Ok(snapshots)
+}
+
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    for snapshot in list(dir)?.into_iter().skip(keep) {
+        debug!("Removing old pre-restore snapshot {}", snapshot.manifest_path.display());
+        snapshot.remove()?;
+    }
+    Ok(())
+}
+
+async fn run_tar(command: &mut TokioCommand) -> Result<()> {
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run tar")?;
+    if !output.status.success() {
+        anyhow::bail!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Tar member for an absolute path archived with `-C /`
+fn relative_to_root(path: &Path) -> String {
+    path.to_string_lossy().trim_start_matches('/').to_string()
+}
+
+/// Snapshots hold copies of whatever was overwritten, credentials
+/// included, so the directory is owner-only
+fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+fn private_file(path: &Path) -> Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(restore_path: PathBuf) -> RestoreItem {
+        RestoreItem {
+            name: restore_path.file_name().unwrap().to_string_lossy().to_string(),
+            original_path: restore_path.clone(),
+            conflicts: restore_path.exists(),
+            restore_path,
+            size: 0,
+            selected: true,
+            privileged_entries: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_overwritten_and_removes_created() {
+        let dir = std::env::temp_dir().join(format!("pre-restore-test-{}", std::process::id()));
+        let target = dir.join("home");
+        fs::create_dir_all(&target).unwrap();
+        let existing = target.join("settings.json");
+        let created = target.join("new.json");
+        fs::write(&existing, "before").unwrap();
+
+        let items = [item(existing.clone()), item(created.clone())];
+        let refs: Vec<&RestoreItem> = items.iter().collect();
+        let snapshot_dir = dir.join("snapshots");
+        PreRestoreSnapshot::create(&snapshot_dir, "test.tar.gz", &refs).await.unwrap();
+
+        // The "restore"
+        fs::write(&existing, "after").unwrap();
+        fs::write(&created, "restored").unwrap();
+
+        let snapshot = PreRestoreSnapshot::latest(&snapshot_dir).unwrap().unwrap();
+        snapshot.rollback().await.unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+        assert!(PreRestoreSnapshot::latest(&snapshot_dir).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+use crate::backend::catalog::{archive_key, Catalog};
+use crate::backend::checksum::HashAlgorithm;
+use crate::backend::remote_restore::shell_quote;
+use crate::core::types::ArchiveInfo;
+
+/// What re-hashing one stored copy of an archive found
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScrubOutcome {
+    Intact,
+    /// No longer matches the checksum recorded when it was written
+    Corrupt,
+    /// Gone from where this machine put it
+    Missing,
+    /// Couldn't be read this time; not recorded against the archive
+    Unreadable(String),
+    /// Cataloged without a checksum, so there is nothing to compare with
+    NoChecksum,
+    /// On an rclone remote, which is only read when asked for
+    Remote,
+    /// Another machine's archive at a path this one can't see
+    Unreachable,
+}
+
+impl ScrubOutcome {
+    /// The copy was there to check and its data is lost
+    pub fn is_damaged(&self) -> bool {
+        matches!(self, ScrubOutcome::Corrupt | ScrubOutcome::Missing)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ScrubOutcome::Intact => "ok".to_string(),
+            ScrubOutcome::Corrupt => "CORRUPT: checksum mismatch".to_string(),
+            ScrubOutcome::Missing => "MISSING".to_string(),
+            ScrubOutcome::Unreadable(e) => format!("unreadable: {}", e),
+            ScrubOutcome::NoChecksum => "skipped: no checksum recorded".to_string(),
+            ScrubOutcome::Remote => "skipped: on a remote (use --remote)".to_string(),
+            ScrubOutcome::Unreachable => "skipped: not reachable from this machine".to_string(),
+        }
+    }
+}
+
+/// One stored copy and what scrubbing it found
+#[derive(Debug, Clone)]
+pub struct ScrubResult {
+    pub archive: ArchiveInfo,
+    pub outcome: ScrubOutcome,
+    /// For a damaged copy, a copy of the same archive at another
+    /// destination that was found intact
+    pub mirror: Option<ArchiveInfo>,
+}
+
+impl ScrubResult {
+    /// Shell command that puts the intact mirror in place of this copy
+    pub fn recovery_command(&self) -> Option<String> {
+        let mirror = self.mirror.as_ref()?;
+        let (from, to) = (shell_quote(&archive_key(mirror)), shell_quote(&archive_key(&self.archive)));
+        Some(match (&mirror.remote, &self.archive.remote) {
+            (None, None) => format!("cp --preserve=mode {} {}", from, to),
+            _ => format!("rclone copyto {} {}", from, to),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScrubOptions {
+    /// Read archives on rclone remotes back as well, downloading each
+    pub include_remote: bool,
+    /// Leave copies found intact more recently than this for a later run;
+    /// None checks every copy
+    pub interval: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+pub struct ScrubReport {
+    pub results: Vec<ScrubResult>,
+    /// Copies found intact within the interval
+    pub not_due: usize,
+}
+
+impl ScrubReport {
+    pub fn damaged(&self) -> impl Iterator<Item = &ScrubResult> {
+        self.results.iter().filter(|result| result.outcome.is_damaged())
+    }
+}
+
+/// Re-hash each cataloged copy that is due and compare it with the
+/// checksum recorded when it was written, recording the outcome in the
+/// catalog. Each damaged copy is matched with an intact one of the same
+/// archive elsewhere, checked now if this run hadn't already. `on_archive`
+/// gets each copy before it is read. Blocking.
+pub fn scrub(
+    catalog: &Catalog,
+    hostname: &str,
+    options: &ScrubOptions,
+    now: DateTime<Utc>,
+    mut on_archive: impl FnMut(&ArchiveInfo, usize, usize),
+) -> Result<ScrubReport> {
+    let archives = catalog.list_archives()?;
+    let mut report = ScrubReport::default();
+    let mut due = Vec::new();
+    for archive in &archives {
+        // A damaged copy is checked every run until it is replaced
+        let recent = match (options.interval, catalog.last_verification(archive)?) {
+            (Some(interval), Some((verified, true))) => now - verified < interval,
+            _ => false,
+        };
+        if recent {
+            report.not_due += 1;
+        } else {
+            due.push(archive);
+        }
+    }
+
+    for (i, archive) in due.iter().enumerate() {
+        on_archive(archive, i, due.len());
+        let outcome = check_copy(catalog, archive, hostname, options.include_remote)?;
+        report.results.push(ScrubResult { archive: (*archive).clone(), outcome, mirror: None });
+    }
+
+    let mut checked: HashMap<String, ScrubOutcome> = report
+        .results
+        .iter()
+        .map(|result| (archive_key(&result.archive), result.outcome.clone()))
+        .collect();
+    for result in report.results.iter_mut().filter(|result| result.outcome.is_damaged()) {
+        let Some(expected) = catalog.checksum(&result.archive)? else {
+            continue;
+        };
+        let key = archive_key(&result.archive);
+        for candidate in archives.iter().filter(|candidate| archive_key(candidate) != key) {
+            if catalog.checksum(candidate)?.as_deref() != Some(expected.as_str()) {
+                continue;
+            }
+            let candidate_key = archive_key(candidate);
+            let outcome = match checked.get(&candidate_key) {
+                Some(outcome) => outcome.clone(),
+                // Worth reading a remote for: it may be the only good copy
+                None => check_copy(catalog, candidate, hostname, true)?,
+            };
+            checked.insert(candidate_key, outcome.clone());
+            if outcome == ScrubOutcome::Intact {
+                result.mirror = Some(candidate.clone());
+                break;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Re-hash one copy, recording an intact or damaged result
+fn check_copy(catalog: &Catalog, archive: &ArchiveInfo, hostname: &str, include_remote: bool) -> Result<ScrubOutcome> {
+    let Some(expected) = catalog.checksum(archive)? else {
+        return Ok(ScrubOutcome::NoChecksum);
+    };
+    let algorithm = HashAlgorithm::of(&expected);
+    let hashed = match &archive.remote {
+        Some(_) if !include_remote => return Ok(ScrubOutcome::Remote),
+        Some(remote) => hash_remote(remote, algorithm),
+        None if !archive.path.exists() => {
+            if archive.hostname.as_deref().is_some_and(|host| host != hostname) {
+                return Ok(ScrubOutcome::Unreachable);
+            }
+            error!("{} is missing", archive.path.display());
+            catalog.record_verification(&archive_key(archive), false)?;
+            return Ok(ScrubOutcome::Missing);
+        }
+        None => algorithm.hash_file(&archive.path),
+    };
+
+    let outcome = match hashed {
+        Ok(actual) if actual == expected => ScrubOutcome::Intact,
+        Ok(_) => ScrubOutcome::Corrupt,
+        Err(e) => {
+            warn!("Could not read {}: {:#}", archive_key(archive), e);
+            return Ok(ScrubOutcome::Unreadable(format!("{:#}", e)));
+        }
+    };
+    match outcome {
+        ScrubOutcome::Intact => info!("{} is intact", archive_key(archive)),
+        _ => error!("{} does not match its recorded checksum", archive_key(archive)),
+    }
+    catalog.record_verification(&archive_key(archive), outcome == ScrubOutcome::Intact)?;
+    Ok(outcome)
+}
+
+/// Checksum of an archive on a remote, streamed through `rclone cat` so
+/// nothing is written to disk
+fn hash_remote(remote: &str, algorithm: HashAlgorithm) -> Result<String> {
+    let mut child = Command::new("rclone")
+        .arg("cat")
+        .arg(remote)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start rclone (is it installed and on PATH?)")?;
+    let stdout = child.stdout.take().context("Failed to read rclone output")?;
+    let hashed = algorithm.hash_reader(stdout);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("rclone cat failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    hashed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::catalog::ArchiveIndex;
+    use crate::core::types::BackupMode;
+    use std::fs;
+    use std::path::Path;
+
+    fn archive(path: &Path, hostname: &str) -> ArchiveInfo {
+        ArchiveInfo {
+            path: path.to_path_buf(),
+            name: "a.tar.gz".to_string(),
+            created: Utc::now(),
+            size: 4,
+            mode: BackupMode::Secure,
+            encrypted: false,
+            description: String::new(),
+            items: Vec::new(),
+            remote: None,
+            hostname: Some(hostname.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_scrub_finds_corruption_and_an_intact_mirror() {
+        let root = std::env::temp_dir().join(format!("scrub-test-{}", std::process::id()));
+        for dir in ["ssd", "usb"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("a.tar.gz"), b"data").unwrap();
+        }
+        let (ssd, usb) = (root.join("ssd/a.tar.gz"), root.join("usb/a.tar.gz"));
+        let index = ArchiveIndex {
+            checksum: HashAlgorithm::Sha256.hash_file(&ssd).unwrap(),
+            size: 4,
+            items: Vec::new(),
+            texts: Vec::new(),
+        };
+
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        for path in [&ssd, &usb] {
+            catalog.record_archive(&archive(path, "laptop"), Some(&index)).unwrap();
+        }
+        catalog.record_archive(&archive(Path::new("/elsewhere/b.tar.gz"), "desktop"), Some(&index)).unwrap();
+        // A bit flipped on the SSD since the backup
+        fs::write(&ssd, b"dat4").unwrap();
+
+        let options = ScrubOptions { include_remote: false, interval: Some(Duration::days(30)) };
+        let report = scrub(&catalog, "laptop", &options, Utc::now(), |_, _, _| {}).unwrap();
+        let outcome = |path: &Path| report.results.iter().find(|r| r.archive.path == path).unwrap().outcome.clone();
+        assert_eq!(outcome(&ssd), ScrubOutcome::Corrupt);
+        assert_eq!(outcome(&usb), ScrubOutcome::Intact);
+        assert_eq!(outcome(Path::new("/elsewhere/b.tar.gz")), ScrubOutcome::Unreachable);
+
+        let damaged: Vec<&ScrubResult> = report.damaged().collect();
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(damaged[0].mirror.as_ref().map(|m| m.path.clone()), Some(usb.clone()));
+        assert_eq!(
+            damaged[0].recovery_command().unwrap(),
+            format!("cp --preserve=mode '{}' '{}'", usb.display(), ssd.display())
+        );
+
+        // Only the mirror was found intact, so it is the one not due again
+        let again = scrub(&catalog, "laptop", &options, Utc::now(), |_, _, _| {}).unwrap();
+        assert_eq!((again.results.len(), again.not_due), (2, 1));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
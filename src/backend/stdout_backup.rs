@@ -0,0 +1,334 @@
+use anyhow::{Context, Result};
+use log::warn;
+use sha2::{Digest, Sha256};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+
+use crate::backend::thread_usage::CompressorThreads;
+use crate::core::security::SecurePassword;
+use crate::core::types::{Compression, TAR_METADATA_OPTIONS};
+
+/// Where the wrapper script puts the collected system state in an archive
+const SYSTEM_STATE_MEMBER: &str = ".backup-system-state";
+
+/// What goes into a streamed archive
+pub struct StreamSource<'a> {
+    pub home: &'a Path,
+    /// NUL-separated home-relative paths, as `FileList` writes them
+    pub file_list: &'a Path,
+    /// `FileList::transforms`, naming followed links' targets
+    pub transforms: &'a [String],
+    pub system_state: Option<&'a Path>,
+    /// When to stop adding items, for a backup held to a time window
+    pub cutoff: Option<Cutoff<'a>>,
+}
+
+/// Stop adding to the archive once `at` has passed. Items end where the
+/// list moves from one of `roots` to the next, so every item archived is
+/// archived whole.
+pub struct Cutoff<'a> {
+    pub at: Instant,
+    /// Top-level home-relative roots, in the order the list was written
+    pub roots: &'a [PathBuf],
+}
+
+/// What was written
+#[derive(Debug)]
+pub struct StreamedArchive {
+    /// Files and directories added
+    pub entries: usize,
+    pub bytes: u64,
+    /// SHA-256 of the bytes written, to check the copy at the other end
+    pub sha256: String,
+    /// Roots of the cutoff never started because time ran out, in order
+    pub unstarted: Vec<PathBuf>,
+}
+
+/// Write an archive of `source` to `out` while it is created: tar and the
+/// compressor, then gpg when there is a password. Nothing is staged on
+/// disk. `on_file` gets each path as tar adds it and `on_bytes` the total
+/// written so far. Blocking.
+#[allow(clippy::too_many_arguments)]
+pub fn stream_archive(
+    source: &StreamSource,
+    compression: Compression,
+    level: u32,
+    threads: u32,
+    password: Option<&SecurePassword>,
+    out: &mut impl Write,
+    mut on_file: impl FnMut(&str, usize),
+    mut on_bytes: impl FnMut(u64),
+) -> Result<StreamedArchive> {
+    let mut tar = Command::new("tar");
+    // With the archive on stdout, tar's file listing goes to stderr
+    let program = compression.program(level, threads);
+    tar.arg("-I").arg(&program);
+    tar.args(["-cvf", "-", "--ignore-failed-read", "--show-transformed-names"]).args(TAR_METADATA_OPTIONS);
+    for transform in source.transforms {
+        tar.arg("--transform").arg(transform);
+    }
+    tar.arg("-C").arg(source.home);
+    // With a cutoff the list is fed over stdin, so it can end early
+    tar.args(["--null", "--no-recursion", "-T"]);
+    if source.cutoff.is_some() {
+        tar.arg("-");
+    } else {
+        tar.arg(source.file_list);
+    }
+    if let Some((parent, name)) = source.system_state.and_then(|dir| dir.parent().zip(dir.file_name())) {
+        let name = name.to_string_lossy();
+        tar.args(["--recursion", "--transform"])
+            .arg(format!("s,^{},{},", name, SYSTEM_STATE_MEMBER))
+            .arg("-C")
+            .arg(parent)
+            .arg(name.as_ref());
+    }
+    let mut tar = tar
+        .stdin(if source.cutoff.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run tar")?;
+    let _compressor_threads = program
+        .split_whitespace()
+        .next()
+        .and_then(|compressor| CompressorThreads::watch(tar.id(), compressor));
+    let tar_out = tar.stdout.take().context("Failed to read tar output")?;
+    let tar_err = tar.stderr.take().context("Failed to read tar errors")?;
+
+    // Every name tar is given gets at least one line: listed, or an error
+    let reported = Arc::new(AtomicUsize::new(0));
+    let tar_done = Arc::new(AtomicBool::new(false));
+    let (lines_tx, lines) = mpsc::channel();
+    let listing = {
+        let (reported, tar_done) = (reported.clone(), tar_done.clone());
+        thread::spawn(move || {
+            for line in BufReader::new(tar_err).lines().map_while(|line| line.ok()) {
+                reported.fetch_add(1, Ordering::Relaxed);
+                if lines_tx.send(line).is_err() {
+                    break;
+                }
+            }
+            tar_done.store(true, Ordering::Relaxed);
+        })
+    };
+
+    let feeding = match &source.cutoff {
+        Some(cutoff) => {
+            let list = std::fs::read(source.file_list)
+                .with_context(|| format!("Failed to read {}", source.file_list.display()))?;
+            let stdin = tar.stdin.take().context("Failed to open tar stdin")?;
+            let (at, roots) = (cutoff.at, cutoff.roots.to_vec());
+            Some(thread::spawn(move || feed_until(stdin, &list, &roots, at, &reported, &tar_done)))
+        }
+        None => None,
+    };
+
+    let (mut archive, mut gpg): (Box<dyn Read>, Option<Encrypting>) = match password {
+        Some(password) => {
+            let (output, encrypting) = spawn_encrypt(password, tar_out)?;
+            (Box::new(output), Some(encrypting))
+        }
+        None => (Box::new(tar_out), None),
+    };
+
+    let mut entries = 0;
+    let mut errors = Vec::new();
+    let mut handle_line = |line: String| {
+        if line.starts_with("tar: Removing leading") {
+            // Followed links' targets are listed by absolute path
+        } else if line.starts_with("tar: ") {
+            warn!("{}", line);
+            errors.push(line);
+        } else {
+            entries += 1;
+            on_file(line.trim_end_matches('/'), entries);
+        }
+    };
+
+    let mut hasher = Sha256::new();
+    let mut bytes = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    let copied = (|| -> Result<()> {
+        loop {
+            let n = archive.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            out.write_all(&buf[..n]).context("The receiving end stopped reading the archive")?;
+            hasher.update(&buf[..n]);
+            bytes += n as u64;
+            on_bytes(bytes);
+            lines.try_iter().for_each(&mut handle_line);
+        }
+        out.flush()?;
+        Ok(())
+    })();
+    drop(archive);
+
+    if let Err(e) = copied {
+        let _ = tar.kill();
+        let _ = tar.wait();
+        if let Some(mut gpg) = gpg.take() {
+            let _ = gpg.child.kill();
+            let _ = gpg.child.wait();
+        }
+        return Err(e);
+    }
+
+    let status = tar.wait()?;
+    let _ = listing.join();
+    let unstarted = match feeding {
+        Some(feeding) => feeding
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("list feeder panicked")))
+            .context("Failed to pass the file list to tar")?,
+        None => Vec::new(),
+    };
+    lines.try_iter().for_each(&mut handle_line);
+    // 1 is files that changed while being read, as in the wrapper script
+    if !matches!(status.code(), Some(0 | 1)) {
+        anyhow::bail!("tar failed: {}", errors.last().map(String::as_str).unwrap_or("no error reported"));
+    }
+    if let Some(mut gpg) = gpg {
+        let encrypted = gpg.child.wait()?;
+        let fed = gpg.feeder.join().unwrap_or_else(|_| Err(io::Error::other("feeder panicked")));
+        if !encrypted.success() {
+            anyhow::bail!("gpg failed to encrypt the archive");
+        }
+        fed.context("Failed to pass the archive to gpg")?;
+    }
+
+    Ok(StreamedArchive {
+        entries,
+        bytes,
+        sha256: hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect(),
+        unstarted,
+    })
+}
+
+/// Pass the NUL-separated `list` to tar one root at a time. Before each
+/// new root, wait until tar has reported every name given so far, so the
+/// clock is read at a real item boundary rather than when the pipe had
+/// room, and stop once `at` has passed. Returns the roots not started.
+fn feed_until(
+    mut stdin: ChildStdin,
+    list: &[u8],
+    roots: &[PathBuf],
+    at: Instant,
+    reported: &AtomicUsize,
+    tar_done: &AtomicBool,
+) -> io::Result<Vec<PathBuf>> {
+    // Names are compared as bytes, as the list holds them
+    let within = |name: &[u8], root: &PathBuf| {
+        let root = root.as_os_str().as_encoded_bytes();
+        name.starts_with(root) && (name.len() == root.len() || name[root.len()] == b'/')
+    };
+    let mut current = None;
+    for (fed, name) in list.split(|&b| b == 0).filter(|name| !name.is_empty()).enumerate() {
+        // Followed links' targets, listed by absolute path, belong to the
+        // item before them
+        let root = roots.iter().position(|root| within(name, root)).or(current);
+        if let Some(started) = current.filter(|&started| root != Some(started)) {
+            stdin.flush()?;
+            while reported.load(Ordering::Relaxed) < fed && !tar_done.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(20));
+            }
+            if Instant::now() >= at {
+                return Ok(roots[started + 1..].to_vec());
+            }
+        }
+        current = root;
+        stdin.write_all(name)?;
+        stdin.write_all(b"\0")?;
+    }
+    Ok(Vec::new())
+}
+
+/// gpg encrypting the archive, and the thread feeding it
+struct Encrypting {
+    child: Child,
+    feeder: thread::JoinHandle<io::Result<()>>,
+}
+
+/// gpg encrypting what `input` produces with `password`, which goes over
+/// its stdin ahead of the data, never argv or the environment
+fn spawn_encrypt(password: &SecurePassword, mut input: impl Read + Send + 'static) -> Result<(impl Read, Encrypting)> {
+    let mut child = Command::new("gpg")
+        .args(["--batch", "--quiet", "--pinentry-mode", "loopback", "--passphrase-fd", "0"])
+        .args(["--symmetric", "--cipher-algo", "AES256", "--output", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run gpg")?;
+    let mut stdin = child.stdin.take().context("Failed to open gpg stdin")?;
+    let output = child.stdout.take().context("Failed to read gpg output")?;
+
+    let mut passphrase = password.as_bytes().to_vec();
+    passphrase.push(b'\n');
+    let feeder = thread::spawn(move || {
+        let written = stdin.write_all(&passphrase);
+        passphrase.zeroize();
+        written?;
+        io::copy(&mut input, &mut stdin)?;
+        Ok(())
+    });
+    Ok((output, Encrypting { child, feeder }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_stream_archive_of_listed_files() {
+        let home = std::env::temp_dir().join(format!("stdout-backup-test-{}", std::process::id()));
+        fs::create_dir_all(home.join(".config/app")).unwrap();
+        fs::write(home.join(".bashrc"), b"alias ll='ls -l'").unwrap();
+        fs::write(home.join(".config/app/settings.ini"), b"theme=dark").unwrap();
+        fs::write(home.join("not-listed"), b"private").unwrap();
+        let list = home.join("list");
+        fs::write(&list, b".bashrc\0.config\0.config/app\0.config/app/settings.ini\0").unwrap();
+
+        let source = StreamSource { home: &home, file_list: &list, transforms: &[], system_state: None, cutoff: None };
+        let mut archive = Vec::new();
+        let mut last_bytes = 0;
+        let streamed =
+            stream_archive(&source, Compression::Gzip, 6, 0, None, &mut archive, |_, _| {}, |bytes| last_bytes = bytes).unwrap();
+
+        assert_eq!(streamed.entries, 4);
+        assert_eq!((streamed.bytes, last_bytes), (archive.len() as u64, archive.len() as u64));
+        let expected: String = Sha256::digest(&archive).iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(streamed.sha256, expected);
+
+        fs::write(home.join("a.tar.gz"), &archive).unwrap();
+        let listing = Command::new("tar").arg("-tzf").arg(home.join("a.tar.gz")).output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&listing.stdout).lines().collect::<Vec<_>>(),
+            [".bashrc", ".config/", ".config/app/", ".config/app/settings.ini"]
+        );
+
+        // Out of time: the item being archived is finished, the next never started
+        let roots = [PathBuf::from(".bashrc"), PathBuf::from(".config")];
+        let source = StreamSource {
+            home: &home,
+            file_list: &list,
+            transforms: &[],
+            system_state: None,
+            cutoff: Some(Cutoff { at: Instant::now(), roots: &roots }),
+        };
+        let mut archive = Vec::new();
+        let streamed = stream_archive(&source, Compression::Gzip, 6, 0, None, &mut archive, |_, _| {}, |_| {}).unwrap();
+        assert_eq!((streamed.entries, streamed.unstarted), (1, vec![PathBuf::from(".config")]));
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
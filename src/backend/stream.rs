@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::process::{ExitStatus, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command as TokioCommand;
+
+/// Lines of stderr kept for error messages; the rest is only logged
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Result of a streamed command: its exit status and the last few lines it
+/// wrote to stderr
+pub struct Streamed {
+    pub status: ExitStatus,
+    pub stderr_tail: String,
+}
+
+impl Streamed {
+    /// Turn a failed exit into an error naming what was being done
+    pub fn check(self, action: &str) -> Result<()> {
+        if self.status.success() {
+            return Ok(());
+        }
+        anyhow::bail!("{}: {}", action, self.stderr_tail.trim())
+    }
+}
+
+/// Run `command`, handing each stdout line to `on_line` as it arrives
+/// instead of collecting the whole output. Memory stays flat however much
+/// the command prints: only the current line and a short stderr tail are
+/// held. stderr is drained concurrently so a chatty child can never block
+/// on a full pipe.
+pub async fn for_each_line<F>(command: &mut TokioCommand, mut on_line: F) -> Result<Streamed>
+where
+    F: FnMut(&str),
+{
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start process")?;
+
+    let stderr = child.stderr.take().context("Failed to read process errors")?;
+    let stderr_task = tokio::spawn(tail_lines(stderr, STDERR_TAIL_LINES));
+
+    let stdout = child.stdout.take().context("Failed to read process output")?;
+    let mut reader = BufReader::new(stdout);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        on_line(text.trim_end_matches(['\n', '\r']));
+    }
+
+    let status = child.wait().await?;
+    let stderr_tail = stderr_task.await.unwrap_or_default();
+    Ok(Streamed { status, stderr_tail })
+}
+
+/// Read a stream to the end, logging every line and keeping only the last
+/// `keep` of them
+pub async fn tail_lines<R>(stream: R, keep: usize) -> String
+where
+    R: AsyncRead + Unpin,
+{
+    tail_lines_except(stream, keep, |_| false).await
+}
+
+/// [`tail_lines`], handing each line to `take` first; lines it returns
+/// true for, such as progress reports, are neither logged nor kept
+pub async fn tail_lines_except<R>(stream: R, keep: usize, mut take: impl FnMut(&str) -> bool) -> String
+where
+    R: AsyncRead + Unpin,
+{
+    let mut tail = VecDeque::with_capacity(keep);
+    let mut reader = BufReader::new(stream);
+    // Raw bytes, since tar names files by theirs; stopping at a line
+    // that isn't UTF-8 would leave the writer blocked or killed by SIGPIPE
+    let mut bytes = Vec::new();
+    while reader.read_until(b'\n', &mut bytes).await.is_ok_and(|read| read > 0) {
+        let line = String::from_utf8_lossy(&bytes).trim_end_matches(['\n', '\r']).to_string();
+        bytes.clear();
+        if take(&line) {
+            continue;
+        }
+        log::debug!("stderr: {}", line);
+        if tail.len() == keep {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+    Vec::from(tail).join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lines_that_are_not_utf8_are_read_through() {
+        // A name that isn't UTF-8 on both streams, with plenty after it
+        let script = "for i in $(seq 1 5000); do printf 'tar: caf\\351/%s: Cannot open\\n' $i; printf 'caf\\351/%s\\n' $i >&2; done";
+        let mut command = TokioCommand::new("sh");
+        command.args(["-c", script]);
+        let mut stdout_lines = 0;
+        let mut last = String::new();
+        let streamed = for_each_line(&mut command, |line| {
+            stdout_lines += 1;
+            last = line.to_string();
+        })
+        .await
+        .unwrap();
+
+        assert!(streamed.status.success());
+        assert_eq!(stdout_lines, 5000);
+        assert_eq!(last, "tar: caf\u{fffd}/5000: Cannot open");
+        assert_eq!(streamed.stderr_tail.lines().count(), STDERR_TAIL_LINES);
+        assert!(streamed.stderr_tail.ends_with("caf\u{fffd}/5000"));
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Local};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::backend::catalog::local_hostname;
+use crate::backend::packages::PackageManifest;
+use crate::backend::remote_restore::shell_quote;
+use crate::backend::system_state::EnabledServices;
+
+/// What the machine looked like, under the system state directory
+pub const SNAPSHOT_FILE: &str = "system-snapshot.json";
+/// Script rebuilding the machine from the snapshot, under the system
+/// state directory
+pub const BOOTSTRAP_FILE: &str = "bootstrap.sh";
+
+/// Kernel parameters naming this machine's disks and boot files, which
+/// mean nothing on another one
+const MACHINE_PARAMS: &[&str] = &["BOOT_IMAGE", "initrd", "root", "rootflags", "resume", "resume_offset", "cryptdevice", "rd.luks.uuid", "rd.luks.name"];
+
+/// System configuration a backup records so the machine can be rebuilt:
+/// what runs, what's installed and how the kernel and mounts are set up
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    pub hostname: String,
+    pub captured: Option<DateTime<Local>>,
+    /// `PRETTY_NAME` from os-release
+    pub os: Option<String>,
+    pub kernel_cmdline: Option<String>,
+    /// Settings from sysctl.conf and sysctl.d, later files winning
+    pub sysctl: BTreeMap<String, String>,
+    /// Modules loaded at boot through modules-load.d
+    pub modules: Vec<String>,
+    /// fstab entries with any inline passwords removed
+    pub fstab: Vec<String>,
+    pub services: EnabledServices,
+    /// Present when the backup listed packages
+    pub packages: Option<PackageManifest>,
+}
+
+impl SystemSnapshot {
+    /// Read the machine's configuration. Files that are missing or
+    /// unreadable are left out of the snapshot.
+    pub fn capture(services: EnabledServices, packages: Option<PackageManifest>) -> Self {
+        Self::capture_from(Path::new("/"), services, packages)
+    }
+
+    fn capture_from(root: &Path, services: EnabledServices, packages: Option<PackageManifest>) -> Self {
+        let read = |path: &str| fs::read_to_string(root.join(path)).ok();
+
+        let os = read("etc/os-release").and_then(|release| {
+            release
+                .lines()
+                .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+                .map(|name| name.trim_matches('"').to_string())
+        });
+
+        let mut sysctl = BTreeMap::new();
+        for content in std::iter::once(read("etc/sysctl.conf")).chain(conf_files(root, "etc/sysctl.d").into_iter().map(Some)).flatten() {
+            for line in config_lines(&content) {
+                if let Some((key, value)) = line.split_once('=') {
+                    sysctl.insert(key.trim().trim_start_matches('-').to_string(), value.trim().to_string());
+                }
+            }
+        }
+
+        let mut modules: Vec<String> = conf_files(root, "etc/modules-load.d")
+            .iter()
+            .flat_map(|content| config_lines(content).map(str::to_string).collect::<Vec<_>>())
+            .collect();
+        modules.sort();
+        modules.dedup();
+
+        let fstab = read("etc/fstab")
+            .map(|content| config_lines(&content).map(redact_mount_options).collect())
+            .unwrap_or_default();
+
+        let snapshot = Self {
+            hostname: local_hostname(),
+            captured: Some(Local::now()),
+            os,
+            kernel_cmdline: read("proc/cmdline").map(|cmdline| cmdline.trim().to_string()),
+            sysctl,
+            modules,
+            fstab,
+            services,
+            packages,
+        };
+        debug!(
+            "System snapshot: {} sysctl settings, {} boot modules, {} mounts",
+            snapshot.sysctl.len(),
+            snapshot.modules.len(),
+            snapshot.fstab.len()
+        );
+        snapshot
+    }
+
+    /// Kernel parameters worth carrying to another machine
+    pub fn portable_kernel_params(&self) -> Vec<&str> {
+        self.kernel_cmdline
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter(|param| !MACHINE_PARAMS.contains(&param.split('=').next().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Shell script redoing what the snapshot recorded on a fresh install:
+    /// packages first so the units they ship can be enabled, then kernel
+    /// settings, then services. A step that fails is reported and the rest
+    /// carry on. Mounts and boot parameters depend on the new disks and
+    /// bootloader, so they are listed for doing by hand.
+    pub fn bootstrap_script(&self) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        let captured = self.captured.map(|at| at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+        let _ = writeln!(script, "# Rebuild of {} ({}) as backed up on {}", self.hostname, self.os.as_deref().unwrap_or("unknown system"), captured);
+        let _ = writeln!(script, "# Read it through before running it on a fresh install: sh {}", BOOTSTRAP_FILE);
+        let _ = writeln!(script);
+        let _ = writeln!(script, "step() {{ printf '\\n==> %s\\n' \"$1\"; }}");
+        let _ = writeln!(script, "failed() {{ echo \"failed: $1\" >&2; }}");
+
+        let _ = writeln!(script, "\nstep \"Packages\"");
+        match &self.packages {
+            Some(manifest) if !manifest.packages.is_empty() => {
+                for (manager, packages) in &manifest.packages {
+                    let _ = writeln!(script, "{} || failed {}", manager.install_command(packages), manager.as_str());
+                }
+            }
+            _ => {
+                let _ = writeln!(script, "# This backup didn't list packages; select the System packages item to include them");
+            }
+        }
+
+        if !self.sysctl.is_empty() {
+            let _ = writeln!(script, "\nstep \"Kernel settings\"");
+            let _ = writeln!(script, "sudo tee /etc/sysctl.d/90-restored.conf >/dev/null <<'SYSCTL'");
+            for (key, value) in &self.sysctl {
+                let _ = writeln!(script, "{} = {}", key, value);
+            }
+            let _ = writeln!(script, "SYSCTL");
+            let _ = writeln!(script, "sudo sysctl --system >/dev/null || failed sysctl");
+        }
+        if !self.modules.is_empty() {
+            let _ = writeln!(script, "\nstep \"Boot modules\"");
+            let _ = writeln!(script, "sudo tee /etc/modules-load.d/restored.conf >/dev/null <<'MODULES'");
+            for module in &self.modules {
+                let _ = writeln!(script, "{}", module);
+            }
+            let _ = writeln!(script, "MODULES");
+        }
+
+        if !self.services.system.is_empty() || !self.services.user.is_empty() {
+            let _ = writeln!(script, "\nstep \"Services\"");
+            for unit in &self.services.system {
+                let _ = writeln!(script, "sudo systemctl enable {} || failed {}", shell_quote(unit), shell_quote(unit));
+            }
+            for unit in &self.services.user {
+                let _ = writeln!(script, "systemctl --user enable {} || failed {}", shell_quote(unit), shell_quote(unit));
+            }
+        }
+
+        let params = self.portable_kernel_params();
+        if !params.is_empty() || !self.fstab.is_empty() {
+            let _ = writeln!(script, "\nstep \"Left to do by hand\"");
+            let _ = writeln!(script, "cat <<'MANUAL'");
+            if !params.is_empty() {
+                let _ = writeln!(script, "Kernel parameters to add to the bootloader entry:\n  {}", params.join(" "));
+            }
+            if !self.fstab.is_empty() {
+                let _ = writeln!(script, "Mounts from the old /etc/fstab; disk UUIDs differ, so add the ones still wanted:");
+                for entry in &self.fstab {
+                    let _ = writeln!(script, "  {}", entry);
+                }
+            }
+            let _ = writeln!(script, "MANUAL");
+        }
+        script
+    }
+}
+
+/// Contents of the `*.conf` files in `dir` under `root`, by file name
+fn conf_files(root: &Path, dir: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root.join(dir)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+        .collect();
+    paths.sort();
+    paths.iter().filter_map(|path| fs::read_to_string(path).ok()).collect()
+}
+
+/// Lines that aren't blank or comments
+fn config_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+}
+
+/// An fstab entry with the value of any `password=`/`pass=` option
+/// replaced, as network mounts sometimes carry one inline
+fn redact_mount_options(entry: &str) -> String {
+    let mut fields: Vec<String> = entry.split_whitespace().map(str::to_string).collect();
+    if let Some(options) = fields.get_mut(3) {
+        *options = options
+            .split(',')
+            .map(|option| match option.split_once('=') {
+                Some((key, _)) if key == "password" || key == "pass" => format!("{}=REDACTED", key),
+                _ => option.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+    }
+    fields.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::packages::PackageManager;
+
+    #[test]
+    fn test_capture_and_bootstrap_script() {
+        let root = std::env::temp_dir().join(format!("system-snapshot-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["etc/sysctl.d", "etc/modules-load.d", "proc"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("etc/os-release"), "NAME=\"Arch Linux\"\nPRETTY_NAME=\"Arch Linux\"\n").unwrap();
+        fs::write(root.join("proc/cmdline"), "BOOT_IMAGE=/vmlinuz-linux root=UUID=1234 rw quiet mitigations=off\n").unwrap();
+        fs::write(root.join("etc/sysctl.conf"), "# defaults\nvm.swappiness = 60\n").unwrap();
+        fs::write(root.join("etc/sysctl.d/99-local.conf"), "vm.swappiness=10\n-net.ipv4.ip_forward = 1\n").unwrap();
+        fs::write(root.join("etc/sysctl.d/README"), "vm.ignored = 1\n").unwrap();
+        fs::write(root.join("etc/modules-load.d/v4l2.conf"), "; loopback camera\nv4l2loopback\n").unwrap();
+        fs::write(
+            root.join("etc/fstab"),
+            "# /dev/nvme0n1p2\nUUID=1234 / ext4 rw 0 1\n//nas/share /mnt/nas cifs username=me,password=hunter2,uid=1000 0 0\n",
+        )
+        .unwrap();
+
+        let services = EnabledServices { user: vec!["syncthing.service".to_string()], system: vec!["sshd.service".to_string()] };
+        let mut packages = PackageManifest::default();
+        packages.packages.insert(PackageManager::Pacman, vec!["base".to_string()]);
+        let snapshot = SystemSnapshot::capture_from(&root, services, Some(packages));
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(snapshot.os.as_deref(), Some("Arch Linux"));
+        assert_eq!(snapshot.sysctl.get("vm.swappiness").map(String::as_str), Some("10"));
+        assert_eq!(snapshot.sysctl.get("net.ipv4.ip_forward").map(String::as_str), Some("1"));
+        assert!(!snapshot.sysctl.contains_key("vm.ignored"));
+        assert_eq!(snapshot.modules, ["v4l2loopback"]);
+        assert_eq!(snapshot.fstab[1], "//nas/share /mnt/nas cifs username=me,password=REDACTED,uid=1000 0 0");
+        assert_eq!(snapshot.portable_kernel_params(), ["rw", "quiet", "mitigations=off"]);
+
+        let script = snapshot.bootstrap_script();
+        assert!(script.contains("\nsudo pacman -S --needed 'base' || failed pacman\n"), "{}", script);
+        assert!(script.contains("\nnet.ipv4.ip_forward = 1\nvm.swappiness = 10\nSYSCTL\n"), "{}", script);
+        assert!(script.contains("\nsudo systemctl enable 'sshd.service' || failed 'sshd.service'\n"));
+        assert!(script.contains("\nsystemctl --user enable 'syncthing.service' || failed 'syncthing.service'\n"));
+        assert!(script.contains("\n  rw quiet mitigations=off\n"));
+        assert!(!script.contains("hunter2"));
+        // Packages come before the services they ship
+        assert!(script.find("step \"Packages\"").unwrap() < script.find("step \"Services\"").unwrap());
+    }
+}
@@ -0,0 +1,501 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command as TokioCommand;
+
+use crate::backend::container_volumes::{self, VolumeConfig, VOLUMES_DIR};
+use crate::backend::database_dumps::{self, DatabaseConfig, DATABASES_DIR};
+use crate::backend::desktop_settings::{self, DCONF_FILE, KDE_FILE};
+use crate::backend::extract;
+use crate::backend::packages::{PackageManifest, PACKAGES_FILE};
+use crate::backend::system_snapshot::{SystemSnapshot, BOOTSTRAP_FILE, SNAPSHOT_FILE};
+use crate::core::security::SecurePassword;
+use crate::core::types::{BackupItem, CheckResult, CheckStatus, ItemOutcome, ItemReport};
+
+/// Directory inside archives holding the collected system state
+pub const SYSTEM_STATE_DIR: &str = ".backup-system-state";
+
+const CRONTAB_FILE: &str = "crontab.txt";
+const SERVICES_FILE: &str = "enabled-services.json";
+const MAX_STATE_BYTES: usize = 1024 * 1024;
+/// Items' command output, one file per item, under the state directory
+const COMMANDS_DIR: &str = "commands";
+const MAX_COMMAND_OUTPUT: usize = 16 * 1024 * 1024;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Where the output of an item's command goes in the archive, relative to
+/// the home directory like the other items
+pub fn command_output_path(name: &str) -> PathBuf {
+    Path::new(SYSTEM_STATE_DIR).join(COMMANDS_DIR).join(format!("{}.txt", file_stem(name)))
+}
+
+/// `name` made safe as a file name: lowercase, with anything but letters,
+/// digits, `-` and `_` replaced by `-`
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+/// Units enabled when the backup was taken
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EnabledServices {
+    pub user: Vec<String>,
+    pub system: Vec<String>,
+}
+
+/// Staging directory for collected state, private to the user and
+/// removed when dropped
+pub struct SystemStateDir {
+    path: PathBuf,
+    /// How each item command fared, in the order given
+    pub command_reports: Vec<ItemReport>,
+}
+
+impl SystemStateDir {
+    /// Run the collectors, and the commands, package listings and volume
+    /// copies of `commands`, into a fresh private directory. Failing collectors are
+    /// logged and skipped; a machine without cron or systemd still gets
+    /// backed up.
+    pub async fn collect(commands: &[&BackupItem]) -> Result<Self> {
+        let parent = dirs::data_local_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("backup-manager");
+        fs::create_dir_all(&parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+        let path = parent.join(format!("system-state-{}", std::process::id()));
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder
+            .create(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut dir = Self { path, command_reports: Vec::new() };
+
+        match crontab().await {
+            Ok(Some(crontab)) => write_private(&dir.path.join(CRONTAB_FILE), crontab.as_bytes())?,
+            Ok(None) => debug!("No user crontab to collect"),
+            Err(e) => warn!("Skipping crontab: {}", e),
+        }
+
+        let services = EnabledServices {
+            user: enabled_units(true).await.unwrap_or_else(|e| {
+                warn!("Skipping enabled user services: {}", e);
+                Vec::new()
+            }),
+            system: enabled_units(false).await.unwrap_or_else(|e| {
+                warn!("Skipping enabled system services: {}", e);
+                Vec::new()
+            }),
+        };
+        write_private(&dir.path.join(SERVICES_FILE), &serde_json::to_vec_pretty(&services)?)?;
+
+        // Desktop settings as portable exports, importable into newer
+        // desktop versions than the raw config files would be
+        match desktop_settings::export_dconf().await {
+            Ok(Some(dump)) => write_private(&dir.path.join(DCONF_FILE), dump.as_bytes())?,
+            Ok(None) => debug!("No dconf settings to collect"),
+            Err(e) => warn!("Skipping dconf settings: {}", e),
+        }
+        if let Some(config_dir) = dirs::config_dir() {
+            let kde = desktop_settings::export_kde(&config_dir);
+            if !kde.is_empty() {
+                write_private(&dir.path.join(KDE_FILE), &serde_json::to_vec_pretty(&kde)?)?;
+            }
+        }
+
+        let mut packages = None;
+        let mut databases = Vec::new();
+        for item in commands {
+            if let Some(command) = &item.command {
+                dir.command_reports.push(dir.run_item_command(item, command).await?);
+            } else if !item.packages.is_empty() {
+                let (report, manifest) = dir.list_packages(item).await?;
+                dir.command_reports.push(report);
+                packages = Some(manifest);
+            } else if let Some(volumes) = &item.volumes {
+                dir.command_reports.push(dir.copy_volumes(item, volumes).await?);
+            } else if let Some(database) = &item.database {
+                let report = dir.dump_database(item, database).await?;
+                if report.outcome == ItemOutcome::BackedUp {
+                    databases.push(database.clone());
+                }
+                dir.command_reports.push(report);
+            }
+        }
+        if !databases.is_empty() {
+            let manifest = dir.path.join(DATABASES_DIR).join(database_dumps::MANIFEST_FILE);
+            write_private(&manifest, &serde_json::to_vec_pretty(&databases)?)?;
+        }
+
+        info!(
+            "Collected system state: {} user and {} system services enabled",
+            services.user.len(),
+            services.system.len()
+        );
+
+        // Everything above plus kernel and mount setup, with a script to
+        // rebuild the machine from it
+        let snapshot = SystemSnapshot::capture(services, packages);
+        write_private(&dir.path.join(SNAPSHOT_FILE), &serde_json::to_vec_pretty(&snapshot)?)?;
+        write_private(&dir.path.join(BOOTSTRAP_FILE), snapshot.bootstrap_script().as_bytes())?;
+        Ok(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Save what `command` prints as the item's file. A failing command
+    /// is reported for the item rather than failing the backup.
+    async fn run_item_command(&self, item: &BackupItem, command: &str) -> Result<ItemReport> {
+        let mut report = ItemReport {
+            path: item.path.clone(),
+            outcome: ItemOutcome::CommandFailed,
+            files: 0,
+            bytes: 0,
+            unreadable: Vec::new(),
+        };
+        let output = match item_command_output(command).await {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("{}: {:#}", item.name, e);
+                return Ok(report);
+            }
+        };
+        let relative = item.path.strip_prefix(SYSTEM_STATE_DIR).unwrap_or(&item.path);
+        let file = self.path.join(relative);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        write_private(&file, &output)?;
+        debug!("Saved the output of `{}` ({} bytes)", command, output.len());
+        report.outcome = ItemOutcome::BackedUp;
+        report.files = 1;
+        report.bytes = output.len() as u64;
+        Ok(report)
+    }
+
+    /// Save the explicitly installed packages of the item's managers.
+    /// Managers missing on this machine are left out of the list.
+    async fn list_packages(&self, item: &BackupItem) -> Result<(ItemReport, PackageManifest)> {
+        let manifest = PackageManifest::capture(&item.packages).await;
+        let content = serde_json::to_vec_pretty(&manifest)?;
+        write_private(&self.path.join(PACKAGES_FILE), &content)?;
+        info!(
+            "Listed {} packages from {} package managers",
+            manifest.packages.values().map(Vec::len).sum::<usize>(),
+            manifest.packages.len()
+        );
+        let report = ItemReport {
+            path: item.path.clone(),
+            outcome: ItemOutcome::BackedUp,
+            files: 1,
+            bytes: content.len() as u64,
+            unreadable: Vec::new(),
+        };
+        Ok((report, manifest))
+    }
+
+    /// Copy the item's container volumes. The item counts as failed when
+    /// any volume couldn't be copied, and as missing without Docker or
+    /// Podman to ask; with no volumes picked there is nothing to miss.
+    async fn copy_volumes(&self, item: &BackupItem, volumes: &VolumeConfig) -> Result<ItemReport> {
+        let mut report = ItemReport {
+            path: item.path.clone(),
+            outcome: ItemOutcome::CommandFailed,
+            files: 0,
+            bytes: 0,
+            unreadable: Vec::new(),
+        };
+        if volumes.names.is_empty() {
+            debug!("{}: no volumes picked", item.name);
+            report.outcome = ItemOutcome::BackedUp;
+            return Ok(report);
+        }
+
+        let dir = self.path.join(VOLUMES_DIR);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let volumes = volumes.clone();
+        let copied = tokio::task::spawn_blocking(move || container_volumes::snapshot_volumes(&volumes, &dir)).await?;
+        let (manifest, failures) = match copied {
+            Ok(copied) => copied,
+            Err(e) => {
+                warn!("{}: {:#}", item.name, e);
+                report.outcome = ItemOutcome::Missing;
+                return Ok(report);
+            }
+        };
+        for failure in &failures {
+            warn!("{}: {}", item.name, failure);
+        }
+        if failures.is_empty() {
+            report.outcome = ItemOutcome::BackedUp;
+        }
+        report.files = manifest.volumes.len();
+        report.bytes = manifest.volumes.iter().map(|volume| volume.bytes).sum();
+        Ok(report)
+    }
+
+    /// Dump the item's database. A failing dump is reported for the item
+    /// rather than failing the backup.
+    async fn dump_database(&self, item: &BackupItem, database: &DatabaseConfig) -> Result<ItemReport> {
+        let mut report = ItemReport {
+            path: item.path.clone(),
+            outcome: ItemOutcome::CommandFailed,
+            files: 0,
+            bytes: 0,
+            unreadable: Vec::new(),
+        };
+        let relative = item.path.strip_prefix(SYSTEM_STATE_DIR).unwrap_or(&item.path);
+        let file = self.path.join(relative);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        // Created private first; the dump tools write into it as it is
+        write_private(&file, b"")?;
+        if let Err(e) = database_dumps::dump(database, &file).await {
+            warn!("{}: {:#}", item.name, e);
+            let _ = fs::remove_file(&file);
+            return Ok(report);
+        }
+        report.outcome = ItemOutcome::BackedUp;
+        report.files = 1;
+        report.bytes = fs::metadata(&file).map(|metadata| metadata.len()).unwrap_or(0);
+        info!("Dumped {} ({} bytes)", database.name, report.bytes);
+        Ok(report)
+    }
+}
+
+/// Run `command` with `sh -c` from the home directory and return what it
+/// printed, or why it failed
+async fn item_command_output(command: &str) -> Result<Vec<u8>> {
+    let home = dirs::home_dir().context("Could not find the home directory")?;
+    let output = TokioCommand::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(home)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+        .await
+        .map_err(|_| anyhow::anyhow!("`{}` was still running after {}s", command, COMMAND_TIMEOUT.as_secs()))?
+        .with_context(|| format!("Failed to run `{}`", command))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("`{}` failed ({}): {}", command, output.status, stderr.trim());
+    }
+    if output.stdout.len() > MAX_COMMAND_OUTPUT {
+        anyhow::bail!("`{}` printed more than {} bytes", command, MAX_COMMAND_OUTPUT);
+    }
+    Ok(output.stdout)
+}
+
+impl Drop for SystemStateDir {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            warn!("Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// `crontab -l`, or None when the user has no crontab
+async fn crontab() -> Result<Option<String>> {
+    let output = TokioCommand::new("crontab")
+        .arg("-l")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run crontab")?;
+
+    if output.status.success() {
+        return Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("no crontab") {
+        Ok(None)
+    } else {
+        anyhow::bail!("crontab -l failed: {}", stderr.trim())
+    }
+}
+
+async fn enabled_units(user: bool) -> Result<Vec<String>> {
+    let mut command = TokioCommand::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let output = command
+        .args(["list-unit-files", "--state=enabled", "--no-legend", "--no-pager"])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run systemctl")?;
+
+    if !output.status.success() {
+        anyhow::bail!("systemctl failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(parse_unit_files(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Unit names from `systemctl list-unit-files --no-legend` output
+fn parse_unit_files(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|unit| unit.contains('.'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Re-enable the services recorded in an archive and reinstall its
+/// crontab if the user has none yet. User units are enabled directly;
+/// system units need root, so the command to run is reported instead.
+pub async fn reenable_services(archive: &Path, password: Option<&SecurePassword>) -> Result<Vec<CheckResult>> {
+    let services_member = format!("{}/{}", SYSTEM_STATE_DIR, SERVICES_FILE);
+    let services: EnabledServices = match extract::read_member(archive, &services_member, password, MAX_STATE_BYTES).await {
+        Ok(content) => serde_json::from_slice(&content).context("Invalid services list in archive")?,
+        Err(e) => anyhow::bail!("This archive has no recorded services ({})", e),
+    };
+
+    let mut results = Vec::new();
+
+    if !services.user.is_empty() {
+        // Units restored into ~/.config/systemd/user aren't known until reloaded
+        let _ = systemctl_user(&["daemon-reload"]).await;
+    }
+    for unit in &services.user {
+        let result = match systemctl_user(&["enable", unit]).await {
+            Ok(_) => CheckResult::new("user", unit, CheckStatus::Pass, "enabled"),
+            Err(e) => CheckResult::new("user", unit, CheckStatus::Fail, &e.to_string()),
+        };
+        results.push(result);
+    }
+
+    if !services.system.is_empty() {
+        results.push(CheckResult::new(
+            "system",
+            &format!("{} system units", services.system.len()),
+            CheckStatus::Warning,
+            &format!("needs root, run: sudo systemctl enable {}", services.system.join(" ")),
+        ));
+    }
+
+    results.push(restore_crontab(archive, password).await);
+    Ok(results)
+}
+
+async fn restore_crontab(archive: &Path, password: Option<&SecurePassword>) -> CheckResult {
+    let member = format!("{}/{}", SYSTEM_STATE_DIR, CRONTAB_FILE);
+    let Ok(content) = extract::read_member(archive, &member, password, MAX_STATE_BYTES).await else {
+        return CheckResult::new("cron", "crontab", CheckStatus::Pass, "none in archive");
+    };
+
+    match crontab().await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return CheckResult::new(
+                "cron",
+                "crontab",
+                CheckStatus::Warning,
+                &format!("kept the existing crontab; the archived one is {}/{} in the archive", SYSTEM_STATE_DIR, CRONTAB_FILE),
+            );
+        }
+        Err(e) => return CheckResult::new("cron", "crontab", CheckStatus::Fail, &e.to_string()),
+    }
+
+    let installed = async {
+        let mut child = TokioCommand::new("crontab")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run crontab")?;
+        {
+            use tokio::io::AsyncWriteExt;
+            let mut stdin = child.stdin.take().context("Failed to open crontab stdin")?;
+            stdin.write_all(&content).await?;
+        }
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+
+    match installed.await {
+        Ok(_) => CheckResult::new("cron", "crontab", CheckStatus::Pass, "installed"),
+        Err(e) => CheckResult::new("cron", "crontab", CheckStatus::Fail, &e.to_string()),
+    }
+}
+
+async fn systemctl_user(args: &[&str]) -> Result<()> {
+    let output = TokioCommand::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run systemctl")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unit_files() {
+        let output = "\
+pipewire.socket        enabled enabled
+syncthing.service      enabled enabled
+
+ssh.service            enabled disabled
+";
+        assert_eq!(
+            parse_unit_files(output),
+            vec!["pipewire.socket", "syncthing.service", "ssh.service"]
+        );
+        assert!(parse_unit_files("0 unit files listed.").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_item_commands() {
+        assert_eq!(command_output_path("VS Code/extensions"), PathBuf::from(".backup-system-state/commands/vs-code-extensions.txt"));
+        assert_eq!(item_command_output("printf 'a\\nb'").await.unwrap(), b"a\nb");
+        let error = item_command_output("echo oops >&2; exit 3").await.unwrap_err().to_string();
+        assert!(error.contains("oops"), "{}", error);
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use crate::core::types::Compression;
+
+/// Sequential reader over the files in an unencrypted archive. Compressed
+/// archives are decompressed by the matching tool as a stream, so nothing
+/// is extracted to disk and memory use doesn't depend on the archive.
+pub struct ArchiveReader {
+    reader: BufReader<Box<dyn Read + Send>>,
+    decompressor: Option<Child>,
+}
+
+impl ArchiveReader {
+    pub fn open(archive: &Path) -> Result<Self> {
+        let (reader, decompressor) = decompressed(archive)?;
+        Ok(Self {
+            reader: BufReader::new(reader),
+            decompressor,
+        })
+    }
+
+    /// Call `visit` with the name, size and contents of each regular file.
+    /// Whatever `visit` leaves unread is skipped. Blocking; run it on the
+    /// blocking pool.
+    pub fn for_each_file(mut self, visit: impl FnMut(&str, u64, &mut dyn Read) -> Result<()>) -> Result<()> {
+        walk(&mut self.reader, visit)
+    }
+}
+
+impl Drop for ArchiveReader {
+    fn drop(&mut self) {
+        if let Some(child) = self.decompressor.as_mut() {
+            // Reading stops at the end-of-archive marker; a decompressor
+            // still writing the trailing padding would otherwise linger
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// The plain tar stream inside an unencrypted archive, along with the
+/// decompressor producing it, if any. Whoever reads the stream to the end
+/// should wait on the decompressor to learn whether it succeeded.
+pub fn decompressed(archive: &Path) -> Result<(Box<dyn Read + Send>, Option<Child>)> {
+    if archive.extension().map(|e| e == "gpg").unwrap_or(false) {
+        anyhow::bail!("{} is encrypted", archive.display());
+    }
+
+    match Compression::detect(archive) {
+        Some(compression) => {
+            let mut child = Command::new(compression.as_str())
+                .arg("-dc")
+                .arg(archive)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .with_context(|| format!("Failed to run {}", compression.as_str()))?;
+            let stdout = child.stdout.take().context("Failed to read decompressor output")?;
+            Ok((Box::new(stdout), Some(child)))
+        }
+        None => Ok((
+            Box::new(File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?),
+            None,
+        )),
+    }
+}
+
+/// Walk a plain tar stream, calling `visit` as `ArchiveReader::for_each_file`
+/// does. Stops at the end-of-archive marker, leaving anything after it
+/// unread.
+pub fn walk(reader: &mut impl Read, mut visit: impl FnMut(&str, u64, &mut dyn Read) -> Result<()>) -> Result<()> {
+    walk_entries(reader, |entry, content| match entry.typeflag {
+        b'0' | 0 => visit(&entry.name, entry.size, content),
+        _ => Ok(()),
+    })
+}
+
+/// Header of one entry in a tar stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct TarEntry {
+    /// Name without a leading `./`, from a GNU long name or pax record
+    /// when there is one
+    pub name: String,
+    pub typeflag: u8,
+    pub size: u64,
+    pub mode: u32,
+    pub mtime: i64,
+    /// Where the entry's data starts in the plain tar stream
+    pub offset: u64,
+    /// Whether a pax record gives it file capabilities
+    pub capabilities: bool,
+}
+
+/// Walk a plain tar stream, calling `visit` with the header and contents
+/// of every entry, directories and links included. GNU long names and pax
+/// headers are folded into the entry they describe. Whatever `visit`
+/// leaves unread is skipped.
+pub fn walk_entries(reader: &mut impl Read, mut visit: impl FnMut(&TarEntry, &mut dyn Read) -> Result<()>) -> Result<()> {
+    let mut reader = Counting { inner: reader, count: 0 };
+    let mut long_name: Option<String> = None;
+    let mut capabilities = false;
+    let mut header = [0u8; 512];
+
+    loop {
+        if !read_block(&mut reader, &mut header)? || header.iter().all(|b| *b == 0) {
+            return Ok(());
+        }
+        let size = entry_size(&header)?;
+        let padding = size.div_ceil(512) * 512 - size;
+        let name = long_name.take().unwrap_or_else(|| entry_name(&header));
+
+        match header[156] {
+            // GNU long name and pax headers name the entry that follows
+            b'L' => {
+                let data = read_data(&mut reader, size, padding)?;
+                long_name = Some(String::from_utf8_lossy(&data).trim_end_matches('\0').to_string());
+            }
+            b'x' => {
+                let data = read_data(&mut reader, size, padding)?;
+                long_name = pax_path(&data);
+                capabilities = pax_has_capabilities(&data);
+            }
+            b'K' | b'g' => skip(&mut reader, size + padding)?,
+            typeflag => {
+                let entry = TarEntry {
+                    name: name.trim_start_matches("./").to_string(),
+                    typeflag,
+                    // Links and directories carry no data, whatever the
+                    // header says
+                    size: if matches!(typeflag, b'0' | 0 | b'7') { size } else { 0 },
+                    mode: numeric_field(&header[100..108]).unwrap_or(0) as u32,
+                    mtime: numeric_field(&header[136..148]).unwrap_or(0) as i64,
+                    offset: reader.count,
+                    capabilities: std::mem::take(&mut capabilities),
+                };
+                let mut content = (&mut reader).take(size);
+                visit(&entry, &mut content)?;
+                let unread = content.limit();
+                skip(&mut reader, unread + padding)?;
+            }
+        }
+    }
+}
+
+/// Reader that keeps count of the bytes read through it
+struct Counting<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Fill `block`, or return false at a clean end of stream
+fn read_block(reader: &mut impl Read, block: &mut [u8; 512]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < block.len() {
+        let n = reader.read(&mut block[filled..])?;
+        if n == 0 {
+            if filled == 0 {
+                return Ok(false);
+            }
+            anyhow::bail!("Archive ends inside a tar header");
+        }
+        filled += n;
+    }
+    Ok(true)
+}
+
+fn read_data(reader: &mut impl Read, size: u64, padding: u64) -> Result<Vec<u8>> {
+    let mut data = vec![0u8; size as usize];
+    reader.read_exact(&mut data).context("Archive ends inside a file")?;
+    skip(reader, padding)?;
+    Ok(data)
+}
+
+fn skip(reader: &mut impl Read, len: u64) -> Result<()> {
+    let skipped = std::io::copy(&mut reader.take(len), &mut std::io::sink())?;
+    if skipped != len {
+        anyhow::bail!("Archive ends inside a file");
+    }
+    Ok(())
+}
+
+/// Entry size, in octal or (for files over 8 GiB) GNU base-256
+fn entry_size(header: &[u8; 512]) -> Result<u64> {
+    numeric_field(&header[124..136]).context("Invalid size in tar header")
+}
+
+/// Numeric header field, in octal or GNU base-256
+fn numeric_field(field: &[u8]) -> Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..].iter().fold(0u64, |value, b| (value << 8) | *b as u64));
+    }
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    Ok(u64::from_str_radix(digits, 8)?)
+}
+
+/// Entry name, joined with the ustar prefix when there is one
+fn entry_name(header: &[u8; 512]) -> String {
+    let field = |range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    };
+    let name = field(0..100);
+    // POSIX ustar only; GNU tar keeps other fields where the prefix would be
+    if &header[257..263] == b"ustar\0" {
+        let prefix = field(345..500);
+        if !prefix.is_empty() {
+            return format!("{}/{}", prefix, name);
+        }
+    }
+    name
+}
+
+/// `path` from pax extended header records (`<len> path=<value>\n`)
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter_map(|record| record.split_once(' ').map(|(_, kv)| kv))
+        .find_map(|kv| kv.strip_prefix("path=").map(str::to_string))
+}
+
+/// Whether pax records carry a `security.capability` xattr, as GNU tar
+/// writes with `--xattrs`
+fn pax_has_capabilities(data: &[u8]) -> bool {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter_map(|record| record.split_once(' ').map(|(_, kv)| kv))
+        .any(|kv| kv.starts_with("SCHILY.xattr.security.capability="))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, typeflag: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = typeflag;
+        let mut entry = header.to_vec();
+        entry.extend_from_slice(data);
+        entry.resize(entry.len().div_ceil(512) * 512, 0);
+        entry
+    }
+
+    #[test]
+    fn test_walk_tar_stream() {
+        let long_name = format!(".config/{}/notes.txt", "d".repeat(120));
+        let mut tar = Vec::new();
+        tar.extend(entry("./.muttrc", b'0', b"set realname=\"Jane\"\n"));
+        tar.extend(entry(".config/", b'5', b""));
+        tar.extend(entry("big.log", b'0', &[b'x'; 2048]));
+        tar.extend(entry("././@LongLink", b'L', format!("{}\0", long_name).as_bytes()));
+        tar.extend(entry("ignored", b'0', b"meeting notes"));
+        tar.extend([0u8; 1024]);
+
+        let mut files = Vec::new();
+        walk(&mut tar.as_slice(), |name, size, content| {
+            // Read only the start of each file; the rest must be skipped
+            let mut start = [0u8; 3];
+            content.read_exact(&mut start)?;
+            files.push((name.to_string(), size, String::from_utf8_lossy(&start).to_string()));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            files,
+            vec![
+                (".muttrc".to_string(), 20, "set".to_string()),
+                ("big.log".to_string(), 2048, "xxx".to_string()),
+                (long_name, 13, "mee".to_string()),
+            ]
+        );
+        assert!(walk(&mut &tar[..700], |_, _, _| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use log::warn;
+use std::path::Path;
+
+use crate::core::config::BackupConfig;
+use crate::core::exclusions::ExclusionSet;
+
+/// Cap on the text gathered from one archive; it is held in memory until
+/// the archive is cataloged
+const MAX_INDEXED_TEXT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Files whose text never goes into the catalog, on top of the paths of
+/// applications rated above "low" in the config
+const NEVER_INDEXED: &[&str] = &[
+    ".ssh", ".gnupg", ".password-store", ".aws", ".kube", ".netrc", ".pgpass",
+    "*.pem", "*.key", "*credential*", "*secret*", "*token*", ".env", "*.env",
+];
+
+/// Text of one archived file, as stored in the catalog's full-text index
+#[derive(Debug, Clone)]
+pub struct IndexedText {
+    pub path: String,
+    pub text: String,
+}
+
+/// Decides which files of an archive have their text indexed, and
+/// gathers that text as the archive is read
+pub struct TextIndexer {
+    max_file_bytes: u64,
+    never: ExclusionSet,
+    total: usize,
+    texts: Vec<IndexedText>,
+}
+
+impl TextIndexer {
+    /// None unless `search_index.contents` is enabled
+    pub fn from_config(config: &BackupConfig) -> Result<Option<Self>> {
+        let Some(search_index) = config.search_index.as_ref().filter(|index| index.contents) else {
+            return Ok(None);
+        };
+
+        let mut never: Vec<String> = NEVER_INDEXED.iter().map(|p| p.to_string()).collect();
+        for category in config.modern_configurations.categories.values() {
+            for app in category.values().filter(|app| app.security_level != "low") {
+                never.extend(app.item_paths());
+            }
+        }
+
+        Ok(Some(Self {
+            max_file_bytes: search_index.max_file_kb * 1024,
+            never: ExclusionSet::new(&never)?,
+            total: 0,
+            texts: Vec::new(),
+        }))
+    }
+
+    /// Whether the text of the file `path` should go into the index
+    pub fn wants(&self, path: &str, size: u64) -> bool {
+        size > 0
+            && size <= self.max_file_bytes
+            && self.total < MAX_INDEXED_TEXT_BYTES
+            && !Path::new(path).ancestors().any(|p| self.never.is_excluded(p))
+    }
+
+    /// Keep a wanted file's contents, unless they turn out to be binary
+    pub fn add(&mut self, path: &str, data: Vec<u8>) {
+        let Some(text) = as_text(data) else {
+            return;
+        };
+        self.total += text.len();
+        if self.total >= MAX_INDEXED_TEXT_BYTES {
+            warn!("Text index limit reached; later files are found by name only");
+        }
+        self.texts.push(IndexedText {
+            path: path.to_string(),
+            text,
+        });
+    }
+
+    pub fn into_texts(self) -> Vec<IndexedText> {
+        self.texts
+    }
+}
+
+/// The file as text, or None for anything that looks binary
+fn as_text(data: Vec<u8>) -> Option<String> {
+    if data.contains(&0) {
+        return None;
+    }
+    String::from_utf8(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_selection() {
+        let mut indexer = TextIndexer {
+            max_file_bytes: 1024,
+            never: ExclusionSet::new(&[".ssh".to_string(), "*.pem".to_string()]).unwrap(),
+            total: 0,
+            texts: Vec::new(),
+        };
+
+        assert!(indexer.wants(".muttrc", 20));
+        assert!(!indexer.wants(".ssh/config", 20));
+        assert!(!indexer.wants("certs/server.pem", 20));
+        assert!(!indexer.wants("big.log", 2048));
+        assert!(!indexer.wants("empty", 0));
+
+        indexer.add(".muttrc", b"set realname=\"Jane\"\n".to_vec());
+        indexer.add("image.png", b"\x89PNG\0\0".to_vec());
+        let texts = indexer.into_texts();
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].path, ".muttrc");
+    }
+}
@@ -0,0 +1,141 @@
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often compressor thread usage is logged
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// Logs, at debug level, how busy each thread of a compressor running
+/// below a process is, every few seconds until dropped
+pub struct CompressorThreads {
+    stop: Arc<AtomicBool>,
+    watcher: Option<thread::JoinHandle<()>>,
+}
+
+impl CompressorThreads {
+    /// Watch processes named `program` below `root`, such as the
+    /// compressor tar runs. None unless debug logging is on.
+    pub fn watch(root: u32, program: &str) -> Option<Self> {
+        if !log::log_enabled!(log::Level::Debug) {
+            return None;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let program = program.to_string();
+        let watcher = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+                let mut last: HashMap<u32, u64> = HashMap::new();
+                let mut sampled = Instant::now();
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(100));
+                    if sampled.elapsed() < INTERVAL {
+                        continue;
+                    }
+                    let elapsed = sampled.elapsed().as_secs_f64();
+                    sampled = Instant::now();
+                    for pid in descendants(root).into_iter().filter(|(_, name)| *name == program).map(|(pid, _)| pid) {
+                        let mut threads: Vec<(u32, u64)> = thread_ticks(pid).into_iter().collect();
+                        threads.sort_unstable();
+                        // Threads seen for the first time have nothing to compare with yet
+                        let usage: Vec<String> = threads
+                            .iter()
+                            .filter_map(|(tid, ticks)| {
+                                let before = last.insert(*tid, *ticks)?;
+                                let percent = ticks.saturating_sub(before) as f64 / ticks_per_second / elapsed * 100.0;
+                                Some(format!("{}: {:.0}%", tid, percent))
+                            })
+                            .collect();
+                        if !usage.is_empty() {
+                            debug!("{} (pid {}) on {} threads: {}", program, pid, threads.len(), usage.join(", "));
+                        }
+                    }
+                }
+            })
+        };
+        Some(Self { stop, watcher: Some(watcher) })
+    }
+}
+
+impl Drop for CompressorThreads {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+    }
+}
+
+/// Command name, parent and CPU ticks (user and system) from a line of
+/// `/proc/<pid>/stat` or `/proc/<pid>/task/<tid>/stat`
+fn parse_stat(line: &str) -> Option<(&str, u32, u64)> {
+    // The name is in parentheses and may hold spaces or parentheses itself
+    let name = &line[line.find('(')? + 1..line.rfind(')')?];
+    let fields: Vec<&str> = line[line.rfind(')')? + 1..].split_whitespace().collect();
+    let parent = fields.get(1)?.parse().ok()?;
+    let ticks = fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+    Some((name, parent, ticks))
+}
+
+/// Processes below `root`, with their command names
+fn descendants(root: u32) -> Vec<(u32, String)> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let processes: Vec<(u32, u32, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let stat = fs::read_to_string(entry.path().join("stat")).ok()?;
+            let (name, parent, _) = parse_stat(&stat)?;
+            Some((pid, parent, name.to_string()))
+        })
+        .collect();
+
+    let mut found = Vec::new();
+    let mut parents = vec![root];
+    while let Some(parent) = parents.pop() {
+        for (pid, _, name) in processes.iter().filter(|(_, of, _)| *of == parent) {
+            parents.push(*pid);
+            found.push((*pid, name.clone()));
+        }
+    }
+    found
+}
+
+/// CPU ticks used so far by each thread of `pid`
+fn thread_ticks(pid: u32) -> HashMap<u32, u64> {
+    let Ok(tasks) = fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return HashMap::new();
+    };
+    tasks
+        .flatten()
+        .filter_map(|task| {
+            let tid = task.file_name().to_str()?.parse().ok()?;
+            let stat = fs::read_to_string(task.path().join("stat")).ok()?;
+            Some((tid, parse_stat(&stat)?.2))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_and_thread_stats() {
+        let line = "4242 (zstd (worker)) S 4200 4242 4200 0 -1 4194304 300 0 0 0 1500 250 0 0 20 0 4 0 100 0 0";
+        assert_eq!(parse_stat(line), Some(("zstd (worker)", 4200, 1750)));
+
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let found = descendants(std::process::id());
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(found.contains(&(child.id(), "sleep".to_string())));
+        assert!(!thread_ticks(std::process::id()).is_empty());
+    }
+}
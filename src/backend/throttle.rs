@@ -0,0 +1,102 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::core::config::{IoClass, ThrottleConfig};
+
+// From linux/ioprio.h, which libc doesn't carry
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+const IOPRIO_CLASS_BE: libc::c_int = 2;
+const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+
+/// Give the calling process, and everything it starts from then on, the
+/// niceness and IO class of `throttle`. Only makes system calls, so it can
+/// run between fork and exec.
+pub fn lower_priority(throttle: &ThrottleConfig) -> io::Result<()> {
+    if let Some(nice) = throttle.nice {
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice.clamp(0, 19)) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    if let Some(class) = throttle.ionice {
+        let priority = match class {
+            IoClass::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            // The lowest of best-effort's eight levels
+            IoClass::BestEffort => (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7,
+        };
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Start `command` with the niceness and IO class of `throttle`. A limit
+/// that can't be applied, such as a niceness below the current one, is
+/// left out rather than failing the command.
+pub fn limit_command(command: &mut tokio::process::Command, throttle: &ThrottleConfig) {
+    if throttle.nice.is_none() && throttle.ionice.is_none() {
+        return;
+    }
+    let throttle = throttle.clone();
+    unsafe {
+        command.pre_exec(move || {
+            let _ = lower_priority(&throttle);
+            Ok(())
+        });
+    }
+}
+
+/// Writer passing bytes on no faster than a fixed rate, averaged from when
+/// it was made
+pub struct RateLimited<W> {
+    inner: W,
+    bytes_per_second: u64,
+    started: Instant,
+    written: u64,
+}
+
+impl<W: Write> RateLimited<W> {
+    pub fn new(inner: W, bytes_per_second: u64) -> Self {
+        Self { inner, bytes_per_second: bytes_per_second.max(1), started: Instant::now(), written: 0 }
+    }
+}
+
+impl<W: Write> Write for RateLimited<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A tenth of a second's worth at most, so large writes are spread out
+        let chunk = buf.len().min((self.bytes_per_second / 10).max(1) as usize);
+        let written = self.inner.write(&buf[..chunk])?;
+        self.written += written as u64;
+        let due = Duration::from_secs_f64(self.written as f64 / self.bytes_per_second as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_throttled_writes_and_commands() {
+        let started = Instant::now();
+        let mut out = RateLimited::new(Vec::new(), 100_000);
+        out.write_all(&[0u8; 30_000]).unwrap();
+        assert_eq!(out.inner.len(), 30_000);
+        assert!(started.elapsed() >= Duration::from_millis(290));
+
+        let throttle = ThrottleConfig { nice: Some(19), ionice: Some(IoClass::Idle), max_mb_per_sec: None };
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", "nice"]);
+        limit_command(&mut command, &throttle);
+        let output = command.output().await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "19");
+    }
+}
@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::catalog::Catalog;
+use crate::backend::checksum;
+use crate::backend::destination::{copy_private, Destination, RcloneRemote};
+use crate::backend::member_index;
+use crate::backend::parity;
+use crate::core::config::TierRule;
+use crate::core::types::ArchiveInfo;
+
+/// Archive that has aged into a colder tier
+#[derive(Debug, Clone)]
+pub struct TierMove {
+    pub archive: ArchiveInfo,
+    pub to: Destination,
+}
+
+/// Archive due to move but left where it is, and why
+#[derive(Debug, Clone)]
+pub struct TierHold {
+    pub archive: ArchiveInfo,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct TierPlan {
+    pub moves: Vec<TierMove>,
+    pub held: Vec<TierHold>,
+}
+
+/// Which of this machine's archives belong in another tier under `rules`:
+/// each goes to the tier with the longest age it has reached, and never
+/// back to a warmer one. Only archives on disk are moved; those already on
+/// an rclone remote stay there.
+pub fn plan(archives: &[ArchiveInfo], rules: &[TierRule], hostname: &str, now: DateTime<Utc>) -> TierPlan {
+    let mut plan = TierPlan::default();
+    for archive in archives {
+        if archive.remote.is_some() || archive.hostname.as_deref().is_some_and(|host| host != hostname) {
+            continue;
+        }
+        let age_days = (now - archive.created).num_days();
+        let Some(target) = rules
+            .iter()
+            .filter(|rule| age_days >= i64::from(rule.older_than_days))
+            .max_by_key(|rule| rule.older_than_days)
+        else {
+            continue;
+        };
+        let current = rules
+            .iter()
+            .filter(|rule| is_in_tier(archive, rule))
+            .map(|rule| rule.older_than_days)
+            .max();
+        if current.is_some_and(|days| days >= target.older_than_days) {
+            continue;
+        }
+
+        let to = target.resolved_destination();
+        if matches!(to, Destination::Rclone(_)) && !archive.encrypted && !target.allow_unencrypted {
+            plan.held.push(TierHold {
+                archive: archive.clone(),
+                reason: format!(
+                    "it is unencrypted; set allow_unencrypted on the {}-day tier to move it to {}",
+                    target.older_than_days, to
+                ),
+            });
+            continue;
+        }
+        plan.moves.push(TierMove { archive: archive.clone(), to });
+    }
+    plan
+}
+
+fn is_in_tier(archive: &ArchiveInfo, rule: &TierRule) -> bool {
+    let Destination::Local(dir) = rule.resolved_destination() else {
+        return false;
+    };
+    let Some(parent) = archive.path.parent() else {
+        return false;
+    };
+    parent == dir || matches!((parent.canonicalize(), dir.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Copy the archive to its new tier, check the copy, point the catalog at
+/// it and delete the original. The original is only removed once the copy
+/// is in place and recorded; any failure before that leaves it as it was.
+pub async fn migrate(catalog: &Catalog, tier_move: &TierMove) -> Result<ArchiveInfo> {
+    let archive = &tier_move.archive;
+    let checksum = catalog.checksum(archive)?;
+    info!("Moving {} to {}", archive.name, tier_move.to);
+
+    let moved = match &tier_move.to {
+        Destination::Local(dir) => copy_to_dir(archive, dir, checksum).await?,
+        Destination::Rclone(remote) => upload(archive, remote).await?,
+    };
+    catalog.relocate(&archive.path.to_string_lossy(), &moved)?;
+    match fs::remove_file(&archive.path) {
+        Ok(()) => {
+            member_index::remove_beside(&archive.path);
+            parity::remove_beside(&archive.path);
+        }
+        Err(e) => warn!("Moved {} but failed to delete the original: {}", archive.name, e),
+    }
+    Ok(moved)
+}
+
+async fn copy_to_dir(archive: &ArchiveInfo, dir: &Path, checksum: Option<String>) -> Result<ArchiveInfo> {
+    let target = dir.join(&archive.name);
+    if target.exists() {
+        anyhow::bail!("{} already exists", target.display());
+    }
+    let partial = dir.join(format!(".partial-{}", archive.name));
+
+    let (source, dest, dir) = (archive.path.clone(), partial.clone(), dir.to_path_buf());
+    let copied = tokio::task::spawn_blocking(move || -> Result<()> {
+        create_private_dir(&dir)?;
+        copy_private(&source, &dest)?;
+        let matches = match checksum {
+            Some(checksum) => checksum::verify_file(&dest, &checksum)?,
+            // Archives cataloged without a checksum are compared by size
+            None => fs::metadata(&dest)?.len() == fs::metadata(&source)?.len(),
+        };
+        if !matches {
+            anyhow::bail!("The copy does not match the original");
+        }
+        Ok(())
+    })
+    .await?;
+    if let Err(e) = copied {
+        let _ = fs::remove_file(&partial);
+        return Err(e.context(format!("{} was left where it was", archive.name)));
+    }
+
+    fs::rename(&partial, &target)
+        .with_context(|| format!("Failed to move the copy to {}", target.display()))?;
+    if let Err(e) = member_index::copy_beside(&archive.path, &target) {
+        warn!("Failed to copy the index of {}: {}", archive.name, e);
+    }
+    if let Err(e) = parity::copy_beside(&archive.path, &target) {
+        warn!("Failed to copy the parity of {}: {}", archive.name, e);
+    }
+    Ok(ArchiveInfo { path: target, remote: None, ..archive.clone() })
+}
+
+async fn upload(archive: &ArchiveInfo, remote: &str) -> Result<ArchiveInfo> {
+    let rclone = RcloneRemote::new(remote);
+    let target = rclone.remote_path(&archive.name);
+    let size_on_remote = |archives: &[ArchiveInfo]| {
+        archives.iter().find(|a| a.remote.as_deref() == Some(target.as_str())).map(|a| a.size)
+    };
+
+    // A tier directory that doesn't exist yet can't be listed
+    match rclone.list_archives().await {
+        Ok(existing) if size_on_remote(&existing).is_some() => anyhow::bail!("{} already exists", target),
+        Ok(_) => {}
+        Err(e) => debug!("Could not list {}: {}", remote, e),
+    }
+
+    rclone.upload(&archive.path, None).await?;
+    let size = fs::metadata(&archive.path)?.len();
+    let uploaded = size_on_remote(&rclone.list_archives().await?)
+        .with_context(|| format!("{} was not found after uploading it", target))?;
+    if uploaded != size {
+        anyhow::bail!(
+            "{} has {} bytes after uploading {}; {} was left where it was",
+            target, uploaded, size, archive.name
+        );
+    }
+    Ok(ArchiveInfo { path: PathBuf::from(&target), remote: Some(target), ..archive.clone() })
+}
+
+/// A tier directory created here is private to the user; an existing one,
+/// such as a shared NFS export, keeps its permissions
+fn create_private_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::BackupMode;
+
+    fn archive(path: &Path, days: i64, encrypted: bool) -> ArchiveInfo {
+        ArchiveInfo {
+            path: path.to_path_buf(),
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            created: Utc::now() - chrono::Duration::days(days),
+            size: 4,
+            mode: BackupMode::Secure,
+            encrypted,
+            description: String::new(),
+            items: Vec::new(),
+            remote: None,
+            hostname: Some("laptop".to_string()),
+        }
+    }
+
+    fn rule(days: u32, destination: &str) -> TierRule {
+        TierRule { older_than_days: days, destination: destination.to_string(), allow_unencrypted: false }
+    }
+
+    #[test]
+    fn test_plan_moves_archives_to_the_coldest_tier_reached() {
+        let rules = [rule(90, "/mnt/nas/backups"), rule(365, "rclone:b2:cold")];
+        let other_host = ArchiveInfo { hostname: Some("desktop".to_string()), ..archive(Path::new("/ssd/d.tar.gz"), 200, true) };
+        let archives = [
+            archive(Path::new("/ssd/new.tar.gz"), 10, false),
+            archive(Path::new("/ssd/old.tar.gz"), 100, false),
+            archive(Path::new("/mnt/nas/backups/nas.tar.gz"), 100, false),
+            archive(Path::new("/mnt/nas/backups/cold.tar.gz.gpg"), 400, true),
+            archive(Path::new("/mnt/nas/backups/plain.tar.gz"), 400, false),
+            other_host,
+        ];
+
+        let plan = plan(&archives, &rules, "laptop", Utc::now());
+        let moves: Vec<(&str, String)> = plan.moves.iter().map(|m| (m.archive.name.as_str(), m.to.to_string())).collect();
+        assert_eq!(
+            moves,
+            [("old.tar.gz", "/mnt/nas/backups".to_string()), ("cold.tar.gz.gpg", "rclone:b2:cold".to_string())]
+        );
+        assert_eq!(plan.held.len(), 1);
+        assert_eq!(plan.held[0].archive.name, "plain.tar.gz");
+    }
+
+    #[tokio::test]
+    async fn test_migrate_moves_the_file_and_its_catalog_entry() {
+        let root = std::env::temp_dir().join(format!("tiering-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("ssd")).unwrap();
+        let source = root.join("ssd").join("a.tar.gz");
+        fs::write(&source, b"data").unwrap();
+
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        catalog.record_archive(&archive(&source, 100, false), None).unwrap();
+        let tier_move = TierMove { archive: archive(&source, 100, false), to: Destination::Local(root.join("nas")) };
+        let moved = migrate(&catalog, &tier_move).await.unwrap();
+
+        assert_eq!(moved.path, root.join("nas").join("a.tar.gz"));
+        assert_eq!(fs::read(&moved.path).unwrap(), b"data");
+        assert!(!source.exists());
+        assert_eq!(catalog.list_archives().unwrap()[0].path, moved.path);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&moved.path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
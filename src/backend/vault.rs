@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime};
+use log::{debug, info};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::stdout_backup::{self, StreamSource, StreamedArchive};
+use crate::core::security::SecurePassword;
+use crate::core::types::Compression;
+
+const PREFIX: &str = "vault_";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Credentials vault already on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vault {
+    pub path: PathBuf,
+    pub created: NaiveDateTime,
+}
+
+/// `vault_<host>_<stamp>.tar.<ext>.gpg`; vaults are always encrypted
+pub fn archive_name(host: &str, compression: Compression, created: NaiveDateTime) -> String {
+    format!("{}{}_{}.tar.{}.gpg", PREFIX, host, created.format(TIMESTAMP_FORMAT), compression.extension())
+}
+
+/// When the vault named `name` was made by `host`, if it is one of theirs
+fn parse_name(name: &str, host: &str) -> Option<NaiveDateTime> {
+    let rest = name.strip_prefix(PREFIX)?.strip_prefix(host)?.strip_prefix('_')?;
+    let (stamp, extension) = rest.split_once(".tar.")?;
+    if !extension.ends_with(".gpg") {
+        return None;
+    }
+    NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()
+}
+
+/// This machine's vaults in `dir`, oldest first. Vaults of other machines
+/// sharing the directory are left to them.
+pub fn list(dir: &Path, host: &str) -> Result<Vec<Vault>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut vaults: Vec<Vault> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+        .filter_map(|entry| {
+            let created = parse_name(&entry.file_name().to_string_lossy(), host)?;
+            Some(Vault { path: entry.path(), created })
+        })
+        .collect();
+    vaults.sort_by_key(|vault| vault.created);
+    Ok(vaults)
+}
+
+/// Whether the newest vault is at least `interval` old at `now`
+pub fn due(vaults: &[Vault], interval: chrono::Duration, now: NaiveDateTime) -> bool {
+    vaults.last().is_none_or(|newest| now - newest.created >= interval)
+}
+
+/// Write a vault of `source` into `dir`, encrypted with `password`. The
+/// archive is written under a hidden name and only renamed into place once
+/// gpg has finished, so an interrupted run never leaves a vault that looks
+/// complete. Blocking.
+pub fn write(
+    dir: &Path,
+    host: &str,
+    source: &StreamSource,
+    compression: Compression,
+    level: u32,
+    password: &SecurePassword,
+) -> Result<(PathBuf, StreamedArchive)> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let name = archive_name(host, compression, Local::now().naive_local());
+    let target = dir.join(&name);
+    let partial = dir.join(format!(".partial-{}", name));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut out = options
+        .open(&partial)
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+
+    let streamed = stdout_backup::stream_archive(source, compression, level, 0, Some(password), &mut out, |_, _| {}, |_| {});
+    let streamed = match streamed.and_then(|streamed| out.sync_all().map(|_| streamed).map_err(Into::into)) {
+        Ok(streamed) => streamed,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    fs::rename(&partial, &target).with_context(|| format!("Failed to move the vault to {}", target.display()))?;
+    info!("Credentials vault written to {}", target.display());
+    Ok((target, streamed))
+}
+
+/// Delete all but the newest `keep` of this machine's vaults in `dir`
+/// and return the paths deleted
+pub fn rotate(dir: &Path, host: &str, keep: usize) -> Result<Vec<PathBuf>> {
+    let vaults = list(dir, host)?;
+    let excess = vaults.len().saturating_sub(keep.max(1));
+    let mut removed = Vec::new();
+    for vault in &vaults[..excess] {
+        debug!("Removing old credentials vault {}", vault.path.display());
+        fs::remove_file(&vault.path).with_context(|| format!("Failed to delete {}", vault.path.display()))?;
+        removed.push(vault.path.clone());
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vaults_are_listed_per_host_and_rotated() {
+        let at = |stamp: &str| NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).unwrap();
+        let name = archive_name("laptop", Compression::Zstd, at("20240102-030405"));
+        assert_eq!(name, "vault_laptop_20240102-030405.tar.zst.gpg");
+        assert_eq!(parse_name(&name, "laptop"), Some(at("20240102-030405")));
+        assert_eq!(parse_name(&name, "lap"), None);
+
+        let dir = std::env::temp_dir().join(format!("vault-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for stamp in ["20240103-000000", "20240101-000000", "20240102-000000"] {
+            fs::write(dir.join(archive_name("laptop", Compression::Gzip, at(stamp))), "").unwrap();
+        }
+        fs::write(dir.join(archive_name("desktop", Compression::Gzip, at("20240101-000000"))), "").unwrap();
+        fs::write(dir.join(".partial-vault_laptop_20240104-000000.tar.gz.gpg"), "").unwrap();
+        fs::write(dir.join("vault_laptop_20240105-000000.tar.gz"), "").unwrap();
+
+        let vaults = list(&dir, "laptop").unwrap();
+        assert_eq!(
+            vaults.iter().map(|vault| vault.created).collect::<Vec<_>>(),
+            vec![at("20240101-000000"), at("20240102-000000"), at("20240103-000000")]
+        );
+        assert!(!due(&vaults, chrono::Duration::hours(24), at("20240103-120000")));
+        assert!(due(&vaults, chrono::Duration::hours(24), at("20240104-000000")));
+        assert!(due(&[], chrono::Duration::hours(24), at("20240104-000000")));
+
+        let removed = rotate(&dir, "laptop", 2).unwrap();
+        assert_eq!(removed, vec![dir.join("vault_laptop_20240101-000000.tar.gz.gpg")]);
+        assert_eq!(list(&dir, "laptop").unwrap().len(), 2);
+        assert_eq!(list(&dir, "desktop").unwrap().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
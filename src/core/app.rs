@@ -1,49 +1,174 @@
 use anyhow::{Context, Result};
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyEvent};
 use log::{debug, error, info, warn};
 use ratatui::backend::Backend;
+use ratatui::layout::Rect;
 use std::path::PathBuf;
+use tokio::sync::mpsc::{error::TryRecvError, UnboundedReceiver};
+use tokio::sync::oneshot;
 
-use crate::backend::BackupEngine;
-use crate::core::config::BackupConfig;
-use crate::core::state::{AppState, AppStateManager};
-use crate::core::types::{BackupItem, BackupMode, RestoreItem};
+use crate::backend::destination::{self, Destination, RcloneRemote};
+use crate::backend::destination_trust::{self, DestinationChange, TrustCheck, TrustStore};
+use crate::backend::catalog::{self, Catalog};
+use crate::backend::catalog_sync::CatalogSync;
+use crate::backend::remote_cache::RemoteCache;
+use crate::backend::remote_restore::RemoteRestore;
+use crate::backend::restore_snapshot::PreRestoreSnapshot;
+use crate::backend::text_index::TextIndexer;
+use crate::backend::dir_size::{self, SizeUpdate};
+use crate::backend::first_estimate::{self, FirstBackupEstimate};
+use crate::backend::item_report;
+use crate::backend::recovery_kit::{KitKey, RecoveryKit};
+use crate::backend::report::{self, OperationReport, ReportFormat, ReportItem};
+use crate::backend::preflight::{self, PreflightPlan};
+use crate::backend::removable;
+use crate::backend::secret_scan;
+use crate::backend::member_index::MemberIndex;
+use crate::backend::{browser_profiles, checksum, clipboard, container_volumes, credential_checks, database_dumps, desktop_settings, extract, gpg_keys, live_changes, packages, parity, privileged, restore_plan, system_state, BackupEngine, BackupOutput};
+use crate::core::audit::{AuditEvent, AuditLog, ChainStatus, Operation};
+use crate::core::config::{expand_home, BackupConfig, CriticalGapReason, ThrottleConfig, CUSTOM_CATEGORY};
+use crate::core::config_form::{self, ConfigForm};
+use crate::core::config_history;
+use crate::core::errors::{AppError, ErrorCategory};
+use crate::core::keymap::{Action, KeyContext};
+use crate::core::keyring_store;
+use crate::core::security::SecurePassword;
+use crate::core::exclusions::{ExclusionSet, MatchCount};
+use crate::core::first_run;
+use crate::core::item_tree::{self, TreeRow};
+use crate::core::notifications::{self, JobOutcome};
+use crate::core::session::SessionStats;
+use crate::core::state::{AppState, AppStateManager, RetryOperation, TOP_CONSUMERS};
+use crate::core::strategies;
+use crate::core::types::{
+    ArchiveInfo, BackupItem, BackupMode, BackupProgress, CatalogMatch, CheckResult, CheckStatus, Compression, DestinationResult,
+    ProgressStatus, RestoreItem, RestoreMetadata, RestoreProgress, SecurityLevel,
+};
+use crate::ui::components::render_status_bar;
+use crate::ui::layout;
+use crate::ui::terminal::{format_bytes, TerminalEvent};
+use crate::ui::theme::{Palette, Theme};
+use crate::ui::widgets::FileBrowser;
 use crate::ui::screens::{
     BackupCompleteScreen, BackupItemSelectionScreen, BackupModeSelectionScreen,
-    BackupPasswordScreen, BackupProgressScreen, ErrorScreen, HelpScreen, MainMenuScreen,
+    BackupConfirmScreen, BackupPasswordScreen, BackupProgressScreen, CatalogBrowserScreen, ConfigEditorScreen, ConfigHistoryScreen, DestinationSelectionScreen, PreflightScreen, RecipientSelectionScreen, SecretReviewScreen, VolumeSelectionScreen, ErrorScreen, ExclusionEditorScreen, FirstRunWizardScreen, FirstBackupGuideScreen, FleetOverviewScreen, AuditHistoryScreen, FindFileScreen, FileHistoryScreen, HelpScreen, MainMenuScreen, ProfileSelectionScreen,
     RestoreArchiveSelectionScreen, RestoreCompleteScreen, RestoreItemSelectionScreen,
     RestorePasswordScreen, RestoreProgressScreen,
 };
 
 pub struct AppConfig {
     pub backup_config: BackupConfig,
-    pub output_path: Option<PathBuf>,
+    /// Resolved location of the loaded config, used when saving edits
+    pub config_path: PathBuf,
+    pub destination: Option<Destination>,
+    /// Profile given with `--profile`, used for every backup this session
+    pub profile: Option<String>,
+    /// Limits from `--nice`, `--ionice` and `--max-rate`, over any profile's
+    pub throttle: ThrottleConfig,
+    /// Palette from `--theme`, over the config's `ui.palette`
+    pub palette: Option<Palette>,
+    /// ASCII-only symbols from `--ascii`, on top of the config's `ui.ascii`
+    pub ascii: bool,
 }
 
 impl AppConfig {
     pub fn load(config_path: &str, output_path: Option<String>) -> Result<Self> {
-        let backup_config = BackupConfig::load(config_path)
+        let config_path = BackupConfig::find_config_file(std::path::Path::new(config_path))?;
+        let backup_config = BackupConfig::load(&config_path)
             .with_context(|| "Failed to load backup configuration")?;
         
-        let output_path = output_path.map(PathBuf::from);
+        let destination = output_path.as_deref().map(Destination::parse);
         
         Ok(Self {
             backup_config,
-            output_path,
+            config_path,
+            destination,
+            profile: None,
+            throttle: ThrottleConfig::default(),
+            palette: None,
+            ascii: false,
+        })
+    }
+
+    /// Settings to start with when no config exists: a starter config from
+    /// everything detected, which the first-run wizard narrows down before
+    /// writing it to the per-user config location
+    pub fn first_run(config_path: &str, output_path: Option<String>) -> Result<Self> {
+        let home = dirs::home_dir().context("Could not find the home directory")?;
+        let backup_config = first_run::starter_config(&first_run::detect(&home), &home)?;
+
+        Ok(Self {
+            backup_config,
+            config_path: first_run::config_path(std::path::Path::new(config_path)),
+            destination: output_path.as_deref().map(Destination::parse),
+            profile: None,
+            throttle: ThrottleConfig::default(),
+            palette: None,
+            ascii: false,
         })
     }
 }
 
+/// What to do once a remote archive has been downloaded
+enum AfterDownload {
+    /// Continue to the password or item selection
+    Browse,
+    /// Run the restore that was already confirmed
+    Restore,
+}
+
+/// Remote archive downloading in the background, shown as its own phase
+/// on the restore progress screen
+struct ArchiveDownload {
+    archive: ArchiveInfo,
+    local_path: PathBuf,
+    then: AfterDownload,
+    /// Screen, and its previous screen, to return to on failure or cancel
+    return_to: (AppState, Option<AppState>),
+    progress: UnboundedReceiver<RestoreProgress>,
+    task: tokio::task::JoinHandle<Result<()>>,
+}
+
+/// A backup running in the background
+struct RunningBackup {
+    progress: UnboundedReceiver<BackupProgress>,
+    task: tokio::task::JoinHandle<Result<BackupOutput>>,
+    job: BackupJob,
+}
+
+/// What a backup was started with, to copy, catalog and record the
+/// archive once it is written
+struct BackupJob {
+    selected_items: Vec<BackupItem>,
+    backup_mode: BackupMode,
+    recipients: Vec<String>,
+    strategy: Option<String>,
+    encrypted: bool,
+    destination: Option<Destination>,
+    copies: Vec<Destination>,
+    output_dir: Option<PathBuf>,
+    started: std::time::Instant,
+}
+
 pub struct App {
     pub config: AppConfig,
     pub state: AppStateManager,
     pub backend: BackupEngine,
+    /// Counts for the summary printed on exit
+    pub session: SessionStats,
     
     // UI screens
     main_menu: MainMenuScreen,
+    profile_selection: ProfileSelectionScreen,
     backup_mode_selection: BackupModeSelectionScreen,
     backup_item_selection: BackupItemSelectionScreen,
+    destination_selection: DestinationSelectionScreen,
+    preflight: PreflightScreen,
+    secret_review: SecretReviewScreen,
+    recipient_selection: RecipientSelectionScreen,
+    volume_selection: VolumeSelectionScreen,
     backup_password: BackupPasswordScreen,
+    backup_confirm: BackupConfirmScreen,
     backup_progress: BackupProgressScreen,
     backup_complete: BackupCompleteScreen,
     restore_archive_selection: RestoreArchiveSelectionScreen,
@@ -51,29 +176,65 @@ pub struct App {
     restore_item_selection: RestoreItemSelectionScreen,
     restore_progress: RestoreProgressScreen,
     restore_complete: RestoreCompleteScreen,
+    catalog_browser: CatalogBrowserScreen,
+    find_file: FindFileScreen,
+    file_history: FileHistoryScreen,
+    config_editor: ConfigEditorScreen,
+    config_history: ConfigHistoryScreen,
+    exclusion_editor: ExclusionEditorScreen,
+    fleet_overview: FleetOverviewScreen,
+    audit_history: AuditHistoryScreen,
+    first_run_wizard: FirstRunWizardScreen,
+    first_backup_guide: FirstBackupGuideScreen,
     help: HelpScreen,
     error: ErrorScreen,
+
+    /// Item sizes arriving from the background size scan
+    size_updates: Option<UnboundedReceiver<SizeUpdate>>,
+    /// Match counts for the patterns shown in the exclusion editor
+    exclusion_counts: Option<oneshot::Receiver<Vec<MatchCount>>>,
+    /// Measurements for the guide shown after the first-run wizard
+    first_estimate: Option<oneshot::Receiver<FirstBackupEstimate>>,
+    archive_download: Option<ArchiveDownload>,
+    running_backup: Option<RunningBackup>,
+    /// Free space where the next backup goes, for the status bar, and
+    /// when it was last asked for
+    free_space: Option<oneshot::Receiver<Option<u64>>>,
+    free_space_checked: Option<std::time::Instant>,
+    /// The terminal is below the minimum size: screens are replaced by a
+    /// note and every key but quit is ignored
+    too_small: bool,
 }
 
 impl App {
     pub fn new(config: AppConfig) -> Result<Self> {
         let mut state = AppStateManager::new();
         
-        // Set initial output path if provided
-        if let Some(ref path) = config.output_path {
-            state.backup_output_path = Some(path.clone());
+        // Set initial destination if provided
+        if let Some(ref destination) = config.destination {
+            state.backup_destination = Some(destination.clone());
         }
+        state.backup_compression = config.backup_config.compression.compression();
+        state.theme = Theme::from_config(config.backup_config.ui.as_ref()).overridden_by(config.palette, config.ascii);
+        state.backup_compression_level = config.backup_config.compression.level();
         
         let backend = BackupEngine::new()?;
         
-        Ok(Self {
+        let mut app = Self {
             config,
             state,
             backend,
             main_menu: MainMenuScreen::new(),
+            profile_selection: ProfileSelectionScreen::new(),
             backup_mode_selection: BackupModeSelectionScreen::new(),
             backup_item_selection: BackupItemSelectionScreen::new(),
+            destination_selection: DestinationSelectionScreen::new(),
+            preflight: PreflightScreen::new(),
+            secret_review: SecretReviewScreen::new(),
+            recipient_selection: RecipientSelectionScreen::new(),
+            volume_selection: VolumeSelectionScreen::new(),
             backup_password: BackupPasswordScreen::new(),
+            backup_confirm: BackupConfirmScreen::new(),
             backup_progress: BackupProgressScreen::new(),
             backup_complete: BackupCompleteScreen::new(),
             restore_archive_selection: RestoreArchiveSelectionScreen::new(),
@@ -81,25 +242,74 @@ impl App {
             restore_item_selection: RestoreItemSelectionScreen::new(),
             restore_progress: RestoreProgressScreen::new(),
             restore_complete: RestoreCompleteScreen::new(),
+            catalog_browser: CatalogBrowserScreen::new(),
+            find_file: FindFileScreen::new(),
+            file_history: FileHistoryScreen::new(),
+            config_editor: ConfigEditorScreen::new(),
+            config_history: ConfigHistoryScreen::new(),
+            exclusion_editor: ExclusionEditorScreen::new(),
+            fleet_overview: FleetOverviewScreen::new(),
+            audit_history: AuditHistoryScreen::new(),
+            first_run_wizard: FirstRunWizardScreen::new(),
+            first_backup_guide: FirstBackupGuideScreen::new(),
             help: HelpScreen::new(),
             error: ErrorScreen::new(),
-        })
+            size_updates: None,
+            exclusion_counts: None,
+            first_estimate: None,
+            archive_download: None,
+            free_space: None,
+            free_space_checked: None,
+            running_backup: None,
+            too_small: false,
+            session: SessionStats::default(),
+        };
+        app.load_strategies();
+        Ok(app)
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame) {
+        self.too_small = layout::too_small(frame.area());
+        if self.too_small {
+            layout::render_too_small(frame, frame.area(), &self.state.theme);
+            self.state.theme.apply_ascii(frame.buffer_mut());
+            return;
+        }
+
         match &self.state.current_state {
             AppState::MainMenu => {
                 self.main_menu.render(frame, &self.state);
             }
+            AppState::ProfileSelection => {
+                self.profile_selection.render(frame, &self.state);
+            }
             AppState::BackupModeSelection => {
                 self.backup_mode_selection.render(frame, &self.state);
             }
             AppState::BackupItemSelection => {
                 self.backup_item_selection.render(frame, &self.state);
             }
+            AppState::DestinationSelection => {
+                self.destination_selection.render(frame, &self.state);
+            }
+            AppState::Preflight => {
+                self.preflight.render(frame, &self.state);
+            }
+            AppState::SecretReview => {
+                self.secret_review.render(frame, &self.state);
+            }
+            AppState::RecipientSelection => {
+                self.recipient_selection.render(frame, &self.state);
+            }
+            AppState::VolumeSelection => {
+                self.volume_selection.render(frame, &self.state);
+            }
             AppState::BackupPasswordInput => {
                 self.backup_password.render(frame, &self.state);
             }
+            AppState::BackupConfirm => {
+                self.backup_confirm.render(frame, &self.state);
+            }
             AppState::BackupProgress => {
                 self.backup_progress.render(frame, &self.state);
             }
@@ -121,6 +331,36 @@ impl App {
             AppState::RestoreComplete => {
                 self.restore_complete.render(frame, &self.state);
             }
+            AppState::CatalogBrowser => {
+                self.catalog_browser.render(frame, &self.state);
+            }
+            AppState::FindFile => {
+                self.find_file.render(frame, &self.state);
+            }
+            AppState::FileHistory => {
+                self.file_history.render(frame, &self.state);
+            }
+            AppState::FirstRunWizard => {
+                self.first_run_wizard.render(frame, &self.state);
+            }
+            AppState::FirstBackupGuide => {
+                self.first_backup_guide.render(frame, &self.state);
+            }
+            AppState::ConfigEditor => {
+                self.config_editor.render(frame, &self.state);
+            }
+            AppState::ConfigHistory => {
+                self.config_history.render(frame, &self.state);
+            }
+            AppState::ExclusionEditor => {
+                self.exclusion_editor.render(frame, &self.state);
+            }
+            AppState::FleetOverview => {
+                self.fleet_overview.render(frame, &self.state);
+            }
+            AppState::AuditHistory => {
+                self.audit_history.render(frame, &self.state);
+            }
             AppState::Help => {
                 self.help.render(frame, &self.state);
             }
@@ -131,26 +371,48 @@ impl App {
                 // This state should trigger app exit
             }
         }
+        render_status_bar(
+            frame,
+            frame.area(),
+            &self.state.status_bar,
+            self.state.active_profile.as_deref(),
+            &self.state.theme,
+        );
+        self.state.theme.apply_ascii(frame.buffer_mut());
+    }
+
+    /// Theme from the config's `ui` section, with `--theme` and `--ascii`
+    /// over it
+    fn theme(&self) -> Theme {
+        Theme::from_config(self.config.backup_config.ui.as_ref()).overridden_by(self.config.palette, self.config.ascii)
     }
 
-    pub async fn handle_event(&mut self, event: Event) -> Result<bool> {
+    pub async fn handle_event(&mut self, event: TerminalEvent) -> Result<bool> {
+        let event = match event {
+            TerminalEvent::Input(event) => event,
+            TerminalEvent::Tick => {
+                self.on_tick();
+                return Ok(false);
+            }
+        };
         match event {
             Event::Key(key) => {
                 // Global key handlers
-                if key.modifiers.contains(KeyModifiers::CONTROL) {
-                    match key.code {
-                        KeyCode::Char('c') => {
-                            info!("Received Ctrl+C, exiting application");
-                            return Ok(true); // Exit
-                        }
-                        KeyCode::Char('h') => {
-                            self.state.transition_to(AppState::Help);
-                            return Ok(false);
-                        }
-                        _ => {}
+                match self.state.keymap.action(KeyContext::Global, &key) {
+                    Some(Action::Quit) => {
+                        info!("Received Ctrl+C, exiting application");
+                        return Ok(true); // Exit
                     }
+                    Some(Action::Help) if !self.too_small => {
+                        self.state.transition_to(AppState::Help);
+                        return Ok(false);
+                    }
+                    _ => {}
                 }
 
+                if self.too_small {
+                    return Ok(false);
+                }
                 self.handle_key_event(key).await?;
                 
                 // Check if we should exit after handling the key event
@@ -159,9 +421,10 @@ impl App {
                     return Ok(true); // Exit
                 }
             }
-            Event::Resize(_, _) => {
-                // Handle terminal resize
-                debug!("Terminal resized");
+            Event::Resize(width, height) => {
+                // Screens lay themselves out again on the next draw
+                self.too_small = layout::too_small(Rect::new(0, 0, width, height));
+                debug!("Terminal resized to {}x{}", width, height);
             }
             _ => {}
         }
@@ -169,20 +432,48 @@ impl App {
         Ok(false) // Continue running
     }
 
+    /// Advance animations and the status bar; the screen is drawn again
+    /// after every tick, so progress and time left stay current too
+    fn on_tick(&mut self) {
+        self.backup_progress.tick();
+        self.update_status_bar();
+    }
+
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         match &self.state.current_state {
             AppState::MainMenu => {
                 self.handle_main_menu_key(key).await?;
             }
+            AppState::ProfileSelection => {
+                self.handle_profile_selection_key(key).await?;
+            }
             AppState::BackupModeSelection => {
                 self.handle_backup_mode_selection_key(key).await?;
             }
             AppState::BackupItemSelection => {
                 self.handle_backup_item_selection_key(key).await?;
             }
+            AppState::DestinationSelection => {
+                self.handle_destination_selection_key(key).await?;
+            }
+            AppState::Preflight => {
+                self.handle_preflight_key(key).await?;
+            }
+            AppState::SecretReview => {
+                self.handle_secret_review_key(key).await?;
+            }
+            AppState::RecipientSelection => {
+                self.handle_recipient_selection_key(key).await?;
+            }
+            AppState::VolumeSelection => {
+                self.handle_volume_selection_key(key).await?;
+            }
             AppState::BackupPasswordInput => {
                 self.handle_backup_password_key(key).await?;
             }
+            AppState::BackupConfirm => {
+                self.handle_backup_confirm_key(key).await?;
+            }
             AppState::BackupProgress => {
                 self.handle_backup_progress_key(key).await?;
             }
@@ -204,6 +495,36 @@ impl App {
             AppState::RestoreComplete => {
                 self.handle_restore_complete_key(key).await?;
             }
+            AppState::CatalogBrowser => {
+                self.handle_catalog_browser_key(key).await?;
+            }
+            AppState::FindFile => {
+                self.handle_find_file_key(key).await?;
+            }
+            AppState::FileHistory => {
+                self.handle_file_history_key(key).await?;
+            }
+            AppState::FirstRunWizard => {
+                self.handle_first_run_wizard_key(key);
+            }
+            AppState::FirstBackupGuide => {
+                self.handle_first_backup_guide_key(key);
+            }
+            AppState::ConfigEditor => {
+                self.handle_config_editor_key(key).await?;
+            }
+            AppState::ConfigHistory => {
+                self.handle_config_history_key(key).await?;
+            }
+            AppState::ExclusionEditor => {
+                self.handle_exclusion_editor_key(key).await?;
+            }
+            AppState::FleetOverview => {
+                self.handle_fleet_overview_key(key).await?;
+            }
+            AppState::AuditHistory => {
+                self.handle_audit_history_key(key).await?;
+            }
             AppState::Help => {
                 self.handle_help_key(key).await?;
             }
@@ -218,34 +539,188 @@ impl App {
         Ok(())
     }
 
-    async fn handle_main_menu_key(&mut self, key: KeyEvent) -> Result<()> {
-        // Handle menu navigation and selection
-        if let Some(selected_key) = self.main_menu.handle_key(key) {
-            match selected_key {
-                '1' => {
-                    self.state.transition_to(AppState::BackupModeSelection);
+    /// Open the first-run wizard in place of the main menu. Nothing is
+    /// written until the user confirms.
+    pub fn start_first_run(&mut self) {
+        let home = dirs::home_dir().unwrap_or_default();
+        self.state.wizard_categories = first_run::detect(&home);
+        self.state.selection_undo.clear();
+        self.state.wizard_config_path = self.config.config_path.clone();
+        self.state.current_state = AppState::FirstRunWizard;
+    }
+
+    fn handle_first_run_wizard_key(&mut self, key: KeyEvent) {
+        let count = self.state.wizard_categories.len();
+
+        match self.state.keymap.action(KeyContext::FirstRunWizard, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(count, 10);
+            }
+            Some(Action::Toggle) => {
+                let index = self.state.selected_item_index;
+                if let Some(category) = self.state.wizard_categories.get(index) {
+                    if category.paths.is_empty() {
+                        let message = format!("Nothing found for {}", category.label);
+                        self.state.set_status(message);
+                    } else {
+                        self.state.change_selection(|state| {
+                            state.wizard_categories[index].selected ^= true;
+                        });
+                    }
                 }
-                '2' => {
-                    self.load_available_archives().await?;
-                    self.state.transition_to(AppState::RestoreArchiveSelection);
+            }
+            Some(Action::Undo) => self.undo_selection(),
+            Some(Action::Redo) => self.redo_selection(),
+            Some(Action::Confirm) => {
+                if !self.state.wizard_categories.iter().any(|category| category.selected) {
+                    self.state.set_status("Select at least one category to back up".to_string());
+                    return;
                 }
-                'q' => {
-                    info!("User requested exit from main menu");
-                    self.state.transition_to(AppState::Exit);
+                self.write_starter_config();
+            }
+            Some(Action::Quit) => {
+                self.state.transition_to(AppState::Exit);
+            }
+            _ => {}
+        }
+    }
+
+    fn write_starter_config(&mut self) {
+        let home = dirs::home_dir().unwrap_or_default();
+        let path = self.config.config_path.clone();
+        let written = first_run::starter_config(&self.state.wizard_categories, &home)
+            .and_then(|config| first_run::write(&config, &path).map(|_| config));
+
+        match written {
+            Ok(config) => {
+                info!("Wrote starter config to {}", path.display());
+                self.session.config_saves += 1;
+                let categories: Vec<&str> = self.state.wizard_categories
+                    .iter()
+                    .filter(|category| category.selected)
+                    .map(|category| category.name)
+                    .collect();
+                if let Err(e) = AuditLog::open_default().record("config_created", &path.to_string_lossy(), &categories.join(", ")) {
+                    error!("Failed to write audit log: {}", e);
                 }
-                _ => {}
+
+                self.config.backup_config = config.clone();
+                self.state.theme = self.theme();
+                self.state.wizard_categories.clear();
+                self.state.transition_to(AppState::FirstBackupGuide);
+                self.state.previous_state = None;
+                self.state.set_status(format!(
+                    "Starter config saved to {}; fine-tune it under Settings (5)",
+                    path.display()
+                ));
+
+                let (tx, rx) = oneshot::channel();
+                tokio::spawn(async move {
+                    let _ = tx.send(first_estimate::run(config, home).await);
+                });
+                self.state.first_estimate = None;
+                self.first_estimate = Some(rx);
             }
-        } else {
-            // Handle direct key presses (for backward compatibility)
-            match key.code {
-                KeyCode::Char('b') | KeyCode::Char('B') => {
-                    self.state.transition_to(AppState::BackupModeSelection);
+            Err(e) => {
+                error!("Failed to write starter config: {}", e);
+                self.state.set_status(format!("Failed to write config: {}", e));
+            }
+        }
+    }
+
+    fn handle_first_backup_guide_key(&mut self, key: KeyEvent) {
+        match self.state.keymap.action(KeyContext::FirstBackupGuide, &key) {
+            Some(Action::Confirm) => {
+                let Some(sample) = self.state.first_estimate.as_ref().and_then(|estimate| estimate.recommended()).cloned() else {
+                    return;
+                };
+                let mut edited = self.config.backup_config.clone();
+                edited.compression.format = sample.compression.as_str().to_string();
+                edited.compression.level = None;
+                match edited.save(&self.config.config_path) {
+                    Ok(_) => {
+                        info!("Set compression to {} from the first-backup estimate", sample.compression.as_str());
+                        self.session.config_saves += 1;
+                        self.config.backup_config = edited;
+                        self.state.backup_compression = sample.compression;
+                        self.state.backup_compression_level = sample.level;
+                        self.leave_first_backup_guide();
+                        self.state.set_status(format!("Backups will use {} compression", sample.compression.as_str()));
+                    }
+                    Err(e) => {
+                        error!("Failed to save compression: {}", e);
+                        self.state.set_status(format!("Failed to save compression: {}", e));
+                    }
+                }
+            }
+            Some(Action::Back) => self.leave_first_backup_guide(),
+            _ => {}
+        }
+    }
+
+    fn leave_first_backup_guide(&mut self) {
+        // Dropping the receiver lets a measurement still running finish unseen
+        self.first_estimate = None;
+        self.state.first_estimate = None;
+        self.state.transition_to(AppState::MainMenu);
+        self.state.previous_state = None;
+    }
+
+    async fn handle_main_menu_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.main_menu.strategies_focused() {
+            return self.handle_strategies_key(key).await;
+        }
+        let Some(action) = self.state.keymap.action(KeyContext::MainMenu, &key) else {
+            return Ok(());
+        };
+        if action == Action::NextField {
+            if !self.state.strategy_statuses.is_empty() {
+                self.main_menu.focus_strategies(true);
+                self.state.selected_item_index = 0;
+            }
+            return Ok(());
+        }
+        // Menu navigation, or the item picked
+        if let Some(selected) = self.main_menu.handle_action(action) {
+            match selected {
+                Action::Backup => {
+                    self.begin_backup().await?;
+                }
+                Action::Restore => self.open_archive_list().await,
+                Action::Catalog => {
+                    self.state.catalog_query.clear();
+                    self.search_catalog();
+                    self.state.transition_to(AppState::CatalogBrowser);
+                }
+                Action::FindFile => {
+                    self.state.find_query.clear();
+                    self.state.find_results.clear();
+                    self.state.find_version_index = 0;
+                    self.state.transition_to(AppState::FindFile);
+                }
+                Action::Settings => {
+                    self.state.config_form = Some(ConfigForm::from_config(&self.config.backup_config));
+                    self.state.config_edit_buffer = None;
+                    self.state.config_confirm_discard = false;
+                    self.state.transition_to(AppState::ConfigEditor);
+                }
+                Action::Machines => {
+                    self.sync_catalog().await;
+                    self.load_fleet();
+                    self.state.transition_to(AppState::FleetOverview);
+                }
+                Action::Exclusions => {
+                    self.load_exclusions(self.state.backup_mode.clone());
+                    self.state.transition_to(AppState::ExclusionEditor);
                 }
-                KeyCode::Char('r') | KeyCode::Char('R') => {
-                    self.load_available_archives().await?;
-                    self.state.transition_to(AppState::RestoreArchiveSelection);
+                Action::History => {
+                    self.load_audit_history();
+                    self.state.transition_to(AppState::AuditHistory);
                 }
-                KeyCode::Char('Q') | KeyCode::Esc => {
+                Action::Quit => {
                     info!("User requested exit from main menu");
                     self.state.transition_to(AppState::Exit);
                 }
@@ -255,144 +730,205 @@ impl App {
         Ok(())
     }
 
-    async fn handle_backup_mode_selection_key(&mut self, key: KeyEvent) -> Result<()> {
-        // Handle menu navigation and selection
-        if let Some(selected_key) = self.backup_mode_selection.handle_key(key) {
-            match selected_key {
-                '1' => {
-                    self.state.backup_mode = BackupMode::Secure;
-                    self.load_backup_items().await?;
-                    self.state.transition_to(AppState::BackupItemSelection);
-                }
-                '2' => {
-                    self.state.backup_mode = BackupMode::Complete;
-                    self.load_backup_items().await?;
-                    self.state.transition_to(AppState::BackupItemSelection);
+    async fn handle_strategies_key(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.state.strategy_statuses.len();
+        match self.state.keymap.action(KeyContext::Strategies, &key) {
+            Some(Action::Up) => self.state.move_selection_up(count),
+            Some(Action::Down) => self.state.move_selection_down(count, count.max(1)),
+            Some(Action::Confirm) => {
+                let name = self.state.strategy_statuses
+                    .get(self.state.selected_item_index)
+                    .map(|status| status.name.clone());
+                if let Some(name) = name {
+                    self.run_strategy(name).await?;
                 }
-                _ => {}
             }
-        } else {
-            // Handle direct key presses (for backward compatibility)
-            match key.code {
-                KeyCode::Char('s') | KeyCode::Char('S') => {
-                    self.state.backup_mode = BackupMode::Secure;
-                    self.load_backup_items().await?;
-                    self.state.transition_to(AppState::BackupItemSelection);
-                }
-                KeyCode::Char('c') | KeyCode::Char('C') => {
-                    self.state.backup_mode = BackupMode::Complete;
-                    self.load_backup_items().await?;
-                    self.state.transition_to(AppState::BackupItemSelection);
-                }
-                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
-                    self.state.go_back();
-                }
-                _ => {}
+            Some(Action::NextField | Action::Back) => self.main_menu.focus_strategies(false),
+            Some(Action::Quit) => {
+                info!("User requested exit from main menu");
+                self.state.transition_to(AppState::Exit);
             }
+            _ => {}
         }
         Ok(())
     }
 
-    async fn handle_backup_item_selection_key(&mut self, key: KeyEvent) -> Result<()> {
-        let item_count = self.state.backup_items.len();
-        
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.state.move_selection_up(item_count);
-            }
-            KeyCode::Down | KeyCode::Char('j') => {
-                self.state.move_selection_down(item_count, 10); // Assume 10 visible items
-            }
-            KeyCode::PageUp => {
-                self.state.page_up(10);
-            }
-            KeyCode::PageDown => {
-                self.state.page_down(item_count, 10);
-            }
-            KeyCode::Char(' ') => {
-                self.state.toggle_backup_item(self.state.selected_item_index);
-            }
-            KeyCode::Char('a') => {
-                self.state.select_all_backup_items(true);
-            }
-            KeyCode::Char('n') => {
-                self.state.select_all_backup_items(false);
+    /// Each configured strategy with its last run from the audit log
+    fn load_strategies(&mut self) {
+        let events = match AuditLog::open_default().read() {
+            Ok((events, _)) => events,
+            Err(e) => {
+                warn!("Failed to read audit log: {}", e);
+                Vec::new()
             }
-            KeyCode::Enter => {
-                if self.state.is_backup_ready() {
-                    if self.state.backup_mode == BackupMode::Complete {
-                        self.state.transition_to(AppState::BackupPasswordInput);
-                    } else {
-                        self.start_backup().await?;
-                    }
-                }
+        };
+        self.state.strategy_statuses = strategies::statuses(&self.config.backup_config, &events, chrono::Utc::now());
+    }
+
+    /// Back up now as a strategy says: its mode's usual items to the
+    /// `--output` destination, through the same checks as any backup
+    async fn run_strategy(&mut self, name: String) -> Result<()> {
+        let Some(strategy) = self.config.backup_config.backup_strategies.get(&name).cloned() else {
+            return Ok(());
+        };
+        let mode = match strategy.backup_mode() {
+            Ok(mode) => mode,
+            Err(e) => {
+                self.state.set_status(format!("Strategy {}: {}", name, e));
+                return Ok(());
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.state.go_back();
+        };
+        info!("Running backup strategy {}", name);
+        self.state.active_profile = None;
+        self.state.backup_destination = self.config.destination.clone();
+        self.state.backup_copies.clear();
+        self.state.backup_throttle = self.config.throttle.clone();
+        self.state.backup_mode = mode;
+        self.load_backup_items().await?;
+        self.state.active_strategy = Some(name);
+        self.state.transition_to(AppState::BackupItemSelection);
+        self.proceed_to_backup(true).await
+    }
+
+    /// Check a profile named on the command line before the UI starts
+    pub fn use_profile(&mut self, name: &str) -> Result<()> {
+        let profiles = &self.config.backup_config.profiles;
+        let Some(profile) = profiles.get(name) else {
+            let mut defined: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            defined.sort_unstable();
+            if defined.is_empty() {
+                anyhow::bail!("Unknown profile '{}'; the config defines no profiles", name);
             }
-            _ => {}
+            anyhow::bail!("Unknown profile '{}'; defined: {}", name, defined.join(", "));
+        };
+        profile
+            .backup_mode()
+            .with_context(|| format!("Invalid mode in profile '{}'", name))?;
+        self.config.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Start a backup: with the `--profile` one, from the profile list when
+    /// the config has profiles, or straight at mode selection
+    async fn begin_backup(&mut self) -> Result<()> {
+        self.state.active_strategy = None;
+        if let Some(name) = self.config.profile.clone() {
+            return self.apply_profile(Some(name)).await;
         }
+        if self.config.backup_config.profiles.is_empty() {
+            self.state.active_profile = None;
+            self.state.transition_to(AppState::BackupModeSelection);
+            return Ok(());
+        }
+
+        let mut choices: Vec<_> = self.config.backup_config.profiles
+            .iter()
+            .map(|(name, profile)| (name.clone(), profile.clone()))
+            .collect();
+        choices.sort_by(|a, b| a.0.cmp(&b.0));
+        self.state.profile_choices = choices;
+        self.state.transition_to(AppState::ProfileSelection);
         Ok(())
     }
 
-    async fn handle_backup_password_key(&mut self, key: KeyEvent) -> Result<()> {
-        // Password input is handled by the password screen
-        match self.backup_password.handle_key(key) {
-            Some(password) => {
-                self.state.backup_password = Some(password);
-                self.start_backup().await?;
+    /// Take the profile's destination and mode; without a mode the user
+    /// still picks one. `None` is a backup set up by hand.
+    async fn apply_profile(&mut self, name: Option<String>) -> Result<()> {
+        let profile = name
+            .as_ref()
+            .and_then(|name| self.config.backup_config.profiles.get(name))
+            .cloned()
+            .unwrap_or_default();
+        let mode = match profile.backup_mode() {
+            Ok(mode) => mode,
+            Err(e) => {
+                self.state.set_status(format!("Profile {}: {}", name.unwrap_or_default(), e));
+                return Ok(());
+            }
+        };
+
+        // `--output` wins over the profile's destinations, copies included
+        self.state.backup_destination = self.config.destination.clone().or_else(|| {
+            profile.resolved_destination().as_deref().map(Destination::parse)
+        });
+        self.state.backup_copies = match self.config.destination {
+            Some(_) => Vec::new(),
+            None => profile.resolved_copies(),
+        };
+        self.state.backup_throttle = profile.throttle.clone().unwrap_or_default().overridden_by(&self.config.throttle);
+        if let Some(name) = &name {
+            info!("Using backup profile {}", name);
+        }
+        self.state.active_profile = name;
+
+        match mode {
+            Some(mode) => {
+                self.state.backup_mode = mode;
+                self.load_backup_items().await?;
+                self.state.transition_to(AppState::BackupItemSelection);
             }
             None => {
-                if key.code == KeyCode::Esc {
-                    self.state.go_back();
-                }
+                self.state.transition_to(AppState::BackupModeSelection);
             }
         }
         Ok(())
     }
 
-    async fn handle_backup_progress_key(&mut self, _key: KeyEvent) -> Result<()> {
-        // Progress screen is mostly read-only
-        // Could add cancellation support here
-        Ok(())
-    }
+    async fn handle_profile_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        // Profiles plus the "No profile" entry
+        let count = self.state.profile_choices.len() + 1;
 
-    async fn handle_backup_complete_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Enter | KeyCode::Char(' ') => {
-                self.state.reset_backup_state();
-                self.state.transition_to(AppState::MainMenu);
+        match self.state.keymap.action(KeyContext::ProfileSelection, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(count);
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.state.transition_to(AppState::Exit);
+            Some(Action::Down) => {
+                self.state.move_selection_down(count, 10);
+            }
+            Some(Action::Confirm) => {
+                let name = self.state.profile_choices
+                    .get(self.state.selected_item_index)
+                    .map(|(name, _)| name.clone());
+                self.apply_profile(name).await?;
+            }
+            Some(Action::Back) => {
+                self.state.transition_to(AppState::MainMenu);
             }
             _ => {}
         }
         Ok(())
     }
 
-    async fn handle_restore_archive_selection_key(&mut self, key: KeyEvent) -> Result<()> {
-        let archive_count = self.state.available_archives.len();
-        
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.state.move_selection_up(archive_count);
+    async fn handle_backup_mode_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(action) = self.state.keymap.action(KeyContext::BackupModeSelection, &key) else {
+            return Ok(());
+        };
+        // Menu navigation, or the mode picked
+        match self.backup_mode_selection.handle_action(action) {
+            Some(Action::SecureMode) => {
+                self.state.backup_mode = BackupMode::Secure;
+                self.load_backup_items().await?;
+                self.state.transition_to(AppState::BackupItemSelection);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                self.state.move_selection_down(archive_count, 10);
+            Some(Action::CompleteMode) => {
+                self.state.backup_mode = BackupMode::Complete;
+                self.load_backup_items().await?;
+                self.state.transition_to(AppState::BackupItemSelection);
             }
-            KeyCode::Enter => {
-                if let Some(archive) = self.state.available_archives.get(self.state.selected_item_index) {
-                    self.state.selected_archive = Some(archive.clone());
-                    if archive.encrypted {
-                        self.state.transition_to(AppState::RestorePasswordInput);
-                    } else {
-                        self.load_restore_items().await?;
-                        self.state.transition_to(AppState::RestoreItemSelection);
-                    }
-                }
+            Some(Action::Compression) => {
+                self.cycle_compression();
+            }
+            Some(Action::LevelUp) => {
+                let range = self.state.backup_compression.level_range();
+                self.state.backup_compression_level = (self.state.backup_compression_level + 1).min(*range.end());
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
+            Some(Action::LevelDown) => {
+                let range = self.state.backup_compression.level_range();
+                self.state.backup_compression_level = self.state.backup_compression_level
+                    .saturating_sub(1)
+                    .max(*range.start());
+            }
+            Some(Action::Back) => {
                 self.state.go_back();
             }
             _ => {}
@@ -400,47 +936,156 @@ impl App {
         Ok(())
     }
 
-    async fn handle_restore_password_key(&mut self, key: KeyEvent) -> Result<()> {
-        match self.restore_password.handle_key(key) {
-            Some(password) => {
-                self.state.restore_password = Some(password);
-                self.load_restore_items().await?;
-                self.state.transition_to(AppState::RestoreItemSelection);
-            }
-            None => {
-                if key.code == KeyCode::Esc {
-                    self.state.go_back();
-                }
-            }
+    /// Step to the next compressor listed in `supported_compression`
+    fn cycle_compression(&mut self) {
+        let supported: Vec<Compression> = self.config.backup_config.validation.supported_compression
+            .iter()
+            .filter_map(|name| Compression::from_name(name))
+            .collect();
+        if supported.is_empty() {
+            return;
         }
-        Ok(())
+
+        let next = supported
+            .iter()
+            .position(|c| *c == self.state.backup_compression)
+            .map(|i| (i + 1) % supported.len())
+            .unwrap_or(0);
+        self.state.backup_compression = supported[next];
+        self.state.backup_compression_level = if supported[next] == self.config.backup_config.compression.compression() {
+            self.config.backup_config.compression.level()
+        } else {
+            supported[next].default_level()
+        };
     }
 
-    async fn handle_restore_item_selection_key(&mut self, key: KeyEvent) -> Result<()> {
-        let item_count = self.state.restore_items.len();
+    async fn handle_backup_item_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.destination_change.is_some() {
+            self.handle_destination_change_key(key).await;
+            return Ok(());
+        }
+        if self.state.critical_gaps.is_some() {
+            return self.handle_critical_gaps_key(key).await;
+        }
+        if self.state.file_browser.is_some() {
+            self.handle_file_browser_key(key);
+            return Ok(());
+        }
+        if self.state.custom_path_pending.is_some() {
+            self.handle_custom_path_key(key);
+            return Ok(());
+        }
+        if self.state.item_filter_input {
+            return self.handle_item_filter_key(key);
+        }
+        let rows = self.state.backup_tree();
+        let item_count = rows.len();
+        let visible_rows = self.backup_item_selection.visible_rows();
         
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
+        match self.state.keymap.action(KeyContext::BackupItemSelection, &key) {
+            Some(Action::Up) => {
                 self.state.move_selection_up(item_count);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                self.state.move_selection_down(item_count, 10);
+            Some(Action::Down) => {
+                self.state.move_selection_down(item_count, visible_rows);
             }
-            KeyCode::Char(' ') => {
-                self.state.toggle_restore_item(self.state.selected_item_index);
+            Some(Action::PageUp) => {
+                self.state.page_up(visible_rows);
             }
-            KeyCode::Char('a') => {
-                self.state.select_all_restore_items(true);
+            Some(Action::PageDown) => {
+                self.state.page_down(item_count, visible_rows);
             }
-            KeyCode::Char('n') => {
-                self.state.select_all_restore_items(false);
+            Some(Action::Toggle) => match rows.get(self.state.selected_item_index) {
+                Some(TreeRow::Group { items, .. }) => self.state.toggle_backup_group(items),
+                Some(TreeRow::Item { index, .. }) => self.state.toggle_backup_item(*index),
+                None => {}
+            },
+            Some(Action::Left) => {
+                // Fold an open group, otherwise step out to the one above
+                let at = self.state.selected_item_index;
+                match rows.get(at) {
+                    Some(TreeRow::Group { key, expanded: true, .. }) => self.state.set_backup_group_expanded(key, false),
+                    Some(_) => {
+                        if let Some(parent) = item_tree::parent(&rows, at) {
+                            self.state.selected_item_index = parent;
+                            self.state.scroll_offset = self.state.scroll_offset.min(parent);
+                        }
+                    }
+                    None => {}
+                }
             }
-            KeyCode::Enter => {
-                if self.state.is_restore_ready() {
-                    self.start_restore().await?;
+            Some(Action::Right) => {
+                if let Some(TreeRow::Group { key, .. }) = rows.get(self.state.selected_item_index) {
+                    self.state.set_backup_group_expanded(key, true);
+                }
+            }
+            Some(Action::SelectAll) => {
+                self.state.select_all_backup_items(true);
+            }
+            Some(Action::SelectNone) => {
+                self.state.select_all_backup_items(false);
+            }
+            Some(Action::Undo) => self.undo_selection(),
+            Some(Action::Redo) => self.redo_selection(),
+            Some(Action::Reclassify) => self.reclassify_backup_item(),
+            Some(Action::Volumes) => self.pick_volumes().await,
+            Some(Action::Largest) => {
+                // Each press moves down the largest items, then starts over
+                let largest = self.state.largest_backup_items(TOP_CONSUMERS);
+                let highlighted = self.state.highlighted_backup_item();
+                let next = largest
+                    .iter()
+                    .position(|(index, _)| Some(*index) == highlighted)
+                    .map_or(0, |at| (at + 1) % largest.len());
+                if let Some((index, _)) = largest.get(next) {
+                    if !item_tree::matches(&self.state.backup_items[*index], &self.state.item_filter) {
+                        self.state.set_item_filter(String::new());
+                    }
+                    self.state.reveal_backup_item(*index);
+                }
+            }
+            Some(Action::Filter) => {
+                self.state.item_filter_input = true;
+            }
+            Some(Action::Sort) => {
+                // Stay on the highlighted item, or group, wherever it moves
+                let key = match rows.get(self.state.selected_item_index) {
+                    Some(TreeRow::Group { key, .. }) => Some(key.clone()),
+                    _ => None,
+                };
+                let highlighted = self.state.highlighted_backup_item();
+                self.state.item_sort = self.state.item_sort.next();
+                if let Some(index) = highlighted {
+                    self.state.reveal_backup_item(index);
+                } else if let Some(key) = key {
+                    let row = self.state.backup_tree()
+                        .iter()
+                        .position(|row| matches!(row, TreeRow::Group { key: at, .. } if *at == key))
+                        .unwrap_or(0);
+                    self.state.selected_item_index = row;
+                    self.state.scroll_offset = row;
                 }
+                self.state.set_status(format!("Sorted by {}", self.state.item_sort.label()));
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
+            Some(Action::AddPath) => {
+                let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+                self.state.file_browser = Some(FileBrowser::new(home));
+            }
+            Some(Action::Destination) => {
+                self.scan_removable_drives().await;
+                self.state.item_selection_previous = self.state.previous_state.clone();
+                self.state.confirm_low_space = None;
+                self.state.transition_to(AppState::DestinationSelection);
+            }
+            Some(Action::Confirm) => {
+                if self.state.is_backup_ready() {
+                    self.proceed_to_backup(true).await?;
+                }
+            }
+            Some(Action::Back) if !self.state.item_filter.is_empty() => {
+                self.state.set_item_filter(String::new());
+            }
+            Some(Action::Back) => {
                 self.state.go_back();
             }
             _ => {}
@@ -448,88 +1093,2607 @@ impl App {
         Ok(())
     }
 
-    async fn handle_restore_progress_key(&mut self, _key: KeyEvent) -> Result<()> {
-        Ok(())
-    }
+    async fn handle_destination_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        let drive_count = self.state.removable_drives.len();
 
-    async fn handle_restore_complete_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Enter | KeyCode::Char(' ') => {
-                self.state.reset_restore_state();
-                self.state.transition_to(AppState::MainMenu);
+        match self.state.keymap.action(KeyContext::DestinationSelection, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(drive_count);
+                self.state.confirm_low_space = None;
             }
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.state.transition_to(AppState::Exit);
+            Some(Action::Down) => {
+                self.state.move_selection_down(drive_count, 10);
+                self.state.confirm_low_space = None;
+            }
+            Some(Action::Rescan) => {
+                self.scan_removable_drives().await;
+                self.state.selected_item_index = 0;
+                self.state.confirm_low_space = None;
+            }
+            Some(Action::Confirm) => {
+                let index = self.state.selected_item_index;
+                let Some(drive) = self.state.removable_drives.get(index).cloned() else {
+                    return Ok(());
+                };
+                // The estimate is before compression, so a drive short of it
+                // may still do; ask once more rather than refuse
+                let (_, estimate, _) = self.state.get_backup_summary();
+                if drive.has_room_for(estimate) == Some(false) && self.state.confirm_low_space != Some(index) {
+                    self.state.confirm_low_space = Some(index);
+                    self.state.set_status(format!(
+                        "⚠ {} has {} free and the backup may need up to {}; press Enter again to use it anyway",
+                        drive.name(),
+                        format_bytes(drive.free.unwrap_or_default()),
+                        format_bytes(estimate),
+                    ));
+                    return Ok(());
+                }
+
+                info!("Backing up to removable drive {} ({})", drive.name(), drive.device);
+                self.state.backup_destination = Some(Destination::Local(drive.mount_point.clone()));
+                self.return_to_item_selection();
+                self.state.set_status(format!("Backing up to {}", drive.name()));
+            }
+            Some(Action::Back) => {
+                self.return_to_item_selection();
             }
             _ => {}
         }
         Ok(())
     }
 
-    async fn handle_help_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.state.go_back();
+    async fn scan_removable_drives(&mut self) {
+        match removable::list_drives().await {
+            Ok(drives) => {
+                debug!("Found {} removable drives", drives.len());
+                self.state.removable_drives = drives;
+            }
+            Err(e) => {
+                warn!("Failed to list removable drives: {}", e);
+                self.state.removable_drives.clear();
+                self.state.set_status(format!("Could not list removable drives: {}", e));
             }
-            _ => {}
         }
-        Ok(())
     }
 
-    async fn handle_error_key(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ') => {
-                // Clear the error and go back to the previous state
-                self.state.error_message = None;
-                self.state.go_back();
-                // Force a full redraw by resetting the terminal
-                // This helps fix screen corruption issues
-                debug!("Returning from error state to: {:?}", self.state.current_state);
+    async fn handle_preflight_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.state.keymap.action(KeyContext::Preflight, &key) {
+            Some(Action::Confirm) if !preflight::has_failures(&self.state.preflight_checks) => {
+                let problems: Vec<String> = self.state.preflight_checks
+                    .iter()
+                    .filter(|check| check.status != CheckStatus::Pass)
+                    .map(|check| format!("{}: {}", check.name, check.detail))
+                    .collect();
+                if let Err(e) = AuditLog::open_default().record(
+                    "preflight_warnings_accepted",
+                    self.state.backup_mode.as_str(),
+                    &problems.join("; "),
+                ) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                self.state.preflight_checks.clear();
+                self.return_to_item_selection();
+                self.continue_to_backup().await?;
+            }
+            Some(Action::Back) => {
+                self.state.preflight_checks.clear();
+                self.return_to_item_selection();
             }
             _ => {}
         }
         Ok(())
     }
 
+    /// Back from the drive list or pre-flight checks, with item selection's
+    /// own way back intact
+    fn return_to_item_selection(&mut self) {
+        self.state.confirm_low_space = None;
+        self.state.transition_to(AppState::BackupItemSelection);
+        self.state.previous_state = self.state.item_selection_previous.take();
+    }
+
+    /// Move on from item selection: stop for critical items left out and
+    /// for pre-flight problems, then check the destination and ask for a
+    /// password for complete backups
+    async fn proceed_to_backup(&mut self, check_critical: bool) -> Result<()> {
+        if check_critical {
+            let home = dirs::home_dir().unwrap_or_default();
+            let selected: Vec<&BackupItem> = self.state.backup_items.iter().filter(|item| item.selected).collect();
+            let gaps = self.config.backup_config.critical_gaps(&self.state.backup_mode, &selected, &home);
+            if !gaps.is_empty() {
+                warn!("Backup would leave out {} critical item(s)", gaps.len());
+                self.state.critical_gaps = Some(gaps);
+                return Ok(());
+            }
+        }
+
+        let checks = self.run_preflight_checks().await;
+        if checks.iter().any(|check| check.status != CheckStatus::Pass) {
+            warn!("Pre-flight checks found problems; asking before the backup starts");
+            self.state.preflight_checks = checks;
+            self.state.item_selection_previous = self.state.previous_state.clone();
+            self.state.transition_to(AppState::Preflight);
+            return Ok(());
+        }
+        self.continue_to_backup().await
+    }
+
+    /// Check what the backup will read and where it will write, as
+    /// `start_backup` would do it
+    async fn run_preflight_checks(&mut self) -> Vec<CheckResult> {
+        let destination = self.state.backup_destination.clone();
+        let copies = self.state.backup_copies.clone();
+        // Without a destination the wrapper script writes to the working directory
+        let staging = staging_dir(destination.as_ref(), &copies).unwrap_or_else(|| PathBuf::from("."));
+        let mut local_dirs = vec![staging.clone()];
+        let mut remotes = Vec::new();
+        for target in destination.iter().chain(&copies) {
+            match target {
+                Destination::Local(path) if !local_dirs.contains(path) => local_dirs.push(path.clone()),
+                Destination::Local(_) => {}
+                remote => remotes.push(remote.to_string()),
+            }
+        }
+
+        let (_, estimate, _) = self.state.get_backup_summary();
+        let exclusions = match ExclusionSet::for_mode(&self.config.backup_config, &self.state.backup_mode) {
+            Ok(exclusions) => exclusions,
+            // start_backup reports this and refuses to go on
+            Err(_) => return Vec::new(),
+        };
+        let minimum_free = config_form::parse_size(&self.config.backup_config.validation.minimum_disk_space);
+        let home = dirs::home_dir().unwrap_or_default();
+        let selected = self.state.get_selected_backup_items();
+        let browsers = browser_profiles::check(&home, &selected, &exclusions);
+        let plan = PreflightPlan {
+            home,
+            items: selected.iter().map(|item| item.path.clone()).collect(),
+            exclusions,
+            estimate,
+            sizes_pending: self.state.backup_sizes_pending,
+            local_dirs,
+            remotes,
+            minimum_free,
+        };
+        let mut results = preflight::run_checks(&plan).await;
+        results.extend(browsers);
+        results
+    }
+
+    async fn continue_to_backup(&mut self) -> Result<()> {
+        if self.state.backup_mode == BackupMode::Complete {
+            if self.destination_trusted().await {
+                self.ask_encryption().await;
+            }
+        } else if self.review_secrets().await {
+            self.confirm_backup();
+        }
+        Ok(())
+    }
+
+    /// Scan what a secure-mode backup will read for credentials, stopping
+    /// on the review screen when any turn up; true to go straight on
+    async fn review_secrets(&mut self) -> bool {
+        self.state.secret_exclusions.clear();
+        let settings = self.config.backup_config.secret_scan.clone();
+        if self.state.backup_mode != BackupMode::Secure || settings.as_ref().is_some_and(|s| !s.enabled) {
+            return true;
+        }
+        let exclusions = match ExclusionSet::for_mode(&self.config.backup_config, &self.state.backup_mode) {
+            Ok(exclusions) => exclusions,
+            // start_backup reports this and refuses to go on
+            Err(_) => return true,
+        };
+        // A bad ignore pattern ignores nothing rather than hiding findings
+        let ignore = ExclusionSet::new(&settings.map(|s| s.ignore).unwrap_or_default()).unwrap_or_else(|e| {
+            warn!("Ignoring secret_scan.ignore: {:#}", e);
+            exclusions.clone()
+        });
+        let home = dirs::home_dir().unwrap_or_default();
+        let roots: Vec<PathBuf> = self.state.get_selected_backup_items().iter().map(|item| item.path.clone()).collect();
+        let findings = match tokio::task::spawn_blocking(move || secret_scan::scan(&exclusions, &ignore, &home, &roots)).await {
+            Ok(findings) => findings,
+            Err(e) => {
+                warn!("Secret scan failed: {}", e);
+                return true;
+            }
+        };
+        if findings.is_empty() {
+            return true;
+        }
+
+        warn!("{} selected file(s) look like they hold credentials", findings.len());
+        self.state.secret_findings = findings.into_iter().map(|finding| (finding, true)).collect();
+        // Back returns to item selection, even from the pre-flight screen
+        if self.state.current_state == AppState::BackupItemSelection {
+            self.state.item_selection_previous = self.state.previous_state.clone();
+        }
+        self.state.transition_to(AppState::SecretReview);
+        false
+    }
+
+    async fn handle_secret_review_key(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.state.secret_findings.len();
+        match self.state.keymap.action(KeyContext::SecretReview, &key) {
+            Some(Action::Up) => self.state.move_selection_up(count),
+            Some(Action::Down) => self.state.move_selection_down(count, 10),
+            Some(Action::PageUp) => self.state.page_up(10),
+            Some(Action::PageDown) if count > 0 => self.state.page_down(count, 10),
+            Some(Action::Toggle) => {
+                if let Some((_, excluded)) = self.state.secret_findings.get_mut(self.state.selected_item_index) {
+                    *excluded = !*excluded;
+                }
+            }
+            Some(Action::Confirm) => {
+                let findings = std::mem::take(&mut self.state.secret_findings);
+                let kept: Vec<String> = findings
+                    .iter()
+                    .filter(|(_, excluded)| !*excluded)
+                    .map(|(finding, _)| format!("{} ({})", finding.path.display(), finding.reason))
+                    .collect();
+                if let Err(e) = AuditLog::open_default().record(
+                    "secret_scan_reviewed",
+                    self.state.backup_mode.as_str(),
+                    &format!("{} flagged, {} kept: {}", findings.len(), kept.len(), kept.join(", ")),
+                ) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                self.return_to_item_selection();
+                self.state.secret_exclusions = findings
+                    .into_iter()
+                    .filter(|(_, excluded)| *excluded)
+                    .map(|(finding, _)| finding.path)
+                    .collect();
+                self.confirm_backup();
+            }
+            Some(Action::Back) => {
+                self.state.secret_findings.clear();
+                self.return_to_item_selection();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_critical_gaps_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.state.keymap.action(KeyContext::CriticalItems, &key) {
+            Some(Action::Yes) => {
+                let Some(gaps) = self.state.critical_gaps.take() else {
+                    return Ok(());
+                };
+                let details: Vec<String> = gaps
+                    .iter()
+                    .map(|gap| match gap.reason {
+                        CriticalGapReason::NotSelected => format!("{} (not selected)", gap.path),
+                        CriticalGapReason::Missing => format!("{} (missing)", gap.path),
+                    })
+                    .collect();
+                if let Err(e) = AuditLog::open_default().record(
+                    "critical_items_skipped",
+                    self.state.backup_mode.as_str(),
+                    &details.join(", "),
+                ) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                self.proceed_to_backup(false).await?;
+            }
+            Some(Action::Back) => {
+                self.state.critical_gaps = None;
+                self.state.set_status("Select the critical items, or acknowledge leaving them out".to_string());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Offer the GPG keys that can encrypt the archive, ticking those the
+    /// config names, or go straight to the passphrase when there are none
+    async fn ask_encryption(&mut self) {
+        let keys = match gpg_keys::list_encryption_keys().await {
+            Ok(keys) => keys,
+            Err(e) => {
+                warn!("Could not list GPG keys: {:#}", e);
+                Vec::new()
+            }
+        };
+        if keys.is_empty() {
+            self.ask_backup_password().await;
+            return;
+        }
+        let wanted = self.config.backup_config.encryption.as_ref().map(|e| e.recipients.as_slice()).unwrap_or_default();
+        self.state.recipient_choices = keys
+            .into_iter()
+            .map(|key| {
+                let selected = wanted.iter().any(|id| key.matches(id));
+                (key, selected)
+            })
+            .collect();
+        // Back returns to item selection, even from the pre-flight screen
+        if self.state.current_state == AppState::BackupItemSelection {
+            self.state.item_selection_previous = self.state.previous_state.clone();
+        }
+        self.state.transition_to(AppState::RecipientSelection);
+    }
+
+    async fn handle_recipient_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.state.recipient_choices.len();
+        match self.state.keymap.action(KeyContext::RecipientSelection, &key) {
+            Some(Action::Up) => self.state.move_selection_up(count),
+            Some(Action::Down) => self.state.move_selection_down(count, 10),
+            Some(Action::Toggle) => {
+                if let Some((_, selected)) = self.state.recipient_choices.get_mut(self.state.selected_item_index) {
+                    *selected = !*selected;
+                }
+            }
+            Some(Action::Confirm) => {
+                let recipients: Vec<String> = self.state.recipient_choices
+                    .iter()
+                    .filter(|(_, selected)| *selected)
+                    .map(|(key, _)| key.fingerprint.clone())
+                    .collect();
+                if recipients.is_empty() {
+                    self.state.set_status("Tick at least one key, or press p to use a passphrase".to_string());
+                    return Ok(());
+                }
+                info!("Encrypting the archive to {} key(s)", recipients.len());
+                self.state.backup_password = None;
+                self.state.backup_recipients = recipients;
+                self.state.clear_status();
+                self.confirm_backup();
+            }
+            Some(Action::UsePassphrase) => {
+                self.state.backup_recipients.clear();
+                self.ask_backup_password().await;
+            }
+            Some(Action::Back) => self.return_to_item_selection(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Offer the container volumes for the highlighted volume item,
+    /// ticking those it already copies
+    async fn pick_volumes(&mut self) {
+        let volumes = self.state.highlighted_backup_item().and_then(|index| Some((index, self.state.backup_items.get(index)?.volumes.clone()?)));
+        let Some((index, config)) = volumes else {
+            self.state.set_status("Only container volume items have volumes to pick".to_string());
+            return;
+        };
+        let choices = match container_volumes::backup_choices().await {
+            Ok(choices) => choices,
+            Err(e) => {
+                warn!("Could not list container volumes: {:#}", e);
+                self.state.set_status(format!("Could not list container volumes: {}", e));
+                return;
+            }
+        };
+        if choices.is_empty() {
+            self.state.set_status("There are no container volumes on this machine".to_string());
+            return;
+        }
+
+        self.state.volume_choices = choices
+            .into_iter()
+            .map(|choice| {
+                let selected = config.names.contains(&choice.name);
+                (choice, selected)
+            })
+            .collect();
+        self.state.volume_pause = config.pause_containers;
+        self.state.volume_item = Some(index);
+        self.state.item_selection_previous = self.state.previous_state.clone();
+        self.state.transition_to(AppState::VolumeSelection);
+    }
+
+    /// Offer the volumes in the selected archive for restoring
+    async fn pick_volumes_to_restore(&mut self) {
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+        match container_volumes::restore_choices(&archive.path, self.state.restore_password.as_ref()).await {
+            Ok(choices) if choices.is_empty() => {
+                self.state.set_status("No volumes were copied into this archive".to_string());
+            }
+            Ok(choices) => {
+                self.state.volume_choices = choices.into_iter().map(|choice| (choice, false)).collect();
+                self.state.volume_item = None;
+                self.state.clear_status();
+                self.state.transition_to(AppState::VolumeSelection);
+            }
+            Err(e) => {
+                warn!("Failed to read container volumes: {}", e);
+                self.state.set_status(e.to_string());
+            }
+        }
+    }
+
+    async fn handle_volume_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.state.volume_choices.len();
+        let context = if self.state.volume_item.is_some() { KeyContext::VolumeSelection } else { KeyContext::VolumeRestore };
+        match self.state.keymap.action(context, &key) {
+            Some(Action::Up) => self.state.move_selection_up(count),
+            Some(Action::Down) => self.state.move_selection_down(count, 10),
+            Some(Action::Toggle) => {
+                if let Some((_, selected)) = self.state.volume_choices.get_mut(self.state.selected_item_index) {
+                    *selected = !*selected;
+                }
+            }
+            Some(Action::PauseContainers) => self.state.volume_pause = !self.state.volume_pause,
+            Some(Action::Confirm) => match self.state.volume_item {
+                Some(index) => self.save_volume_selection(index),
+                None => self.restore_container_volumes().await,
+            },
+            Some(Action::Back) => match self.state.volume_item {
+                Some(_) => self.return_to_item_selection(),
+                None => self.state.transition_to(AppState::RestoreComplete),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Keep the ticked volumes for the item, in the config as well so
+    /// later backups copy them too. Volumes the config names that aren't
+    /// here now are kept.
+    fn save_volume_selection(&mut self, index: usize) {
+        let Some(item) = self.state.backup_items.get(index) else {
+            return;
+        };
+        let mut volumes = item.volumes.clone().unwrap_or_default();
+        volumes.names.retain(|name| !self.state.volume_choices.iter().any(|(choice, _)| &choice.name == name));
+        volumes.names.extend(
+            self.state.volume_choices
+                .iter()
+                .filter(|(_, selected)| *selected)
+                .map(|(choice, _)| choice.name.clone()),
+        );
+        volumes.pause_containers = self.state.volume_pause;
+
+        let mut edited = self.config.backup_config.clone();
+        if edited.set_volumes(&item.name, volumes.clone()) {
+            if let Err(e) = edited.save(&self.config.config_path) {
+                error!("Failed to save container volumes: {}", e);
+                self.state.set_status(format!("Failed to save the volumes: {}", e));
+                return;
+            }
+            self.session.config_saves += 1;
+            self.config.backup_config = edited;
+        }
+        info!("{} copies {} container volume(s)", item.name, volumes.names.len());
+
+        let pick = !volumes.names.is_empty() && !item.selected;
+        if let Some(item) = self.state.backup_items.get_mut(index) {
+            item.volumes = Some(volumes);
+        }
+        self.return_to_item_selection();
+        if pick {
+            self.state.toggle_backup_item(index);
+        }
+        self.state.reveal_backup_item(index);
+    }
+
+    /// Restore the ticked volumes and show how each went with the other
+    /// system state results
+    async fn restore_container_volumes(&mut self) {
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+        let names: Vec<String> = self.state.volume_choices
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(choice, _)| choice.name.clone())
+            .collect();
+        if names.is_empty() {
+            self.state.set_status("Tick the volumes to restore".to_string());
+            return;
+        }
+
+        let results = container_volumes::restore_volumes(&archive.path, self.state.restore_password.as_ref(), &names).await;
+        self.state.service_results.retain(|result| !container_volumes::is_volume_result(result));
+        self.state.service_results.extend(results);
+        self.state.clear_status();
+        self.state.transition_to(AppState::RestoreComplete);
+    }
+
+    /// Ask before replaying the database dumps in the selected archive,
+    /// which overwrites what the databases hold now
+    async fn ask_database_replay(&mut self) {
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+        match database_dumps::read_manifest(&archive.path, self.state.restore_password.as_ref()).await {
+            Ok(databases) if databases.is_empty() => {
+                self.state.set_status("No databases were dumped into this archive".to_string());
+            }
+            Ok(databases) => {
+                self.state.clear_status();
+                self.state.database_replay = Some(databases);
+            }
+            Err(e) => {
+                warn!("Failed to read database dumps: {}", e);
+                self.state.set_status(e.to_string());
+            }
+        }
+    }
+
+    /// Replay the dumps and show how each went with the other system
+    /// state results
+    async fn replay_databases(&mut self) {
+        let (Some(archive), Some(databases)) = (self.state.selected_archive.clone(), self.state.database_replay.take()) else {
+            return;
+        };
+        self.state.set_status(format!("Replaying {} database dump(s)...", databases.len()));
+        let results = database_dumps::replay(&archive.path, self.state.restore_password.as_ref(), &databases).await;
+        self.state.service_results.retain(|result| !database_dumps::is_database_result(result));
+        self.state.service_results.extend(results);
+        self.state.clear_status();
+    }
+
+    /// Show the passphrase screen, with any passphrase the keyring
+    /// remembers ready to use
+    async fn ask_backup_password(&mut self) {
+        let account = self.config.backup_config.keyring_account().map(str::to_string);
+        let remembered = match account {
+            Some(account) => match tokio::task::spawn_blocking(move || keyring_store::load(&account)).await {
+                Ok(Ok(remembered)) => remembered,
+                Ok(Err(e)) => {
+                    warn!("{:#}", e);
+                    self.state.set_status("The system keyring is unavailable; type the passphrase".to_string());
+                    None
+                }
+                Err(e) => {
+                    warn!("Keyring lookup failed: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        self.backup_password.offer_keyring(self.config.backup_config.keyring_account().is_some(), remembered);
+        // Back returns to item selection, even from the confirmation screen
+        if self.state.current_state == AppState::BackupItemSelection {
+            self.state.item_selection_previous = self.state.previous_state.clone();
+        }
+        self.state.transition_to(AppState::BackupPasswordInput);
+    }
+
+    /// Keep the passphrase in the keyring, or forget the one there, as the
+    /// passphrase screen's box says
+    async fn update_keyring(&mut self, password: &SecurePassword) {
+        let Some(account) = self.config.backup_config.keyring_account().map(str::to_string) else {
+            return;
+        };
+        let (remember, had) = (self.backup_password.remember(), self.backup_password.has_remembered());
+        if !remember && !had {
+            return;
+        }
+        let password = password.clone();
+        let updated = tokio::task::spawn_blocking(move || {
+            if remember {
+                keyring_store::store(&account, &password)
+            } else {
+                keyring_store::forget(&account)
+            }
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+        if let Err(e) = updated {
+            warn!("{:#}", e);
+        }
+    }
+
+    /// Whether a complete archive may be written to the destinations: each
+    /// must be the same drive or remote as the last time. A destination
+    /// seen for the first time is trusted from now on.
+    async fn destination_trusted(&mut self) -> bool {
+        let destinations: Vec<Destination> = self.state.backup_destination
+            .iter()
+            .chain(&self.state.backup_copies)
+            .cloned()
+            .collect();
+        for destination in destinations {
+            if !self.check_destination(destination).await {
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn check_destination(&mut self, destination: Destination) -> bool {
+        let fingerprint = match destination_trust::fingerprint(&destination).await {
+            Ok(fingerprint) => fingerprint,
+            Err(e) => {
+                warn!("Could not fingerprint {}: {}", destination, e);
+                self.state.set_status(format!("⚠ Could not verify {} is the usual destination: {}", destination, e));
+                return true;
+            }
+        };
+
+        match TrustStore::open_default().check(&destination, &fingerprint) {
+            Ok(TrustCheck::Trusted) => true,
+            Ok(TrustCheck::New) => {
+                info!("Trusting new destination {}", destination);
+                self.state.set_status(format!("Remembered {}; you'll be warned if it changes", destination));
+                true
+            }
+            Ok(TrustCheck::Changed(changes)) => {
+                warn!("Destination {} no longer matches its trusted fingerprint", destination);
+                self.state.destination_change = Some(DestinationChange {
+                    destination,
+                    fingerprint,
+                    changes,
+                });
+                false
+            }
+            Err(e) => {
+                error!("Failed to check trusted destinations: {}", e);
+                self.state.set_status(format!("Failed to check trusted destinations: {}", e));
+                false
+            }
+        }
+    }
+
+    async fn handle_destination_change_key(&mut self, key: KeyEvent) {
+        match self.state.keymap.action(KeyContext::DestinationChange, &key) {
+            Some(Action::Trust) => {
+                let Some(change) = self.state.destination_change.take() else {
+                    return;
+                };
+                if let Err(e) = TrustStore::open_default().trust(&change.destination, &change.fingerprint) {
+                    error!("Failed to trust destination: {}", e);
+                    self.state.set_status(format!("Failed to trust destination: {}", e));
+                    return;
+                }
+                let details: Vec<String> = change.changes.iter().map(|c| c.field.clone()).collect();
+                if let Err(e) = AuditLog::open_default().record(
+                    "destination_retrusted",
+                    &change.destination.to_string(),
+                    &format!("changed: {}", details.join(", ")),
+                ) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                // Other destinations may still need checking
+                if self.destination_trusted().await {
+                    self.ask_encryption().await;
+                }
+            }
+            Some(Action::Back) => {
+                self.state.destination_change = None;
+                self.state.set_status("Backup cancelled; check the destination".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_backup_password_key(&mut self, key: KeyEvent) -> Result<()> {
+        // Password input is handled by the password screen
+        let action = self.state.keymap.action(KeyContext::BackupPassword, &key);
+        match self.backup_password.handle_key(action, key) {
+            Some(password) => {
+                self.update_keyring(&password).await;
+                self.state.backup_recipients.clear();
+                self.state.backup_password = Some(password);
+                self.confirm_backup();
+            }
+            None => {
+                if action == Some(Action::Back) {
+                    if self.state.previous_state == Some(AppState::BackupItemSelection) {
+                        self.return_to_item_selection();
+                    } else {
+                        self.state.go_back();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Show what the backup will do before it starts
+    fn confirm_backup(&mut self) {
+        // Back returns to item selection, even from the pre-flight screen
+        if self.state.current_state == AppState::BackupItemSelection {
+            self.state.item_selection_previous = self.state.previous_state.clone();
+        }
+        self.state.transition_to(AppState::BackupConfirm);
+    }
+
+    async fn handle_backup_confirm_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.state.keymap.action(KeyContext::BackupConfirm, &key) {
+            Some(Action::Confirm) => self.start_backup().await?,
+            Some(Action::Back) => {
+                self.state.backup_password = None;
+                self.state.backup_recipients.clear();
+                self.return_to_item_selection();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_backup_progress_key(&mut self, _key: KeyEvent) -> Result<()> {
+        // Progress screen is mostly read-only
+        // Could add cancellation support here
+        Ok(())
+    }
+
+    async fn handle_backup_complete_key(&mut self, key: KeyEvent) -> Result<()> {
+        let shown = self.state.visible_item_reports().len();
+
+        match self.state.keymap.action(KeyContext::BackupComplete, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(shown);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(shown, 10);
+            }
+            Some(Action::PageUp) => {
+                self.state.page_up(10);
+            }
+            Some(Action::PageDown) if shown > 0 => {
+                self.state.page_down(shown, 10);
+            }
+            Some(Action::Filter) => {
+                self.state.item_failures_only = !self.state.item_failures_only;
+                self.state.selected_item_index = 0;
+                self.state.scroll_offset = 0;
+            }
+            Some(Action::SaveReport) => self.save_report(ReportFormat::Markdown),
+            Some(Action::SaveHtmlReport) => self.save_report(ReportFormat::Html),
+            Some(Action::RecoveryKit) => self.save_recovery_kit(),
+            Some(Action::Confirm) => {
+                self.state.reset_backup_state();
+                self.state.transition_to(AppState::MainMenu);
+            }
+            Some(Action::Quit) => {
+                self.state.transition_to(AppState::Exit);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Save the report of the backup or restore just finished, with the
+    /// checks run on the restore complete screen since
+    fn save_report(&mut self, format: ReportFormat) {
+        let Some(mut report) = self.state.completion_report.clone() else {
+            return;
+        };
+        if self.state.current_state == AppState::RestoreComplete {
+            report.warnings.extend(
+                self.state.credential_checks
+                    .iter()
+                    .chain(&self.state.service_results)
+                    .filter(|check| check.status != CheckStatus::Pass)
+                    .map(|check| format!("{}: {}", check.name, check.detail)),
+            );
+            if self.state.restore_rolled_back {
+                report.warnings.push("The restore was rolled back afterwards".to_string());
+            }
+        }
+
+        match report::default_path(report.operation, format).and_then(|path| report.save(&path).map(|_| path)) {
+            Ok(path) => {
+                info!("Saved {} report to {}", report.operation.to_lowercase(), path.display());
+                self.state.set_status(format!("Report saved to {}", path.display()));
+            }
+            Err(e) => {
+                error!("Failed to save report: {}", e);
+                self.state.set_status(format!("Failed to save report: {}", e));
+            }
+        }
+    }
+
+    /// Write a printable recovery kit for the archive just made
+    fn save_recovery_kit(&mut self) {
+        let Some(report) = &self.state.completion_report else {
+            return;
+        };
+        let Some(archive) = &report.archive else {
+            return;
+        };
+        let mut kit = RecoveryKit::new(archive, &report.mode);
+        kit.size = report.archive_size;
+        kit.checksum = report.checksum.clone();
+        kit.locations = report.destinations.iter().filter_map(|result| result.outcome.clone().ok()).collect();
+        kit.passphrase = report.encrypted && self.state.backup_recipients.is_empty();
+        kit.keys = self.state.backup_recipients
+            .iter()
+            .map(|fingerprint| KitKey {
+                fingerprint: fingerprint.clone(),
+                name: self.state.recipient_choices
+                    .iter()
+                    .find(|(key, _)| key.matches(fingerprint))
+                    .map(|(key, _)| key.name().to_string()),
+            })
+            .collect();
+
+        match kit.save() {
+            Ok(path) => {
+                info!("Saved recovery kit to {}", path.display());
+                self.state.set_status(format!("Recovery kit saved to {}; print it and keep it apart from the backups", path.display()));
+            }
+            Err(e) => {
+                error!("Failed to save recovery kit: {}", e);
+                self.state.set_status(format!("Failed to save recovery kit: {}", e));
+            }
+        }
+    }
+
+    async fn handle_restore_archive_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        let archive_count = self.state.available_archives.len();
+        
+        match self.state.keymap.action(KeyContext::RestoreArchiveSelection, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(archive_count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(archive_count, self.restore_archive_selection.visible_rows());
+            }
+            Some(Action::Confirm) => {
+                if let Some(archive) = self.state.available_archives.get(self.state.selected_item_index).cloned() {
+                    if let Err(e) = self.open_archive(archive).await {
+                        error!("Failed to open archive: {}", e);
+                        self.state.set_error(AppError::from_anyhow(ErrorCategory::Archive, "Failed to open archive", &e));
+                    }
+                }
+            }
+            Some(Action::Sort) => {
+                // Stay on the highlighted archive wherever it moves
+                let highlighted = self.state.available_archives
+                    .get(self.state.selected_item_index)
+                    .map(|archive| (archive.path.clone(), archive.remote.clone()));
+                self.state.archive_sort = self.state.archive_sort.next();
+                self.state.archive_sort.sort(&mut self.state.available_archives);
+                let at = self.state.available_archives
+                    .iter()
+                    .position(|archive| Some((archive.path.clone(), archive.remote.clone())) == highlighted);
+                if let Some(at) = at {
+                    self.state.selected_item_index = at;
+                }
+            }
+            Some(Action::Back) => {
+                self.state.go_back();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_restore_password_key(&mut self, key: KeyEvent) -> Result<()> {
+        let action = self.state.keymap.action(KeyContext::RestorePassword, &key);
+        match self.restore_password.handle_key(action, key) {
+            Some(password) => {
+                self.state.restore_password = Some(password);
+                self.load_restore_items().await?;
+                self.state.transition_to(AppState::RestoreItemSelection);
+            }
+            None => {
+                if action == Some(Action::Back) {
+                    self.state.go_back();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the highlighted item to the next security level and keep that
+    /// in the config's `security_classifications`
+    fn reclassify_backup_item(&mut self) {
+        let Some(item) = self.state.highlighted_backup_item().and_then(|index| self.state.backup_items.get(index)) else {
+            return;
+        };
+        if item.is_generated() {
+            self.state.set_status("Command output, package lists and volumes take their application's security level; change it in Settings".to_string());
+            return;
+        }
+        let path = item.path.to_string_lossy().to_string();
+        let level = item.security_level.next();
+
+        let mut edited = self.config.backup_config.clone();
+        edited.classify(&path, level.clone());
+        if let Err(e) = edited.save(&self.config.config_path) {
+            error!("Failed to save security classification: {}", e);
+            self.state.set_status(format!("Failed to save the security level: {}", e));
+            return;
+        }
+        info!("Classified {} as {} security", path, level.as_str());
+        self.session.config_saves += 1;
+        self.config.backup_config = edited;
+        if let Err(e) = AuditLog::open_default().record("security_reclassified", &path, level.as_str()) {
+            warn!("Failed to write audit log: {}", e);
+        }
+
+        for item in self.state.backup_items.iter_mut().filter(|item| item.path.to_string_lossy() == path) {
+            item.security_level = level.clone();
+        }
+        // Secure archives aren't encrypted, so high items stay out of them
+        if level == SecurityLevel::High && self.state.backup_mode == BackupMode::Secure {
+            self.state.change_selection(|state| {
+                for item in state.backup_items.iter_mut().filter(|item| item.path.to_string_lossy() == path) {
+                    item.selected = false;
+                }
+            });
+            self.state.set_status(format!("{} is now high security: secure backups leave it out", path));
+        } else {
+            self.state.set_status(format!("{} is now {} security", path, level.as_str()));
+        }
+    }
+
+    fn handle_file_browser_key(&mut self, key: KeyEvent) {
+        let Some(action) = self.state.keymap.action(KeyContext::FileBrowser, &key) else {
+            return;
+        };
+        if action == Action::Back {
+            self.state.file_browser = None;
+            return;
+        }
+        let picked = self.state.file_browser.as_mut().and_then(|browser| browser.handle_action(action));
+        if let Some(path) = picked {
+            self.state.file_browser = None;
+            self.state.custom_path_pending = Some(path);
+        }
+    }
+
+    fn handle_custom_path_key(&mut self, key: KeyEvent) {
+        match self.state.keymap.action(KeyContext::CustomPath, &key) {
+            Some(Action::Yes) => self.add_custom_path(true),
+            Some(Action::Confirm) => self.add_custom_path(false),
+            Some(Action::Back) => self.state.custom_path_pending = None,
+            _ => {}
+        }
+    }
+
+    /// Add the path picked in the browser to this backup, ticked, and when
+    /// `persist` to the mode's paths in the config
+    fn add_custom_path(&mut self, persist: bool) {
+        let Some(path) = self.state.custom_path_pending.take() else {
+            return;
+        };
+        let path = path.to_string_lossy().to_string();
+        let mut item = self.config.backup_config.path_item(&path, CUSTOM_CATEGORY);
+        // Secure archives aren't encrypted, so high items stay out of them
+        if item.security_level == SecurityLevel::High && self.state.backup_mode == BackupMode::Secure {
+            self.state.set_status(format!("{} is high security: secure backups leave it out, use complete mode", path));
+            return;
+        }
+
+        let mode = self.state.backup_mode.clone();
+        if persist {
+            let mut edited = self.config.backup_config.clone();
+            if edited.add_custom_path(&mode, &path) {
+                if let Err(e) = edited.save(&self.config.config_path) {
+                    error!("Failed to save custom path: {}", e);
+                    self.state.set_status(format!("Failed to save {} to the config: {}", path, e));
+                    return;
+                }
+                info!("Added {} to the {} mode's paths", path, mode.as_str());
+                self.session.config_saves += 1;
+                self.config.backup_config = edited;
+                if let Err(e) = AuditLog::open_default().record("backup_path_added", &path, mode.as_str()) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+            }
+        }
+
+        let index = match self.state.backup_items.iter().position(|existing| existing.path == item.path) {
+            Some(index) => index,
+            None => {
+                item.exists = true;
+                self.state.backup_items.push(item);
+                // Earlier selections don't cover the new item
+                self.state.selection_undo.clear();
+                self.state.backup_items.len() - 1
+            }
+        };
+        if !self.state.backup_items[index].selected {
+            self.state.toggle_backup_item(index);
+        }
+        if !item_tree::matches(&self.state.backup_items[index], &self.state.item_filter) {
+            self.state.set_item_filter(String::new());
+        }
+        self.state.reveal_backup_item(index);
+
+        // Restart the size scan with what it hadn't sized yet, and the new item
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let to_size: Vec<(PathBuf, PathBuf)> = self.state.backup_items
+            .iter()
+            .filter(|item| item.exists && !item.is_generated() && item.size.is_none())
+            .map(|item| (item.path.clone(), home.join(&item.path)))
+            .collect();
+        self.state.backup_sizes_pending = to_size.len();
+        self.size_updates = Some(dir_size::spawn_size_scan(to_size));
+
+        self.state.set_status(if persist {
+            format!("Added ~/{} to this backup and to {} mode backups", path, mode.as_str())
+        } else {
+            format!("Added ~/{} to this backup", path)
+        });
+    }
+
+    fn undo_selection(&mut self) {
+        if !self.state.undo_selection() {
+            self.state.set_status("Nothing to undo".to_string());
+        }
+    }
+
+    fn redo_selection(&mut self) {
+        if !self.state.redo_selection() {
+            self.state.set_status("Nothing to redo".to_string());
+        }
+    }
+
+    async fn handle_restore_item_selection_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.confirm_privileged_restore {
+            return self.handle_privileged_confirm_key(key).await;
+        }
+        if self.state.restore_diff.is_some() {
+            return self.handle_restore_diff_key(key).await;
+        }
+        if self.state.live_changes.is_some() {
+            return self.handle_live_changes_key(key);
+        }
+        if self.state.restore_plan.is_some() {
+            return self.handle_restore_plan_key(key).await;
+        }
+        if self.state.remote_restore_host.is_some() {
+            return self.handle_remote_restore_key(key).await;
+        }
+        if self.state.extract_item_dest.is_some() {
+            return self.handle_extract_item_key(key).await;
+        }
+
+        if self.state.item_filter_input {
+            return self.handle_item_filter_key(key);
+        }
+        let visible = self.state.visible_restore_items();
+        let item_count = visible.len();
+        
+        match self.state.keymap.action(KeyContext::RestoreItemSelection, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(item_count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(item_count, self.restore_item_selection.visible_rows());
+            }
+            Some(Action::Toggle) => {
+                if let Some(&index) = visible.get(self.state.selected_item_index) {
+                    self.state.toggle_restore_item(index);
+                }
+            }
+            Some(Action::SelectAll) => {
+                self.state.select_all_restore_items(true);
+            }
+            Some(Action::SelectNone) => {
+                self.state.select_all_restore_items(false);
+            }
+            Some(Action::Undo) => self.undo_selection(),
+            Some(Action::Redo) => self.redo_selection(),
+            Some(Action::Diff) => {
+                self.show_restore_diff().await;
+            }
+            Some(Action::Changes) => {
+                self.show_live_changes().await;
+            }
+            Some(Action::Extract) => {
+                if let Some(item) = self.state.highlighted_restore_item() {
+                    self.state.extract_item_dest = Some(item.restore_path.to_string_lossy().to_string());
+                }
+            }
+            Some(Action::PushToHost) if self.state.is_restore_ready() => {
+                self.state.remote_restore_host = Some(String::new());
+            }
+            Some(Action::Metadata) => {
+                self.state.restore_metadata = self.state.restore_metadata.toggled();
+                self.state.set_status(match self.state.restore_metadata {
+                    RestoreMetadata::Apply => "Owners, ACLs and extended attributes will be restored as archived".to_string(),
+                    RestoreMetadata::Skip => "Files will belong to you and take the ACLs and SELinux labels of where they land".to_string(),
+                });
+            }
+            Some(Action::DryRun) => {
+                self.state.restore_dry_run = !self.state.restore_dry_run;
+                self.state.set_status(if self.state.restore_dry_run {
+                    "Dry run: Enter shows what the restore would write, without writing anything".to_string()
+                } else {
+                    "Dry run off: Enter restores".to_string()
+                });
+            }
+            Some(Action::Confirm) if self.state.restore_dry_run => {
+                if self.state.is_restore_ready() {
+                    self.show_restore_plan().await;
+                }
+            }
+            Some(Action::Confirm) => self.confirm_restore().await?,
+            Some(Action::Filter) => {
+                self.state.item_filter_input = true;
+            }
+            Some(Action::Back) if !self.state.item_filter.is_empty() => {
+                self.state.set_item_filter(String::new());
+            }
+            Some(Action::Back) => {
+                self.state.go_back();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Typing the item filter; the list narrows as it changes
+    fn handle_item_filter_key(&mut self, key: KeyEvent) -> Result<()> {
+        match (self.state.keymap.action(KeyContext::ItemFilter, &key), key.code) {
+            (Some(Action::Confirm), _) => {
+                self.state.item_filter_input = false;
+            }
+            (Some(Action::Back), _) => {
+                self.state.item_filter_input = false;
+                self.state.set_item_filter(String::new());
+            }
+            (_, KeyCode::Backspace) => {
+                let mut filter = self.state.item_filter.clone();
+                filter.pop();
+                self.state.set_item_filter(filter);
+            }
+            (_, KeyCode::Char(c)) => {
+                let filter = format!("{}{}", self.state.item_filter, c);
+                self.state.set_item_filter(filter);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn confirm_restore(&mut self) -> Result<()> {
+        if self.state.is_restore_ready() {
+            if self.state.selected_privileged_entries().is_empty() {
+                self.start_restore().await?;
+            } else {
+                // setuid/setgid/capability files need an explicit decision
+                self.state.confirm_privileged_restore = true;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_restore_plan_key(&mut self, key: KeyEvent) -> Result<()> {
+        let line_count = match &self.state.restore_plan {
+            Some(Ok(plan)) => plan.paths.len() as u16,
+            _ => 0,
+        };
+        let max_scroll = line_count.saturating_sub(1);
+
+        match self.state.keymap.action(KeyContext::RestoreDryRun, &key) {
+            Some(Action::Up) => {
+                self.state.restore_plan_scroll = self.state.restore_plan_scroll.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.state.restore_plan_scroll = (self.state.restore_plan_scroll + 1).min(max_scroll);
+            }
+            Some(Action::PageUp) => {
+                self.state.restore_plan_scroll = self.state.restore_plan_scroll.saturating_sub(10);
+            }
+            Some(Action::PageDown) => {
+                self.state.restore_plan_scroll = (self.state.restore_plan_scroll + 10).min(max_scroll);
+            }
+            Some(Action::Confirm) if matches!(self.state.restore_plan, Some(Ok(_))) => {
+                self.state.restore_plan = None;
+                self.state.restore_dry_run = false;
+                self.confirm_restore().await?;
+            }
+            Some(Action::Back) => {
+                self.state.restore_plan = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Simulate restoring the selected items: list the files and
+    /// directories it would create and the files it would overwrite
+    async fn show_restore_plan(&mut self) {
+        self.state.restore_plan_scroll = 0;
+        if let Err(e) = self.ensure_archive_fetched().await {
+            self.state.restore_plan = Some(Err(format!("Failed to fetch remote archive: {}", e)));
+            return;
+        }
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+        let items: Vec<RestoreItem> = self.state.get_selected_restore_items().into_iter().cloned().collect();
+
+        let plan = match live_changes::members(&archive.path, self.state.restore_password.as_ref()).await {
+            Ok(members) => tokio::task::spawn_blocking(move || {
+                let items: Vec<&RestoreItem> = items.iter().collect();
+                restore_plan::plan(&members, &items)
+            })
+            .await
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = &plan {
+            warn!("Could not simulate restoring from {}: {}", archive.name, e);
+        }
+        self.state.restore_plan = Some(plan);
+    }
+
+    async fn handle_remote_restore_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.remote_restore_result.is_some() {
+            self.state.remote_restore_result = None;
+            self.state.remote_restore_host = None;
+            return Ok(());
+        }
+
+        match (self.state.keymap.action(KeyContext::RemoteRestore, &key), key.code) {
+            (Some(Action::Confirm), _) => {
+                self.push_restore().await;
+            }
+            (Some(Action::Back), _) => {
+                self.state.remote_restore_host = None;
+            }
+            (_, KeyCode::Backspace) => {
+                if let Some(host) = &mut self.state.remote_restore_host {
+                    host.pop();
+                }
+            }
+            (_, KeyCode::Char(c)) => {
+                if let Some(host) = &mut self.state.remote_restore_host {
+                    host.push(c);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Restore the selected items onto another machine over SSH
+    async fn push_restore(&mut self) {
+        if let Err(e) = self.ensure_archive_fetched().await {
+            self.state.remote_restore_result = Some(Err(format!("Failed to fetch remote archive: {}", e)));
+            return;
+        }
+        let (Some(archive), Some(host)) = (
+            self.state.selected_archive.clone(),
+            self.state.remote_restore_host.clone(),
+        ) else {
+            return;
+        };
+
+        let remote = match RemoteRestore::new(&host) {
+            Ok(remote) => remote,
+            Err(e) => {
+                self.state.remote_restore_result = Some(Err(e.to_string()));
+                return;
+            }
+        };
+
+        let items = self.state.get_selected_restore_items();
+        let all_selected = items.len() == self.state.restore_items.len();
+        let result = remote
+            .push(&archive, &items, all_selected, self.state.restore_password.as_ref(), self.state.restore_metadata)
+            .await;
+
+        let details = format!("{} items from {}", items.len(), archive.name);
+        self.session.record_restore(items.len(), result.is_ok());
+        let audit = AuditEvent::new("restore_remote", remote.host(), &details).with_operation(Operation {
+            mode: archive.mode.as_str().to_string(),
+            items: items.iter().map(|item| item.original_path.display().to_string()).collect(),
+            destination: Some(remote.host().to_string()),
+            result: operation_result(&result),
+            archive_checksum: None,
+            strategy: None,
+        });
+        if let Err(e) = AuditLog::open_default().append(audit) {
+            warn!("Failed to write audit log: {}", e);
+        }
+        self.state.remote_restore_result = Some(match result {
+            Ok(_) => Ok(format!("Restored {} onto {}", details, remote.host())),
+            Err(e) => {
+                error!("Remote restore failed: {}", e);
+                Err(e.to_string())
+            }
+        });
+    }
+
+    async fn handle_extract_item_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.extract_item_result.is_some() {
+            self.state.extract_item_result = None;
+            self.state.extract_item_dest = None;
+            return Ok(());
+        }
+
+        match (self.state.keymap.action(KeyContext::ExtractItem, &key), key.code) {
+            (Some(Action::Confirm), _) => {
+                self.extract_item().await;
+            }
+            (Some(Action::Back), _) => {
+                self.state.extract_item_dest = None;
+            }
+            (_, KeyCode::Backspace) => {
+                if let Some(dest) = &mut self.state.extract_item_dest {
+                    dest.pop();
+                }
+            }
+            (_, KeyCode::Char(c)) => {
+                if let Some(dest) = &mut self.state.extract_item_dest {
+                    dest.push(c);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Extract the highlighted item to the typed path, outside the restore
+    /// workflow. Encrypted archives use the password already entered to
+    /// browse them.
+    async fn extract_item(&mut self) {
+        if let Err(e) = self.ensure_archive_fetched().await {
+            self.state.extract_item_result = Some(Err(format!("Failed to fetch remote archive: {}", e)));
+            return;
+        }
+        let (Some(archive), Some(item), Some(dest)) = (
+            self.state.selected_archive.as_ref(),
+            self.state.highlighted_restore_item(),
+            self.state.extract_item_dest.as_deref(),
+        ) else {
+            return;
+        };
+        if dest.trim().is_empty() {
+            return;
+        }
+
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let dest = home_dir.join(expand_home(dest.trim()));
+        let result = extract::extract_item_to(
+            &archive.path,
+            &extract::member_name(&item.original_path),
+            self.state.restore_password.as_ref(),
+            &dest,
+        )
+        .await;
+
+        self.state.extract_item_result = Some(match result {
+            Ok(()) => {
+                let details = format!("{} from {}", item.name, archive.name);
+                if let Err(e) = AuditLog::open_default().record("extract_item", &dest.to_string_lossy(), &details) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+                Ok(format!("Extracted {} to {}", item.name, dest.display()))
+            }
+            Err(e) => {
+                error!("Failed to extract {}: {}", item.name, e);
+                Err(e.to_string())
+            }
+        });
+    }
+
+    async fn handle_restore_diff_key(&mut self, key: KeyEvent) -> Result<()> {
+        let line_count = match &self.state.restore_diff {
+            Some(Ok(diff)) => diff.len() as u16,
+            _ => 0,
+        };
+        let max_scroll = line_count.saturating_sub(1);
+
+        match self.state.keymap.action(KeyContext::RestoreDiff, &key) {
+            Some(Action::Up) => {
+                self.state.restore_diff_scroll = self.state.restore_diff_scroll.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.state.restore_diff_scroll = (self.state.restore_diff_scroll + 1).min(max_scroll);
+            }
+            Some(Action::PageUp) => {
+                self.state.restore_diff_scroll = self.state.restore_diff_scroll.saturating_sub(10);
+            }
+            Some(Action::PageDown) => {
+                self.state.restore_diff_scroll = (self.state.restore_diff_scroll + 10).min(max_scroll);
+            }
+            Some(Action::Back) => {
+                self.state.restore_diff = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Diff the highlighted item's archived copy against the file it would overwrite
+    async fn show_restore_diff(&mut self) {
+        let conflicts = self.state.restore_items
+            .get(self.state.selected_item_index)
+            .is_some_and(|item| item.conflicts);
+        if conflicts {
+            if let Err(e) = self.ensure_archive_fetched().await {
+                self.state.restore_diff = Some(Err(format!("Failed to fetch remote archive: {}", e)));
+                return;
+            }
+        }
+        let (Some(archive), Some(item)) = (
+            self.state.selected_archive.as_ref(),
+            self.state.highlighted_restore_item(),
+        ) else {
+            return;
+        };
+
+        if !item.conflicts {
+            self.state.restore_diff = Some(Err("No file exists at the restore location".to_string()));
+            return;
+        }
+
+        let diff = extract::diff_with_disk(&archive.path, item, self.state.restore_password.as_ref())
+            .await
+            .map_err(|e| {
+                warn!("Could not diff {}: {}", item.name, e);
+                e.to_string()
+            });
+        self.state.restore_diff = Some(diff);
+        self.state.restore_diff_scroll = 0;
+    }
+
+    fn handle_live_changes_key(&mut self, key: KeyEvent) -> Result<()> {
+        let line_count = match &self.state.live_changes {
+            Some(Ok(changes)) => changes.changed.len() as u16,
+            _ => 0,
+        };
+        let max_scroll = line_count.saturating_sub(1);
+
+        match self.state.keymap.action(KeyContext::LiveChanges, &key) {
+            Some(Action::Up) => {
+                self.state.live_changes_scroll = self.state.live_changes_scroll.saturating_sub(1);
+            }
+            Some(Action::Down) => {
+                self.state.live_changes_scroll = (self.state.live_changes_scroll + 1).min(max_scroll);
+            }
+            Some(Action::PageUp) => {
+                self.state.live_changes_scroll = self.state.live_changes_scroll.saturating_sub(10);
+            }
+            Some(Action::PageDown) => {
+                self.state.live_changes_scroll = (self.state.live_changes_scroll + 10).min(max_scroll);
+            }
+            Some(Action::Back) => {
+                self.state.live_changes = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Compare the selected archive's contents with the files on disk now,
+    /// to see what a restore would bring back or overwrite
+    async fn show_live_changes(&mut self) {
+        self.state.live_changes_scroll = 0;
+        if let Err(e) = self.ensure_archive_fetched().await {
+            self.state.live_changes = Some(Err(format!("Failed to fetch remote archive: {}", e)));
+            return;
+        }
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+        let exclusions = match ExclusionSet::for_mode(&self.config.backup_config, &archive.mode) {
+            Ok(exclusions) => exclusions,
+            Err(e) => {
+                self.state.live_changes = Some(Err(format!("Invalid exclusion patterns: {}", e)));
+                return;
+            }
+        };
+
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let changes = match live_changes::members(&archive.path, self.state.restore_password.as_ref()).await {
+            Ok(members) => tokio::task::spawn_blocking(move || live_changes::compare(&members, &home_dir, &exclusions))
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = &changes {
+            warn!("Could not compare {} with disk: {}", archive.name, e);
+        }
+        self.state.live_changes = Some(changes);
+    }
+
+    async fn handle_privileged_confirm_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.state.keymap.action(KeyContext::PrivilegedRestore, &key) {
+            Some(Action::Yes) => {
+                self.state.confirm_privileged_restore = false;
+                self.state.strip_privileged_bits = false;
+                self.start_restore().await?;
+            }
+            Some(Action::StripPrivileged) => {
+                self.state.confirm_privileged_restore = false;
+                self.state.strip_privileged_bits = true;
+                self.start_restore().await?;
+            }
+            Some(Action::Back) => {
+                self.state.confirm_privileged_restore = false;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_restore_progress_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.keymap.action(KeyContext::RestoreProgress, &key) == Some(Action::Back) {
+            self.cancel_download();
+        }
+        Ok(())
+    }
+
+    async fn handle_restore_complete_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.confirm_restore_rollback {
+            match self.state.keymap.action(KeyContext::RestoreRollback, &key) {
+                Some(Action::Yes) => {
+                    self.state.confirm_restore_rollback = false;
+                    self.rollback_restore().await;
+                }
+                Some(Action::Back) => {
+                    self.state.confirm_restore_rollback = false;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+        if self.state.database_replay.is_some() {
+            match self.state.keymap.action(KeyContext::DatabaseReplay, &key) {
+                Some(Action::Yes) => self.replay_databases().await,
+                Some(Action::Back) => self.state.database_replay = None,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match self.state.keymap.action(KeyContext::RestoreComplete, &key) {
+            Some(Action::RollBack) if self.state.pre_restore_snapshot.is_some() => {
+                self.state.confirm_restore_rollback = true;
+            }
+            Some(Action::ReenableServices) if !self.state.restore_rolled_back => {
+                self.reenable_services().await;
+            }
+            Some(Action::ImportDesktop) if !self.state.restore_rolled_back => {
+                self.import_desktop_settings().await;
+            }
+            Some(Action::ReinstallPackages) if !self.state.restore_rolled_back => {
+                self.write_reinstall_script().await;
+            }
+            Some(Action::RestoreVolumes) if !self.state.restore_rolled_back => {
+                self.pick_volumes_to_restore().await;
+            }
+            Some(Action::ReplayDatabases) if !self.state.restore_rolled_back => {
+                self.ask_database_replay().await;
+            }
+            Some(Action::ValidateCredentials) if !self.state.restored_credentials.is_empty() => {
+                let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+                self.state.credential_checks =
+                    credential_checks::run_checks(&self.state.restored_credentials, &home_dir).await;
+            }
+            Some(Action::SaveReport) => self.save_report(ReportFormat::Markdown),
+            Some(Action::SaveHtmlReport) => self.save_report(ReportFormat::Html),
+            Some(Action::Confirm) => {
+                self.state.reset_restore_state();
+                self.state.transition_to(AppState::MainMenu);
+            }
+            Some(Action::Quit) => {
+                self.state.transition_to(AppState::Exit);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_catalog_browser_key(&mut self, key: KeyEvent) -> Result<()> {
+        let result_count = self.state.catalog_results.len();
+
+        match (self.state.keymap.action(KeyContext::CatalogBrowser, &key), key.code) {
+            (Some(Action::Up), _) => {
+                self.state.move_selection_up(result_count);
+            }
+            (Some(Action::Down), _) => {
+                self.state.move_selection_down(result_count, 10);
+            }
+            (Some(Action::Back), _) => {
+                self.state.go_back();
+            }
+            (_, KeyCode::Backspace) => {
+                self.state.catalog_query.pop();
+                self.search_catalog();
+            }
+            (_, KeyCode::Char(c)) => {
+                self.state.catalog_query.push(c);
+                self.search_catalog();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn search_catalog(&mut self) {
+        const MAX_RESULTS: usize = 500;
+
+        let results = Catalog::open_default()
+            .and_then(|catalog| catalog.search_all(&self.state.catalog_query, MAX_RESULTS));
+
+        match results {
+            Ok(results) => {
+                self.state.catalog_results = results;
+                self.state.status_message = None;
+            }
+            Err(e) => {
+                warn!("Catalog search failed: {}", e);
+                self.state.catalog_results.clear();
+                self.state.status_message = Some(format!("Catalog search failed: {}", e));
+            }
+        }
+        self.state.selected_item_index = 0;
+        self.state.scroll_offset = 0;
+    }
+
+    async fn handle_find_file_key(&mut self, key: KeyEvent) -> Result<()> {
+        let file_count = self.state.find_results.len();
+
+        match (self.state.keymap.action(KeyContext::FindFile, &key), key.code) {
+            (Some(Action::Up), _) => {
+                self.state.move_selection_up(file_count);
+                self.state.find_version_index = 0;
+            }
+            (Some(Action::Down), _) => {
+                self.state.move_selection_down(file_count, 10);
+                self.state.find_version_index = 0;
+            }
+            (Some(Action::Left), _) => {
+                self.state.find_version_index = self.state.find_version_index.saturating_sub(1);
+            }
+            (Some(Action::Right), _) => {
+                let version_count = self.state.find_results
+                    .get(self.state.selected_item_index)
+                    .map(|file| file.versions.len())
+                    .unwrap_or(0);
+                if self.state.find_version_index + 1 < version_count {
+                    self.state.find_version_index += 1;
+                }
+            }
+            (Some(Action::Confirm), _) => {
+                let version = self.state.find_results
+                    .get(self.state.selected_item_index)
+                    .and_then(|file| file.versions.get(self.state.find_version_index))
+                    .cloned();
+                if let Some(version) = version {
+                    self.restore_found_file(version).await?;
+                }
+            }
+            (Some(Action::History), _) => {
+                let file = self.state.find_results.get(self.state.selected_item_index).cloned();
+                if let Some(file) = file {
+                    self.state.file_history = Some(file);
+                    self.state.transition_to(AppState::FileHistory);
+                }
+            }
+            (Some(Action::Back), _) => {
+                // The history view comes back here, so going back one
+                // level can't be relied on to reach the menu
+                self.state.transition_to(AppState::MainMenu);
+            }
+            (_, KeyCode::Backspace) => {
+                self.state.find_query.pop();
+                self.find_file();
+            }
+            (_, KeyCode::Char(c)) => {
+                self.state.find_query.push(c);
+                self.find_file();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_file_history_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(file) = self.state.file_history.as_ref() else {
+            self.state.transition_to(AppState::FindFile);
+            return Ok(());
+        };
+        let version_count = file.versions.len();
+
+        match self.state.keymap.action(KeyContext::FileHistory, &key) {
+            Some(Action::Up) => {
+                self.state.find_version_index = self.state.find_version_index.saturating_sub(1);
+            }
+            Some(Action::Down) if self.state.find_version_index + 1 < version_count => {
+                self.state.find_version_index += 1;
+            }
+            Some(Action::Confirm) => {
+                if let Some(version) = file.versions.get(self.state.find_version_index).cloned() {
+                    self.restore_found_file(version).await?;
+                }
+            }
+            Some(Action::Back) => {
+                // Back to the same file in the search results
+                let path = self.state.file_history.take().map(|file| file.path);
+                self.state.transition_to(AppState::FindFile);
+                if let Some(index) = self.state.find_results.iter().position(|file| Some(&file.path) == path.as_ref()) {
+                    self.state.selected_item_index = index;
+                    self.state.scroll_offset = index;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn find_file(&mut self) {
+        const MAX_MATCHES: usize = 1000;
+
+        let results = Catalog::open_default()
+            .and_then(|catalog| catalog.find_file(&self.state.find_query, MAX_MATCHES));
+
+        match results {
+            Ok(results) => {
+                self.state.find_results = results;
+                self.state.status_message = None;
+            }
+            Err(e) => {
+                warn!("Find file failed: {}", e);
+                self.state.find_results.clear();
+                self.state.status_message = Some(format!("Find file failed: {}", e));
+            }
+        }
+        self.state.selected_item_index = 0;
+        self.state.scroll_offset = 0;
+        self.state.find_version_index = 0;
+    }
+
+    /// Jump into the restore flow for one archived copy of a file
+    async fn restore_found_file(&mut self, version: CatalogMatch) -> Result<()> {
+        let archive = self.backend
+            .list_archives()
+            .await?
+            .into_iter()
+            .find(|archive| archive.path == version.archive_path);
+
+        let Some(archive) = archive else {
+            self.state.set_status(format!("Archive {} is no longer available", version.archive_name));
+            return Ok(());
+        };
+
+        info!("Restoring {} from {}", version.item_path, archive.name);
+        self.state.reset_restore_state();
+        self.state.restore_target_file = Some(version);
+        if let Err(e) = self.open_archive(archive).await {
+            error!("Failed to open archive: {}", e);
+            self.state.set_error(AppError::from_anyhow(ErrorCategory::Archive, "Failed to open archive", &e));
+        }
+        Ok(())
+    }
+
+    async fn handle_config_editor_key(&mut self, key: KeyEvent) -> Result<()> {
+        let index = self.state.selected_item_index;
+        let Some(form) = self.state.config_form.as_mut() else {
+            self.state.go_back();
+            return Ok(());
+        };
+
+        // Typing into a field
+        if let Some(buffer) = self.state.config_edit_buffer.as_mut() {
+            match (self.state.keymap.action(KeyContext::ConfigField, &key), key.code) {
+                (Some(Action::Confirm), _) => {
+                    let value = std::mem::take(buffer);
+                    if let Some(field) = form.fields.get_mut(index) {
+                        field.set_value(value);
+                        form.dirty = true;
+                    }
+                    self.state.config_edit_buffer = None;
+                }
+                (Some(Action::Back), _) => {
+                    self.state.config_edit_buffer = None;
+                }
+                (_, KeyCode::Backspace) => {
+                    buffer.pop();
+                }
+                (_, KeyCode::Char(c)) => {
+                    buffer.push(c);
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let field_count = form.fields.len();
+        let discard_pending = std::mem::take(&mut self.state.config_confirm_discard);
+
+        match self.state.keymap.action(KeyContext::ConfigEditor, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(field_count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(field_count, 10);
+            }
+            Some(Action::Toggle) => {
+                if let Some(field) = form.fields.get_mut(index) {
+                    if field.is_toggle() {
+                        field.cycle();
+                        form.dirty = true;
+                    }
+                }
+            }
+            Some(Action::Confirm) => {
+                if let Some(field) = form.fields.get(index) {
+                    if !field.is_toggle() {
+                        self.state.config_edit_buffer = Some(field.value.clone());
+                    }
+                }
+            }
+            Some(Action::Save) => {
+                self.save_config();
+            }
+            Some(Action::History) => {
+                if form.dirty {
+                    self.state.set_status("Save or discard changes before opening history".to_string());
+                } else {
+                    self.load_config_history();
+                    self.state.transition_to(AppState::ConfigHistory);
+                }
+            }
+            Some(Action::Back) => {
+                if form.dirty && !discard_pending {
+                    self.state.config_confirm_discard = true;
+                    self.state.set_status("Unsaved changes: press Esc again to discard, S to save".to_string());
+                } else {
+                    self.state.config_form = None;
+                    self.state.clear_status();
+                    self.state.go_back();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn save_config(&mut self) {
+        let Some(form) = &self.state.config_form else {
+            return;
+        };
+
+        let mut edited = match form.apply(&self.config.backup_config) {
+            Ok(edited) => edited,
+            Err(e) => {
+                self.state.set_status(format!("Cannot save: {}", e));
+                return;
+            }
+        };
+
+        match edited.save(&self.config.config_path) {
+            Ok(backup_path) => {
+                info!("Saved config to {}", self.config.config_path.display());
+                self.session.config_saves += 1;
+                self.state.config_form = Some(ConfigForm::from_config(&edited));
+                self.config.backup_config = edited;
+                self.state.theme = self.theme();
+                self.state.set_status(match backup_path {
+                    Some(path) => format!("Config saved; previous version kept at {}", path.display()),
+                    None => "Config saved".to_string(),
+                });
+            }
+            Err(e) => {
+                error!("Failed to save config: {}", e);
+                self.state.set_status(format!("Failed to save config: {}", e));
+            }
+        }
+    }
+
+    async fn handle_config_history_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.config_confirm_rollback {
+            match self.state.keymap.action(KeyContext::ConfigRollback, &key) {
+                Some(Action::Yes) => {
+                    self.state.config_confirm_rollback = false;
+                    self.rollback_config();
+                }
+                Some(Action::Back) => {
+                    self.state.config_confirm_rollback = false;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let snapshot_count = self.state.config_snapshots.len();
+
+        match self.state.keymap.action(KeyContext::ConfigHistory, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(snapshot_count);
+                self.load_config_diff();
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(snapshot_count, 10);
+                self.load_config_diff();
+            }
+            Some(Action::PageUp) => {
+                self.state.config_diff_scroll = self.state.config_diff_scroll.saturating_sub(10);
+            }
+            Some(Action::PageDown) => {
+                let max_scroll = self.state.config_diff.len().saturating_sub(1) as u16;
+                self.state.config_diff_scroll = (self.state.config_diff_scroll + 10).min(max_scroll);
+            }
+            Some(Action::RollBack) if snapshot_count > 0 => {
+                self.state.config_confirm_rollback = true;
+            }
+            Some(Action::Back) => {
+                self.state.config_form = Some(ConfigForm::from_config(&self.config.backup_config));
+                self.state.go_back();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_exclusion_editor_key(&mut self, key: KeyEvent) -> Result<()> {
+        // Typing a new pattern
+        if let Some(input) = self.state.exclusion_input.as_mut() {
+            match (self.state.keymap.action(KeyContext::ExclusionInput, &key), key.code) {
+                (Some(Action::Confirm), _) => {
+                    let pattern = input.trim().to_string();
+                    if pattern.is_empty() {
+                        self.state.exclusion_input = None;
+                    } else if let Err(e) = ExclusionSet::new(std::slice::from_ref(&pattern)) {
+                        self.state.set_status(e.to_string());
+                    } else if self.state.exclusion_patterns.contains(&pattern) {
+                        self.state.set_status(format!("'{}' is already excluded", pattern));
+                    } else {
+                        self.state.exclusion_input = None;
+                        self.state.exclusion_patterns.push(pattern);
+                        self.state.selected_item_index = self.state.exclusion_patterns.len() - 1;
+                        self.state.exclusions_dirty = true;
+                        self.state.clear_status();
+                        self.count_exclusions();
+                    }
+                }
+                (Some(Action::Back), _) => {
+                    self.state.exclusion_input = None;
+                    self.state.clear_status();
+                }
+                (_, KeyCode::Backspace) => {
+                    input.pop();
+                }
+                (_, KeyCode::Char(c)) => {
+                    input.push(c);
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let pattern_count = self.state.exclusion_patterns.len();
+        let discard_pending = std::mem::take(&mut self.state.config_confirm_discard);
+
+        match self.state.keymap.action(KeyContext::ExclusionEditor, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(pattern_count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(pattern_count, 10);
+            }
+            Some(Action::Add) => {
+                self.state.exclusion_input = Some(String::new());
+            }
+            Some(Action::Remove) if pattern_count > 0 => {
+                let index = self.state.selected_item_index.min(pattern_count - 1);
+                self.state.exclusion_patterns.remove(index);
+                self.state.selected_item_index = index.min(pattern_count.saturating_sub(2));
+                self.state.exclusions_dirty = true;
+                self.count_exclusions();
+            }
+            Some(Action::SwitchMode) => {
+                if self.state.exclusions_dirty {
+                    self.state.set_status("Save or discard changes before switching mode".to_string());
+                } else {
+                    let mode = match self.state.exclusion_mode {
+                        BackupMode::Secure => BackupMode::Complete,
+                        BackupMode::Complete => BackupMode::Secure,
+                    };
+                    self.load_exclusions(mode);
+                }
+            }
+            Some(Action::Save) => {
+                self.save_exclusions();
+            }
+            Some(Action::Back) => {
+                if self.state.exclusions_dirty && !discard_pending {
+                    self.state.config_confirm_discard = true;
+                    self.state.set_status("Unsaved changes: press Esc again to discard, S to save".to_string());
+                } else {
+                    self.exclusion_counts = None;
+                    self.state.clear_status();
+                    self.state.go_back();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn load_exclusions(&mut self, mode: BackupMode) {
+        self.state.exclusion_patterns = self.config.backup_config
+            .get_exclusions_for_mode(&mode)
+            .into_iter()
+            .filter(|pattern| !pattern.trim().is_empty())
+            .collect();
+        self.state.exclusion_mode = mode;
+        self.state.exclusion_input = None;
+        self.state.exclusions_dirty = false;
+        self.state.config_confirm_discard = false;
+        self.state.selected_item_index = 0;
+        self.state.scroll_offset = 0;
+        self.state.clear_status();
+        self.count_exclusions();
+    }
+
+    /// Recount what the edited patterns match, in the background. Any
+    /// count still running for the previous patterns is abandoned.
+    fn count_exclusions(&mut self) {
+        self.state.exclusion_counts = None;
+        let exclusions = match ExclusionSet::new(&self.state.exclusion_patterns) {
+            Ok(exclusions) => exclusions,
+            Err(e) => {
+                self.state.set_status(e.to_string());
+                self.exclusion_counts = None;
+                return;
+            }
+        };
+
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let roots: Vec<PathBuf> = self.config.backup_config
+            .get_items_for_mode(&self.state.exclusion_mode)
+            .into_iter()
+            .map(|item| item.path)
+            .collect();
+
+        let (tx, rx) = oneshot::channel();
+        tokio::task::spawn_blocking(move || {
+            let _ = tx.send(exclusions.count_matches(&home_dir, &roots));
+        });
+        self.exclusion_counts = Some(rx);
+    }
+
+    fn save_exclusions(&mut self) {
+        let mut edited = self.config.backup_config.clone();
+        let Some(mode_config) = edited.backup_modes.get_mut(self.state.exclusion_mode.as_str()) else {
+            self.state.set_status(format!("No {} mode in the config", self.state.exclusion_mode.as_str()));
+            return;
+        };
+        mode_config.exclusions = self.state.exclusion_patterns.clone();
+
+        match edited.save(&self.config.config_path) {
+            Ok(backup_path) => {
+                info!("Saved exclusions to {}", self.config.config_path.display());
+                self.session.config_saves += 1;
+                self.config.backup_config = edited;
+                self.state.theme = self.theme();
+                self.state.exclusions_dirty = false;
+                self.state.set_status(match backup_path {
+                    Some(path) => format!("Exclusions saved; previous config kept at {}", path.display()),
+                    None => "Exclusions saved".to_string(),
+                });
+            }
+            Err(e) => {
+                error!("Failed to save exclusions: {}", e);
+                self.state.set_status(format!("Failed to save exclusions: {}", e));
+            }
+        }
+    }
+
+    fn load_config_history(&mut self) {
+        match config_history::list_snapshots(&self.config.config_path) {
+            Ok(snapshots) => self.state.config_snapshots = snapshots,
+            Err(e) => {
+                warn!("Failed to list config history: {}", e);
+                self.state.config_snapshots.clear();
+                self.state.set_status(format!("Failed to list config history: {}", e));
+            }
+        }
+        self.state.selected_item_index = 0;
+        self.state.config_confirm_rollback = false;
+        self.load_config_diff();
+    }
+
+    fn load_config_diff(&mut self) {
+        self.state.config_diff_scroll = 0;
+        self.state.config_diff = match self.state.config_snapshots.get(self.state.selected_item_index) {
+            Some(snapshot) => match config_history::diff_snapshot(snapshot, &self.config.config_path) {
+                Ok(diff) => diff,
+                Err(e) => {
+                    warn!("Failed to diff config snapshot: {}", e);
+                    self.state.set_status(format!("Failed to diff snapshot: {}", e));
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+    }
+
+    fn rollback_config(&mut self) {
+        let Some(snapshot) = self.state.config_snapshots.get(self.state.selected_item_index).cloned() else {
+            return;
+        };
+
+        match config_history::rollback(&snapshot, &self.config.config_path) {
+            Ok(config) => {
+                info!("Rolled config back to {}", snapshot.path.display());
+                self.session.config_saves += 1;
+                self.config.backup_config = config;
+                self.state.theme = self.theme();
+                self.load_config_history();
+                self.state.set_status(format!(
+                    "Rolled back to version from {}",
+                    snapshot.taken.format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+            Err(e) => {
+                error!("Config rollback failed: {}", e);
+                self.state.set_status(format!("Rollback failed: {}", e));
+            }
+        }
+    }
+
+    async fn handle_fleet_overview_key(&mut self, key: KeyEvent) -> Result<()> {
+        let host_count = self.state.fleet_hosts.len();
+
+        match self.state.keymap.action(KeyContext::FleetOverview, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(host_count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(host_count, 10);
+            }
+            Some(Action::Verify) => {
+                if let Some(host) = self.state.fleet_hosts.get(self.state.selected_item_index).cloned() {
+                    self.verify_latest_archive(&host).await;
+                }
+            }
+            Some(Action::Back) => {
+                self.state.go_back();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_audit_history_key(&mut self, key: KeyEvent) -> Result<()> {
+        let entry_count = self.state.audit_entries.len();
+
+        match self.state.keymap.action(KeyContext::AuditHistory, &key) {
+            Some(Action::Up) => {
+                self.state.move_selection_up(entry_count);
+            }
+            Some(Action::Down) => {
+                self.state.move_selection_down(entry_count, 10);
+            }
+            Some(Action::PageUp) => {
+                self.state.page_up(10);
+            }
+            Some(Action::PageDown) if entry_count > 0 => {
+                self.state.page_down(entry_count, 10);
+            }
+            Some(Action::Back) => {
+                self.state.go_back();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn load_audit_history(&mut self) {
+        match AuditLog::open_default().read() {
+            Ok((mut events, chain)) => {
+                if let ChainStatus::Broken { entry } = chain {
+                    warn!("Audit log integrity chain is broken at entry {}", entry + 1);
+                }
+                events.reverse();
+                self.state.audit_entries = events;
+                self.state.audit_chain = Some(chain);
+            }
+            Err(e) => {
+                warn!("Failed to read audit log: {}", e);
+                self.state.audit_entries.clear();
+                self.state.audit_chain = None;
+                self.state.set_status(format!("Failed to read audit log: {}", e));
+            }
+        }
+    }
+
+    fn load_fleet(&mut self) {
+        let hosts = Catalog::open_default().and_then(|catalog| catalog.host_summaries());
+        match hosts {
+            Ok(mut hosts) => {
+                for host in &mut hosts {
+                    host.overdue = self.config.backup_config.is_overdue(&host.hostname, host.last_backup);
+                }
+                self.state.fleet_hosts = hosts;
+            }
+            Err(e) => {
+                warn!("Failed to load machines from catalog: {}", e);
+                self.state.fleet_hosts.clear();
+                self.state.set_status(format!("Failed to load machines: {}", e));
+            }
+        }
+    }
+
+    /// Re-hash a host's latest archive and compare it with the checksum
+    /// recorded when it was created
+    async fn verify_latest_archive(&mut self, host: &crate::core::types::HostSummary) {
+        let Some(expected) = host.last_checksum.clone() else {
+            self.state.set_status(format!("No checksum recorded for {}'s latest archive", host.hostname));
+            return;
+        };
+        let path = PathBuf::from(&host.last_archive);
+        if host.last_remote.is_some() || !path.exists() {
+            self.state.set_status(format!("{} is not reachable from this machine", host.last_archive));
+            return;
+        }
+
+        let hash_path = path.clone();
+        let ok = match tokio::task::spawn_blocking(move || checksum::verify_file(&hash_path, &expected)).await {
+            Ok(Ok(ok)) => ok,
+            Ok(Err(e)) => {
+                self.state.set_status(format!("Verification failed: {}", e));
+                return;
+            }
+            Err(e) => {
+                self.state.set_status(format!("Verification failed: {}", e));
+                return;
+            }
+        };
+
+        if let Err(e) = Catalog::open_default().and_then(|catalog| catalog.record_verification(&host.last_archive, ok)) {
+            warn!("Failed to record verification: {}", e);
+        }
+        if ok {
+            info!("Verified {}", host.last_archive);
+            self.state.set_status(format!("{}: latest archive verified", host.hostname));
+        } else {
+            error!("Checksum mismatch for {}", host.last_archive);
+            self.state.set_status(format!("{}: CHECKSUM MISMATCH in latest archive", host.hostname));
+        }
+        self.load_fleet();
+    }
+
+    async fn handle_help_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.state.keymap.action(KeyContext::Help, &key) == Some(Action::Back) {
+            self.state.go_back();
+        }
+        Ok(())
+    }
+
+    async fn handle_error_key(&mut self, key: KeyEvent) -> Result<()> {
+        match self.state.keymap.action(KeyContext::Error, &key) {
+            Some(Action::Confirm) => {
+                // Clear the error and go back to the previous state
+                self.state.error_message = None;
+                self.state.retry = None;
+                self.state.clear_status();
+                self.state.go_back();
+                // Force a full redraw by resetting the terminal
+                // This helps fix screen corruption issues
+                debug!("Returning from error state to: {:?}", self.state.current_state);
+            }
+            Some(Action::Details) => self.state.error_details = !self.state.error_details,
+            Some(Action::Copy) => {
+                let AppState::Error(error) = &self.state.current_state else {
+                    return Ok(());
+                };
+                match clipboard::copy(&error.report()) {
+                    Ok(tool) => {
+                        debug!("Copied the error with {}", tool);
+                        self.state.set_status("Error details copied to the clipboard".to_string());
+                    }
+                    Err(e) => {
+                        warn!("Could not copy the error: {:#}", e);
+                        self.state.set_status(format!("Could not copy: {}", e));
+                    }
+                }
+            }
+            Some(Action::Retry) => {
+                let Some(operation) = self.state.retry.take() else {
+                    return Ok(());
+                };
+                info!("Retrying {}", operation.describe());
+                self.state.error_message = None;
+                self.state.clear_status();
+                self.state.go_back();
+                match operation {
+                    RetryOperation::Backup => self.start_backup().await?,
+                    RetryOperation::Restore => self.start_restore().await?,
+                    RetryOperation::ListArchives => self.open_archive_list().await,
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// List the archives at the destination and show them for restore
+    async fn open_archive_list(&mut self) {
+        match self.load_available_archives().await {
+            Ok(()) => self.state.transition_to(AppState::RestoreArchiveSelection),
+            Err(e) => {
+                error!("Failed to list archives: {}", e);
+                self.state.set_retryable_error(
+                    AppError::from_anyhow(ErrorCategory::Archive, "Failed to list archives", &e),
+                    RetryOperation::ListArchives,
+                );
+            }
+        }
+    }
+
     async fn load_backup_items(&mut self) -> Result<()> {
         info!("Loading backup items for mode: {:?}", self.state.backup_mode);
         
-        self.state.backup_items = self.config.backup_config.get_items_for_mode(&self.state.backup_mode);
+        let profile = self.state.active_profile
+            .as_ref()
+            .and_then(|name| Some(name).zip(self.config.backup_config.profiles.get(name)));
+        self.state.backup_items = match profile.filter(|(_, profile)| !profile.items.is_empty()) {
+            Some((name, profile)) => self.config.backup_config.get_items_for_profile(name, profile, &self.state.backup_mode),
+            None => self.config.backup_config.get_items_for_mode(&self.state.backup_mode),
+        };
+        if let Some((name, profile)) = profile {
+            self.state.backup_items.extend(profile.database_items(name));
+        }
         
-        // Validate items exist and get their sizes
+        // Validate items exist; sizes are filled in as the background scan
+        // reports them so the screen opens immediately
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let mut to_size = Vec::new();
         for item in &mut self.state.backup_items {
-            let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
             let full_path = home_dir.join(&item.path);
-            item.exists = full_path.exists();
-            
-            if item.exists {
-                item.size = Self::get_path_size(&full_path).ok();
+            // Command output, package lists, volume copies and database
+            // dumps only exist once the backup makes them
+            item.exists = item.is_generated() || full_path.exists();
+
+            if item.exists && !item.is_generated() {
+                to_size.push((item.path.clone(), full_path));
             }
         }
         
+        self.state.selection_undo.clear();
+        self.state.backup_sizes_pending = to_size.len();
+        self.size_updates = Some(dir_size::spawn_size_scan(to_size));
         debug!("Loaded {} backup items", self.state.backup_items.len());
         Ok(())
     }
 
     async fn load_available_archives(&mut self) -> Result<()> {
         info!("Loading available archives");
+        self.sync_catalog().await;
         
         // This would typically scan for archive files in the backup directory
         // For now, we'll use the backend to get available archives
         self.state.available_archives = self.backend.list_archives().await?;
+
+        if let Some(Destination::Rclone(remote)) = &self.state.backup_destination {
+            match RcloneRemote::new(remote.clone()).list_archives().await {
+                Ok(remote_archives) => {
+                    // Skip remote archives the catalog already knows about
+                    for archive in remote_archives {
+                        let known = self.state.available_archives
+                            .iter()
+                            .any(|existing| existing.remote == archive.remote);
+                        if !known {
+                            self.state.available_archives.push(archive);
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to list archives on {}: {}", remote, e),
+            }
+        }
+        self.state.archive_sort.sort(&mut self.state.available_archives);
         
+        self.state.archive_parity = self.state.available_archives
+            .iter()
+            .filter(|archive| archive.remote.is_none())
+            .filter_map(|archive| match parity::read_header(&archive.path) {
+                Ok(Some(header)) => Some((archive.path.clone(), header.describe())),
+                Ok(None) => None,
+                Err(e) => Some((archive.path.clone(), format!("unreadable: {}", e))),
+            })
+            .collect();
+
         debug!("Found {} available archives", self.state.available_archives.len());
         Ok(())
     }
 
+    /// Exchange catalogs with other machines when a shared location is configured
+    async fn sync_catalog(&mut self) {
+        let Some(sync_config) = &self.config.backup_config.catalog_sync else {
+            return;
+        };
+
+        let sync = CatalogSync::new(sync_config.resolved_path());
+        let result = match Catalog::open_default() {
+            Ok(mut catalog) => sync.sync(&mut catalog).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Catalog sync failed: {}", e);
+            self.state.set_status(format!("Catalog sync failed: {}", e));
+        }
+    }
+
+    /// Select `archive` and move on to its password or items. A remote
+    /// archive is downloaded first, in the background, unless its contents
+    /// are cached; then the download waits until a restore needs the data.
+    async fn open_archive(&mut self, mut archive: ArchiveInfo) -> Result<()> {
+        if let Some(remote_path) = archive.remote.clone() {
+            if RemoteCache::open_default().items(&archive).is_none() {
+                return self.start_download(archive, AfterDownload::Browse);
+            }
+            archive.path = PathBuf::from(remote_path);
+        }
+
+        self.state.selected_archive = Some(archive.clone());
+        if archive.encrypted {
+            self.state.transition_to(AppState::RestorePasswordInput);
+        } else {
+            self.load_restore_items().await?;
+            self.state.transition_to(AppState::RestoreItemSelection);
+        }
+        Ok(())
+    }
+
+    /// Write parity beside one local copy of a new archive. The first copy
+    /// is encoded; the rest are identical, so they get a copy of its parity.
+    async fn write_parity(&self, archive: &std::path::Path, percent: u32, first: &mut Option<PathBuf>) {
+        let archive = archive.to_path_buf();
+        let source = first.clone();
+        let written = tokio::task::spawn_blocking({
+            let archive = archive.clone();
+            move || match source {
+                Some(source) => parity::copy_beside(&source, &archive),
+                None => parity::create(&archive, percent).map(|_| ()),
+            }
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.into()));
+        match written {
+            Ok(()) => {
+                first.get_or_insert(archive);
+            }
+            Err(e) => warn!("Failed to write parity for {}: {}", archive.display(), e),
+        }
+    }
+
+    /// Fetch `archive` to a private temp file so the rest of the restore
+    /// flow can treat it like a local one, showing the transfer on the
+    /// progress screen
+    fn start_download(&mut self, archive: ArchiveInfo, then: AfterDownload) -> Result<()> {
+        let Some(remote_path) = archive.remote.clone() else {
+            anyhow::bail!("{} is not on a remote", archive.name);
+        };
+
+        let local_path = std::env::temp_dir().join(&archive.name);
+        let (progress_tx, progress) = tokio::sync::mpsc::unbounded_channel();
+        let (path, size) = (local_path.clone(), archive.size);
+        let task = tokio::spawn(async move {
+            RcloneRemote::fetch(&remote_path, &path, size, Some(&progress_tx)).await
+        });
+
+        self.state.restore_progress = Some(RestoreProgress {
+            current_item: format!("Downloading {}", archive.name),
+            total_items: 1,
+            total_bytes: archive.size,
+            status: ProgressStatus::Downloading,
+            ..RestoreProgress::default()
+        });
+        let return_to = (self.state.current_state.clone(), self.state.previous_state.clone());
+        self.state.transition_to(AppState::RestoreProgress);
+        self.archive_download = Some(ArchiveDownload {
+            archive,
+            local_path,
+            then,
+            return_to,
+            progress,
+            task,
+        });
+        Ok(())
+    }
+
+    /// Show download progress, and carry on once the archive has arrived
+    async fn poll_download(&mut self) {
+        let Some(download) = self.archive_download.as_mut() else {
+            return;
+        };
+        while let Ok(progress) = download.progress.try_recv() {
+            self.state.restore_progress = Some(progress);
+        }
+        if !download.task.is_finished() {
+            return;
+        }
+        let Some(download) = self.archive_download.take() else {
+            return;
+        };
+
+        let fetched = download.task.await.unwrap_or_else(|e| Err(e.into()));
+        self.state.restore_progress = None;
+        let (return_to, previous) = download.return_to;
+        if let Err(e) = fetched {
+            error!("Failed to fetch remote archive: {}", e);
+            self.state.current_state = return_to;
+            self.state.previous_state = previous;
+            let error = AppError::from_anyhow(ErrorCategory::Archive, "Failed to fetch remote archive", &e);
+            match download.then {
+                AfterDownload::Restore => self.state.set_retryable_error(error, RetryOperation::Restore),
+                AfterDownload::Browse => self.state.set_error(error),
+            }
+            return;
+        }
+
+        let mut archive = download.archive;
+        archive.path = download.local_path;
+        info!("Downloaded {} to {}", archive.name, archive.path.display());
+        self.state.selected_archive = Some(archive.clone());
+        let next = match download.then {
+            AfterDownload::Browse if archive.encrypted => {
+                self.state.transition_to(AppState::RestorePasswordInput);
+                Ok(())
+            }
+            AfterDownload::Browse => self.load_restore_items().await.map(|_| {
+                self.state.transition_to(AppState::RestoreItemSelection);
+            }),
+            AfterDownload::Restore => self.start_restore().await,
+        };
+        if matches!(download.then, AfterDownload::Browse) {
+            self.state.previous_state = Some(return_to);
+        }
+        if let Err(e) = next {
+            error!("Failed to open archive: {}", e);
+            self.state.set_error(AppError::from_anyhow(ErrorCategory::Archive, "Failed to open archive", &e));
+        }
+    }
+
+    /// Stop a download in progress and go back to where it was started
+    fn cancel_download(&mut self) {
+        let Some(download) = self.archive_download.take() else {
+            return;
+        };
+        // Dropping the task kills rclone
+        download.task.abort();
+        let _ = std::fs::remove_file(&download.local_path);
+        self.state.restore_progress = None;
+        (self.state.current_state, self.state.previous_state) = download.return_to;
+        self.state.set_status(format!("Download of {} cancelled", download.archive.name));
+    }
+
+    /// Fetch the selected archive if browsing it used the cached listing
+    async fn ensure_archive_fetched(&mut self) -> Result<()> {
+        let Some(mut archive) = self.state.selected_archive.clone().filter(|archive| !is_fetched(archive)) else {
+            return Ok(());
+        };
+        let Some(remote_path) = archive.remote.clone() else {
+            return Ok(());
+        };
+
+        let local_path = std::env::temp_dir().join(&archive.name);
+        RcloneRemote::fetch(&remote_path, &local_path, archive.size, None).await?;
+        archive.path = local_path;
+        self.state.selected_archive = Some(archive);
+        Ok(())
+    }
+
     async fn load_restore_items(&mut self) -> Result<()> {
-        if let Some(archive) = &self.state.selected_archive {
+        if let Some(archive) = self.state.selected_archive.clone() {
             info!("Loading restore items from archive: {}", archive.name);
+
+            let cache = RemoteCache::open_default();
+            match cache.items(&archive) {
+                Some(items) => self.state.restore_items = items,
+                None => {
+                    self.ensure_archive_fetched().await?;
+                    let archive = self.state.selected_archive.clone().unwrap_or(archive);
+                    self.state.restore_items = self.backend
+                        .list_archive_contents(&archive, self.state.restore_password.as_ref())
+                        .await?;
+                    if RemoteCache::caches(&archive) {
+                        if let Err(e) = cache.store(&archive, &self.state.restore_items) {
+                            warn!("Failed to cache the listing of {}: {}", archive.name, e);
+                        }
+                    }
+                }
+            }
+
+            if let Some(target) = &self.state.restore_target_file {
+                self.state.restore_items = vec![single_file_item(&self.state.restore_items, target)];
+            }
             
-            self.state.restore_items = self.backend
-                .list_archive_contents(archive, self.state.restore_password.as_ref())
-                .await?;
-            
+            self.state.selection_undo.clear();
             debug!("Loaded {} restore items", self.state.restore_items.len());
         }
         Ok(())
@@ -540,7 +3704,7 @@ impl App {
         
         if !self.state.is_backup_ready() {
             warn!("No items selected for backup");
-            self.state.set_error("No items selected for backup".to_string());
+            self.state.set_error(AppError::new(ErrorCategory::Selection, "No items selected for backup"));
             return Ok(());
         }
 
@@ -548,39 +3712,400 @@ impl App {
         let selected_items: Vec<BackupItem> = self.state.get_selected_backup_items().into_iter().cloned().collect();
         let backup_mode = self.state.backup_mode.clone();
         let backup_password = self.state.backup_password.clone();
-        let backup_output_path = self.state.backup_output_path.clone();
+        let recipients = self.state.backup_recipients.clone();
+        let strategy = self.state.active_strategy.clone();
+        let encrypted = backup_password.is_some() || !recipients.is_empty();
+        let destination = self.state.backup_destination.clone();
+        let copies = self.state.backup_copies.clone();
+        self.state.destination_results.clear();
+        self.state.item_reports.clear();
+        self.state.item_report_path = None;
+        
+        let output_dir = staging_dir(destination.as_ref(), &copies);
+        
+        let exclusions = match ExclusionSet::for_mode(&self.config.backup_config, &backup_mode)
+            .and_then(|exclusions| exclusions.with_paths(&self.state.secret_exclusions))
+        {
+            Ok(exclusions) => exclusions,
+            Err(e) => {
+                self.state.set_error(AppError::from_anyhow(ErrorCategory::Config, "Cannot apply exclusions", &e));
+                return Ok(());
+            }
+        };
         
         self.state.transition_to(AppState::BackupProgress);
+        self.state.backup_progress = None;
+        let started = std::time::Instant::now();
         
-        // Start backup in background
-        let selected_item_refs: Vec<&BackupItem> = selected_items.iter().collect();
-        let result = self.backend.start_backup(
-            selected_item_refs,
-            &backup_mode,
-            backup_password.as_ref(),
-            backup_output_path.as_ref(),
-        ).await;
+        // Start backup in background; the progress screen follows the bytes
+        // tar writes until poll_backup sees it finish
+        let (progress_tx, progress) = tokio::sync::mpsc::unbounded_channel();
+        let engine = self.backend.clone().with_throttle(self.state.backup_throttle.clone());
+        let (items, mode, output) = (selected_items.clone(), backup_mode.clone(), output_dir.clone());
+        let (compression, level) = (self.state.backup_compression, self.state.backup_compression_level);
+        let threads = self.config.backup_config.compression_threads();
+        let task_recipients = recipients.clone();
+        let task = tokio::spawn(async move {
+            engine.start_backup(
+                items.iter().collect(),
+                &mode,
+                &exclusions,
+                compression,
+                level,
+                threads,
+                backup_password.as_ref(),
+                &task_recipients,
+                output.as_ref(),
+                Some(&progress_tx),
+            ).await
+        });
+        self.running_backup = Some(RunningBackup {
+            progress,
+            task,
+            job: BackupJob {
+                selected_items,
+                backup_mode,
+                recipients,
+                strategy,
+                encrypted,
+                destination,
+                copies,
+                output_dir,
+                started,
+            },
+        });
+        Ok(())
+    }
+
+    /// Show backup progress, and finish the backup once the archive is
+    /// written
+    async fn poll_backup(&mut self) {
+        let Some(running) = self.running_backup.as_mut() else {
+            return;
+        };
+        while let Ok(progress) = running.progress.try_recv() {
+            self.state.backup_progress = Some(progress);
+        }
+        if !running.task.is_finished() {
+            return;
+        }
+        let Some(running) = self.running_backup.take() else {
+            return;
+        };
+
+        let output = running.task.await.unwrap_or_else(|e| Err(e.into()));
+        if let Err(e) = self.finish_backup(running.job, output).await {
+            error!("Failed to finish backup: {}", e);
+            self.state.set_retryable_error(AppError::from_anyhow(ErrorCategory::Backup, "Backup failed", &e), RetryOperation::Backup);
+        }
+    }
+
+    /// Copy, index, catalog and record a backup the engine has finished
+    async fn finish_backup(&mut self, job: BackupJob, output: Result<BackupOutput>) -> Result<()> {
+        let BackupJob {
+            selected_items,
+            backup_mode,
+            recipients,
+            strategy,
+            encrypted,
+            destination,
+            copies,
+            output_dir,
+            started,
+        } = job;
+        let mut result = output.map(|output| {
+            self.state.item_reports = output.items;
+            output.archive
+        });
+
+        // Text inside encrypted archives stays encrypted: it is never copied
+        // into the catalog
+        let text_indexer = if encrypted {
+            None
+        } else {
+            TextIndexer::from_config(&self.config.backup_config).unwrap_or_else(|e| {
+                warn!("Text indexing disabled: {}", e);
+                None
+            })
+        };
+
+        // Index the archive while it is still on local disk
+        let index = match &result {
+            Ok(Some(archive_path)) => match catalog::index_archive(archive_path, text_indexer, self.config.backup_config.hash_algorithm()).await {
+                Ok(index) => Some(index),
+                Err(e) => {
+                    warn!("Failed to index archive for catalog: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        // Offsets of every member, kept beside each local copy so restores
+        // can list and read it without going through the whole archive.
+        // Encrypted archives get none; it would give their file names away.
+        let members = match &result {
+            Ok(Some(archive_path)) if !encrypted => {
+                let path = archive_path.clone();
+                match tokio::task::spawn_blocking(move || MemberIndex::build(&path)).await? {
+                    Ok(members) => Some(members),
+                    Err(e) => {
+                        warn!("Failed to index archive members: {}", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // Where the archive ended up: its path, and its rclone path when it
+        // is on a remote
+        let mut locations: Vec<(PathBuf, Option<String>)> = Vec::new();
+        match (&result, &destination) {
+            (Ok(Some(archive_path)), Some(primary)) if !copies.is_empty() => {
+                let destinations: Vec<Destination> = std::iter::once(primary).chain(&copies).cloned().collect();
+                match self.copy_to_destinations(archive_path, &destinations, output_dir.as_deref()).await {
+                    Ok(held) => locations = held,
+                    Err(e) => result = Err(e),
+                }
+            }
+            (Ok(archive_path), Some(Destination::Rclone(remote))) => {
+                match self.upload_to_rclone(remote, archive_path.as_deref()).await {
+                    Ok(target) => locations.push((archive_path.clone().unwrap_or_default(), Some(target))),
+                    Err(e) => result = Err(e),
+                }
+            }
+            (Ok(Some(archive_path)), _) => locations.push((archive_path.clone(), None)),
+            _ => {}
+        }
+        let location_names: Vec<String> = locations
+            .iter()
+            .map(|(path, remote)| remote.clone().unwrap_or_else(|| path.display().to_string()))
+            .collect();
+        let destination_names = destination
+            .iter()
+            .chain(&copies)
+            .map(|destination| destination.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let outcome = JobOutcome {
+            job: "Backup",
+            error: result.as_ref().err().map(|e| e.to_string()),
+            duration: started.elapsed(),
+            size: index.as_ref().map(|i| i.size),
+            destination: if location_names.is_empty() {
+                destination.as_ref().map(|_| destination_names.clone())
+            } else {
+                Some(location_names.join(", "))
+            },
+        };
+        notifications::notify(self.config.backup_config.notifications.as_ref(), outcome);
+        let written = result.as_ref().ok().and_then(|archive| archive.as_ref()).map(|_| index.as_ref().map(|i| i.size).unwrap_or(0));
+        self.session.record_backup(written, result.is_ok());
+
+        let archive_name = location_names.first().cloned().unwrap_or_default();
+        let mut details = match location_names.len() {
+            0 | 1 => format!("{} items", selected_items.len()),
+            held => format!("{} items, {} copies", selected_items.len(), held),
+        };
+        if !recipients.is_empty() {
+            details.push_str(&format!(", encrypted to {}", recipients.join(" ")));
+        }
+        let audit = AuditEvent::new("backup", &archive_name, &details)
+            .with_operation(Operation {
+                mode: backup_mode.as_str().to_string(),
+                items: selected_items.iter().map(|item| item.path.display().to_string()).collect(),
+                destination: destination.as_ref().map(|_| destination_names.clone()),
+                result: operation_result(&result),
+                archive_checksum: index.as_ref().map(|index| index.checksum.clone()),
+                strategy,
+            });
+        if let Err(e) = AuditLog::open_default().append(audit) {
+            warn!("Failed to write audit log: {}", e);
+        }
+        self.load_strategies();
 
         match result {
-            Ok(_) => {
+            Ok(archive_path) => {
                 info!("Backup completed successfully");
+                // Each copy is cataloged, so any that survives can be restored
+                let name = archive_path
+                    .as_ref()
+                    .and_then(|path| path.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let created = chrono::Utc::now();
+                let parity_percent = self.config.backup_config.parity_percent();
+                let mut parity_written: Option<PathBuf> = None;
+                for (path, remote) in &locations {
+                    let archive = ArchiveInfo {
+                        name: name.clone(),
+                        created,
+                        size: index.as_ref().map(|i| i.size).unwrap_or(0),
+                        mode: backup_mode.clone(),
+                        encrypted,
+                        description: String::new(),
+                        items: Vec::new(),
+                        remote: remote.clone(),
+                        hostname: Some(catalog::local_hostname()),
+                        path: path.clone(),
+                    };
+                    if let Err(e) = self.backend.catalog_archive(&archive, index.as_ref()).await {
+                        warn!("Failed to record archive in catalog: {}", e);
+                    }
+                    if let (Some(members), None) = (&members, remote) {
+                        if let Err(e) = members.write_beside(path) {
+                            warn!("Failed to write archive index: {}", e);
+                        }
+                    }
+                    if let (Some(percent), None) = (parity_percent, remote) {
+                        self.write_parity(path, percent, &mut parity_written).await;
+                    }
+                }
+                if !locations.is_empty() {
+                    self.sync_catalog().await;
+                }
+                if !self.state.item_reports.is_empty() {
+                    match item_report::write(&item_report::default_dir(), &name, &backup_mode, &self.state.item_reports) {
+                        Ok(path) => self.state.item_report_path = Some(path),
+                        Err(e) => warn!("Failed to save item report: {}", e),
+                    }
+                }
+                let mut report = OperationReport::new("Backup", backup_mode.as_str()).with_item_reports(&self.state.item_reports);
+                report.duration = Some(started.elapsed());
+                report.archive = Some(name.clone()).filter(|name| !name.is_empty());
+                report.archive_size = index.as_ref().map(|index| index.size);
+                report.encrypted = encrypted;
+                report.checksum = index.as_ref().map(|index| index.checksum.clone());
+                report.destinations = if self.state.destination_results.is_empty() {
+                    let destination = destination.as_ref().map(|d| d.to_string()).unwrap_or_else(|| "Default directory".to_string());
+                    location_names
+                        .iter()
+                        .map(|location| DestinationResult { destination: destination.clone(), outcome: Ok(location.clone()) })
+                        .collect()
+                } else {
+                    self.state.destination_results.clone()
+                };
+                self.state.completion_report = Some(report);
+                self.state.item_failures_only = false;
+                self.state.clear_status();
                 self.state.transition_to(AppState::BackupComplete);
             }
             Err(e) => {
                 error!("Backup failed: {}", e);
-                self.state.set_error(format!("Backup failed: {}", e));
+                self.state.set_retryable_error(AppError::from_anyhow(ErrorCategory::Backup, "Backup failed", &e), RetryOperation::Backup);
             }
         }
         
         Ok(())
     }
 
+    /// Copy the archive written to `staged_in` to every other destination
+    /// at once. The backup succeeds while at least one destination holds
+    /// the archive; each one's outcome is shown on the results screen.
+    async fn copy_to_destinations(
+        &mut self,
+        archive_path: &std::path::Path,
+        destinations: &[Destination],
+        staged_in: Option<&std::path::Path>,
+    ) -> Result<Vec<(PathBuf, Option<String>)>> {
+        let written_to = |destination: &Destination| {
+            matches!(destination, Destination::Local(dir) if Some(dir.as_path()) == staged_in)
+        };
+        let tasks: Vec<_> = destinations
+            .iter()
+            .map(|destination| {
+                let (archive, to, written) = (archive_path.to_path_buf(), destination.clone(), written_to(destination));
+                let throttle = self.state.backup_throttle.clone();
+                tokio::spawn(async move {
+                    if written {
+                        Ok(archive.display().to_string())
+                    } else {
+                        destination::copy_archive(&archive, &to, &throttle).await
+                    }
+                })
+            })
+            .collect();
+
+        let mut held = Vec::new();
+        let mut results = Vec::new();
+        for (destination, task) in destinations.iter().zip(tasks) {
+            let outcome = task.await.map_err(anyhow::Error::from).and_then(|copied| copied);
+            match &outcome {
+                Ok(location) => {
+                    let remote = matches!(destination, Destination::Rclone(_)).then(|| location.clone());
+                    held.push((PathBuf::from(location), remote));
+                }
+                Err(e) => warn!("Failed to copy the archive to {}: {}", destination, e),
+            }
+            results.push(DestinationResult {
+                destination: destination.to_string(),
+                outcome: outcome.map_err(|e| e.to_string()),
+            });
+        }
+
+        // Staged outside every destination, and possibly holding
+        // credentials in complete mode
+        if !destinations.iter().any(written_to) {
+            if let Err(e) = std::fs::remove_file(archive_path) {
+                warn!("Failed to remove staged archive {}: {}", archive_path.display(), e);
+            }
+        }
+
+        let failures: Vec<String> = results
+            .iter()
+            .filter_map(|result| result.outcome.as_ref().err().map(|e| format!("{}: {}", result.destination, e)))
+            .collect();
+        self.state.destination_results = results;
+        if held.is_empty() {
+            anyhow::bail!("No destination could take the archive ({})", failures.join("; "));
+        }
+        Ok(held)
+    }
+
+    async fn upload_to_rclone(&mut self, remote: &str, archive_path: Option<&std::path::Path>) -> Result<String> {
+        let archive_path = archive_path
+            .context("Backup script did not report the archive it created")?;
+
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let upload = RcloneRemote::new(remote)
+            .with_throttle(&self.state.backup_throttle)
+            .upload(archive_path, Some(&progress_tx))
+            .await;
+        drop(progress_tx);
+
+        while let Ok(progress) = progress_rx.try_recv() {
+            self.state.backup_progress = Some(progress);
+        }
+
+        // Remove the local staging copy whether or not the upload succeeded;
+        // it may contain credentials in complete mode
+        if let Err(e) = std::fs::remove_file(archive_path) {
+            warn!("Failed to remove staged archive {}: {}", archive_path.display(), e);
+        }
+
+        upload
+    }
+
     async fn start_restore(&mut self) -> Result<()> {
         info!("Starting restore operation");
         
         if !self.state.is_restore_ready() {
             warn!("No items selected for restore");
-            self.state.set_error("No items selected for restore".to_string());
+            self.state.set_error(AppError::new(ErrorCategory::Selection, "No items selected for restore"));
+            return Ok(());
+        }
+
+        // Browsing used the cached listing; the data comes down first
+        if let Some(archive) = self.state.selected_archive.clone().filter(|archive| !is_fetched(archive)) {
+            if let Err(e) = self.start_download(archive, AfterDownload::Restore) {
+                error!("Failed to fetch remote archive: {}", e);
+                self.state.set_retryable_error(
+                    AppError::from_anyhow(ErrorCategory::Archive, "Failed to fetch remote archive", &e),
+                    RetryOperation::Restore,
+                );
+            }
             return Ok(());
         }
 
@@ -590,22 +4115,93 @@ impl App {
             let restore_password = self.state.restore_password.clone();
             
             self.state.transition_to(AppState::RestoreProgress);
+            let started = std::time::Instant::now();
             
             let selected_item_refs: Vec<&RestoreItem> = selected_items.iter().collect();
+
+            // Never overwrite anything that can't be put back
+            match PreRestoreSnapshot::create(
+                &PreRestoreSnapshot::default_dir(),
+                &archive.name,
+                &selected_item_refs,
+            ).await {
+                Ok(snapshot) => self.state.pre_restore_snapshot = Some(snapshot),
+                Err(e) => {
+                    error!("Pre-restore snapshot failed: {}", e);
+                    self.state.set_retryable_error(
+                        AppError::from_anyhow(ErrorCategory::Restore, "Restore cancelled, could not snapshot existing files", &e),
+                        RetryOperation::Restore,
+                    );
+                    return Ok(());
+                }
+            }
+
             let result = self.backend.start_restore(
                 &archive,
                 selected_item_refs,
                 restore_password.as_ref(),
+                self.state.restore_metadata,
             ).await;
 
+            self.session.record_restore(selected_items.len(), result.is_ok());
+
+            let checksum = Catalog::open_default().and_then(|catalog| catalog.checksum(&archive)).unwrap_or_else(|e| {
+                warn!("Could not look up the checksum of {}: {}", archive.name, e);
+                None
+            });
+            let audit = AuditEvent::new("restore", &archive.path.display().to_string(), &format!("{} items", selected_items.len()))
+                .with_operation(Operation {
+                    mode: archive.mode.as_str().to_string(),
+                    items: selected_items.iter().map(|item| item.original_path.display().to_string()).collect(),
+                    destination: dirs::home_dir().map(|home| home.display().to_string()),
+                    result: operation_result(&result),
+                    archive_checksum: checksum.clone(),
+                    strategy: None,
+                });
+            if let Err(e) = AuditLog::open_default().append(audit) {
+                warn!("Failed to write audit log: {}", e);
+            }
             match result {
                 Ok(_) => {
                     info!("Restore completed successfully");
+                    let mut report = OperationReport::new("Restore", archive.mode.as_str());
+                    report.duration = Some(started.elapsed());
+                    report.archive = Some(archive.name.clone());
+                    report.archive_size = Some(archive.size);
+                    report.encrypted = archive.encrypted;
+                    report.checksum = checksum;
+                    report.items = selected_items
+                        .iter()
+                        .map(|item| ReportItem {
+                            path: item.original_path.display().to_string(),
+                            status: "restored".to_string(),
+                            files: None,
+                            bytes: Some(item.size),
+                        })
+                        .collect();
+
+                    // Credential stores only ship in complete-mode archives.
+                    // ssh and gpg refuse keys others can read, so loose modes
+                    // are fixed before anything tries to use them.
+                    if archive.mode == BackupMode::Complete {
+                        let restored: Vec<&RestoreItem> = selected_items.iter().collect();
+                        self.state.restored_credentials = credential_checks::restored_kinds(&restored);
+                        let paths: Vec<PathBuf> = selected_items
+                            .iter()
+                            .map(|item| item.original_path.strip_prefix("./").unwrap_or(&item.original_path).to_path_buf())
+                            .collect();
+                        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+                        self.state.permission_fixes = credential_checks::harden_permissions(&home_dir, &paths);
+                        report.warnings.extend(self.state.permission_fixes.iter().map(|fix| format!("{}: {}", fix.name, fix.detail)));
+                    }
+                    self.state.completion_report = Some(report);
+                    self.finish_privileged_restore(&selected_items).await;
+                    self.state.clear_status();
                     self.state.transition_to(AppState::RestoreComplete);
                 }
                 Err(e) => {
                     error!("Restore failed: {}", e);
-                    self.state.set_error(format!("Restore failed: {}", e));
+                    self.state.set_retryable_error(AppError::from_anyhow(ErrorCategory::Restore, "Restore failed", &e), RetryOperation::Restore);
                 }
             }
         }
@@ -613,23 +4209,276 @@ impl App {
         Ok(())
     }
 
-    fn get_path_size(path: &std::path::Path) -> Result<u64> {
-        if path.is_file() {
-            Ok(path.metadata()?.len())
-        } else if path.is_dir() {
-            let mut total_size = 0;
-            for entry in std::fs::read_dir(path)? {
-                let entry = entry?;
-                let entry_path = entry.path();
-                if entry_path.is_file() {
-                    total_size += entry.metadata()?.len();
-                } else if entry_path.is_dir() {
-                    total_size += Self::get_path_size(&entry_path)?;
+    async fn reenable_services(&mut self) {
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+
+        match system_state::reenable_services(&archive.path, self.state.restore_password.as_ref()).await {
+            Ok(results) => {
+                self.state.clear_status();
+                self.state
+                    .service_results
+                    .retain(|result| {
+                        desktop_settings::is_desktop_result(result)
+                            || packages::is_package_result(result)
+                            || container_volumes::is_volume_result(result)
+                            || database_dumps::is_database_result(result)
+                    });
+                self.state.service_results.extend(results);
+            }
+            Err(e) => {
+                warn!("Failed to re-enable services: {}", e);
+                self.state.set_status(e.to_string());
+            }
+        }
+    }
+
+    async fn import_desktop_settings(&mut self) {
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+
+        let results =
+            desktop_settings::import_desktop_settings(&archive.path, self.state.restore_password.as_ref()).await;
+        self.state.clear_status();
+        // Shown alongside any service results from this restore
+        self.state.service_results.retain(|result| !desktop_settings::is_desktop_result(result));
+        self.state.service_results.extend(results);
+    }
+
+    /// Write the archive's package lists out as a reinstall script. It is
+    /// left for the user to review and run, as it needs sudo and a
+    /// terminal of its own.
+    async fn write_reinstall_script(&mut self) {
+        let Some(archive) = self.state.selected_archive.clone() else {
+            return;
+        };
+
+        match packages::write_reinstall_script(&archive.path, self.state.restore_password.as_ref()).await {
+            Ok((path, results)) => {
+                info!("Wrote package reinstall script to {}", path.display());
+                self.state.set_status(format!("Reinstall script saved to {}", path.display()));
+                self.state.service_results.retain(|result| !packages::is_package_result(result));
+                self.state.service_results.extend(results);
+            }
+            Err(e) => {
+                warn!("Failed to write reinstall script: {}", e);
+                self.state.set_status(e.to_string());
+            }
+        }
+    }
+
+    async fn rollback_restore(&mut self) {
+        let Some(snapshot) = self.state.pre_restore_snapshot.take() else {
+            return;
+        };
+
+        match snapshot.rollback().await {
+            Ok(_) => {
+                let details = format!("{} paths from restore of {}", snapshot.entries.len(), snapshot.archive);
+                if let Err(e) = AuditLog::open_default().record("restore_rollback", &snapshot.archive, &details) {
+                    warn!("Failed to write audit log: {}", e);
                 }
+                self.state.restore_rolled_back = true;
+                self.session.rollbacks += 1;
+                self.state.set_status("Restore rolled back, files are as they were before".to_string());
             }
-            Ok(total_size)
-        } else {
-            Ok(0)
+            Err(e) => {
+                error!("Rollback failed: {}", e);
+                self.state.set_status(format!("Rollback failed: {}", e));
+                self.state.pre_restore_snapshot = Some(snapshot);
+            }
+        }
+    }
+
+    /// Strip or keep privileged bits on restored files according to the
+    /// user's choice, recording each one in the audit log
+    async fn finish_privileged_restore(&self, items: &[RestoreItem]) {
+        let audit = AuditLog::open_default();
+        let strip = self.state.strip_privileged_bits;
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+
+        for entry in items.iter().flat_map(|item| item.privileged_entries.iter()) {
+            let target = home_dir.join(&entry.path);
+            let decision = if strip {
+                match privileged::strip_privileges(&target).await {
+                    Ok(_) => "stripped",
+                    Err(e) => {
+                        warn!("Failed to strip privileges from {}: {}", target.display(), e);
+                        "strip failed"
+                    }
+                }
+            } else {
+                "kept"
+            };
+
+            let details = format!("{} ({})", entry.describe(), decision);
+            if let Err(e) = audit.record("restore_privileged", &target.to_string_lossy(), &details) {
+                warn!("Failed to write audit log: {}", e);
+            }
+        }
+    }
+
+    /// Bring the status bar up to date with the background work running,
+    /// the time and, every so often, the free space at the destination
+    fn update_status_bar(&mut self) {
+        let mut activities = Vec::new();
+        if self.running_backup.is_some() {
+            activities.push("Backing up".to_string());
+        }
+        if self.archive_download.is_some() {
+            activities.push("Downloading archive".to_string());
+        }
+        if self.state.backup_sizes_pending > 0 {
+            activities.push(format!("Measuring sizes ({} left)", self.state.backup_sizes_pending));
+        }
+        if self.exclusion_counts.is_some() {
+            activities.push("Counting exclusion matches".to_string());
+        }
+        if self.first_estimate.is_some() {
+            activities.push("Estimating the first backup".to_string());
+        }
+        self.state.status_bar.activities = activities;
+        self.state.status_bar.clock = chrono::Local::now().format("%H:%M").to_string();
+
+        if let Some(rx) = self.free_space.as_mut() {
+            match rx.try_recv() {
+                Ok(free) => {
+                    self.state.status_bar.free_space = free;
+                    self.free_space = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => self.free_space = None,
+            }
+            return;
         }
+        if self.free_space_checked.is_some_and(|checked| checked.elapsed() < FREE_SPACE_INTERVAL) {
+            return;
+        }
+        self.free_space_checked = Some(std::time::Instant::now());
+        // Without a destination the wrapper script writes to the working directory
+        let dir = staging_dir(self.state.backup_destination.as_ref(), &self.state.backup_copies)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let free = match dir.ancestors().find(|ancestor| ancestor.is_dir()) {
+                Some(existing) => dir_size::disk_space(existing).await.ok().map(|(_, free)| free),
+                None => None,
+            };
+            let _ = tx.send(free);
+        });
+        self.free_space = Some(rx);
+    }
+
+    /// Apply results from background work; called once per frame
+    pub async fn poll_background(&mut self) {
+        self.poll_download().await;
+        self.poll_backup().await;
+        self.backup_password.poll();
+
+        if let Some(rx) = self.exclusion_counts.as_mut() {
+            match rx.try_recv() {
+                Ok(counts) => {
+                    self.state.exclusion_counts = Some(counts);
+                    self.exclusion_counts = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => self.exclusion_counts = None,
+            }
+        }
+
+        if let Some(rx) = self.first_estimate.as_mut() {
+            match rx.try_recv() {
+                Ok(estimate) => {
+                    self.state.first_estimate = Some(estimate);
+                    self.first_estimate = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => self.first_estimate = None,
+            }
+        }
+
+        let Some(rx) = self.size_updates.as_mut() else {
+            return;
+        };
+
+        loop {
+            match rx.try_recv() {
+                Ok(update) => {
+                    if let Some(item) = self.state.backup_items.iter_mut().find(|item| item.path == update.path) {
+                        item.size = update.size;
+                    }
+                    self.state.backup_sizes_pending = self.state.backup_sizes_pending.saturating_sub(1);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.size_updates = None;
+                    self.state.backup_sizes_pending = 0;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// How often the status bar's free space is checked again
+const FREE_SPACE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Where the archive is written first. Remote destinations are staged
+/// locally and streamed up afterwards. With copies the archive is written
+/// once, to a local destination that is available, and copied from there
+/// to the others. None leaves it to the backup script.
+fn staging_dir(destination: Option<&Destination>, copies: &[Destination]) -> Option<PathBuf> {
+    match destination {
+        Some(Destination::Local(path)) if copies.is_empty() || path.is_dir() => Some(path.clone()),
+        Some(_) => copies
+            .iter()
+            .find_map(|copy| match copy {
+                Destination::Local(path) if path.is_dir() => Some(path.clone()),
+                _ => None,
+            })
+            .or_else(|| Some(std::env::temp_dir())),
+        None => None,
+    }
+}
+
+/// How an operation ended, as the audit log records it
+fn operation_result<T>(result: &Result<T>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => format!("failed: {}", e),
+    }
+}
+
+/// Whether a remote archive has been downloaded; until then its path is
+/// the remote one
+fn is_fetched(archive: &ArchiveInfo) -> bool {
+    archive.remote.as_deref().is_none_or(|remote| archive.path != std::path::Path::new(remote))
+}
+
+/// Restore item for one file picked from the catalog, carrying over any
+/// privileged-bit findings recorded for it
+fn single_file_item(items: &[RestoreItem], target: &CatalogMatch) -> RestoreItem {
+    let original_path = PathBuf::from(&target.item_path);
+    let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+    let restore_path = home_dir.join(&original_path);
+
+    RestoreItem {
+        name: original_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| target.item_path.clone()),
+        conflicts: restore_path.exists(),
+        original_path,
+        restore_path,
+        size: target.size,
+        selected: true,
+        privileged_entries: items
+            .iter()
+            .flat_map(|item| item.privileged_entries.iter())
+            .filter(|entry| entry.path == target.item_path)
+            .cloned()
+            .collect(),
     }
-}
\ No newline at end of file
+}
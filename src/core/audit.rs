@@ -0,0 +1,285 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// Single entry in the append-only audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub target: String,
+    pub details: String,
+    /// `user@host` that did it; empty in entries written before it was kept
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub actor: String,
+    /// What a backup or restore covered and how it ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<Operation>,
+    /// SHA-256 of the previous line of the log, so an edited or removed
+    /// entry breaks the chain at the entry after it
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prev_hash: String,
+}
+
+/// Backup or restore recorded in the audit log
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Operation {
+    pub mode: String,
+    pub items: Vec<String>,
+    /// Where the archive was written, or where files were restored to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// "ok", or "failed: " and the error
+    pub result: String,
+    /// Checksum of the archive written or read, as the catalog records it:
+    /// SHA-256 hex, or `blake3:` and the BLAKE3 hex
+    #[serde(default, alias = "archive_sha256", skip_serializing_if = "Option::is_none")]
+    pub archive_checksum: Option<String>,
+    /// Backup strategy (key of `backup_strategies`) the backup was run for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &str, target: &str, details: &str) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action: action.to_string(),
+            target: target.to_string(),
+            details: details.to_string(),
+            actor: actor(),
+            operation: None,
+            prev_hash: String::new(),
+        }
+    }
+
+    pub fn with_operation(mut self, operation: Operation) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// How the operation ended; empty for other entries
+    pub fn result(&self) -> &str {
+        self.operation.as_ref().map(|operation| operation.result.as_str()).unwrap_or("")
+    }
+}
+
+/// Outcome of checking the integrity chain
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainStatus {
+    /// Every entry links to the one before it, apart from `unchained`
+    /// entries at the start written before the log was chained
+    Intact { unchained: usize },
+    /// The entry at this index (oldest first) doesn't match the line
+    /// before it: an entry was edited, inserted or removed
+    Broken { entry: usize },
+}
+
+/// Append-only JSON-lines log of security-relevant operations
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Default location under the user's data directory
+    pub fn default_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("backup-manager")
+            .join("audit.log")
+    }
+
+    pub fn open_default() -> Self {
+        Self::new(Self::default_path())
+    }
+
+    pub fn record(&self, action: &str, target: &str, details: &str) -> Result<()> {
+        self.append(AuditEvent::new(action, target, details))
+    }
+
+    /// Add `event` at the end of the log, chained to the last entry
+    pub fn append(&self, mut event: AuditEvent) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create audit log directory: {}", parent.display()))?;
+        }
+
+        let mut options = OpenOptions::new();
+        options.create(true).read(true).append(true);
+
+        // The log names restored credential paths, so keep it private
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log: {}", self.path.display()))?;
+
+        // Held until the file is closed, so another process appending at
+        // the same time (a scheduled run-backup beside the UI) can't chain
+        // to the same entry
+        file.lock().with_context(|| format!("Failed to lock audit log: {}", self.path.display()))?;
+        event.prev_hash = last_line(&mut file)
+            .with_context(|| format!("Failed to read audit log: {}", self.path.display()))?
+            .map(|line| line_hash(&line))
+            .unwrap_or_default();
+
+        writeln!(file, "{}", serde_json::to_string(&event)?)?;
+        Ok(())
+    }
+
+    /// Every entry, oldest first, and whether the chain holds
+    pub fn read(&self) -> Result<(Vec<AuditEvent>, ChainStatus)> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read audit log: {}", self.path.display())),
+        };
+
+        let mut events = Vec::new();
+        let mut broken = None;
+        let mut unchained = 0;
+        let mut previous: Option<&str> = None;
+        for line in content.lines() {
+            let index = events.len();
+            let Ok(event) = serde_json::from_str::<AuditEvent>(line) else {
+                broken.get_or_insert(index);
+                continue;
+            };
+            match previous {
+                // Entries from before the chain existed
+                _ if event.prev_hash.is_empty() && index == unchained => unchained += 1,
+                Some(previous) if event.prev_hash == line_hash(previous) => {}
+                _ => {
+                    broken.get_or_insert(index);
+                }
+            }
+            previous = Some(line);
+            events.push(event);
+        }
+
+        // The first entry of a log has nothing before it, but the next
+        // one still covers it
+        let status = match broken {
+            Some(entry) => ChainStatus::Broken { entry },
+            None => ChainStatus::Intact { unchained: unchained.saturating_sub(1) },
+        };
+        Ok((events, status))
+    }
+}
+
+/// Last line of `file`, read back from the end so a long log isn't read
+/// whole on every append
+fn last_line(file: &mut File) -> std::io::Result<Option<String>> {
+    const CHUNK: u64 = 4096;
+    let mut end = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+        end = start;
+
+        let body = tail.strip_suffix(b"\n").unwrap_or(&tail);
+        if let Some(newline) = body.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(Some(String::from_utf8_lossy(&body[newline + 1..]).into_owned()));
+        }
+    }
+    let body = tail.strip_suffix(b"\n").unwrap_or(&tail);
+    Ok((!body.is_empty()).then(|| String::from_utf8_lossy(body).into_owned()))
+}
+
+fn line_hash(line: &str) -> String {
+    Sha256::digest(line.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// `user@host` running this process
+fn actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("{}@{}", user, crate::backend::catalog::local_hostname())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_detects_edits() {
+        let path = std::env::temp_dir().join(format!("audit-chain-test-{}.log", std::process::id()));
+        // An entry from before the chain existed
+        std::fs::write(
+            &path,
+            "{\"timestamp\":\"2025-01-01T00:00:00Z\",\"action\":\"restore_remote\",\"target\":\"nas\",\"details\":\"2 items\"}\n",
+        )
+        .unwrap();
+
+        let log = AuditLog::new(path.clone());
+        log.append(AuditEvent::new("backup", "/mnt/backups/a.tar.gz", "").with_operation(Operation {
+            mode: "secure".to_string(),
+            items: vec![".bashrc".to_string()],
+            destination: Some("/mnt/backups".to_string()),
+            result: "ok".to_string(),
+            archive_checksum: Some("ab".repeat(32)),
+            strategy: None,
+        }))
+        .unwrap();
+        log.record("restore_rollback", "a.tar.gz", "1 paths").unwrap();
+
+        let (events, status) = log.read().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(status, ChainStatus::Intact { unchained: 0 });
+        assert_eq!(events[1].result(), "ok");
+        assert!(events[2].actor.contains('@'));
+
+        // Rewrite the backup's result after the fact
+        let content = std::fs::read_to_string(&path).unwrap().replace("\"result\":\"ok\"", "\"result\":\"failed\"");
+        std::fs::write(&path, content).unwrap();
+        assert_eq!(log.read().unwrap().1, ChainStatus::Broken { entry: 2 });
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_appends_keep_the_chain() {
+        let path = std::env::temp_dir().join(format!("audit-concurrent-test-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        // A first line longer than the chunk read back from the end
+        AuditLog::new(path.clone()).record("backup", &"x".repeat(5000), "").unwrap();
+
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let log = AuditLog::new(path);
+                    for entry in 0..25 {
+                        log.record("restore", &format!("{}-{}", writer, entry), "").unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let (events, status) = AuditLog::new(path.clone()).read().unwrap();
+        assert_eq!(events.len(), 101);
+        assert_eq!(status, ChainStatus::Intact { unchained: 0 });
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,13 +1,30 @@
 use anyhow::{Context, Result};
+use chrono::Local;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::core::types::{BackupItem, BackupMode, SecurityLevel};
+use crate::backend::checksum::HashAlgorithm;
+use crate::backend::browser_profiles::BrowserConfig;
+use crate::backend::container_volumes::{VolumeConfig, VOLUMES_DIR};
+use crate::backend::database_dumps::DatabaseConfig;
+use crate::backend::destination::Destination;
+use crate::backend::packages::{PackageManager, PACKAGES_FILE};
+use crate::backend::system_state::{command_output_path, SYSTEM_STATE_DIR};
+use crate::core::config_history;
+use crate::core::config_migration::{self, CONFIG_SCHEMA_VERSION};
+use crate::core::types::{BackupItem, BackupMode, Compression, SecurityLevel, SymlinkPolicy};
+use crate::ui::theme::Palette;
+
+/// Category of the paths added from the item selection screen
+pub const CUSTOM_CATEGORY: &str = "Custom";
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BackupConfig {
+    /// Layout of this file, upgraded on load by `config_migration`
+    #[serde(default)]
+    pub schema_version: u32,
     pub version: String,
     pub description: String,
     pub last_updated: String,
@@ -16,15 +33,58 @@ pub struct BackupConfig {
     pub security_classifications: HashMap<String, SecurityClassification>,
     pub backup_strategies: HashMap<String, BackupStrategy>,
     pub validation: ValidationConfig,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_sync: Option<CatalogSyncConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_index: Option<SearchIndexConfig>,
+    /// Named backup setups, chosen with `--profile` or before the mode
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, ProfileConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui: Option<UiConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationConfig>,
+    /// Where archives move as they age, applied by `backup-ui maintain`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiers: Vec<TierRule>,
+    /// Small encrypted archives of high-security items only, written by
+    /// `backup-ui vault` on their own schedule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials_vault: Option<CredentialsVaultConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<KeyringConfig>,
+    /// Hours `backup-ui run-backup` may run in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_window: Option<BackupWindowConfig>,
+    /// GPG keys complete-mode archives are encrypted to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionConfig>,
+    /// Look for stray credentials before secure-mode backups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_scan: Option<SecretScanConfig>,
+    /// Home paths mirrored to an NFS share by `backup-ui sync`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nfs_sync: Option<NfsSyncConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModeConfig {
     pub description: String,
     pub excludes_sensitive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub security_warning: Option<String>,
     pub categories: HashMap<String, Vec<String>>,
     pub exclusions: Vec<String>,
+    /// Home-relative paths every backup in this mode must include; leaving
+    /// one out, or one missing on disk, needs explicit acknowledgment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub critical_items: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -35,12 +95,60 @@ pub struct ModernConfigurations {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ApplicationConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub paths: Vec<String>,
+    /// Shell command, run from the home directory, whose output is saved
+    /// into the archive, for state that isn't kept in files, such as
+    /// `code --list-extensions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Package managers (`pacman`, `flatpak`, `cargo`, `pip`, `npm`) whose
+    /// explicitly installed packages are listed into the archive, so a
+    /// restore can install them again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<PackageManager>,
+    /// Docker or Podman volumes copied into the archive, picked on the
+    /// item screen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<VolumeConfig>,
+    /// Browser (`firefox`, `chromium`, `chrome`, `brave`, ...) whose
+    /// profiles `paths` holds, or its usual profile directories when
+    /// `paths` is empty. Caches are left out, and an open browser is
+    /// warned about, waited for or skipped as `when_running` says.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<BrowserConfig>,
     pub description: String,
     pub security_level: String,
     pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub warning: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub exclusions: Option<Vec<String>>,
+    /// `archive` (the default), `follow` or `skip` for links under the
+    /// application's paths
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlinks: Option<SymlinkPolicy>,
+}
+
+impl ApplicationConfig {
+    /// `paths`, or for a browser without any, the profile directories it
+    /// has under the home directory
+    pub fn item_paths(&self) -> Vec<String> {
+        match &self.browser {
+            Some(browser) if self.paths.is_empty() => {
+                browser.name.existing_dirs(&dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")))
+            }
+            _ => self.paths.clone(),
+        }
+    }
+
+    pub fn level(&self) -> SecurityLevel {
+        match self.security_level.as_str() {
+            "high" => SecurityLevel::High,
+            "medium" => SecurityLevel::Medium,
+            _ => SecurityLevel::Low,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -49,6 +157,28 @@ pub struct SecurityClassification {
     pub requires_encryption: serde_json::Value, // Can be bool or string
     pub storage_warning: String,
     pub examples: Vec<String>,
+    /// Home-relative paths put at this level from the item screen; they
+    /// override the built-in rules and the application's own level
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+}
+
+impl SecurityClassification {
+    /// Entry for a level the config doesn't describe yet
+    fn for_level(level: &SecurityLevel) -> Self {
+        let (requires_encryption, storage_warning) = match level {
+            SecurityLevel::High => (serde_json::Value::Bool(true), "Must be stored securely with strong encryption"),
+            SecurityLevel::Medium => (serde_json::Value::from("recommended"), "Should be encrypted for additional security"),
+            SecurityLevel::Low => (serde_json::Value::Bool(false), "Standard backup security practices apply"),
+        };
+        Self {
+            description: format!("Paths classified as {} security", level.as_str()),
+            requires_encryption,
+            storage_warning: storage_warning.to_string(),
+            examples: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -57,10 +187,33 @@ pub struct BackupStrategy {
     pub mode: String,
     pub frequency: String,
     pub retention: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encryption: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub storage: Option<String>,
 }
 
+impl BackupStrategy {
+    pub fn backup_mode(&self) -> Result<BackupMode> {
+        match self.mode.as_str() {
+            "secure" => Ok(BackupMode::Secure),
+            "complete" => Ok(BackupMode::Complete),
+            other => anyhow::bail!("Unknown backup mode '{}'; use secure or complete", other),
+        }
+    }
+
+    /// Time between scheduled runs, or None for on-demand strategies
+    pub fn interval(&self) -> Option<chrono::Duration> {
+        match self.frequency.as_str() {
+            "hourly" => Some(chrono::Duration::hours(1)),
+            "daily" => Some(chrono::Duration::days(1)),
+            "weekly" => Some(chrono::Duration::weeks(1)),
+            "monthly" => Some(chrono::Duration::days(30)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ValidationConfig {
     pub required_tools: Vec<String>,
@@ -68,6 +221,565 @@ pub struct ValidationConfig {
     pub minimum_disk_space: String,
     pub supported_compression: Vec<String>,
     pub supported_encryption: Vec<String>,
+    /// Threads the compressor runs on; one per core when unset or 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_threads: Option<u32>,
+}
+
+/// Compressor and level used for new archives
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompressionConfig {
+    /// One of `validation.supported_compression`
+    pub format: String,
+    /// Compressor level; the format's default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<u32>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            format: Compression::Gzip.as_str().to_string(),
+            level: None,
+        }
+    }
+}
+
+impl CompressionConfig {
+    pub fn compression(&self) -> Compression {
+        Compression::from_name(&self.format).unwrap_or(Compression::Gzip)
+    }
+
+    pub fn level(&self) -> u32 {
+        let compression = self.compression();
+        self.level
+            .filter(|level| compression.level_range().contains(level))
+            .unwrap_or_else(|| compression.default_level())
+    }
+}
+
+/// Shared directory (NFS mount or git checkout) used to exchange catalogs
+/// between machines
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CatalogSyncConfig {
+    pub path: String,
+    /// Strategy (key of `backup_strategies`) each machine is expected to
+    /// follow; machines not listed use the most frequent scheduled strategy
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub host_strategies: HashMap<String, String>,
+}
+
+/// Files kept in step with a directory on an NFS share, separately from
+/// archives: pushed as they change, pulled onto a new machine
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NfsSyncConfig {
+    /// Where the share is mounted. Syncing refuses to run when nothing is
+    /// mounted there rather than fill the local disk
+    pub mount: String,
+    /// Directory under the mount to sync into; the host name when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    /// Home-relative paths to sync
+    pub paths: Vec<String>,
+    /// rsync patterns left out of the sync, such as `Cache` or `*.log`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclusions: Vec<String>,
+    /// Delete files from the share that were deleted locally
+    #[serde(default)]
+    pub delete: bool,
+}
+
+impl CatalogSyncConfig {
+    pub fn resolved_path(&self) -> PathBuf {
+        match (self.path.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(relative), Some(home)) => home.join(relative),
+            _ => PathBuf::from(&self.path),
+        }
+    }
+}
+
+/// Named set of choices for a backup: what to back up, how and where
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileConfig {
+    #[serde(default)]
+    pub description: String,
+    /// `secure` or `complete`; asked for when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Directory or `rclone:<remote>:<path>`; `--output` takes precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// More destinations that each get a copy of the same archive, in
+    /// parallel; one that is unavailable doesn't fail the backup
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub copies: Vec<String>,
+    /// Home-relative paths backed up instead of the mode's items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<String>,
+    /// What to do with symbolic links under the items; `archive` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlinks: Option<SymlinkPolicy>,
+    /// Databases dumped into the archive alongside the files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub databases: Vec<DatabaseConfig>,
+    /// How hard the backup may use the machine; `--nice`, `--ionice` and
+    /// `--max-rate` take precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<ThrottleConfig>,
+}
+
+impl ProfileConfig {
+    pub fn backup_mode(&self) -> Result<Option<BackupMode>> {
+        match self.mode.as_deref() {
+            None => Ok(None),
+            Some("secure") => Ok(Some(BackupMode::Secure)),
+            Some("complete") => Ok(Some(BackupMode::Complete)),
+            Some(other) => anyhow::bail!("Unknown backup mode '{}'; use secure or complete", other),
+        }
+    }
+
+    /// Destination with a leading `~/` expanded
+    pub fn resolved_destination(&self) -> Option<String> {
+        self.destination.as_deref().map(expand_home)
+    }
+
+    /// `copies` with a leading `~/` expanded
+    pub fn resolved_copies(&self) -> Vec<Destination> {
+        self.copies.iter().map(|copy| Destination::parse(&expand_home(copy))).collect()
+    }
+
+    /// An item per database of the profile named `name`, added whichever
+    /// items the backup takes otherwise
+    pub fn database_items(&self, name: &str) -> Vec<BackupItem> {
+        self.databases
+            .iter()
+            .map(|database| {
+                BackupItem::new(
+                    database.name.clone(),
+                    database.dump_path(),
+                    name.to_string(),
+                    format!("{} database {}", database.engine.as_str(), database.target()),
+                )
+                .with_security_level(SecurityLevel::Medium)
+                .with_database(database.clone())
+            })
+            .collect()
+    }
+}
+
+/// Limits that keep a backup running in the background from making the
+/// desktop unusable
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ThrottleConfig {
+    /// CPU niceness of tar, the compressor and gpg, 0 to 19
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// IO scheduling class, `idle` or `best-effort`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ionice: Option<IoClass>,
+    /// Cap in MB/s on writing the archive and on copying or uploading it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_mb_per_sec: Option<f64>,
+}
+
+impl ThrottleConfig {
+    /// These limits, with any set in `overrides` taking their place
+    pub fn overridden_by(&self, overrides: &ThrottleConfig) -> ThrottleConfig {
+        ThrottleConfig {
+            nice: overrides.nice.or(self.nice),
+            ionice: overrides.ionice.or(self.ionice),
+            max_mb_per_sec: overrides.max_mb_per_sec.or(self.max_mb_per_sec),
+        }
+    }
+
+    /// The rate cap in bytes a second; a cap of zero or less is none
+    pub fn bytes_per_second(&self) -> Option<u64> {
+        self.max_mb_per_sec
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| ((rate * 1024.0 * 1024.0) as u64).max(1))
+    }
+}
+
+/// Linux IO scheduling class, as set by `ionice -c`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoClass {
+    /// Only when no other process wants the disk
+    Idle,
+    /// The default class, at its lowest priority
+    BestEffort,
+}
+
+impl std::str::FromStr for IoClass {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "idle" => Ok(IoClass::Idle),
+            "best-effort" => Ok(IoClass::BestEffort),
+            other => Err(format!("unknown IO class '{}'; use idle or best-effort", other)),
+        }
+    }
+}
+
+/// `destination` with a leading `~/` replaced by the home directory
+pub fn expand_home(destination: &str) -> String {
+    match (destination.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(relative), Some(home)) => home.join(relative).to_string_lossy().to_string(),
+        _ => destination.to_string(),
+    }
+}
+
+/// Why a critical item won't be in a backup
+#[derive(Debug, Clone, PartialEq)]
+pub enum CriticalGapReason {
+    NotSelected,
+    Missing,
+}
+
+/// Critical item a backup is about to leave out
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriticalGap {
+    pub path: String,
+    pub reason: CriticalGapReason,
+}
+
+/// What the catalog's search index holds beyond file names
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SearchIndexConfig {
+    /// Also index the text of small text files in unencrypted archives
+    #[serde(default)]
+    pub contents: bool,
+    /// Larger files are found by name only
+    #[serde(default = "default_max_text_kb")]
+    pub max_file_kb: u64,
+}
+
+fn default_max_text_kb() -> u64 {
+    256
+}
+
+/// Desktop notifications when a backup or other long job finishes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotificationsConfig {
+    #[serde(default = "default_true")]
+    pub on_success: bool,
+    #[serde(default = "default_true")]
+    pub on_failure: bool,
+    /// Jobs that finish sooner than this don't notify
+    #[serde(default = "default_min_seconds")]
+    pub min_seconds: u64,
+    /// Name the destination and the error; notifications can appear on a
+    /// locked screen, where these would be readable by anyone nearby
+    #[serde(default = "default_true")]
+    pub show_details: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_seconds() -> u64 {
+    30
+}
+
+/// Retention tier: archives older than `older_than_days` belong in
+/// `destination`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TierRule {
+    pub older_than_days: u32,
+    /// Directory or `rclone:<remote>:<path>`
+    pub destination: String,
+    /// Also move unencrypted archives to an rclone remote; off by default
+    /// so readable archives stay on storage you control
+    #[serde(default)]
+    pub allow_unencrypted: bool,
+}
+
+impl TierRule {
+    /// Destination with a leading `~/` expanded
+    pub fn resolved_destination(&self) -> Destination {
+        Destination::parse(&expand_home(&self.destination))
+    }
+}
+
+/// How archives are checksummed when cataloged and later verified
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VerificationConfig {
+    /// `sha256`, or `blake3` for much faster hashing of large archives
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// Days before `backup-ui scrub` re-hashes an archive it found intact
+    #[serde(default = "default_scrub_interval_days")]
+    pub scrub_interval_days: u32,
+    /// Percentage of each archive's blocks that Reed-Solomon parity
+    /// written beside it can rebuild; none is written when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parity_percent: Option<u32>,
+}
+
+fn default_scrub_interval_days() -> u32 {
+    30
+}
+
+/// Time of day scheduled backups may run in, such as 01:00 to 06:00. An
+/// end before the start runs past midnight.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BackupWindowConfig {
+    /// `HH:MM`, local time
+    pub start: String,
+    pub end: String,
+}
+
+impl BackupWindowConfig {
+    /// End of the window `now` is in, or None when it is outside
+    pub fn end_after(&self, now: chrono::NaiveDateTime) -> Result<Option<chrono::NaiveDateTime>> {
+        let parse = |time: &str| {
+            chrono::NaiveTime::parse_from_str(time, "%H:%M")
+                .with_context(|| format!("backup_window times are HH:MM, not {}", time))
+        };
+        let (start, end) = (parse(&self.start)?, parse(&self.end)?);
+        if start == end {
+            anyhow::bail!("backup_window starts and ends at {}", self.start);
+        }
+        let (date, time) = (now.date(), now.time());
+        let today_end = date.and_time(end);
+        Ok(if start < end {
+            (start <= time && time < end).then_some(today_end)
+        } else if time >= start {
+            Some(today_end + chrono::Duration::days(1))
+        } else {
+            (time < end).then_some(today_end)
+        })
+    }
+}
+
+/// Remembering the complete-mode passphrase in GNOME Keyring or KWallet,
+/// so scheduled and repeated backups don't need it typed
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KeyringConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Name the passphrase is filed under; give configs with different
+    /// passphrases different accounts
+    #[serde(default = "default_keyring_account")]
+    pub account: String,
+}
+
+fn default_keyring_account() -> String {
+    "complete-mode".to_string()
+}
+
+/// Public-key encryption of complete-mode archives
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EncryptionConfig {
+    /// Fingerprints or long key IDs ticked when the key screen opens
+    #[serde(default)]
+    pub recipients: Vec<String>,
+}
+
+/// Scan of the selected files for credentials before a secure-mode backup;
+/// on unless a `secret_scan` section turns it off
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecretScanConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Files never to flag, as exclusion patterns
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+/// Where credentials vaults go and how often a new one is made
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CredentialsVaultConfig {
+    #[serde(default = "default_vault_directory")]
+    pub directory: String,
+    /// Hours after the newest vault before `backup-ui vault` makes another
+    #[serde(default = "default_vault_interval_hours")]
+    pub interval_hours: u32,
+    /// Vaults kept; older ones are deleted as new ones are made
+    #[serde(default = "default_vault_keep")]
+    pub keep: usize,
+}
+
+impl Default for CredentialsVaultConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_vault_directory(),
+            interval_hours: default_vault_interval_hours(),
+            keep: default_vault_keep(),
+        }
+    }
+}
+
+impl CredentialsVaultConfig {
+    /// Directory with a leading `~/` expanded
+    pub fn resolved_directory(&self) -> PathBuf {
+        PathBuf::from(expand_home(&self.directory))
+    }
+
+    pub fn interval(&self) -> chrono::Duration {
+        chrono::Duration::hours(i64::from(self.interval_hours))
+    }
+}
+
+fn default_vault_directory() -> String {
+    "~/backups/vault".to_string()
+}
+
+fn default_vault_interval_hours() -> u32 {
+    24
+}
+
+fn default_vault_keep() -> usize {
+    14
+}
+
+/// How the terminal UI looks
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UiConfig {
+    /// `standard` (or `dark`), `light`, `high-contrast`, or `colorblind`
+    /// for blue, yellow and vermillion in place of green, yellow and red
+    #[serde(default)]
+    pub palette: Palette,
+    /// Text tags such as `[HIGH]` and `[!]` beside colored statuses
+    #[serde(default = "default_true")]
+    pub status_tags: bool,
+    /// Draw borders, icons and arrows in plain ASCII, for terminals
+    /// without Unicode fonts. Unset, it is on for the Linux console and
+    /// locales that aren't UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ascii: Option<bool>,
+}
+
+/// JSON-lines log file kept as well as the console log, so diagnostics
+/// from the full-screen UI survive it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoggingConfig {
+    /// Defaults to `~/.local/state/backup-manager/logs/backup-ui.log`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Lowest level written to the file
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// Size at which the file is rotated
+    #[serde(default = "default_log_max_bytes")]
+    pub max_bytes: u64,
+    /// Rotated files kept besides the current one
+    #[serde(default = "default_log_keep")]
+    pub keep: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            level: default_log_level(),
+            max_bytes: default_log_max_bytes(),
+            keep: default_log_keep(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn resolved_file(&self) -> Option<PathBuf> {
+        let file = self.file.as_deref()?;
+        Some(match (file.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(relative), Some(home)) => home.join(relative),
+            _ => PathBuf::from(file),
+        })
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_max_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_log_keep() -> usize {
+    5
+}
+
+/// File formats the config can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub const ALL: [ConfigFormat; 3] = [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Toml => "TOML",
+            ConfigFormat::Yaml => "YAML",
+        }
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ConfigFormat::Json => &["json"],
+            ConfigFormat::Toml => &["toml"],
+            ConfigFormat::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    /// Format named by the file extension, if it is a config extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        Self::ALL.into_iter().find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    pub fn parse(&self, content: &str) -> Result<BackupConfig> {
+        BackupConfig::from_value(self.parse_value(content)?).map(|(config, _)| config)
+    }
+
+    /// The file's settings before migration and type checking
+    fn parse_value(&self, content: &str) -> Result<serde_json::Value> {
+        let value: serde_json::Value = match self {
+            ConfigFormat::Json => serde_json::from_str(content).context("Failed to parse config JSON")?,
+            ConfigFormat::Toml => toml::from_str(content).context("Failed to parse config TOML")?,
+            ConfigFormat::Yaml => serde_yaml::from_str(content).context("Failed to parse config YAML")?,
+        };
+        if !value.is_object() {
+            anyhow::bail!("{} config must be a map of settings at the top level", self.as_str());
+        }
+        Ok(value)
+    }
+
+    /// Serialize with map keys in a stable order, so successive versions
+    /// diff cleanly
+    pub fn render(&self, config: &BackupConfig) -> Result<String> {
+        let value = serde_json::to_value(config)?;
+        let content = match self {
+            ConfigFormat::Json => format!("{}\n", serde_json::to_string_pretty(&value)?),
+            ConfigFormat::Toml => toml::to_string_pretty(&value)?,
+            ConfigFormat::Yaml => serde_yaml::to_string(&value)?,
+        };
+        Ok(content)
+    }
+}
+
+/// `path` followed by the same name with each other config extension
+fn with_config_extensions(path: &Path) -> Vec<PathBuf> {
+    let mut candidates = vec![path.to_path_buf()];
+    if ConfigFormat::from_path(path).is_some() {
+        for format in ConfigFormat::ALL {
+            for extension in format.extensions() {
+                let candidate = path.with_extension(extension);
+                if candidate != path {
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+    candidates
 }
 
 impl BackupConfig {
@@ -77,17 +789,112 @@ impl BackupConfig {
         // Try to find the config file in multiple locations
         let config_path = Self::find_config_file(specified_path)?;
         
-        let content = fs::read_to_string(&config_path)
-            .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
-        
-        let config: BackupConfig = serde_json::from_str(&content)
-            .with_context(|| "Failed to parse config JSON")?;
-        
+        let (mut config, migrated_from) = Self::read_migrated(&config_path)?;
+
+        // Write the upgrade back so it happens once; `save` keeps the
+        // original as a timestamped snapshot
+        if let Some(from) = migrated_from {
+            match config.save(&config_path) {
+                Ok(snapshot) => log::info!(
+                    "Upgraded {} from schema version {} to {}; original kept as {}",
+                    config_path.display(),
+                    from,
+                    CONFIG_SCHEMA_VERSION,
+                    snapshot.map(|p| p.display().to_string()).unwrap_or_default()
+                ),
+                Err(e) => log::warn!(
+                    "Using {} upgraded to schema version {} without saving it: {}",
+                    config_path.display(),
+                    CONFIG_SCHEMA_VERSION,
+                    e
+                ),
+            }
+        }
         Ok(config)
     }
+
+    /// Read exactly `path`, upgrading it in memory only. Used for files
+    /// that must not be rewritten, such as config snapshots.
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::read_migrated(path).map(|(config, _)| config)
+    }
+
+    fn read_migrated(path: &Path) -> Result<(Self, Option<u32>)> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        Self::parse_migrated(path, &content)
+            .with_context(|| format!("Invalid config file: {}", path.display()))
+    }
+
+    /// Parse in the format named by the extension. Files without one, such
+    /// as config snapshots, are tried as each format in turn.
+    fn parse_migrated(path: &Path, content: &str) -> Result<(Self, Option<u32>)> {
+        if let Some(format) = ConfigFormat::from_path(path) {
+            return Self::from_value(format.parse_value(content)?);
+        }
+
+        // JSON is the historical default, so its error is the one reported
+        let mut first_error = None;
+        for format in ConfigFormat::ALL {
+            match format.parse_value(content) {
+                Ok(value) => return Self::from_value(value),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.expect("at least one config format"))
+    }
+
+    /// Upgrade parsed settings to the current schema, then check them
+    /// against it. Returns the schema version the settings started at if
+    /// they needed upgrading.
+    fn from_value(mut value: serde_json::Value) -> Result<(Self, Option<u32>)> {
+        let migrated_from = config_migration::migrate(&mut value)?;
+        let config = serde_json::from_value(value)
+            .context("Config does not match the expected layout")?;
+        Ok((config, migrated_from))
+    }
+    
+    /// Write the config back to `path`, first copying the current file to a
+    /// timestamped `.bak` alongside it and pruning snapshots beyond
+    /// `CONFIG_HISTORY_LIMIT`. Returns the backup's path, if one was made.
+    pub fn save(&mut self, path: &Path) -> Result<Option<PathBuf>> {
+        self.last_updated = Local::now().format("%Y-%m-%d").to_string();
+
+        let backup_path = if path.exists() {
+            let backup_path = config_history::snapshot_path(path);
+            // fs::copy carries the original permissions over to the backup
+            fs::copy(path, &backup_path)
+                .with_context(|| format!("Failed to back up config to {}", backup_path.display()))?;
+            if let Err(e) = config_history::prune_snapshots(path, config_history::CONFIG_HISTORY_LIMIT) {
+                log::warn!("Failed to prune old config snapshots: {}", e);
+            }
+            Some(backup_path)
+        } else {
+            None
+        };
+
+        let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Json);
+        let content = format.render(self)?;
+
+        // Write to a sibling temp file and rename so a crash never leaves a
+        // half-written config behind for scheduled backups to choke on
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("json");
+        let tmp_path = path.with_extension(format!("{}.tmp", extension));
+        fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write config: {}", tmp_path.display()))?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&tmp_path, metadata.permissions())?;
+        }
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to replace config: {}", path.display()))?;
+
+        Ok(backup_path)
+    }
     
     /// Find the config file by checking multiple standard locations
-    fn find_config_file(specified_path: &std::path::Path) -> Result<PathBuf> {
+    pub fn find_config_file(specified_path: &std::path::Path) -> Result<PathBuf> {
         // First try the exact path specified
         if specified_path.exists() {
             return Ok(specified_path.to_path_buf());
@@ -135,11 +942,14 @@ impl BackupConfig {
             }
         }
         
-        // Try each location
+        // Try each location, accepting the same name in another format
+        // (`backup-config.toml` when `backup-config.json` was asked for)
         for path in &search_paths {
-            if path.exists() {
-                log::debug!("Found config file at: {}", path.display());
-                return Ok(path.clone());
+            for candidate in with_config_extensions(path) {
+                if candidate.exists() {
+                    log::debug!("Found config file at: {}", candidate.display());
+                    return Ok(candidate);
+                }
             }
         }
         
@@ -155,6 +965,57 @@ impl BackupConfig {
         );
     }
 
+    /// How often `hostname` is expected to back up
+    pub fn expected_interval(&self, hostname: &str) -> Option<chrono::Duration> {
+        let assigned = self
+            .catalog_sync
+            .as_ref()
+            .and_then(|sync| sync.host_strategies.get(hostname))
+            .and_then(|name| self.backup_strategies.get(name));
+
+        match assigned {
+            Some(strategy) => strategy.interval(),
+            None => self.backup_strategies.values().filter_map(BackupStrategy::interval).min(),
+        }
+    }
+
+    /// Checksum algorithm for new archives; archives already cataloged are
+    /// verified with whatever they were recorded with
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.verification.as_ref().map(|verification| verification.algorithm).unwrap_or_default()
+    }
+
+    /// Threads the compressor runs on, 0 for one per core
+    pub fn compression_threads(&self) -> u32 {
+        self.validation.compression_threads.unwrap_or(0)
+    }
+
+    /// Keyring account holding the complete-mode passphrase, when the
+    /// keyring is enabled
+    pub fn keyring_account(&self) -> Option<&str> {
+        self.keyring.as_ref().filter(|keyring| keyring.enabled).map(|keyring| keyring.account.as_str())
+    }
+
+    /// Redundancy of the parity written beside new local archives, if any
+    pub fn parity_percent(&self) -> Option<u32> {
+        self.verification.as_ref().and_then(|verification| verification.parity_percent).filter(|percent| *percent > 0)
+    }
+
+    /// How long an archive found intact goes before it is scrubbed again
+    pub fn scrub_interval(&self) -> chrono::Duration {
+        let days = self.verification.as_ref().map(|v| v.scrub_interval_days).unwrap_or_else(default_scrub_interval_days);
+        chrono::Duration::days(i64::from(days))
+    }
+
+    /// Whether a backup taken at `last_backup` is older than the host's
+    /// schedule allows, with a quarter-interval grace for drifting timers
+    pub fn is_overdue(&self, hostname: &str, last_backup: chrono::DateTime<chrono::Utc>) -> bool {
+        match self.expected_interval(hostname) {
+            Some(interval) => chrono::Utc::now() - last_backup > interval + interval / 4,
+            None => false,
+        }
+    }
+
     pub fn get_items_for_mode(&self, mode: &BackupMode) -> Vec<BackupItem> {
         let mode_str = mode.as_str();
         let mut items = Vec::new();
@@ -163,22 +1024,13 @@ impl BackupConfig {
         if let Some(mode_config) = self.backup_modes.get(mode_str) {
             for (category, paths) in &mode_config.categories {
                 for path in paths {
-                    let mut item = BackupItem::new(
-                        path.clone(),
-                        PathBuf::from(path),
-                        category.clone(),
-                        format!("Backup item from {} category", category),
-                    );
-                    
-                    // Set security level based on path
-                    item.security_level = self.determine_security_level(path);
-                    
-                    // Add warnings for sensitive items
-                    if let Some(warning) = self.get_security_warning(path) {
-                        item = item.with_warning(warning);
+                    // Paths classified high are left to complete mode, like
+                    // high-security applications
+                    if mode == &BackupMode::Secure && self.determine_security_level(path) == SecurityLevel::High {
+                        continue;
                     }
-                    
-                    items.push(item);
+
+                    items.push(self.path_item(path, category));
                 }
             }
         }
@@ -191,19 +1043,23 @@ impl BackupConfig {
                     continue;
                 }
 
-                for path in &app_config.paths {
+                for path in &app_config.item_paths() {
+                    let security_level = self.classified_level(path).unwrap_or_else(|| app_config.level());
+                    if mode == &BackupMode::Secure && security_level == SecurityLevel::High {
+                        continue;
+                    }
+
                     let mut item = BackupItem::new(
                         format!("{} ({})", app_name, path),
                         PathBuf::from(path),
                         app_config.category.clone(),
                         app_config.description.clone(),
-                    );
+                    )
+                    .with_application(app_name.clone());
 
-                    item.security_level = match app_config.security_level.as_str() {
-                        "high" => SecurityLevel::High,
-                        "medium" => SecurityLevel::Medium,
-                        _ => SecurityLevel::Low,
-                    };
+                    item.security_level = security_level;
+                    item.symlinks = app_config.symlinks.unwrap_or_default();
+                    item.browser = app_config.browser.clone();
 
                     if let Some(warning) = &app_config.warning {
                         item = item.with_warning(warning.clone());
@@ -211,13 +1067,212 @@ impl BackupConfig {
 
                     items.push(item);
                 }
+
+                if let Some(command) = &app_config.command {
+                    let mut item = BackupItem::new(
+                        format!("{} (`{}`)", app_name, command),
+                        command_output_path(app_name),
+                        app_config.category.clone(),
+                        app_config.description.clone(),
+                    )
+                    .with_command(command.clone())
+                    .with_application(app_name.clone());
+                    item.security_level = app_config.level();
+                    if let Some(warning) = &app_config.warning {
+                        item = item.with_warning(warning.clone());
+                    }
+                    items.push(item);
+                }
+
+                if !app_config.packages.is_empty() {
+                    let mut item = BackupItem::new(
+                        app_name.clone(),
+                        Path::new(SYSTEM_STATE_DIR).join(PACKAGES_FILE),
+                        app_config.category.clone(),
+                        app_config.description.clone(),
+                    )
+                    .with_packages(app_config.packages.clone())
+                    .with_application(app_name.clone());
+                    item.security_level = app_config.level();
+                    if let Some(warning) = &app_config.warning {
+                        item = item.with_warning(warning.clone());
+                    }
+                    items.push(item);
+                }
+
+                if let Some(volumes) = &app_config.volumes {
+                    let mut item = BackupItem::new(
+                        app_name.clone(),
+                        Path::new(SYSTEM_STATE_DIR).join(VOLUMES_DIR),
+                        app_config.category.clone(),
+                        app_config.description.clone(),
+                    )
+                    .with_volumes(volumes.clone())
+                    .with_application(app_name.clone());
+                    item.security_level = app_config.level();
+                    if let Some(warning) = &app_config.warning {
+                        item = item.with_warning(warning.clone());
+                    }
+                    items.push(item);
+                }
             }
         }
 
         items
     }
 
+    /// High-security items of complete mode, once each: what goes into a
+    /// credentials vault
+    pub fn vault_items(&self) -> Vec<BackupItem> {
+        let mut items: Vec<BackupItem> = self
+            .get_items_for_mode(&BackupMode::Complete)
+            .into_iter()
+            // Vaults hold files only
+            .filter(|item| item.security_level == SecurityLevel::High && !item.is_generated())
+            .collect();
+        items.sort_by(|a, b| a.path.cmp(&b.path));
+        items.dedup_by(|a, b| a.path == b.path);
+        items
+    }
+
+    /// Critical items of `mode` that `selected` leaves out or that don't
+    /// exist under `home`. An item counts as included when it, or a
+    /// directory above it, is selected.
+    pub fn critical_gaps(&self, mode: &BackupMode, selected: &[&BackupItem], home: &Path) -> Vec<CriticalGap> {
+        let Some(mode_config) = self.backup_modes.get(mode.as_str()) else {
+            return Vec::new();
+        };
+        mode_config.critical_items
+            .iter()
+            .filter_map(|path| {
+                let critical = Path::new(path);
+                let reason = if !home.join(critical).exists() {
+                    CriticalGapReason::Missing
+                } else if !selected.iter().any(|item| critical.starts_with(&item.path)) {
+                    CriticalGapReason::NotSelected
+                } else {
+                    return None;
+                };
+                Some(CriticalGap { path: path.clone(), reason })
+            })
+            .collect()
+    }
+
+    /// A profile's own items. Paths holding private keys or credentials are
+    /// left out of secure mode, which doesn't encrypt the archive.
+    pub fn get_items_for_profile(&self, name: &str, profile: &ProfileConfig, mode: &BackupMode) -> Vec<BackupItem> {
+        let mut items = Vec::new();
+        for path in &profile.items {
+            let security_level = self.determine_security_level(path);
+            if mode == &BackupMode::Secure && security_level == SecurityLevel::High {
+                log::warn!("Profile {} lists {}, which only complete mode backs up", name, path);
+                continue;
+            }
+
+            let mut item = BackupItem::new(
+                path.clone(),
+                PathBuf::from(path),
+                name.to_string(),
+                format!("Backup item from profile {}", name),
+            );
+            item.security_level = security_level;
+            item.symlinks = profile.symlinks.unwrap_or_default();
+            if let Some(warning) = self.get_security_warning(path) {
+                item = item.with_warning(warning);
+            }
+            items.push(item);
+        }
+        items
+    }
+
+    /// Item backing up the home-relative `path`, with the security level
+    /// and warning its classification gives it
+    pub fn path_item(&self, path: &str, category: &str) -> BackupItem {
+        let mut item = BackupItem::new(
+            path.to_string(),
+            PathBuf::from(path),
+            category.to_string(),
+            format!("Backup item from {} category", category),
+        );
+        item.security_level = self.determine_security_level(path);
+        if let Some(warning) = self.get_security_warning(path) {
+            item = item.with_warning(warning);
+        }
+        item
+    }
+
+    /// Back up `path` in every `mode` backup from now on, under the
+    /// [`CUSTOM_CATEGORY`]; false when the mode already has it
+    pub fn add_custom_path(&mut self, mode: &BackupMode, path: &str) -> bool {
+        let Some(mode_config) = self.backup_modes.get_mut(mode.as_str()) else {
+            return false;
+        };
+        if mode_config.categories.values().flatten().any(|existing| existing == path) {
+            return false;
+        }
+        mode_config.categories.entry(CUSTOM_CATEGORY.to_string()).or_default().push(path.to_string());
+        true
+    }
+
+    /// Put `path` at `level`, replacing any classification it had
+    pub fn classify(&mut self, path: &str, level: SecurityLevel) {
+        for classification in self.security_classifications.values_mut() {
+            classification.paths.retain(|classified| classified != path);
+        }
+        self.security_classifications
+            .entry(level.as_str().to_string())
+            .or_insert_with(|| SecurityClassification::for_level(&level))
+            .paths
+            .push(path.to_string());
+    }
+
+    /// Set the volumes of the application named `app`; false when no
+    /// application of that name backs up volumes
+    pub fn set_volumes(&mut self, app: &str, volumes: VolumeConfig) -> bool {
+        let config = self
+            .modern_configurations
+            .categories
+            .values_mut()
+            .filter_map(|apps| apps.get_mut(app))
+            .find(|config| config.volumes.is_some());
+        match config {
+            Some(config) => {
+                config.volumes = Some(volumes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Level of the most specific classified path that is `path` or holds it
+    fn classified_level(&self, path: &str) -> Option<SecurityLevel> {
+        [SecurityLevel::High, SecurityLevel::Medium, SecurityLevel::Low]
+            .into_iter()
+            .flat_map(|level| {
+                let paths = self.security_classifications.get(level.as_str()).map(|c| c.paths.as_slice()).unwrap_or_default();
+                paths
+                    .iter()
+                    .filter(|classified| Path::new(path).starts_with(classified.as_str()))
+                    .map(move |classified| (classified.len(), level.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .max_by_key(|(length, _)| *length)
+            .map(|(_, level)| level)
+    }
+
+    /// Paths classified at `level` from the item screen
+    pub fn classified_paths(&self, level: &SecurityLevel) -> Vec<PathBuf> {
+        self.security_classifications
+            .get(level.as_str())
+            .map(|classification| classification.paths.iter().map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+
     fn determine_security_level(&self, path: &str) -> SecurityLevel {
+        if let Some(level) = self.classified_level(path) {
+            return level;
+        }
+
         // High security paths
         let high_security = [".ssh", ".gnupg", ".aws", ".kube", ".docker/config.json"];
         if high_security.iter().any(|&p| path.contains(p)) {
@@ -266,4 +1321,209 @@ impl BackupConfig {
             None
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_formats_round_trip() {
+        let json = include_str!("../../backup-config.json");
+        let (config, migrated_from) = BackupConfig::parse_migrated(Path::new("backup-config.json"), json).unwrap();
+        assert_eq!(migrated_from, None);
+        let expected = ConfigFormat::Json.render(&config).unwrap();
+
+        for format in [ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let content = format.render(&config).unwrap();
+            let parsed = format.parse(&content).unwrap();
+            assert_eq!(ConfigFormat::Json.render(&parsed).unwrap(), expected, "{}", format.as_str());
+
+            // Snapshots have no config extension and are recognized by content
+            let (snapshot, _) =
+                BackupConfig::parse_migrated(Path::new("backup-config.toml.20240101-120000.bak"), &content).unwrap();
+            assert_eq!(ConfigFormat::Json.render(&snapshot).unwrap(), expected);
+        }
+
+        assert_eq!(ConfigFormat::from_path(Path::new("a/backup-config.YML")), Some(ConfigFormat::Yaml));
+        assert!(BackupConfig::parse_migrated(Path::new("backup-config.toml"), json).is_err());
+    }
+
+    #[test]
+    fn test_profile_items_leave_credentials_out_of_secure_mode() {
+        let json = include_str!("../../backup-config.json");
+        let config = ConfigFormat::Json.parse(json).unwrap();
+        let profile = ProfileConfig {
+            mode: Some("secure".to_string()),
+            items: vec![".bashrc".to_string(), ".ssh".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(profile.backup_mode().unwrap(), Some(BackupMode::Secure));
+
+        let secure = config.get_items_for_profile("laptop", &profile, &BackupMode::Secure);
+        let paths: Vec<&str> = secure.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(paths, vec![".bashrc"]);
+        assert_eq!(secure[0].category, "laptop");
+
+        let complete = config.get_items_for_profile("laptop", &profile, &BackupMode::Complete);
+        assert_eq!(complete.len(), 2);
+        assert_eq!(complete[1].security_level, SecurityLevel::High);
+
+        let invalid = ProfileConfig { mode: Some("fast".to_string()), ..Default::default() };
+        assert!(invalid.backup_mode().is_err());
+    }
+
+    #[test]
+    fn test_classified_paths_override_security_levels() {
+        use crate::core::exclusions::ExclusionSet;
+
+
+        let json = include_str!("../../backup-config.json");
+        let mut config = ConfigFormat::Json.parse(json).unwrap();
+        config.classify(".local/bin", SecurityLevel::High);
+        config.classify(".config/secrets", SecurityLevel::Medium);
+        config.classify(".config/secrets", SecurityLevel::High);
+        config.classify(".config/secrets/public", SecurityLevel::Low);
+        config.classify(".ssh", SecurityLevel::Low);
+
+        assert_eq!(config.determine_security_level(".local/bin"), SecurityLevel::High);
+        assert_eq!(config.determine_security_level(".config/secrets/api"), SecurityLevel::High);
+        assert_eq!(config.determine_security_level(".config/secrets/public/key"), SecurityLevel::Low);
+        assert_eq!(config.determine_security_level(".ssh"), SecurityLevel::Low);
+        assert!(config.security_classifications["medium"].paths.is_empty());
+
+        // Secure mode leaves high items and paths out, complete mode keeps them
+        let secure = config.get_items_for_mode(&BackupMode::Secure);
+        assert!(!secure.iter().any(|item| item.path == Path::new(".local/bin")));
+        assert!(config.get_items_for_mode(&BackupMode::Complete).iter().any(|item| item.path == Path::new(".local/bin")));
+        let exclusions = ExclusionSet::for_mode(&config, &BackupMode::Secure).unwrap();
+        assert!(exclusions.is_excluded(Path::new(".config/secrets")));
+        assert!(!ExclusionSet::for_mode(&config, &BackupMode::Complete).unwrap().is_excluded(Path::new(".config/secrets")));
+
+        // Classifications survive a save and load
+        let reparsed = ConfigFormat::Json.parse(&ConfigFormat::Json.render(&config).unwrap()).unwrap();
+        assert_eq!(reparsed.classified_paths(&SecurityLevel::High), [PathBuf::from(".local/bin"), PathBuf::from(".config/secrets")]);
+    }
+
+    #[test]
+    fn test_critical_gaps() {
+        let home = std::env::temp_dir().join(format!("critical-items-{}", std::process::id()));
+        fs::create_dir_all(home.join(".ssh")).unwrap();
+        fs::create_dir_all(home.join(".local/share/keyrings")).unwrap();
+
+        let json = include_str!("../../backup-config.json");
+        let mut config = ConfigFormat::Json.parse(json).unwrap();
+        config.backup_modes.get_mut("complete").unwrap().critical_items =
+            vec![".ssh".to_string(), ".local/share/keyrings".to_string(), ".password-store".to_string()];
+
+        let item = |path: &str| BackupItem::new(path.to_string(), PathBuf::from(path), "test".to_string(), String::new());
+        let items = [item(".local/share"), item(".bashrc")];
+        let selected: Vec<&BackupItem> = items.iter().collect();
+
+        let gaps = config.critical_gaps(&BackupMode::Complete, &selected, &home);
+        assert_eq!(gaps, vec![
+            CriticalGap { path: ".ssh".to_string(), reason: CriticalGapReason::NotSelected },
+            CriticalGap { path: ".password-store".to_string(), reason: CriticalGapReason::Missing },
+        ]);
+        assert!(config.critical_gaps(&BackupMode::Secure, &selected, &home).is_empty());
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_custom_paths() {
+        let json = include_str!("../../backup-config.json");
+        let mut config = ConfigFormat::Json.parse(json).unwrap();
+        assert!(config.add_custom_path(&BackupMode::Secure, "Projects/notes"));
+        assert!(!config.add_custom_path(&BackupMode::Secure, "Projects/notes"));
+
+        let items = config.get_items_for_mode(&BackupMode::Secure);
+        let custom = items.iter().find(|item| item.name == "Projects/notes").unwrap();
+        assert_eq!(custom.category, CUSTOM_CATEGORY);
+        assert!(!config.get_items_for_mode(&BackupMode::Complete).iter().any(|item| item.name == "Projects/notes"));
+
+        let ssh = config.path_item(".ssh/config", CUSTOM_CATEGORY);
+        assert_eq!(ssh.security_level, SecurityLevel::High);
+        assert!(ssh.warning.is_some());
+    }
+
+    #[test]
+    fn test_overdue_follows_each_host_strategy() {
+        let json = include_str!("../../backup-config.json");
+        let mut config = ConfigFormat::Json.parse(json).unwrap();
+        let ago = |hours: i64| chrono::Utc::now() - chrono::Duration::hours(hours);
+
+        // Unassigned machines are held to the most frequent schedule, daily
+        assert_eq!(config.expected_interval("laptop"), Some(chrono::Duration::days(1)));
+        assert!(!config.is_overdue("laptop", ago(29)));
+        assert!(config.is_overdue("laptop", ago(31)));
+
+        config.catalog_sync = Some(CatalogSyncConfig {
+            path: "/mnt/shared/catalogs".to_string(),
+            host_strategies: HashMap::from([
+                ("server".to_string(), "weekly_complete".to_string()),
+                ("spare".to_string(), "pre_migration".to_string()),
+            ]),
+        });
+        assert!(!config.is_overdue("server", ago(24 * 8)));
+        assert!(config.is_overdue("server", ago(24 * 9)));
+        assert!(!config.is_overdue("spare", ago(24 * 365)));
+        assert!(config.is_overdue("laptop", ago(31)));
+    }
+
+    #[test]
+    fn test_backup_window_end() {
+        let at = |time: &str| chrono::NaiveDateTime::parse_from_str(&format!("2024-03-10 {}", time), "%Y-%m-%d %H:%M").unwrap();
+        let window = |start: &str, end: &str| BackupWindowConfig { start: start.to_string(), end: end.to_string() };
+
+        let night = window("01:00", "06:00");
+        assert_eq!(night.end_after(at("01:00")).unwrap(), Some(at("06:00")));
+        assert_eq!(night.end_after(at("06:00")).unwrap(), None);
+        assert_eq!(night.end_after(at("23:00")).unwrap(), None);
+
+        let overnight = window("22:00", "04:00");
+        assert_eq!(overnight.end_after(at("23:30")).unwrap(), Some(at("04:00") + chrono::Duration::days(1)));
+        assert_eq!(overnight.end_after(at("03:00")).unwrap(), Some(at("04:00")));
+        assert_eq!(overnight.end_after(at("12:00")).unwrap(), None);
+
+        assert!(window("1am", "06:00").end_after(at("01:00")).is_err());
+    }
+
+    #[test]
+    fn test_compression_settings_write_archives_tar_reads_back() {
+        let config = |format: &str, level: Option<u32>| CompressionConfig { format: format.to_string(), level };
+        // Levels the format doesn't have, and unknown formats, fall back
+        // to defaults rather than failing the backup
+        assert_eq!(config("zstd", Some(22)).level(), 3);
+        assert_eq!(config("xz", Some(0)).level(), 0);
+        assert_eq!(config("gzip", Some(0)).level(), 6);
+        assert_eq!(config("lz4", Some(5)).compression(), Compression::Gzip);
+
+        let dir = std::env::temp_dir().join(format!("compression-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("home")).unwrap();
+        fs::write(dir.join("home/notes.txt"), "compressed and back\n").unwrap();
+        for compression in Compression::ALL {
+            let settings = config(compression.as_str(), Some(compression.default_level()));
+            let archive = dir.join(format!("backup.tar.{}", settings.compression().extension()));
+            let status = std::process::Command::new("tar")
+                .arg("-C").arg(dir.join("home"))
+                .arg("--use-compress-program").arg(settings.compression().program(settings.level(), 0))
+                .arg("-cf").arg(&archive)
+                .arg("notes.txt")
+                .status()
+                .unwrap();
+            assert!(status.success(), "{}", compression.as_str());
+
+            // Read from a pipe, as encrypted and remote restores do
+            let detected = Compression::detect(Path::new(&format!("{}.gpg", archive.display()))).unwrap();
+            let output = std::process::Command::new("tar")
+                .arg("-xO").arg(detected.tar_option()).arg("-f").arg("-")
+                .stdin(fs::File::open(&archive).unwrap())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{}", compression.as_str());
+            assert_eq!(output.stdout, b"compressed and back\n");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,402 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::core::config::BackupConfig;
+
+const SECURITY_LEVELS: &[&str] = &["low", "medium", "high"];
+const COMPRESSION_FORMATS: &[&str] = &["gzip", "xz", "bzip2", "zstd"];
+const ENCRYPTION_METHODS: &[&str] = &["gpg-symmetric", "gpg-public-key"];
+
+/// How a form field is edited and validated
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKind {
+    /// Required free text
+    Text,
+    /// Free text; empty removes the key
+    OptionalText,
+    Bool,
+    /// One value out of a fixed set
+    Choice(&'static [&'static str]),
+    /// Comma-separated home-relative paths or globs
+    PathList { optional: bool },
+    /// Comma-separated executable names
+    ToolList,
+    /// Comma-separated values from a fixed set
+    ChoiceList(&'static [&'static str]),
+    /// Disk size such as `100MB`
+    Size,
+    /// Whole number in a range; empty removes the key
+    OptionalNumber { min: u32, max: u32 },
+}
+
+impl FieldKind {
+    pub fn hint(&self) -> String {
+        match self {
+            FieldKind::Text => "Text (required)".to_string(),
+            FieldKind::OptionalText => "Text (leave empty to remove)".to_string(),
+            FieldKind::Bool => "Space toggles true/false".to_string(),
+            FieldKind::Choice(options) => format!("Space cycles: {}", options.join(" / ")),
+            FieldKind::PathList { optional: false } => "Comma-separated paths relative to home".to_string(),
+            FieldKind::PathList { optional: true } => "Comma-separated paths (may be empty)".to_string(),
+            FieldKind::ToolList => "Comma-separated command names".to_string(),
+            FieldKind::ChoiceList(options) => format!("Comma-separated, any of: {}", options.join(", ")),
+            FieldKind::Size => "Size with unit, e.g. 500MB or 2GB".to_string(),
+            FieldKind::OptionalNumber { min, max } => format!("Number from {} to {} (leave empty for default)", min, max),
+        }
+    }
+
+    /// Check a raw field value, returning a message describing the problem
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match self {
+            FieldKind::Text => {
+                if value.is_empty() {
+                    return Err("Value is required".to_string());
+                }
+            }
+            FieldKind::OptionalText => {}
+            FieldKind::Bool => {
+                if value != "true" && value != "false" {
+                    return Err("Must be true or false".to_string());
+                }
+            }
+            FieldKind::Choice(options) => {
+                if !options.contains(&value) {
+                    return Err(format!("Must be one of: {}", options.join(", ")));
+                }
+            }
+            FieldKind::PathList { optional } => {
+                let paths = split_list(value);
+                if paths.is_empty() && !optional {
+                    return Err("At least one path is required".to_string());
+                }
+                for path in paths {
+                    if path.starts_with('/') || path.starts_with('~') {
+                        return Err(format!("'{}' must be relative to the home directory", path));
+                    }
+                    if path.split('/').any(|component| component == "..") {
+                        return Err(format!("'{}' must not escape the home directory", path));
+                    }
+                }
+            }
+            FieldKind::ToolList => {
+                for tool in split_list(value) {
+                    let valid = tool
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'));
+                    if !valid {
+                        return Err(format!("'{}' is not a command name", tool));
+                    }
+                }
+            }
+            FieldKind::ChoiceList(options) => {
+                let values = split_list(value);
+                if values.is_empty() {
+                    return Err("At least one value is required".to_string());
+                }
+                if let Some(unknown) = values.iter().find(|v| !options.contains(v)) {
+                    return Err(format!("'{}' is not supported (use {})", unknown, options.join(", ")));
+                }
+            }
+            FieldKind::Size => {
+                parse_size(value).ok_or_else(|| "Expected a size like 100MB or 2GB".to_string())?;
+            }
+            FieldKind::OptionalNumber { min, max } => {
+                if !value.is_empty() {
+                    match value.parse::<u32>() {
+                        Ok(number) if (*min..=*max).contains(&number) => {}
+                        _ => return Err(format!("Must be a number from {} to {}", min, max)),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn to_json(&self, value: &str) -> Value {
+        let value = value.trim();
+        match self {
+            FieldKind::Bool => Value::Bool(value == "true"),
+            FieldKind::OptionalText | FieldKind::OptionalNumber { .. } if value.is_empty() => Value::Null,
+            FieldKind::OptionalNumber { .. } => value.parse::<u32>().map(Value::from).unwrap_or(Value::Null),
+            FieldKind::PathList { optional: true } if value.is_empty() => Value::Null,
+            FieldKind::PathList { .. } | FieldKind::ToolList | FieldKind::ChoiceList(_) => Value::Array(
+                split_list(value).into_iter().map(|v| Value::String(v.to_string())).collect(),
+            ),
+            _ => Value::String(value.to_string()),
+        }
+    }
+}
+
+/// Single editable value from the backup configuration
+#[derive(Debug, Clone)]
+pub struct ConfigField {
+    pub section: String,
+    pub label: String,
+    pub kind: FieldKind,
+    pub value: String,
+    pub error: Option<String>,
+    /// Keys leading to the value in the config's JSON representation
+    path: Vec<String>,
+}
+
+impl ConfigField {
+    fn new(section: &str, label: &str, path: &[&str], kind: FieldKind, value: String) -> Self {
+        Self {
+            section: section.to_string(),
+            label: label.to_string(),
+            kind,
+            value,
+            error: None,
+            path: path.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Replace the value, recording a validation error if it is invalid
+    pub fn set_value(&mut self, value: String) {
+        self.error = self.kind.validate(&value).err();
+        self.value = value;
+    }
+
+    /// Flip a boolean or advance a choice to its next option
+    pub fn cycle(&mut self) {
+        let next = match &self.kind {
+            FieldKind::Bool => if self.value == "true" { "false" } else { "true" },
+            FieldKind::Choice(options) => {
+                let current = options.iter().position(|o| *o == self.value).unwrap_or(options.len() - 1);
+                options[(current + 1) % options.len()]
+            }
+            _ => return,
+        };
+        self.set_value(next.to_string());
+    }
+
+    pub fn is_toggle(&self) -> bool {
+        matches!(self.kind, FieldKind::Bool | FieldKind::Choice(_))
+    }
+}
+
+/// Form over the editable parts of `backup-config.json`: backup modes,
+/// modern application configurations, and validation settings
+#[derive(Debug, Clone)]
+pub struct ConfigForm {
+    pub fields: Vec<ConfigField>,
+    pub dirty: bool,
+}
+
+impl ConfigForm {
+    pub fn from_config(config: &BackupConfig) -> Self {
+        let mut fields = Vec::new();
+
+        let mut mode_names: Vec<&String> = config.backup_modes.keys().collect();
+        mode_names.sort();
+        for name in mode_names {
+            let mode = &config.backup_modes[name];
+            let section = format!("Mode: {}", name);
+            let base = ["backup_modes", name.as_str()];
+
+            fields.push(ConfigField::new(&section, "Description", &[&base[..], &["description"]].concat(),
+                FieldKind::Text, mode.description.clone()));
+            fields.push(ConfigField::new(&section, "Excludes sensitive", &[&base[..], &["excludes_sensitive"]].concat(),
+                FieldKind::Bool, mode.excludes_sensitive.to_string()));
+            fields.push(ConfigField::new(&section, "Security warning", &[&base[..], &["security_warning"]].concat(),
+                FieldKind::OptionalText, mode.security_warning.clone().unwrap_or_default()));
+
+            let mut categories: Vec<&String> = mode.categories.keys().collect();
+            categories.sort();
+            for category in categories {
+                fields.push(ConfigField::new(
+                    &section,
+                    &format!("Category {}", category),
+                    &[&base[..], &["categories", category.as_str()]].concat(),
+                    FieldKind::PathList { optional: false },
+                    join_list(&mode.categories[category]),
+                ));
+            }
+
+            fields.push(ConfigField::new(&section, "Exclusions", &[&base[..], &["exclusions"]].concat(),
+                FieldKind::PathList { optional: true }, join_list(&mode.exclusions)));
+            fields.push(ConfigField::new(&section, "Critical items", &[&base[..], &["critical_items"]].concat(),
+                FieldKind::PathList { optional: true }, join_list(&mode.critical_items)));
+        }
+
+        let mut groups: Vec<&String> = config.modern_configurations.categories.keys().collect();
+        groups.sort();
+        for group in groups {
+            let apps = &config.modern_configurations.categories[group];
+            let mut app_names: Vec<&String> = apps.keys().collect();
+            app_names.sort();
+            for app_name in app_names {
+                let app = &apps[app_name];
+                let section = format!("App: {}/{}", group, app_name);
+                let base = ["modern_configurations", "categories", group.as_str(), app_name.as_str()];
+
+                // Apps that run a command, list packages or copy volumes may
+                // have no files, and browsers have usual ones
+                let optional = app.command.is_some() || !app.packages.is_empty() || app.volumes.is_some() || app.browser.is_some();
+                fields.push(ConfigField::new(&section, "Paths", &[&base[..], &["paths"]].concat(),
+                    FieldKind::PathList { optional }, join_list(&app.paths)));
+                fields.push(ConfigField::new(&section, "Description", &[&base[..], &["description"]].concat(),
+                    FieldKind::Text, app.description.clone()));
+                fields.push(ConfigField::new(&section, "Security level", &[&base[..], &["security_level"]].concat(),
+                    FieldKind::Choice(SECURITY_LEVELS), app.security_level.clone()));
+                fields.push(ConfigField::new(&section, "Category", &[&base[..], &["category"]].concat(),
+                    FieldKind::Text, app.category.clone()));
+                fields.push(ConfigField::new(&section, "Warning", &[&base[..], &["warning"]].concat(),
+                    FieldKind::OptionalText, app.warning.clone().unwrap_or_default()));
+                fields.push(ConfigField::new(&section, "Exclusions", &[&base[..], &["exclusions"]].concat(),
+                    FieldKind::PathList { optional: true }, app.exclusions.as_deref().map(join_list).unwrap_or_default()));
+            }
+        }
+
+        let validation = &config.validation;
+        let section = "Validation";
+        fields.push(ConfigField::new(section, "Required tools", &["validation", "required_tools"],
+            FieldKind::ToolList, join_list(&validation.required_tools)));
+        fields.push(ConfigField::new(section, "Optional tools", &["validation", "optional_tools"],
+            FieldKind::ToolList, join_list(&validation.optional_tools)));
+        fields.push(ConfigField::new(section, "Minimum disk space", &["validation", "minimum_disk_space"],
+            FieldKind::Size, validation.minimum_disk_space.clone()));
+        fields.push(ConfigField::new(section, "Compression", &["validation", "supported_compression"],
+            FieldKind::ChoiceList(COMPRESSION_FORMATS), join_list(&validation.supported_compression)));
+        fields.push(ConfigField::new(section, "Encryption", &["validation", "supported_encryption"],
+            FieldKind::ChoiceList(ENCRYPTION_METHODS), join_list(&validation.supported_encryption)));
+
+        let section = "Compression";
+        fields.push(ConfigField::new(section, "Format", &["compression", "format"],
+            FieldKind::Choice(COMPRESSION_FORMATS), config.compression.format.clone()));
+        fields.push(ConfigField::new(section, "Level", &["compression", "level"],
+            FieldKind::OptionalNumber { min: 0, max: 19 },
+            config.compression.level.map(|level| level.to_string()).unwrap_or_default()));
+
+        // Flag anything already invalid in the file on disk
+        for field in &mut fields {
+            field.error = field.kind.validate(&field.value).err();
+        }
+
+        Self { fields, dirty: false }
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.fields.iter().filter(|field| field.error.is_some()).count()
+    }
+
+    /// Produce a new config with every field applied on top of `base`
+    pub fn apply(&self, base: &BackupConfig) -> Result<BackupConfig> {
+        if let Some(field) = self.fields.iter().find(|field| field.error.is_some()) {
+            anyhow::bail!(
+                "{} › {}: {}",
+                field.section,
+                field.label,
+                field.error.as_deref().unwrap_or_default()
+            );
+        }
+
+        let mut value = serde_json::to_value(base)?;
+        for field in &self.fields {
+            set_json_path(&mut value, &field.path, field.kind.to_json(&field.value))?;
+        }
+
+        serde_json::from_value(value).context("Edited configuration does not match the config schema")
+    }
+}
+
+fn set_json_path(root: &mut Value, path: &[String], new_value: Value) -> Result<()> {
+    let (key, parents) = path.split_last().context("Empty config field path")?;
+    let mut current = root;
+    for parent in parents {
+        current = current
+            .get_mut(parent)
+            .with_context(|| format!("Config key '{}' not found", parent))?;
+    }
+    let object = current
+        .as_object_mut()
+        .with_context(|| format!("Config key '{}' is not inside an object", key))?;
+
+    if new_value.is_null() {
+        object.remove(key);
+    } else {
+        object.insert(key.clone(), new_value);
+    }
+    Ok(())
+}
+
+fn join_list(values: &[String]) -> String {
+    values.join(", ")
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Parse sizes like `100MB` into bytes
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        "T" | "TB" => 1024 * 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_config() -> BackupConfig {
+        serde_json::from_str(include_str!("../../backup-config.json")).unwrap()
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(FieldKind::Size.validate("100MB").is_ok());
+        assert!(FieldKind::Size.validate("lots").is_err());
+        assert!(FieldKind::PathList { optional: false }.validate(".config/app, .bashrc").is_ok());
+        assert!(FieldKind::PathList { optional: false }.validate("/etc/passwd").is_err());
+        assert!(FieldKind::PathList { optional: false }.validate(".config/../../etc").is_err());
+        assert!(FieldKind::PathList { optional: true }.validate("").is_ok());
+        assert!(FieldKind::ToolList.validate("tar, sha256sum").is_ok());
+        assert!(FieldKind::ToolList.validate("rm -rf").is_err());
+        assert!(FieldKind::ChoiceList(COMPRESSION_FORMATS).validate("gzip, lz4").is_err());
+        assert!(FieldKind::OptionalNumber { min: 0, max: 19 }.validate("").is_ok());
+        assert!(FieldKind::OptionalNumber { min: 0, max: 19 }.validate("22").is_err());
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let config = sample_config();
+        let mut form = ConfigForm::from_config(&config);
+        assert_eq!(form.error_count(), 0);
+
+        let field = form.fields.iter_mut().find(|f| f.label == "Minimum disk space").unwrap();
+        field.set_value("2GB".to_string());
+        let field = form
+            .fields
+            .iter_mut()
+            .find(|f| f.section == "Mode: secure" && f.label == "Excludes sensitive")
+            .unwrap();
+        field.cycle();
+        let field = form.fields.iter_mut().find(|f| f.section == "Compression" && f.label == "Level").unwrap();
+        field.set_value("19".to_string());
+
+        let edited = form.apply(&config).unwrap();
+        assert_eq!(edited.validation.minimum_disk_space, "2GB");
+        assert!(!edited.backup_modes["secure"].excludes_sensitive);
+        assert_eq!(edited.compression.level, Some(19));
+
+        let field = form.fields.iter_mut().find(|f| f.label == "Required tools").unwrap();
+        field.set_value("tar; curl | sh".to_string());
+        assert!(form.apply(&config).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::BackupConfig;
+use crate::core::diff::{collapse_context, diff_lines, DiffLine};
+
+/// Number of previous config versions kept next to the config file
+pub const CONFIG_HISTORY_LIMIT: usize = 10;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Previous version of the config saved as `<config>.<timestamp>.bak`
+#[derive(Debug, Clone)]
+pub struct ConfigSnapshot {
+    pub path: PathBuf,
+    pub taken: chrono::DateTime<Local>,
+    pub size: u64,
+}
+
+/// Snapshot path for the version of `config_path` being replaced now
+pub fn snapshot_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(format!(
+        "{}.{}.bak",
+        file_name(config_path),
+        Local::now().format(TIMESTAMP_FORMAT)
+    ))
+}
+
+/// Snapshots of `config_path`, newest first
+pub fn list_snapshots(config_path: &Path) -> Result<Vec<ConfigSnapshot>> {
+    let dir = match config_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let prefix = format!("{}.", file_name(config_path));
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read config directory: {}", dir.display()))?
+        .flatten()
+    {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(timestamp) = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".bak"))
+        else {
+            continue;
+        };
+        let Some(taken) = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+            .ok()
+            .and_then(|t| Local.from_local_datetime(&t).earliest())
+        else {
+            continue;
+        };
+
+        snapshots.push(ConfigSnapshot {
+            path: entry.path(),
+            taken,
+            size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        });
+    }
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.taken));
+    Ok(snapshots)
+}
+
+/// Delete all but the newest `keep` snapshots
+pub fn prune_snapshots(config_path: &Path, keep: usize) -> Result<()> {
+    for snapshot in list_snapshots(config_path)?.into_iter().skip(keep) {
+        log::debug!("Removing old config snapshot {}", snapshot.path.display());
+        fs::remove_file(&snapshot.path)
+            .with_context(|| format!("Failed to remove {}", snapshot.path.display()))?;
+    }
+    Ok(())
+}
+
+/// Diff a snapshot against the current config file
+pub fn diff_snapshot(snapshot: &ConfigSnapshot, config_path: &Path) -> Result<Vec<DiffLine>> {
+    let old = fs::read_to_string(&snapshot.path)
+        .with_context(|| format!("Failed to read {}", snapshot.path.display()))?;
+    let current = fs::read_to_string(config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    Ok(collapse_context(diff_lines(&old, &current), 3))
+}
+
+/// Restore a snapshot as the active config. The current version is itself
+/// snapshotted first, so a rollback can be undone from the same screen.
+pub fn rollback(snapshot: &ConfigSnapshot, config_path: &Path) -> Result<BackupConfig> {
+    // Refuse to roll back to something that would fail to load
+    let mut config = BackupConfig::read(&snapshot.path)
+        .with_context(|| format!("Snapshot {} is not a valid config", snapshot.path.display()))?;
+    config.save(config_path)?;
+    Ok(config)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "backup-config.json".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshots_pruned_and_rolled_back() {
+        let dir = std::env::temp_dir().join(format!("config-history-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("backup-config.json");
+        fs::write(&config_path, include_str!("../../backup-config.json")).unwrap();
+
+        for day in 1..=4 {
+            let name = format!("backup-config.json.2024010{}-120000.bak", day);
+            fs::write(dir.join(name), include_str!("../../backup-config.json")).unwrap();
+        }
+        fs::write(dir.join("backup-config.json.garbage.bak"), "").unwrap();
+
+        prune_snapshots(&config_path, 2).unwrap();
+        let snapshots = list_snapshots(&config_path).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots[0].path.ends_with("backup-config.json.20240104-120000.bak"));
+
+        let config = rollback(&snapshots[1], &config_path).unwrap();
+        assert_eq!(config.version, "1.0.0");
+        // The replaced version became a new snapshot
+        let snapshots = list_snapshots(&config_path).unwrap();
+        assert_eq!(snapshots.len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+
+/// Layout of the config file this release reads and writes. Bump it and
+/// append to `MIGRATIONS` whenever a field is renamed, moved or changes
+/// type, so existing files keep loading.
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a schema version `n` config to version `n + 1`
+const MIGRATIONS: [Migration; CONFIG_SCHEMA_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Bring a parsed config up to `CONFIG_SCHEMA_VERSION` in place. Returns
+/// the version it started at when anything was upgraded.
+pub fn migrate(value: &mut Value) -> Result<Option<u32>> {
+    let config = value
+        .as_object_mut()
+        .context("Config must be a map of settings at the top level")?;
+
+    // Files written before versioning existed are version 0
+    let from = match config.get("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("schema_version must be a whole number")?,
+    };
+    if from > CONFIG_SCHEMA_VERSION {
+        anyhow::bail!(
+            "Config schema version {} is newer than this release understands ({}); update backup-ui",
+            from,
+            CONFIG_SCHEMA_VERSION
+        );
+    }
+    if from == CONFIG_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migration(config).with_context(|| {
+            format!("Failed to upgrade config from schema version {} to {}", version, version + 1)
+        })?;
+        config.insert("schema_version".to_string(), Value::from(version as u32 + 1));
+    }
+    Ok(Some(from))
+}
+
+/// Configs from before zstd support list only the compressors that existed
+/// then, which would keep zstd out of the mode screen's selector
+fn migrate_v0_to_v1(config: &mut Map<String, Value>) -> Result<()> {
+    let supported = config
+        .get_mut("validation")
+        .and_then(|validation| validation.get_mut("supported_compression"))
+        .and_then(Value::as_array_mut);
+    if let Some(supported) = supported {
+        if !supported.iter().any(|name| name == "zstd") {
+            supported.push(Value::from("zstd"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_unversioned_config() {
+        let mut value = serde_json::json!({
+            "validation": { "supported_compression": ["gzip", "xz"] }
+        });
+        assert_eq!(migrate(&mut value).unwrap(), Some(0));
+        assert_eq!(value["schema_version"], CONFIG_SCHEMA_VERSION);
+        assert_eq!(value["validation"]["supported_compression"], serde_json::json!(["gzip", "xz", "zstd"]));
+
+        // Already current: left alone
+        assert_eq!(migrate(&mut value).unwrap(), None);
+
+        let mut newer = serde_json::json!({ "schema_version": CONFIG_SCHEMA_VERSION + 1 });
+        assert!(migrate(&mut newer).unwrap_err().to_string().contains("newer"));
+    }
+}
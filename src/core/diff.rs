@@ -0,0 +1,138 @@
+/// Line-level difference between two versions of a text file
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+    /// Unchanged lines omitted between hunks
+    Skipped(usize),
+}
+
+/// Largest LCS table built before falling back to a plain remove/add of the
+/// changed region, so diffing two large files can't exhaust memory
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Longest-common-subsequence line diff
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Unchanged head and tail don't need to go through the LCS table
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().map(|l| DiffLine::Same(l.to_string())).collect();
+    if (old_mid.len() + 1).saturating_mul(new_mid.len() + 1) > MAX_LCS_CELLS {
+        lines.extend(old_mid.iter().map(|l| DiffLine::Removed(l.to_string())));
+        lines.extend(new_mid.iter().map(|l| DiffLine::Added(l.to_string())));
+    } else {
+        lines.extend(lcs_diff(old_mid, new_mid));
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|l| DiffLine::Same(l.to_string())));
+    lines
+}
+
+fn lcs_diff(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    // lcs[i][j] = LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    lines.extend(new[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+    lines
+}
+
+/// Keep only `context` unchanged lines around each change. Identical
+/// inputs collapse to an empty diff.
+pub fn collapse_context(lines: Vec<DiffLine>, context: usize) -> Vec<DiffLine> {
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return Vec::new();
+    }
+
+    let near_change = |i: usize| {
+        changed
+            .iter()
+            .any(|&c| i + context >= c && i <= c + context)
+    };
+
+    let mut collapsed = Vec::new();
+    let mut skipped = 0;
+    for (i, line) in lines.into_iter().enumerate() {
+        if near_change(i) {
+            if skipped > 0 {
+                collapsed.push(DiffLine::Skipped(skipped));
+                skipped = 0;
+            }
+            collapsed.push(line);
+        } else {
+            skipped += 1;
+        }
+    }
+    if skipped > 0 {
+        collapsed.push(DiffLine::Skipped(skipped));
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nc\nd\ne\n";
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Same("d".to_string()),
+                DiffLine::Added("e".to_string()),
+            ]
+        );
+
+        let collapsed = collapse_context(diff_lines("1\n2\n3\n4\n5\n6\n", "1\n2\n3\n4\n5\nx\n"), 1);
+        assert_eq!(collapsed[0], DiffLine::Skipped(4));
+        assert_eq!(collapsed.len(), 4);
+
+        assert!(collapse_context(diff_lines("same\n", "same\n"), 3).is_empty());
+    }
+}
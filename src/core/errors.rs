@@ -0,0 +1,126 @@
+/// What kind of failure sent the user to the error screen. Each has a
+/// code to quote in bug reports and its own suggestions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Nothing ticked to back up or restore
+    Selection,
+    /// The config can't be used as it is, such as a bad exclusion pattern
+    Config,
+    /// An archive couldn't be listed, fetched or opened
+    Archive,
+    Backup,
+    Restore,
+}
+
+impl ErrorCategory {
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorCategory::Selection => "E100",
+            ErrorCategory::Config => "E200",
+            ErrorCategory::Archive => "E300",
+            ErrorCategory::Backup => "E400",
+            ErrorCategory::Restore => "E500",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorCategory::Selection => "Selection",
+            ErrorCategory::Config => "Configuration",
+            ErrorCategory::Archive => "Archive",
+            ErrorCategory::Backup => "Backup",
+            ErrorCategory::Restore => "Restore",
+        }
+    }
+
+    fn suggestions(self) -> &'static [&'static str] {
+        match self {
+            ErrorCategory::Selection => &["Go back and tick at least one item"],
+            ErrorCategory::Config => &["Fix the patterns in the exclusion editor or the config file"],
+            ErrorCategory::Archive => &[
+                "Make sure the destination is mounted or reachable",
+                "Check the archive file is still there and readable",
+                "Run backup-ui scrub to check the stored archives for damage",
+            ],
+            ErrorCategory::Backup => &[
+                "Ensure you have sufficient disk space",
+                "Check file and directory permissions",
+                "Make sure required tools are installed",
+                "Try with a smaller selection of files",
+            ],
+            ErrorCategory::Restore => &[
+                "Check the passphrase or GPG key the archive was made with",
+                "Check permissions where the files are restored",
+                "Ensure you have sufficient disk space",
+            ],
+        }
+    }
+}
+
+/// An error as the error screen shows it: a message for the user, the
+/// full chain of causes for bug reports, and what to try
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppError {
+    pub category: ErrorCategory,
+    pub message: String,
+    /// The error and each of its causes, outermost first
+    pub chain: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl AppError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self {
+            category,
+            chain: vec![message.clone()],
+            message,
+            suggestions: category.suggestions().iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// `summary` and the outermost cause of `error` as the message, with
+    /// all of its causes kept for the details
+    pub fn from_anyhow(category: ErrorCategory, summary: &str, error: &anyhow::Error) -> Self {
+        Self {
+            chain: error.chain().map(|cause| cause.to_string()).collect(),
+            ..Self::new(category, format!("{}: {}", summary, error))
+        }
+    }
+
+    /// Plain text of the error for pasting into a bug report
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "backup-ui {} error {} ({})\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            self.category.code(),
+            self.category.label(),
+            self.message,
+        );
+        for (depth, cause) in self.chain.iter().enumerate() {
+            report.push_str(&format!("{:>4}: {}\n", depth, cause));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_keeps_its_causes() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let error = Err::<(), _>(io).context("Failed to read /mnt/usb/home.tar.gz").unwrap_err();
+        let error = AppError::from_anyhow(ErrorCategory::Archive, "Failed to open archive", &error);
+
+        assert_eq!(error.message, "Failed to open archive: Failed to read /mnt/usb/home.tar.gz");
+        assert_eq!(error.chain, vec!["Failed to read /mnt/usb/home.tar.gz", "no such file"]);
+        assert!(!error.suggestions.is_empty());
+
+        let report = error.report();
+        assert!(report.contains("E300 (Archive)"));
+        assert!(report.ends_with("   1: no such file\n"));
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::time::Instant;
+
+use crate::core::audit::AuditEvent;
+
+/// One line of `--json` output. Events never carry passphrases, keys or
+/// file contents beyond the search snippets the user asked for.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Start {
+        command: String,
+        version: String,
+    },
+    /// A file handled by a long-running step
+    Progress {
+        phase: String,
+        path: String,
+        done: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+    },
+    /// An archived file matched by `find` or `search`
+    Found {
+        path: String,
+        archive: String,
+        created: DateTime<Utc>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hostname: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        snippet: Option<String>,
+    },
+    /// An entry of the audit log, listed by `history`
+    Audit(AuditEvent),
+    Warning {
+        message: String,
+    },
+    /// Log record below warning level, only with `--debug` or `RUST_LOG`
+    Log {
+        level: String,
+        message: String,
+    },
+    Complete {
+        ok: bool,
+        duration_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+        summary: serde_json::Value,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    time: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl Event {
+    /// The event as a single JSON line, stamped with the current time
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(&Line { time: Utc::now(), event: self })
+            .unwrap_or_else(|e| format!("{{\"event\":\"warning\",\"message\":\"unserializable event: {}\"}}", e))
+    }
+
+    /// Log record as an event, so warnings reach scripts on stdout too
+    pub fn from_record(record: &log::Record) -> Self {
+        let message = record.args().to_string();
+        if record.level() <= log::Level::Warn {
+            Event::Warning { message }
+        } else {
+            Event::Log { level: record.level().as_str().to_lowercase(), message }
+        }
+    }
+}
+
+/// Event output of a command-line subcommand: JSON lines on stdout with
+/// `--json`, nothing otherwise
+#[derive(Clone)]
+pub struct Events {
+    json: bool,
+    started: Instant,
+    stderr: bool,
+}
+
+impl Events {
+    /// Begin `command`, announcing it when JSON output is on
+    pub fn start(json: bool, command: &str) -> Self {
+        Self::start_on(json, false, command)
+    }
+
+    /// Begin `command` with its events on stderr, for commands that write
+    /// their data to stdout
+    pub fn start_on_stderr(json: bool, command: &str) -> Self {
+        Self::start_on(json, true, command)
+    }
+
+    fn start_on(json: bool, stderr: bool, command: &str) -> Self {
+        let events = Self { json, started: Instant::now(), stderr };
+        events.emit(Event::Start {
+            command: command.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        });
+        events
+    }
+
+    /// Whether output is JSON lines rather than text for people
+    pub fn json(&self) -> bool {
+        self.json
+    }
+
+    pub fn emit(&self, event: Event) {
+        if !self.json {
+            return;
+        }
+        // A script that stopped reading shouldn't turn into a failed command
+        let line = event.to_line();
+        if self.stderr {
+            let _ = writeln!(std::io::stderr().lock(), "{}", line);
+        } else {
+            let mut out = std::io::stdout().lock();
+            let _ = writeln!(out, "{}", line);
+            let _ = out.flush();
+        }
+    }
+
+    /// Report success along with what the command did
+    pub fn complete(&self, summary: serde_json::Value) {
+        self.emit(Event::Complete {
+            ok: true,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            error: None,
+            summary,
+        });
+    }
+
+    /// Pass `result` through, reporting a failure first
+    pub fn finish<T>(&self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        if let Err(e) = &result {
+            self.emit(Event::Complete {
+                ok: false,
+                duration_ms: self.started.elapsed().as_millis() as u64,
+                error: Some(format!("{:#}", e)),
+                summary: serde_json::Value::Null,
+            });
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_lines() {
+        let progress = Event::Progress {
+            phase: "verify".to_string(),
+            path: ".config/app/settings.json".to_string(),
+            done: 3,
+            total: Some(10),
+        };
+        let line: serde_json::Value = serde_json::from_str(&progress.to_line()).unwrap();
+        assert_eq!(line["event"], "progress");
+        assert_eq!(line["phase"], "verify");
+        assert_eq!(line["done"], 3);
+        assert_eq!(line["total"], 10);
+        assert!(line["time"].is_string());
+
+        let complete = Event::Complete { ok: true, duration_ms: 5, error: None, summary: serde_json::Value::Null };
+        let line = complete.to_line();
+        assert!(!line.contains('\n'));
+        let line: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(line["event"], "complete");
+        assert!(line.get("error").is_none() && line.get("summary").is_none());
+
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .args(format_args!("Skipping unreadable shared catalog"))
+            .build();
+        assert!(matches!(Event::from_record(&record), Event::Warning { .. }));
+    }
+}
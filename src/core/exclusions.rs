@@ -0,0 +1,553 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use log::{debug, warn};
+use std::fs;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::core::config::BackupConfig;
+use crate::core::types::{BackupMode, ItemOutcome, ItemReport, SecurityLevel, SymlinkPolicy};
+
+/// Compiled exclusion patterns, matched against home-relative paths.
+///
+/// A pattern without a `/` matches any single path component, so
+/// `node_modules` or `*.log` apply at every depth. A pattern containing a
+/// `/` is anchored at the home directory (`.config/*/Cache`), with `*`
+/// staying within one component and `**` crossing directories. Excluding
+/// a directory excludes everything below it.
+#[derive(Debug, Clone)]
+pub struct ExclusionSet {
+    patterns: Vec<String>,
+    set: GlobSet,
+}
+
+/// Absolute path of what a followed link points to, and the link's
+/// home-relative path
+pub type FollowedLink = (PathBuf, PathBuf);
+
+/// Files and bytes a pattern keeps out of the backup
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatchCount {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Files and bytes a file list has taken in so far, read from another
+/// thread while the list is written
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    pub files: AtomicUsize,
+    pub bytes: AtomicU64,
+}
+
+impl ScanProgress {
+    pub fn get(&self) -> MatchCount {
+        MatchCount { files: self.files.load(Ordering::Relaxed), bytes: self.bytes.load(Ordering::Relaxed) }
+    }
+}
+
+impl ExclusionSet {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let mut kept = Vec::new();
+        for pattern in patterns {
+            let Some(glob) = to_glob(pattern) else {
+                continue;
+            };
+            builder.add(
+                GlobBuilder::new(&glob)
+                    .literal_separator(true)
+                    .build()
+                    .with_context(|| format!("Invalid exclusion pattern '{}'", pattern))?,
+            );
+            kept.push(pattern.clone());
+        }
+        Ok(Self {
+            patterns: kept,
+            set: builder.build()?,
+        })
+    }
+
+    /// The mode's own exclusions plus those of individual applications,
+    /// which are relative to the application's paths. Secure mode also
+    /// leaves out paths classified high, wherever they sit in an item.
+    pub fn for_mode(config: &BackupConfig, mode: &BackupMode) -> Result<Self> {
+        let mut patterns = config.get_exclusions_for_mode(mode);
+        for category in config.modern_configurations.categories.values() {
+            for app in category.values() {
+                let paths = app.item_paths();
+                let caches = app.browser.iter().flat_map(|browser| browser.name.cache_exclusions()).map(|pattern| pattern.to_string());
+                for pattern in app.exclusions.iter().flatten().cloned().chain(caches) {
+                    for path in &paths {
+                        let path = path.trim_end_matches('/');
+                        patterns.push(if pattern.contains('/') {
+                            format!("{}/{}", path, pattern)
+                        } else {
+                            format!("{}/**/{}", path, pattern)
+                        });
+                    }
+                }
+            }
+        }
+        let set = Self::new(&patterns)?;
+        if mode == &BackupMode::Secure {
+            return set.with_paths(&config.classified_paths(&SecurityLevel::High));
+        }
+        Ok(set)
+    }
+
+    /// These patterns plus each of `paths`, home-relative, taken literally
+    pub fn with_paths(&self, paths: &[PathBuf]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let mut patterns = self.patterns.clone();
+        for pattern in &self.patterns {
+            if let Some(glob) = to_glob(pattern) {
+                builder.add(GlobBuilder::new(&glob).literal_separator(true).build()?);
+            }
+        }
+        for path in paths {
+            let path = path.to_string_lossy();
+            builder.add(GlobBuilder::new(&globset::escape(&path)).literal_separator(true).build()?);
+            patterns.push(path.to_string());
+        }
+        Ok(Self {
+            patterns,
+            set: builder.build()?,
+        })
+    }
+
+    /// Whether `path` itself matches; callers walking a tree stop at
+    /// excluded directories
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.set.is_match(path)
+    }
+
+    /// Index of the first pattern matching `path`
+    fn first_match(&self, path: &Path) -> Option<usize> {
+        self.set.matches(path).into_iter().min()
+    }
+
+    /// Write the NUL-separated, home-relative paths under `roots` that are
+    /// not excluded, for `tar --null --no-recursion -T`. Files that can't be
+    /// opened are left out rather than failing tar. Symbolic links are
+    /// handled by the policy of the longest of `symlinks` containing them,
+    /// `archive` otherwise; what a followed link points to is listed by its
+    /// absolute path and returned as (target, link) for tar to rename.
+    /// Each file listed is added to `scan`, when given, as it is found.
+    /// Returns how many entries were written, a report for each of
+    /// `roots`, in order, and the links followed.
+    pub fn write_file_list(
+        &self,
+        home: &Path,
+        roots: &[PathBuf],
+        symlinks: &[(PathBuf, SymlinkPolicy)],
+        scan: Option<&ScanProgress>,
+        out: &mut impl Write,
+    ) -> Result<(usize, Vec<ItemReport>, Vec<FollowedLink>)> {
+        let mut reports: Vec<ItemReport> = roots.iter().map(|root| self.item_report(home, root)).collect();
+        let policy = |rel: &Path| {
+            symlinks
+                .iter()
+                .filter(|(root, _)| rel.starts_with(root))
+                .max_by_key(|(root, _)| root.components().count())
+                .map(|(_, policy)| *policy)
+                .unwrap_or_default()
+        };
+        let mut followed: Vec<FollowedLink> = Vec::new();
+        let mut written = 0;
+        for root in top_level(roots) {
+            if self.is_excluded(&root) || home.join(&root).symlink_metadata().is_err() {
+                continue;
+            }
+            // Below a followed link, where the entry is on disk
+            let mut pending: Vec<(PathBuf, Option<PathBuf>)> = vec![(root, None)];
+            while let Some((rel, mut on_disk)) = pending.pop() {
+                let mut full = on_disk.clone().unwrap_or_else(|| home.join(&rel));
+                let Ok(mut metadata) = full.symlink_metadata() else {
+                    continue;
+                };
+                if metadata.file_type().is_symlink() {
+                    match policy(&rel) {
+                        SymlinkPolicy::Archive => {}
+                        SymlinkPolicy::Skip => continue,
+                        SymlinkPolicy::Follow => match fs::canonicalize(&full) {
+                            // A target inside or around one already followed
+                            // is a loop, or would be stored twice
+                            Ok(target) if followed.iter().any(|(other, _)| target.starts_with(other) || other.starts_with(&target)) => {
+                                debug!("Archiving {} as a link; {} is already followed", rel.display(), target.display());
+                            }
+                            Ok(target) => {
+                                if let Ok(target_metadata) = target.metadata() {
+                                    followed.push((target.clone(), rel.clone()));
+                                    metadata = target_metadata;
+                                    full = target.clone();
+                                    on_disk = Some(target);
+                                }
+                            }
+                            Err(e) => debug!("Archiving dangling link {}: {}", rel.display(), e),
+                        },
+                    }
+                }
+
+                // Each entry counts towards every selected item it is in
+                let mut within: Vec<&mut ItemReport> = reports.iter_mut().filter(|report| rel.starts_with(&report.path)).collect();
+
+                let opened = if metadata.is_dir() {
+                    fs::read_dir(&full).map(|entries| {
+                        for entry in entries.flatten() {
+                            let child = rel.join(entry.file_name());
+                            if !self.is_excluded(&child) {
+                                pending.push((child, on_disk.as_ref().map(|dir| dir.join(entry.file_name()))));
+                            }
+                        }
+                    })
+                } else if metadata.is_file() {
+                    fs::File::open(&full).map(drop)
+                } else {
+                    Ok(())
+                };
+                match opened {
+                    Ok(()) => {}
+                    // Removed since it was found
+                    Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                    Err(e) => {
+                        warn!("Skipping unreadable {}: {}", full.display(), e);
+                        for report in within.iter_mut() {
+                            report.unreadable.push(rel.clone());
+                        }
+                        // An unreadable directory is still archived, empty
+                        if !metadata.is_dir() {
+                            continue;
+                        }
+                    }
+                }
+
+                out.write_all(on_disk.as_deref().unwrap_or(&rel).as_os_str().as_encoded_bytes())?;
+                out.write_all(b"\0")?;
+                written += 1;
+                if metadata.is_file() {
+                    for report in within.iter_mut() {
+                        report.files += 1;
+                        report.bytes += metadata.len();
+                    }
+                    if let Some(scan) = scan {
+                        scan.files.fetch_add(1, Ordering::Relaxed);
+                        scan.bytes.fetch_add(metadata.len(), Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+
+        for report in &mut reports {
+            if report.outcome == ItemOutcome::BackedUp && report.unreadable.first() == Some(&report.path) {
+                report.outcome = ItemOutcome::PermissionDenied;
+                report.unreadable.remove(0);
+            }
+            report.unreadable.sort();
+        }
+        Ok((written, reports, followed))
+    }
+
+    /// Report for `root` before walking it: whether it is excluded, by its
+    /// own path or a directory above it, or missing
+    fn item_report(&self, home: &Path, root: &Path) -> ItemReport {
+        let excluded = root.ancestors().filter(|path| !path.as_os_str().is_empty()).any(|path| self.is_excluded(path));
+        let outcome = if excluded {
+            ItemOutcome::Excluded
+        } else {
+            match home.join(root).symlink_metadata() {
+                Ok(_) => ItemOutcome::BackedUp,
+                Err(e) if e.kind() == ErrorKind::NotFound => ItemOutcome::Missing,
+                Err(_) => ItemOutcome::PermissionDenied,
+            }
+        };
+        ItemReport { path: root.to_path_buf(), outcome, files: 0, bytes: 0, unreadable: Vec::new() }
+    }
+
+    /// Walk `roots` and total up, per pattern, the files each one keeps
+    /// out of the backup. A file under an excluded directory counts
+    /// towards the pattern that excluded the directory.
+    pub fn count_matches(&self, home: &Path, roots: &[PathBuf]) -> Vec<MatchCount> {
+        let mut counts = vec![MatchCount::default(); self.patterns.len()];
+        for root in top_level(roots) {
+            let mut pending = vec![(root, None)];
+            while let Some((rel, inherited)) = pending.pop() {
+                let matched = inherited.or_else(|| self.first_match(&rel));
+                let full = home.join(&rel);
+                let Ok(metadata) = full.symlink_metadata() else {
+                    continue;
+                };
+
+                if metadata.is_dir() {
+                    match fs::read_dir(&full) {
+                        Ok(entries) => {
+                            for entry in entries.flatten() {
+                                pending.push((rel.join(entry.file_name()), matched));
+                            }
+                        }
+                        Err(e) => debug!("Skipping unreadable {}: {}", full.display(), e),
+                    }
+                } else if let Some(index) = matched {
+                    counts[index].files += 1;
+                    counts[index].bytes += metadata.len();
+                }
+            }
+        }
+        counts
+    }
+}
+
+/// Glob for a pattern as written in the config, or None for a blank one
+fn to_glob(pattern: &str) -> Option<String> {
+    let pattern = pattern.trim().trim_start_matches("~/").trim_start_matches("./").trim_end_matches('/');
+    if pattern.is_empty() {
+        None
+    } else if pattern.contains('/') {
+        Some(pattern.to_string())
+    } else {
+        Some(format!("**/{}", pattern))
+    }
+}
+
+/// `roots` without those inside another root, so nothing is listed twice,
+/// sorted as the file list is written
+pub fn top_level(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut sorted: Vec<PathBuf> = roots.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    let mut top: Vec<PathBuf> = Vec::new();
+    for root in sorted {
+        if !top.iter().any(|parent| root.starts_with(parent)) {
+            top.push(root);
+        }
+    }
+    top
+}
+
+/// List of paths to archive, written to a private file that is removed
+/// when dropped. The list names every file in the backup, so it gets the
+/// same protection as the archive itself.
+pub struct FileList {
+    path: PathBuf,
+    pub entries: usize,
+    /// One for each root the list was written from
+    pub items: Vec<ItemReport>,
+    /// `tar --transform` expressions naming what followed links point to
+    /// after the links
+    pub transforms: Vec<String>,
+    /// The same, NUL-separated, for the wrapper script
+    transforms_path: Option<PathBuf>,
+}
+
+impl FileList {
+    pub fn write(
+        exclusions: &ExclusionSet,
+        home: &Path,
+        roots: &[PathBuf],
+        symlinks: &[(PathBuf, SymlinkPolicy)],
+        scan: Option<&ScanProgress>,
+    ) -> Result<Self> {
+        let dir = dirs::data_local_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("backup-manager");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let path = dir.join(format!("file-list-{}", std::process::id()));
+        let file = private_file(&path)?;
+        // Removed again on drop if the walk fails part way
+        let mut list = Self { path, entries: 0, items: Vec::new(), transforms: Vec::new(), transforms_path: None };
+
+        let mut out = BufWriter::new(file);
+        let followed;
+        (list.entries, list.items, followed) = exclusions.write_file_list(home, roots, symlinks, scan, &mut out)?;
+        out.flush()?;
+
+        list.transforms = followed.iter().map(|(target, link)| tar_transform(target, link)).collect();
+        if !list.transforms.is_empty() {
+            let path = list.path.with_extension("transforms");
+            let mut out = BufWriter::new(private_file(&path)?);
+            list.transforms_path = Some(path);
+            for transform in &list.transforms {
+                out.write_all(transform.as_bytes())?;
+                out.write_all(b"\0")?;
+            }
+            out.flush()?;
+        }
+        Ok(list)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file of transforms, when any links were followed
+    pub fn transforms_path(&self) -> Option<&Path> {
+        self.transforms_path.as_deref()
+    }
+}
+
+impl Drop for FileList {
+    fn drop(&mut self) {
+        for path in std::iter::once(&self.path).chain(&self.transforms_path) {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// New file at `path` only the user can read
+fn private_file(path: &Path) -> Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// `tar --transform` expression renaming `target`, listed by its absolute
+/// path, and everything below it to `link`. tar strips the leading `/`
+/// before transforming; `S` leaves the targets of links in the archive
+/// alone.
+fn tar_transform(target: &Path, link: &Path) -> String {
+    let target = target.to_string_lossy();
+    format!(
+        "s,^{}\\(/.*\\)\\?$,{}\\1,S",
+        escape(target.trim_start_matches('/'), "\\.*[]^$,"),
+        escape(&link.to_string_lossy(), "\\&,"),
+    )
+}
+
+/// `text` with a backslash before each of `special`
+fn escape(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusion_patterns() {
+        let patterns: Vec<String> = ["node_modules", "*.log", ".config/*/Cache", ".aws/credentials"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let set = ExclusionSet::new(&patterns).unwrap();
+
+        assert!(set.is_excluded(Path::new("node_modules")));
+        assert!(set.is_excluded(Path::new("code/app/node_modules")));
+        assert!(set.is_excluded(Path::new(".local/state/x.log")));
+        assert!(set.is_excluded(Path::new(".config/Code/Cache")));
+        assert!(!set.is_excluded(Path::new(".config/Code/User/Cache")));
+        assert!(set.is_excluded(Path::new(".aws/credentials")));
+        assert!(!set.is_excluded(Path::new(".aws/config")));
+        assert!(ExclusionSet::new(&["[".to_string()]).is_err());
+
+        // Added paths are taken literally and anchored at home
+        let set = set.with_paths(&[PathBuf::from("code/[draft]/.env")]).unwrap();
+        assert!(set.is_excluded(Path::new("code/[draft]/.env")));
+        assert!(!set.is_excluded(Path::new("code/d/.env")));
+        assert!(!set.is_excluded(Path::new("other/code/[draft]/.env")));
+        assert!(set.is_excluded(Path::new("node_modules")));
+    }
+
+    #[test]
+    fn test_file_list_and_counts() {
+        let home = std::env::temp_dir().join(format!("exclusions-test-{}", std::process::id()));
+        fs::create_dir_all(home.join(".config/app/Cache")).unwrap();
+        fs::write(home.join(".config/app/settings.json"), "{}").unwrap();
+        fs::write(home.join(".config/app/Cache/blob"), vec![0u8; 10]).unwrap();
+        fs::write(home.join(".config/app/debug.log"), vec![0u8; 5]).unwrap();
+
+        let set = ExclusionSet::new(&[".config/*/Cache".to_string(), "*.log".to_string()]).unwrap();
+        let roots = vec![PathBuf::from(".config"), PathBuf::from(".config/app"), PathBuf::from(".config/app/Cache/blob"), PathBuf::from(".missing")];
+
+        let mut out = Vec::new();
+        let scan = ScanProgress::default();
+        let (written, reports, _) = set.write_file_list(&home, &roots, &[], Some(&scan), &mut out).unwrap();
+        assert_eq!(written, 3);
+        // Nested items share their files, which are scanned once
+        assert_eq!(scan.get(), MatchCount { files: 1, bytes: 2 });
+        let outcomes: Vec<(ItemOutcome, usize, u64)> = reports.iter().map(|r| (r.outcome, r.files, r.bytes)).collect();
+        assert_eq!(outcomes, vec![
+            (ItemOutcome::BackedUp, 1, 2),
+            (ItemOutcome::BackedUp, 1, 2),
+            (ItemOutcome::Excluded, 0, 0),
+            (ItemOutcome::Missing, 0, 0),
+        ]);
+        assert!(reports[2].is_failure() && !reports[0].is_failure());
+        let mut listed: Vec<String> = String::from_utf8(out)
+            .unwrap()
+            .split('\0')
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect();
+        listed.sort();
+        assert_eq!(listed, vec![".config", ".config/app", ".config/app/settings.json"]);
+
+        let counts = set.count_matches(&home, &roots);
+        fs::remove_dir_all(&home).unwrap();
+        assert_eq!(counts, vec![MatchCount { files: 1, bytes: 10 }, MatchCount { files: 1, bytes: 5 }]);
+    }
+
+    #[test]
+    fn test_file_list_symlink_policies() {
+        let root = std::env::temp_dir().join(format!("symlinks-test-{}", std::process::id()));
+        let (home, dotfiles) = (root.join("home"), root.join("dotfiles"));
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::create_dir_all(dotfiles.join("nvim")).unwrap();
+        fs::write(dotfiles.join("bashrc"), "alias ll='ls -l'").unwrap();
+        fs::write(dotfiles.join("nvim/init.lua"), "").unwrap();
+        std::os::unix::fs::symlink(dotfiles.join("bashrc"), home.join(".bashrc")).unwrap();
+        std::os::unix::fs::symlink(dotfiles.join("nvim"), home.join(".config/nvim")).unwrap();
+        // Loops back to the directory it is in
+        std::os::unix::fs::symlink("..", dotfiles.join("nvim/up")).unwrap();
+
+        let set = ExclusionSet::new(&[]).unwrap();
+        let roots = vec![PathBuf::from(".bashrc"), PathBuf::from(".config")];
+        let list = |symlinks: &[(PathBuf, SymlinkPolicy)]| {
+            let mut out = Vec::new();
+            let (_, reports, followed) = set.write_file_list(&home, &roots, symlinks, None, &mut out).unwrap();
+            let mut listed: Vec<String> = String::from_utf8(out).unwrap().split('\0').filter(|e| !e.is_empty()).map(str::to_string).collect();
+            listed.sort();
+            (listed, reports, followed)
+        };
+
+        let (listed, _, followed) = list(&[]);
+        assert_eq!(listed, [".bashrc", ".config", ".config/nvim"]);
+        assert!(followed.is_empty());
+
+        let (listed, _, _) = list(&[(PathBuf::from(".config"), SymlinkPolicy::Skip)]);
+        assert_eq!(listed, [".bashrc", ".config"]);
+
+        // Targets are listed where they are and renamed by tar; the loop
+        // inside the followed directory is kept as a link
+        let (dotfiles, bashrc) = (fs::canonicalize(&dotfiles).unwrap(), fs::canonicalize(dotfiles.join("bashrc")).unwrap());
+        let (listed, reports, followed) = list(&[(PathBuf::from(".config"), SymlinkPolicy::Follow), (PathBuf::from(".bashrc"), SymlinkPolicy::Follow)]);
+        let at = |path: &Path| path.to_string_lossy().to_string();
+        let mut expected = vec![at(&bashrc), ".config".to_string(), at(&dotfiles.join("nvim")), at(&dotfiles.join("nvim/init.lua")), at(&dotfiles.join("nvim/up"))];
+        expected.sort();
+        assert_eq!(listed, expected);
+        assert_eq!(reports[0].files, 1);
+        assert_eq!(followed.len(), 2);
+        let transform = tar_transform(&bashrc, Path::new(".bashrc"));
+        assert_eq!(transform, format!("s,^{}\\(/.*\\)\\?$,.bashrc\\1,S", at(&bashrc).trim_start_matches('/').replace('.', "\\.")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::{BackupConfig, ConfigFormat};
+
+/// Config shipped with the project; modes, classifications and tool
+/// checks in a starter config come from here
+const STARTER_TEMPLATE: &str = include_str!("../../backup-config.json");
+
+/// Groups of dotfiles the wizard looks for, as (name, label, home-relative
+/// paths, sensitive). Sensitive groups only go into the complete mode,
+/// which is always encrypted.
+const CANDIDATES: &[(&str, &str, &[&str], bool)] = &[
+    ("shells", "Shells", &[".bashrc", ".bash_profile", ".bash_logout", ".profile", ".zshrc", ".p10k.zsh", ".config/fish", ".tmux.conf"], false),
+    ("editors", "Editors", &[".vimrc", ".config/nvim", ".config/Code", ".config/zed", ".config/micro", ".config/helix", ".emacs.d"], false),
+    ("git", "Git", &[".gitconfig", ".config/git"], false),
+    ("package_managers", "Package managers", &[".cargo/config.toml", ".npmrc", ".yarnrc", ".config/pip", ".bun"], false),
+    ("configurations", "Everything in ~/.config", &[".config", ".local/bin", ".fonts"], false),
+    ("credentials", "SSH, GPG and cloud keys", &[".ssh", ".gnupg", ".aws", ".kube", ".docker"], true),
+];
+
+/// Category offered by the first-run wizard
+#[derive(Debug, Clone)]
+pub struct WizardCategory {
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Paths that exist in the home directory
+    pub paths: Vec<String>,
+    pub sensitive: bool,
+    pub selected: bool,
+}
+
+/// The wizard's categories, with what was found under `home`. Everything
+/// found starts selected.
+pub fn detect(home: &Path) -> Vec<WizardCategory> {
+    CANDIDATES
+        .iter()
+        .map(|(name, label, paths, sensitive)| {
+            let paths: Vec<String> = paths
+                .iter()
+                .filter(|path| home.join(path).symlink_metadata().is_ok())
+                .map(|path| path.to_string())
+                .collect();
+            WizardCategory {
+                name,
+                label,
+                selected: !paths.is_empty(),
+                paths,
+                sensitive: *sensitive,
+            }
+        })
+        .collect()
+}
+
+/// Where the wizard saves the config: the per-user location
+/// `find_config_file` checks, under the name that was asked for
+pub fn config_path(specified: &Path) -> PathBuf {
+    let file_name = specified
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| "backup-config.json".into());
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from(".config"))
+        .join("backup-manager")
+        .join(file_name)
+}
+
+/// Starter config backing up the selected categories. Applications from
+/// the template are kept only if they are installed under `home`.
+pub fn starter_config(categories: &[WizardCategory], home: &Path) -> Result<BackupConfig> {
+    let mut config = ConfigFormat::Json.parse(STARTER_TEMPLATE)?;
+    config.last_updated = Local::now().format("%Y-%m-%d").to_string();
+
+    for (mode, mode_config) in config.backup_modes.iter_mut() {
+        let complete = mode == "complete";
+        mode_config.categories = categories
+            .iter()
+            .filter(|category| category.selected && !category.paths.is_empty())
+            .filter(|category| complete || !category.sensitive)
+            .map(|category| (category.name.to_string(), category.paths.clone()))
+            .collect();
+    }
+
+    for apps in config.modern_configurations.categories.values_mut() {
+        apps.retain(|_, app| app.item_paths().iter().any(|path| home.join(path).exists()));
+    }
+    config.modern_configurations.categories.retain(|_, apps| !apps.is_empty());
+    Ok(config)
+}
+
+/// Write the starter config to `path`, which must not exist yet. The
+/// config maps out where credentials live, so the directory is created
+/// 0700 and the file 0600.
+pub fn write(config: &BackupConfig, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder
+            .create(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Json);
+    let content = format.render(config)?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    std::io::Write::write_all(&mut file, content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starter_config_from_detected_paths() {
+        let home = std::env::temp_dir().join(format!("first-run-test-{}", std::process::id()));
+        fs::create_dir_all(home.join(".ssh")).unwrap();
+        fs::create_dir_all(home.join(".config/nvim")).unwrap();
+        fs::write(home.join(".bashrc"), "").unwrap();
+
+        let mut categories = detect(&home);
+        let selected: Vec<&str> = categories.iter().filter(|c| c.selected).map(|c| c.name).collect();
+        assert_eq!(selected, vec!["shells", "editors", "configurations", "credentials"]);
+        categories.iter_mut().find(|c| c.name == "configurations").unwrap().selected = false;
+
+        let config = starter_config(&categories, &home).unwrap();
+        fs::remove_dir_all(&home).unwrap();
+
+        let secure = &config.backup_modes["secure"].categories;
+        assert_eq!(secure["shells"], vec![".bashrc"]);
+        assert_eq!(secure["editors"], vec![".config/nvim"]);
+        assert!(!secure.contains_key("credentials"));
+        assert!(!secure.contains_key("configurations"));
+        assert_eq!(config.backup_modes["complete"].categories["credentials"], vec![".ssh"]);
+
+        // Only installed applications are kept
+        let apps: Vec<&String> = config.modern_configurations.categories.values().flat_map(|apps| apps.keys()).collect();
+        assert_eq!(apps, vec!["nvim"]);
+    }
+}
@@ -0,0 +1,32 @@
+/// Whether `query` fuzzily matches any of `fields`: each word of the query,
+/// ignoring case, must appear in one of them with its letters in order,
+/// though not necessarily next to each other. An empty query matches
+/// everything.
+pub fn matches(query: &str, fields: &[&str]) -> bool {
+    query.split_whitespace().all(|word| fields.iter().any(|field| subsequence(word, field)))
+}
+
+fn subsequence(word: &str, field: &str) -> bool {
+    let mut letters = field.chars().flat_map(char::to_lowercase);
+    word.chars()
+        .flat_map(char::to_lowercase)
+        .all(|wanted| letters.any(|letter| letter == wanted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_matches() {
+        let fields = ["Firefox profile", ".mozilla/firefox", "Browsers"];
+        assert!(matches("", &fields));
+        assert!(matches("ffx", &fields));
+        assert!(matches("MOZ brow", &fields));
+        assert!(matches("mzl/ff", &fields));
+        assert!(!matches("xff", &fields));
+        // Each word has to fit within one field
+        assert!(!matches("profilebrowsers", &fields));
+        assert!(!matches("ffx chrome", &fields));
+    }
+}
@@ -0,0 +1,233 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::core::fuzzy;
+use crate::core::types::BackupItem;
+
+/// One line of the item selection tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeRow {
+    /// A category, or an application within one, with the indices of the
+    /// items under it
+    Group { key: String, label: String, depth: usize, items: Vec<usize>, expanded: bool },
+    Item { index: usize, depth: usize },
+}
+
+impl TreeRow {
+    pub fn depth(&self) -> usize {
+        match self {
+            TreeRow::Group { depth, .. } | TreeRow::Item { depth, .. } => *depth,
+        }
+    }
+}
+
+/// Key a category's group is collapsed under
+pub fn category_key(item: &BackupItem) -> String {
+    item.category.clone()
+}
+
+/// Key an application's group within its category is collapsed under
+pub fn application_key(item: &BackupItem) -> Option<String> {
+    item.application.as_ref().map(|application| format!("{}/{}", item.category, application))
+}
+
+/// Whether `item` is kept by the fuzzy `filter`
+pub fn matches(item: &BackupItem, filter: &str) -> bool {
+    fuzzy::matches(filter, &[&item.name, &item.path.to_string_lossy(), &item.category])
+}
+
+/// Order of the categories, and of the applications and items in them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemSort {
+    #[default]
+    Name,
+    /// Largest first, groups by their total
+    Size,
+    /// Most sensitive first, groups by their most sensitive item
+    Security,
+}
+
+impl ItemSort {
+    pub fn next(self) -> Self {
+        match self {
+            ItemSort::Name => ItemSort::Size,
+            ItemSort::Size => ItemSort::Security,
+            ItemSort::Security => ItemSort::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemSort::Name => "name",
+            ItemSort::Size => "size",
+            ItemSort::Security => "security level",
+        }
+    }
+
+    /// Order two entries, each a label and the items under it, falling
+    /// back to the label so the order is the same every run
+    fn compare(self, items: &[BackupItem], a: (&str, &[usize]), b: (&str, &[usize])) -> Ordering {
+        let size = |indices: &[usize]| indices.iter().filter_map(|&index| items[index].size).sum::<u64>();
+        let level = |indices: &[usize]| indices.iter().map(|&index| &items[index].security_level).max();
+        let by_name = a.0.to_lowercase().cmp(&b.0.to_lowercase()).then_with(|| a.0.cmp(b.0));
+        match self {
+            ItemSort::Name => by_name,
+            ItemSort::Size => size(b.1).cmp(&size(a.1)).then(by_name),
+            ItemSort::Security => level(b.1).cmp(&level(a.1)).then(by_name),
+        }
+    }
+}
+
+/// An item, or an application's items, directly under a category
+enum Entry {
+    Item(usize),
+    Application { key: String, label: String, items: Vec<usize> },
+}
+
+impl Entry {
+    /// Label and items to sort the entry by
+    fn sort_key<'a>(&'a self, items: &'a [BackupItem]) -> (&'a str, &'a [usize]) {
+        match self {
+            Entry::Item(index) => (&items[*index].name, std::slice::from_ref(index)),
+            Entry::Application { label, items, .. } => (label, items),
+        }
+    }
+}
+
+/// Lay `items` out by category, then by application where an application
+/// has more than one item, each level in `sort` order. Groups whose key is
+/// in `collapsed` are listed without their items. With a `filter`, only the
+/// items it matches are listed, with their groups unfolded.
+pub fn rows(items: &[BackupItem], collapsed: &HashSet<String>, filter: &str, sort: ItemSort) -> Vec<TreeRow> {
+    let mut categories: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, item) in items.iter().enumerate().filter(|(_, item)| matches(item, filter)) {
+        categories.entry(&item.category).or_default().push(index);
+    }
+    let mut categories: Vec<(&str, Vec<usize>)> = categories.into_iter().collect();
+    categories.sort_by(|a, b| sort.compare(items, (a.0, &a.1), (b.0, &b.1)));
+    let folded = |key: &str| filter.trim().is_empty() && collapsed.contains(key);
+
+    let mut rows = Vec::new();
+    for (category, indices) in categories {
+        let expanded = !folded(category);
+        rows.push(TreeRow::Group {
+            key: category.to_string(),
+            label: category.to_string(),
+            depth: 0,
+            items: indices.clone(),
+            expanded,
+        });
+        if !expanded {
+            continue;
+        }
+
+        let mut applications: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for &index in &indices {
+            if let Some(key) = application_key(&items[index]) {
+                applications.entry(key).or_default().push(index);
+            }
+        }
+        let mut entries: Vec<Entry> = applications
+            .iter()
+            .filter(|(_, members)| members.len() > 1)
+            .map(|(key, members)| Entry::Application {
+                key: key.clone(),
+                label: items[members[0]].application.clone().unwrap_or_default(),
+                items: members.clone(),
+            })
+            .collect();
+        entries.extend(
+            indices
+                .iter()
+                .filter(|&&index| application_key(&items[index]).is_none_or(|key| applications[&key].len() < 2))
+                .map(|&index| Entry::Item(index)),
+        );
+        entries.sort_by(|a, b| sort.compare(items, a.sort_key(items), b.sort_key(items)));
+
+        for entry in entries {
+            match entry {
+                Entry::Item(index) => rows.push(TreeRow::Item { index, depth: 1 }),
+                Entry::Application { key, label, items: mut members } => {
+                    let expanded = !folded(&key);
+                    members.sort_by(|&a, &b| sort.compare(items, (&items[a].name, &[a]), (&items[b].name, &[b])));
+                    rows.push(TreeRow::Group { key, label, depth: 1, items: members.clone(), expanded });
+                    if expanded {
+                        rows.extend(members.into_iter().map(|index| TreeRow::Item { index, depth: 2 }));
+                    }
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Row of the group `rows[at]` sits in, if any
+pub fn parent(rows: &[TreeRow], at: usize) -> Option<usize> {
+    let depth = rows.get(at)?.depth();
+    rows[..at].iter().rposition(|row| matches!(row, TreeRow::Group { .. }) && row.depth() < depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn item(name: &str, category: &str, application: Option<&str>) -> BackupItem {
+        let item = BackupItem::new(name.to_string(), PathBuf::from(name), category.to_string(), String::new());
+        match application {
+            Some(application) => item.with_application(application.to_string()),
+            None => item,
+        }
+    }
+
+    #[test]
+    fn test_tree_rows() {
+        let items = vec![
+            item("shell", "Shell", None),
+            item("code settings", "Development", Some("vscode")),
+            item("git", "Development", Some("git")),
+            item("code extensions", "Development", Some("vscode")),
+        ];
+
+        let rows = rows(&items, &HashSet::new(), "", ItemSort::Name);
+        let expected = vec![
+            TreeRow::Group { key: "Development".into(), label: "Development".into(), depth: 0, items: vec![1, 2, 3], expanded: true },
+            TreeRow::Item { index: 2, depth: 1 },
+            TreeRow::Group { key: "Development/vscode".into(), label: "vscode".into(), depth: 1, items: vec![3, 1], expanded: true },
+            TreeRow::Item { index: 3, depth: 2 },
+            TreeRow::Item { index: 1, depth: 2 },
+            TreeRow::Group { key: "Shell".into(), label: "Shell".into(), depth: 0, items: vec![0], expanded: true },
+            TreeRow::Item { index: 0, depth: 1 },
+        ];
+        assert_eq!(rows, expected);
+        assert_eq!(parent(&rows, 4), Some(2));
+        assert_eq!(parent(&rows, 1), Some(0));
+        assert_eq!(parent(&rows, 5), None);
+
+        let collapsed = HashSet::from(["Development/vscode".to_string(), "Shell".to_string()]);
+        let rows = super::rows(&items, &collapsed, "", ItemSort::Name);
+        assert_eq!(rows.len(), 4);
+        assert!(matches!(&rows[2], TreeRow::Group { expanded: false, items, .. } if items == &vec![3, 1]));
+        assert!(matches!(&rows[3], TreeRow::Group { expanded: false, .. }));
+
+        // Filtering unfolds the groups with matches and drops the rest
+        let rows = super::rows(&items, &collapsed, "code ext", ItemSort::Name);
+        assert_eq!(rows, vec![
+            TreeRow::Group { key: "Development".into(), label: "Development".into(), depth: 0, items: vec![3], expanded: true },
+            TreeRow::Item { index: 3, depth: 1 },
+        ]);
+
+        // Largest first, groups by their total
+        let mut items = items;
+        items[0].size = Some(500);
+        items[1].size = Some(100);
+        items[2].size = Some(300);
+        items[3].size = Some(300);
+        let rows = super::rows(&items, &HashSet::new(), "", ItemSort::Size);
+        let order: Vec<usize> = rows.iter().filter_map(|row| match row {
+            TreeRow::Item { index, .. } => Some(*index),
+            _ => None,
+        }).collect();
+        assert_eq!(order, vec![3, 1, 2, 0]);
+    }
+}
@@ -0,0 +1,680 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Something a key press asks the current screen to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Toggle,
+    SelectAll,
+    SelectNone,
+    Undo,
+    Redo,
+    Confirm,
+    Back,
+    Quit,
+    Help,
+    NextField,
+    Yes,
+    Retry,
+    Details,
+    Copy,
+    // Main menu
+    Backup,
+    Restore,
+    Catalog,
+    FindFile,
+    Settings,
+    Machines,
+    Exclusions,
+    // Backup
+    SecureMode,
+    CompleteMode,
+    Compression,
+    LevelUp,
+    LevelDown,
+    Trust,
+    Destination,
+    Reclassify,
+    Volumes,
+    Largest,
+    Sort,
+    AddPath,
+    Hidden,
+    PauseContainers,
+    Rescan,
+    Filter,
+    SaveReport,
+    SaveHtmlReport,
+    RecoveryKit,
+    Remember,
+    UsePassphrase,
+    // Restore
+    Diff,
+    Changes,
+    Extract,
+    PushToHost,
+    Metadata,
+    DryRun,
+    StripPrivileged,
+    RollBack,
+    ReenableServices,
+    ImportDesktop,
+    ReinstallPackages,
+    RestoreVolumes,
+    ReplayDatabases,
+    ValidateCredentials,
+    // Settings and tools
+    Save,
+    History,
+    Add,
+    Remove,
+    SwitchMode,
+    Verify,
+}
+
+/// Screen, or dialog on a screen, that reads keys. `Global` bindings
+/// apply everywhere and are checked first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Global,
+    MainMenu,
+    Strategies,
+    ProfileSelection,
+    BackupModeSelection,
+    BackupItemSelection,
+    DestinationSelection,
+    Preflight,
+    SecretReview,
+    CriticalItems,
+    DestinationChange,
+    RecipientSelection,
+    VolumeSelection,
+    VolumeRestore,
+    BackupPassword,
+    BackupConfirm,
+    BackupComplete,
+    RestoreArchiveSelection,
+    RestorePassword,
+    RestoreItemSelection,
+    RestoreDiff,
+    LiveChanges,
+    RestoreDryRun,
+    RemoteRestore,
+    ExtractItem,
+    PrivilegedRestore,
+    RestoreProgress,
+    RestoreComplete,
+    RestoreRollback,
+    DatabaseReplay,
+    CatalogBrowser,
+    FindFile,
+    FileHistory,
+    FirstRunWizard,
+    FirstBackupGuide,
+    ConfigEditor,
+    ConfigField,
+    ConfigHistory,
+    ConfigRollback,
+    ExclusionEditor,
+    ExclusionInput,
+    ItemFilter,
+    FileBrowser,
+    CustomPath,
+    FleetOverview,
+    AuditHistory,
+    Help,
+    Error,
+}
+
+/// A key, optionally with Ctrl. Letters match either case; Shift is
+/// ignored so `+` and `Q` work however the terminal reports them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl Key {
+    fn matches(&self, event: &KeyEvent) -> bool {
+        if event.modifiers.contains(KeyModifiers::CONTROL) != self.ctrl {
+            return false;
+        }
+        match (self.code, event.code) {
+            (KeyCode::Char(bound), KeyCode::Char(pressed)) => bound.eq_ignore_ascii_case(&pressed),
+            (bound, pressed) => bound == pressed,
+        }
+    }
+
+    fn is_arrow(&self) -> bool {
+        !self.ctrl && matches!(self.code, KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        match self.code {
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Keys for one action on one screen; `hint` is the footer text, `None`
+/// for keys not worth the footer space
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub action: Action,
+    pub keys: Vec<Key>,
+    pub hint: Option<&'static str>,
+}
+
+/// Footer entry: the keys to press and what they do
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyHint {
+    pub keys: String,
+    pub description: &'static str,
+}
+
+/// Every key binding of every screen. Key handlers look actions up here
+/// and footers are rendered from it, so the two can't disagree.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, Vec<Binding>>,
+}
+
+impl Keymap {
+    /// Action bound to `key` on `context`
+    pub fn action(&self, context: KeyContext, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .get(&context)?
+            .iter()
+            .find(|binding| binding.keys.iter().any(|bound| bound.matches(key)))
+            .map(|binding| binding.action)
+    }
+
+    /// Footer hints of `context` for the actions `enabled` lets through.
+    /// Neighbouring bindings with the same text share one entry, e.g.
+    /// `↑↓: Navigate`.
+    pub fn hints(&self, context: KeyContext, enabled: impl Fn(Action) -> bool) -> Vec<KeyHint> {
+        let mut hints: Vec<(Vec<Key>, &'static str)> = Vec::new();
+        let bindings = self.bindings.get(&context).map(Vec::as_slice).unwrap_or_default();
+        for binding in bindings.iter().filter(|binding| enabled(binding.action)) {
+            let (Some(hint), Some(key)) = (binding.hint, binding.keys.first()) else {
+                continue;
+            };
+            match hints.last_mut() {
+                Some((keys, description)) if *description == hint => keys.push(*key),
+                _ => hints.push((vec![*key], hint)),
+            }
+        }
+
+        hints
+            .into_iter()
+            .map(|(keys, description)| {
+                let separator = if keys.iter().all(Key::is_arrow) { "" } else { "/" };
+                let labels: Vec<String> = keys.iter().map(Key::to_string).collect();
+                KeyHint { keys: labels.join(separator), description }
+            })
+            .collect()
+    }
+
+    /// Footer hint of a single action, e.g. the global help key
+    pub fn hint(&self, context: KeyContext, action: Action) -> Option<KeyHint> {
+        self.hints(context, |bound| bound == action).pop()
+    }
+
+    /// First key bound to `action` on `context`, as shown to the user
+    pub fn key_label(&self, context: KeyContext, action: Action) -> Option<String> {
+        self.bindings
+            .get(&context)?
+            .iter()
+            .find(|binding| binding.action == action)
+            .and_then(|binding| binding.keys.first())
+            .map(Key::to_string)
+    }
+}
+
+const fn key(code: KeyCode) -> Key {
+    Key { code, ctrl: false }
+}
+
+const fn ch(c: char) -> Key {
+    key(KeyCode::Char(c))
+}
+
+const fn ctrl(c: char) -> Key {
+    Key { code: KeyCode::Char(c), ctrl: true }
+}
+
+const UP: Key = key(KeyCode::Up);
+const DOWN: Key = key(KeyCode::Down);
+const ENTER: Key = key(KeyCode::Enter);
+const ESC: Key = key(KeyCode::Esc);
+const SPACE: Key = ch(' ');
+
+fn bind(action: Action, keys: &[Key], hint: &'static str) -> Binding {
+    Binding { action, keys: keys.to_vec(), hint: Some(hint) }
+}
+
+/// Bound but left out of the footer
+fn quiet(action: Action, keys: &[Key]) -> Binding {
+    Binding { action, keys: keys.to_vec(), hint: None }
+}
+
+/// Up and down with the vi keys as well
+fn navigate(hint: &'static str) -> [Binding; 2] {
+    [bind(Action::Up, &[UP, ch('k')], hint), bind(Action::Down, &[DOWN, ch('j')], hint)]
+}
+
+fn back(keys: &[Key], hint: &'static str) -> Binding {
+    bind(Action::Back, keys, hint)
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+
+        let page = [quiet(PageUp, &[key(KeyCode::PageUp)]), quiet(PageDown, &[key(KeyCode::PageDown)])];
+        let selection = [
+            bind(Toggle, &[SPACE], "Toggle"),
+            bind(SelectAll, &[ch('a')], "Select All"),
+            bind(SelectNone, &[ch('n')], "Select None"),
+            bind(Undo, &[ch('u')], "Undo/Redo"),
+            bind(Redo, &[ctrl('r')], "Undo/Redo"),
+        ];
+
+        let contexts: Vec<(KeyContext, Vec<Binding>)> = vec![
+            (KeyContext::Global, vec![
+                bind(Quit, &[ctrl('c')], "Quit"),
+                bind(Help, &[ctrl('h')], "Help"),
+            ]),
+            (KeyContext::MainMenu, vec![
+                quiet(Up, &[UP, ch('k')]),
+                quiet(Down, &[DOWN, ch('j')]),
+                quiet(Confirm, &[ENTER]),
+                bind(Backup, &[ch('1'), ch('b')], "Backup"),
+                bind(Restore, &[ch('2'), ch('r')], "Restore"),
+                bind(Catalog, &[ch('3')], "Catalog"),
+                bind(FindFile, &[ch('4')], "Find file"),
+                bind(Settings, &[ch('5')], "Settings"),
+                bind(Machines, &[ch('6')], "Machines"),
+                bind(Exclusions, &[ch('7')], "Exclusions"),
+                bind(History, &[ch('8')], "History"),
+                bind(NextField, &[key(KeyCode::Tab)], "Strategies"),
+                bind(Quit, &[ch('q'), ESC], "Quit"),
+            ]),
+            (KeyContext::Strategies, [
+                navigate("Strategy").to_vec(),
+                vec![
+                    bind(Confirm, &[ENTER, ch('r')], "Run now"),
+                    bind(NextField, &[key(KeyCode::Tab)], "Menu"),
+                    back(&[ESC], "Menu"),
+                    bind(Quit, &[ch('q')], "Quit"),
+                ],
+            ].concat()),
+            (KeyContext::ProfileSelection, [
+                navigate("Profile").to_vec(),
+                vec![bind(Confirm, &[ENTER], "Use profile"), back(&[ESC, ch('q')], "Back")],
+            ].concat()),
+            (KeyContext::BackupModeSelection, vec![
+                quiet(Up, &[UP, ch('k')]),
+                quiet(Down, &[DOWN, ch('j')]),
+                bind(SecureMode, &[ch('1'), ch('s')], "Secure"),
+                bind(CompleteMode, &[ch('2'), ch('c')], "Complete"),
+                bind(Compression, &[ch('z')], "Compression"),
+                bind(LevelUp, &[ch('+'), ch('=')], "Level"),
+                bind(LevelDown, &[ch('-')], "Level"),
+                bind(Confirm, &[ENTER], "Select"),
+                back(&[ESC, ch('q')], "Back"),
+            ]),
+            (KeyContext::BackupItemSelection, [
+                navigate("Navigate").to_vec(),
+                page.to_vec(),
+                selection.to_vec(),
+                vec![
+                    bind(Left, &[key(KeyCode::Left)], "Collapse"),
+                    bind(Right, &[key(KeyCode::Right)], "Expand"),
+                    bind(Reclassify, &[ch('s')], "Security level"),
+                    bind(Destination, &[ch('d')], "Removable drive"),
+                    bind(Volumes, &[ch('v')], "Pick volumes"),
+                    bind(Largest, &[ch('l')], "Largest items"),
+                    bind(Filter, &[ch('/')], "Filter"),
+                    bind(Sort, &[ch('o')], "Sort"),
+                    bind(AddPath, &[ch('p')], "Add path..."),
+                    bind(Confirm, &[ENTER], "Continue"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::DestinationSelection, [
+                navigate("Drive").to_vec(),
+                vec![
+                    bind(Confirm, &[ENTER], "Use drive"),
+                    bind(Rescan, &[ch('r')], "Rescan"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::Preflight, vec![
+                bind(Confirm, &[ENTER], "Continue anyway"),
+                back(&[ESC, ch('q')], "Back to selection"),
+            ]),
+            (KeyContext::SecretReview, [
+                navigate("File").to_vec(),
+                page.to_vec(),
+                vec![
+                    bind(Toggle, &[SPACE], "Leave out/keep"),
+                    bind(Confirm, &[ENTER], "Back up"),
+                    back(&[ESC, ch('q')], "Back to selection"),
+                ],
+            ].concat()),
+            (KeyContext::CriticalItems, vec![
+                bind(Yes, &[ch('y')], "Back up without them"),
+                back(&[ESC, ch('n')], "Back to selection"),
+            ]),
+            (KeyContext::DestinationChange, vec![
+                bind(Trust, &[ch('t')], "Trust the new destination and continue"),
+                back(&[ESC, ch('n')], "Cancel"),
+            ]),
+            (KeyContext::RecipientSelection, [
+                navigate("Key").to_vec(),
+                vec![
+                    bind(Toggle, &[SPACE], "Toggle"),
+                    bind(UsePassphrase, &[ch('p')], "Use a passphrase instead"),
+                    bind(Confirm, &[ENTER], "Encrypt to selected"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::VolumeSelection, [
+                navigate("Volume").to_vec(),
+                vec![
+                    bind(Toggle, &[SPACE], "Toggle"),
+                    bind(PauseContainers, &[ch('p')], "Pause containers"),
+                    bind(Confirm, &[ENTER], "Save"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::VolumeRestore, [
+                navigate("Volume").to_vec(),
+                vec![
+                    bind(Toggle, &[SPACE], "Toggle"),
+                    bind(Confirm, &[ENTER], "Restore selected"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::BackupPassword, vec![
+                bind(NextField, &[key(KeyCode::Tab)], "Switch fields"),
+                bind(Remember, &[ctrl('r')], "Remember in keyring"),
+                bind(Confirm, &[ENTER], "Continue"),
+                back(&[ESC], "Back"),
+            ]),
+            (KeyContext::BackupConfirm, vec![
+                bind(Confirm, &[ENTER, ch('y')], "Start backup"),
+                back(&[ESC, ch('q')], "Back to selection"),
+            ]),
+            (KeyContext::BackupComplete, [
+                navigate("Items").to_vec(),
+                page.to_vec(),
+                vec![
+                    bind(Filter, &[ch('f')], "Failures only"),
+                    bind(SaveReport, &[ch('s')], "Save report (Markdown)"),
+                    bind(SaveHtmlReport, &[ch('h')], "Save report (HTML)"),
+                    bind(RecoveryKit, &[ch('p')], "Recovery kit"),
+                    bind(Confirm, &[ENTER, SPACE], "Return to Main Menu"),
+                    bind(Quit, &[ch('q'), ESC], "Quit Application"),
+                ],
+            ].concat()),
+            (KeyContext::RestoreArchiveSelection, [
+                navigate("Navigate").to_vec(),
+                vec![
+                    bind(Confirm, &[ENTER], "Select"),
+                    bind(Sort, &[ch('o')], "Sort"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::RestorePassword, vec![
+                bind(Confirm, &[ENTER], "Unlock Archive"),
+                back(&[ESC], "Back"),
+            ]),
+            (KeyContext::RestoreItemSelection, [
+                navigate("Navigate").to_vec(),
+                selection.to_vec(),
+                vec![
+                    bind(Diff, &[ch('d')], "Diff"),
+                    bind(Changes, &[ch('c')], "Changes since backup"),
+                    bind(Extract, &[ch('x')], "Extract to..."),
+                    bind(PushToHost, &[ch('p')], "Push to host"),
+                    bind(Metadata, &[ch('o')], "Owners/ACLs"),
+                    bind(DryRun, &[ch('r')], "Dry run"),
+                    bind(Filter, &[ch('/')], "Filter"),
+                    bind(Confirm, &[ENTER], "Start Restore"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::RestoreDiff, vec![
+                bind(Up, &[UP, ch('k')], "scroll"),
+                bind(Down, &[DOWN, ch('j')], "scroll"),
+                bind(PageUp, &[key(KeyCode::PageUp)], "page"),
+                bind(PageDown, &[key(KeyCode::PageDown)], "page"),
+                back(&[ESC, ch('q'), ch('d')], "close"),
+            ]),
+            (KeyContext::LiveChanges, vec![
+                bind(Up, &[UP, ch('k')], "scroll"),
+                bind(Down, &[DOWN, ch('j')], "scroll"),
+                bind(PageUp, &[key(KeyCode::PageUp)], "page"),
+                bind(PageDown, &[key(KeyCode::PageDown)], "page"),
+                back(&[ESC, ch('q'), ch('c')], "close"),
+            ]),
+            (KeyContext::RestoreDryRun, vec![
+                bind(Up, &[UP, ch('k')], "scroll"),
+                bind(Down, &[DOWN, ch('j')], "scroll"),
+                bind(PageUp, &[key(KeyCode::PageUp)], "page"),
+                bind(PageDown, &[key(KeyCode::PageDown)], "page"),
+                bind(Confirm, &[ENTER], "restore for real"),
+                back(&[ESC, ch('q')], "close"),
+            ]),
+            (KeyContext::RemoteRestore, vec![
+                bind(Confirm, &[ENTER], "Push"),
+                back(&[ESC], "Cancel"),
+            ]),
+            (KeyContext::ExtractItem, vec![
+                bind(Confirm, &[ENTER], "Extract"),
+                back(&[ESC], "Cancel"),
+            ]),
+            (KeyContext::PrivilegedRestore, vec![
+                bind(Yes, &[ch('y')], "Restore with bits"),
+                bind(StripPrivileged, &[ch('s')], "Restore without bits"),
+                back(&[ESC, ch('n')], "Cancel"),
+            ]),
+            (KeyContext::RestoreProgress, vec![
+                back(&[ESC], "Cancel download"),
+            ]),
+            (KeyContext::RestoreComplete, vec![
+                bind(RollBack, &[ch('r')], "Roll back restore"),
+                bind(ReenableServices, &[ch('e')], "Re-enable services"),
+                bind(ImportDesktop, &[ch('d')], "Import desktop settings"),
+                bind(ReinstallPackages, &[ch('p')], "Reinstall script"),
+                bind(RestoreVolumes, &[ch('c')], "Container volumes"),
+                bind(ReplayDatabases, &[ch('b')], "Replay databases"),
+                bind(ValidateCredentials, &[ch('v')], "Validate credentials"),
+                bind(SaveReport, &[ch('s')], "Save report (Markdown)"),
+                bind(SaveHtmlReport, &[ch('h')], "Save report (HTML)"),
+                bind(Confirm, &[ENTER, SPACE], "Return to Main Menu"),
+                bind(Quit, &[ch('q'), ESC], "Quit Application"),
+            ]),
+            (KeyContext::RestoreRollback, vec![
+                bind(Yes, &[ch('y')], "Roll back"),
+                back(&[ESC, ch('n')], "Cancel"),
+            ]),
+            (KeyContext::DatabaseReplay, vec![
+                bind(Yes, &[ch('y')], "Replay"),
+                back(&[ESC, ch('n')], "Cancel"),
+            ]),
+            (KeyContext::CatalogBrowser, vec![
+                bind(Up, &[UP], "Navigate"),
+                bind(Down, &[DOWN], "Navigate"),
+                back(&[ESC], "Back"),
+            ]),
+            (KeyContext::FindFile, vec![
+                bind(Up, &[UP], "File"),
+                bind(Down, &[DOWN], "File"),
+                bind(Left, &[key(KeyCode::Left)], "Version"),
+                bind(Right, &[key(KeyCode::Right)], "Version"),
+                bind(Confirm, &[ENTER], "Restore this file"),
+                bind(History, &[key(KeyCode::Tab)], "History"),
+                back(&[ESC], "Back"),
+            ]),
+            (KeyContext::FileHistory, [
+                navigate("Version").to_vec(),
+                vec![bind(Confirm, &[ENTER], "Restore this version"), back(&[ESC, ch('q')], "Back")],
+            ].concat()),
+            (KeyContext::FirstRunWizard, [
+                navigate("Category").to_vec(),
+                vec![
+                    bind(Toggle, &[SPACE], "Toggle"),
+                    bind(Undo, &[ch('u')], "Undo/Redo"),
+                    bind(Redo, &[ctrl('r')], "Undo/Redo"),
+                    bind(Confirm, &[ENTER], "Write config"),
+                    bind(Quit, &[ESC, ch('q')], "Quit without saving"),
+                ],
+            ].concat()),
+            (KeyContext::FirstBackupGuide, vec![
+                bind(Confirm, &[ENTER], "Use recommended compression"),
+                back(&[ESC, ch('q')], "Main menu"),
+            ]),
+            (KeyContext::ConfigEditor, [
+                navigate("Navigate").to_vec(),
+                vec![
+                    bind(Confirm, &[ENTER], "Edit"),
+                    bind(Toggle, &[SPACE], "Toggle"),
+                    bind(Save, &[ch('s')], "Save"),
+                    bind(History, &[ch('h')], "History"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::ConfigField, vec![
+                bind(Confirm, &[ENTER], "Apply"),
+                back(&[ESC], "Cancel"),
+            ]),
+            (KeyContext::ConfigHistory, [
+                navigate("Version").to_vec(),
+                vec![
+                    bind(PageUp, &[key(KeyCode::PageUp)], "Scroll diff"),
+                    bind(PageDown, &[key(KeyCode::PageDown)], "Scroll diff"),
+                    bind(RollBack, &[ch('r')], "Roll back"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::ConfigRollback, vec![
+                bind(Yes, &[ch('y')], "Roll back"),
+                back(&[ESC, ch('n')], "Cancel"),
+            ]),
+            (KeyContext::ExclusionEditor, [
+                navigate("Navigate").to_vec(),
+                vec![
+                    bind(Add, &[ch('a')], "Add"),
+                    bind(Remove, &[ch('d'), key(KeyCode::Delete)], "Remove"),
+                    bind(SwitchMode, &[ch('m')], "Mode"),
+                    bind(Save, &[ch('s')], "Save"),
+                    back(&[ESC, ch('q')], "Back"),
+                ],
+            ].concat()),
+            (KeyContext::ExclusionInput, vec![
+                bind(Confirm, &[ENTER], "Add"),
+                back(&[ESC], "Cancel"),
+            ]),
+            (KeyContext::FileBrowser, [
+                navigate("Navigate").to_vec(),
+                page.to_vec(),
+                vec![
+                    bind(Right, &[key(KeyCode::Right)], "Open"),
+                    bind(Left, &[key(KeyCode::Left), key(KeyCode::Backspace)], "Up"),
+                    bind(Confirm, &[ENTER], "Open/Add"),
+                    bind(Toggle, &[SPACE], "Add"),
+                    bind(Hidden, &[ch('.')], "Hidden files"),
+                    back(&[ESC, ch('q')], "Cancel"),
+                ],
+            ].concat()),
+            (KeyContext::CustomPath, vec![
+                bind(Yes, &[ch('y')], "Save to config"),
+                bind(Confirm, &[ENTER, ch('n')], "This backup only"),
+                back(&[ESC], "Cancel"),
+            ]),
+            (KeyContext::ItemFilter, vec![
+                bind(Confirm, &[ENTER, DOWN], "Keep filter"),
+                back(&[ESC], "Clear"),
+            ]),
+            (KeyContext::FleetOverview, [
+                navigate("Navigate").to_vec(),
+                vec![bind(Verify, &[ch('v')], "Verify latest"), back(&[ESC, ch('q')], "Back")],
+            ].concat()),
+            (KeyContext::AuditHistory, [
+                navigate("Navigate").to_vec(),
+                page.to_vec(),
+                vec![back(&[ESC, ch('q')], "Back")],
+            ].concat()),
+            (KeyContext::Help, vec![
+                back(&[ESC, ch('q')], "Back"),
+            ]),
+            (KeyContext::Error, vec![
+                bind(Retry, &[ch('r')], "Retry"),
+                bind(Details, &[ch('t')], "Technical details"),
+                bind(Copy, &[ch('c')], "Copy error"),
+                bind(Confirm, &[ENTER, ESC, SPACE], "Return"),
+            ]),
+        ];
+
+        Self { bindings: contexts.into_iter().collect() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actions_and_hints_come_from_the_same_bindings() {
+        let keymap = Keymap::default();
+        let press = |code, modifiers| KeyEvent::new(code, modifiers);
+
+        let quit = press(KeyCode::Char('Q'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(KeyContext::MainMenu, &quit), Some(Action::Quit));
+        let ctrl_c = press(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(KeyContext::Global, &ctrl_c), Some(Action::Quit));
+        assert_eq!(keymap.action(KeyContext::BackupModeSelection, &ctrl_c), None);
+        // Letters type into the search box rather than acting
+        let k = press(KeyCode::Char('k'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(KeyContext::CatalogBrowser, &k), None);
+
+        let hints = keymap.hints(KeyContext::BackupModeSelection, |_| true);
+        let rendered: Vec<String> = hints.iter().map(|h| format!("{}: {}", h.keys, h.description)).collect();
+        assert_eq!(rendered, vec![
+            "1: Secure", "2: Complete", "Z: Compression", "+/-: Level", "Enter: Select", "Esc: Back",
+        ]);
+
+        let hints = keymap.hints(KeyContext::RestoreItemSelection, |action| action != Action::Diff);
+        assert_eq!(hints[0], KeyHint { keys: "↑↓".to_string(), description: "Navigate" });
+        assert!(hints.iter().all(|hint| hint.description != "Diff"));
+        assert_eq!(keymap.hint(KeyContext::Global, Action::Help).unwrap().keys, "Ctrl+H");
+        assert_eq!(keymap.key_label(KeyContext::MainMenu, Action::Backup).as_deref(), Some("1"));
+    }
+}
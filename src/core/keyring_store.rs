@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use keyring::Entry;
+
+use crate::core::security::SecurePassword;
+
+/// Service the passphrase is filed under in GNOME Keyring or KWallet
+const SERVICE: &str = "backup-manager";
+
+fn entry(account: &str) -> Result<Entry> {
+    Entry::new(SERVICE, account).context("Failed to open the system keyring")
+}
+
+/// Passphrase remembered under `account`, if there is one. Blocking: the
+/// keyring is asked over D-Bus and may prompt the user to unlock it, so
+/// call it from a blocking thread.
+pub fn load(account: &str) -> Result<Option<SecurePassword>> {
+    match entry(account)?.get_password() {
+        Ok(password) => Ok(Some(SecurePassword::new(password))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e).context("Failed to read the passphrase from the system keyring"),
+    }
+}
+
+/// Remember `password` under `account`, replacing any passphrase there.
+/// Blocking.
+pub fn store(account: &str, password: &SecurePassword) -> Result<()> {
+    let text = std::str::from_utf8(password.as_bytes()).context("The passphrase is not valid UTF-8")?;
+    entry(account)?
+        .set_password(text)
+        .context("Failed to save the passphrase in the system keyring")
+}
+
+/// Remove the passphrase remembered under `account`, if any. Blocking.
+pub fn forget(account: &str) -> Result<()> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e).context("Failed to remove the passphrase from the system keyring"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+    use std::any::Any;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Secrets by service and account, shared by every entry as the D-Bus
+    /// service would
+    type Secrets = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+    #[derive(Debug)]
+    struct MemoryCredential {
+        secrets: Secrets,
+        key: String,
+    }
+
+    impl CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            self.secrets.lock().unwrap().insert(self.key.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            self.secrets.lock().unwrap().get(&self.key).cloned().ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            self.secrets.lock().unwrap().remove(&self.key).map(|_| ()).ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct MemoryKeyring {
+        secrets: Secrets,
+    }
+
+    impl CredentialBuilderApi for MemoryKeyring {
+        fn build(&self, _target: Option<&str>, service: &str, user: &str) -> keyring::Result<Box<Credential>> {
+            Ok(Box::new(MemoryCredential { secrets: self.secrets.clone(), key: format!("{}/{}", service, user) }))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_passphrase_is_remembered_per_account() {
+        keyring::set_default_credential_builder(Box::new(MemoryKeyring::default()));
+        assert!(load("complete-mode").unwrap().is_none());
+
+        store("complete-mode", &SecurePassword::new("first passphrase".to_string())).unwrap();
+        store("complete-mode", &SecurePassword::new("pässphrase".to_string())).unwrap();
+        store("laptop", &SecurePassword::new("another".to_string())).unwrap();
+        assert_eq!(load("complete-mode").unwrap().unwrap().as_bytes(), "pässphrase".as_bytes());
+
+        // Forgetting one account leaves the others, and is fine to repeat
+        forget("complete-mode").unwrap();
+        forget("complete-mode").unwrap();
+        assert!(load("complete-mode").unwrap().is_none());
+        assert_eq!(load("laptop").unwrap().unwrap().as_bytes(), b"another");
+
+        // The keyring only holds text
+        assert!(store("binary", &SecurePassword::from_bytes(vec![0xff, 0xfe])).is_err());
+    }
+}
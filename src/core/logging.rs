@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::core::config::LoggingConfig;
+
+/// Records with this target go to the log file only; for messages that
+/// are also printed, such as the session summary
+pub const FILE_ONLY_TARGET: &str = "backup_ui::file_only";
+
+/// Warnings and errors logged so far in this run
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn warnings_logged() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// One line of the log file
+#[derive(Debug, Serialize)]
+struct FileRecord<'a> {
+    time: DateTime<Utc>,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+    pid: u32,
+}
+
+/// Where the log file goes and how much of it is kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileLogging {
+    pub path: PathBuf,
+    pub level: LevelFilter,
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+impl FileLogging {
+    /// `~/.local/state/backup-manager/logs/backup-ui.log`
+    pub fn default_path() -> PathBuf {
+        dirs::state_dir()
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("state")))
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("backup-manager")
+            .join("logs")
+            .join("backup-ui.log")
+    }
+
+    /// Log file asked for by the config's `logging` section or by
+    /// `--log-file` (with or without a path), or `None` when neither is set.
+    /// `--debug` lowers the file's level to match the console.
+    pub fn resolve(config: Option<&LoggingConfig>, flag: Option<&Option<PathBuf>>, debug: bool) -> Result<Option<Self>> {
+        if config.is_none() && flag.is_none() {
+            return Ok(None);
+        }
+        let defaults = LoggingConfig::default();
+        let config = config.unwrap_or(&defaults);
+
+        let path = match flag {
+            Some(Some(path)) => path.clone(),
+            _ => config.resolved_file().unwrap_or_else(Self::default_path),
+        };
+        let level = if debug {
+            LevelFilter::Debug
+        } else {
+            config
+                .level
+                .parse()
+                .with_context(|| format!("Unknown log level '{}'; use error, warn, info, debug or trace", config.level))?
+        };
+        Ok(Some(Self { path, level, max_bytes: config.max_bytes, keep: config.keep }))
+    }
+}
+
+/// Append-only file renamed to `.1`, `.2`, ... once it reaches `max_bytes`,
+/// keeping the newest `keep` of those
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_bytes: u64, keep: usize) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_private_dir(dir)?;
+        }
+        let file = open_private(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, max_bytes, keep, file, size })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            // The oldest is overwritten by the one after it
+            for n in (1..self.keep).rev() {
+                let older = rotated_path(&self.path, n);
+                if older.exists() {
+                    fs::rename(&older, rotated_path(&self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.file = open_private(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Console logging from `env_logger`, copied as JSON lines to the log file
+/// when there is one
+struct Logger {
+    console: env_logger::Logger,
+    file: Option<(LevelFilter, Mutex<RotatingFile>)>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata) || self.file.as_ref().is_some_and(|(level, _)| metadata.level() <= *level)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= log::Level::Warn {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.console.matches(record) && record.target() != FILE_ONLY_TARGET {
+            self.console.log(record);
+        }
+        let Some((level, file)) = &self.file else {
+            return;
+        };
+        if record.level() > *level {
+            return;
+        }
+        let line = FileRecord {
+            time: Utc::now(),
+            level: record.level().as_str(),
+            target: record.target(),
+            message: record.args().to_string(),
+            pid: std::process::id(),
+        };
+        // Nowhere left to report a failed log write; the console still has it
+        if let (Ok(line), Ok(mut file)) = (serde_json::to_string(&line), file.lock()) {
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some((_, file)) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+/// Install `console` as the logger, also writing to the log file if one is
+/// set up
+pub fn init(mut console: env_logger::Builder, file: Option<FileLogging>) -> Result<()> {
+    let console = console.build();
+    let file = match file {
+        Some(file) => {
+            let opened = RotatingFile::open(file.path.clone(), file.max_bytes, file.keep)
+                .with_context(|| format!("Failed to open log file {}", file.path.display()))?;
+            Some((file.level, Mutex::new(opened)))
+        }
+        None => None,
+    };
+
+    let max_level = console.filter().max(file.as_ref().map(|(level, _)| *level).unwrap_or(LevelFilter::Off));
+    log::set_boxed_logger(Box::new(Logger { console, file }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Log lines name backed-up paths and destinations, so keep them private
+fn open_private(path: &Path) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_newest_files() {
+        let dir = std::env::temp_dir().join(format!("rotating-log-test-{}", std::process::id()));
+        let path = dir.join("logs").join("backup-ui.log");
+        let mut file = RotatingFile::open(path.clone(), 20, 2).unwrap();
+
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            file.write_line(line).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth line\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "third line\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "second line\n");
+        assert!(!rotated_path(&path, 3).exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+            assert_eq!(fs::metadata(path.parent().unwrap()).unwrap().permissions().mode() & 0o777, 0o700);
+        }
+
+        let flag = Some(Some(path.clone()));
+        let resolved = FileLogging::resolve(None, flag.as_ref(), false).unwrap().unwrap();
+        assert_eq!((resolved.path, resolved.level), (path, LevelFilter::Info));
+        assert!(FileLogging::resolve(None, None, true).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
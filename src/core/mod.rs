@@ -1,5 +1,24 @@
 pub mod app;
+pub mod audit;
 pub mod config;
+pub mod config_form;
+pub mod config_history;
+pub mod config_migration;
+pub mod diff;
+pub mod errors;
+pub mod events;
+pub mod exclusions;
+pub mod first_run;
+pub mod fuzzy;
+pub mod item_tree;
+pub mod keymap;
+pub mod keyring_store;
+pub mod logging;
+pub mod notifications;
 pub mod state;
+pub mod strategies;
 pub mod types;
-pub mod security;
\ No newline at end of file
+pub mod undo;
+pub mod secret_sharing;
+pub mod security;
+pub mod session;
\ No newline at end of file
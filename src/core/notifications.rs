@@ -0,0 +1,103 @@
+use log::{debug, warn};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::core::config::NotificationsConfig;
+use crate::ui::terminal::{format_bytes, format_duration};
+
+/// How a long-running job ended, for its desktop notification
+#[derive(Debug, Clone)]
+pub struct JobOutcome {
+    /// "Backup", "Repack", ...
+    pub job: &'static str,
+    pub error: Option<String>,
+    pub duration: Duration,
+    pub size: Option<u64>,
+    pub destination: Option<String>,
+}
+
+impl JobOutcome {
+    /// Title and body of the notification, or `None` when the config
+    /// doesn't ask for one
+    pub fn message(&self, config: &NotificationsConfig) -> Option<(String, String)> {
+        let wanted = if self.error.is_some() { config.on_failure } else { config.on_success };
+        if !wanted || self.duration < Duration::from_secs(config.min_seconds) {
+            return None;
+        }
+
+        let title = match self.error {
+            Some(_) => format!("{} failed", self.job),
+            None => format!("{} finished", self.job),
+        };
+        let mut lines = vec![format!("Took {}", format_duration(self.duration))];
+        if let Some(size) = self.size.filter(|_| self.error.is_none()) {
+            lines.push(format!("Size {}", format_bytes(size)));
+        }
+        if config.show_details {
+            match (&self.destination, &self.error) {
+                (Some(destination), None) => lines.push(format!("Saved to {}", destination)),
+                (Some(destination), Some(_)) => lines.push(format!("Destination {}", destination)),
+                (None, _) => {}
+            }
+            if let Some(error) = &self.error {
+                lines.push(error.lines().next().unwrap_or_default().to_string());
+            }
+        }
+        Some((title, lines.join("\n")))
+    }
+}
+
+/// Show a desktop notification for `outcome` if the config asks for one.
+/// It is sent from its own thread so a slow notification daemon can't
+/// stall the UI; a command about to exit should join the returned handle.
+/// A missing daemon is only logged.
+pub fn notify(config: Option<&NotificationsConfig>, outcome: JobOutcome) -> Option<JoinHandle<()>> {
+    let (title, body) = config.and_then(|config| outcome.message(config))?;
+
+    debug!("Notifying: {}", title);
+    Some(std::thread::spawn(move || {
+        let shown = notify_rust::Notification::new()
+            .appname("backup-ui")
+            .summary(&title)
+            .body(&body)
+            .show();
+        if let Err(e) = shown {
+            warn!("Could not show desktop notification: {}", e);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_message() {
+        let config = NotificationsConfig {
+            on_success: true,
+            on_failure: true,
+            min_seconds: 30,
+            show_details: false,
+        };
+        let outcome = JobOutcome {
+            job: "Backup",
+            error: None,
+            duration: Duration::from_secs(95),
+            size: Some(3 * 1024 * 1024),
+            destination: Some("/mnt/backups/backup_host.tar.gz".to_string()),
+        };
+
+        let (title, body) = outcome.message(&config).unwrap();
+        assert_eq!(title, "Backup finished");
+        assert_eq!(body, "Took 1m 35s\nSize 3.0 MB");
+
+        let detailed = NotificationsConfig { show_details: true, ..config.clone() };
+        let failed = JobOutcome { error: Some("rclone upload failed\ndetails".to_string()), ..outcome.clone() };
+        let (title, body) = failed.message(&detailed).unwrap();
+        assert_eq!(title, "Backup failed");
+        assert_eq!(body, "Took 1m 35s\nDestination /mnt/backups/backup_host.tar.gz\nrclone upload failed");
+
+        let quick = JobOutcome { duration: Duration::from_secs(5), ..outcome };
+        assert!(quick.message(&config).is_none());
+    }
+}
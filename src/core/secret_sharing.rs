@@ -0,0 +1,260 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use qrcode::render::unicode;
+use qrcode::QrCode;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use zeroize::Zeroize;
+
+use crate::core::security::SecurePassword;
+
+/// Start of every encoded share, with the format version
+const SHARE_PREFIX: &str = "BKSHARE-1";
+
+/// One share of a passphrase split with Shamir's scheme over GF(256):
+/// any `threshold` shares of the same set give the passphrase back, and
+/// fewer say nothing about it
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    /// Random tag shared by the shares of one split, so shares of
+    /// different splits aren't mixed up
+    pub set: [u8; 4],
+    pub threshold: u8,
+    /// Point the share was taken at, 1 to 255
+    pub index: u8,
+    data: Vec<u8>,
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Share[{} #{} of {} needed]", hex(&self.set), self.index, self.threshold)
+    }
+}
+
+impl Share {
+    /// `BKSHARE-1-<set>-<threshold>-<index>-<data>-<check>`, in upper-case
+    /// hex so it fits a QR code's alphanumeric mode; `check` catches typos
+    pub fn encode(&self) -> String {
+        let body = format!("{}-{}-{}-{}-{}", SHARE_PREFIX, hex(&self.set), self.threshold, self.index, hex(&self.data));
+        let check = hex(&Sha256::digest(body.as_bytes())[..4]);
+        format!("{}-{}", body, check)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().to_uppercase();
+        let (body, check) = text.rsplit_once('-').context("Not a passphrase share")?;
+        let rest = body.strip_prefix(SHARE_PREFIX).and_then(|rest| rest.strip_prefix('-')).context("Not a passphrase share")?;
+        if hex(&Sha256::digest(body.as_bytes())[..4]) != check {
+            anyhow::bail!("The share is damaged or mistyped (checksum mismatch)");
+        }
+        let fields: Vec<&str> = rest.split('-').collect();
+        let [set, threshold, index, data] = fields[..] else {
+            anyhow::bail!("Not a passphrase share");
+        };
+        let set = unhex(set).ok().and_then(|set| <[u8; 4]>::try_from(set).ok()).context("Bad share set")?;
+        let threshold: u8 = threshold.parse().context("Bad share threshold")?;
+        let index: u8 = index.parse().context("Bad share number")?;
+        if index == 0 || threshold < 2 {
+            anyhow::bail!("Not a passphrase share");
+        }
+        Ok(Self { set, threshold, index, data: unhex(data).context("Bad share data")? })
+    }
+
+    /// The encoded share as a QR code of block characters, dark on light
+    /// as printed
+    pub fn qr_code(&self) -> Result<String> {
+        let code = QrCode::new(self.encode().as_bytes()).context("Failed to make a QR code of the share")?;
+        Ok(code.render::<unicode::Dense1x2>().build())
+    }
+
+    /// Printable page for one share, saying what it is and how to use it
+    pub fn document(&self, count: u8, hostname: &str, created: DateTime<Local>) -> Result<String> {
+        Ok(format!(
+            "Backup passphrase share {} of {} for {}\n\
+             Created {}, set {}. Any {} shares of this set recover the passphrase;\n\
+             fewer reveal nothing about it. Keep each share in a different place.\n\
+             \n\
+             Recover with: backup-ui shares combine <share files>\n\
+             \n\
+             {}\n\
+             \n\
+             {}\n",
+            self.index,
+            count,
+            hostname,
+            created.format("%Y-%m-%d %H:%M"),
+            hex(&self.set),
+            self.threshold,
+            self.encode(),
+            self.qr_code()?,
+        ))
+    }
+
+    /// The share in a file written by `document`, or holding just the
+    /// encoded line, such as one scanned from the QR code
+    pub fn read_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let line = text
+            .lines()
+            .find(|line| line.trim().to_uppercase().starts_with(SHARE_PREFIX))
+            .with_context(|| format!("No passphrase share in {}", path.display()))?;
+        Self::parse(line).with_context(|| format!("Failed to read the share in {}", path.display()))
+    }
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which recover it
+pub fn split(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Share>> {
+    if threshold < 2 {
+        anyhow::bail!("The threshold must be at least 2; one share would be a copy of the passphrase");
+    }
+    if count < threshold {
+        anyhow::bail!("Cannot need {} shares out of only {}", threshold, count);
+    }
+    if secret.is_empty() {
+        anyhow::bail!("The passphrase is empty");
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut set = [0u8; 4];
+    rng.fill_bytes(&mut set);
+    let mut shares: Vec<Share> = (1..=count)
+        .map(|index| Share { set, threshold, index, data: Vec::with_capacity(secret.len()) })
+        .collect();
+
+    // A random polynomial of degree threshold - 1 per byte, whose value
+    // at 0 is the byte
+    let mut coefficients = vec![0u8; usize::from(threshold)];
+    for &byte in secret {
+        coefficients[0] = byte;
+        rng.fill_bytes(&mut coefficients[1..]);
+        for share in &mut shares {
+            let y = coefficients.iter().rev().fold(0, |y, &c| gf_mul(y, share.index) ^ c);
+            share.data.push(y);
+        }
+    }
+    coefficients.zeroize();
+    Ok(shares)
+}
+
+/// The passphrase from at least `threshold` shares of one set
+pub fn combine(shares: &[Share]) -> Result<SecurePassword> {
+    let first = shares.first().context("No shares given")?;
+    if shares.iter().any(|share| share.set != first.set) {
+        anyhow::bail!("The shares come from different splits; use shares of set {} only", hex(&first.set));
+    }
+    if shares.iter().any(|share| share.data.len() != first.data.len() || share.threshold != first.threshold) {
+        anyhow::bail!("The shares don't match each other; one may be damaged");
+    }
+    let mut distinct: Vec<&Share> = Vec::new();
+    for share in shares {
+        if !distinct.iter().any(|other| other.index == share.index) {
+            distinct.push(share);
+        }
+    }
+    let needed = usize::from(first.threshold);
+    if distinct.len() < needed {
+        anyhow::bail!("{} different shares are needed, {} given", needed, distinct.len());
+    }
+    let used = &distinct[..needed];
+
+    // Lagrange interpolation at 0
+    let weights: Vec<u8> = used
+        .iter()
+        .map(|share| {
+            used.iter().filter(|other| other.index != share.index).fold(1, |weight, other| {
+                gf_mul(weight, gf_div(other.index, other.index ^ share.index))
+            })
+        })
+        .collect();
+    let secret = (0..first.data.len())
+        .map(|i| used.iter().zip(&weights).fold(0, |byte, (share, &weight)| byte ^ gf_mul(share.data[i], weight)))
+        .collect();
+    Ok(SecurePassword::from_bytes(secret))
+}
+
+/// Multiplication in GF(256) with the AES polynomial, without lookup
+/// tables that would leak through the cache
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// `a / b` in GF(256); `b` is never 0 since share indexes are distinct
+fn gf_div(a: u8, b: u8) -> u8 {
+    // b^254 is b's inverse
+    let mut inverse = 1;
+    for _ in 0..254 {
+        inverse = gf_mul(inverse, b);
+    }
+    gf_mul(a, inverse)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn unhex(text: &str) -> Result<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        anyhow::bail!("Odd number of hex digits");
+    }
+    // Pairs of bytes, so a pasted non-ASCII character is an error rather
+    // than a slice through the middle of it
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digit = |byte: u8| char::from(byte).to_digit(16).context("Not a hex digit");
+            Ok((digit(pair[0])? * 16 + digit(pair[1])?) as u8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_combine_passphrase() {
+        let secret = b"correct horse battery staple";
+        let shares = split(secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        // Any three, in any order, after a round trip through the text form
+        let picked: Vec<Share> = [4, 0, 2].iter().map(|&i| Share::parse(&shares[i].encode()).unwrap()).collect();
+        assert_eq!(combine(&picked).unwrap().as_bytes(), secret);
+        assert_eq!(combine(&shares).unwrap().as_bytes(), secret);
+
+        // Two aren't enough, and the same share twice counts once
+        assert!(combine(&shares[..2]).is_err());
+        assert!(combine(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).is_err());
+
+        // Shares of another split of the same passphrase don't mix
+        let other = split(secret, 3, 5).unwrap();
+        assert!(combine(&[shares[0].clone(), shares[1].clone(), other[2].clone()]).is_err());
+
+        // A mistyped digit is caught by the check
+        let mut typo = shares[1].encode();
+        let at = typo.len() - 12;
+        let digit = if &typo[at..at + 1] == "0" { "1" } else { "0" };
+        typo.replace_range(at..at + 1, digit);
+        assert!(Share::parse(&typo).is_err());
+        assert!(unhex("aéb").is_err());
+        assert_eq!(unhex("0aFF").unwrap(), vec![0x0a, 0xff]);
+
+        assert!(split(secret, 1, 3).is_err());
+        assert!(split(secret, 4, 3).is_err());
+        assert!(shares[0].qr_code().unwrap().contains('█'));
+    }
+}
@@ -1,50 +1,71 @@
 use anyhow::{Context, Result};
 use rand::RngCore;
 use sha2::{Digest, Sha256};
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::ptr::NonNull;
+use std::sync::Once;
 use zeroize::Zeroize;
 
-/// Secure password container that automatically clears memory
-#[derive(Clone)]
+use crate::core::secret_sharing::{self, Share};
+
+/// Secure password container that automatically clears memory. The bytes
+/// are kept in locked memory (see `LockedBytes`) so they can't be written
+/// to swap, or on the heap when the system won't lock any more memory.
 pub struct SecurePassword {
-    password: Vec<u8>,
+    password: Storage,
+}
+
+enum Storage {
+    Locked(LockedBytes),
+    Heap(Vec<u8>),
 }
 
 impl Drop for SecurePassword {
     fn drop(&mut self) {
-        self.password.zeroize();
+        self.wipe();
+    }
+}
+
+impl Clone for SecurePassword {
+    fn clone(&self) -> Self {
+        Self::from_bytes(self.as_bytes().to_vec())
     }
 }
 
 impl SecurePassword {
     pub fn new(password: String) -> Self {
-        Self {
-            password: password.into_bytes(),
-        }
+        Self::from_bytes(password.into_bytes())
     }
 
     pub fn from_bytes(mut password: Vec<u8>) -> Self {
-        let result = Self { password: password.clone() };
+        let storage = match LockedBytes::new(&password) {
+            Some(locked) => Storage::Locked(locked),
+            None => Storage::Heap(password.clone()),
+        };
         password.zeroize(); // Clear the original
-        result
+        Self { password: storage }
     }
 
     pub fn as_bytes(&self) -> &[u8] {
-        &self.password
+        match &self.password {
+            Storage::Locked(locked) => locked.as_bytes(),
+            Storage::Heap(bytes) => bytes,
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.password.len()
+        self.as_bytes().len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.password.is_empty()
+        self.as_bytes().is_empty()
     }
 
     /// Generate password hash for verification
     pub fn hash(&self) -> Vec<u8> {
         let mut hasher = Sha256::new();
-        hasher.update(&self.password);
+        hasher.update(self.as_bytes());
         hasher.finalize().to_vec()
     }
 
@@ -53,11 +74,79 @@ impl SecurePassword {
         let computed_hash = self.hash();
         computed_hash == hash
     }
+
+    /// Overwrite the bytes with zeroes, in place, before they are freed
+    fn wipe(&mut self) {
+        match &mut self.password {
+            Storage::Locked(locked) => locked.wipe(),
+            Storage::Heap(bytes) => bytes.zeroize(),
+        }
+    }
 }
 
 impl std::fmt::Debug for SecurePassword {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "SecurePassword[*** {} bytes ***]", self.password.len())
+        write!(f, "SecurePassword[*** {} bytes ***]", self.len())
+    }
+}
+
+/// Secret bytes on their own pages, locked in RAM (`mlock`) and left out
+/// of core dumps, between inaccessible guard pages and read-only once
+/// written, so an overrun elsewhere can't read or change them
+struct LockedBytes {
+    memory: NonNull<[u8]>,
+}
+
+// Read-only after `new`, so sharing it between threads is safe
+unsafe impl Send for LockedBytes {}
+unsafe impl Sync for LockedBytes {}
+
+/// Warn about unlockable memory once, not for every password
+static LOCK_WARNING: Once = Once::new();
+
+impl LockedBytes {
+    /// `None` for nothing to protect, or when the memory can't be locked,
+    /// usually because RLIMIT_MEMLOCK (`ulimit -l`) is used up
+    fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            return None;
+        }
+        unsafe {
+            let memory = memsec::malloc_sized(bytes.len())?;
+            let start = memory.as_ptr() as *mut u8;
+            // malloc_sized tries to lock but doesn't say whether it could
+            if !memsec::mlock(start, bytes.len()) {
+                memsec::free(memory);
+                LOCK_WARNING.call_once(|| {
+                    log::warn!("Could not lock memory for passphrases (raise `ulimit -l`); they may be written to swap");
+                });
+                return None;
+            }
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), start, bytes.len());
+            memsec::mprotect(memory, memsec::Prot::ReadOnly);
+            Some(Self { memory })
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { self.memory.as_ref() }
+    }
+
+    /// Zero the bytes, leaving the pages writable
+    fn wipe(&mut self) {
+        unsafe {
+            memsec::mprotect(self.memory, memsec::Prot::ReadWrite);
+            memsec::memzero(self.memory.as_ptr() as *mut u8, self.memory.len());
+        }
+    }
+}
+
+impl Drop for LockedBytes {
+    fn drop(&mut self) {
+        self.wipe();
+        unsafe {
+            memsec::free(self.memory);
+        }
     }
 }
 
@@ -74,6 +163,101 @@ pub fn read_password(prompt: &str) -> Result<SecurePassword> {
     Ok(SecurePassword::new(password))
 }
 
+/// Read a password on the controlling terminal, for commands whose stdin
+/// or stdout carries an archive
+pub fn read_password_from_terminal(prompt: &str) -> Result<SecurePassword> {
+    let password = rpassword::read_password_from_tty(Some(prompt))
+        .context("No terminal is available to ask for the passphrase")?;
+    Ok(SecurePassword::new(password))
+}
+
+/// Environment variable unattended runs can pass the passphrase in
+pub const PASSPHRASE_ENV: &str = "BACKUP_PASSPHRASE";
+
+/// Where a command without a terminal gets its passphrase
+#[derive(Clone, Default)]
+pub enum PassphraseSource {
+    /// Nothing given: use the keyring or ask on the terminal
+    #[default]
+    Prompt,
+    /// First line of a file, from `--password-file`
+    File(PathBuf),
+    /// First line of stdin, from `--password-stdin`
+    Stdin,
+    /// Taken from `BACKUP_PASSPHRASE`
+    Env(SecurePassword),
+    /// Combined from Shamir shares, from `--password-share`
+    Shares(Vec<PathBuf>),
+}
+
+impl PassphraseSource {
+    /// From the command line, falling back to `BACKUP_PASSPHRASE`. The
+    /// variable is always removed from the environment so gpg, tar and
+    /// item commands don't inherit it; call before starting any of them,
+    /// and before any other thread, as changing the environment while
+    /// another thread may read it is unsound.
+    pub fn from_args(file: Option<PathBuf>, stdin: bool, shares: Vec<PathBuf>) -> Self {
+        let env = std::env::var_os(PASSPHRASE_ENV);
+        std::env::remove_var(PASSPHRASE_ENV);
+        match (file, stdin, env) {
+            _ if !shares.is_empty() => Self::Shares(shares),
+            (Some(path), _, _) => Self::File(path),
+            (None, true, _) => Self::Stdin,
+            (None, false, Some(env)) if !env.is_empty() => {
+                Self::Env(SecurePassword::from_bytes(env.into_encoded_bytes()))
+            }
+            _ => Self::Prompt,
+        }
+    }
+
+    /// The passphrase, or `None` when it is to be asked for. Blocking when
+    /// it comes from stdin.
+    pub fn read(&self) -> Result<Option<SecurePassword>> {
+        match self {
+            Self::Prompt => Ok(None),
+            Self::Env(password) => Ok(Some(password.clone())),
+            Self::File(path) => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = std::fs::metadata(path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?
+                        .permissions()
+                        .mode();
+                    if mode & 0o077 != 0 {
+                        log::warn!("{} can be read by other users; chmod 600 it", path.display());
+                    }
+                }
+                let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                first_line(bytes).with_context(|| format!("No passphrase in {}", path.display())).map(Some)
+            }
+            Self::Shares(paths) => {
+                let shares = paths.iter().map(|path| Share::read_file(path)).collect::<Result<Vec<_>>>()?;
+                secret_sharing::combine(&shares).map(Some)
+            }
+            Self::Stdin => {
+                let mut bytes = Vec::new();
+                io::stdin().lock().read_until(b'\n', &mut bytes).context("Failed to read the passphrase from stdin")?;
+                first_line(bytes).context("No passphrase on stdin").map(Some)
+            }
+        }
+    }
+}
+
+/// The first line of `bytes` without its line ending; the rest is wiped
+fn first_line(mut bytes: Vec<u8>) -> Result<SecurePassword> {
+    let mut end = bytes.iter().position(|&b| b == b'\n').unwrap_or(bytes.len());
+    if end > 0 && bytes[end - 1] == b'\r' {
+        end -= 1;
+    }
+    bytes[end..].zeroize();
+    bytes.truncate(end);
+    if bytes.is_empty() {
+        anyhow::bail!("The passphrase is empty");
+    }
+    Ok(SecurePassword::from_bytes(bytes))
+}
+
 /// Generate secure random bytes
 pub fn generate_random_bytes(len: usize) -> Vec<u8> {
     let mut bytes = vec![0u8; len];
@@ -212,6 +396,48 @@ mod tests {
         
         let hash = password.hash();
         assert!(password.verify_hash(&hash));
+
+        // Locked where `ulimit -l` allows, on the heap otherwise; the
+        // bytes read back the same either way
+        assert_eq!(password.clone().as_bytes(), b"test123");
+        let heap = SecurePassword { password: Storage::Heap(b"abc".to_vec()) };
+        assert_eq!(heap.clone().as_bytes(), b"abc");
+        assert!(SecurePassword::new(String::new()).is_empty());
+    }
+
+    #[test]
+    fn test_passwords_are_zeroed_when_dropped() {
+        let secret = b"correct horse battery staple";
+        let locked = SecurePassword::from_bytes(secret.to_vec());
+        assert!(matches!(locked.password, Storage::Locked(_)), "mlock failed; is `ulimit -l` 0?");
+        let heap = SecurePassword { password: Storage::Heap(secret.to_vec()) };
+
+        for mut password in [locked, heap] {
+            assert_eq!(password.as_bytes(), secret);
+            // The memory is still allocated after the wipe, until the
+            // password itself goes
+            let (start, len) = (password.as_bytes().as_ptr(), password.len());
+            password.wipe();
+            let wiped = unsafe { std::slice::from_raw_parts(start, len) };
+            assert!(wiped.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn test_passphrase_is_taken_out_of_the_environment() {
+        std::env::set_var(PASSPHRASE_ENV, "from the environment");
+        let source = PassphraseSource::from_args(None, false, Vec::new());
+        assert!(std::env::var_os(PASSPHRASE_ENV).is_none());
+        match source {
+            PassphraseSource::Env(password) => assert_eq!(password.as_bytes(), b"from the environment"),
+            _ => panic!("BACKUP_PASSPHRASE was not used"),
+        }
+
+        // Removed even when a file takes precedence
+        std::env::set_var(PASSPHRASE_ENV, "unused");
+        let source = PassphraseSource::from_args(Some(PathBuf::from("/run/passphrase")), false, Vec::new());
+        assert!(std::env::var_os(PASSPHRASE_ENV).is_none());
+        assert!(matches!(source, PassphraseSource::File(_)));
     }
 
     #[test]
@@ -232,4 +458,18 @@ mod tests {
         assert_ne!(bytes1, bytes2);
         assert_eq!(bytes1.len(), 32);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_passphrase_file_first_line() {
+        assert_eq!(first_line(b"s3cret\r\nmore".to_vec()).unwrap().as_bytes(), b"s3cret");
+        assert_eq!(first_line(b"s3cret".to_vec()).unwrap().as_bytes(), b"s3cret");
+        assert!(first_line(b"\nafter".to_vec()).is_err());
+
+        let path = std::env::temp_dir().join(format!("passphrase-test-{}", std::process::id()));
+        std::fs::write(&path, "from file\n").unwrap();
+        let source = PassphraseSource::File(path.clone());
+        assert_eq!(source.read().unwrap().unwrap().as_bytes(), b"from file");
+        assert!(PassphraseSource::Prompt.read().unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
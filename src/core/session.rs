@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use crate::ui::terminal::{format_bytes, format_duration};
+
+/// What one run of the UI did, printed and logged when it exits
+#[derive(Debug, Clone)]
+pub struct SessionStats {
+    started: Instant,
+    pub backups: usize,
+    pub failed_backups: usize,
+    pub archives_created: usize,
+    pub bytes_written: u64,
+    pub restores: usize,
+    pub failed_restores: usize,
+    pub items_restored: usize,
+    pub rollbacks: usize,
+    pub config_saves: usize,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            backups: 0,
+            failed_backups: 0,
+            archives_created: 0,
+            bytes_written: 0,
+            restores: 0,
+            failed_restores: 0,
+            items_restored: 0,
+            rollbacks: 0,
+            config_saves: 0,
+        }
+    }
+}
+
+impl SessionStats {
+    /// A finished backup, with the size of the archive it wrote if any
+    pub fn record_backup(&mut self, archive_size: Option<u64>, ok: bool) {
+        if !ok {
+            self.failed_backups += 1;
+            return;
+        }
+        self.backups += 1;
+        if let Some(size) = archive_size {
+            self.archives_created += 1;
+            self.bytes_written += size;
+        }
+    }
+
+    pub fn record_restore(&mut self, items: usize, ok: bool) {
+        if ok {
+            self.restores += 1;
+            self.items_restored += items;
+        } else {
+            self.failed_restores += 1;
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// One line for the terminal and the log; only what happened is listed
+    pub fn summary(&self, duration: Duration, warnings: usize) -> String {
+        let plural = |count: usize, noun: &str| format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" });
+        let mut parts = Vec::new();
+
+        if self.backups > 0 || self.failed_backups > 0 {
+            let mut backups = plural(self.backups, "backup");
+            if self.failed_backups > 0 {
+                backups.push_str(&format!(" ({} failed)", self.failed_backups));
+            }
+            parts.push(backups);
+        }
+        if self.archives_created > 0 {
+            parts.push(format!(
+                "{} created ({} written)",
+                plural(self.archives_created, "archive"),
+                format_bytes(self.bytes_written)
+            ));
+        }
+        if self.restores > 0 || self.failed_restores > 0 {
+            let mut restores = format!("{} of {}", plural(self.restores, "restore"), plural(self.items_restored, "item"));
+            if self.failed_restores > 0 {
+                restores.push_str(&format!(" ({} failed)", self.failed_restores));
+            }
+            parts.push(restores);
+        }
+        if self.rollbacks > 0 {
+            parts.push(plural(self.rollbacks, "rollback"));
+        }
+        if self.config_saves > 0 {
+            parts.push(plural(self.config_saves, "config save"));
+        }
+        if parts.is_empty() {
+            parts.push("no backups or restores".to_string());
+        }
+        parts.push(plural(warnings, "warning"));
+
+        format!("Session {}: {}", format_duration(duration), parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_summary() {
+        let mut stats = SessionStats::default();
+        assert_eq!(
+            stats.summary(Duration::from_secs(42), 0),
+            "Session 42s: no backups or restores, 0 warnings"
+        );
+
+        stats.record_backup(Some(2 * 1024 * 1024), true);
+        stats.record_backup(None, false);
+        stats.record_restore(3, true);
+        stats.rollbacks += 1;
+        assert_eq!(
+            stats.summary(Duration::from_secs(95), 1),
+            "Session 1m 35s: 1 backup (1 failed), 1 archive created (2.0 MB written), 1 restore of 3 items, 1 rollback, 1 warning"
+        );
+    }
+}
@@ -1,16 +1,52 @@
 use crate::core::types::{
-    ArchiveInfo, BackupItem, BackupMode, BackupProgress, RestoreItem, RestoreProgress,
-    ValidationResult,
+    ArchiveInfo, ArchiveSort, BackupItem, CatalogMatch, DestinationResult, CheckResult, Compression, FileVersions, HostSummary, ItemReport, BackupMode, BackupProgress, PrivilegedEntry, RestoreItem, RestoreMetadata,
+    RestoreProgress, SearchHit, ValidationResult,
 };
+use crate::backend::container_volumes::VolumeChoice;
+use crate::backend::credential_checks::CredentialKind;
+use crate::backend::database_dumps::DatabaseConfig;
+use crate::backend::destination::Destination;
+use crate::backend::destination_trust::DestinationChange;
+use crate::backend::first_estimate::FirstBackupEstimate;
+use crate::backend::gpg_keys::GpgKey;
+use crate::backend::live_changes::LiveChanges;
+use crate::backend::removable::RemovableDrive;
+use crate::backend::report::OperationReport;
+use crate::backend::restore_plan::RestorePlan;
+use crate::backend::restore_snapshot::PreRestoreSnapshot;
+use crate::backend::secret_scan::SecretFinding;
+use crate::core::audit::{AuditEvent, ChainStatus};
+use crate::core::config::{CriticalGap, ProfileConfig, ThrottleConfig};
+use crate::core::config_form::ConfigForm;
+use crate::core::config_history::ConfigSnapshot;
+use crate::core::diff::DiffLine;
+use crate::core::errors::AppError;
+use crate::core::exclusions::MatchCount;
+use crate::core::first_run::WizardCategory;
+use crate::core::fuzzy;
+use crate::core::item_tree::{self, ItemSort, TreeRow};
+use crate::core::keymap::Keymap;
 use crate::core::security::SecurePassword;
+use crate::core::strategies::StrategyStatus;
+use crate::core::undo::UndoStack;
+use crate::ui::theme::Theme;
+use crate::ui::widgets::FileBrowser;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppState {
     MainMenu,
+    ProfileSelection,
     BackupModeSelection,
     BackupItemSelection,
+    DestinationSelection,
+    Preflight,
+    SecretReview,
+    RecipientSelection,
+    VolumeSelection,
     BackupPasswordInput,
+    BackupConfirm,
     BackupProgress,
     BackupComplete,
     RestoreArchiveSelection,
@@ -18,30 +54,276 @@ pub enum AppState {
     RestoreItemSelection,
     RestoreProgress,
     RestoreComplete,
+    CatalogBrowser,
+    FindFile,
+    FileHistory,
+    ConfigEditor,
+    ConfigHistory,
+    ExclusionEditor,
+    FleetOverview,
+    AuditHistory,
+    FirstRunWizard,
+    FirstBackupGuide,
     Help,
-    Error(String),
+    Error(AppError),
     Exit,
 }
 
+/// Stage of the backup flow, shown as a step indicator in the header of
+/// each backup screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BackupStep {
+    Mode,
+    Items,
+    Password,
+    Confirm,
+    Progress,
+}
+
+impl BackupStep {
+    pub const ALL: [BackupStep; 5] =
+        [BackupStep::Mode, BackupStep::Items, BackupStep::Password, BackupStep::Confirm, BackupStep::Progress];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BackupStep::Mode => "Mode",
+            BackupStep::Items => "Items",
+            BackupStep::Password => "Password",
+            BackupStep::Confirm => "Confirm",
+            BackupStep::Progress => "Progress",
+        }
+    }
+}
+
+impl AppState {
+    /// Step of the backup flow this screen belongs to, if any
+    pub fn backup_step(&self) -> Option<BackupStep> {
+        match self {
+            AppState::BackupModeSelection => Some(BackupStep::Mode),
+            AppState::BackupItemSelection
+            | AppState::DestinationSelection
+            | AppState::Preflight
+            | AppState::SecretReview
+            | AppState::VolumeSelection => Some(BackupStep::Items),
+            AppState::RecipientSelection | AppState::BackupPasswordInput => Some(BackupStep::Password),
+            AppState::BackupConfirm => Some(BackupStep::Confirm),
+            AppState::BackupProgress | AppState::BackupComplete => Some(BackupStep::Progress),
+            _ => None,
+        }
+    }
+}
+
+/// Operation the error screen can run again with the same selections,
+/// destination and password once the cause is fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOperation {
+    Backup,
+    Restore,
+    ListArchives,
+}
+
+impl RetryOperation {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            RetryOperation::Backup => "the backup",
+            RetryOperation::Restore => "the restore",
+            RetryOperation::ListArchives => "listing the archives",
+        }
+    }
+}
+
+/// How many of the largest selected items the item selection screen lists
+pub const TOP_CONSUMERS: usize = 5;
+
+/// What the status bar along the bottom of every screen shows besides the
+/// active profile, refreshed once per frame
+#[derive(Debug, Clone, Default)]
+pub struct StatusBar {
+    /// Work running in the background, such as the size scan
+    pub activities: Vec<String>,
+    /// Free space where the next backup will be written, checked now and then
+    pub free_space: Option<u64>,
+    pub clock: String,
+}
+
 #[derive(Debug)]
 pub struct AppStateManager {
     pub current_state: AppState,
     pub previous_state: Option<AppState>,
     
     // Backup state
+    /// Profiles offered before mode selection, sorted by name
+    pub profile_choices: Vec<(String, ProfileConfig)>,
+    /// Profile the current backup was started from
+    pub active_profile: Option<String>,
+    /// Strategy the current backup was started from on the main menu
+    pub active_strategy: Option<String>,
+    /// Configured backup strategies and their last runs, for the main menu
+    pub strategy_statuses: Vec<StrategyStatus>,
     pub backup_mode: BackupMode,
+    pub backup_compression: Compression,
+    pub backup_compression_level: u32,
     pub backup_items: Vec<BackupItem>,
+    /// Items whose size is still being calculated in the background
+    pub backup_sizes_pending: usize,
     pub backup_password: Option<SecurePassword>,
+    /// Keys offered for encrypting a complete backup, and whether each is
+    /// ticked
+    pub recipient_choices: Vec<(GpgKey, bool)>,
+    /// Fingerprints the archive is encrypted to, instead of a passphrase
+    pub backup_recipients: Vec<String>,
+    /// Container volumes offered on the volume screen, and whether each is
+    /// ticked
+    pub volume_choices: Vec<(VolumeChoice, bool)>,
+    /// Pause the containers using a volume while it is backed up
+    pub volume_pause: bool,
+    /// Backup item the volume screen picks volumes for; None when it
+    /// restores them from the selected archive
+    pub volume_item: Option<usize>,
     pub backup_progress: Option<BackupProgress>,
-    pub backup_output_path: Option<PathBuf>,
+    pub backup_destination: Option<Destination>,
+    /// Profile destinations that get a copy of the archive as well
+    pub backup_copies: Vec<Destination>,
+    /// Niceness, IO class and rate cap for the backup and its copies
+    pub backup_throttle: ThrottleConfig,
+    /// Outcome at each destination of a backup with copies
+    pub destination_results: Vec<DestinationResult>,
+    /// What became of each selected item in the last backup
+    pub item_reports: Vec<ItemReport>,
+    /// Where `item_reports` were saved, once they are
+    pub item_report_path: Option<PathBuf>,
+    /// Show only the items not backed up in full
+    pub item_failures_only: bool,
+    /// Record of the backup or restore just finished, for saving from its
+    /// completion screen
+    pub completion_report: Option<OperationReport>,
+    /// Destination that no longer looks like the one trusted before,
+    /// holding up a complete backup until the user decides
+    pub destination_change: Option<DestinationChange>,
+    /// Critical items this backup would leave out, waiting for the user to
+    /// acknowledge them or go back and select them
+    pub critical_gaps: Option<Vec<CriticalGap>>,
+    /// Removable drives offered as the destination
+    pub removable_drives: Vec<RemovableDrive>,
+    /// Drive without room for the estimated backup, chosen once and waiting
+    /// for a second confirmation
+    pub confirm_low_space: Option<usize>,
+    /// Screen item selection goes back to, kept while a drive is picked
+    /// or pre-flight problems are shown
+    pub item_selection_previous: Option<AppState>,
+    /// Problems found before the backup started, for the user to fix or
+    /// accept
+    pub preflight_checks: Vec<CheckResult>,
+    /// Files that look like they hold credentials, ticked to leave them
+    /// out of a secure-mode backup
+    pub secret_findings: Vec<(SecretFinding, bool)>,
+    /// Files left out of this backup from the secrets review
+    pub secret_exclusions: Vec<PathBuf>,
     
     // Restore state
     pub available_archives: Vec<ArchiveInfo>,
+    /// Parity of each local archive that has any, described for its details
+    pub archive_parity: HashMap<PathBuf, String>,
     pub selected_archive: Option<ArchiveInfo>,
     pub restore_password: Option<SecurePassword>,
     pub restore_items: Vec<RestoreItem>,
     pub restore_progress: Option<RestoreProgress>,
+    pub confirm_privileged_restore: bool,
+    pub strip_privileged_bits: bool,
+    /// Whether the restore puts back the archive's owners, ACLs and
+    /// extended attributes
+    pub restore_metadata: RestoreMetadata,
+    pub restored_credentials: Vec<CredentialKind>,
+    pub credential_checks: Vec<CheckResult>,
+    /// Sensitive files the restore put back with modes that were too open,
+    /// as tightened
+    pub permission_fixes: Vec<CheckResult>,
+    /// Single file chosen from the Find file screen to restore on its own
+    pub restore_target_file: Option<CatalogMatch>,
+    /// On-disk vs archived diff of the highlighted conflicting file, or why
+    /// it couldn't be produced
+    pub restore_diff: Option<Result<Vec<DiffLine>, String>>,
+    pub restore_diff_scroll: u16,
+    /// What changed on disk under the selected archive's items since it
+    /// was made, or why that couldn't be worked out
+    pub live_changes: Option<Result<LiveChanges, String>>,
+    pub live_changes_scroll: u16,
+    /// Whether starting the restore only simulates it
+    pub restore_dry_run: bool,
+    /// What the simulated restore would write, or why it couldn't be
+    /// worked out
+    pub restore_plan: Option<Result<RestorePlan, String>>,
+    pub restore_plan_scroll: u16,
+    /// Copy of what the last restore overwrote, for rolling it back
+    pub pre_restore_snapshot: Option<PreRestoreSnapshot>,
+    pub confirm_restore_rollback: bool,
+    /// Databases the selected archive holds dumps of, while their replay
+    /// waits to be confirmed
+    pub database_replay: Option<Vec<DatabaseConfig>>,
+    pub restore_rolled_back: bool,
+    /// SSH destination being typed for a restore onto another machine
+    pub remote_restore_host: Option<String>,
+    pub remote_restore_result: Option<Result<String, String>>,
+    /// Destination being typed to extract the highlighted item straight
+    /// out of the archive
+    pub extract_item_dest: Option<String>,
+    pub extract_item_result: Option<Result<String, String>>,
+    /// Outcome of re-enabling the services recorded in the restored archive
+    pub service_results: Vec<CheckResult>,
+    
+    // Catalog state
+    pub catalog_query: String,
+    pub catalog_results: Vec<SearchHit>,
+    pub find_query: String,
+    pub find_results: Vec<FileVersions>,
+    pub find_version_index: usize,
+    /// File whose versions the history view lists
+    pub file_history: Option<FileVersions>,
+    pub fleet_hosts: Vec<HostSummary>,
+    /// Audit log entries, newest first, and whether their chain holds
+    pub audit_entries: Vec<AuditEvent>,
+    pub audit_chain: Option<ChainStatus>,
+
+    // First-run wizard state
+    pub wizard_categories: Vec<WizardCategory>,
+    /// Where the wizard will write the starter config
+    pub wizard_config_path: PathBuf,
+    /// Time and space a first backup needs, once measured
+    pub first_estimate: Option<FirstBackupEstimate>,
+    
+    // Config editor state
+    pub config_form: Option<ConfigForm>,
+    /// Text being typed into the selected field, while editing
+    pub config_edit_buffer: Option<String>,
+    pub config_confirm_discard: bool,
+    pub config_snapshots: Vec<ConfigSnapshot>,
+    /// Changes between the selected snapshot and the current config
+    pub config_diff: Vec<DiffLine>,
+    pub config_diff_scroll: u16,
+    pub config_confirm_rollback: bool,
     
+    // Exclusion editor state
+    pub exclusion_mode: BackupMode,
+    pub exclusion_patterns: Vec<String>,
+    /// Files and bytes each pattern excludes, None while still counting
+    pub exclusion_counts: Option<Vec<MatchCount>>,
+    /// Pattern being typed, while adding one
+    pub exclusion_input: Option<String>,
+    pub exclusions_dirty: bool,
+    
+    /// Keys of the item selection tree's collapsed groups
+    pub collapsed_groups: HashSet<String>,
+    /// Fuzzy filter on the backup or restore item list, and whether it is
+    /// being typed
+    pub item_filter: String,
+    pub item_filter_input: bool,
+    pub item_sort: ItemSort,
+    pub archive_sort: ArchiveSort,
+    /// Browser picking a path to add to the backup
+    pub file_browser: Option<FileBrowser>,
+    /// Path picked in the browser, waiting on whether to keep it in the config
+    pub custom_path_pending: Option<PathBuf>,
+
     // UI state
     pub selected_item_index: usize,
     pub scroll_offset: usize,
@@ -49,6 +331,17 @@ pub struct AppStateManager {
     pub validation_result: Option<ValidationResult>,
     pub status_message: Option<String>,
     pub error_message: Option<String>,
+    /// Whether the error screen shows the error's full chain of causes
+    pub error_details: bool,
+    /// What the error screen's retry runs, when the error allows it
+    pub retry: Option<RetryOperation>,
+    /// Key bindings shared by the key handlers and the footers
+    pub keymap: Keymap,
+    /// Status colors and tags, from the config's `ui` section
+    pub theme: Theme,
+    /// Earlier selections of the list on the current selection screen
+    pub selection_undo: UndoStack<Vec<bool>>,
+    pub status_bar: StatusBar,
 }
 
 impl Default for AppStateManager {
@@ -56,22 +349,110 @@ impl Default for AppStateManager {
         Self {
             current_state: AppState::MainMenu,
             previous_state: None,
+            profile_choices: Vec::new(),
+            active_profile: None,
+            active_strategy: None,
+            strategy_statuses: Vec::new(),
             backup_mode: BackupMode::Secure,
+            backup_compression: Compression::Gzip,
+            backup_compression_level: Compression::Gzip.default_level(),
             backup_items: Vec::new(),
+            backup_sizes_pending: 0,
             backup_password: None,
+            recipient_choices: Vec::new(),
+            backup_recipients: Vec::new(),
+            volume_choices: Vec::new(),
+            volume_pause: false,
+            volume_item: None,
             backup_progress: None,
-            backup_output_path: None,
+            backup_destination: None,
+            backup_copies: Vec::new(),
+            backup_throttle: ThrottleConfig::default(),
+            destination_results: Vec::new(),
+            item_reports: Vec::new(),
+            item_report_path: None,
+            item_failures_only: false,
+            completion_report: None,
+            destination_change: None,
+            critical_gaps: None,
+            removable_drives: Vec::new(),
+            confirm_low_space: None,
+            preflight_checks: Vec::new(),
+            secret_findings: Vec::new(),
+            secret_exclusions: Vec::new(),
+            item_selection_previous: None,
             available_archives: Vec::new(),
+            archive_parity: HashMap::new(),
             selected_archive: None,
             restore_password: None,
             restore_items: Vec::new(),
             restore_progress: None,
+            confirm_privileged_restore: false,
+            strip_privileged_bits: false,
+            restore_metadata: RestoreMetadata::Apply,
+            restored_credentials: Vec::new(),
+            credential_checks: Vec::new(),
+            permission_fixes: Vec::new(),
+            restore_target_file: None,
+            restore_diff: None,
+            restore_diff_scroll: 0,
+            live_changes: None,
+            live_changes_scroll: 0,
+            restore_dry_run: false,
+            restore_plan: None,
+            restore_plan_scroll: 0,
+            pre_restore_snapshot: None,
+            confirm_restore_rollback: false,
+            database_replay: None,
+            restore_rolled_back: false,
+            remote_restore_host: None,
+            remote_restore_result: None,
+            extract_item_dest: None,
+            extract_item_result: None,
+            service_results: Vec::new(),
+            catalog_query: String::new(),
+            catalog_results: Vec::new(),
+            find_query: String::new(),
+            find_results: Vec::new(),
+            find_version_index: 0,
+            file_history: None,
+            fleet_hosts: Vec::new(),
+            audit_entries: Vec::new(),
+            audit_chain: None,
+            wizard_categories: Vec::new(),
+            wizard_config_path: PathBuf::new(),
+            first_estimate: None,
+            config_form: None,
+            config_edit_buffer: None,
+            config_confirm_discard: false,
+            config_snapshots: Vec::new(),
+            config_diff: Vec::new(),
+            config_diff_scroll: 0,
+            config_confirm_rollback: false,
+            exclusion_mode: BackupMode::Secure,
+            exclusion_patterns: Vec::new(),
+            exclusion_counts: None,
+            exclusion_input: None,
+            exclusions_dirty: false,
+            collapsed_groups: HashSet::new(),
+            item_filter: String::new(),
+            item_filter_input: false,
+            item_sort: ItemSort::default(),
+            archive_sort: ArchiveSort::default(),
+            file_browser: None,
+            custom_path_pending: None,
             selected_item_index: 0,
             scroll_offset: 0,
             show_help: false,
             validation_result: None,
             status_message: None,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            selection_undo: UndoStack::default(),
+            status_bar: StatusBar::default(),
             error_message: None,
+            error_details: false,
+            retry: None,
         }
     }
 }
@@ -88,6 +469,8 @@ impl AppStateManager {
         // Reset UI state on transitions
         self.selected_item_index = 0;
         self.scroll_offset = 0;
+        self.item_filter.clear();
+        self.item_filter_input = false;
         self.error_message = None;
     }
 
@@ -96,14 +479,31 @@ impl AppStateManager {
             self.current_state = previous;
             self.selected_item_index = 0;
             self.scroll_offset = 0;
+            self.item_filter.clear();
+            self.item_filter_input = false;
             self.error_message = None;
         }
     }
 
     pub fn reset_backup_state(&mut self) {
         self.backup_items.clear();
+        self.selection_undo.clear();
+        self.active_profile = None;
+        self.active_strategy = None;
+        self.destination_change = None;
+        self.critical_gaps = None;
+        self.preflight_checks.clear();
+        self.secret_findings.clear();
+        self.secret_exclusions.clear();
         self.backup_password = None;
+        self.recipient_choices.clear();
+        self.backup_recipients.clear();
         self.backup_progress = None;
+        self.destination_results.clear();
+        self.item_reports.clear();
+        self.item_report_path = None;
+        self.item_failures_only = false;
+        self.completion_report = None;
         self.validation_result = None;
     }
 
@@ -111,14 +511,49 @@ impl AppStateManager {
         self.selected_archive = None;
         self.restore_password = None;
         self.restore_items.clear();
+        self.selection_undo.clear();
         self.restore_progress = None;
+        self.confirm_privileged_restore = false;
+        self.strip_privileged_bits = false;
+        self.restore_metadata = RestoreMetadata::Apply;
+        self.restored_credentials.clear();
+        self.credential_checks.clear();
+        self.permission_fixes.clear();
+        self.restore_target_file = None;
+        self.restore_diff = None;
+        self.restore_diff_scroll = 0;
+        self.live_changes = None;
+        self.live_changes_scroll = 0;
+        self.restore_dry_run = false;
+        self.restore_plan = None;
+        self.restore_plan_scroll = 0;
+        self.pre_restore_snapshot = None;
+        self.confirm_restore_rollback = false;
+        self.database_replay = None;
+        self.restore_rolled_back = false;
+        self.remote_restore_host = None;
+        self.remote_restore_result = None;
+        self.extract_item_dest = None;
+        self.extract_item_result = None;
+        self.service_results.clear();
+        self.completion_report = None;
     }
 
-    pub fn set_error(&mut self, error: String) {
-        self.error_message = Some(error.clone());
+    pub fn set_error(&mut self, error: AppError) {
+        self.error_message = Some(error.message.clone());
+        self.retry = None;
+        self.error_details = false;
+        self.status_message = None;
         self.transition_to(AppState::Error(error));
     }
 
+    /// Show an error the user may fix outside the app, such as an unplugged
+    /// drive, and then retry `operation` from the error screen
+    pub fn set_retryable_error(&mut self, error: AppError, operation: RetryOperation) {
+        self.set_error(error);
+        self.retry = Some(operation);
+    }
+
     pub fn set_status(&mut self, message: String) {
         self.status_message = Some(message);
     }
@@ -136,26 +571,107 @@ impl AppStateManager {
     }
 
     pub fn toggle_backup_item(&mut self, index: usize) {
-        if let Some(item) = self.backup_items.get_mut(index) {
-            item.selected = !item.selected;
-        }
+        self.change_selection(|state| {
+            if let Some(item) = state.backup_items.get_mut(index) {
+                item.selected = !item.selected;
+            }
+        });
+    }
+
+    /// Tick every item in a group, or untick them all when all are ticked
+    pub fn toggle_backup_group(&mut self, indices: &[usize]) {
+        self.change_selection(|state| {
+            let select = !indices.iter().all(|&index| state.backup_items.get(index).is_some_and(|item| item.selected));
+            for &index in indices {
+                if let Some(item) = state.backup_items.get_mut(index) {
+                    item.selected = select;
+                }
+            }
+        });
     }
 
     pub fn toggle_restore_item(&mut self, index: usize) {
-        if let Some(item) = self.restore_items.get_mut(index) {
-            item.selected = !item.selected;
-        }
+        self.change_selection(|state| {
+            if let Some(item) = state.restore_items.get_mut(index) {
+                item.selected = !item.selected;
+            }
+        });
     }
 
+    /// Tick or untick every item the filter keeps
     pub fn select_all_backup_items(&mut self, select: bool) {
-        for item in &mut self.backup_items {
-            item.selected = select;
-        }
+        self.change_selection(|state| {
+            for item in state.backup_items.iter_mut().filter(|item| item_tree::matches(item, &state.item_filter)) {
+                item.selected = select;
+            }
+        });
     }
 
+    /// Tick or untick every item the filter keeps
     pub fn select_all_restore_items(&mut self, select: bool) {
-        for item in &mut self.restore_items {
-            item.selected = select;
+        self.change_selection(|state| {
+            for index in state.visible_restore_items() {
+                state.restore_items[index].selected = select;
+            }
+        });
+    }
+
+    /// Selected flags of the list on the current screen
+    fn selection(&self) -> Vec<bool> {
+        match self.current_state {
+            AppState::BackupItemSelection => self.backup_items.iter().map(|item| item.selected).collect(),
+            AppState::RestoreItemSelection => self.restore_items.iter().map(|item| item.selected).collect(),
+            AppState::FirstRunWizard => self.wizard_categories.iter().map(|category| category.selected).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn set_selection(&mut self, selection: &[bool]) {
+        let flags: Vec<&mut bool> = match self.current_state {
+            AppState::BackupItemSelection => self.backup_items.iter_mut().map(|item| &mut item.selected).collect(),
+            AppState::RestoreItemSelection => self.restore_items.iter_mut().map(|item| &mut item.selected).collect(),
+            AppState::FirstRunWizard => self.wizard_categories.iter_mut().map(|category| &mut category.selected).collect(),
+            _ => Vec::new(),
+        };
+        // A snapshot of some other list is never applied
+        if flags.len() != selection.len() {
+            return;
+        }
+        for (flag, selected) in flags.into_iter().zip(selection) {
+            *flag = *selected;
+        }
+    }
+
+    /// Apply `change` to the current screen's selection so it can be undone
+    pub fn change_selection(&mut self, change: impl FnOnce(&mut Self)) {
+        let before = self.selection();
+        change(self);
+        if self.selection() != before {
+            self.selection_undo.record(before);
+        }
+    }
+
+    /// Step the selection back; false when there is nothing to undo
+    pub fn undo_selection(&mut self) -> bool {
+        let current = self.selection();
+        match self.selection_undo.undo(current) {
+            Some(previous) => {
+                self.set_selection(&previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapply the last undone selection change; false when there is none
+    pub fn redo_selection(&mut self) -> bool {
+        let current = self.selection();
+        match self.selection_undo.redo(current) {
+            Some(next) => {
+                self.set_selection(&next);
+                true
+            }
+            None => false,
         }
     }
 
@@ -184,6 +700,15 @@ impl AppStateManager {
         }
     }
 
+    /// Item reports shown on the complete screen, with the failures
+    /// filter applied
+    pub fn visible_item_reports(&self) -> Vec<&ItemReport> {
+        self.item_reports
+            .iter()
+            .filter(|report| !self.item_failures_only || report.is_failure())
+            .collect()
+    }
+
     pub fn move_selection_up(&mut self, max_items: usize) {
         if max_items > 0 {
             self.selected_item_index = if self.selected_item_index == 0 {
@@ -203,9 +728,11 @@ impl AppStateManager {
         if max_items > 0 {
             self.selected_item_index = (self.selected_item_index + 1) % max_items;
             
-            // Adjust scroll if needed
+            // Adjust scroll if needed, including after wrapping to the top
             if self.selected_item_index >= self.scroll_offset + visible_height {
                 self.scroll_offset = self.selected_item_index - visible_height + 1;
+            } else if self.selected_item_index < self.scroll_offset {
+                self.scroll_offset = self.selected_item_index;
             }
         }
     }
@@ -227,6 +754,13 @@ impl AppStateManager {
         self.selected_item_index = (self.selected_item_index + page_size).min(max_items - 1);
     }
 
+    pub fn selected_privileged_entries(&self) -> Vec<&PrivilegedEntry> {
+        self.get_selected_restore_items()
+            .into_iter()
+            .flat_map(|item| item.privileged_entries.iter())
+            .collect()
+    }
+
     pub fn is_backup_ready(&self) -> bool {
         !self.get_selected_backup_items().is_empty()
     }
@@ -250,6 +784,85 @@ impl AppStateManager {
         (item_count, total_size, high_security_count)
     }
 
+    /// Item selection rows, with the collapsed groups folded away
+    pub fn backup_tree(&self) -> Vec<TreeRow> {
+        item_tree::rows(&self.backup_items, &self.collapsed_groups, &self.item_filter, self.item_sort)
+    }
+
+    /// Indices of the restore items the filter keeps
+    pub fn visible_restore_items(&self) -> Vec<usize> {
+        self.restore_items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| {
+                let original = item.original_path.to_string_lossy();
+                let restore = item.restore_path.to_string_lossy();
+                fuzzy::matches(&self.item_filter, &[&item.name, &original, &restore])
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Restore item on the highlighted row
+    pub fn highlighted_restore_item(&self) -> Option<&RestoreItem> {
+        let index = *self.visible_restore_items().get(self.selected_item_index)?;
+        self.restore_items.get(index)
+    }
+
+    /// Change the item filter, going back to the top of the list it leaves
+    pub fn set_item_filter(&mut self, filter: String) {
+        self.item_filter = filter;
+        self.selected_item_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// Item on the highlighted row of the item selection tree; None on a
+    /// group row
+    pub fn highlighted_backup_item(&self) -> Option<usize> {
+        match self.backup_tree().get(self.selected_item_index) {
+            Some(TreeRow::Item { index, .. }) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Fold or unfold the group with `key`
+    pub fn set_backup_group_expanded(&mut self, key: &str, expanded: bool) {
+        if expanded {
+            self.collapsed_groups.remove(key);
+        } else {
+            self.collapsed_groups.insert(key.to_string());
+        }
+    }
+
+    /// Unfold the groups holding item `index` and highlight its row
+    pub fn reveal_backup_item(&mut self, index: usize) {
+        let Some(item) = self.backup_items.get(index) else {
+            return;
+        };
+        self.collapsed_groups.remove(&item_tree::category_key(item));
+        if let Some(key) = item_tree::application_key(item) {
+            self.collapsed_groups.remove(&key);
+        }
+        if let Some(row) = self.backup_tree().iter().position(|row| matches!(row, TreeRow::Item { index: at, .. } if *at == index)) {
+            self.selected_item_index = row;
+            self.scroll_offset = row;
+        }
+    }
+
+    /// Up to `count` selected items with a known size, largest first, as
+    /// (index, size)
+    pub fn largest_backup_items(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut sized: Vec<(usize, u64)> = self.backup_items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.selected)
+            .filter_map(|(index, item)| Some((index, item.size?)))
+            .collect();
+        sized.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        sized.truncate(count);
+        sized
+    }
+
     pub fn get_restore_summary(&self) -> (usize, u64, usize) {
         let selected_items = self.get_selected_restore_items();
         let item_count = selected_items.len();
@@ -258,4 +871,34 @@ impl AppStateManager {
         
         (item_count, total_size, conflicts)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::BackupItem;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_largest_selected_items_come_first() {
+        let mut state = AppStateManager::new();
+        let item = |name: &str, size: Option<u64>, selected: bool| {
+            let mut item = BackupItem::new(name.to_string(), PathBuf::from(name), "Shell".to_string(), String::new());
+            item.size = size;
+            item.selected = selected;
+            item
+        };
+        state.backup_items = vec![
+            item(".bashrc", Some(4), true),
+            item("Videos", Some(9_000), false),
+            item(".cache", Some(700), true),
+            item(".local", None, true),
+            item("Documents", Some(2_000), true),
+            item(".config", Some(50), true),
+        ];
+
+        // Unchecked items and ones still being sized are left out
+        assert_eq!(state.largest_backup_items(TOP_CONSUMERS), [(4, 2_000), (2, 700), (5, 50), (0, 4)]);
+        assert_eq!(state.largest_backup_items(2), [(4, 2_000), (2, 700)]);
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+
+use crate::core::audit::AuditEvent;
+use crate::core::config::{BackupConfig, BackupStrategy};
+use crate::ui::theme::Tone;
+
+/// Audit actions that are backups a strategy can be satisfied by
+const BACKUP_ACTIONS: &[&str] = &["backup", "backup_stdout"];
+
+/// How a strategy's last run went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunStatus {
+    Never,
+    Ok,
+    Partial,
+    Failed,
+}
+
+impl RunStatus {
+    fn from_result(result: &str) -> Self {
+        if result == "ok" {
+            Self::Ok
+        } else if result.starts_with("partial") {
+            Self::Partial
+        } else {
+            Self::Failed
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Never => "never run",
+            Self::Ok => "ok",
+            Self::Partial => "partial",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A configured backup strategy and where it stands on this machine
+#[derive(Debug, Clone)]
+pub struct StrategyStatus {
+    /// Key of `backup_strategies`
+    pub name: String,
+    pub strategy: BackupStrategy,
+    /// When the last backup that counts for the strategy finished
+    pub last_run: Option<DateTime<Utc>>,
+    pub status: RunStatus,
+    /// No successful run within the strategy's frequency and a quarter
+    pub overdue: bool,
+}
+
+impl StrategyStatus {
+    /// Label and tone for the status column; overdue outranks the result
+    pub fn summary(&self) -> (&'static str, Option<Tone>) {
+        match self.status {
+            _ if self.overdue => ("overdue", Some(Tone::Danger)),
+            RunStatus::Never => (self.status.label(), None),
+            RunStatus::Ok => (self.status.label(), Some(Tone::Good)),
+            RunStatus::Partial => (self.status.label(), Some(Tone::Caution)),
+            RunStatus::Failed => (self.status.label(), Some(Tone::Danger)),
+        }
+    }
+}
+
+/// Every strategy in the config, by name, with its last run from the audit
+/// log (`events` oldest first). A backup run from the strategy counts for
+/// it alone; any other backup counts for every strategy of its mode.
+pub fn statuses(config: &BackupConfig, events: &[AuditEvent], now: DateTime<Utc>) -> Vec<StrategyStatus> {
+    let mut statuses: Vec<StrategyStatus> = config
+        .backup_strategies
+        .iter()
+        .map(|(name, strategy)| {
+            let runs: Vec<(&AuditEvent, &str)> = events
+                .iter()
+                .filter(|event| BACKUP_ACTIONS.contains(&event.action.as_str()))
+                .filter_map(|event| {
+                    let operation = event.operation.as_ref()?;
+                    let counts = match &operation.strategy {
+                        Some(run_for) => run_for == name,
+                        None => operation.mode == strategy.mode,
+                    };
+                    counts.then_some((event, operation.result.as_str()))
+                })
+                .collect();
+            let last = runs.last();
+            let last_ok = runs.iter().rev().find(|(_, result)| *result == "ok").map(|(event, _)| event.timestamp);
+            let overdue = match strategy.interval() {
+                Some(interval) => last_ok.is_none_or(|at| now - at > interval + interval / 4),
+                None => false,
+            };
+            StrategyStatus {
+                name: name.clone(),
+                strategy: strategy.clone(),
+                last_run: last.map(|(event, _)| event.timestamp),
+                status: last.map(|(_, result)| RunStatus::from_result(result)).unwrap_or(RunStatus::Never),
+                overdue,
+            }
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.name.cmp(&b.name));
+    statuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::audit::Operation;
+
+    #[test]
+    fn test_strategy_statuses_from_audit_log() {
+        let config: BackupConfig = serde_json::from_str(include_str!("../../backup-config.json")).unwrap();
+        let now = Utc::now();
+        let backup = |days: i64, mode: &str, strategy: Option<&str>, result: &str| {
+            let mut event = AuditEvent::new("backup", "a.tar.gz", "").with_operation(Operation {
+                mode: mode.to_string(),
+                result: result.to_string(),
+                strategy: strategy.map(str::to_string),
+                ..Default::default()
+            });
+            event.timestamp = now - chrono::Duration::days(days);
+            event
+        };
+        let events = vec![
+            backup(3, "secure", None, "ok"),
+            backup(2, "complete", Some("pre_migration"), "ok"),
+            backup(1, "secure", None, "failed: disk full"),
+            AuditEvent::new("restore", "a.tar.gz", ""),
+        ];
+
+        let statuses = statuses(&config, &events, now);
+        let names: Vec<&str> = statuses.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["daily_secure", "pre_migration", "weekly_complete"]);
+
+        // Failed yesterday, and the last good run is three days old
+        assert_eq!(statuses[0].status, RunStatus::Failed);
+        assert_eq!(statuses[0].last_run, Some(now - chrono::Duration::days(1)));
+        assert_eq!(statuses[0].summary(), ("overdue", Some(Tone::Danger)));
+
+        // On demand: never overdue
+        assert_eq!(statuses[1].status, RunStatus::Ok);
+        assert!(!statuses[1].overdue);
+
+        // The pre-migration run was for that strategy only
+        assert_eq!(statuses[2].status, RunStatus::Never);
+        assert!(statuses[2].overdue);
+    }
+}
@@ -1,5 +1,17 @@
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::backend::browser_profiles::BrowserConfig;
+use crate::backend::container_volumes::VolumeConfig;
+use crate::backend::database_dumps::DatabaseConfig;
+use crate::backend::packages::PackageManager;
+use crate::ui::theme::Tone;
+
+/// Whether `program` is on the PATH
+fn installed(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BackupMode {
@@ -16,7 +28,174 @@ impl BackupMode {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Compressor used for archive tarballs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Bzip2,
+    Zstd,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 4] = [Compression::Gzip, Compression::Xz, Compression::Bzip2, Compression::Zstd];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bzip2",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == name)
+    }
+
+    /// File extension following `.tar`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bz2",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// GNU tar option selecting this compressor, needed when reading
+    /// from a pipe where tar can't detect the format itself
+    pub fn tar_option(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "--gzip",
+            Compression::Xz => "--xz",
+            Compression::Bzip2 => "--bzip2",
+            Compression::Zstd => "--zstd",
+        }
+    }
+
+    pub fn level_range(&self) -> RangeInclusive<u32> {
+        match self {
+            Compression::Gzip | Compression::Bzip2 => 1..=9,
+            Compression::Xz => 0..=9,
+            // Levels above 19 need --ultra and gigabytes of memory
+            Compression::Zstd => 1..=19,
+        }
+    }
+
+    pub fn default_level(&self) -> u32 {
+        match self {
+            Compression::Gzip | Compression::Xz => 6,
+            Compression::Bzip2 => 9,
+            Compression::Zstd => 3,
+        }
+    }
+
+    /// Compressor command line for `tar --use-compress-program`, on
+    /// `threads` threads, 0 for one per core. gzip and bzip2 have no
+    /// threads of their own, so pigz and pbzip2, whose output they read
+    /// as usual, stand in for them when installed.
+    pub fn program(&self, level: u32, threads: u32) -> String {
+        let level = level.clamp(*self.level_range().start(), *self.level_range().end());
+        let cores = || match threads {
+            0 => std::thread::available_parallelism().map_or(1, |cores| cores.get() as u32),
+            threads => threads,
+        };
+        match self {
+            Compression::Gzip if installed("pigz") => format!("pigz -{} -p {}", level, cores()),
+            Compression::Gzip => format!("gzip -{}", level),
+            Compression::Xz => format!("xz -{} -T{}", level, threads),
+            Compression::Bzip2 if installed("pbzip2") => format!("pbzip2 -{} -p{}", level, cores()),
+            Compression::Bzip2 => format!("bzip2 -{}", level),
+            Compression::Zstd => format!("zstd -{} -T{}", level, threads),
+        }
+    }
+
+    /// Compression of an archive from its file name. Encrypted archives
+    /// without an inner extension predate the choice and are gzipped.
+    pub fn detect(archive: &Path) -> Option<Self> {
+        let name = archive.to_string_lossy().to_lowercase();
+        let (name, encrypted) = match name.strip_suffix(".gpg") {
+            Some(inner) => (inner.to_string(), true),
+            None => (name, false),
+        };
+        let found = Self::ALL.into_iter().find(|c| name.ends_with(&format!(".tar.{}", c.extension())));
+        match found {
+            Some(compression) => Some(compression),
+            None if name.ends_with(".tgz") => Some(Compression::Gzip),
+            None if encrypted && !name.ends_with(".tar") => Some(Compression::Gzip),
+            None => None,
+        }
+    }
+}
+
+/// GNU tar options that keep each file's extended attributes (SELinux
+/// labels, file capabilities, `user.*`) and POSIX ACLs in the archive.
+/// Hard links are kept as links by tar itself.
+pub const TAR_METADATA_OPTIONS: &[&str] = &["--xattrs", "--xattrs-include=*", "--acls", "--selinux"];
+
+/// What a restore does with the owners, ACLs and extended attributes an
+/// archive holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestoreMetadata {
+    /// Put them back as archived. Owners are only restored when running
+    /// as root, and attributes the user may not set are skipped with a
+    /// warning.
+    #[default]
+    Apply,
+    /// Files belong to the restoring user and take the ACLs and SELinux
+    /// labels of where they land
+    Skip,
+}
+
+impl RestoreMetadata {
+    /// GNU tar extract options for this choice
+    pub fn tar_options(&self) -> &'static [&'static str] {
+        match self {
+            RestoreMetadata::Apply => TAR_METADATA_OPTIONS,
+            RestoreMetadata::Skip => &["--no-same-owner", "--no-xattrs", "--no-acls", "--no-selinux"],
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestoreMetadata::Apply => "apply",
+            RestoreMetadata::Skip => "skip",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            RestoreMetadata::Apply => RestoreMetadata::Skip,
+            RestoreMetadata::Skip => RestoreMetadata::Apply,
+        }
+    }
+}
+
+/// What a backup does with the symbolic links it finds under an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Store the link itself, pointing where it did
+    #[default]
+    Archive,
+    /// Store what the link points to under the link's name
+    Follow,
+    /// Leave links out
+    Skip,
+}
+
+impl SymlinkPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymlinkPolicy::Archive => "archive",
+            SymlinkPolicy::Follow => "follow",
+            SymlinkPolicy::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SecurityLevel {
     Low,
     Medium,
@@ -24,11 +203,37 @@ pub enum SecurityLevel {
 }
 
 impl SecurityLevel {
-    pub fn color(&self) -> ratatui::style::Color {
+    pub fn tone(&self) -> Tone {
+        match self {
+            SecurityLevel::Low => Tone::Good,
+            SecurityLevel::Medium => Tone::Caution,
+            SecurityLevel::High => Tone::Danger,
+        }
+    }
+
+    /// Key of the level in `security_classifications`
+    pub fn as_str(&self) -> &'static str {
         match self {
-            SecurityLevel::Low => ratatui::style::Color::Green,
-            SecurityLevel::Medium => ratatui::style::Color::Yellow,
-            SecurityLevel::High => ratatui::style::Color::Red,
+            SecurityLevel::Low => "low",
+            SecurityLevel::Medium => "medium",
+            SecurityLevel::High => "high",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecurityLevel::Low => "Low",
+            SecurityLevel::Medium => "Medium",
+            SecurityLevel::High => "High",
+        }
+    }
+
+    /// The level after this one, wrapping from High back to Low
+    pub fn next(&self) -> Self {
+        match self {
+            SecurityLevel::Low => SecurityLevel::Medium,
+            SecurityLevel::Medium => SecurityLevel::High,
+            SecurityLevel::High => SecurityLevel::Low,
         }
     }
 }
@@ -44,6 +249,25 @@ pub struct BackupItem {
     pub selected: bool,
     pub exists: bool,
     pub size: Option<u64>,
+    /// Shell command whose output is archived in place of files; `path`
+    /// is then where the output goes in the archive
+    pub command: Option<String>,
+    /// Package managers whose explicitly installed packages are listed
+    /// into the archive; `path` is then where the list goes
+    pub packages: Vec<PackageManager>,
+    /// Container volumes copied into the archive through the Docker or
+    /// Podman API; `path` is then where they go
+    pub volumes: Option<VolumeConfig>,
+    /// Database dumped into the archive; `path` is then where the dump
+    /// goes
+    pub database: Option<DatabaseConfig>,
+    /// Browser whose profiles are under `path`, checked for being open
+    /// before they are copied
+    pub browser: Option<BrowserConfig>,
+    pub symlinks: SymlinkPolicy,
+    /// Entry in `modern_configurations` the item comes from, grouping it
+    /// with the application's other items
+    pub application: Option<String>,
 }
 
 impl BackupItem {
@@ -58,6 +282,13 @@ impl BackupItem {
             selected: false,
             exists: false,
             size: None,
+            command: None,
+            packages: Vec::new(),
+            volumes: None,
+            database: None,
+            browser: None,
+            symlinks: SymlinkPolicy::Archive,
+            application: None,
         }
     }
 
@@ -70,6 +301,38 @@ impl BackupItem {
         self.warning = Some(warning);
         self
     }
+
+    pub fn with_command(mut self, command: String) -> Self {
+        self.command = Some(command);
+        self
+    }
+
+    pub fn with_packages(mut self, packages: Vec<PackageManager>) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    pub fn with_volumes(mut self, volumes: VolumeConfig) -> Self {
+        self.volumes = Some(volumes);
+        self
+    }
+
+    pub fn with_database(mut self, database: DatabaseConfig) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    pub fn with_application(mut self, application: String) -> Self {
+        self.application = Some(application);
+        self
+    }
+
+    /// Whether the backup produces the item's content, by running a
+    /// command, listing packages, copying volumes or dumping a database,
+    /// rather than reading files
+    pub fn is_generated(&self) -> bool {
+        self.command.is_some() || !self.packages.is_empty() || self.volumes.is_some() || self.database.is_some()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -77,16 +340,26 @@ pub struct BackupProgress {
     pub current_item: String,
     pub items_completed: usize,
     pub total_items: usize,
+    /// Files in the backup; while preparing, those counted so far
+    pub total_files: usize,
     pub bytes_processed: u64,
     pub total_bytes: u64,
     pub start_time: DateTime<Utc>,
     pub estimated_completion: Option<DateTime<Utc>>,
     pub status: ProgressStatus,
+    /// (time, bytes processed) over the last [`THROUGHPUT_WINDOW_SECS`],
+    /// for the current rather than the average rate
+    pub throughput_samples: std::collections::VecDeque<(DateTime<Utc>, u64)>,
 }
 
+/// How far back the throughput of a running backup is measured
+pub const THROUGHPUT_WINDOW_SECS: i64 = 10;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ProgressStatus {
     Preparing,
+    /// Fetching the archive from a remote before extracting it
+    Downloading,
     Processing,
     Compressing,
     Encrypting,
@@ -99,6 +372,7 @@ impl ProgressStatus {
     pub fn as_str(&self) -> &str {
         match self {
             ProgressStatus::Preparing => "Preparing",
+            ProgressStatus::Downloading => "Downloading",
             ProgressStatus::Processing => "Processing",
             ProgressStatus::Compressing => "Compressing", 
             ProgressStatus::Encrypting => "Encrypting",
@@ -110,7 +384,7 @@ impl ProgressStatus {
 
     pub fn color(&self) -> ratatui::style::Color {
         match self {
-            ProgressStatus::Preparing | ProgressStatus::Processing 
+            ProgressStatus::Preparing | ProgressStatus::Downloading | ProgressStatus::Processing
             | ProgressStatus::Compressing | ProgressStatus::Encrypting 
             | ProgressStatus::Finalizing => ratatui::style::Color::Blue,
             ProgressStatus::Completed => ratatui::style::Color::Green,
@@ -125,15 +399,51 @@ impl Default for BackupProgress {
             current_item: String::new(),
             items_completed: 0,
             total_items: 0,
+            total_files: 0,
             bytes_processed: 0,
             total_bytes: 0,
             start_time: Utc::now(),
             estimated_completion: None,
             status: ProgressStatus::Preparing,
+            throughput_samples: std::collections::VecDeque::new(),
         }
     }
 }
 
+impl BackupProgress {
+    /// Set the bytes processed so far, updating the throughput window and
+    /// the estimated completion
+    pub fn record_bytes(&mut self, bytes: u64) {
+        let now = Utc::now();
+        self.bytes_processed = bytes;
+        self.throughput_samples.push_back((now, bytes));
+        while self.throughput_samples.len() > 2
+            && self.throughput_samples.front().is_some_and(|(time, _)| (now - *time).num_seconds() > THROUGHPUT_WINDOW_SECS)
+        {
+            self.throughput_samples.pop_front();
+        }
+        self.estimated_completion = self
+            .eta()
+            .and_then(|eta| chrono::Duration::from_std(eta).ok())
+            .map(|eta| now + eta);
+    }
+
+    /// Bytes per second over the throughput window, once it spans any time
+    pub fn bytes_per_second(&self) -> Option<f64> {
+        let (first_time, first_bytes) = self.throughput_samples.front()?;
+        let (last_time, last_bytes) = self.throughput_samples.back()?;
+        let elapsed = (*last_time - *first_time).num_milliseconds();
+        (elapsed > 0).then(|| last_bytes.saturating_sub(*first_bytes) as f64 * 1000.0 / elapsed as f64)
+    }
+
+    /// Time left at the current rate
+    pub fn eta(&self) -> Option<std::time::Duration> {
+        let rate = self.bytes_per_second().filter(|rate| *rate > 0.0)?;
+        let remaining = self.total_bytes.checked_sub(self.bytes_processed)?;
+        Some(std::time::Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ArchiveInfo {
     pub path: PathBuf,
@@ -144,6 +454,205 @@ pub struct ArchiveInfo {
     pub encrypted: bool,
     pub description: String,
     pub items: Vec<String>,
+    /// rclone path when the archive lives on a remote rather than on disk
+    pub remote: Option<String>,
+    /// Machine that created the archive, when known
+    pub hostname: Option<String>,
+}
+
+/// Order of the restore archive list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveSort {
+    #[default]
+    Newest,
+    Name,
+    Size,
+    Mode,
+}
+
+impl ArchiveSort {
+    pub fn next(self) -> Self {
+        match self {
+            ArchiveSort::Newest => ArchiveSort::Name,
+            ArchiveSort::Name => ArchiveSort::Size,
+            ArchiveSort::Size => ArchiveSort::Mode,
+            ArchiveSort::Mode => ArchiveSort::Newest,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ArchiveSort::Newest => "newest first",
+            ArchiveSort::Name => "name",
+            ArchiveSort::Size => "largest first",
+            ArchiveSort::Mode => "complete first",
+        }
+    }
+
+    /// Sort `archives`, newest first among equals
+    pub fn sort(self, archives: &mut [ArchiveInfo]) {
+        archives.sort_by(|a, b| {
+            let newest = b.created.cmp(&a.created).then_with(|| a.name.cmp(&b.name));
+            match self {
+                ArchiveSort::Newest => newest,
+                ArchiveSort::Name => a.name.cmp(&b.name).then(newest),
+                ArchiveSort::Size => b.size.cmp(&a.size).then(newest),
+                // Complete archives hold the credentials, so they lead
+                ArchiveSort::Mode => (a.mode == BackupMode::Secure).cmp(&(b.mode == BackupMode::Secure)).then(newest),
+            }
+        });
+    }
+}
+
+/// How a backup's archive fared at one of its destinations
+#[derive(Debug, Clone)]
+pub struct DestinationResult {
+    pub destination: String,
+    /// Where the copy is, or why there isn't one
+    pub outcome: Result<String, String>,
+}
+
+/// What became of one selected item in a backup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemOutcome {
+    BackedUp,
+    /// The item itself couldn't be read, so nothing of it was archived
+    PermissionDenied,
+    Missing,
+    Excluded,
+    /// The item's command failed, so its output isn't in the archive
+    CommandFailed,
+}
+
+impl ItemOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ItemOutcome::BackedUp => "backed up",
+            ItemOutcome::PermissionDenied => "permission denied",
+            ItemOutcome::Missing => "missing",
+            ItemOutcome::Excluded => "excluded",
+            ItemOutcome::CommandFailed => "command failed",
+        }
+    }
+
+    pub fn tone(&self) -> Tone {
+        match self {
+            ItemOutcome::BackedUp => Tone::Good,
+            ItemOutcome::PermissionDenied | ItemOutcome::CommandFailed => Tone::Danger,
+            ItemOutcome::Missing | ItemOutcome::Excluded => Tone::Caution,
+        }
+    }
+}
+
+/// One selected item's result in a backup
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemReport {
+    /// Relative to the home directory, as selected
+    pub path: PathBuf,
+    pub outcome: ItemOutcome,
+    /// Files archived from the item, and their total size
+    pub files: usize,
+    pub bytes: u64,
+    /// Paths below the item that couldn't be read and were left out
+    pub unreadable: Vec<PathBuf>,
+}
+
+impl ItemReport {
+    /// Whether anything of the item is missing from the archive
+    pub fn is_failure(&self) -> bool {
+        self.outcome != ItemOutcome::BackedUp || !self.unreadable.is_empty()
+    }
+
+    /// Outcome as shown, noting what a backed up item left out
+    pub fn status(&self) -> String {
+        match self.unreadable.len() {
+            0 => self.outcome.label().to_string(),
+            n => format!("{}, {} unreadable", self.outcome.label(), n),
+        }
+    }
+
+    pub fn tone(&self) -> Tone {
+        match self.outcome {
+            ItemOutcome::BackedUp if !self.unreadable.is_empty() => Tone::Caution,
+            outcome => outcome.tone(),
+        }
+    }
+}
+
+/// File found in the catalog while searching across archives
+#[derive(Debug, Clone)]
+pub struct CatalogMatch {
+    pub archive_name: String,
+    pub archive_path: PathBuf,
+    pub archive_created: DateTime<Utc>,
+    pub item_path: String,
+    pub size: u64,
+    pub hostname: Option<String>,
+    /// Contents hash, when the archive was readable at catalog time
+    pub sha256: Option<String>,
+}
+
+/// File found by a catalog search: by name, or by its indexed text when
+/// `snippet` is set
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub file: CatalogMatch,
+    pub snippet: Option<String>,
+}
+
+/// Whether an archive's recorded checksum has been re-checked
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationStatus {
+    Verified(DateTime<Utc>),
+    Failed(DateTime<Utc>),
+    Unverified,
+    NoChecksum,
+}
+
+impl VerificationStatus {
+    pub fn label(&self) -> String {
+        match self {
+            VerificationStatus::Verified(at) => format!("✓ verified {}", at.format("%Y-%m-%d")),
+            VerificationStatus::Failed(at) => format!("✗ FAILED {}", at.format("%Y-%m-%d")),
+            VerificationStatus::Unverified => "unverified".to_string(),
+            VerificationStatus::NoChecksum => "no checksum".to_string(),
+        }
+    }
+
+    /// `None` when there is nothing to check against
+    pub fn tone(&self) -> Option<Tone> {
+        match self {
+            VerificationStatus::Verified(_) => Some(Tone::Good),
+            VerificationStatus::Failed(_) => Some(Tone::Danger),
+            VerificationStatus::Unverified => Some(Tone::Caution),
+            VerificationStatus::NoChecksum => None,
+        }
+    }
+}
+
+/// Backup state of one machine in the shared catalog
+#[derive(Debug, Clone)]
+pub struct HostSummary {
+    pub hostname: String,
+    pub archive_count: usize,
+    pub total_size: u64,
+    pub last_backup: DateTime<Utc>,
+    pub last_mode: BackupMode,
+    pub last_size: u64,
+    /// Catalog key of the latest archive (local path or rclone path)
+    pub last_archive: String,
+    pub last_remote: Option<String>,
+    pub last_checksum: Option<String>,
+    pub verification: VerificationStatus,
+    /// Set by the caller from the host's configured backup strategy
+    pub overdue: bool,
+}
+
+/// Every cataloged copy of one file, newest first
+#[derive(Debug, Clone)]
+pub struct FileVersions {
+    pub path: String,
+    pub versions: Vec<CatalogMatch>,
 }
 
 #[derive(Debug, Clone)]
@@ -154,6 +663,33 @@ pub struct RestoreItem {
     pub size: u64,
     pub selected: bool,
     pub conflicts: bool,
+    /// Entries under this item that carry setuid/setgid bits or capabilities
+    pub privileged_entries: Vec<PrivilegedEntry>,
+}
+
+/// Archive entry whose permissions grant elevated privileges when restored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivilegedEntry {
+    pub path: String,
+    pub setuid: bool,
+    pub setgid: bool,
+    pub capabilities: bool,
+}
+
+impl PrivilegedEntry {
+    pub fn describe(&self) -> String {
+        let mut bits = Vec::new();
+        if self.setuid {
+            bits.push("setuid");
+        }
+        if self.setgid {
+            bits.push("setgid");
+        }
+        if self.capabilities {
+            bits.push("capabilities");
+        }
+        bits.join(", ")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -183,6 +719,67 @@ impl Default for RestoreProgress {
     }
 }
 
+impl RestoreProgress {
+    /// Average bytes per second since the start, once there is any
+    pub fn bytes_per_second(&self) -> Option<f64> {
+        let elapsed = (Utc::now() - self.start_time).num_milliseconds();
+        (elapsed > 0 && self.bytes_processed > 0)
+            .then(|| self.bytes_processed as f64 * 1000.0 / elapsed as f64)
+    }
+
+    /// Time left at the average rate so far
+    pub fn eta(&self) -> Option<std::time::Duration> {
+        let rate = self.bytes_per_second()?;
+        let remaining = self.total_bytes.saturating_sub(self.bytes_processed);
+        Some(std::time::Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckStatus {
+    Pass,
+    Warning,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✓",
+            CheckStatus::Warning => "⚠️",
+            CheckStatus::Fail => "✗",
+        }
+    }
+
+    pub fn tone(&self) -> Tone {
+        match self {
+            CheckStatus::Pass => Tone::Good,
+            CheckStatus::Warning => Tone::Caution,
+            CheckStatus::Fail => Tone::Danger,
+        }
+    }
+}
+
+/// Outcome of a single post-restore sanity check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub category: String,
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    pub fn new(category: &str, name: &str, status: CheckStatus, detail: &str) -> Self {
+        Self {
+            category: category.to_string(),
+            name: name.to_string(),
+            status,
+            detail: detail.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ValidationResult {
     pub success: bool,
@@ -190,4 +787,76 @@ pub struct ValidationResult {
     pub warnings: Vec<String>,
     pub total_size: u64,
     pub missing_items: Vec<String>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput_follows_the_recent_rate() {
+        let mut progress = BackupProgress { total_bytes: 1000, ..BackupProgress::default() };
+        progress.record_bytes(0);
+        assert_eq!(progress.bytes_per_second(), None);
+        assert_eq!(progress.eta(), None);
+
+        // A slow start drops out of the window once it is over ten seconds old
+        let now = Utc::now();
+        progress.throughput_samples = [(now - chrono::Duration::seconds(60), 0), (now - chrono::Duration::seconds(4), 100)].into();
+        progress.record_bytes(500);
+        assert_eq!(progress.throughput_samples.len(), 2);
+        let rate = progress.bytes_per_second().unwrap();
+        assert!((rate - 100.0).abs() < 5.0, "{}", rate);
+        let eta = progress.eta().unwrap().as_secs_f64();
+        assert!((eta - 5.0).abs() < 0.5, "{}", eta);
+        let completion = progress.estimated_completion.unwrap() - Utc::now();
+        assert!((completion.num_milliseconds() - 5000).abs() < 500);
+
+        // Nothing left, or more done than expected, has no time left
+        progress.total_bytes = 400;
+        assert_eq!(progress.eta(), None);
+    }
+
+    #[test]
+    fn test_archive_sort_orders_keep_ties_newest_first() {
+        let archive = |name: &str, days_ago: i64, size: u64, mode: BackupMode| ArchiveInfo {
+            path: PathBuf::from(format!("/backups/{}", name)),
+            name: name.to_string(),
+            created: Utc::now() - chrono::Duration::days(days_ago),
+            size,
+            mode,
+            encrypted: false,
+            description: String::new(),
+            items: Vec::new(),
+            remote: None,
+            hostname: None,
+        };
+        let mut archives = vec![
+            archive("b-secure", 3, 100, BackupMode::Secure),
+            archive("a-complete", 2, 900, BackupMode::Complete),
+            archive("c-secure", 1, 100, BackupMode::Secure),
+            archive("d-complete", 4, 50, BackupMode::Complete),
+        ];
+        let names = |archives: &[ArchiveInfo]| archives.iter().map(|a| a.name.clone()).collect::<Vec<_>>();
+
+        let mut sort = ArchiveSort::default();
+        let mut orders = Vec::new();
+        for _ in 0..4 {
+            sort.sort(&mut archives);
+            orders.push((sort.label(), names(&archives)));
+            sort = sort.next();
+        }
+        assert_eq!(sort, ArchiveSort::Newest);
+        let expected = [
+            ("newest first", ["c-secure", "a-complete", "b-secure", "d-complete"]),
+            ("name", ["a-complete", "b-secure", "c-secure", "d-complete"]),
+            // Equal sizes and modes fall back to newest first
+            ("largest first", ["a-complete", "c-secure", "b-secure", "d-complete"]),
+            ("complete first", ["a-complete", "d-complete", "c-secure", "b-secure"]),
+        ];
+        for ((label, order), (expected_label, expected_order)) in orders.iter().zip(expected) {
+            assert_eq!(*label, expected_label);
+            assert_eq!(order, &expected_order, "{}", label);
+        }
+    }
+}
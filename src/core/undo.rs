@@ -0,0 +1,73 @@
+/// Edits beyond this many back are forgotten
+const DEFAULT_LIMIT: usize = 100;
+
+/// Undo and redo for edits captured as snapshots of what they change
+#[derive(Debug, Clone)]
+pub struct UndoStack<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    limit: usize,
+}
+
+impl<T> Default for UndoStack<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIMIT)
+    }
+}
+
+impl<T> UndoStack<T> {
+    pub fn new(limit: usize) -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), limit }
+    }
+
+    /// Remember `before`, the state an edit has just changed. Anything
+    /// undone earlier can no longer be redone.
+    pub fn record(&mut self, before: T) {
+        self.undo.push(before);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// State to go back to, trading it for `current`
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// State undone last, trading it for `current`
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut stack = UndoStack::new(2);
+        stack.record(vec![true, true]);
+        stack.record(vec![false, true]);
+        stack.record(vec![false, false]);
+
+        // Current is [true, false]; the oldest snapshot fell off the end
+        assert_eq!(stack.undo(vec![true, false]), Some(vec![false, false]));
+        assert_eq!(stack.undo(vec![false, false]), Some(vec![false, true]));
+        assert_eq!(stack.undo(vec![false, true]), None);
+
+        assert_eq!(stack.redo(vec![false, true]), Some(vec![false, false]));
+        stack.record(vec![false, false]);
+        assert_eq!(stack.redo(vec![true, true]), None);
+    }
+}
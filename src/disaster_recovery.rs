@@ -21,6 +21,11 @@ use std::{
     time::{Duration, Instant},
 };
 
+use crate::backend::mount_health::{self, MountCheck, MountHealth};
+use crate::backend::nfs_sync::{NfsSync, SyncOperation};
+use crate::core::config::BackupConfig;
+use crate::ui::theme;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MenuItem {
     pub name: String,
@@ -38,6 +43,13 @@ pub struct MenuConfig {
     pub items: Vec<MenuItem>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Menu,
+    /// Health of the network shares, with remount actions
+    Mounts,
+}
+
 pub struct App {
     pub items: Vec<MenuItem>,
     pub selected: usize,
@@ -47,10 +59,15 @@ pub struct App {
     pub filter: String,
     pub filtered_items: Vec<usize>,
     pub config: MenuConfig,
+    /// Backup config, for the operations run in-process
+    pub config_path: String,
+    pub view: View,
+    pub mounts: Vec<MountCheck>,
+    pub mount_selected: usize,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
+    pub fn new(config_path: &str) -> Result<Self> {
         let config = Self::load_config()?;
         let filtered_items: Vec<usize> = (0..config.items.len()).collect();
         
@@ -63,6 +80,10 @@ impl App {
             filter: String::new(),
             filtered_items,
             config,
+            config_path: config_path.to_string(),
+            view: View::Menu,
+            mounts: Vec::new(),
+            mount_selected: 0,
         })
     }
 
@@ -84,7 +105,7 @@ impl App {
                 MenuItem {
                     name: String::from("💾 Sync to NFS Backup"),
                     description: String::from("Backup local data to NFS storage"),
-                    command: String::from("~/sync-to-nfs-backup.sh sync"),
+                    command: String::from("backup-ui sync push"),
                     category: String::from("Backup"),
                     shortcut: Some('s'),
                     dangerous: false,
@@ -92,7 +113,7 @@ impl App {
                 MenuItem {
                     name: String::from("📈 NFS Backup Status"),
                     description: String::from("Check NFS backup sync status"),
-                    command: String::from("~/sync-to-nfs-backup.sh status"),
+                    command: String::from("backup-ui sync status"),
                     category: String::from("Backup"),
                     shortcut: None,
                     dangerous: false,
@@ -110,25 +131,17 @@ impl App {
                 MenuItem {
                     name: String::from("📥 Pull from NFS"),
                     description: String::from("Restore configs from NFS for new system"),
-                    command: String::from("~/sync-from-nfs-restore.sh pull"),
+                    command: String::from("backup-ui sync pull"),
                     category: String::from("Restore"),
                     shortcut: Some('p'),
                     dangerous: false,
                 },
-                MenuItem {
-                    name: String::from("👁️ Check NFS Backup"),
-                    description: String::from("View available backups on NFS"),
-                    command: String::from("~/sync-from-nfs-restore.sh check"),
-                    category: String::from("Restore"),
-                    shortcut: Some('c'),
-                    dangerous: false,
-                },
                 
                 // Mount Management
                 MenuItem {
                     name: String::from("🔗 Check Mounts"),
                     description: String::from("Check and fix NFS mount status"),
-                    command: String::from("~/check-and-mount-nfs.sh"),
+                    command: String::from("backup-ui mounts"),
                     category: String::from("Mount"),
                     shortcut: Some('m'),
                     dangerous: false,
@@ -216,6 +229,11 @@ impl App {
 
         let actual_index = self.filtered_items[index];
         let item = &self.items[actual_index];
+
+        if mount_health::is_mounts_command(&item.command) {
+            self.open_mounts();
+            return Ok(());
+        }
         
         self.status_message = format!("Running: {}", item.name);
         
@@ -226,33 +244,52 @@ impl App {
         println!("\n🚀 Executing: {}\n", item.name);
         println!("Command: {}\n", item.command);
         
-        // Run the command
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(&item.command)
-            .output()?;
-        
-        // Show output
-        if !output.stdout.is_empty() {
-            println!("{}", String::from_utf8_lossy(&output.stdout));
-        }
-        if !output.stderr.is_empty() {
-            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
-        }
-        
-        // Store output for display in TUI
-        self.last_command_output = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .map(String::from)
-            .collect();
-        
-        if output.status.success() {
-            self.status_message = format!("✓ {} completed successfully", item.name);
+        if let Some(operation) = SyncOperation::from_command(&item.command) {
+            // NFS sync runs in-process, with rsync's progress on the terminal
+            let name = item.name.clone();
+            match self.run_sync(operation) {
+                Ok(lines) => {
+                    for line in &lines {
+                        println!("{}", line);
+                    }
+                    self.last_command_output = lines;
+                    self.status_message = format!("✓ {} completed successfully", name);
+                }
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    self.last_command_output = vec![format!("{:#}", e)];
+                    self.status_message = format!("✗ {} failed: {}", name, e);
+                }
+            }
         } else {
-            self.status_message = format!("✗ {} failed with exit code: {}", 
-                item.name, 
-                output.status.code().unwrap_or(-1)
-            );
+            // Run the command
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(&item.command)
+                .output()?;
+
+            // Show output
+            if !output.stdout.is_empty() {
+                println!("{}", String::from_utf8_lossy(&output.stdout));
+            }
+            if !output.stderr.is_empty() {
+                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+            }
+
+            // Store output for display in TUI
+            self.last_command_output = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(String::from)
+                .collect();
+
+            if output.status.success() {
+                self.status_message = format!("✓ {} completed successfully", item.name);
+            } else {
+                self.status_message = format!("✗ {} failed with exit code: {}",
+                    item.name,
+                    output.status.code().unwrap_or(-1)
+                );
+            }
         }
         
         println!("\n📋 Press Enter to return to menu...");
@@ -266,6 +303,76 @@ impl App {
         Ok(())
     }
 
+    fn run_sync(&self, operation: SyncOperation) -> Result<Vec<String>> {
+        let config = BackupConfig::load(&self.config_path)?;
+        let Some(sync_config) = config.nfs_sync else {
+            anyhow::bail!("No nfs_sync share configured in {}", self.config_path);
+        };
+        NfsSync::from_config(&sync_config)?.run_in_terminal(operation)
+    }
+
+    pub fn open_mounts(&mut self) {
+        self.view = View::Mounts;
+        self.check_mounts();
+    }
+
+    pub fn check_mounts(&mut self) {
+        self.mounts = mount_health::check_all();
+        self.mount_selected = self.mount_selected.min(self.mounts.len().saturating_sub(1));
+        let problems = self.mounts.iter().filter(|check| check.health != MountHealth::Healthy).count();
+        self.status_message = match (self.mounts.len(), problems) {
+            (0, _) => String::from("No NFS or SMB shares mounted or in /etc/fstab"),
+            (total, 0) => format!("✓ All {} shares healthy", total),
+            (total, problems) => format!("✗ {} of {} shares need attention", problems, total),
+        };
+    }
+
+    /// Unmount and mount the selected share again outside the TUI, where
+    /// sudo can ask for a password, then check it again
+    pub fn remount_selected(&mut self) -> Result<()> {
+        let Some(check) = self.mounts.get(self.mount_selected).cloned() else {
+            return Ok(());
+        };
+        if !check.health.needs_remount() {
+            self.status_message = match check.health {
+                MountHealth::Healthy => format!("{} is healthy; nothing to do", check.mount.mount_point.display()),
+                _ => format!("Remounting won't help: {}", check.detail),
+            };
+            return Ok(());
+        }
+
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        disable_raw_mode()?;
+
+        println!("\n🔗 Remounting {} on {}\n", check.mount.source, check.mount.mount_point.display());
+        let outcome = mount_health::remount(&check.mount);
+        let after = mount_health::network_mounts()
+            .into_iter()
+            .find(|mount| mount.mount_point == check.mount.mount_point)
+            .map(mount_health::check);
+        self.status_message = match (&outcome, &after) {
+            (Ok(_), Some(after)) if after.health == MountHealth::Healthy => {
+                format!("✓ {} remounted and healthy", check.mount.mount_point.display())
+            }
+            (Ok(_), Some(after)) => format!("✗ {} remounted but {}: {}", check.mount.mount_point.display(), after.health.label(), after.detail),
+            (Ok(_), None) => format!("✗ {} is gone from /etc/fstab", check.mount.mount_point.display()),
+            (Err(e), _) => format!("✗ Remount failed: {}", e),
+        };
+        println!("{}", self.status_message);
+
+        println!("\n📋 Press Enter to return to the mounts...");
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+
+        let message = self.status_message.clone();
+        self.check_mounts();
+        self.status_message = message;
+        Ok(())
+    }
+
     pub fn update_filter(&mut self) {
         self.filtered_items = self.items
             .iter()
@@ -284,7 +391,9 @@ impl App {
     }
 }
 
-pub fn run_tui() -> Result<()> {
+/// Run the menu; with `ascii`, symbols are drawn as ASCII stand-ins for
+/// terminals without Unicode fonts
+pub fn run_tui(config_path: &str, ascii: bool) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -293,13 +402,40 @@ pub fn run_tui() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new()?;
+    let mut app = App::new(config_path)?;
 
     // Main loop
     loop {
-        terminal.draw(|f| draw_ui(f, &app))?;
+        terminal.draw(|f| {
+            draw_ui(f, &app);
+            if ascii {
+                theme::to_ascii(f.buffer_mut());
+            }
+        })?;
 
         if let Event::Key(key) = event::read()? {
+            if app.view == View::Mounts {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        app.view = View::Menu;
+                        app.status_message = String::from("Ready. Press 'h' for help.");
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.mount_selected = app.mount_selected.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if app.mount_selected + 1 < app.mounts.len() => {
+                        app.mount_selected += 1;
+                    }
+                    KeyCode::Char('c') => app.check_mounts(),
+                    KeyCode::Char('r') | KeyCode::Enter => {
+                        app.remount_selected()?;
+                        terminal.clear()?;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
             match key.code {
                 KeyCode::Char('q') | KeyCode::Esc => break,
                 KeyCode::Char('h') => {
@@ -406,6 +542,12 @@ fn draw_ui(f: &mut Frame, app: &App) {
     .alignment(Alignment::Center);
     f.render_widget(header, chunks[0]);
 
+    if app.view == View::Mounts {
+        draw_mounts(f, app, chunks[1]);
+        draw_status(f, app, chunks[2], "↑/↓:select r:remount c:check again Esc:back");
+        return;
+    }
+
     // Main area - split into menu and output
     let (menu_area, output_area) = if app.show_output {
         let split = Layout::default()
@@ -476,11 +618,60 @@ fn draw_ui(f: &mut Frame, app: &App) {
     }
 
     // Status bar
+    draw_status(f, app, chunks[2], "h:help q:quit /:filter Enter:run");
+}
+
+fn draw_status(f: &mut Frame, app: &App, area: Rect, hints: &str) {
     let status = Paragraph::new(Line::from(vec![
         Span::raw(&app.status_message),
         Span::raw(" | "),
-        Span::styled("h:help q:quit /:filter Enter:run", Style::default().fg(Color::DarkGray)),
+        Span::styled(hints, Style::default().fg(Color::DarkGray)),
     ]))
     .block(Block::default().borders(Borders::ALL));
-    f.render_widget(status, chunks[2]);
+    f.render_widget(status, area);
+}
+
+fn draw_mounts(f: &mut Frame, app: &App, area: Rect) {
+    let split = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(5)])
+        .split(area);
+
+    let items: Vec<ListItem> = app.mounts
+        .iter()
+        .enumerate()
+        .map(|(i, check)| {
+            let (icon, color) = match check.health {
+                MountHealth::Healthy => ("✓", Color::Green),
+                MountHealth::NotMounted => ("○", Color::Yellow),
+                _ => ("✗", Color::Red),
+            };
+            let style = if i == app.mount_selected {
+                Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} {:<15} ", icon, check.health.label()), Style::default().fg(color)),
+                Span::raw(check.mount.mount_point.display().to_string()),
+                Span::styled(format!("  {} ({})", check.mount.source, check.mount.fs_type), Style::default().fg(Color::DarkGray)),
+            ]))
+            .style(style)
+        })
+        .collect();
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" Network Mounts "));
+    f.render_widget(list, split[0]);
+
+    let detail = match app.mounts.get(app.mount_selected) {
+        Some(check) if check.health.needs_remount() => vec![
+            Line::from(check.detail.clone()),
+            Line::from(Span::styled("Press r to unmount and mount it again (runs sudo)", Style::default().fg(Color::Yellow))),
+        ],
+        Some(check) => vec![Line::from(check.detail.clone())],
+        None => vec![Line::from("No NFS or SMB shares are mounted or listed in /etc/fstab")],
+    };
+    let detail = Paragraph::new(detail)
+        .block(Block::default().borders(Borders::ALL).title(" Details "))
+        .wrap(Wrap { trim: true });
+    f.render_widget(detail, split[1]);
 }
\ No newline at end of file
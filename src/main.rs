@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use log::{debug, error, info};
 use crossterm::execute;
@@ -9,6 +9,8 @@ mod backend;
 mod disaster_recovery;
 
 use core::app::{App, AppConfig};
+use core::events::{Event, Events};
+use core::types::RestoreMetadata;
 use ui::terminal::Terminal;
 
 #[derive(Parser)]
@@ -17,7 +19,7 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
     
-    /// Path to backup configuration file
+    /// Path to backup configuration file (JSON, TOML or YAML, by extension)
     #[arg(short, long, default_value = "backup-config.json")]
     config: String,
     
@@ -25,9 +27,70 @@ struct Cli {
     #[arg(short, long)]
     debug: bool,
     
-    /// Backup destination directory
+    /// Backup destination directory, or `rclone:<remote>:<path>` to stream
+    /// archives to any configured rclone remote
     #[arg(short = 'o', long)]
     output: Option<String>,
+
+    /// Backup profile from the config to use instead of choosing one
+    #[arg(short, long)]
+    profile: Option<String>,
+
+    /// CPU niceness, 0 to 19, of the backup's tar, compressor, gpg and
+    /// uploads; overrides the profile's `throttle.nice`
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(i32).range(0..=19))]
+    nice: Option<i32>,
+
+    /// IO scheduling class of the backup: idle or best-effort
+    #[arg(long, global = true, value_name = "CLASS")]
+    ionice: Option<core::config::IoClass>,
+
+    /// Cap in MB/s on writing the archive and on copying or uploading it
+    #[arg(long, global = true, value_name = "MB/S")]
+    max_rate: Option<f64>,
+
+    /// Print JSON lines to stdout (start, per-file progress, warnings and a
+    /// completion summary) instead of text, for scripts
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Also log JSON lines to a file, rotated by size. Without a path the
+    /// config's `logging.file` or ~/.local/state/backup-manager/logs/ is used
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<Option<std::path::PathBuf>>,
+
+    /// Read the archive passphrase for run-backup, vault and restore from
+    /// the first line of this file instead of the keyring or terminal
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "password_stdin")]
+    password_file: Option<std::path::PathBuf>,
+
+    /// Read the archive passphrase from the first line of stdin, such as
+    /// `pass show backup | backup-ui run-backup --stdout --encrypt --password-stdin`
+    #[arg(long, global = true)]
+    password_stdin: bool,
+
+    /// Recover the archive passphrase from a file written by `shares
+    /// split`; repeat for as many shares as the split needs
+    #[arg(long, global = true, value_name = "PATH", conflicts_with_all = ["password_file", "password_stdin"])]
+    password_share: Vec<std::path::PathBuf>,
+
+    /// Colors of the UI: dark, light, high-contrast or colorblind;
+    /// overrides the config's `ui.palette`
+    #[arg(long, value_name = "THEME")]
+    theme: Option<ui::theme::Palette>,
+
+    /// Draw borders, icons and arrows in plain ASCII, for terminals
+    /// without Unicode fonts. On by default on the Linux console and
+    /// under locales that aren't UTF-8.
+    #[arg(long, global = true)]
+    ascii: bool,
+}
+
+impl Cli {
+    /// Limits given on the command line, over any from the profile
+    fn throttle(&self) -> core::config::ThrottleConfig {
+        core::config::ThrottleConfig { nice: self.nice, ionice: self.ionice, max_mb_per_sec: self.max_rate }
+    }
 }
 
 #[derive(Subcommand)]
@@ -36,34 +99,504 @@ enum Commands {
     Dr,
     /// Launch the backup UI (original)
     Backup,
+    /// Find which archives contain a file, by path or glob
+    Find {
+        /// Path relative to home, or a glob such as `.config/*/settings.json`
+        pattern: String,
+
+        /// Maximum number of archived copies to list
+        #[arg(short, long, default_value_t = 200)]
+        limit: usize,
+    },
+    /// Search every cataloged archive for files by name and, where text
+    /// indexing is enabled, by the words inside them
+    Search {
+        /// Part of a file name, or words the file contains
+        query: String,
+
+        /// Maximum number of matches of each kind to list
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Exchange the archive catalog with other machines through the
+    /// `catalog_sync` location in the config
+    SyncCatalog,
+    /// Undo the most recent restore from the snapshot taken before it ran
+    RollbackRestore,
+    /// Write a script installing the packages an archive recorded, to
+    /// review and run, or run it straight away with --run
+    ReinstallPackages {
+        /// Archive made with the System packages item selected
+        archive: std::path::PathBuf,
+
+        /// Run the script after writing it; pacman asks for sudo
+        #[arg(long)]
+        run: bool,
+    },
+    /// Load the database dumps an archive holds back into their databases
+    ReplayDatabases {
+        /// Archive made from a profile with `databases`
+        archive: std::path::PathBuf,
+
+        /// Databases to replay, by name; all of them when none are given
+        names: Vec<String>,
+    },
+    /// Store the password of a database's `keyring` account in the system
+    /// keyring, read from --password-file, --password-stdin or the terminal
+    DatabasePassword {
+        /// The `keyring` account the database names in the profile
+        account: String,
+
+        /// Remove the stored password instead
+        #[arg(long)]
+        forget: bool,
+    },
+    /// Show the audit trail of backups and restores and check its
+    /// integrity chain; fails if an entry was altered
+    History {
+        /// Number of most recent entries to list
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Rewrite an unencrypted archive with another compressor, check every
+    /// file against the original and replace it in the catalog
+    Repack {
+        /// Local archive to rewrite
+        archive: std::path::PathBuf,
+        /// gzip, xz, bzip2 or zstd
+        #[arg(long, default_value = "zstd")]
+        compression: String,
+        /// Compression level; defaults to the compressor's usual level
+        #[arg(long)]
+        level: Option<u32>,
+        /// Keep the original archive and its catalog entry
+        #[arg(long)]
+        keep_original: bool,
+    },
+    /// Move archives that have aged into a colder retention tier (the
+    /// config's `tiers`) and update the catalog; meant for a timer or cron
+    Maintain {
+        /// List the moves without making them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Re-hash stored archives and compare them with the checksums in the
+    /// catalog, to catch silent corruption; meant for a timer or cron.
+    /// Archives found intact within `verification.scrub_interval_days` are
+    /// left for a later run
+    Scrub {
+        /// Check every archive, however recently it was found intact
+        #[arg(long)]
+        all: bool,
+        /// Read archives on rclone remotes back too, downloading each one
+        #[arg(long)]
+        remote: bool,
+    },
+    /// Write a small encrypted archive of only the high-security items
+    /// (keys and credentials) to the `credentials_vault` directory, and
+    /// delete the oldest beyond its `keep`. Skipped until
+    /// `interval_hours` after the last vault; meant for a timer or cron
+    Vault {
+        /// Make a vault even if the last one is recent
+        #[arg(long)]
+        force: bool,
+    },
+    /// Back up without the screens, writing the archive to stdout, such as
+    /// `backup-ui run-backup --stdout | ssh nas 'cat > laptop.tar.gz'`.
+    /// Items come from `--profile`, or else the mode's items in the config
+    RunBackup {
+        /// Write the archive to stdout; logs and progress go to stderr
+        #[arg(long, required = true)]
+        stdout: bool,
+
+        /// secure or complete; defaults to the profile's mode, then secure
+        #[arg(long)]
+        mode: Option<String>,
+
+        /// Encrypt the archive with a passphrase read from the terminal
+        #[arg(long)]
+        encrypt: bool,
+
+        /// Stream a complete-mode archive, credentials included, without
+        /// encrypting it
+        #[arg(long, conflicts_with = "encrypt")]
+        allow_unencrypted: bool,
+
+        /// Also write a report of the backup (items, sizes, duration,
+        /// warnings, checksum) to this `.md` or `.html` file
+        #[arg(long, value_name = "PATH")]
+        report: Option<std::path::PathBuf>,
+
+        /// Run outside the config's `backup_window` and don't stop when
+        /// it ends
+        #[arg(long)]
+        ignore_window: bool,
+    },
+    /// Restore from an archive piped in, such as
+    /// `ssh nas cat backup.tar.gz | backup-ui restore --stdin`, without
+    /// copying it to disk first
+    Restore {
+        /// Read the archive from stdin; compressed and GPG-encrypted
+        /// archives are recognized by their first bytes
+        #[arg(long, required = true)]
+        stdin: bool,
+
+        /// Only restore this home-relative path; repeat for more
+        #[arg(long = "item", value_name = "PATH")]
+        items: Vec<String>,
+
+        /// Restore under this directory instead of the home directory
+        #[arg(long)]
+        target: Option<std::path::PathBuf>,
+
+        /// Replace files that already exist, keeping each one beside it as
+        /// `<name>.~1~`
+        #[arg(long, conflicts_with = "list")]
+        overwrite: bool,
+
+        /// Leave out the owners, ACLs and extended attributes (SELinux
+        /// labels, capabilities) stored in the archive
+        #[arg(long, conflicts_with = "list")]
+        skip_metadata: bool,
+
+        /// List the archive's files without restoring anything
+        #[arg(long)]
+        list: bool,
+
+        /// Keep the setuid/setgid bits and file capabilities archived
+        /// files have; otherwise they are cleared. Each such file is
+        /// recorded in the audit log either way
+        #[arg(long, conflicts_with = "list")]
+        allow_privileged: bool,
+
+        /// Also write a report of the restore to this `.md` or `.html` file
+        #[arg(long, value_name = "PATH", conflicts_with = "list")]
+        report: Option<std::path::PathBuf>,
+    },
+    /// Work with the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Write an emergency sheet: archive locations, checksums, GPG key IDs
+    /// and restore steps, for a password manager or a printout
+    EmergencySheet {
+        /// Save the sheet GPG-encrypted (ASCII armor) to this file, which
+        /// must be outside the backup directories
+        #[arg(short, long, conflicts_with = "print")]
+        output: Option<std::path::PathBuf>,
+
+        /// Print the sheet as plain text, to paste into a password manager
+        #[arg(long)]
+        print: bool,
+    },
+    /// Split the archive passphrase into Shamir shares kept in different
+    /// places, or recover it from them
+    Shares {
+        #[command(subcommand)]
+        command: SharesCommands,
+    },
+    /// Check the NFS and SMB shares: server reachable, export offered,
+    /// mount responding; fails when any needs attention
+    Mounts,
+    /// Keep the `nfs_sync` paths in step with the NFS share
+    Sync {
+        #[command(subcommand)]
+        command: SyncCommands,
+    },
+    /// Work with a single archive
+    Archive {
+        #[command(subcommand)]
+        command: ArchiveCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ArchiveCommands {
+    /// Mount an archive read-only with FUSE, to browse, grep and copy
+    /// files with the usual tools; unmounted on Ctrl-C
+    Mount {
+        /// Local archive, encrypted or not
+        archive: std::path::PathBuf,
+        /// Empty directory to mount it on
+        mountpoint: std::path::PathBuf,
+    },
+    /// List what was modified, deleted or added under an archive's items
+    /// since it was made, by comparing it with the files on disk now
+    Changes {
+        archive: std::path::PathBuf,
+    },
+    /// Write Reed-Solomon parity beside an archive, so damage to it can
+    /// later be repaired
+    Parity {
+        archive: std::path::PathBuf,
+        /// Percentage of the archive's blocks the parity can rebuild;
+        /// defaults to `verification.parity_percent`, else 10
+        #[arg(long)]
+        percent: Option<u32>,
+    },
+    /// Check an archive against its parity and rebuild damaged blocks
+    Repair {
+        archive: std::path::PathBuf,
+        /// Only report damage; change nothing
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SyncCommands {
+    /// Copy what changed locally to the share
+    Push,
+    /// Show when the share was last synced and what a push would copy
+    Status,
+    /// Copy the share's files into the home directory, keeping newer
+    /// local files
+    Pull,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum SharesCommands {
+    /// Write the archive passphrase as `--shares` files, each with a QR
+    /// code, any `--threshold` of which recover it
+    Split {
+        /// Shares needed to recover the passphrase
+        #[arg(short, long)]
+        threshold: u8,
+        /// Shares to write
+        #[arg(short, long)]
+        shares: u8,
+        /// Directory to write the share files to, created if missing
+        #[arg(short, long)]
+        output_dir: std::path::PathBuf,
+    },
+    /// Print the passphrase recovered from share files, to type into the
+    /// restore screen or pipe into `--password-stdin`
+    Combine {
+        /// Share files, or files holding a share's line scanned from its
+        /// QR code
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Translate the config given by `--config` into JSON, TOML or YAML,
+    /// chosen by the output file's extension
+    Convert {
+        /// Output file: `.json`, `.toml`, `.yaml` or `.yml`
+        output: std::path::PathBuf,
+
+        /// Replace the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+fn main() -> Result<()> {
     let cli = Cli::parse();
+    // Before the runtime starts its worker threads: taking the passphrase
+    // out of the environment is only sound while nothing else can read it
+    let passphrase = core::security::PassphraseSource::from_args(cli.password_file.clone(), cli.password_stdin, cli.password_share.clone());
+    tokio::runtime::Runtime::new()
+        .context("Failed to start the async runtime")?
+        .block_on(run(cli, passphrase))
+}
+
+async fn run(cli: Cli, passphrase: core::security::PassphraseSource) -> Result<()> {
+    // The screens and the emergency sheet's passphrase prompt own the
+    // terminal; JSON output is only for commands that run unattended
+    if cli.json && matches!(cli.command, None | Some(Commands::Dr | Commands::Backup | Commands::EmergencySheet { .. } | Commands::Shares { .. } | Commands::ReinstallPackages { .. } | Commands::ReplayDatabases { .. } | Commands::DatabasePassword { .. } | Commands::Archive { .. } | Commands::Mounts)) {
+        anyhow::bail!("--json is only available for find, search, sync-catalog, sync, run-backup, restore, rollback-restore, repack, maintain, scrub, vault, history and config convert");
+    }
     
     // Check if we're running the disaster recovery TUI
     if let Some(Commands::Dr) = &cli.command {
         // Run disaster recovery TUI with simpler setup
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("error"))
-            .init();
-        return disaster_recovery::run_tui();
+        init_logging(&cli, "error")?;
+        return disaster_recovery::run_tui(&cli.config, cli.ascii || !ui::terminal::supports_unicode());
+    }
+    
+    if let Some(Commands::Find { pattern, limit }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "find");
+        return events.finish(run_find(pattern, *limit, &events));
+    }
+    
+    if let Some(Commands::Search { query, limit }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "search");
+        return events.finish(run_search(query, *limit, &events));
+    }
+    
+    if let Some(Commands::Mounts) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_mounts().await;
+    }
+
+    if let Some(Commands::Sync { command }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let operation = match command {
+            SyncCommands::Push => backend::nfs_sync::SyncOperation::Push,
+            SyncCommands::Status => backend::nfs_sync::SyncOperation::Status,
+            SyncCommands::Pull => backend::nfs_sync::SyncOperation::Pull,
+        };
+        let events = Events::start(cli.json, "sync");
+        return events.finish(run_nfs_sync(&cli.config, operation, &events).await);
+    }
+
+    if let Some(Commands::SyncCatalog) = &cli.command {
+        init_logging(&cli, "info")?;
+        let events = Events::start(cli.json, "sync-catalog");
+        return events.finish(run_sync_catalog(&cli.config, &events).await);
+    }
+    
+    if let Some(Commands::Config { command: ConfigCommands::Convert { output, force } }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "config-convert");
+        return events.finish(run_config_convert(&cli.config, output, *force, &events));
+    }
+    
+    if let Some(Commands::EmergencySheet { output, print }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_emergency_sheet(&cli.config, cli.output.as_deref(), output.as_deref(), *print).await;
+    }
+    
+    if let Some(Commands::Shares { command }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return match command {
+            SharesCommands::Split { threshold, shares, output_dir } => {
+                run_shares_split(&cli.config, &passphrase, *threshold, *shares, output_dir).await
+            }
+            SharesCommands::Combine { files } => run_shares_combine(files),
+        };
+    }
+    
+    if let Some(Commands::Repack { archive, compression, level, keep_original }) = &cli.command {
+        init_logging(&cli, "info")?;
+        let events = Events::start(cli.json, "repack");
+        return events.finish(run_repack(&cli.config, archive, compression, *level, *keep_original, &events).await);
+    }
+    
+    if let Some(Commands::Maintain { dry_run }) = &cli.command {
+        init_logging(&cli, "info")?;
+        let events = Events::start(cli.json, "maintain");
+        return events.finish(run_maintain(&cli.config, *dry_run, &events).await);
+    }
+    
+    if let Some(Commands::Scrub { all, remote }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "scrub");
+        return events.finish(run_scrub(&cli.config, *all, *remote, &events));
+    }
+    
+    if let Some(Commands::Vault { force }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "vault");
+        return events.finish(run_vault(&cli.config, *force, &passphrase, &events).await);
+    }
+    
+    if let Some(Commands::RunBackup { stdout: _, mode, encrypt, allow_unencrypted, report, ignore_window }) = &cli.command {
+        init_logging(&cli, "info")?;
+        let events = Events::start_on_stderr(cli.json, "run-backup");
+        let throttle = cli.throttle();
+        let backup = run_stdout_backup(
+            &cli.config,
+            cli.profile.as_deref(),
+            &throttle,
+            mode.as_deref(),
+            *encrypt,
+            *allow_unencrypted,
+            report.as_deref(),
+            *ignore_window,
+            &passphrase,
+            &events,
+        );
+        return events.finish(backup.await);
+    }
+    
+    if let Some(Commands::Restore { stdin: _, items, target, overwrite, skip_metadata, list, allow_privileged, report }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "restore");
+        let metadata = if *skip_metadata { RestoreMetadata::Skip } else { RestoreMetadata::Apply };
+        return events.finish(run_piped_restore(items, target.as_deref(), *overwrite, metadata, *list, *allow_privileged, report.as_deref(), passphrase, &events).await);
+    }
+    
+    if let Some(Commands::RollbackRestore) = &cli.command {
+        init_logging(&cli, "info")?;
+        let events = Events::start(cli.json, "rollback-restore");
+        return events.finish(run_rollback_restore(&events).await);
+    }
+    
+    if let Some(Commands::ReinstallPackages { archive, run }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_reinstall_packages(archive, *run, &passphrase).await;
+    }
+
+    if let Some(Commands::Archive { command: ArchiveCommands::Mount { archive, mountpoint } }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_archive_mount(archive, mountpoint, &passphrase).await;
+    }
+
+    if let Some(Commands::Archive { command: ArchiveCommands::Changes { archive } }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_archive_changes(&cli.config, archive, &passphrase).await;
+    }
+
+    if let Some(Commands::Archive { command: ArchiveCommands::Parity { archive, percent } }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_archive_parity(&cli.config, archive, *percent).await;
+    }
+
+    if let Some(Commands::Archive { command: ArchiveCommands::Repair { archive, check } }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_archive_repair(archive, *check).await;
+    }
+
+    if let Some(Commands::ReplayDatabases { archive, names }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_replay_databases(archive, names, &passphrase).await;
+    }
+
+    if let Some(Commands::DatabasePassword { account, forget }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        return run_database_password(account, *forget, &passphrase).await;
+    }
+    
+    if let Some(Commands::History { limit }) = &cli.command {
+        init_logging(&cli, "warn")?;
+        let events = Events::start(cli.json, "history");
+        return events.finish(run_history(*limit, &events));
     }
     
     // Initialize logging for backup UI
-    let log_level = if cli.debug { "debug" } else { "info" };
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
-        .init();
+    init_logging(&cli, "info")?;
     
     info!("Starting Backup UI v{}", env!("CARGO_PKG_VERSION"));
     debug!("Debug logging enabled");
     
-    // Load configuration
-    let config = AppConfig::load(&cli.config, cli.output)?;
+    // Load configuration, or set up a new one when there is none anywhere
+    let first_run = core::config::BackupConfig::find_config_file(std::path::Path::new(&cli.config)).is_err();
+    let throttle = cli.throttle();
+    let mut config = if first_run {
+        info!("No config found; starting the first-run wizard");
+        AppConfig::first_run(&cli.config, cli.output)?
+    } else {
+        AppConfig::load(&cli.config, cli.output)?
+    };
+    config.throttle = throttle;
+    config.palette = cli.theme;
+    config.ascii = cli.ascii;
     debug!("Configuration loaded successfully");
     
     // Initialize application
     let mut app = App::new(config)?;
+    if first_run {
+        app.start_first_run();
+    } else if let Some(profile) = &cli.profile {
+        app.use_profile(profile)?;
+    }
     debug!("Application initialized");
     
     // Initialize terminal
@@ -98,6 +631,11 @@ async fn main() -> Result<()> {
         // If we already had an error, log the cleanup error but return the original
     }
     
+    // Left in the scrollback, and in the log file for unattended runs
+    let summary = app.session.summary(app.session.elapsed(), core::logging::warnings_logged());
+    println!("{}", summary);
+    info!(target: core::logging::FILE_ONLY_TARGET, "{}", summary);
+    
     match result {
         Ok(_) => {
             info!("Application exited successfully");
@@ -110,16 +648,1589 @@ async fn main() -> Result<()> {
     }
 }
 
+/// Console logging, plus the log file when the config or `--log-file` asks
+/// for one. With `--json` console records go to stdout as events, so
+/// warnings reach scripts with everything else.
+fn init_logging(cli: &Cli, default_level: &str) -> Result<()> {
+    let log_level = if cli.debug { "debug" } else { default_level };
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level));
+    // run-backup writes the archive itself to stdout
+    if cli.json && !matches!(cli.command, Some(Commands::RunBackup { .. })) {
+        builder
+            .target(env_logger::Target::Stdout)
+            .format(|buf, record| {
+                use std::io::Write;
+                writeln!(buf, "{}", Event::from_record(record).to_line())
+            });
+    } else if cli.json {
+        builder
+            .format(|buf, record| {
+                use std::io::Write;
+                writeln!(buf, "{}", Event::from_record(record).to_line())
+            });
+    }
+
+    // Read without upgrading: an upgrade saves the config, which should be
+    // logged once the logger is in place
+    let logging = core::config::BackupConfig::find_config_file(std::path::Path::new(&cli.config))
+        .and_then(|path| core::config::BackupConfig::read(&path))
+        .ok()
+        .and_then(|config| config.logging);
+    let file = core::logging::FileLogging::resolve(logging.as_ref(), cli.log_file.as_ref(), cli.debug)?;
+    core::logging::init(builder, file)
+}
+
+fn run_find(pattern: &str, limit: usize, events: &Events) -> Result<()> {
+    let catalog = backend::catalog::Catalog::open_default()?;
+    let files = catalog.find_file(pattern, limit)?;
+
+    if events.json() {
+        for file in &files {
+            for version in &file.versions {
+                events.emit(Event::Found {
+                    path: file.path.clone(),
+                    archive: version.archive_path.display().to_string(),
+                    created: version.archive_created,
+                    size: Some(version.size),
+                    hostname: version.hostname.clone(),
+                    snippet: None,
+                });
+            }
+        }
+        let copies: usize = files.iter().map(|file| file.versions.len()).sum();
+        events.complete(serde_json::json!({ "files": files.len(), "copies": copies }));
+        return Ok(());
+    }
+
+    if files.is_empty() {
+        println!("No archived copies of '{}' found", pattern);
+        return Ok(());
+    }
+
+    for file in &files {
+        println!("{}", file.path);
+        for (i, version) in file.versions.iter().enumerate() {
+            println!(
+                "  {} {}  {:>10}  {}  {}{}",
+                if i == 0 { "*" } else { " " },
+                version.archive_created.format("%Y-%m-%d %H:%M"),
+                ui::terminal::format_bytes(version.size),
+                version.hostname.as_deref().unwrap_or("?"),
+                version.archive_path.display(),
+                if i == 0 { "  (newest)" } else { "" },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn run_history(limit: usize, events: &Events) -> Result<()> {
+    let log = core::audit::AuditLog::open_default();
+    let (entries, chain) = log.read()?;
+    let shown = &entries[entries.len().saturating_sub(limit)..];
+
+    if events.json() {
+        for entry in shown {
+            events.emit(Event::Audit(entry.clone()));
+        }
+    } else if entries.is_empty() {
+        println!("The audit log at {} is empty", core::audit::AuditLog::default_path().display());
+    } else {
+        for entry in shown {
+            let result = entry.result();
+            println!(
+                "{}  {:<20} {:<16} {}{}",
+                entry.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                entry.actor,
+                entry.action,
+                entry.target,
+                if result.is_empty() { String::new() } else { format!("  [{}]", result) },
+            );
+            if let Some(operation) = &entry.operation {
+                println!(
+                    "    {} mode, {} item(s), destination {}, checksum {}",
+                    operation.mode,
+                    operation.items.len(),
+                    operation.destination.as_deref().unwrap_or("-"),
+                    operation.archive_checksum.as_deref().unwrap_or("not recorded"),
+                );
+            }
+        }
+    }
+
+    match chain {
+        core::audit::ChainStatus::Broken { entry } => {
+            anyhow::bail!(
+                "Audit log integrity chain is broken at entry {} of {}: an entry was edited, inserted or removed",
+                entry + 1,
+                entries.len()
+            )
+        }
+        core::audit::ChainStatus::Intact { unchained } => {
+            if events.json() {
+                events.complete(serde_json::json!({
+                    "entries": entries.len(),
+                    "shown": shown.len(),
+                    "chain": "intact",
+                    "unchained": unchained,
+                }));
+            } else if !entries.is_empty() {
+                println!();
+                println!("Integrity chain intact over {} entries", entries.len() - unchained);
+                if unchained > 0 {
+                    println!("{} older entries predate the chain and can't be checked", unchained);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_search(query: &str, limit: usize, events: &Events) -> Result<()> {
+    let catalog = backend::catalog::Catalog::open_default()?;
+    let hits = catalog.search_all(query, limit)?;
+
+    if events.json() {
+        for hit in &hits {
+            events.emit(Event::Found {
+                path: hit.file.item_path.clone(),
+                archive: hit.file.archive_path.display().to_string(),
+                created: hit.file.archive_created,
+                size: Some(hit.file.size),
+                hostname: hit.file.hostname.clone(),
+                snippet: hit.snippet.clone(),
+            });
+        }
+        events.complete(serde_json::json!({ "matches": hits.len() }));
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No archived files match '{}'", query);
+        return Ok(());
+    }
+
+    for hit in &hits {
+        let file = &hit.file;
+        println!(
+            "{}  {}  {}  {}",
+            file.archive_created.format("%Y-%m-%d %H:%M"),
+            file.hostname.as_deref().unwrap_or("?"),
+            file.item_path,
+            file.archive_path.display(),
+        );
+        if let Some(snippet) = &hit.snippet {
+            println!("    {}", snippet);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_sync_catalog(config_path: &str, events: &Events) -> Result<()> {
+    let config = core::config::BackupConfig::load(config_path)?;
+    let Some(sync_config) = config.catalog_sync else {
+        anyhow::bail!("No catalog_sync location configured in {}", config_path);
+    };
+
+    let mut catalog = backend::catalog::Catalog::open_default()?;
+    let summary = backend::catalog_sync::CatalogSync::new(sync_config.resolved_path())
+        .sync(&mut catalog)
+        .await?;
+    if events.json() {
+        events.complete(serde_json::json!({ "archives": summary.archives, "hosts": summary.hosts }));
+        return Ok(());
+    }
+    println!(
+        "Imported {} archives from {} other machines",
+        summary.archives, summary.hosts
+    );
+    Ok(())
+}
+
+async fn run_mounts() -> Result<()> {
+    use backend::mount_health::{self, MountHealth};
+
+    let checks = tokio::task::spawn_blocking(mount_health::check_all).await?;
+    if checks.is_empty() {
+        println!("No NFS or SMB shares are mounted or listed in /etc/fstab");
+        return Ok(());
+    }
+    for check in &checks {
+        println!("{:<18} {} ({}): {}", check.health.label(), check.mount.mount_point.display(), check.mount.source, check.detail);
+    }
+    let problems = checks.iter().filter(|check| check.health != MountHealth::Healthy).count();
+    if problems > 0 {
+        anyhow::bail!("{} of {} shares need attention; `backup-ui dr` can remount them", problems, checks.len());
+    }
+    Ok(())
+}
+
+async fn run_nfs_sync(config_path: &str, operation: backend::nfs_sync::SyncOperation, events: &Events) -> Result<()> {
+    use backend::nfs_sync::{NfsSync, SyncOperation};
+
+    let config = core::config::BackupConfig::load(config_path)?;
+    let Some(sync_config) = config.nfs_sync else {
+        anyhow::bail!("No nfs_sync share configured in {}", config_path);
+    };
+    let sync = NfsSync::from_config(&sync_config)?;
+    if !events.json() {
+        let lines = tokio::task::spawn_blocking(move || sync.run_in_terminal(operation)).await??;
+        for line in lines {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    let summary = tokio::task::spawn_blocking(move || -> Result<serde_json::Value> {
+        Ok(match operation {
+            SyncOperation::Push => serde_json::to_value(sync.push(|_| {})?)?,
+            SyncOperation::Pull => serde_json::to_value(sync.pull(|_| {})?)?,
+            SyncOperation::Status => serde_json::to_value(sync.status()?)?,
+        })
+    })
+    .await??;
+    events.complete(summary);
+    Ok(())
+}
+
+fn run_config_convert(config_path: &str, output: &std::path::Path, force: bool, events: &Events) -> Result<()> {
+    use core::config::{BackupConfig, ConfigFormat};
+
+    let Some(format) = ConfigFormat::from_path(output) else {
+        anyhow::bail!("Give the output a .json, .toml, .yaml or .yml extension to choose its format");
+    };
+    if output.exists() && !force {
+        anyhow::bail!("{} already exists; pass --force to replace it", output.display());
+    }
+
+    let source = BackupConfig::find_config_file(std::path::Path::new(config_path))?;
+    let config = BackupConfig::read(&source)?;
+    let content = format.render(&config)?;
+    // Parse the result back so a lossy conversion is caught before it's saved
+    format.parse(&content)?;
+
+    // The config maps out where credentials live, so keep it private
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(output)?, content.as_bytes())?;
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "source": source.display().to_string(),
+            "output": output.display().to_string(),
+            "format": format.as_str(),
+        }));
+        return Ok(());
+    }
+    println!("Converted {} to {} in {}", source.display(), format.as_str(), output.display());
+    Ok(())
+}
+
+async fn run_emergency_sheet(
+    config_path: &str,
+    backup_output: Option<&str>,
+    output: Option<&std::path::Path>,
+    print: bool,
+) -> Result<()> {
+    use backend::emergency_sheet::{self, EmergencySheet};
+
+    if output.is_none() && !print {
+        anyhow::bail!("Choose where the sheet goes: --output <file> (encrypted) or --print (plain text)");
+    }
+
+    let catalog_sync = core::config::BackupConfig::load(config_path)
+        .ok()
+        .and_then(|config| config.catalog_sync)
+        .map(|sync| sync.resolved_path());
+    let catalog = backend::catalog::Catalog::open_default()?;
+    let sheet = EmergencySheet::gather(&catalog, catalog_sync).await?;
+
+    if print {
+        eprintln!("Warning: the sheet below is unencrypted. Paste it into a password manager and clear your scrollback.");
+        print!("{}", sheet.render());
+        return Ok(());
+    }
+    let Some(output) = output else {
+        return Ok(());
+    };
+
+    // A sheet stored with the backups is lost along with them
+    let mut backup_dirs = sheet.archive_dirs();
+    if let Some(dir) = backup_output.filter(|dir| !dir.starts_with("rclone:")) {
+        backup_dirs.push(std::path::PathBuf::from(dir));
+    }
+    let target = output
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(std::path::Path::new("."))
+        .canonicalize()?;
+    for dir in backup_dirs {
+        if dir.canonicalize().map(|dir| target.starts_with(dir)).unwrap_or(false) {
+            anyhow::bail!(
+                "{} is inside the backup location; store the sheet somewhere that survives losing the backups",
+                output.display()
+            );
+        }
+    }
+
+    let password = core::security::read_password("Passphrase for the sheet: ")?;
+    let confirm = core::security::read_password("Repeat passphrase: ")?;
+    if password.as_bytes() != confirm.as_bytes() {
+        anyhow::bail!("Passphrases do not match");
+    }
+    if core::security::validate_password_strength(&password).score < 60 {
+        eprintln!("Warning: weak passphrase. The sheet tells anyone who decrypts it where your backups are.");
+    }
+
+    let encrypted = emergency_sheet::encrypt(&sheet.render(), &password).await?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(output)?;
+    std::io::Write::write_all(&mut file, &encrypted)?;
+
+    println!(
+        "Encrypted emergency sheet for {} archives written to {}",
+        sheet.archives.len(),
+        output.display()
+    );
+    println!("Decrypt with: gpg --decrypt {}", output.display());
+    Ok(())
+}
+
+async fn run_shares_split(
+    config_path: &str,
+    passphrase: &core::security::PassphraseSource,
+    threshold: u8,
+    count: u8,
+    output_dir: &std::path::Path,
+) -> Result<()> {
+    use anyhow::Context;
+    use core::secret_sharing;
+
+    let config = core::config::BackupConfig::load(config_path)?;
+    let password = archive_passphrase(&config, passphrase, "archives", "the shares protect nothing a guess can open").await?;
+    let shares = secret_sharing::split(password.as_bytes(), threshold, count)?;
+    // Checked before anything is written
+    if secret_sharing::combine(&shares[..usize::from(threshold)])?.as_bytes() != password.as_bytes() {
+        anyhow::bail!("The shares don't recover the passphrase");
+    }
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(output_dir).with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+    let hostname = backend::catalog::local_hostname();
+    let created = chrono::Local::now();
+    let mut written = Vec::new();
+    for share in &shares {
+        let path = output_dir.join(format!("{}-share-{}-of-{}.txt", hostname, share.index, count));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("Failed to write {}; move earlier shares away first", path.display()))?;
+        std::io::Write::write_all(&mut file, share.document(count, &hostname, created)?.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+
+    let details = format!("{} of {} shares", threshold, count);
+    if let Err(e) = core::audit::AuditLog::open_default().record("shares_split", &output_dir.display().to_string(), &details) {
+        error!("Failed to write audit log: {}", e);
+    }
+    for path in &written {
+        println!("{}", path.display());
+    }
+    println!(
+        "Wrote {} shares; any {} recover the passphrase. Print or copy each to a different place, then delete {}",
+        count,
+        threshold,
+        output_dir.display()
+    );
+    Ok(())
+}
+
+fn run_shares_combine(files: &[std::path::PathBuf]) -> Result<()> {
+    use core::secret_sharing::{self, Share};
+    use std::io::IsTerminal;
+
+    let shares = files.iter().map(|path| Share::read_file(path)).collect::<Result<Vec<_>>>()?;
+    let password = secret_sharing::combine(&shares)?;
+    if std::io::stdout().is_terminal() {
+        eprintln!("Warning: the passphrase below stays in your scrollback; clear it once you have used it.");
+    }
+    let mut stdout = std::io::stdout().lock();
+    std::io::Write::write_all(&mut stdout, password.as_bytes())?;
+    std::io::Write::write_all(&mut stdout, b"\n")?;
+    Ok(())
+}
+
+async fn run_rollback_restore(events: &Events) -> Result<()> {
+    use backend::restore_snapshot::PreRestoreSnapshot;
+
+    let Some(snapshot) = PreRestoreSnapshot::latest(&PreRestoreSnapshot::default_dir())? else {
+        anyhow::bail!("No pre-restore snapshot to roll back to");
+    };
+
+    let mut done = 0;
+    snapshot
+        .rollback_reporting(|path| {
+            done += 1;
+            events.emit(Event::Progress {
+                phase: "rollback".to_string(),
+                path: path.display().to_string(),
+                done,
+                total: Some(snapshot.entries.len()),
+            });
+        })
+        .await?;
+    let details = format!("{} paths from restore of {}", snapshot.entries.len(), snapshot.archive);
+    if let Err(e) = core::audit::AuditLog::open_default().record("restore_rollback", &snapshot.archive, &details) {
+        error!("Failed to write audit log: {}", e);
+    }
+    if events.json() {
+        events.complete(serde_json::json!({
+            "archive": snapshot.archive,
+            "paths": snapshot.entries.len(),
+            "snapshot_taken": snapshot.taken,
+        }));
+        return Ok(());
+    }
+    println!(
+        "Rolled back the restore of {} from {}",
+        snapshot.archive,
+        snapshot.taken.format("%Y-%m-%d %H:%M:%S")
+    );
+    Ok(())
+}
+
+async fn run_reinstall_packages(
+    archive: &std::path::Path,
+    run: bool,
+    passphrase: &core::security::PassphraseSource,
+) -> Result<()> {
+    use anyhow::Context;
+
+    let password = archive_password(archive, passphrase)?;
+    let (script, results) = backend::packages::write_reinstall_script(archive, password.as_ref()).await?;
+    for result in results.iter().filter(|result| result.status == core::types::CheckStatus::Pass) {
+        println!("{}: {}", result.name, result.detail);
+    }
+    if !run {
+        println!("Wrote {}; review it, then run: sh {}", script.display(), script.display());
+        return Ok(());
+    }
+
+    println!("Running {}", script.display());
+    let status = tokio::process::Command::new("sh")
+        .arg(&script)
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", script.display()))?;
+    if !status.success() {
+        anyhow::bail!("{} failed ({}); it can be run again once fixed", script.display(), status);
+    }
+    Ok(())
+}
+
+/// Passphrase of an existing archive, from `--password-file` and the like
+/// or else the terminal; None for archives that aren't encrypted
+fn archive_password(
+    archive: &std::path::Path,
+    passphrase: &core::security::PassphraseSource,
+) -> Result<Option<core::security::SecurePassword>> {
+    if archive.extension().is_some_and(|e| e == "gpg") {
+        match passphrase.read()? {
+            Some(password) => Ok(Some(password)),
+            None => core::security::read_password_from_terminal("Archive passphrase: ").map(Some),
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+async fn run_archive_mount(
+    archive: &std::path::Path,
+    mountpoint: &std::path::Path,
+    passphrase: &core::security::PassphraseSource,
+) -> Result<()> {
+    let password = archive_password(archive, passphrase)?;
+    let stop = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    backend::archive_mount::mount_archive(archive, password.as_ref(), mountpoint, stop).await
+}
+
+async fn run_archive_changes(
+    config_path: &str,
+    archive: &std::path::Path,
+    passphrase: &core::security::PassphraseSource,
+) -> Result<()> {
+    use anyhow::Context;
+    let config = core::config::BackupConfig::load(config_path)?;
+    // Archives found on disk carry their mode in the name
+    let mode = if archive.file_name().is_some_and(|name| name.to_string_lossy().contains("secure")) {
+        core::types::BackupMode::Secure
+    } else {
+        core::types::BackupMode::Complete
+    };
+    let exclusions = core::exclusions::ExclusionSet::for_mode(&config, &mode)?;
+    let password = archive_password(archive, passphrase)?;
+    let members = backend::live_changes::members(archive, password.as_ref()).await?;
+    let home_dir = dirs::home_dir().context("Could not find the home directory")?;
+    let changes = tokio::task::spawn_blocking(move || backend::live_changes::compare(&members, &home_dir, &exclusions)).await?;
+
+    for changed in &changes.changed {
+        println!("{} {}  {}", changed.change.marker(), changed.path, changed.detail);
+    }
+    println!("{}", changes.summary());
+    Ok(())
+}
+
+async fn run_archive_parity(config_path: &str, archive: &std::path::Path, percent: Option<u32>) -> Result<()> {
+    let percent = match percent {
+        Some(percent) => percent,
+        None => core::config::BackupConfig::load(config_path)?.parity_percent().unwrap_or(10),
+    };
+    let path = archive.to_path_buf();
+    let header = tokio::task::spawn_blocking(move || backend::parity::create(&path, percent)).await??;
+    println!("Wrote {} ({})", backend::parity::path_for(archive).display(), header.describe());
+    Ok(())
+}
+
+async fn run_archive_repair(archive: &std::path::Path, check_only: bool) -> Result<()> {
+    let path = archive.to_path_buf();
+    let found = if check_only {
+        tokio::task::spawn_blocking(move || backend::parity::check(&path)).await??
+    } else {
+        tokio::task::spawn_blocking(move || backend::parity::repair(&path)).await??
+    };
+    println!("{}: {}", archive.display(), found.describe());
+    if found.is_intact() {
+        return Ok(());
+    }
+    if check_only {
+        anyhow::bail!("{} is damaged", archive.display());
+    }
+
+    let details = found.describe();
+    if let Err(e) = core::audit::AuditLog::open_default().record("archive_repair", &archive.to_string_lossy(), &details) {
+        error!("Failed to write audit log: {}", e);
+    }
+    println!("Repaired {}", archive.display());
+    Ok(())
+}
+
+async fn run_replay_databases(
+    archive: &std::path::Path,
+    names: &[String],
+    passphrase: &core::security::PassphraseSource,
+) -> Result<()> {
+    let password = archive_password(archive, passphrase)?;
+    let mut databases = backend::database_dumps::read_manifest(archive, password.as_ref()).await?;
+    if !names.is_empty() {
+        if let Some(unknown) = names.iter().find(|name| !databases.iter().any(|database| &database.name == *name)) {
+            let dumped: Vec<&str> = databases.iter().map(|database| database.name.as_str()).collect();
+            anyhow::bail!("{} has no dump of {}; it has {}", archive.display(), unknown, dumped.join(", "));
+        }
+        databases.retain(|database| names.contains(&database.name));
+    }
+
+    let results = backend::database_dumps::replay(archive, password.as_ref(), &databases).await;
+    let mut failed = 0;
+    for result in &results {
+        println!("{}: {}", result.name, result.detail);
+        if result.status == core::types::CheckStatus::Fail {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} databases were not replayed", failed, results.len());
+    }
+    Ok(())
+}
+
+async fn run_database_password(account: &str, forget: bool, passphrase: &core::security::PassphraseSource) -> Result<()> {
+    let account = account.to_string();
+    if forget {
+        tokio::task::spawn_blocking(move || backend::database_dumps::forget_password(&account)).await??;
+        println!("Removed the database password from the system keyring");
+        return Ok(());
+    }
+    let password = match passphrase.read()? {
+        Some(password) => password,
+        None => core::security::read_password_from_terminal("Database password: ")?,
+    };
+    tokio::task::spawn_blocking(move || backend::database_dumps::store_password(&account, &password)).await??;
+    println!("Stored the database password in the system keyring");
+    Ok(())
+}
+
+/// Passphrase to encrypt an archive with: the one given by
+/// `--password-file`, `--password-stdin` or `BACKUP_PASSPHRASE`, then the
+/// one remembered in the system keyring when the config enables it, so
+/// timers and cron need no terminal, or else asked for twice on the terminal
+async fn archive_passphrase(
+    config: &core::config::BackupConfig,
+    source: &core::security::PassphraseSource,
+    what: &str,
+    weak: &str,
+) -> Result<core::security::SecurePassword> {
+    let given = source.clone();
+    if let Some(password) = tokio::task::spawn_blocking(move || given.read()).await?? {
+        if core::security::validate_password_strength(&password).score < 60 {
+            log::warn!("Weak passphrase: {}", weak);
+        }
+        return Ok(password);
+    }
+    if let Some(account) = config.keyring_account().map(str::to_string) {
+        match tokio::task::spawn_blocking(move || core::keyring_store::load(&account)).await? {
+            Ok(Some(password)) => {
+                info!("Using the passphrase remembered in the system keyring");
+                return Ok(password);
+            }
+            Ok(None) => info!("No passphrase is remembered in the system keyring"),
+            Err(e) => log::warn!("{:#}", e),
+        }
+    }
+    let password = core::security::read_password_from_terminal(&format!("Passphrase for the {}: ", what))?;
+    let confirm = core::security::read_password_from_terminal("Repeat passphrase: ")?;
+    if password.as_bytes() != confirm.as_bytes() {
+        anyhow::bail!("Passphrases do not match");
+    }
+    if core::security::validate_password_strength(&password).score < 60 {
+        log::warn!("Weak passphrase: {}", weak);
+    }
+    Ok(password)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_stdout_backup(
+    config_path: &str,
+    profile: Option<&str>,
+    throttle: &core::config::ThrottleConfig,
+    mode: Option<&str>,
+    encrypt: bool,
+    allow_unencrypted: bool,
+    report_path: Option<&std::path::Path>,
+    ignore_window: bool,
+    passphrase: &core::security::PassphraseSource,
+    events: &Events,
+) -> Result<()> {
+    use anyhow::Context;
+    use backend::partial_run::{self, PartialRun};
+    use backend::report::{OperationReport, ReportFormat};
+    use backend::stdout_backup::{self, Cutoff, StreamSource};
+    use core::audit::{AuditEvent, AuditLog, Operation};
+    use core::exclusions::{self, ExclusionSet, FileList};
+    use core::types::BackupMode;
+    use std::io::IsTerminal;
+
+    if std::io::stdout().is_terminal() {
+        anyhow::bail!("Redirect stdout to a file or pipe it on, e.g. `backup-ui run-backup --stdout | ssh nas 'cat > laptop.tar.gz'`");
+    }
+    // Refused before the backup rather than after it
+    if let Some(path) = report_path {
+        ReportFormat::from_path(path)?;
+    }
+    let config = core::config::BackupConfig::load(config_path)?;
+    let profile = match profile {
+        Some(name) => {
+            let profile = config.profiles.get(name).with_context(|| format!("No profile named {} in {}", name, config_path))?;
+            Some((name, profile))
+        }
+        None => None,
+    };
+    // Everything the backup starts from here on inherits the priority
+    let throttle = profile
+        .and_then(|(_, profile)| profile.throttle.clone())
+        .unwrap_or_default()
+        .overridden_by(throttle);
+    if let Err(e) = backend::throttle::lower_priority(&throttle) {
+        log::warn!("Failed to lower the backup's priority: {}", e);
+    }
+    let mode = match mode.or_else(|| profile.and_then(|(_, profile)| profile.mode.as_deref())) {
+        None | Some("secure") => BackupMode::Secure,
+        Some("complete") => BackupMode::Complete,
+        Some(other) => anyhow::bail!("Unknown backup mode '{}'; use secure or complete", other),
+    };
+    if mode == BackupMode::Complete && !encrypt && !allow_unencrypted {
+        anyhow::bail!(
+            "Complete mode includes credentials; add --encrypt, or --allow-unencrypted if everything downstream of the pipe is trusted"
+        );
+    }
+
+    let window = config.backup_window.as_ref().filter(|_| !ignore_window);
+    let window_end = match window {
+        Some(window) => match window.end_after(chrono::Local::now().naive_local())? {
+            Some(end) => Some(end),
+            None => {
+                info!("Outside the backup window {}-{}; nothing written", window.start, window.end);
+                if events.json() {
+                    events.complete(serde_json::json!({ "skipped": true, "window": format!("{}-{}", window.start, window.end) }));
+                }
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let password = if encrypt {
+        Some(archive_passphrase(&config, passphrase, "archive", "the archive is only as safe as its passphrase once it leaves this machine").await?)
+    } else {
+        None
+    };
+
+    let mut items = match profile.filter(|(_, profile)| !profile.items.is_empty()) {
+        Some((name, profile)) => config.get_items_for_profile(name, profile, &mode),
+        None => config.get_items_for_mode(&mode),
+    };
+    if let Some((name, profile)) = profile {
+        items.extend(profile.database_items(name));
+    }
+
+    // Pick up where a run stopped by the end of the last window left off
+    let partial_path = partial_run::default_path();
+    let run_key = PartialRun::key(mode.as_str(), profile.map(|(name, _)| name));
+    let resumed = match PartialRun::load(&partial_path) {
+        Ok(run) => run.filter(|run| run.key == run_key),
+        Err(e) => {
+            log::warn!("Ignoring the unfinished run: {:#}", e);
+            None
+        }
+    };
+    if let Some(run) = &resumed {
+        items.retain(|item| !run.covers(&item.path));
+        info!("Resuming the backup started {}: {} items left", run.started.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"), items.len());
+    }
+
+    let home = dirs::home_dir().context("Could not find the home directory")?;
+    let (commands, files): (Vec<&core::types::BackupItem>, Vec<&core::types::BackupItem>) =
+        items.iter().partition(|item| item.is_generated());
+    let mut roots: Vec<std::path::PathBuf> = files.iter().map(|item| item.path.clone()).collect();
+    roots.push(std::path::PathBuf::from(".config/systemd/user"));
+    let top_roots = exclusions::top_level(&roots);
+    let exclusions = ExclusionSet::for_mode(&config, &mode)?;
+    let skipped = backend::browser_profiles::settle(&home, &files, &exclusions).await;
+    let exclusions = exclusions.with_paths(&skipped)?;
+    let list_home = home.clone();
+    let symlinks: Vec<_> = files.iter().map(|item| (item.path.clone(), item.symlinks)).collect();
+    let list = tokio::task::spawn_blocking(move || FileList::write(&exclusions, &list_home, &roots, &symlinks, None))
+        .await?
+        .context("Failed to list files for backup")?;
+    let system_state = backend::system_state::SystemStateDir::collect(&commands)
+        .await
+        .map_err(|e| log::warn!("Failed to collect system state: {}", e))
+        .ok();
+    info!("Streaming a {} backup of {} paths to stdout", mode.as_str(), list.entries);
+    // The unit directory added above isn't one of the items
+    let mut item_reports: Vec<core::types::ItemReport> = list.items.iter().take(files.len()).cloned().collect();
+    item_reports.extend(system_state.iter().flat_map(|state| state.command_reports.iter().cloned()));
+
+    let (compression, level) = (config.compression.compression(), config.compression.level());
+    let threads = config.compression_threads();
+    // The system state directory and the files in it
+    let total = list.entries
+        + system_state
+            .as_ref()
+            .and_then(|dir| std::fs::read_dir(dir.path()).ok())
+            .map_or(0, |files| files.count() + 1);
+    let progress = events.clone();
+    let started = std::time::Instant::now();
+    // Measured from now, since the passphrase prompt took its time
+    let cutoff_at = window_end.map(|end| {
+        started + (end - chrono::Local::now().naive_local()).to_std().unwrap_or_default()
+    });
+    let cutoff_roots = top_roots.clone();
+    let rate = throttle.bytes_per_second();
+    let streamed = tokio::task::spawn_blocking(move || {
+        let source = StreamSource {
+            home: &home,
+            file_list: list.path(),
+            transforms: &list.transforms,
+            system_state: system_state.as_ref().map(|dir| dir.path()),
+            cutoff: cutoff_at.map(|at| Cutoff { at, roots: &cutoff_roots }),
+        };
+        let mut out: Box<dyn std::io::Write> = match rate {
+            Some(rate) => Box::new(backend::throttle::RateLimited::new(std::io::stdout().lock(), rate)),
+            None => Box::new(std::io::stdout().lock()),
+        };
+        let show_bytes = !progress.json() && std::io::stderr().is_terminal();
+        let mut shown = std::time::Instant::now();
+        let streamed = stdout_backup::stream_archive(
+            &source,
+            compression,
+            level,
+            threads,
+            password.as_ref(),
+            &mut out,
+            |path, done| {
+                progress.emit(Event::Progress {
+                    phase: "archive".to_string(),
+                    path: path.to_string(),
+                    done,
+                    total: Some(total),
+                })
+            },
+            |bytes| {
+                if show_bytes && shown.elapsed() >= std::time::Duration::from_millis(250) {
+                    shown = std::time::Instant::now();
+                    eprint!("\r{} written", ui::terminal::format_bytes(bytes));
+                }
+            },
+        );
+        if show_bytes {
+            eprintln!();
+        }
+        streamed
+    })
+    .await?;
+
+    // Items under roots tar never reached wait for the next window
+    let unstarted = streamed.as_ref().map(|streamed| streamed.unstarted.clone()).unwrap_or_default();
+    let left: Vec<&core::types::BackupItem> = items
+        .iter()
+        .filter(|item| unstarted.iter().any(|root| item.path.starts_with(root)))
+        .collect();
+    if streamed.is_ok() {
+        let recorded = if left.is_empty() {
+            PartialRun::clear(&partial_path)
+        } else {
+            let mut run = resumed.clone().unwrap_or_else(|| PartialRun {
+                key: run_key.clone(),
+                started: chrono::Utc::now(),
+                done: Vec::new(),
+                parts: 0,
+            });
+            run.done.extend(top_roots.iter().filter(|root| !unstarted.contains(root)).cloned());
+            run.parts += 1;
+            run.save(&partial_path)
+        };
+        if let Err(e) = recorded {
+            error!("Failed to record the unfinished run: {:#}", e);
+        }
+    }
+    let item_reports: Vec<core::types::ItemReport> = item_reports
+        .into_iter()
+        .filter(|report| !left.iter().any(|item| item.path == report.path))
+        .collect();
+
+    let outcome = core::notifications::JobOutcome {
+        job: "Backup",
+        error: streamed.as_ref().err().map(|e| e.to_string()),
+        duration: started.elapsed(),
+        size: streamed.as_ref().ok().map(|streamed| streamed.bytes),
+        destination: Some("stdout".to_string()),
+    };
+    if let Some(shown) = core::notifications::notify(config.notifications.as_ref(), outcome) {
+        let _ = shown.join();
+    }
+    let audit = AuditEvent::new("backup_stdout", "stdout", &format!("{} items", items.len())).with_operation(Operation {
+        mode: mode.as_str().to_string(),
+        items: items.iter().map(|item| item.path.display().to_string()).collect(),
+        destination: Some("stdout".to_string()),
+        result: match &streamed {
+            Ok(_) if !left.is_empty() => format!("partial: stopped at the end of the backup window, {} items left", left.len()),
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("failed: {}", e),
+        },
+        archive_checksum: streamed.as_ref().ok().map(|streamed| streamed.sha256.clone()),
+        strategy: None,
+    });
+    if let Err(e) = AuditLog::open_default().append(audit) {
+        error!("Failed to write audit log: {}", e);
+    }
+    if let Some(path) = report_path {
+        let mut report = OperationReport::new("Backup", mode.as_str()).with_item_reports(&item_reports);
+        report.warnings.extend(
+            left.iter()
+                .map(|item| format!("{}: left for the next backup window", item.path.display())),
+        );
+        report.error = streamed.as_ref().err().map(|e| e.to_string());
+        report.duration = Some(started.elapsed());
+        report.archive_size = streamed.as_ref().ok().map(|streamed| streamed.bytes);
+        report.encrypted = encrypt;
+        report.checksum = streamed.as_ref().ok().map(|streamed| streamed.sha256.clone());
+        report.destinations = vec![core::types::DestinationResult {
+            destination: "stdout".to_string(),
+            outcome: streamed.as_ref().map(|_| "piped on".to_string()).map_err(|e| e.to_string()),
+        }];
+        match report.save(path) {
+            Ok(()) => info!("Report saved to {}", path.display()),
+            Err(e) => error!("Failed to save report: {}", e),
+        }
+    }
+    let streamed = streamed?;
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "mode": mode.as_str(),
+            "entries": streamed.entries,
+            "bytes": streamed.bytes,
+            "sha256": streamed.sha256,
+            "encrypted": encrypt,
+            "extension": format!("tar.{}{}", compression.extension(), if encrypt { ".gpg" } else { "" }),
+            "partial": !left.is_empty(),
+            "left": left.iter().map(|item| item.path.display().to_string()).collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+    eprintln!(
+        "Streamed a {} backup of {} paths ({}) to stdout",
+        mode.as_str(),
+        streamed.entries,
+        ui::terminal::format_bytes(streamed.bytes)
+    );
+    if !left.is_empty() {
+        eprintln!("Stopped at the end of the backup window; {} items are left for the next run", left.len());
+    }
+    eprintln!(
+        "Save it as a .tar.{}{} file; SHA-256 {}",
+        compression.extension(),
+        if encrypt { ".gpg" } else { "" },
+        streamed.sha256
+    );
+    Ok(())
+}
+
+async fn run_vault(
+    config_path: &str,
+    force: bool,
+    passphrase: &core::security::PassphraseSource,
+    events: &Events,
+) -> Result<()> {
+    use anyhow::Context;
+    use backend::catalog::{self, ArchiveIndex, Catalog};
+    use backend::stdout_backup::StreamSource;
+    use backend::vault;
+    use core::audit::{AuditEvent, AuditLog, Operation};
+    use core::exclusions::{ExclusionSet, FileList};
+    use core::types::{ArchiveInfo, BackupMode};
+
+    let config = core::config::BackupConfig::load(config_path)?;
+    let settings = config.credentials_vault.clone().unwrap_or_default();
+    let dir = settings.resolved_directory();
+    let host = catalog::local_hostname();
+    let existing = vault::list(&dir, &host)?;
+    if !force && !vault::due(&existing, settings.interval(), chrono::Local::now().naive_local()) {
+        let newest = existing.last().map(|vault| vault.path.display().to_string()).unwrap_or_default();
+        if events.json() {
+            events.complete(serde_json::json!({ "skipped": true, "latest": newest }));
+        } else {
+            println!("Not due yet; the last vault is {} (--force to make one anyway)", newest);
+        }
+        return Ok(());
+    }
+
+    let items = config.vault_items();
+    if items.is_empty() {
+        anyhow::bail!("No high-security items in complete mode in {}", config_path);
+    }
+    let home = dirs::home_dir().context("Could not find the home directory")?;
+    let roots: Vec<std::path::PathBuf> = items.iter().map(|item| item.path.clone()).collect();
+    let exclusions = ExclusionSet::for_mode(&config, &BackupMode::Complete)?;
+    let symlinks: Vec<_> = items.iter().map(|item| (item.path.clone(), item.symlinks)).collect();
+    let list = FileList::write(&exclusions, &home, &roots, &symlinks, None).context("Failed to list files for the vault")?;
+    // Most people have only some of the credential stores; missing ones aren't worth a warning
+    for report in list.items.iter().filter(|report| report.is_failure() && report.outcome != core::types::ItemOutcome::Missing) {
+        log::warn!("{}: {}", report.path.display(), report.status());
+    }
+    if list.entries == 0 {
+        anyhow::bail!("None of the {} high-security items exist", items.len());
+    }
+
+    let password = archive_passphrase(&config, passphrase, "vault", "the vault holds keys and credentials and is only as safe as its passphrase").await?;
+
+    let (compression, level) = (config.compression.compression(), config.compression.level());
+    let (vault_dir, vault_host) = (dir.clone(), host.clone());
+    let written = tokio::task::spawn_blocking(move || {
+        let source = StreamSource { home: &home, file_list: list.path(), transforms: &list.transforms, system_state: None, cutoff: None };
+        vault::write(&vault_dir, &vault_host, &source, compression, level, &password)
+    })
+    .await?;
+
+    let audit_items = items.iter().map(|item| item.path.display().to_string()).collect();
+    let audit_target = written.as_ref().map(|(path, _)| path.display().to_string()).unwrap_or_else(|_| dir.display().to_string());
+    let audit = AuditEvent::new("vault", &audit_target, &format!("{} items", items.len())).with_operation(Operation {
+        mode: BackupMode::Complete.as_str().to_string(),
+        items: audit_items,
+        destination: Some(dir.display().to_string()),
+        result: match &written {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("failed: {}", e),
+        },
+        archive_checksum: written.as_ref().ok().map(|(_, streamed)| streamed.sha256.clone()),
+        strategy: None,
+    });
+    if let Err(e) = AuditLog::open_default().append(audit) {
+        error!("Failed to write audit log: {}", e);
+    }
+    let (path, streamed) = written?;
+
+    // The contents can't be listed without the passphrase, so only the
+    // archive itself is cataloged
+    let mut catalog = Catalog::open_default()?;
+    let algorithm = config.hash_algorithm();
+    let hash_path = path.clone();
+    let checksum = tokio::task::spawn_blocking(move || algorithm.hash_file(&hash_path)).await??;
+    let info = ArchiveInfo {
+        path: path.clone(),
+        name: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        created: chrono::Utc::now(),
+        size: streamed.bytes,
+        mode: BackupMode::Complete,
+        encrypted: true,
+        description: "Credentials vault".to_string(),
+        items: roots.iter().map(|root| root.display().to_string()).collect(),
+        remote: None,
+        hostname: Some(host.clone()),
+    };
+    let index = ArchiveIndex { checksum, size: streamed.bytes, items: Vec::new(), texts: Vec::new() };
+    catalog.record_archive(&info, Some(&index))?;
+
+    let removed = vault::rotate(&dir, &host, settings.keep)?;
+    for old in &removed {
+        if let Err(e) = catalog.remove_archive(&old.to_string_lossy()) {
+            log::warn!("Failed to remove {} from the catalog: {}", old.display(), e);
+        }
+    }
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "skipped": false,
+            "archive": path.display().to_string(),
+            "entries": streamed.entries,
+            "bytes": streamed.bytes,
+            "sha256": streamed.sha256,
+            "removed": removed.iter().map(|old| old.display().to_string()).collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+    println!(
+        "Wrote {} ({} paths, {})",
+        path.display(),
+        streamed.entries,
+        ui::terminal::format_bytes(streamed.bytes)
+    );
+    if !removed.is_empty() {
+        println!("Deleted {} older vaults, keeping {}", removed.len(), settings.keep.max(1));
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_piped_restore(
+    items: &[String],
+    target: Option<&std::path::Path>,
+    overwrite: bool,
+    metadata: RestoreMetadata,
+    list: bool,
+    allow_privileged: bool,
+    report_path: Option<&std::path::Path>,
+    passphrase: core::security::PassphraseSource,
+    events: &Events,
+) -> Result<()> {
+    use backend::piped_restore::{self, PipedRestore};
+    use backend::report::{OperationReport, ReportFormat, ReportItem};
+    use core::audit::{AuditEvent, AuditLog, Operation};
+    use std::io::IsTerminal;
+
+    if std::io::stdin().is_terminal() {
+        anyhow::bail!("Pipe an archive in, e.g. `ssh nas cat backup.tar.gz | backup-ui restore --stdin`");
+    }
+    if matches!(passphrase, core::security::PassphraseSource::Stdin) {
+        anyhow::bail!("stdin carries the archive; pass the passphrase with --password-file or {}", core::security::PASSPHRASE_ENV);
+    }
+    if let Some(path) = report_path {
+        ReportFormat::from_path(path)?;
+    }
+    let target = match target {
+        Some(target) => target.to_path_buf(),
+        None => dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find the home directory"))?,
+    };
+    if !list && !target.is_dir() {
+        anyhow::bail!("{} is not a directory", target.display());
+    }
+
+    let options = PipedRestore { target: target.clone(), items: items.to_vec(), overwrite, list_only: list, metadata, allow_privileged };
+    let progress = events.clone();
+    let started = std::time::Instant::now();
+    let restored = tokio::task::spawn_blocking(move || {
+        let mut done = 0;
+        // stdin carries the archive, so the passphrase comes from the terminal
+        let password = || match passphrase.read()? {
+            Some(password) => Ok(password),
+            None => core::security::read_password_from_terminal("Archive passphrase: "),
+        };
+        piped_restore::restore(Box::new(std::io::stdin()), &options, password, |name, size| {
+            done += 1;
+            if list && !progress.json() {
+                println!("{:>10}  {}", ui::terminal::format_bytes(size), name);
+            }
+            progress.emit(Event::Progress {
+                phase: if list { "list" } else { "restore" }.to_string(),
+                path: name.to_string(),
+                done,
+                total: None,
+            });
+        })
+    })
+    .await?;
+
+    // Recognize the archive by its checksum, whichever algorithm it was
+    // cataloged with
+    let matched = restored.as_ref().ok().and_then(|summary| {
+        let catalog = backend::catalog::Catalog::open_default().ok()?;
+        [&summary.sha256, &summary.blake3].into_iter().find_map(|checksum| {
+            catalog.archive_with_checksum(checksum).ok().flatten().map(|name| (name, checksum.clone()))
+        })
+    });
+    if !list {
+        let (files, checksum) = match &restored {
+            Ok(summary) => (
+                if items.is_empty() { summary.top_level.iter().cloned().collect() } else { items.to_vec() },
+                Some(matched.as_ref().map(|(_, checksum)| checksum.clone()).unwrap_or_else(|| summary.sha256.clone())),
+            ),
+            Err(_) => (items.to_vec(), None),
+        };
+        let details = match &restored {
+            Ok(summary) => format!("{} files from stdin, {} kept, metadata {}", summary.files, summary.kept.len(), metadata.as_str()),
+            Err(_) => "from stdin".to_string(),
+        };
+        let audit = AuditEvent::new("restore_stdin", matched.as_ref().map(|(name, _)| name.as_str()).unwrap_or("stdin"), &details)
+            .with_operation(Operation {
+                mode: "piped".to_string(),
+                items: files,
+                destination: Some(target.display().to_string()),
+                result: match &restored {
+                    Ok(_) => "ok".to_string(),
+                    Err(e) => format!("failed: {}", e),
+                },
+                archive_checksum: checksum,
+                strategy: None,
+            });
+        if let Err(e) = AuditLog::open_default().append(audit) {
+            error!("Failed to write audit log: {}", e);
+        }
+        // Each setuid/setgid or capability file, as the restore screen
+        // records them
+        let decision = if allow_privileged { "kept" } else { "stripped" };
+        for entry in restored.iter().flat_map(|summary| summary.privileged.iter()) {
+            let path = target.join(&entry.path);
+            let details = format!("{} ({})", entry.describe(), decision);
+            if let Err(e) = AuditLog::open_default().record("restore_privileged", &path.to_string_lossy(), &details) {
+                error!("Failed to write audit log: {}", e);
+            }
+        }
+    }
+    // ssh and gpg refuse keys others can read; the archive's mode isn't
+    // known here, so whatever sensitive paths it put back are checked
+    let permission_fixes = match &restored {
+        Ok(summary) if !list => {
+            let paths: Vec<std::path::PathBuf> = summary.top_level.iter().map(std::path::PathBuf::from).collect();
+            backend::credential_checks::harden_permissions(&target, &paths)
+        }
+        _ => Vec::new(),
+    };
+    if let Some(path) = report_path {
+        let mut report = OperationReport::new("Restore", "piped");
+        report.error = restored.as_ref().err().map(|e| e.to_string());
+        report.duration = Some(started.elapsed());
+        report.archive = matched.as_ref().map(|(name, _)| name.clone());
+        if let Ok(summary) = &restored {
+            report.archive_size = Some(summary.archive_bytes);
+            report.encrypted = matches!(summary.format, Some(piped_restore::StreamFormat::Encrypted));
+            report.checksum = Some(matched.as_ref().map(|(_, checksum)| checksum.clone()).unwrap_or_else(|| summary.sha256.clone()));
+            report.items = summary
+                .top_level
+                .iter()
+                .map(|name| ReportItem { path: name.clone(), status: "restored".to_string(), files: None, bytes: None })
+                .collect();
+            report.warnings = summary
+                .kept
+                .iter()
+                .map(|kept| format!("{}: already existed, left as it was", kept))
+                .collect();
+            report.warnings.extend(permission_fixes.iter().map(|fix| format!("{}: {}", fix.name, fix.detail)));
+            report.warnings.extend(summary.privileged.iter().map(|entry| {
+                format!("{}: {} {}", entry.path, entry.describe(), if allow_privileged { "kept" } else { "cleared" })
+            }));
+            if matched.is_none() {
+                report.warnings.push("The archive is not in the catalog".to_string());
+            }
+        }
+        report.destinations = vec![core::types::DestinationResult {
+            destination: "Target".to_string(),
+            outcome: Ok(target.display().to_string()),
+        }];
+        match report.save(path) {
+            Ok(()) if !events.json() => println!("Report saved to {}", path.display()),
+            Ok(()) => {}
+            Err(e) => error!("Failed to save report: {}", e),
+        }
+    }
+    let summary = restored?;
+    if matched.is_none() {
+        log::warn!("The archive is not in the catalog; compare its SHA-256 {} with a checksum you trust", summary.sha256);
+    }
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "format": summary.format.map(|format| format.describe()),
+            "files": summary.files,
+            "bytes": summary.bytes,
+            "kept": summary.kept,
+            "replaced": summary.replaced,
+            "target": (!list).then(|| target.display().to_string()),
+            "archive_bytes": summary.archive_bytes,
+            "sha256": summary.sha256,
+            "catalog_archive": matched.map(|(name, _)| name),
+            "privileged": summary
+                .privileged
+                .iter()
+                .map(|entry| serde_json::json!({ "path": entry.path, "bits": entry.describe(), "kept": allow_privileged }))
+                .collect::<Vec<_>>(),
+            "permissions_fixed": permission_fixes
+                .iter()
+                .map(|fix| serde_json::json!({ "path": fix.name, "detail": fix.detail }))
+                .collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+    let format = summary.format.map(|format| format.describe()).unwrap_or("unknown");
+    if list {
+        println!("{} files ({}) in the {} archive", summary.files, ui::terminal::format_bytes(summary.bytes), format);
+    } else {
+        println!(
+            "Restored {} of {} files ({}) from the {} archive to {}",
+            summary.files - summary.kept.len(),
+            summary.files,
+            ui::terminal::format_bytes(summary.bytes),
+            format,
+            target.display()
+        );
+        if !summary.kept.is_empty() {
+            println!("Left {} existing files as they were; use --overwrite to replace them", summary.kept.len());
+        }
+        if summary.replaced > 0 {
+            println!("Replaced {} files; each previous version is beside it as <name>.~1~", summary.replaced);
+        }
+        for fix in &permission_fixes {
+            println!("{}: {}", fix.name, fix.detail);
+        }
+        for entry in &summary.privileged {
+            if allow_privileged {
+                println!("{}: restored {}; it runs with elevated privileges", entry.path, entry.describe());
+            } else {
+                println!("{}: {} cleared; use --allow-privileged to keep it", entry.path, entry.describe());
+            }
+        }
+    }
+    if let Some((name, _)) = matched {
+        println!("The archive matches {} in the catalog", name);
+    }
+    Ok(())
+}
+
+async fn run_repack(
+    config_path: &str,
+    archive: &std::path::Path,
+    compression: &str,
+    level: Option<u32>,
+    keep_original: bool,
+    events: &Events,
+) -> Result<()> {
+    use core::types::Compression;
+
+    let Some(compression) = Compression::from_name(compression) else {
+        anyhow::bail!("Unknown compression '{}'; use gzip, xz, bzip2 or zstd", compression);
+    };
+    let config = core::config::BackupConfig::load(config_path).ok();
+    let texts = config
+        .as_ref()
+        .and_then(|config| backend::text_index::TextIndexer::from_config(config).ok().flatten());
+    let algorithm = config.as_ref().map(|config| config.hash_algorithm()).unwrap_or_default();
+    let threads = config.as_ref().map_or(0, |config| config.compression_threads());
+    let notifications = config.and_then(|config| config.notifications);
+
+    let level = level.unwrap_or_else(|| compression.default_level());
+    let started = std::time::Instant::now();
+    let progress = events.clone();
+    let on_file = move |path: &str, done: usize, total: usize| {
+        progress.emit(Event::Progress {
+            phase: "verify".to_string(),
+            path: path.to_string(),
+            done,
+            total: Some(total),
+        });
+    };
+    let repacked = backend::repack::repack(archive, compression, level, threads, keep_original, texts, algorithm, on_file).await;
+    let outcome = core::notifications::JobOutcome {
+        job: "Repack",
+        error: repacked.as_ref().err().map(|e| e.to_string()),
+        duration: started.elapsed(),
+        size: repacked.as_ref().ok().map(|result| result.new_size),
+        destination: repacked.as_ref().ok().map(|result| result.archive.display().to_string()),
+    };
+    if let Some(shown) = core::notifications::notify(notifications.as_ref(), outcome) {
+        let _ = shown.join();
+    }
+    let result = repacked?;
+    let details = format!(
+        "{} files, {} -> {}",
+        result.files,
+        ui::terminal::format_bytes(result.old_size),
+        ui::terminal::format_bytes(result.new_size)
+    );
+    if let Err(e) = core::audit::AuditLog::open_default().record("archive_repack", &result.archive.to_string_lossy(), &details) {
+        error!("Failed to write audit log: {}", e);
+    }
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "original": archive.display().to_string(),
+            "archive": result.archive.display().to_string(),
+            "files": result.files,
+            "old_size": result.old_size,
+            "new_size": result.new_size,
+            "original_removed": result.original_removed,
+        }));
+        return Ok(());
+    }
+    println!("Repacked {} into {} ({})", archive.display(), result.archive.display(), details);
+    if result.original_removed {
+        println!("All files matched the original, which has been deleted");
+    } else {
+        println!("All files matched the original, which was kept");
+    }
+    Ok(())
+}
+
+async fn run_maintain(config_path: &str, dry_run: bool, events: &Events) -> Result<()> {
+    use backend::catalog::{self, Catalog};
+    use backend::tiering;
+
+    let config = core::config::BackupConfig::load(config_path)?;
+    if config.tiers.is_empty() {
+        anyhow::bail!("No tiers configured in {}", config_path);
+    }
+
+    let catalog = Catalog::open_default()?;
+    let archives: Vec<_> = catalog
+        .list_archives()?
+        .into_iter()
+        .filter(|archive| archive.remote.is_some() || archive.path.exists())
+        .collect();
+    let plan = tiering::plan(&archives, &config.tiers, &catalog::local_hostname(), chrono::Utc::now());
+    for hold in &plan.held {
+        log::warn!("Leaving {} where it is: {}", hold.archive.name, hold.reason);
+    }
+
+    let started = std::time::Instant::now();
+    let (mut moved, mut failed, mut bytes) = (0, 0, 0);
+    for (i, tier_move) in plan.moves.iter().enumerate() {
+        let archive = &tier_move.archive;
+        events.emit(Event::Progress {
+            phase: "tier".to_string(),
+            path: archive.name.clone(),
+            done: i,
+            total: Some(plan.moves.len()),
+        });
+        if dry_run {
+            if !events.json() {
+                println!("Would move {} to {}", archive.name, tier_move.to);
+            }
+            continue;
+        }
+        match tiering::migrate(&catalog, tier_move).await {
+            Ok(relocated) => {
+                moved += 1;
+                bytes += archive.size;
+                let details = format!("moved from {}", archive.path.display());
+                if let Err(e) = core::audit::AuditLog::open_default().record("archive_tier", &relocated.path.to_string_lossy(), &details) {
+                    error!("Failed to write audit log: {}", e);
+                }
+                if !events.json() {
+                    println!("Moved {} to {}", archive.name, tier_move.to);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                error!("Failed to move {}: {:#}", archive.name, e);
+            }
+        }
+    }
+
+    // Other machines find the archives at their new locations
+    if moved > 0 {
+        if let Some(sync_config) = &config.catalog_sync {
+            let mut catalog = catalog;
+            if let Err(e) = backend::catalog_sync::CatalogSync::new(sync_config.resolved_path()).sync(&mut catalog).await {
+                log::warn!("Catalog sync failed: {}", e);
+            }
+        }
+    }
+
+    if !dry_run {
+        let outcome = core::notifications::JobOutcome {
+            job: "Maintenance",
+            error: (failed > 0).then(|| format!("{} of {} archives could not be moved", failed, plan.moves.len())),
+            duration: started.elapsed(),
+            size: (moved > 0).then_some(bytes),
+            destination: None,
+        };
+        if let Some(shown) = core::notifications::notify(config.notifications.as_ref(), outcome) {
+            let _ = shown.join();
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} archives could not be moved", failed, plan.moves.len());
+    }
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "moved": moved,
+            "planned": plan.moves.len(),
+            "held": plan.held.len(),
+            "dry_run": dry_run,
+        }));
+        return Ok(());
+    }
+    if plan.moves.is_empty() {
+        println!("Every archive is in its tier");
+    } else if !dry_run {
+        println!("Moved {} archives ({})", moved, ui::terminal::format_bytes(bytes));
+    }
+    Ok(())
+}
+
+fn run_scrub(config_path: &str, all: bool, include_remote: bool, events: &Events) -> Result<()> {
+    use backend::catalog::{self, Catalog};
+    use backend::scrub::{self, ScrubOptions};
+
+    let config = core::config::BackupConfig::load(config_path)?;
+    let catalog = Catalog::open_default()?;
+    let options = ScrubOptions {
+        include_remote,
+        interval: (!all).then(|| config.scrub_interval()),
+    };
+
+    let started = std::time::Instant::now();
+    let report = scrub::scrub(&catalog, &catalog::local_hostname(), &options, chrono::Utc::now(), |archive, done, total| {
+        events.emit(Event::Progress {
+            phase: "scrub".to_string(),
+            path: catalog::archive_key(archive),
+            done,
+            total: Some(total),
+        });
+    })?;
+
+    let checked: Vec<_> = report.results.iter().filter(|r| r.outcome == scrub::ScrubOutcome::Intact || r.outcome.is_damaged()).collect();
+    let damaged: Vec<_> = report.damaged().collect();
+    let details = format!("{} checked, {} damaged", checked.len(), damaged.len());
+    if let Err(e) = core::audit::AuditLog::open_default().record("scrub", &format!("{} archives", report.results.len()), &details) {
+        error!("Failed to write audit log: {}", e);
+    }
+    for result in &damaged {
+        let target = catalog::archive_key(&result.archive);
+        if let Err(e) = core::audit::AuditLog::open_default().record("scrub_damaged", &target, &result.outcome.describe()) {
+            error!("Failed to write audit log: {}", e);
+        }
+    }
+
+    // Corruption is found by a timer, so tell whoever is at the desktop
+    let outcome = core::notifications::JobOutcome {
+        job: "Scrub",
+        error: (!damaged.is_empty()).then(|| format!("{} archive copies are corrupt or missing", damaged.len())),
+        duration: started.elapsed(),
+        size: None,
+        destination: None,
+    };
+    if let Some(shown) = core::notifications::notify(config.notifications.as_ref(), outcome) {
+        let _ = shown.join();
+    }
+
+    if events.json() {
+        for result in &report.results {
+            if result.outcome.is_damaged() {
+                log::warn!(
+                    "{}: {}; recover with: {}",
+                    catalog::archive_key(&result.archive),
+                    result.outcome.describe(),
+                    result.recovery_command().unwrap_or_else(|| "no intact copy found".to_string()),
+                );
+            }
+        }
+    } else {
+        for result in &report.results {
+            println!("{:<60} {}", catalog::archive_key(&result.archive), result.outcome.describe());
+        }
+        for result in &damaged {
+            match result.recovery_command() {
+                Some(command) => println!("\n{} has an intact copy; recover it with:\n  {}", result.archive.name, command),
+                None if backend::parity::path_for(&result.archive.path).exists() => println!(
+                    "\n{} has no intact copy elsewhere, but has parity; try:\n  backup-ui archive repair {}",
+                    result.archive.name,
+                    result.archive.path.display(),
+                ),
+                None => println!("\n{} has no intact copy at any other destination", result.archive.name),
+            }
+        }
+        println!(
+            "\nChecked {} archive copies, {} damaged; {} found intact in the last {} days were skipped",
+            checked.len(),
+            damaged.len(),
+            report.not_due,
+            config.scrub_interval().num_days(),
+        );
+    }
+    if !damaged.is_empty() {
+        anyhow::bail!("{} archive copies are corrupt or missing", damaged.len());
+    }
+
+    if events.json() {
+        events.complete(serde_json::json!({
+            "checked": checked.len(),
+            "skipped": report.results.len() - checked.len(),
+            "not_due": report.not_due,
+        }));
+    }
+    Ok(())
+}
+
 async fn run_app(app: &mut App, terminal: &mut Terminal) -> Result<()> {
     loop {
+        app.poll_background().await;
+
         // Draw UI
         terminal.draw(|f| app.render(f))?;
         
-        // Handle events
-        if let Some(event) = terminal.next_event().await? {
-            if app.handle_event(event).await? {
-                break; // Exit requested
-            }
+        // Handle input, or a tick when none came
+        let event = terminal.next_event().await?;
+        if app.handle_event(event).await? {
+            break; // Exit requested
         }
     }
     
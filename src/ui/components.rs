@@ -1,12 +1,16 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
 };
 
-use crate::core::types::{BackupItem, RestoreItem, SecurityLevel};
-use crate::ui::terminal::{format_bytes, truncate_text};
+use crate::core::item_tree::TreeRow;
+use crate::core::keymap::KeyHint;
+use crate::core::state::{BackupStep, StatusBar};
+use crate::core::types::{BackupItem, BackupMode, RestoreItem, SecurityLevel};
+use crate::ui::theme::{Icon, Theme, Tone};
+use crate::ui::terminal::{display_width, format_bytes, format_duration, truncate_text};
 
 /// Header component showing application title and current state
 pub fn render_header(
@@ -14,10 +18,11 @@ pub fn render_header(
     area: Rect,
     title: &str,
     subtitle: Option<&str>,
+    theme: &Theme,
 ) {
     let header_block = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Cyan));
+        .style(theme.accent());
 
     let header_text = if let Some(subtitle) = subtitle {
         vec![
@@ -25,7 +30,7 @@ pub fn render_header(
                 Span::styled(title, Style::default().add_modifier(Modifier::BOLD)),
             ]),
             Line::from(vec![
-                Span::styled(subtitle, Style::default().fg(Color::Gray)),
+                Span::styled(subtitle, theme.muted()),
             ]),
         ]
     } else {
@@ -42,33 +47,59 @@ pub fn render_header(
     frame.render_widget(header, area);
 }
 
+/// Steps of the backup flow on the bottom border of a header, the ones
+/// done, the current one and the ones to come each drawn differently.
+/// Secure backups aren't encrypted, so they skip the password step.
+pub fn render_backup_steps(
+    frame: &mut ratatui::Frame,
+    header: Rect,
+    current: Option<BackupStep>,
+    mode: &BackupMode,
+    theme: &Theme,
+) {
+    let Some(current) = current else {
+        return;
+    };
+    let mut spans = vec![Span::raw(" ")];
+    let steps = BackupStep::ALL
+        .into_iter()
+        .filter(|&step| step != BackupStep::Password || *mode == BackupMode::Complete);
+    for (i, step) in steps.enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" → ", theme.muted()));
+        }
+        let style = match step.cmp(&current) {
+            std::cmp::Ordering::Less => theme.style(Tone::Good),
+            std::cmp::Ordering::Equal => theme.highlight().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            std::cmp::Ordering::Greater => theme.muted(),
+        };
+        spans.push(Span::styled(step.label(), style));
+    }
+    spans.push(Span::raw(" "));
+
+    let area = Rect::new(header.x + 1, header.bottom().saturating_sub(1), header.width.saturating_sub(2), 1);
+    frame.render_widget(Paragraph::new(Line::from(spans)).alignment(Alignment::Center), area);
+}
+
 /// Footer component showing keyboard shortcuts and status
 pub fn render_footer(
     frame: &mut ratatui::Frame,
     area: Rect,
-    shortcuts: &[(&str, &str)],
+    hints: &[KeyHint],
     status: Option<&str>,
+    theme: &Theme,
 ) {
     let footer_block = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Gray));
+        .style(theme.muted());
 
-    let mut footer_spans = Vec::new();
-    
-    for (i, (key, desc)) in shortcuts.iter().enumerate() {
-        if i > 0 {
-            footer_spans.push(Span::raw(" | "));
-        }
-        footer_spans.push(Span::styled(*key, Style::default().fg(Color::Yellow)));
-        footer_spans.push(Span::raw(": "));
-        footer_spans.push(Span::raw(*desc));
-    }
+    let mut footer_spans = hint_spans(hints, theme);
 
     if let Some(status) = status {
         if !footer_spans.is_empty() {
             footer_spans.push(Span::raw(" | "));
         }
-        footer_spans.push(Span::styled(status, Style::default().fg(Color::Green)));
+        footer_spans.push(Span::styled(status, theme.style(Tone::Good)));
     }
 
     let footer = Paragraph::new(Line::from(footer_spans))
@@ -79,59 +110,145 @@ pub fn render_footer(
     frame.render_widget(footer, area);
 }
 
-/// Backup item list component with selection support
+/// Status bar on the bottom border of the screen: background work on the
+/// left, then the active profile, the destination's free space and the time
+/// on the right. The activities are cut short before they reach the right.
+pub fn render_status_bar(
+    frame: &mut ratatui::Frame,
+    screen: Rect,
+    status: &StatusBar,
+    profile: Option<&str>,
+    theme: &Theme,
+) {
+    let area = Rect::new(screen.x + 1, screen.bottom().saturating_sub(1), screen.width.saturating_sub(2), 1);
+
+    let mut right = Vec::new();
+    if let Some(profile) = profile {
+        right.push(format!("Profile: {}", profile));
+    }
+    if let Some(free) = status.free_space {
+        right.push(format!("{} free", format_bytes(free)));
+    }
+    right.push(status.clock.clone());
+    let right = format!(" {} ", right.join(" | "));
+    let right_width = (display_width(&right) as u16).min(area.width);
+    let right_area = Rect::new(area.right() - right_width, area.y, right_width, 1);
+    frame.render_widget(Paragraph::new(Span::styled(right, theme.muted())), right_area);
+
+    if status.activities.is_empty() {
+        return;
+    }
+    let left_width = area.width.saturating_sub(right_width + 1);
+    let left = truncate_text(&format!(" • {} ", status.activities.join(" • ")), left_width as usize);
+    frame.render_widget(
+        Paragraph::new(Span::styled(left, theme.style(Tone::Caution))),
+        Rect::new(area.x, area.y, left_width, 1),
+    );
+}
+
+/// `Key: what it does | ...` spans, for footers and dialogs
+pub fn hint_spans(hints: &[KeyHint], theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for (i, hint) in hints.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" | "));
+        }
+        spans.push(Span::styled(hint.keys.clone(), theme.highlight()));
+        spans.push(Span::raw(": "));
+        spans.push(Span::raw(hint.description));
+    }
+    spans
+}
+
+/// Backup item tree with selection support, folded into categories and
+/// applications
+#[allow(clippy::too_many_arguments)]
 pub fn render_backup_item_list(
     frame: &mut ratatui::Frame,
     area: Rect,
     items: &[BackupItem],
+    rows: &[TreeRow],
     selected_index: usize,
     scroll_offset: usize,
+    filter: &str,
+    theme: &Theme,
 ) {
-    let visible_items: Vec<ListItem> = items
+    let height = area.height.saturating_sub(2) as usize; // Account for borders
+    let scroll_offset = visible_offset(selected_index, scroll_offset, height);
+    let visible_items: Vec<ListItem> = rows
         .iter()
         .skip(scroll_offset)
-        .take(area.height.saturating_sub(2) as usize) // Account for borders
+        .take(height)
         .enumerate()
-        .map(|(i, item)| {
+        .map(|(i, row)| {
             let actual_index = scroll_offset + i;
             let is_selected = actual_index == selected_index;
-            
-            let checkbox = if item.selected { "☑" } else { "☐" };
-            let status_icon = if !item.exists {
-                "❌"
-            } else {
-                match item.security_level {
-                    SecurityLevel::High => "🔒",
-                    SecurityLevel::Medium => "⚠️",
-                    SecurityLevel::Low => " ",
+            let indent = "  ".repeat(row.depth());
+
+            let (item_text, style) = match row {
+                TreeRow::Group { label, items: members, expanded, .. } => {
+                    let selected = members.iter().filter(|&&index| items[index].selected).count();
+                    let size: u64 = members.iter().filter_map(|&index| items[index].size).sum();
+                    let checkbox = theme.checkbox(selected, members.len());
+                    let text = format!(
+                        "{}{} {} {} ({}/{}, {})",
+                        indent,
+                        theme.fold(*expanded),
+                        checkbox,
+                        label,
+                        selected,
+                        members.len(),
+                        format_bytes(size)
+                    );
+                    (text, Style::default().add_modifier(Modifier::BOLD))
                 }
-            };
-            
-            let size_text = item.size
-                .map(|s| format_bytes(s))
-                .unwrap_or_else(|| "N/A".to_string());
-            
-            let item_text = format!(
-                "{} {} {} ({}) - {}",
-                checkbox,
-                status_icon,
-                truncate_text(&item.name, 30),
-                size_text,
-                item.category
-            );
-            
-            let style = if is_selected {
-                Style::default().bg(Color::Blue).fg(Color::White)
-            } else if !item.exists {
-                Style::default().fg(Color::Red)
-            } else {
-                match item.security_level {
-                    SecurityLevel::High => Style::default().fg(Color::Red),
-                    SecurityLevel::Medium => Style::default().fg(Color::Yellow),
-                    SecurityLevel::Low => Style::default(),
+                TreeRow::Item { index, .. } => {
+                    let item = &items[*index];
+                    let checkbox = theme.checkbox(item.selected as usize, 1);
+                    let status_icon = if !item.exists {
+                        theme.icon(Icon::Missing)
+                    } else {
+                        match item.security_level {
+                            SecurityLevel::High => theme.icon(Icon::Locked),
+                            SecurityLevel::Medium => theme.icon(Icon::Warning),
+                            SecurityLevel::Low => " ",
+                        }
+                    };
+
+                    let size_text = item.size
+                        .map(|s| format_bytes(s))
+                        .unwrap_or_else(|| "N/A".to_string());
+
+                    let tag = if !item.exists {
+                        theme.tag(Tone::Danger)
+                    } else {
+                        theme.security_tag(&item.security_level)
+                    };
+
+                    let text = format!(
+                        "{}  {} {} {}{} ({})",
+                        indent,
+                        checkbox,
+                        status_icon,
+                        tag,
+                        truncate_text(&item.name, 30),
+                        size_text
+                    );
+
+                    let style = if !item.exists {
+                        theme.style(Tone::Danger)
+                    } else {
+                        match item.security_level {
+                            SecurityLevel::Low => Style::default(),
+                            ref level => theme.style(level.tone()),
+                        }
+                    };
+                    (text, style)
                 }
             };
-            
+
+            let style = if is_selected { theme.selection() } else { style };
+
             ListItem::new(item_text).style(style)
         })
         .collect();
@@ -140,46 +257,57 @@ pub fn render_backup_item_list(
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Select Items to Backup")
+                .title(list_title("Select Items to Backup", filter))
                 .title_alignment(Alignment::Center),
         )
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
     frame.render_widget(list, area);
+    render_scrollbar(frame, area, rows.len(), scroll_offset, selected_index, theme);
 }
 
 /// Restore item list component with conflict indicators
+#[allow(clippy::too_many_arguments)]
 pub fn render_restore_item_list(
     frame: &mut ratatui::Frame,
     area: Rect,
     items: &[RestoreItem],
+    visible: &[usize],
     selected_index: usize,
     scroll_offset: usize,
+    filter: &str,
+    theme: &Theme,
 ) {
-    let visible_items: Vec<ListItem> = items
+    let height = area.height.saturating_sub(2) as usize;
+    let scroll_offset = visible_offset(selected_index, scroll_offset, height);
+    let visible_items: Vec<ListItem> = visible
         .iter()
+        .map(|&index| &items[index])
         .skip(scroll_offset)
-        .take(area.height.saturating_sub(2) as usize)
+        .take(height)
         .enumerate()
         .map(|(i, item)| {
             let actual_index = scroll_offset + i;
             let is_selected = actual_index == selected_index;
             
-            let checkbox = if item.selected { "☑" } else { "☐" };
-            let conflict_icon = if item.conflicts { "⚠️" } else { " " };
+            let checkbox = theme.checkbox(item.selected as usize, 1);
+            let conflict_icon = if item.conflicts { theme.icon(Icon::Warning) } else { " " };
             
+            let tag = if item.conflicts { theme.tag(Tone::Caution) } else { "" };
+
             let item_text = format!(
-                "{} {} {} ({})",
+                "{} {} {}{} ({})",
                 checkbox,
                 conflict_icon,
+                tag,
                 truncate_text(&item.name, 40),
                 format_bytes(item.size)
             );
             
             let style = if is_selected {
-                Style::default().bg(Color::Blue).fg(Color::White)
+                theme.selection()
             } else if item.conflicts {
-                Style::default().fg(Color::Yellow)
+                theme.style(Tone::Caution)
             } else {
                 Style::default()
             };
@@ -192,12 +320,59 @@ pub fn render_restore_item_list(
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Select Items to Restore")
+                .title(list_title("Select Items to Restore", filter))
                 .title_alignment(Alignment::Center),
         )
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
     frame.render_widget(list, area);
+    render_scrollbar(frame, area, visible.len(), scroll_offset, selected_index, theme);
+}
+
+/// First row to show so the `selected` one is in view, moving as little as
+/// possible from `scroll_offset`
+pub fn visible_offset(selected: usize, scroll_offset: usize, height: usize) -> usize {
+    scroll_offset.min(selected).max((selected + 1).saturating_sub(height))
+}
+
+/// Scrollbar on the right border of a bordered list of `total` rows, with
+/// the rows in view noted on the bottom border; nothing when all fit
+pub fn render_scrollbar(frame: &mut ratatui::Frame, area: Rect, total: usize, offset: usize, selected: usize, theme: &Theme) {
+    let height = area.height.saturating_sub(2) as usize;
+    if total <= height {
+        return;
+    }
+
+    let mut scrollbar_state = ScrollbarState::new(total).position(selected);
+    frame.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight),
+        area.inner(Margin { vertical: 1, horizontal: 0 }),
+        &mut scrollbar_state,
+    );
+
+    let label = format!(" {}-{} of {} ", offset + 1, (offset + height).min(total), total);
+    let width = (label.chars().count() as u16).min(area.width.saturating_sub(2));
+    let label_area = Rect::new(area.right().saturating_sub(width + 1), area.bottom().saturating_sub(1), width, 1);
+    frame.render_widget(Paragraph::new(label).style(theme.muted()), label_area);
+}
+
+/// Item list title, with the filter narrowing the list
+fn list_title(title: &str, filter: &str) -> String {
+    if filter.is_empty() {
+        title.to_string()
+    } else {
+        format!("{} (filter: {})", title, filter)
+    }
+}
+
+/// Items and bytes done so far, for [`render_progress_bar`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProgressCounts {
+    pub items_completed: usize,
+    pub total_items: usize,
+    pub bytes_processed: u64,
+    pub total_bytes: u64,
+    pub bytes_per_second: Option<f64>,
 }
 
 /// Progress bar component for backup/restore operations
@@ -207,9 +382,10 @@ pub fn render_progress_bar(
     title: &str,
     percentage: f64,
     current_item: &str,
-    items_completed: usize,
-    total_items: usize,
+    counts: &ProgressCounts,
+    theme: &Theme,
 ) {
+    let ProgressCounts { items_completed, total_items, bytes_processed, total_bytes, bytes_per_second } = *counts;
     let progress_block = Block::default()
         .borders(Borders::ALL)
         .title(title)
@@ -237,16 +413,41 @@ pub fn render_progress_bar(
     // Progress bar
     let gauge = Gauge::default()
         .block(Block::default().borders(Borders::ALL))
-        .gauge_style(Style::default().fg(Color::Green))
+        .gauge_style(theme.style(Tone::Good))
         .percent(percentage as u16)
-        .label(format!("{}/{} items", items_completed, total_items));
+        .label(if bytes_processed > 0 && items_completed == 0 {
+            // Items aren't counted off while bytes are
+            format!("{} items", total_items)
+        } else {
+            format!("{}/{} items", items_completed, total_items)
+        });
     frame.render_widget(gauge, chunks[1]);
 
     // Current item
     let current_item_text = Paragraph::new(format!("Processing: {}", truncate_text(current_item, 50)))
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Blue));
+        .style(theme.accent());
     frame.render_widget(current_item_text, chunks[2]);
+
+    // Bytes, throughput and time left, once anything has been counted
+    if bytes_processed > 0 {
+        let mut parts = vec![if total_bytes > 0 {
+            format!("{} / {}", format_bytes(bytes_processed), format_bytes(total_bytes))
+        } else {
+            format_bytes(bytes_processed)
+        }];
+        if let Some(rate) = bytes_per_second.filter(|rate| *rate > 0.0) {
+            parts.push(format!("{}/s", format_bytes(rate as u64)));
+            if total_bytes > bytes_processed {
+                let eta = std::time::Duration::from_secs_f64((total_bytes - bytes_processed) as f64 / rate);
+                parts.push(format!("{} left", format_duration(eta)));
+            }
+        }
+        let transfer_text = Paragraph::new(parts.join(" • "))
+            .alignment(Alignment::Center)
+            .style(theme.muted());
+        frame.render_widget(transfer_text, chunks[3]);
+    }
 }
 
 /// Security warning component for sensitive operations
@@ -254,18 +455,19 @@ pub fn render_security_warning(
     frame: &mut ratatui::Frame,
     area: Rect,
     warning_text: &str,
+    theme: &Theme,
 ) {
     let warning_block = Block::default()
         .borders(Borders::ALL)
-        .title("⚠️  Security Warning")
+        .title(format!("{}  Security Warning", theme.icon(Icon::Warning)))
         .title_alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Red));
+        .style(theme.style(Tone::Danger));
 
     let warning = Paragraph::new(warning_text)
         .block(warning_block)
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true })
-        .style(Style::default().fg(Color::Yellow));
+        .style(theme.style(Tone::Caution));
 
     frame.render_widget(warning, area);
 }
@@ -278,6 +480,7 @@ pub fn render_modal(
     content: &str,
     buttons: &[&str],
     selected_button: usize,
+    theme: &Theme,
 ) {
     // Clear the background
     frame.render_widget(Clear, area);
@@ -286,7 +489,7 @@ pub fn render_modal(
         .borders(Borders::ALL)
         .title(title)
         .title_alignment(Alignment::Center)
-        .style(Style::default().bg(Color::Black).fg(Color::White));
+        .style(theme.surface());
 
     let modal_area = modal_block.inner(area);
     frame.render_widget(modal_block, area);
@@ -317,11 +520,7 @@ pub fn render_modal(
         .split(chunks[1]);
 
     for (i, &button_text) in buttons.iter().enumerate() {
-        let button_style = if i == selected_button {
-            Style::default().bg(Color::Blue).fg(Color::White)
-        } else {
-            Style::default().fg(Color::Gray)
-        };
+        let button_style = if i == selected_button { theme.selection() } else { theme.muted() };
 
         let button = Paragraph::new(button_text)
             .alignment(Alignment::Center)
@@ -359,4 +558,84 @@ pub fn render_summary_panel(
         .wrap(Wrap { trim: true });
 
     frame.render_widget(summary, area);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn bottom_border(total: usize, offset: usize, selected: usize) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                frame.render_widget(Block::default().borders(Borders::ALL), area);
+                render_scrollbar(frame, area, total, offset, selected, &Theme::default());
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..40).map(|x| buffer[(x, 11)].symbol().to_string()).collect()
+    }
+
+    #[test]
+    fn test_selection_stays_in_view() {
+        // Ten rows fit: scrolling only when the selection leaves the view
+        assert_eq!(visible_offset(0, 0, 10), 0);
+        assert_eq!(visible_offset(9, 0, 10), 0);
+        assert_eq!(visible_offset(10, 0, 10), 1);
+        assert_eq!(visible_offset(25, 3, 10), 16);
+        assert_eq!(visible_offset(12, 16, 10), 12);
+        // Wrapping from the bottom to the top
+        assert_eq!(visible_offset(0, 20, 10), 0);
+
+        assert!(bottom_border(30, 16, 25).contains(" 17-26 of 30 "));
+        assert!(bottom_border(30, 20, 29).contains(" 21-30 of 30 "));
+        // No scrollbar or count when every row fits
+        assert!(!bottom_border(10, 0, 9).contains("of"));
+    }
+
+    #[test]
+    fn test_backup_steps_follow_the_mode() {
+        use crate::core::state::AppState;
+
+        let steps = |state: AppState, mode: BackupMode| {
+            let mut terminal = Terminal::new(TestBackend::new(80, 4)).unwrap();
+            terminal
+                .draw(|frame| render_backup_steps(frame, frame.area(), state.backup_step(), &mode, &Theme::default()))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..80).map(|x| buffer[(x, 3)].symbol().to_string()).collect::<String>().trim().to_string()
+        };
+
+        // Secure backups aren't encrypted, so have no password step
+        assert_eq!(steps(AppState::Preflight, BackupMode::Secure), "Mode → Items → Confirm → Progress");
+        assert_eq!(steps(AppState::BackupPasswordInput, BackupMode::Complete), "Mode → Items → Password → Confirm → Progress");
+        assert_eq!(AppState::BackupConfirm.backup_step(), Some(BackupStep::Confirm));
+        assert_eq!(steps(AppState::MainMenu, BackupMode::Complete), "");
+    }
+
+    #[test]
+    fn test_status_bar_keeps_its_right_side_in_view() {
+        let bar = |status: &StatusBar, profile: Option<&str>, width: u16| {
+            let mut terminal = Terminal::new(TestBackend::new(width, 3)).unwrap();
+            terminal.draw(|frame| render_status_bar(frame, frame.area(), status, profile, &Theme::default())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..width).map(|x| buffer[(x, 2)].symbol().to_string()).collect::<String>()
+        };
+
+        let mut status = StatusBar { clock: "09:41".to_string(), ..StatusBar::default() };
+        assert_eq!(bar(&status, None, 20).trim(), "09:41");
+
+        status.free_space = Some(3 * 1024 * 1024 * 1024);
+        status.activities = vec!["Backing up".to_string(), "Measuring sizes (12 left)".to_string()];
+        let text = bar(&status, Some("laptop"), 100);
+        assert!(text.contains(" • Backing up • Measuring sizes (12 left) "));
+        assert!(text.trim_end().ends_with("Profile: laptop | 3.0 GB free | 09:41"), "{}", text);
+
+        // A narrow screen cuts the activities short, never the right side
+        let text = bar(&status, Some("laptop"), 60);
+        assert!(text.contains("..."));
+        assert!(text.trim_end().ends_with("Profile: laptop | 3.0 GB free | 09:41"));
+    }
+}
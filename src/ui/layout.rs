@@ -0,0 +1,98 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::ui::theme::{Theme, Tone};
+
+/// Smallest terminal the screens are drawn in; below it only a note
+/// asking for a bigger window is shown
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 16;
+
+/// Width below which side-by-side panels are stacked
+pub const STACK_BELOW_WIDTH: u16 = 100;
+
+/// Height of the content area below which a stacked side panel is left
+/// out, so the main panel keeps enough rows to be usable
+pub const SIDE_PANEL_MIN_HEIGHT: u16 = 20;
+
+pub fn too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Split `area` into a main panel taking `main_percent` of it and a side
+/// panel for details. Wide terminals get them side by side, narrow ones
+/// one above the other, and narrow, short ones only the main panel, the
+/// side panel left empty.
+pub fn split_panels(area: Rect, main_percent: u16) -> [Rect; 2] {
+    let constraints = [Constraint::Percentage(main_percent), Constraint::Percentage(100 - main_percent)];
+    if area.width >= STACK_BELOW_WIDTH {
+        let chunks = Layout::default().direction(Direction::Horizontal).constraints(constraints).split(area);
+        [chunks[0], chunks[1]]
+    } else if area.height >= SIDE_PANEL_MIN_HEIGHT {
+        let chunks = Layout::default().direction(Direction::Vertical).constraints(constraints).split(area);
+        [chunks[0], chunks[1]]
+    } else {
+        [area, Rect::new(area.x, area.bottom(), area.width, 0)]
+    }
+}
+
+/// Shown in place of a screen while the terminal is below the minimum
+pub fn render_too_small(frame: &mut ratatui::Frame, area: Rect, theme: &Theme) {
+    let lines = vec![
+        Line::from("Terminal too small").style(theme.style(Tone::Caution).add_modifier(Modifier::BOLD)),
+        Line::from(format!("{}x{}, needs at least {}x{}", area.width, area.height, MIN_WIDTH, MIN_HEIGHT)),
+        Line::from("Enlarge the window to continue").style(theme.muted()),
+    ];
+    let top = area.height.saturating_sub(lines.len() as u16 + 2) / 2;
+    let note_area = Rect::new(area.x, area.y + top, area.width, area.height.saturating_sub(top));
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        note_area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::state::AppStateManager;
+    use crate::core::types::BackupItem;
+    use crate::ui::screens::BackupItemSelectionScreen;
+    use ratatui::{backend::TestBackend, Terminal};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_panels_adapt_to_width() {
+        let [main, side] = split_panels(Rect::new(0, 0, 120, 30), 70);
+        assert_eq!((main.width, side.width, main.height), (84, 36, 30));
+
+        let [main, side] = split_panels(Rect::new(0, 0, 80, 30), 70);
+        assert_eq!((main.width, side.width, main.height, side.height), (80, 80, 21, 9));
+        assert_eq!(side.y, main.bottom());
+
+        let [main, side] = split_panels(Rect::new(0, 0, 80, 12), 70);
+        assert_eq!((main.height, side.height), (12, 0));
+
+        assert!(too_small(Rect::new(0, 0, 59, 40)));
+        assert!(too_small(Rect::new(0, 0, 200, 15)));
+        assert!(!too_small(Rect::new(0, 0, MIN_WIDTH, MIN_HEIGHT)));
+
+        // The busiest screen draws at the minimum size and when stacked
+        let mut state = AppStateManager::new();
+        state.backup_items = (0..30)
+            .map(|i| BackupItem::new(format!("item {}", i), PathBuf::from(format!("item{}", i)), "Shell".to_string(), String::new()))
+            .collect();
+        let mut screen = BackupItemSelectionScreen::new();
+        for (width, height) in [(MIN_WIDTH, MIN_HEIGHT), (90, 40), (160, 50)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|frame| screen.render(frame, &state)).unwrap();
+            assert!(screen.visible_rows() >= 1);
+        }
+    }
+}
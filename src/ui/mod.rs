@@ -1,4 +1,6 @@
 pub mod terminal;
 pub mod screens;
 pub mod components;
-pub mod widgets;
\ No newline at end of file
+pub mod widgets;
+pub mod theme;
+pub mod layout;
@@ -0,0 +1,169 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::audit::ChainStatus;
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::terminal::pad_text;
+use crate::ui::theme::Tone;
+
+pub struct AuditHistoryScreen;
+
+impl AuditHistoryScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        let count = state.audit_entries.len();
+        // Entries are shown newest first; the chain counts oldest first
+        let broken_at = match state.audit_chain {
+            Some(ChainStatus::Broken { entry }) => Some(count.saturating_sub(entry + 1)),
+            _ => None,
+        };
+        let subtitle = match &state.audit_chain {
+            Some(ChainStatus::Intact { unchained: 0 }) => format!("{} entries, integrity chain intact", count),
+            Some(ChainStatus::Intact { unchained }) => {
+                format!("{} entries, chain intact after {} older unchained entries", count, unchained)
+            }
+            Some(ChainStatus::Broken { entry }) => {
+                format!("{} entries, CHAIN BROKEN at entry {}: the log was altered", count, entry + 1)
+            }
+            None => "Audit log could not be read".to_string(),
+        };
+        render_header(frame, chunks[0], "Audit History", Some(&subtitle), &state.theme);
+
+        let content_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),     // Entries
+                Constraint::Length(7),  // Selected entry
+            ])
+            .split(chunks[1]);
+
+        // Entries
+        let visible_height = content_chunks[0].height.saturating_sub(3) as usize;
+        let mut entry_items = vec![ListItem::new(format!(
+            "  {:<16} {:<20} {:<16} {:<10}  {}",
+            "When", "Who", "Action", "Result", "Target"
+        ))
+        .style(Style::default().add_modifier(Modifier::BOLD))];
+
+        entry_items.extend(
+            state.audit_entries
+                .iter()
+                .enumerate()
+                .skip(state.scroll_offset)
+                .take(visible_height)
+                .map(|(i, event)| {
+                    let failed = event.result().starts_with("failed");
+                    let style = if i == state.selected_item_index {
+                        state.theme.selection()
+                    } else if Some(i) == broken_at || failed {
+                        state.theme.style(Tone::Danger)
+                    } else {
+                        Style::default()
+                    };
+
+                    let marker = if Some(i) == broken_at { "! " } else { "  " };
+                    let result = if failed { "failed" } else { event.result() };
+                    ListItem::new(format!(
+                        "{}{:<16} {} {} {:<10}  {}",
+                        marker,
+                        event.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                        pad_text(&event.actor, 20),
+                        pad_text(&event.action, 16),
+                        result,
+                        event.target,
+                    ))
+                    .style(style)
+                }),
+        );
+
+        let entries_list = List::new(entry_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Entries ({})", count))
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(entries_list, content_chunks[0]);
+
+        // Selected entry
+        let label = |text: &'static str| Span::styled(text, Style::default().add_modifier(Modifier::BOLD));
+        let detail_lines = match state.audit_entries.get(state.selected_item_index) {
+            Some(event) => {
+                let mut lines = Vec::new();
+                if Some(state.selected_item_index) == broken_at {
+                    lines.push(Line::from(Span::styled(
+                        format!(
+                            "{}This entry doesn't match the one before it: an entry was edited, inserted or removed here",
+                            state.theme.tag(Tone::Danger),
+                        ),
+                        state.theme.style(Tone::Danger),
+                    )));
+                }
+                if let Some(operation) = &event.operation {
+                    lines.push(Line::from(vec![
+                        label("Mode: "),
+                        Span::raw(format!("{}   ", operation.mode)),
+                        label("Result: "),
+                        Span::raw(&operation.result),
+                    ]));
+                    lines.push(Line::from(vec![
+                        label("Destination: "),
+                        Span::raw(operation.destination.as_deref().unwrap_or("-")),
+                    ]));
+                    lines.push(Line::from(vec![
+                        label(if operation.items.len() == 1 { "Item: " } else { "Items: " }),
+                        Span::raw(format!("({}) {}", operation.items.len(), operation.items.join(", "))),
+                    ]));
+                    lines.push(Line::from(vec![
+                        label("Archive checksum: "),
+                        Span::raw(operation.archive_checksum.as_deref().unwrap_or("not recorded")),
+                    ]));
+                }
+                if !event.details.is_empty() {
+                    lines.push(Line::from(vec![label("Details: "), Span::raw(&event.details)]));
+                }
+                lines
+            }
+            None => vec![Line::from(Span::styled(
+                "Nothing in the audit log yet. Backups and restores are recorded as they happen.",
+                state.theme.style(Tone::Caution),
+            ))],
+        };
+
+        let details = Paragraph::new(detail_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Details")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::AuditHistory, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -1,14 +1,17 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
 };
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
-use crate::core::types::ProgressStatus;
-use crate::ui::components::{render_header, render_footer};
-use crate::ui::terminal::format_bytes;
+use crate::core::types::{ItemOutcome, ProgressStatus};
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{format_bytes, pad_text};
+use crate::ui::theme::Tone;
 
 pub struct BackupCompleteScreen;
 
@@ -45,15 +48,27 @@ impl BackupCompleteScreen {
             chunks[0],
             header_title,
             Some("Your backup operation has finished"),
+            &state.theme,
         );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
 
         // Content
+        let has_items = !state.item_reports.is_empty();
+        let summary_height = 12 + state.destination_results.len() as u16 + if has_items { 2 } else { 0 };
         let content_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(12), // Summary
-                Constraint::Min(0),     // Details/Actions
-            ])
+            .constraints(if has_items {
+                vec![
+                    Constraint::Length(summary_height), // Summary
+                    Constraint::Min(8),     // Items
+                    Constraint::Length(11), // Details/Actions
+                ]
+            } else {
+                vec![
+                    Constraint::Length(summary_height), // Summary
+                    Constraint::Min(0),     // Details/Actions
+                ]
+            })
             .split(chunks[1]);
 
         // Summary
@@ -64,7 +79,7 @@ impl BackupCompleteScreen {
                 ProgressStatus::Completed => {
                     summary_lines.push(Line::from(vec![
                         Span::styled("✅ Backup completed successfully!", 
-                            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                            state.theme.style(Tone::Good).add_modifier(Modifier::BOLD))
                     ]));
                     summary_lines.push(Line::from(""));
                     
@@ -83,19 +98,62 @@ impl BackupCompleteScreen {
                     summary_lines.push(Line::from(format!("• Items processed: {}", progress.items_completed)));
                     summary_lines.push(Line::from(format!("• Data processed: {}", format_bytes(progress.bytes_processed))));
                     summary_lines.push(Line::from(format!("• Time taken: {}", duration_str)));
+                    if has_items {
+                        let incomplete = state.item_reports.iter().filter(|report| report.is_failure()).count();
+                        let (tone, text) = match incomplete {
+                            0 => (Tone::Good, format!("All {} items backed up in full", state.item_reports.len())),
+                            n => (Tone::Caution, format!("{} of {} items not backed up in full", n, state.item_reports.len())),
+                        };
+                        summary_lines.push(Line::from(Span::styled(
+                            format!("• {}{}", state.theme.tag(tone), text),
+                            state.theme.style(tone),
+                        )));
+                        summary_lines.push(Line::from(match &state.item_report_path {
+                            Some(path) => format!("• Item report: {}", path.display()),
+                            None => "• Item report: not saved, see the log".to_string(),
+                        }));
+                    }
                     
-                    if let Some(path) = &state.backup_output_path {
-                        summary_lines.push(Line::from(format!("• Location: {}", path.display())));
+                    if !state.destination_results.is_empty() {
+                        summary_lines.push(Line::from(vec![
+                            Span::styled("Destinations:", Style::default().add_modifier(Modifier::BOLD))
+                        ]));
+                        for result in &state.destination_results {
+                            summary_lines.push(match &result.outcome {
+                                Ok(location) => Line::from(Span::styled(
+                                    format!("  {}{}", state.theme.tag(Tone::Good), location),
+                                    state.theme.style(Tone::Good),
+                                )),
+                                Err(error) => Line::from(Span::styled(
+                                    format!("  {}{}: {}", state.theme.tag(Tone::Danger), result.destination, error),
+                                    state.theme.style(Tone::Danger),
+                                )),
+                            });
+                        }
+                        let failed = state.destination_results.iter().filter(|result| result.outcome.is_err()).count();
+                        if failed > 0 {
+                            summary_lines.push(Line::from(Span::styled(
+                                format!(
+                                    "{}{} of {} destinations missed this backup; the others hold it",
+                                    state.theme.tag(Tone::Caution),
+                                    failed,
+                                    state.destination_results.len()
+                                ),
+                                state.theme.style(Tone::Caution),
+                            )));
+                        }
+                    } else if let Some(destination) = &state.backup_destination {
+                        summary_lines.push(Line::from(format!("• Location: {}", destination)));
                     }
                 }
                 ProgressStatus::Failed(error) => {
                     summary_lines.push(Line::from(vec![
                         Span::styled("❌ Backup failed!", 
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                            state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
                     ]));
                     summary_lines.push(Line::from(""));
                     summary_lines.push(Line::from(vec![
-                        Span::styled("Error: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Red)),
+                        Span::styled("Error: ", state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD)),
                         Span::raw(error),
                     ]));
                     summary_lines.push(Line::from(""));
@@ -133,7 +191,7 @@ impl BackupCompleteScreen {
         let actions_lines = if is_success {
             vec![
                 Line::from(vec![
-                    Span::styled("Next Steps:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                    Span::styled("Next Steps:", state.theme.accent().add_modifier(Modifier::BOLD))
                 ]),
                 Line::from(""),
                 Line::from("• Your backup has been created successfully"),
@@ -143,12 +201,12 @@ impl BackupCompleteScreen {
                 Line::from(""),
                 if state.backup_mode == crate::core::types::BackupMode::Complete {
                     Line::from(vec![
-                        Span::styled("⚠️ Security Reminder: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                        Span::styled("⚠️ Security Reminder: ", state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD)),
                         Span::raw("This backup contains sensitive data and is encrypted."),
                     ])
                 } else {
                     Line::from(vec![
-                        Span::styled("ℹ️ Info: ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+                        Span::styled("ℹ️ Info: ", state.theme.accent().add_modifier(Modifier::BOLD)),
                         Span::raw("This secure backup excludes sensitive credentials."),
                     ])
                 },
@@ -157,7 +215,7 @@ impl BackupCompleteScreen {
         } else {
             vec![
                 Line::from(vec![
-                    Span::styled("What to do next:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow))
+                    Span::styled("What to do next:", state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD))
                 ]),
                 Line::from(""),
                 Line::from("• Review the error message above"),
@@ -175,19 +233,107 @@ impl BackupCompleteScreen {
                     .borders(Borders::ALL)
                     .title(if is_success { "Success" } else { "Troubleshooting" })
                     .title_alignment(Alignment::Center)
-                    .style(Style::default().fg(if is_success { Color::Green } else { Color::Yellow })),
+                    .style(state.theme.style(if is_success { Tone::Good } else { Tone::Caution })),
             )
             .alignment(Alignment::Left)
             .wrap(Wrap { trim: true });
 
-        frame.render_widget(actions_paragraph, content_chunks[1]);
+        frame.render_widget(actions_paragraph, content_chunks[content_chunks.len() - 1]);
+        if has_items {
+            render_items(frame, content_chunks[1], state);
+        }
 
         // Footer
-        let shortcuts = [
-            ("Enter", "Return to Main Menu"),
-            ("Q", "Quit Application"),
-        ];
-
-        render_footer(frame, chunks[2], &shortcuts, None);
+        let hints = state.keymap.hints(KeyContext::BackupComplete, |action| match action {
+            Action::SaveReport | Action::SaveHtmlReport => state.completion_report.is_some(),
+            Action::RecoveryKit => state.completion_report.as_ref().is_some_and(|report| report.archive.is_some()),
+            Action::Confirm | Action::Quit => true,
+            _ => has_items,
+        });
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
     }
+}
+
+/// Each selected item and its outcome, beside what the highlighted one
+/// left out
+fn render_items(frame: &mut ratatui::Frame, area: Rect, state: &AppStateManager) {
+    let item_chunks = split_panels(area, 60);
+
+    let reports = state.visible_item_reports();
+    let visible_height = item_chunks[0].height.saturating_sub(3) as usize;
+    let mut rows = vec![ListItem::new(format!("{:<24} {:>8} {:>10}  {}", "Status", "Files", "Size", "Item"))
+        .style(Style::default().add_modifier(Modifier::BOLD))];
+    rows.extend(
+        reports
+            .iter()
+            .enumerate()
+            .skip(state.scroll_offset)
+            .take(visible_height)
+            .map(|(i, report)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    state.theme.style(report.tone())
+                };
+                ListItem::new(format!(
+                    "{} {:>8} {:>10}  {}",
+                    pad_text(&format!("{}{}", state.theme.tag(report.tone()), report.status()), 24),
+                    report.files,
+                    format_bytes(report.bytes),
+                    report.path.display(),
+                ))
+                .style(style)
+            }),
+    );
+
+    let title = if state.item_failures_only {
+        format!("Items not backed up in full ({} of {})", reports.len(), state.item_reports.len())
+    } else {
+        format!("Items ({})", reports.len())
+    };
+    let items = List::new(rows).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_alignment(Alignment::Center),
+    );
+    frame.render_widget(items, item_chunks[0]);
+
+    let detail_lines = match reports.get(state.selected_item_index) {
+        Some(report) => {
+            let mut lines = vec![Line::from(Span::styled(
+                report.path.display().to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))];
+            lines.push(Line::from(match report.outcome {
+                ItemOutcome::BackedUp => format!("{} files, {}", report.files, format_bytes(report.bytes)),
+                ItemOutcome::PermissionDenied => "Could not be read, so none of it is in the archive".to_string(),
+                ItemOutcome::Missing => "Did not exist when the backup ran".to_string(),
+                ItemOutcome::Excluded => "Matches an exclusion pattern for this mode".to_string(),
+                ItemOutcome::CommandFailed => "Its command failed; the log has its error output".to_string(),
+            }));
+            if !report.unreadable.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Left out, could not be read:",
+                    state.theme.style(Tone::Caution),
+                )));
+                lines.extend(report.unreadable.iter().map(|path| Line::from(format!("  {}", path.display()))));
+            }
+            lines
+        }
+        None => vec![Line::from(Span::styled(
+            "Every item was backed up in full.",
+            state.theme.style(Tone::Good),
+        ))],
+    };
+    let details = Paragraph::new(detail_lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Selected Item")
+                .title_alignment(Alignment::Center),
+        )
+        .wrap(Wrap { trim: true });
+    frame.render_widget(details, item_chunks[1]);
 }
\ No newline at end of file
@@ -0,0 +1,134 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::core::types::{BackupMode, SecurityLevel};
+use crate::ui::components::{render_backup_steps, render_header, render_footer, render_summary_panel};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::format_bytes;
+use crate::ui::theme::{Icon, Tone};
+
+pub struct BackupConfirmScreen;
+
+impl BackupConfirmScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Ready to Back Up",
+            Some("Check what will be backed up before it starts"),
+            &state.theme,
+        );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
+
+        let content_chunks = split_panels(chunks[1], 50);
+        let left_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),    // Summary
+                Constraint::Length(4), // Note
+            ])
+            .split(content_chunks[0]);
+
+        // Summary
+        let (item_count, total_size, high_security_count) = state.get_backup_summary();
+        let size = if state.backup_sizes_pending > 0 {
+            format!("{}+ ({} still being measured)", format_bytes(total_size), state.backup_sizes_pending)
+        } else {
+            format_bytes(total_size)
+        };
+        let destination = state
+            .backup_destination
+            .as_ref()
+            .map(|destination| destination.to_string())
+            .unwrap_or_else(|| "Working directory".to_string());
+        let encrypted = state.backup_password.is_some() || !state.backup_recipients.is_empty();
+        let encryption = if state.backup_password.is_some() {
+            "Passphrase".to_string()
+        } else if !state.backup_recipients.is_empty() {
+            format!("{} GPG key(s)", state.backup_recipients.len())
+        } else {
+            "None".to_string()
+        };
+        let mut stats = vec![
+            ("Mode", state.backup_mode.as_str().to_string()),
+            ("Items", format!("{} ({} high security)", item_count, high_security_count)),
+            ("Size", size),
+            ("Destination", destination),
+        ];
+        if !state.backup_copies.is_empty() {
+            stats.push(("Copies", state.backup_copies.iter().map(|copy| copy.to_string()).collect::<Vec<_>>().join(", ")));
+        }
+        stats.push((
+            "Compression",
+            format!("{} level {}", state.backup_compression.as_str(), state.backup_compression_level),
+        ));
+        stats.push(("Encryption", encryption));
+        if !state.secret_exclusions.is_empty() {
+            stats.push(("Secrets Left Out", state.secret_exclusions.len().to_string()));
+        }
+        render_summary_panel(frame, left_chunks[0], "Backup Summary", &stats);
+
+        // Note
+        let theme = &state.theme;
+        let (tone, note) = match state.backup_mode {
+            BackupMode::Complete if !encrypted => (Tone::Danger, "The archive will not be encrypted."),
+            BackupMode::Complete => (Tone::Good, "Credentials are included and the archive is encrypted."),
+            BackupMode::Secure => (Tone::Caution, "Credentials and keys are left out of secure backups."),
+        };
+        let note = Paragraph::new(Line::from(Span::styled(format!("{}{}", theme.tag(tone), note), theme.style(tone))))
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(note, left_chunks[1]);
+
+        // Selected items
+        let items: Vec<ListItem> = state
+            .get_selected_backup_items()
+            .iter()
+            .map(|item| {
+                let mut spans = Vec::new();
+                if item.security_level == SecurityLevel::High {
+                    spans.push(Span::styled(
+                        format!("{} {}", theme.icon(Icon::Locked), theme.security_tag(&item.security_level)),
+                        theme.style(Tone::Danger),
+                    ));
+                }
+                spans.push(Span::raw(item.name.clone()));
+                let size = item.size.map(format_bytes).unwrap_or_else(|| "...".to_string());
+                spans.push(Span::styled(format!("  {}", size), theme.muted()));
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Selected Items ({})", item_count))
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::BackupConfirm, |_| true);
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -1,20 +1,33 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
-use crate::core::state::AppStateManager;
+use crate::backend::destination_trust::DestinationChange;
+use crate::core::config::{CriticalGap, CriticalGapReason, CUSTOM_CATEGORY};
+use crate::core::item_tree::TreeRow;
+use crate::core::keymap::{Action, KeyContext};
+use crate::core::state::{AppStateManager, TOP_CONSUMERS};
 use crate::core::types::SecurityLevel;
-use crate::ui::components::{render_header, render_footer, render_backup_item_list, render_summary_panel};
-use crate::ui::terminal::format_bytes;
+use crate::ui::components::{hint_spans, render_backup_steps, render_header, render_footer, render_backup_item_list, render_summary_panel};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{centered_rect, format_bytes};
+use crate::ui::theme::Tone;
 
-pub struct BackupItemSelectionScreen;
+pub struct BackupItemSelectionScreen {
+    /// List rows that fit on screen, as of the last render
+    visible_rows: usize,
+}
 
 impl BackupItemSelectionScreen {
     pub fn new() -> Self {
-        Self
+        Self { visible_rows: 10 }
+    }
+
+    pub fn visible_rows(&self) -> usize {
+        self.visible_rows.max(1)
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
@@ -39,26 +52,26 @@ impl BackupItemSelectionScreen {
             frame,
             chunks[0],
             "Select Items to Backup",
-            Some(&format!("Mode: {} | Use Space to toggle, A/N to select/deselect all", mode_name)),
+            Some(&format!("Mode: {} | Use Space to toggle an item or group, ←/→ to fold groups, A/N to select/deselect all, / to filter", mode_name)),
+            &state.theme,
         );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
 
         // Main content
-        let content_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(70), // Item list
-                Constraint::Percentage(30), // Summary and legend
-            ])
-            .split(chunks[1]);
+        let content_chunks = split_panels(chunks[1], 70);
 
         // Item list
-        let available_height = content_chunks[0].height.saturating_sub(2) as usize;
+        self.visible_rows = content_chunks[0].height.saturating_sub(2) as usize;
+        let rows = state.backup_tree();
         render_backup_item_list(
             frame,
             content_chunks[0],
             &state.backup_items,
+            &rows,
             state.selected_item_index,
             state.scroll_offset,
+            &state.item_filter,
+            &state.theme,
         );
 
         // Right panel
@@ -66,6 +79,7 @@ impl BackupItemSelectionScreen {
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(8),  // Summary
+                Constraint::Length(TOP_CONSUMERS as u16 + 2),  // Top space consumers
                 Constraint::Length(8),  // Legend
                 Constraint::Min(0),     // Item details
             ])
@@ -75,14 +89,22 @@ impl BackupItemSelectionScreen {
         let (item_count, total_size, high_security_count) = state.get_backup_summary();
         let summary_stats = vec![
             ("Selected Items", item_count.to_string()),
-            ("Total Size", format_bytes(total_size)),
+            ("Total Size", if state.backup_sizes_pending > 0 {
+                format!("{} (sizing {} more)", format_bytes(total_size), state.backup_sizes_pending)
+            } else {
+                format_bytes(total_size)
+            }),
             ("High Security", high_security_count.to_string()),
             ("Missing Items", state.backup_items.iter().filter(|item| !item.exists).count().to_string()),
+            ("Destination", state.backup_destination.as_ref().map(|d| d.to_string()).unwrap_or_else(|| "default".to_string())),
+            ("Sorted By", state.item_sort.label().to_string()),
         ];
 
         render_summary_panel(frame, right_chunks[0], "Backup Summary", &summary_stats);
+        self.render_top_consumers(frame, right_chunks[1], state, total_size);
 
         // Legend
+        let theme = &state.theme;
         let legend_lines = vec![
             Line::from(vec![
                 Span::styled("Legend:", Style::default().add_modifier(Modifier::BOLD))
@@ -90,23 +112,23 @@ impl BackupItemSelectionScreen {
             Line::from(""),
             Line::from(vec![
                 Span::raw("☑ "),
-                Span::styled("Selected", Style::default().fg(Color::Green)),
+                Span::styled("Selected", state.theme.style(Tone::Good)),
             ]),
             Line::from(vec![
                 Span::raw("☐ "),
-                Span::styled("Not selected", Style::default().fg(Color::Gray)),
+                Span::styled("Not selected", state.theme.muted()),
             ]),
             Line::from(vec![
-                Span::raw("🔒 "),
-                Span::styled("High security", Style::default().fg(Color::Red)),
+                Span::raw(format!("🔒 {}", theme.security_tag(&SecurityLevel::High))),
+                Span::styled("High security", theme.style(Tone::Danger)),
             ]),
             Line::from(vec![
-                Span::raw("⚠️ "),
-                Span::styled("Medium security", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("⚠️ {}", theme.security_tag(&SecurityLevel::Medium))),
+                Span::styled("Medium security", theme.style(Tone::Caution)),
             ]),
             Line::from(vec![
-                Span::raw("❌ "),
-                Span::styled("Missing/Not found", Style::default().fg(Color::Red)),
+                Span::raw(format!("❌ {}", theme.tag(Tone::Danger))),
+                Span::styled("Missing/Not found", theme.style(Tone::Danger)),
             ]),
         ];
 
@@ -119,10 +141,11 @@ impl BackupItemSelectionScreen {
             )
             .wrap(Wrap { trim: true });
 
-        frame.render_widget(legend_paragraph, right_chunks[1]);
+        frame.render_widget(legend_paragraph, right_chunks[2]);
 
         // Item details
-        if let Some(item) = state.backup_items.get(state.selected_item_index) {
+        let highlighted = state.highlighted_backup_item();
+        if let Some(item) = highlighted.and_then(|index| state.backup_items.get(index)) {
             let mut details_lines = vec![
                 Line::from(vec![
                     Span::styled("Selected Item:", Style::default().add_modifier(Modifier::BOLD))
@@ -142,28 +165,58 @@ impl BackupItemSelectionScreen {
                 ]),
                 Line::from(vec![
                     Span::styled("Size: ", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(item.size.map(format_bytes).unwrap_or_else(|| "Unknown".to_string())),
+                    Span::raw(match item.size {
+                        Some(size) => format_bytes(size),
+                        None if item.exists && state.backup_sizes_pending > 0 => "Calculating...".to_string(),
+                        None => "Unknown".to_string(),
+                    }),
                 ]),
                 Line::from(vec![
                     Span::styled("Security: ", Style::default().add_modifier(Modifier::BOLD)),
                     Span::styled(
-                        match item.security_level {
-                            SecurityLevel::High => "High",
-                            SecurityLevel::Medium => "Medium", 
-                            SecurityLevel::Low => "Low",
-                        },
-                        Style::default().fg(item.security_level.color()),
+                        format!("{}{}", theme.security_tag(&item.security_level), item.security_level.label()),
+                        theme.style(item.security_level.tone()),
                     ),
                 ]),
                 Line::from(vec![
                     Span::styled("Status: ", Style::default().add_modifier(Modifier::BOLD)),
                     Span::styled(
-                        if item.exists { "Found" } else { "Missing" },
-                        Style::default().fg(if item.exists { Color::Green } else { Color::Red }),
+                        if item.exists { "Found".to_string() } else { format!("{}Missing", theme.tag(Tone::Danger)) },
+                        theme.style(if item.exists { Tone::Good } else { Tone::Danger }),
                     ),
                 ]),
             ];
 
+            if !item.is_generated() {
+                details_lines.push(Line::from(vec![
+                    Span::styled("Symlinks: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(item.symlinks.as_str()),
+                ]));
+                // The item itself is a link, as dotfiles managed elsewhere are
+                let target = dirs::home_dir().and_then(|home| std::fs::read_link(home.join(&item.path)).ok());
+                if let Some(target) = target {
+                    details_lines.push(Line::from(vec![
+                        Span::styled("Links to: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(target.to_string_lossy().to_string()),
+                    ]));
+                }
+            }
+
+            if let Some(volumes) = &item.volumes {
+                let key = state.keymap.key_label(KeyContext::BackupItemSelection, Action::Volumes).unwrap_or_default();
+                details_lines.push(Line::from(vec![
+                    Span::styled("Volumes: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(if volumes.names.is_empty() {
+                        format!("none yet, press {} to pick them", key)
+                    } else {
+                        volumes.names.join(", ")
+                    }),
+                ]));
+                if volumes.pause_containers {
+                    details_lines.push(Line::from("Their running containers are paused while they are copied"));
+                }
+            }
+
             if !item.description.is_empty() {
                 details_lines.push(Line::from(""));
                 details_lines.push(Line::from(vec![
@@ -175,10 +228,10 @@ impl BackupItemSelectionScreen {
             if let Some(warning) = &item.warning {
                 details_lines.push(Line::from(""));
                 details_lines.push(Line::from(vec![
-                    Span::styled("⚠️ Warning:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow))
+                    Span::styled("⚠️ Warning:", state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD))
                 ]));
                 details_lines.push(Line::from(vec![
-                    Span::styled(warning, Style::default().fg(Color::Yellow))
+                    Span::styled(warning, state.theme.style(Tone::Caution))
                 ]));
             }
 
@@ -191,31 +244,259 @@ impl BackupItemSelectionScreen {
                 )
                 .wrap(Wrap { trim: true });
 
-            frame.render_widget(details_paragraph, right_chunks[2]);
+            frame.render_widget(details_paragraph, right_chunks[3]);
+        } else if let Some(TreeRow::Group { label, items, expanded, .. }) = rows.get(state.selected_item_index) {
+            self.render_group_details(frame, right_chunks[3], state, label, items, *expanded);
         }
 
         // Footer
-        let mut shortcuts = vec![
-            ("↑↓", "Navigate"),
-            ("Space", "Toggle"),
-            ("A", "Select All"),
-            ("N", "Select None"),
-        ];
-
-        if state.is_backup_ready() {
-            shortcuts.push(("Enter", "Continue"));
+        let ready = state.is_backup_ready();
+        let volumes = highlighted.and_then(|index| state.backup_items.get(index)).is_some_and(|item| item.volumes.is_some());
+        if state.item_filter_input {
+            let hints = state.keymap.hints(KeyContext::ItemFilter, |_| true);
+            render_footer(frame, chunks[2], &hints, Some(&format!("Filter: {}▏", state.item_filter)), &state.theme);
         } else {
-            shortcuts.push(("Enter", "Continue (disabled)"));
+            let hints = state.keymap.hints(KeyContext::BackupItemSelection, |action| match action {
+                Action::Confirm => ready,
+                Action::Volumes => volumes,
+                _ => true,
+            });
+
+            let status = if !state.is_backup_ready() {
+                Some("Select at least one item to continue")
+            } else {
+                state.status_message.as_deref()
+            };
+
+            render_footer(frame, chunks[2], &hints, status, &state.theme);
+        }
+
+        if let Some(change) = &state.destination_change {
+            self.render_destination_change(frame, state, change);
+        }
+        if let Some(gaps) = &state.critical_gaps {
+            self.render_critical_gaps(frame, state, gaps);
+        }
+        if let Some(browser) = &state.file_browser {
+            browser.render(frame, centered_rect(70, 70, frame.area()), &state.keymap, &state.theme);
+        }
+        if let Some(path) = &state.custom_path_pending {
+            self.render_custom_path(frame, state, path);
         }
+    }
+
+    fn render_custom_path(&self, frame: &mut ratatui::Frame, state: &AppStateManager, path: &std::path::Path) {
+        let area = centered_rect(60, 30, frame.area());
+        frame.render_widget(Clear, area);
 
-        shortcuts.push(("Esc", "Back"));
+        let lines = vec![
+            Line::from(Span::styled(
+                format!("Add ~/{} to this backup", path.display()),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(format!(
+                "It can also be saved under {} in the {} mode's paths, so every {} backup includes it.",
+                CUSTOM_CATEGORY,
+                state.backup_mode.as_str(),
+                state.backup_mode.as_str(),
+            )),
+            Line::from(""),
+            Line::from(hint_spans(&state.keymap.hints(KeyContext::CustomPath, |_| true), &state.theme)),
+        ];
 
-        let status = if !state.is_backup_ready() {
-            Some("Select at least one item to continue")
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Add Path")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+
+    /// The largest selected items and their share of the backup, so the
+    /// one that dominates it can be found and unchecked
+    fn render_top_consumers(&self, frame: &mut ratatui::Frame, area: ratatui::layout::Rect, state: &AppStateManager, total_size: u64) {
+        let largest = state.largest_backup_items(TOP_CONSUMERS);
+        let highlighted = state.highlighted_backup_item();
+        let lines: Vec<Line> = if largest.is_empty() {
+            vec![Line::from(Span::styled(
+                if state.backup_sizes_pending > 0 { "Sizing items..." } else { "No sized items selected" },
+                state.theme.muted(),
+            ))]
+        } else {
+            largest
+                .iter()
+                .map(|(index, size)| {
+                    let share = (*size as f64 / total_size.max(1) as f64 * 100.0).round() as u64;
+                    // More than half the archive is worth a second look
+                    let mut style = if share > 50 { state.theme.style(Tone::Caution) } else { Style::default() };
+                    if Some(*index) == highlighted {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    Line::from(vec![
+                        Span::styled(format!("{:>9} {:>3}% ", format_bytes(*size), share), style),
+                        Span::styled(state.backup_items[*index].name.clone(), style),
+                    ])
+                })
+                .collect()
+        };
+
+        let key = state.keymap.key_label(KeyContext::BackupItemSelection, Action::Largest).unwrap_or_default();
+        let title = if state.backup_sizes_pending > 0 {
+            "Top Space Consumers (sizing)".to_string()
         } else {
-            state.status_message.as_deref()
+            format!("Top Space Consumers ({})", key)
         };
+        let panel = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(panel, area);
+    }
+
+    /// Counts for the highlighted category or application
+    fn render_group_details(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppStateManager,
+        label: &str,
+        items: &[usize],
+        expanded: bool,
+    ) {
+        let members: Vec<_> = items.iter().filter_map(|&index| state.backup_items.get(index)).collect();
+        let selected: Vec<_> = members.iter().filter(|item| item.selected).collect();
+        let stat = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{}: ", name), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(value),
+            ])
+        };
+        let toggle = state.keymap.key_label(KeyContext::BackupItemSelection, Action::Toggle).unwrap_or_default();
+        let fold = state.keymap
+            .key_label(KeyContext::BackupItemSelection, if expanded { Action::Left } else { Action::Right })
+            .unwrap_or_default();
+
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("Selected Group:", Style::default().add_modifier(Modifier::BOLD))
+            ]),
+            Line::from(""),
+            stat("Name", label.to_string()),
+            stat("Items", members.len().to_string()),
+            stat("Selected", selected.len().to_string()),
+            stat("Selected Size", format_bytes(selected.iter().filter_map(|item| item.size).sum())),
+            stat("High Security", members.iter().filter(|item| item.security_level == SecurityLevel::High).count().to_string()),
+            stat("Missing", members.iter().filter(|item| !item.exists).count().to_string()),
+            Line::from(""),
+            Line::from(format!(
+                "{} ticks the whole group, or unticks it when all are ticked. {} {} it.",
+                toggle,
+                fold,
+                if expanded { "collapses" } else { "expands" }
+            )),
+        ];
+
+        let details_paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Group Details")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(details_paragraph, area);
+    }
+
+    fn render_critical_gaps(&self, frame: &mut ratatui::Frame, state: &AppStateManager, gaps: &[CriticalGap]) {
+        let area = centered_rect(70, 60, frame.area());
+        frame.render_widget(Clear, area);
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "This backup leaves out items marked critical",
+                state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+        for gap in gaps {
+            let reason = match gap.reason {
+                CriticalGapReason::NotSelected => "not selected",
+                CriticalGapReason::Missing => "missing on disk",
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("• ~/{} ", gap.path)),
+                Span::styled(format!("({})", reason), state.theme.style(Tone::Caution)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "Restoring from this archive won't bring them back. Go back and select them, \
+             or continue only if leaving them out is intended.",
+        ));
+        lines.push(Line::from(""));
+        lines.push(Line::from(hint_spans(&state.keymap.hints(KeyContext::CriticalItems, |_| true), &state.theme)));
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("⚠️  Critical Items Missing")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.style(Tone::Danger)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+
+    fn render_destination_change(&self, frame: &mut ratatui::Frame, state: &AppStateManager, change: &DestinationChange) {
+        let area = centered_rect(70, 60, frame.area());
+        frame.render_widget(Clear, area);
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("{} is not the destination used before", change.destination),
+                state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+        for field in &change.changes {
+            lines.push(Line::from(vec![
+                Span::raw(format!("• {}: ", field.field)),
+                Span::styled(field.trusted.clone().unwrap_or_else(|| "-".to_string()), state.theme.muted()),
+                Span::raw(" → "),
+                Span::styled(field.current.clone().unwrap_or_else(|| "-".to_string()), state.theme.style(Tone::Caution)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "A different drive may be plugged in or mounted there, the drive may not be mounted at all, \
+             or for SSH the server's host key changed, which can mean someone is intercepting the connection.",
+        ));
+        lines.push(Line::from(
+            "This backup includes credentials. Only continue if you expected this change.",
+        ));
+        lines.push(Line::from(""));
+        lines.push(Line::from(hint_spans(&state.keymap.hints(KeyContext::DestinationChange, |_| true), &state.theme)));
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("⚠️  Destination Changed")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.style(Tone::Danger)),
+            )
+            .wrap(Wrap { trim: true });
 
-        render_footer(frame, chunks[2], &shortcuts, status);
+        frame.render_widget(dialog, area);
     }
 }
\ No newline at end of file
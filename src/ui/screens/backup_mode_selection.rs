@@ -1,14 +1,17 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
 };
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
 use crate::core::types::BackupMode;
-use crate::ui::components::{render_header, render_footer, render_security_warning};
+use crate::ui::components::{render_backup_steps, render_header, render_footer, render_security_warning};
+use crate::ui::layout::split_panels;
 use crate::ui::widgets::{Menu, MenuItem};
+use crate::ui::theme::Tone;
 
 pub struct BackupModeSelectionScreen {
     menu: Menu,
@@ -17,9 +20,9 @@ pub struct BackupModeSelectionScreen {
 impl BackupModeSelectionScreen {
     pub fn new() -> Self {
         let menu_items = vec![
-            MenuItem::new('1', "Secure Mode".to_string(), 
+            MenuItem::new(Action::SecureMode, "Secure Mode".to_string(), 
                 "Safe backup excluding sensitive credentials".to_string()),
-            MenuItem::new('2', "Complete Mode".to_string(), 
+            MenuItem::new(Action::CompleteMode, "Complete Mode".to_string(), 
                 "Full backup including SSH keys and credentials (encrypted)".to_string()),
         ];
 
@@ -28,8 +31,8 @@ impl BackupModeSelectionScreen {
         }
     }
 
-    pub fn handle_key(&mut self, key: crossterm::event::KeyEvent) -> Option<char> {
-        self.menu.handle_key(key)
+    pub fn handle_action(&mut self, action: Action) -> Option<Action> {
+        self.menu.handle_action(action)
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
@@ -50,19 +53,15 @@ impl BackupModeSelectionScreen {
             chunks[0],
             "Select Backup Mode",
             Some("Choose the type of backup to create"),
+            &state.theme,
         );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
 
         // Main content
-        let content_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(50), // Menu
-                Constraint::Percentage(50), // Details
-            ])
-            .split(chunks[1]);
+        let content_chunks = split_panels(chunks[1], 50);
 
         // Menu
-        self.menu.render(frame, content_chunks[0], "Backup Modes");
+        self.menu.render(frame, content_chunks[0], "Backup Modes", &state.keymap, KeyContext::BackupModeSelection, &state.theme);
 
         // Details panel
         let details_chunks = Layout::default()
@@ -105,16 +104,27 @@ impl BackupModeSelectionScreen {
 
         let mut details_lines = vec![
             Line::from(vec![
-                Span::styled(mode_title, Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                Span::styled(mode_title, state.theme.accent().add_modifier(Modifier::BOLD))
             ]),
             Line::from(""),
             Line::from(mode_description),
             Line::from(""),
-            Line::from(vec![
-                Span::styled("Included Items:", Style::default().add_modifier(Modifier::BOLD))
-            ]),
         ];
 
+        details_lines.push(Line::from(vec![
+            Span::styled("Compression: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(
+                "{} level {} (.tar.{})",
+                state.backup_compression.as_str(),
+                state.backup_compression_level,
+                state.backup_compression.extension(),
+            )),
+        ]));
+        details_lines.push(Line::from(""));
+        details_lines.push(Line::from(vec![
+            Span::styled("Included Items:", Style::default().add_modifier(Modifier::BOLD))
+        ]));
+
         for feature in mode_features {
             let (symbol, text) = if feature.starts_with('✓') {
                 // Skip the checkmark character (which is 3 bytes in UTF-8) and the space after it
@@ -127,15 +137,15 @@ impl BackupModeSelectionScreen {
                 ("", feature.to_string())
             };
             
-            let color = match symbol {
-                "✓" => Color::Green,
-                "✗" => Color::Red,
-                _ => Color::White,
+            let style = match symbol {
+                "✓" => state.theme.style(Tone::Good),
+                "✗" => state.theme.style(Tone::Danger),
+                _ => Style::default(),
             };
             
             if !symbol.is_empty() {
                 details_lines.push(Line::from(vec![
-                    Span::styled(format!("  {} ", symbol), Style::default().fg(color)),
+                    Span::styled(format!("  {} ", symbol), style),
                     Span::raw(text),
                 ]));
             } else {
@@ -162,12 +172,13 @@ impl BackupModeSelectionScreen {
                 frame,
                 details_chunks[1],
                 "Complete mode includes sensitive credentials like SSH keys, GPG keys, and API tokens. This backup MUST be encrypted and stored securely. Never share or store unencrypted complete backups in unsecured locations.",
+                &state.theme,
             );
         } else {
             // Show security info for secure mode
             let security_info = vec![
                 Line::from(vec![
-                    Span::styled("Security Info", Style::default().add_modifier(Modifier::BOLD).fg(Color::Green))
+                    Span::styled("Security Info", state.theme.style(Tone::Good).add_modifier(Modifier::BOLD))
                 ]),
                 Line::from(""),
                 Line::from("Secure mode excludes sensitive files to ensure your"),
@@ -186,7 +197,7 @@ impl BackupModeSelectionScreen {
                         .borders(Borders::ALL)
                         .title("✓ Security Information")
                         .title_alignment(Alignment::Center)
-                        .style(Style::default().fg(Color::Green)),
+                        .style(state.theme.style(Tone::Good)),
                 )
                 .wrap(Wrap { trim: true });
 
@@ -194,13 +205,7 @@ impl BackupModeSelectionScreen {
         }
 
         // Footer
-        let shortcuts = [
-            ("1", "Secure"),
-            ("2", "Complete"),
-            ("Enter", "Select"),
-            ("Esc", "Back"),
-        ];
-
-        render_footer(frame, chunks[2], &shortcuts, None);
+        let hints = state.keymap.hints(KeyContext::BackupModeSelection, |_| true);
+        render_footer(frame, chunks[2], &hints, None, &state.theme);
     }
 }
\ No newline at end of file
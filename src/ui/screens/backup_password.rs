@@ -1,31 +1,63 @@
 use crossterm::event::KeyEvent;
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout};
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::security::SecurePassword;
 use crate::core::state::AppStateManager;
-use crate::ui::components::{render_header, render_footer};
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
 use crate::ui::widgets::PasswordInput;
 use crate::ui::terminal::centered_rect;
 
 pub struct BackupPasswordScreen {
     password_input: PasswordInput,
+    /// The config enables the system keyring
+    keyring: bool,
+    /// Passphrase already in the keyring, used when Enter is pressed
+    /// with nothing typed
+    remembered: Option<SecurePassword>,
+    /// "Remember passphrase" is ticked
+    remember: bool,
 }
 
 impl BackupPasswordScreen {
     pub fn new() -> Self {
         Self {
             password_input: PasswordInput::new(true, true), // Show strength, confirm mode
+            keyring: false,
+            remembered: None,
+            remember: false,
         }
     }
 
+    /// Set up the keyring option before the screen is shown; a passphrase
+    /// already remembered starts the box ticked
+    pub fn offer_keyring(&mut self, enabled: bool, remembered: Option<SecurePassword>) {
+        self.keyring = enabled;
+        self.remember = remembered.is_some();
+        self.remembered = remembered;
+    }
+
+    /// Whether the passphrase should be kept in the keyring
+    pub fn remember(&self) -> bool {
+        self.keyring && self.remember
+    }
+
+    /// Whether the keyring held a passphrase when the screen was shown
+    pub fn has_remembered(&self) -> bool {
+        self.remembered.is_some()
+    }
+
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
         let size = frame.area();
-        
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(4),  // Header
                 Constraint::Min(0),     // Content
+                Constraint::Length(if self.keyring { 3 } else { 0 }), // Keyring option
                 Constraint::Length(3),  // Footer
             ])
             .split(size);
@@ -36,23 +68,44 @@ impl BackupPasswordScreen {
             chunks[0],
             "Backup Encryption Password",
             Some("Complete mode requires encryption - enter a strong password"),
+            &state.theme,
         );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
 
         // Password input (centered)
         let password_area = centered_rect(60, 60, chunks[1]);
-        self.password_input.render(frame, password_area);
+        self.password_input.render(frame, password_area, &state.theme);
+
+        if self.keyring {
+            let mut lines = vec![Line::from(format!(
+                "{} Remember passphrase in the system keyring",
+                if self.remember { "[x]" } else { "[ ]" }
+            ))];
+            if self.remembered.is_some() {
+                lines.push(Line::from("A passphrase is remembered: press Enter with the fields empty to use it"));
+            }
+            frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[2]);
+        }
 
         // Footer
-        let shortcuts = [
-            ("Tab", "Switch fields"),
-            ("Enter", "Continue"),
-            ("Esc", "Back"),
-        ];
+        let hints = state.keymap.hints(KeyContext::BackupPassword, |action| action != Action::Remember || self.keyring);
+
+        render_footer(frame, chunks[3], &hints, state.status_message.as_deref(), &state.theme);
+    }
 
-        render_footer(frame, chunks[2], &shortcuts, None);
+    /// Pick up the password strength calculated in the background
+    pub fn poll(&mut self) {
+        self.password_input.poll_strength();
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> Option<SecurePassword> {
-        self.password_input.handle_key(key)
+    pub fn handle_key(&mut self, action: Option<Action>, key: KeyEvent) -> Option<SecurePassword> {
+        match action {
+            Some(Action::Remember) => {
+                self.remember = self.keyring && !self.remember;
+                None
+            }
+            Some(Action::Confirm) if self.password_input.is_empty() => self.remembered.clone(),
+            _ => self.password_input.handle_key(action, key),
+        }
     }
-}
\ No newline at end of file
+}
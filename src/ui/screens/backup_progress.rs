@@ -1,14 +1,25 @@
 use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::widgets::{Block, Borders};
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
 use crate::core::types::ProgressStatus;
-use crate::ui::components::{render_header, render_footer, render_progress_bar};
+use crate::ui::components::{render_backup_steps, render_header, render_footer, render_progress_bar, ProgressCounts};
+use crate::ui::terminal::format_bytes;
+use crate::ui::widgets::LoadingSpinner;
 
-pub struct BackupProgressScreen;
+pub struct BackupProgressScreen {
+    spinner: LoadingSpinner,
+}
 
 impl BackupProgressScreen {
     pub fn new() -> Self {
-        Self
+        Self { spinner: LoadingSpinner::new() }
+    }
+
+    /// Move the spinner on; called on every tick of the event loop
+    pub fn tick(&mut self) {
+        self.spinner.tick();
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
@@ -34,42 +45,62 @@ impl BackupProgressScreen {
             chunks[0],
             "Backup in Progress",
             Some(&format!("Creating {} backup...", mode_name)),
+            &state.theme,
         );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
 
-        // Progress content
-        if let Some(progress) = &state.backup_progress {
-            let percentage = if progress.total_items > 0 {
-                (progress.items_completed as f64 / progress.total_items as f64) * 100.0
-            } else {
-                0.0
-            };
-
-            render_progress_bar(
-                frame,
-                chunks[1],
-                &format!("Backup Progress - {}", progress.status.as_str()),
-                percentage,
-                &progress.current_item,
-                progress.items_completed,
-                progress.total_items,
-            );
-        } else {
-            // Fallback if no progress data
-            render_progress_bar(
-                frame,
-                chunks[1],
-                "Initializing Backup...",
-                0.0,
-                "Preparing...",
-                0,
-                1,
-            );
+        // Progress content: a spinner while the selected items are walked
+        // and counted, then a bar against what was counted
+        match &state.backup_progress {
+            Some(progress) if progress.status != ProgressStatus::Preparing => {
+                // Bytes when tar reports them, so one large item doesn't hold
+                // the bar still for its whole length
+                let percentage = if progress.total_bytes > 0 {
+                    (progress.bytes_processed as f64 / progress.total_bytes as f64).min(1.0) * 100.0
+                } else if progress.total_items > 0 {
+                    (progress.items_completed as f64 / progress.total_items as f64) * 100.0
+                } else {
+                    0.0
+                };
+
+                render_progress_bar(
+                    frame,
+                    chunks[1],
+                    &format!("Backup Progress - {}", progress.status.as_str()),
+                    percentage,
+                    &progress.current_item,
+                    &ProgressCounts {
+                        items_completed: progress.items_completed,
+                        total_items: progress.total_items,
+                        bytes_processed: progress.bytes_processed,
+                        total_bytes: progress.total_bytes,
+                        bytes_per_second: progress.bytes_per_second(),
+                    },
+                    &state.theme,
+                );
+            }
+            progress => {
+                let message = match progress {
+                    Some(progress) if progress.total_files > 0 => format!(
+                        "Counting files: {} files, {}",
+                        progress.total_files,
+                        format_bytes(progress.total_bytes)
+                    ),
+                    _ => "Preparing backup...".to_string(),
+                };
+                let block = Block::default().borders(Borders::ALL).title("Backup Progress - Preparing");
+                let inner = block.inner(chunks[1]);
+                frame.render_widget(block, chunks[1]);
+                let line = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(50), Constraint::Length(1), Constraint::Min(0)])
+                    .split(inner);
+                self.spinner.render(frame, line[1], &message, &state.theme);
+            }
         }
 
         // Footer
-        let shortcuts = [
-            ("Ctrl+C", "Cancel"),
-        ];
+        let hints: Vec<_> = state.keymap.hint(KeyContext::Global, Action::Quit).into_iter().collect();
 
         let status = if let Some(progress) = &state.backup_progress {
             match &progress.status {
@@ -80,6 +111,57 @@ impl BackupProgressScreen {
             None
         };
 
-        render_footer(frame, chunks[2], &shortcuts, status);
+        render_footer(frame, chunks[2], &hints, status, &state.theme);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::BackupProgress;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn screen_text(screen: &mut BackupProgressScreen, state: &AppStateManager) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|frame| screen.render(frame, state)).unwrap();
+        terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn test_files_are_counted_before_the_bar_shows() {
+        let mut screen = BackupProgressScreen::new();
+        let mut state = AppStateManager::new();
+        assert!(screen_text(&mut screen, &state).contains("Preparing backup..."));
+
+        state.backup_progress = Some(BackupProgress {
+            total_files: 1234,
+            total_bytes: 5 * 1024 * 1024,
+            status: ProgressStatus::Preparing,
+            ..BackupProgress::default()
+        });
+        let text = screen_text(&mut screen, &state);
+        assert!(text.contains("Counting files: 1234 files, 5.0 MB"), "{}", text);
+        assert!(!text.contains("%"));
+
+        // Once archiving starts, the bar runs against what was counted
+        let progress = state.backup_progress.as_mut().unwrap();
+        progress.status = ProgressStatus::Processing;
+        progress.current_item = "Archiving 1234 files".to_string();
+        progress.bytes_processed = 5 * 1024 * 1024 / 4;
+        let text = screen_text(&mut screen, &state);
+        assert!(text.contains("Archiving 1234 files"));
+        assert!(text.contains("25.0%"));
+        assert!(!text.contains("Counting files"));
+    }
+
+    #[test]
+    fn test_spinner_moves_on_ticks_not_redraws() {
+        let mut screen = BackupProgressScreen::new();
+        let state = AppStateManager::new();
+        let first = screen_text(&mut screen, &state);
+        assert!(first.contains("⠋ Preparing backup..."));
+        assert_eq!(screen_text(&mut screen, &state), first);
+
+        screen.tick();
+        assert!(screen_text(&mut screen, &state).contains("⠙ Preparing backup..."));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,159 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::{KeyContext, KeyHint};
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{format_bytes, truncate_text};
+use crate::ui::theme::Tone;
+
+pub struct CatalogBrowserScreen;
+
+impl CatalogBrowserScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Length(3),  // Search box
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Backup Catalog",
+            Some("Search file names, and indexed text, across every cataloged archive"),
+            &state.theme,
+        );
+
+        // Search box
+        let search = Paragraph::new(Line::from(vec![
+            Span::styled("🔍 ", state.theme.highlight()),
+            Span::raw(&state.catalog_query),
+            Span::styled("▏", state.theme.highlight()),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("File name or words in a file")
+                .style(state.theme.accent()),
+        );
+        frame.render_widget(search, chunks[1]);
+
+        // Results
+        let content_chunks = split_panels(chunks[2], 65);
+
+        let visible_height = content_chunks[0].height.saturating_sub(2) as usize;
+        let result_items: Vec<ListItem> = state.catalog_results
+            .iter()
+            .enumerate()
+            .skip(state.scroll_offset)
+            .take(visible_height)
+            .map(|(i, result)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    Style::default()
+                };
+
+                let file = &result.file;
+                ListItem::new(format!(
+                    "{} {} ({}) - {}",
+                    if result.snippet.is_some() { "≡" } else { " " },
+                    truncate_text(&file.item_path, 50),
+                    format_bytes(file.size),
+                    file.archive_created.format("%Y-%m-%d"),
+                ))
+                .style(style)
+            })
+            .collect();
+
+        let results_title = format!("Matches ({})", state.catalog_results.len());
+        let results_list = List::new(result_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(results_title)
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(results_list, content_chunks[0]);
+
+        let details_lines = if let Some(hit) = state.catalog_results.get(state.selected_item_index) {
+            let result = &hit.file;
+            let mut lines = vec![
+                Line::from(vec![
+                    Span::styled("File: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(&result.item_path),
+                ]),
+                Line::from(vec![
+                    Span::styled("Size: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(format_bytes(result.size)),
+                ]),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("Archive: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(&result.archive_name),
+                ]),
+                Line::from(vec![
+                    Span::styled("Created: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(result.archive_created.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Location: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(result.archive_path.to_string_lossy()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Host: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(result.hostname.as_deref().unwrap_or("unknown")),
+                ]),
+            ];
+            if let Some(snippet) = &hit.snippet {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled("Text match:", Style::default().add_modifier(Modifier::BOLD))));
+                lines.push(Line::from(Span::styled(snippet.as_str(), state.theme.accent())));
+            }
+            lines
+        } else {
+            vec![
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("No matching files", state.theme.style(Tone::Caution)),
+                ]),
+                Line::from(""),
+                Line::from("Archives are added to the catalog when they are created."),
+                Line::from("≡ marks a match inside a file (search_index.contents in the config)."),
+            ]
+        };
+
+        let details = Paragraph::new(details_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Details")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let mut hints = vec![KeyHint { keys: "Type".to_string(), description: "Search" }];
+        hints.extend(state.keymap.hints(KeyContext::CatalogBrowser, |_| true));
+
+        render_footer(frame, chunks[3], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -0,0 +1,160 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::truncate_text;
+use crate::ui::theme::Tone;
+
+pub struct ConfigEditorScreen;
+
+impl ConfigEditorScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        let Some(form) = &state.config_form else {
+            return;
+        };
+
+        // Header
+        let subtitle = if form.dirty {
+            "Unsaved changes"
+        } else {
+            "Edit backup modes, application configurations and validation settings"
+        };
+        render_header(frame, chunks[0], "Configuration Editor", Some(subtitle), &state.theme);
+
+        let content_chunks = split_panels(chunks[1], 60);
+
+        // Fields
+        let visible_height = content_chunks[0].height.saturating_sub(2) as usize;
+        let field_items: Vec<ListItem> = form.fields
+            .iter()
+            .enumerate()
+            .skip(state.scroll_offset)
+            .take(visible_height)
+            .map(|(i, field)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else if field.error.is_some() {
+                    state.theme.style(Tone::Danger)
+                } else {
+                    Style::default()
+                };
+
+                let marker = if field.error.is_some() { "✗ " } else { "  " };
+                ListItem::new(format!(
+                    "{}{} › {}: {}",
+                    marker,
+                    field.section,
+                    field.label,
+                    truncate_text(&field.value, 40),
+                ))
+                .style(style)
+            })
+            .collect();
+
+        let error_count = form.error_count();
+        let fields_title = if error_count > 0 {
+            format!("Fields ({} invalid)", error_count)
+        } else {
+            format!("Fields ({})", form.fields.len())
+        };
+        let fields_list = List::new(field_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(fields_title)
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(fields_list, content_chunks[0]);
+
+        // Selected field
+        let mut detail_lines = Vec::new();
+        if let Some(field) = form.fields.get(state.selected_item_index) {
+            detail_lines.push(Line::from(vec![
+                Span::styled("Section: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(&field.section),
+            ]));
+            detail_lines.push(Line::from(vec![
+                Span::styled("Field: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(&field.label),
+            ]));
+            detail_lines.push(Line::from(Span::styled(
+                field.kind.hint(),
+                state.theme.muted(),
+            )));
+            detail_lines.push(Line::from(""));
+
+            match &state.config_edit_buffer {
+                Some(buffer) => {
+                    detail_lines.push(Line::from(Span::styled(
+                        "Editing:",
+                        state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD),
+                    )));
+                    detail_lines.push(Line::from(vec![
+                        Span::raw(buffer.as_str()),
+                        Span::styled("▏", state.theme.highlight()),
+                    ]));
+                    if let Err(e) = field.kind.validate(buffer) {
+                        detail_lines.push(Line::from(""));
+                        detail_lines.push(Line::from(Span::styled(e, state.theme.style(Tone::Danger))));
+                    }
+                }
+                None => {
+                    detail_lines.push(Line::from(Span::styled(
+                        "Value:",
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )));
+                    detail_lines.push(Line::from(field.value.as_str()));
+                    if let Some(error) = &field.error {
+                        detail_lines.push(Line::from(""));
+                        detail_lines.push(Line::from(Span::styled(
+                            format!("✗ {}", error),
+                            state.theme.style(Tone::Danger),
+                        )));
+                    }
+                }
+            }
+        }
+
+        let details = Paragraph::new(detail_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Field")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let context = if state.config_edit_buffer.is_some() {
+            KeyContext::ConfigField
+        } else {
+            KeyContext::ConfigEditor
+        };
+        let hints = state.keymap.hints(context, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -0,0 +1,169 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::diff::DiffLine;
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{hint_spans, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{centered_rect, format_bytes};
+use crate::ui::theme::Tone;
+
+pub struct ConfigHistoryScreen;
+
+impl ConfigHistoryScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Configuration History",
+            Some("Compare earlier versions with the current config and roll back"),
+            &state.theme,
+        );
+
+        let content_chunks = split_panels(chunks[1], 30);
+
+        // Snapshots
+        let visible_height = content_chunks[0].height.saturating_sub(2) as usize;
+        let snapshot_items: Vec<ListItem> = state.config_snapshots
+            .iter()
+            .enumerate()
+            .skip(state.scroll_offset)
+            .take(visible_height)
+            .map(|(i, snapshot)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    Style::default()
+                };
+
+                ListItem::new(format!(
+                    "{}  {}",
+                    snapshot.taken.format("%Y-%m-%d %H:%M:%S"),
+                    format_bytes(snapshot.size),
+                ))
+                .style(style)
+            })
+            .collect();
+
+        let snapshots_title = format!("Versions ({})", state.config_snapshots.len());
+        let snapshots_list = List::new(snapshot_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(snapshots_title)
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(snapshots_list, content_chunks[0]);
+
+        // Diff of the selected version against the current config
+        let diff_lines: Vec<Line> = if state.config_snapshots.is_empty() {
+            vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    "No earlier versions yet. A version is kept each time the config is saved.",
+                    state.theme.style(Tone::Caution),
+                )),
+            ]
+        } else if state.config_diff.is_empty() {
+            vec![Line::from(Span::styled(
+                "Identical to the current config",
+                state.theme.style(Tone::Good),
+            ))]
+        } else {
+            state.config_diff
+                .iter()
+                .map(|line| match line {
+                    DiffLine::Same(text) => Line::from(format!("  {}", text)),
+                    // The snapshot is the "old" side, so lines only in it
+                    // come back on rollback
+                    DiffLine::Removed(text) => Line::from(Span::styled(
+                        format!("+ {}", text),
+                        state.theme.style(Tone::Good),
+                    )),
+                    DiffLine::Added(text) => Line::from(Span::styled(
+                        format!("- {}", text),
+                        state.theme.style(Tone::Danger),
+                    )),
+                    DiffLine::Skipped(count) => Line::from(Span::styled(
+                        format!("  … {} unchanged lines …", count),
+                        state.theme.muted(),
+                    )),
+                })
+                .collect()
+        };
+
+        let diff = Paragraph::new(diff_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Changes on rollback (+ restored, - removed)")
+                    .title_alignment(Alignment::Center),
+            )
+            .scroll((state.config_diff_scroll, 0));
+        frame.render_widget(diff, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::ConfigHistory, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+
+        if state.config_confirm_rollback {
+            self.render_rollback_confirm(frame, state);
+        }
+    }
+
+    fn render_rollback_confirm(&self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let Some(snapshot) = state.config_snapshots.get(state.selected_item_index) else {
+            return;
+        };
+
+        let area = centered_rect(60, 30, frame.area());
+        frame.render_widget(Clear, area);
+
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    format!("Roll back to the version from {}?", snapshot.taken.format("%Y-%m-%d %H:%M:%S")),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            Line::from(""),
+            Line::from("The current config is kept as a new version, so this can be undone."),
+            Line::from(""),
+            Line::from(hint_spans(&state.keymap.hints(KeyContext::ConfigRollback, |_| true), &state.theme)),
+        ];
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Confirm Rollback")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.style(Tone::Caution)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+}
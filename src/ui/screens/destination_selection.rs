@@ -0,0 +1,138 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{format_bytes, pad_text};
+use crate::ui::theme::Tone;
+
+pub struct DestinationSelectionScreen;
+
+impl DestinationSelectionScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        let (_, estimate, _) = state.get_backup_summary();
+        let pending = if state.backup_sizes_pending > 0 { ", still sizing" } else { "" };
+        let subtitle = format!(
+            "Estimated backup: {} before compression{} | Current: {}",
+            format_bytes(estimate),
+            pending,
+            state.backup_destination.as_ref().map(|d| d.to_string()).unwrap_or_else(|| "default".to_string()),
+        );
+        render_header(frame, chunks[0], "Back Up to a Removable Drive", Some(&subtitle), &state.theme);
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
+
+        let content_chunks = split_panels(chunks[1], 60);
+
+        // Drives
+        let items: Vec<ListItem> = state.removable_drives
+            .iter()
+            .enumerate()
+            .map(|(i, drive)| {
+                let free = drive.free.map(format_bytes).unwrap_or_else(|| "?".to_string());
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else if drive.has_room_for(estimate) == Some(false) {
+                    state.theme.style(Tone::Danger)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(format!("{} {:>10} free  {}", pad_text(&drive.name(), 40), free, drive.fs_type)).style(style)
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Mounted Drives ({})", state.removable_drives.len()))
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[0]);
+
+        // Details
+        let label = |text: &'static str| Span::styled(text, Style::default().add_modifier(Modifier::BOLD));
+        let lines = match state.removable_drives.get(state.selected_item_index) {
+            Some(drive) => {
+                let mut lines = vec![
+                    Line::from(vec![label("Device: "), Span::raw(&drive.device)]),
+                    Line::from(vec![label("Mount point: "), Span::raw(drive.mount_point.display().to_string())]),
+                    Line::from(vec![label("Filesystem: "), Span::raw(&drive.fs_type)]),
+                    Line::from(vec![
+                        label("Free: "),
+                        Span::raw(match (drive.free, drive.size) {
+                            (Some(free), Some(size)) => format!("{} of {}", format_bytes(free), format_bytes(size)),
+                            _ => "unknown".to_string(),
+                        }),
+                    ]),
+                    Line::from(""),
+                ];
+                match (drive.has_room_for(estimate), drive.free) {
+                    (Some(false), Some(free)) => lines.push(Line::from(Span::styled(
+                        format!(
+                            "{}Not enough space: the backup needs up to {} more before compression",
+                            state.theme.tag(Tone::Danger),
+                            format_bytes(estimate - free),
+                        ),
+                        state.theme.style(Tone::Danger),
+                    ))),
+                    (Some(true), _) => lines.push(Line::from(Span::styled(
+                        format!("{}Room for the estimated backup", state.theme.tag(Tone::Good)),
+                        state.theme.style(Tone::Good),
+                    ))),
+                    _ => lines.push(Line::from("Free space could not be read")),
+                }
+                if !drive.keeps_permissions() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        format!(
+                            "{}{} has no file permissions: anyone with the drive can read what is on it. \
+                             Encrypt backups written here, or reformat it as ext4.",
+                            state.theme.tag(Tone::Caution),
+                            drive.fs_type,
+                        ),
+                        state.theme.style(Tone::Caution),
+                    )));
+                }
+                lines
+            }
+            None => vec![Line::from(Span::styled(
+                "No removable drives are mounted. Plug one in, let the desktop mount it, and rescan.",
+                state.theme.style(Tone::Caution),
+            ))],
+        };
+
+        let details = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Details")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::DestinationSelection, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
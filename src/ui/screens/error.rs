@@ -1,13 +1,15 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
 };
 
-use crate::core::state::AppStateManager;
+use crate::core::keymap::{Action, KeyContext};
+use crate::core::state::{AppState, AppStateManager};
 use crate::ui::components::{render_header, render_footer};
 use crate::ui::terminal::centered_rect;
+use crate::ui::theme::Tone;
 
 pub struct ErrorScreen;
 
@@ -28,50 +30,64 @@ impl ErrorScreen {
             ])
             .split(size);
 
+        let AppState::Error(error) = &state.current_state else {
+            return;
+        };
+
         // Header
         render_header(
             frame,
             chunks[0],
             "Error",
-            Some("An error has occurred"),
+            Some(&format!("{} error {}", error.category.label(), error.category.code())),
+            &state.theme,
         );
 
-        // Error content (centered)
-        let error_area = centered_rect(80, 60, chunks[1]);
-        
-        let error_message = if let crate::core::state::AppState::Error(ref error) = state.current_state {
-            error.clone()
-        } else {
-            state.error_message.clone().unwrap_or_else(|| "Unknown error occurred".to_string())
-        };
+        // Error content (centered), taller with the technical details
+        let error_area = centered_rect(80, if state.error_details { 90 } else { 60 }, chunks[1]);
 
-        let error_lines = vec![
+        let mut error_lines = vec![
             Line::from(""),
             Line::from(vec![
-                Span::styled("❌ Error Details:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Red))
+                Span::styled("❌ Error Details:", state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
             ]),
             Line::from(""),
-            Line::from(error_message),
+            Line::from(error.message.clone()),
             Line::from(""),
+        ];
+        if state.error_details {
+            error_lines.push(Line::from(vec![
+                Span::styled("Technical Details:", Style::default().add_modifier(Modifier::BOLD))
+            ]));
+            for (depth, cause) in error.chain.iter().enumerate() {
+                let prefix = if depth == 0 { "" } else { "caused by: " };
+                error_lines.push(Line::from(Span::styled(format!("{}{}", prefix, cause), state.theme.muted())));
+            }
+            error_lines.push(Line::from(""));
+        }
+        error_lines.extend([
             Line::from(""),
             Line::from(vec![
                 Span::styled("What you can do:", Style::default().add_modifier(Modifier::BOLD))
             ]),
+        ]);
+        if let Some(operation) = state.retry {
+            error_lines.push(Line::from(format!(
+                "• Fix the cause (plug in the drive, mount the share) and press R to retry {} with the same settings",
+                operation.describe()
+            )));
+        }
+        error_lines.extend([
             Line::from("• Press Enter or Esc to return to the previous screen"),
-            Line::from("• Check the error message for specific details"),
-            Line::from("• Try the operation again with different settings"),
+            Line::from("• Press T for the technical details, and C to copy them for a bug report"),
             Line::from("• Use Ctrl+H to view the help guide"),
             Line::from("• Enable debug mode for more detailed logging"),
             Line::from(""),
             Line::from(vec![
-                Span::styled("Common Solutions:", Style::default().add_modifier(Modifier::BOLD))
+                Span::styled("Suggestions:", Style::default().add_modifier(Modifier::BOLD))
             ]),
-            Line::from("• Ensure you have sufficient disk space"),
-            Line::from("• Check file and directory permissions"),
-            Line::from("• Verify the backup configuration is correct"),
-            Line::from("• Make sure required tools are installed"),
-            Line::from("• Try with a smaller selection of files"),
-        ];
+        ]);
+        error_lines.extend(error.suggestions.iter().map(|suggestion| Line::from(format!("• {}", suggestion))));
 
         let error_paragraph = Paragraph::new(error_lines)
             .block(
@@ -79,7 +95,7 @@ impl ErrorScreen {
                     .borders(Borders::ALL)
                     .title("Error Information")
                     .title_alignment(Alignment::Center)
-                    .style(Style::default().fg(Color::Red)),
+                    .style(state.theme.style(Tone::Danger)),
             )
             .alignment(Alignment::Left)
             .wrap(Wrap { trim: true });
@@ -87,12 +103,43 @@ impl ErrorScreen {
         frame.render_widget(error_paragraph, error_area);
 
         // Footer
-        let shortcuts = [
-            ("Enter", "Return"),
-            ("Esc", "Return"),
-            ("Ctrl+H", "Help"),
-        ];
+        let mut hints = state.keymap.hints(KeyContext::Error, |action| {
+            action != Action::Retry || state.retry.is_some()
+        });
+        hints.extend(state.keymap.hint(KeyContext::Global, Action::Help));
+
+        let status = state.status_message.as_deref().unwrap_or("Review the error and try again");
+        render_footer(frame, chunks[2], &hints, Some(status), &state.theme);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::errors::{AppError, ErrorCategory};
+    use crate::core::state::RetryOperation;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn screen_text(state: &AppStateManager) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(200, 50)).unwrap();
+        terminal.draw(|frame| ErrorScreen::new().render(frame, state)).unwrap();
+        terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn test_retry_is_offered_only_for_retryable_errors() {
+        let mut state = AppStateManager::new();
+        state.transition_to(AppState::BackupProgress);
+        state.set_retryable_error(AppError::new(ErrorCategory::Backup, "Backup failed: drive gone"), RetryOperation::Backup);
+        let text = screen_text(&state);
+        assert!(text.contains("press R to retry the backup"));
+        assert!(text.contains("Retry"));
 
-        render_footer(frame, chunks[2], &shortcuts, Some("Review the error and try again"));
+        // A later error that can't be retried doesn't inherit the offer
+        state.set_error(AppError::new(ErrorCategory::Config, "Bad exclusion pattern"));
+        assert_eq!(state.retry, None);
+        let text = screen_text(&state);
+        assert!(!text.contains("to retry"));
+        assert!(!text.contains("Retry"));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,159 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{hint_spans, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{centered_rect, format_bytes, pad_text};
+use crate::ui::theme::Tone;
+
+pub struct ExclusionEditorScreen;
+
+impl ExclusionEditorScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        let subtitle = format!(
+            "Patterns kept out of {} mode backups{}",
+            state.exclusion_mode.as_str(),
+            if state.exclusions_dirty { " (unsaved changes)" } else { "" }
+        );
+        render_header(frame, chunks[0], "Exclusions", Some(&subtitle), &state.theme);
+
+        let content_chunks = split_panels(chunks[1], 60);
+
+        // Patterns with what each one matches on this machine
+        let visible_height = content_chunks[0].height.saturating_sub(3) as usize;
+        let mut pattern_items = vec![ListItem::new(format!("  {:<32} {:>9} {:>10}", "Pattern", "Files", "Size"))
+            .style(Style::default().add_modifier(Modifier::BOLD))];
+
+        pattern_items.extend(
+            state.exclusion_patterns
+                .iter()
+                .enumerate()
+                .skip(state.scroll_offset)
+                .take(visible_height)
+                .map(|(i, pattern)| {
+                    let style = if i == state.selected_item_index {
+                        state.theme.selection()
+                    } else {
+                        Style::default()
+                    };
+
+                    let (files, bytes) = match state.exclusion_counts.as_ref().and_then(|counts| counts.get(i)) {
+                        Some(count) => (count.files.to_string(), format_bytes(count.bytes)),
+                        None => ("…".to_string(), "…".to_string()),
+                    };
+                    ListItem::new(format!("  {} {:>9} {:>10}", pad_text(pattern, 32), files, bytes))
+                        .style(style)
+                }),
+        );
+
+        let patterns_list = List::new(pattern_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Patterns ({})", state.exclusion_patterns.len()))
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(patterns_list, content_chunks[0]);
+
+        let mut info_lines = match &state.exclusion_counts {
+            Some(counts) => {
+                let files: usize = counts.iter().map(|count| count.files).sum();
+                let bytes: u64 = counts.iter().map(|count| count.bytes).sum();
+                vec![
+                    Line::from(vec![
+                        Span::styled("Excluded: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(format!("{} files, {}", files, format_bytes(bytes))),
+                    ]),
+                    Line::from(Span::styled(
+                        "Counted across the items this mode backs up",
+                        state.theme.muted(),
+                    )),
+                ]
+            }
+            None => vec![Line::from(Span::styled(
+                "Counting matches in your home directory…",
+                state.theme.style(Tone::Caution),
+            ))],
+        };
+        info_lines.extend([
+            Line::from(""),
+            Line::from(Span::styled("Syntax", Style::default().add_modifier(Modifier::BOLD))),
+            Line::from("• node_modules, *.log - no '/': matches that name at any depth"),
+            Line::from("• .config/*/Cache - with '/': relative to your home directory"),
+            Line::from("• * stays within one directory, ** spans several"),
+            Line::from("• Excluding a directory excludes everything in it"),
+        ]);
+
+        let info = Paragraph::new(info_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Matches")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+        frame.render_widget(info, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::ExclusionEditor, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+
+        if let Some(input) = &state.exclusion_input {
+            self.render_add_pattern(frame, state, input);
+        }
+    }
+
+    fn render_add_pattern(&self, frame: &mut ratatui::Frame, state: &AppStateManager, input: &str) {
+        let area = centered_rect(60, 30, frame.area());
+        frame.render_widget(Clear, area);
+
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("Pattern: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(input),
+                Span::styled("▏", state.theme.highlight()),
+            ]),
+            Line::from(Span::styled(
+                "e.g. *.iso, .cache or .config/*/GPUCache",
+                state.theme.muted(),
+            )),
+            Line::from(""),
+            Line::from(hint_spans(&state.keymap.hints(KeyContext::ExclusionInput, |_| true), &state.theme)),
+        ];
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Add Exclusion")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.accent()),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+}
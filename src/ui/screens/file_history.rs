@@ -0,0 +1,121 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem},
+};
+
+use crate::backend::checksum::{self, HashAlgorithm};
+use crate::core::keymap::{Action, KeyContext};
+use crate::core::state::AppStateManager;
+use crate::core::types::CatalogMatch;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::terminal::{format_bytes, pad_text, truncate_text};
+use crate::ui::theme::{Theme, Tone};
+
+pub struct FileHistoryScreen;
+
+impl FileHistoryScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        let Some(file) = state.file_history.as_ref() else {
+            render_header(frame, chunks[0], "File History", None, &state.theme);
+            let hints = state.keymap.hints(KeyContext::FileHistory, |action| action == Action::Back);
+            render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+            return;
+        };
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "File History",
+            Some(truncate_text(&file.path, 70).as_str()),
+            &state.theme,
+        );
+
+        // Versions, newest first, each compared with the run before it
+        let visible_height = chunks[1].height.saturating_sub(3) as usize;
+        let skip = (state.find_version_index + 1).saturating_sub(visible_height);
+        let mut lines = vec![ListItem::new(Line::from(Span::styled(
+            format!("{:<16}  {:<12}  {:>10}  {:<12}  {:<8}  {}", "Date", "Host", "Size", "SHA-256", "Change", "Archive"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )))];
+        lines.extend(
+            file.versions
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .take(visible_height)
+                .map(|(i, version)| {
+                    let style = if i == state.find_version_index {
+                        state.theme.selection()
+                    } else {
+                        Style::default()
+                    };
+                    let (change, change_style) = describe_change(version, file.versions.get(i + 1), &state.theme);
+                    let hash = version.sha256.as_deref().map(checksum::digest).map(|h| &h[..h.len().min(12)]).unwrap_or("-");
+
+                    ListItem::new(Line::from(vec![
+                        Span::raw(format!(
+                            "{:<16}  {}  {:>10}  {:<12}  ",
+                            version.archive_created.format("%Y-%m-%d %H:%M"),
+                            pad_text(version.hostname.as_deref().unwrap_or("?"), 12),
+                            format_bytes(version.size),
+                            hash,
+                        )),
+                        Span::styled(format!("{:<8}", change), change_style),
+                        Span::raw(format!("  {}", truncate_text(&version.archive_name, 40))),
+                    ]))
+                    .style(style)
+                }),
+        );
+
+        let title = format!("Versions ({})", file.versions.len());
+        let list = List::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::FileHistory, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
+
+/// How a version differs from the previous run's copy. Without hashes
+/// made the same way on both sides only a size change can be told apart.
+fn describe_change(version: &CatalogMatch, older: Option<&CatalogMatch>, theme: &Theme) -> (&'static str, Style) {
+    let Some(older) = older else {
+        return ("first", theme.accent());
+    };
+    match (&version.sha256, &older.sha256) {
+        (Some(new), Some(old)) if HashAlgorithm::of(new) == HashAlgorithm::of(old) => {
+            if new == old {
+                ("same", theme.muted())
+            } else {
+                ("changed", theme.style(Tone::Caution))
+            }
+        }
+        _ if version.size != older.size => ("changed", theme.style(Tone::Caution)),
+        _ => ("?", theme.muted()),
+    }
+}
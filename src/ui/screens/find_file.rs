@@ -0,0 +1,160 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+
+use crate::core::keymap::{KeyContext, KeyHint};
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{format_bytes, truncate_text};
+use crate::ui::theme::Tone;
+
+pub struct FindFileScreen;
+
+impl FindFileScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Length(3),  // Search box
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Find File",
+            Some("Locate a file or glob across every archive and restore any version"),
+            &state.theme,
+        );
+
+        // Search box
+        let search = Paragraph::new(Line::from(vec![
+            Span::styled("🔍 ", state.theme.highlight()),
+            Span::raw(&state.find_query),
+            Span::styled("▏", state.theme.highlight()),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Path or glob (e.g. .bashrc, .config/*/settings.json)")
+                .style(state.theme.accent()),
+        );
+        frame.render_widget(search, chunks[1]);
+
+        let content_chunks = split_panels(chunks[2], 55);
+
+        // Files
+        let visible_height = content_chunks[0].height.saturating_sub(2) as usize;
+        let file_items: Vec<ListItem> = state.find_results
+            .iter()
+            .enumerate()
+            .skip(state.scroll_offset)
+            .take(visible_height)
+            .map(|(i, file)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    Style::default()
+                };
+
+                let versions = file.versions.len();
+                ListItem::new(format!(
+                    "{} ({} version{})",
+                    truncate_text(&file.path, 45),
+                    versions,
+                    if versions == 1 { "" } else { "s" },
+                ))
+                .style(style)
+            })
+            .collect();
+
+        let files_title = format!("Files ({})", state.find_results.len());
+        let files_list = List::new(file_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(files_title)
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(files_list, content_chunks[0]);
+
+        // Versions
+        if let Some(file) = state.find_results.get(state.selected_item_index) {
+            let version_items: Vec<ListItem> = file.versions
+                .iter()
+                .enumerate()
+                .map(|(i, version)| {
+                    let style = if i == state.find_version_index {
+                        state.theme.selection()
+                    } else {
+                        Style::default()
+                    };
+
+                    let mut spans = vec![
+                        Span::raw(format!(
+                            "{}  {:>10}  {}  {}",
+                            version.archive_created.format("%Y-%m-%d %H:%M"),
+                            format_bytes(version.size),
+                            version.hostname.as_deref().unwrap_or("?"),
+                            truncate_text(&version.archive_name, 30),
+                        )),
+                    ];
+                    if i == 0 {
+                        spans.push(Span::styled(
+                            "  ★ newest",
+                            state.theme.style(Tone::Good).add_modifier(Modifier::BOLD),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans)).style(style)
+                })
+                .collect();
+
+            let versions_list = List::new(version_items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Versions")
+                        .title_alignment(Alignment::Center),
+                );
+            frame.render_widget(versions_list, content_chunks[1]);
+        } else {
+            let hint = if state.find_query.is_empty() {
+                "Type a path or glob to search the catalog."
+            } else {
+                "No archived copies found."
+            };
+            let empty = Paragraph::new(vec![
+                Line::from(""),
+                Line::from(Span::styled(hint, state.theme.style(Tone::Caution))),
+            ])
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Versions")
+                    .title_alignment(Alignment::Center),
+            );
+            frame.render_widget(empty, content_chunks[1]);
+        }
+
+        // Footer
+        let mut hints = vec![KeyHint { keys: "Type".to_string(), description: "Search" }];
+        hints.extend(state.keymap.hints(KeyContext::FindFile, |_| true));
+
+        render_footer(frame, chunks[3], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
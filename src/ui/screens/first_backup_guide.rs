@@ -0,0 +1,196 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::backend::first_estimate::FirstBackupEstimate;
+use crate::core::keymap::{Action, KeyContext};
+use crate::core::state::AppStateManager;
+use crate::core::types::BackupMode;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{format_bytes, format_duration};
+use crate::ui::theme::{Theme, Tone};
+
+pub struct FirstBackupGuideScreen;
+
+impl FirstBackupGuideScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Your First Backup",
+            Some("Measured on this machine from the config just written; nothing has been backed up yet"),
+            &state.theme,
+        );
+
+        let content_chunks = split_panels(chunks[1], 55);
+
+        let (estimates, recommendations) = match &state.first_estimate {
+            Some(estimate) => (estimate_lines(estimate, &state.theme), recommendation_lines(estimate, &state.theme)),
+            None => {
+                let measuring = vec![Line::from(Span::styled(
+                    "Measuring how much there is to back up, and how fast this machine reads and compresses it...",
+                    state.theme.style(Tone::Caution),
+                ))];
+                (measuring, Vec::new())
+            }
+        };
+
+        let estimates = Paragraph::new(estimates).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Estimates")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(estimates, content_chunks[0]);
+
+        let recommendations = Paragraph::new(recommendations).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Recommendations")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(recommendations, content_chunks[1]);
+
+        // Footer
+        let ready = state.first_estimate.as_ref().is_some_and(|estimate| estimate.recommended().is_some());
+        let hints = state.keymap.hints(KeyContext::FirstBackupGuide, |action| action != Action::Confirm || ready);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
+
+/// Size and time of each mode's first backup with each compressor
+fn estimate_lines(estimate: &FirstBackupEstimate, theme: &Theme) -> Vec<Line<'static>> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let recommended = estimate.recommended().map(|sample| sample.compression);
+    let mut lines = Vec::new();
+    for volume in &estimate.volumes {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} mode", volume.mode.as_str()), bold),
+            Span::raw(format!(": {} in {} files", format_bytes(volume.bytes), volume.files)),
+        ]));
+        for sample in &estimate.compressors {
+            let time = estimate
+                .duration(volume, sample)
+                .map(|time| format!("about {}", format_duration(time)))
+                .unwrap_or_else(|| "time unknown".to_string());
+            let marker = if Some(sample.compression) == recommended { "  (recommended)" } else { "" };
+            lines.push(Line::from(format!(
+                "  {:<6} ~{:>10}, {}{}",
+                sample.compression.as_str(),
+                format_bytes(estimate.archive_size(volume, sample)),
+                time,
+                marker,
+            )));
+        }
+        lines.push(Line::from(""));
+    }
+
+    if estimate.compressors.is_empty() {
+        lines.push(Line::from("No compressor could be timed; is gzip installed?"));
+    }
+    lines.push(Line::from(match estimate.read_speed {
+        Some(speed) => format!("Read speed: {}/s, sampled from your files", format_bytes(speed as u64)),
+        None => "Read speed: nothing could be read to sample".to_string(),
+    }));
+    for sample in &estimate.compressors {
+        lines.push(Line::from(format!(
+            "{} level {}: {}/s, {:.0}% of the original size",
+            sample.compression.as_str(),
+            sample.level,
+            format_bytes(sample.speed as u64),
+            sample.ratio * 100.0,
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Later backups of the same files are usually quicker: they are cached by then.",
+        theme.muted(),
+    )));
+    lines
+}
+
+/// Compression and destination advice from what was measured
+fn recommendation_lines(estimate: &FirstBackupEstimate, theme: &Theme) -> Vec<Line<'static>> {
+    let advice = |tone: Tone, text: String| Line::from(Span::styled(format!("{}{}", theme.tag(tone), text), theme.style(tone)));
+    let mut lines = Vec::new();
+
+    let recommended = estimate.recommended();
+    if let Some(sample) = recommended {
+        lines.push(advice(
+            Tone::Good,
+            format!(
+                "Compress with {}: within 10% of the smallest archive, and the fastest of those. Press Enter to save it to the config.",
+                sample.compression.as_str()
+            ),
+        ));
+        lines.push(Line::from(""));
+    }
+
+    // The complete archive is the larger one to find room for
+    let complete = estimate.volumes.iter().find(|volume| volume.mode == BackupMode::Complete);
+    let archive = match (complete, recommended) {
+        (Some(volume), Some(sample)) => estimate.archive_size(volume, sample),
+        (Some(volume), None) => volume.bytes,
+        _ => 0,
+    };
+    let mut drives = estimate.drives_with_room(archive).peekable();
+    if drives.peek().is_some() {
+        let names: Vec<String> = drives.map(|drive| drive.name()).collect();
+        lines.push(advice(
+            Tone::Good,
+            format!(
+                "Back up to {}: it has room for a complete archive. Press D when selecting items to use it.",
+                names.join(" or ")
+            ),
+        ));
+    } else {
+        lines.push(advice(
+            Tone::Caution,
+            "A backup on the disk it came from is lost with that disk. Plug in a removable drive, \
+             or add an rclone remote to a profile's copies."
+                .to_string(),
+        ));
+    }
+    lines.push(Line::from(""));
+
+    if let Some(free) = estimate.home_free.filter(|free| *free < archive) {
+        lines.push(advice(
+            Tone::Danger,
+            format!(
+                "Only {} free on this disk; a complete archive needs about {}. Back up elsewhere.",
+                format_bytes(free),
+                format_bytes(archive)
+            ),
+        ));
+        lines.push(Line::from(""));
+    }
+
+    lines.push(advice(
+        Tone::Caution,
+        "Complete backups hold your keys and are encrypted. Keep the password somewhere other than this machine, \
+         or the backup can't be opened after losing it."
+            .to_string(),
+    ));
+    lines
+}
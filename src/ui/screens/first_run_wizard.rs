@@ -0,0 +1,120 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::theme::Tone;
+
+pub struct FirstRunWizardScreen;
+
+impl FirstRunWizardScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Welcome: First-Run Setup",
+            Some("No backup config was found; choose what to back up and a starter config is written"),
+            &state.theme,
+        );
+
+        let content_chunks = split_panels(chunks[1], 50);
+
+        // Categories
+        let items: Vec<ListItem> = state.wizard_categories
+            .iter()
+            .enumerate()
+            .map(|(i, category)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else if category.paths.is_empty() {
+                    state.theme.muted()
+                } else {
+                    Style::default()
+                };
+
+                let mut spans = vec![
+                    Span::raw(if category.selected { "[x] " } else { "[ ] " }),
+                    Span::raw(category.label),
+                ];
+                if category.paths.is_empty() {
+                    spans.push(Span::raw("  (not found)"));
+                } else {
+                    spans.push(Span::raw(format!("  ({} found)", category.paths.len())));
+                }
+                if category.sensitive {
+                    spans.push(Span::styled("  🔒", state.theme.style(Tone::Caution)));
+                }
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Categories")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[0]);
+
+        // Paths found for the selected category
+        let mut lines = Vec::new();
+        if let Some(category) = state.wizard_categories.get(state.selected_item_index) {
+            if category.paths.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "Nothing from this category was found in your home directory.",
+                    state.theme.muted(),
+                )));
+            }
+            for path in &category.paths {
+                lines.push(Line::from(format!("~/{}", path)));
+            }
+            if category.sensitive {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Private keys and credentials: backed up only in complete mode, which always encrypts the archive. Secure mode leaves them out.",
+                    state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD),
+                )));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Saved to {} (0600)", state.wizard_config_path.display()),
+            state.theme.accent(),
+        )));
+
+        let found = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Found")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(found, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::FirstRunWizard, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -0,0 +1,147 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::terminal::{format_age, format_bytes, pad_text};
+use crate::ui::theme::Tone;
+
+pub struct FleetOverviewScreen;
+
+impl FleetOverviewScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        let overdue = state.fleet_hosts.iter().filter(|host| host.overdue).count();
+        let subtitle = if overdue > 0 {
+            format!("{} of {} machines overdue for a backup", overdue, state.fleet_hosts.len())
+        } else {
+            format!("{} machines, all backed up on schedule", state.fleet_hosts.len())
+        };
+        render_header(frame, chunks[0], "Machines", Some(&subtitle), &state.theme);
+
+        let content_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),     // Hosts
+                Constraint::Length(7),  // Selected host
+            ])
+            .split(chunks[1]);
+
+        // Hosts
+        let now = chrono::Utc::now();
+        let visible_height = content_chunks[0].height.saturating_sub(3) as usize;
+        let mut host_items = vec![ListItem::new(format!(
+            "  {:<20} {:<16} {:<10} {:>10} {:>8}  {}",
+            "Host", "Last backup", "Mode", "Size", "Archives", "Verification"
+        ))
+        .style(Style::default().add_modifier(Modifier::BOLD))];
+
+        host_items.extend(
+            state.fleet_hosts
+                .iter()
+                .enumerate()
+                .skip(state.scroll_offset)
+                .take(visible_height)
+                .map(|(i, host)| {
+                    let style = if i == state.selected_item_index {
+                        state.theme.selection()
+                    } else if host.overdue {
+                        state.theme.style(Tone::Danger)
+                    } else {
+                        Style::default()
+                    };
+
+                    let marker = if host.overdue { "! " } else { "  " };
+                    let verification_style = match host.verification.tone() {
+                        Some(tone) => state.theme.style(tone),
+                        None => state.theme.muted(),
+                    };
+                    let line = Line::from(vec![
+                        Span::raw(format!(
+                            "{}{} {:<16} {:<10} {:>10} {:>8}  ",
+                            marker,
+                            pad_text(&host.hostname, 20),
+                            format_age(now - host.last_backup),
+                            host.last_mode.as_str(),
+                            format_bytes(host.last_size),
+                            host.archive_count,
+                        )),
+                        Span::styled(host.verification.label(), verification_style),
+                    ]);
+                    ListItem::new(line).style(style)
+                }),
+        );
+
+        let hosts_list = List::new(host_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Hosts ({})", state.fleet_hosts.len()))
+                    .title_alignment(Alignment::Center),
+            );
+        frame.render_widget(hosts_list, content_chunks[0]);
+
+        // Selected host
+        let detail_lines = match state.fleet_hosts.get(state.selected_item_index) {
+            Some(host) => {
+                let mut lines = vec![
+                    Line::from(vec![
+                        Span::styled("Latest archive: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(&host.last_archive),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("Backed up: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(host.last_backup.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()),
+                        Span::raw(format!("   Total in catalog: {}", format_bytes(host.total_size))),
+                    ]),
+                ];
+                if host.overdue {
+                    lines.push(Line::from(Span::styled(
+                        format!("{}Overdue: the last backup is older than this machine's backup strategy allows", state.theme.tag(Tone::Danger)),
+                        state.theme.style(Tone::Danger),
+                    )));
+                }
+                lines
+            }
+            None => vec![Line::from(Span::styled(
+                "No machines in the catalog yet. Run a backup or set up catalog sync.",
+                state.theme.style(Tone::Caution),
+            ))],
+        };
+
+        let details = Paragraph::new(detail_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Details")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::FleetOverview, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -1,12 +1,15 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
 };
 
+use crate::core::keymap::KeyContext;
 use crate::core::state::AppStateManager;
 use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::theme::Tone;
 
 pub struct HelpScreen;
 
@@ -15,7 +18,7 @@ impl HelpScreen {
         Self
     }
 
-    pub fn render(&mut self, frame: &mut ratatui::Frame, _state: &AppStateManager) {
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
         let size = frame.area();
         
         let chunks = Layout::default()
@@ -33,16 +36,11 @@ impl HelpScreen {
             chunks[0],
             "Help & Usage Guide",
             Some("Backup & Restore System Documentation"),
+            &state.theme,
         );
 
         // Content
-        let content_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(50), // Left column
-                Constraint::Percentage(50), // Right column
-            ])
-            .split(chunks[1]);
+        let content_chunks = split_panels(chunks[1], 50);
 
         // Left column - General help
         let left_chunks = Layout::default()
@@ -56,7 +54,7 @@ impl HelpScreen {
         // Navigation and Controls
         let navigation_lines = vec![
             Line::from(vec![
-                Span::styled("Navigation & Controls:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                Span::styled("Navigation & Controls:", state.theme.accent().add_modifier(Modifier::BOLD))
             ]),
             Line::from(""),
             Line::from(vec![
@@ -73,6 +71,7 @@ impl HelpScreen {
                 Span::styled("List Controls:", Style::default().add_modifier(Modifier::BOLD))
             ]),
             Line::from("• Space - Toggle item selection"),
+            Line::from("• U / Ctrl+R - Undo / redo a selection change"),
             Line::from("• A - Select all items"),
             Line::from("• N - Deselect all items"),
             Line::from("• Page Up/Down - Fast scroll"),
@@ -100,11 +99,11 @@ impl HelpScreen {
         // Backup Modes
         let modes_lines = vec![
             Line::from(vec![
-                Span::styled("Backup Modes:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                Span::styled("Backup Modes:", state.theme.accent().add_modifier(Modifier::BOLD))
             ]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("🔰 Secure Mode:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                Span::styled("🔰 Secure Mode:", state.theme.style(Tone::Good).add_modifier(Modifier::BOLD))
             ]),
             Line::from("• Excludes sensitive credentials"),
             Line::from("• Safe for cloud storage/sharing"),
@@ -113,7 +112,7 @@ impl HelpScreen {
             Line::from("• Excludes: SSH keys, GPG keys, tokens"),
             Line::from(""),
             Line::from(vec![
-                Span::styled("🔑 Complete Mode:", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                Span::styled("🔑 Complete Mode:", state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
             ]),
             Line::from("• Includes ALL files and credentials"),
             Line::from("• Requires strong password"),
@@ -145,7 +144,7 @@ impl HelpScreen {
         // Security & Best Practices
         let security_lines = vec![
             Line::from(vec![
-                Span::styled("Security & Best Practices:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                Span::styled("Security & Best Practices:", state.theme.accent().add_modifier(Modifier::BOLD))
             ]),
             Line::from(""),
             Line::from(vec![
@@ -189,7 +188,7 @@ impl HelpScreen {
         // Troubleshooting
         let troubleshooting_lines = vec![
             Line::from(vec![
-                Span::styled("Troubleshooting:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                Span::styled("Troubleshooting:", state.theme.accent().add_modifier(Modifier::BOLD))
             ]),
             Line::from(""),
             Line::from(vec![
@@ -230,11 +229,7 @@ impl HelpScreen {
         frame.render_widget(troubleshooting_paragraph, right_chunks[1]);
 
         // Footer
-        let shortcuts = [
-            ("Esc", "Back"),
-            ("Q", "Back"),
-        ];
-
-        render_footer(frame, chunks[2], &shortcuts, Some("Press Esc or Q to return"));
+        let hints = state.keymap.hints(KeyContext::Help, |_| true);
+        render_footer(frame, chunks[2], &hints, None, &state.theme);
     }
 }
\ No newline at end of file
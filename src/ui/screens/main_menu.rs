@@ -1,33 +1,53 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
 };
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
 use crate::ui::components::{render_header, render_footer};
+use crate::ui::terminal::{format_age, pad_text};
+use crate::ui::theme::Theme;
 use crate::ui::widgets::{Menu, MenuItem};
 
 pub struct MainMenuScreen {
     menu: Menu,
+    /// Keys go to the strategies panel rather than the menu
+    strategies_focused: bool,
 }
 
 impl MainMenuScreen {
     pub fn new() -> Self {
         let menu_items = vec![
-            MenuItem::new('1', "Backup".to_string(), "Create a backup of your files".to_string()),
-            MenuItem::new('2', "Restore".to_string(), "Restore files from a backup".to_string()),
-            MenuItem::new('q', "Quit".to_string(), "Exit the application".to_string()),
+            MenuItem::new(Action::Backup, "Backup".to_string(), "Create a backup of your files".to_string()),
+            MenuItem::new(Action::Restore, "Restore".to_string(), "Restore files from a backup".to_string()),
+            MenuItem::new(Action::Catalog, "Catalog".to_string(), "Search files across all archives".to_string()),
+            MenuItem::new(Action::FindFile, "Find file".to_string(), "Find every archived version of a file".to_string()),
+            MenuItem::new(Action::Settings, "Settings".to_string(), "Edit backup modes, applications and validation".to_string()),
+            MenuItem::new(Action::Machines, "Machines".to_string(), "Backup status of every machine in the catalog".to_string()),
+            MenuItem::new(Action::Exclusions, "Exclusions".to_string(), "Patterns kept out of backups, with what they match".to_string()),
+            MenuItem::new(Action::History, "History".to_string(), "Audit trail of backups and restores".to_string()),
+            MenuItem::new(Action::Quit, "Quit".to_string(), "Exit the application".to_string()),
         ];
 
         Self {
             menu: Menu::new(menu_items),
+            strategies_focused: false,
         }
     }
 
-    pub fn handle_key(&mut self, key: crossterm::event::KeyEvent) -> Option<char> {
-        self.menu.handle_key(key)
+    pub fn strategies_focused(&self) -> bool {
+        self.strategies_focused
+    }
+
+    pub fn focus_strategies(&mut self, focused: bool) {
+        self.strategies_focused = focused;
+    }
+
+    pub fn handle_action(&mut self, action: Action) -> Option<Action> {
+        self.menu.handle_action(action)
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
@@ -48,62 +68,125 @@ impl MainMenuScreen {
             chunks[0],
             "Backup & Restore System",
             Some("Select an option to continue"),
+            &state.theme,
         );
 
         // Main content
+        let strategies = &state.strategy_statuses;
         let content_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(15), // Menu
-                Constraint::Min(0),     // Welcome text
+                Constraint::Length(if strategies.is_empty() { 15 } else { 11 }), // Menu
+                Constraint::Min(0),     // Strategies, or welcome text
             ])
             .split(chunks[1]);
 
         // Menu
-        self.menu.render(frame, content_chunks[0], "Main Menu");
-
-        // Welcome text
-        let welcome_text = vec![
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("Welcome to the Backup & Restore System", 
-                    Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
-            ]),
-            Line::from(""),
-            Line::from("This tool helps you safely backup and restore your important files."),
-            Line::from("Choose from secure mode (excludes sensitive data) or complete mode"),
-            Line::from("(includes all files with encryption support)."),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("Security Features:", Style::default().add_modifier(Modifier::BOLD)),
-            ]),
-            Line::from("• Password-protected backups with strong encryption"),
-            Line::from("• Secure memory handling for passwords"),
-            Line::from("• File integrity verification"),
-            Line::from("• Selective restore with conflict detection"),
-        ];
-
-        let welcome_paragraph = Paragraph::new(welcome_text)
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: true })
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Information")
-                    .title_alignment(Alignment::Center),
-            );
+        self.menu.render(frame, content_chunks[0], "Main Menu", &state.keymap, KeyContext::MainMenu, &state.theme);
 
-        frame.render_widget(welcome_paragraph, content_chunks[1]);
+        if strategies.is_empty() {
+            render_welcome(frame, content_chunks[1], &state.theme);
+        } else {
+            self.render_strategies(frame, content_chunks[1], state);
+        }
 
         // Footer
-        let shortcuts = [
-            ("1", "Backup"),
-            ("2", "Restore"),
-            ("Ctrl+H", "Help"),
-            ("Q", "Quit"),
-        ];
+        let mut hints = if self.strategies_focused {
+            state.keymap.hints(KeyContext::Strategies, |_| true)
+        } else {
+            state.keymap.hints(KeyContext::MainMenu, |action| action != Action::NextField || !strategies.is_empty())
+        };
+        hints.extend(state.keymap.hint(KeyContext::Global, Action::Help));
 
         let status = state.status_message.as_deref();
-        render_footer(frame, chunks[2], &shortcuts, status);
+        render_footer(frame, chunks[2], &hints, status, &state.theme);
     }
-}
\ No newline at end of file
+
+    /// Each strategy with how long ago it last ran and how that went
+    fn render_strategies(&self, frame: &mut ratatui::Frame, area: Rect, state: &AppStateManager) {
+        let now = chrono::Utc::now();
+        let mut lines = vec![Line::from(Span::styled(
+            format!("  {:<20} {:<9} {:<10} {:<16} {}", "Strategy", "Mode", "Frequency", "Last run", "Status"),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        for (i, status) in state.strategy_statuses.iter().enumerate() {
+            let highlighted = self.strategies_focused && i == state.selected_item_index;
+            let style = if highlighted {
+                state.theme.selection()
+            } else {
+                Style::default()
+            };
+            let (label, tone) = status.summary();
+            let status_style = match tone {
+                Some(tone) if !highlighted => state.theme.style(tone),
+                None if !highlighted => state.theme.muted(),
+                _ => style,
+            };
+            let tag = tone.map(|tone| state.theme.tag(tone)).unwrap_or_default();
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{}{} {:<9} {:<10} {:<16} ",
+                        if highlighted { "▶ " } else { "  " },
+                        pad_text(&status.name, 20),
+                        status.strategy.mode,
+                        status.strategy.frequency,
+                        status.last_run.map(|at| format_age(now - at)).unwrap_or_else(|| "-".to_string()),
+                    ),
+                    style,
+                ),
+                Span::styled(format!("{}{}", tag, label), status_style),
+            ]));
+        }
+
+        // What the highlighted strategy is for
+        if let Some(status) = state.strategy_statuses.get(state.selected_item_index).filter(|_| self.strategies_focused) {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(status.strategy.description.clone(), state.theme.muted())));
+        }
+
+        let title = if self.strategies_focused { "Backup Strategies" } else { "Backup Strategies (Tab to run one)" };
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(paragraph, area);
+    }
+}
+
+/// Introduction shown while the config has no backup strategies
+fn render_welcome(frame: &mut ratatui::Frame, area: Rect, theme: &Theme) {
+    let welcome_text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Welcome to the Backup & Restore System", 
+                theme.accent().add_modifier(Modifier::BOLD))
+        ]),
+        Line::from(""),
+        Line::from("This tool helps you safely backup and restore your important files."),
+        Line::from("Choose from secure mode (excludes sensitive data) or complete mode"),
+        Line::from("(includes all files with encryption support)."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Security Features:", Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from("• Password-protected backups with strong encryption"),
+        Line::from("• Secure memory handling for passwords"),
+        Line::from("• File integrity verification"),
+        Line::from("• Selective restore with conflict detection"),
+    ];
+
+    let welcome_paragraph = Paragraph::new(welcome_text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Information")
+                .title_alignment(Alignment::Center),
+        );
+
+    frame.render_widget(welcome_paragraph, area);
+}
@@ -1,7 +1,14 @@
 pub mod main_menu;
+pub mod profile_selection;
 pub mod backup_mode_selection;
 pub mod backup_item_selection;
+pub mod destination_selection;
+pub mod preflight;
+pub mod secret_review;
+pub mod recipient_selection;
+pub mod volume_selection;
 pub mod backup_password;
+pub mod backup_confirm;
 pub mod backup_progress;
 pub mod backup_complete;
 pub mod restore_archive_selection;
@@ -9,13 +16,30 @@ pub mod restore_password;
 pub mod restore_item_selection;
 pub mod restore_progress;
 pub mod restore_complete;
+pub mod catalog_browser;
+pub mod find_file;
+pub mod file_history;
+pub mod config_editor;
+pub mod config_history;
+pub mod exclusion_editor;
+pub mod fleet_overview;
+pub mod audit_history;
+pub mod first_run_wizard;
+pub mod first_backup_guide;
 pub mod help;
 pub mod error;
 
 pub use main_menu::MainMenuScreen;
+pub use profile_selection::ProfileSelectionScreen;
 pub use backup_mode_selection::BackupModeSelectionScreen;
 pub use backup_item_selection::BackupItemSelectionScreen;
+pub use destination_selection::DestinationSelectionScreen;
+pub use preflight::PreflightScreen;
+pub use secret_review::SecretReviewScreen;
+pub use recipient_selection::RecipientSelectionScreen;
+pub use volume_selection::VolumeSelectionScreen;
 pub use backup_password::BackupPasswordScreen;
+pub use backup_confirm::BackupConfirmScreen;
 pub use backup_progress::BackupProgressScreen;
 pub use backup_complete::BackupCompleteScreen;
 pub use restore_archive_selection::RestoreArchiveSelectionScreen;
@@ -23,5 +47,15 @@ pub use restore_password::RestorePasswordScreen;
 pub use restore_item_selection::RestoreItemSelectionScreen;
 pub use restore_progress::RestoreProgressScreen;
 pub use restore_complete::RestoreCompleteScreen;
+pub use catalog_browser::CatalogBrowserScreen;
+pub use find_file::FindFileScreen;
+pub use file_history::FileHistoryScreen;
+pub use config_editor::ConfigEditorScreen;
+pub use config_history::ConfigHistoryScreen;
+pub use exclusion_editor::ExclusionEditorScreen;
+pub use fleet_overview::FleetOverviewScreen;
+pub use audit_history::AuditHistoryScreen;
+pub use first_run_wizard::FirstRunWizardScreen;
+pub use first_backup_guide::FirstBackupGuideScreen;
 pub use help::HelpScreen;
 pub use error::ErrorScreen;
\ No newline at end of file
@@ -0,0 +1,102 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::backend::preflight;
+use crate::core::keymap::{Action, KeyContext};
+use crate::core::state::AppStateManager;
+use crate::core::types::CheckStatus;
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
+use crate::ui::theme::Tone;
+
+pub struct PreflightScreen;
+
+impl PreflightScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        let checks = &state.preflight_checks;
+        let failed = preflight::has_failures(checks);
+        let count = |status: CheckStatus| checks.iter().filter(|check| check.status == status).count();
+
+        // Header
+        let subtitle = format!(
+            "{} checks | {} warnings | {} failed",
+            checks.len(),
+            count(CheckStatus::Warning),
+            count(CheckStatus::Fail),
+        );
+        render_header(frame, chunks[0], "Before the Backup Starts", Some(&subtitle), &state.theme);
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
+
+        let content_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),    // Checks
+                Constraint::Length(4), // Verdict
+            ])
+            .split(chunks[1]);
+
+        // Checks
+        let theme = &state.theme;
+        let lines: Vec<Line> = checks
+            .iter()
+            .map(|check| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} {}", check.status.icon(), theme.tag(check.status.tone())),
+                        theme.style(check.status.tone()),
+                    ),
+                    Span::styled(format!("[{}] ", check.category), state.theme.muted()),
+                    Span::styled(check.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!(" - {}", check.detail)),
+                ])
+            })
+            .collect();
+
+        let list = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Pre-flight Checks")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
+        frame.render_widget(list, content_chunks[0]);
+
+        // Verdict
+        let (tone, verdict) = if failed {
+            (Tone::Danger, "The backup can't be written as things stand. Free up space, fix the destination's permissions, or choose another destination.")
+        } else {
+            (Tone::Caution, "The backup may stop partway or leave files out. Continue only if that is acceptable.")
+        };
+        let verdict = Paragraph::new(Line::from(Span::styled(
+            format!("{}{}", theme.tag(tone), verdict),
+            theme.style(tone),
+        )))
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(verdict, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::Preflight, |action| action != Action::Confirm || !failed);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
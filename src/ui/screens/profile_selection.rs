@@ -0,0 +1,108 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_header, render_footer};
+use crate::ui::layout::split_panels;
+
+pub struct ProfileSelectionScreen;
+
+impl ProfileSelectionScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Select Backup Profile",
+            Some("Use a saved setup, or choose the mode and items by hand"),
+            &state.theme,
+        );
+
+        let content_chunks = split_panels(chunks[1], 40);
+
+        // Profiles, then the manual choice last
+        let items: Vec<ListItem> = state.profile_choices
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(std::iter::once("No profile"))
+            .enumerate()
+            .map(|(i, name)| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    Style::default()
+                };
+                ListItem::new(name.to_string()).style(style)
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Profiles")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[0]);
+
+        // Details
+        let label = |text: &'static str| Span::styled(text, state.theme.accent());
+        let lines = match state.profile_choices.get(state.selected_item_index) {
+            Some((_, profile)) => {
+                let mut lines = vec![
+                    Line::from(profile.description.clone()),
+                    Line::from(""),
+                    Line::from(vec![
+                        label("Mode: "),
+                        Span::raw(profile.mode.clone().unwrap_or_else(|| "chosen next".to_string())),
+                    ]),
+                    Line::from(vec![
+                        label("Destination: "),
+                        Span::raw(profile.destination.clone().unwrap_or_else(|| "default".to_string())),
+                    ]),
+                    Line::from(""),
+                ];
+                if profile.items.is_empty() {
+                    lines.push(Line::from(vec![label("Items: "), Span::raw("the mode's usual items")]));
+                } else {
+                    lines.push(Line::from(label("Items:")));
+                    lines.extend(profile.items.iter().map(|item| Line::from(format!("  ~/{}", item))));
+                }
+                lines
+            }
+            None => vec![Line::from("Pick the backup mode and items yourself.")],
+        };
+
+        let details = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Details")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::ProfileSelection, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
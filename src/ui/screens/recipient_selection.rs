@@ -0,0 +1,128 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::backend::gpg_keys::SecretKey;
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::theme::Tone;
+
+pub struct RecipientSelectionScreen;
+
+impl RecipientSelectionScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        render_header(
+            frame,
+            chunks[0],
+            "Encrypt to GPG Keys",
+            Some("Only the private keys of the keys ticked here can open the archive"),
+            &state.theme,
+        );
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
+
+        let content_chunks = split_panels(chunks[1], 55);
+
+        // Keys
+        let items: Vec<ListItem> = state.recipient_choices
+            .iter()
+            .enumerate()
+            .map(|(i, (key, selected))| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    Style::default()
+                };
+                let checkbox = if *selected { "[x]" } else { "[ ]" };
+                ListItem::new(format!("{} {}  {}", checkbox, key.short_id(), key.name())).style(style)
+            })
+            .collect();
+
+        let ticked = state.recipient_choices.iter().filter(|(_, selected)| *selected).count();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Keys ({} selected)", ticked))
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[0]);
+
+        // Details
+        let label = |text: &'static str| Span::styled(text, Style::default().add_modifier(Modifier::BOLD));
+        let mut lines = match state.recipient_choices.get(state.selected_item_index) {
+            Some((key, _)) => {
+                let mut lines = vec![Line::from(vec![label("Fingerprint: "), Span::raw(&key.fingerprint)])];
+                lines.extend(key.user_ids.iter().map(|uid| Line::from(vec![label("User ID: "), Span::raw(uid)])));
+                lines.push(Line::from(vec![
+                    label("Expires: "),
+                    Span::raw(key.expires.map(|at| at.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "never".to_string())),
+                ]));
+                lines.push(Line::from(""));
+                lines.push(match &key.secret {
+                    SecretKey::Card(serial) => Line::from(Span::styled(
+                        format!("{}Private key on the smartcard {}", state.theme.tag(Tone::Good), serial),
+                        state.theme.style(Tone::Good),
+                    )),
+                    SecretKey::Local => Line::from("Private key in this keyring"),
+                    SecretKey::Missing => Line::from(Span::styled(
+                        format!("{}No private key here: only the key's owner can restore", state.theme.tag(Tone::Caution)),
+                        state.theme.style(Tone::Caution),
+                    )),
+                });
+                lines
+            }
+            None => Vec::new(),
+        };
+
+        // An archive no private key at hand can open is easy to make by
+        // mistake, and impossible to restore from here
+        let unreadable = state.recipient_choices
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .all(|(key, _)| key.secret == SecretKey::Missing);
+        if ticked > 0 && unreadable {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{}None of the selected keys has its private key on this machine or a card it sees. \
+                     Make sure you can decrypt with one of them elsewhere.",
+                    state.theme.tag(Tone::Danger),
+                ),
+                state.theme.style(Tone::Danger),
+            )));
+        }
+
+        let details = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Details")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::RecipientSelection, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
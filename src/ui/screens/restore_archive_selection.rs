@@ -1,19 +1,29 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
 };
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
-use crate::ui::components::{render_header, render_footer};
+use crate::ui::components::{render_header, render_footer, render_scrollbar, visible_offset};
+use crate::ui::layout::split_panels;
 use crate::ui::terminal::format_bytes;
+use crate::ui::theme::Tone;
 
-pub struct RestoreArchiveSelectionScreen;
+pub struct RestoreArchiveSelectionScreen {
+    /// List rows that fit on screen, as of the last render
+    visible_rows: usize,
+}
 
 impl RestoreArchiveSelectionScreen {
     pub fn new() -> Self {
-        Self
+        Self { visible_rows: 10 }
+    }
+
+    pub fn visible_rows(&self) -> usize {
+        self.visible_rows.max(1)
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
@@ -34,6 +44,7 @@ impl RestoreArchiveSelectionScreen {
             chunks[0],
             "Select Archive to Restore",
             Some("Choose a backup archive to restore from"),
+            &state.theme,
         );
 
         if state.available_archives.is_empty() {
@@ -42,11 +53,11 @@ impl RestoreArchiveSelectionScreen {
                 Line::from(""),
                 Line::from(vec![
                     Span::styled("No backup archives found", 
-                        Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow))
+                        state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD))
                 ]),
                 Line::from(""),
                 Line::from("Make sure backup files are in the correct location."),
-                Line::from("Supported formats: .tar.gz, .tar.xz (encrypted and unencrypted)"),
+                Line::from("Supported formats: .tar.gz, .tar.xz, .tar.bz2, .tar.zst (encrypted and unencrypted)"),
                 Line::from(""),
                 Line::from("Create a backup first using the backup option from the main menu."),
             ];
@@ -64,18 +75,16 @@ impl RestoreArchiveSelectionScreen {
             frame.render_widget(no_archives_paragraph, chunks[1]);
         } else {
             // Main content
-            let content_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Percentage(60), // Archive list
-                    Constraint::Percentage(40), // Archive details
-                ])
-                .split(chunks[1]);
+            let content_chunks = split_panels(chunks[1], 60);
 
             // Archive list
+            self.visible_rows = content_chunks[0].height.saturating_sub(2) as usize;
+            let offset = visible_offset(state.selected_item_index, state.scroll_offset, self.visible_rows);
             let archive_items: Vec<ListItem> = state.available_archives
                 .iter()
                 .enumerate()
+                .skip(offset)
+                .take(self.visible_rows)
                 .map(|(i, archive)| {
                     let is_selected = i == state.selected_item_index;
                     
@@ -85,16 +94,26 @@ impl RestoreArchiveSelectionScreen {
                         crate::core::types::BackupMode::Complete => "🔑",
                     };
                     
-                    let item_text = format!(
-                        "{} {} {} ({})",
-                        encryption_icon,
-                        mode_icon,
-                        archive.name,
-                        format_bytes(archive.size)
-                    );
+                    let item_text = match &archive.hostname {
+                        Some(hostname) => format!(
+                            "{} {} {} ({}) [{}]",
+                            encryption_icon,
+                            mode_icon,
+                            archive.name,
+                            format_bytes(archive.size),
+                            hostname
+                        ),
+                        None => format!(
+                            "{} {} {} ({})",
+                            encryption_icon,
+                            mode_icon,
+                            archive.name,
+                            format_bytes(archive.size)
+                        ),
+                    };
                     
                     let style = if is_selected {
-                        Style::default().bg(Color::Blue).fg(Color::White)
+                        state.theme.selection()
                     } else {
                         Style::default()
                     };
@@ -107,12 +126,13 @@ impl RestoreArchiveSelectionScreen {
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title("Available Archives")
+                        .title(format!("Available Archives ({})", state.archive_sort.label()))
                         .title_alignment(Alignment::Center),
                 )
                 .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
             frame.render_widget(archive_list, content_chunks[0]);
+            render_scrollbar(frame, content_chunks[0], state.available_archives.len(), offset, state.selected_item_index, &state.theme);
 
             // Archive details
             if let Some(archive) = state.available_archives.get(state.selected_item_index) {
@@ -147,13 +167,28 @@ impl RestoreArchiveSelectionScreen {
                         Span::styled("Encrypted: ", Style::default().add_modifier(Modifier::BOLD)),
                         Span::styled(
                             if archive.encrypted { "Yes" } else { "No" },
-                            Style::default().fg(if archive.encrypted { Color::Green } else { Color::Gray }),
+                            if archive.encrypted { state.theme.style(Tone::Good) } else { state.theme.muted() },
                         ),
                     ]),
                     Line::from(vec![
                         Span::styled("Items: ", Style::default().add_modifier(Modifier::BOLD)),
                         Span::raw(archive.items.len().to_string()),
                     ]),
+                    Line::from(vec![
+                        Span::styled("Host: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(archive.hostname.as_deref().unwrap_or("unknown")),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("Location: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(archive.remote.clone().unwrap_or_else(|| archive.path.display().to_string())),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("Parity: ", Style::default().add_modifier(Modifier::BOLD)),
+                        match state.archive_parity.get(&archive.path) {
+                            Some(parity) => Span::raw(parity.as_str()),
+                            None => Span::styled("none", state.theme.muted()),
+                        },
+                    ]),
                 ];
 
                 if !archive.description.is_empty() {
@@ -169,14 +204,14 @@ impl RestoreArchiveSelectionScreen {
                 match archive.mode {
                     crate::core::types::BackupMode::Secure => {
                         details_lines.push(Line::from(vec![
-                            Span::styled("🔰 Secure Mode:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                            Span::styled("🔰 Secure Mode:", state.theme.style(Tone::Good).add_modifier(Modifier::BOLD))
                         ]));
                         details_lines.push(Line::from("Excludes sensitive credentials"));
                         details_lines.push(Line::from("Safe to restore on shared systems"));
                     }
                     crate::core::types::BackupMode::Complete => {
                         details_lines.push(Line::from(vec![
-                            Span::styled("🔑 Complete Mode:", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                            Span::styled("🔑 Complete Mode:", state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
                         ]));
                         details_lines.push(Line::from("Contains sensitive credentials"));
                         details_lines.push(Line::from("Use caution when restoring"));
@@ -186,7 +221,7 @@ impl RestoreArchiveSelectionScreen {
                 if archive.encrypted {
                     details_lines.push(Line::from(""));
                     details_lines.push(Line::from(vec![
-                        Span::styled("🔒 Encrypted:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                        Span::styled("🔒 Encrypted:", state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD))
                     ]));
                     details_lines.push(Line::from("Password required to access"));
                 }
@@ -205,18 +240,11 @@ impl RestoreArchiveSelectionScreen {
         }
 
         // Footer
-        let mut shortcuts = vec![
-            ("↑↓", "Navigate"),
-        ];
-
-        if !state.available_archives.is_empty() {
-            shortcuts.push(("Enter", "Select"));
-        }
-
-        shortcuts.extend_from_slice(&[
-            ("Esc", "Back"),
-            ("Ctrl+H", "Help"),
-        ]);
+        let has_archives = !state.available_archives.is_empty();
+        let mut hints = state.keymap.hints(KeyContext::RestoreArchiveSelection, |action| {
+            action != Action::Confirm || has_archives
+        });
+        hints.extend(state.keymap.hint(KeyContext::Global, Action::Help));
 
         let status = if state.available_archives.is_empty() {
             Some("No archives available for restore")
@@ -224,6 +252,6 @@ impl RestoreArchiveSelectionScreen {
             state.status_message.as_deref()
         };
 
-        render_footer(frame, chunks[2], &shortcuts, status);
+        render_footer(frame, chunks[2], &hints, status, &state.theme);
     }
 }
\ No newline at end of file
@@ -1,14 +1,18 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
+use crate::backend::database_dumps::DatabaseConfig;
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
 use crate::core::types::ProgressStatus;
-use crate::ui::components::{render_header, render_footer};
-use crate::ui::terminal::format_bytes;
+use crate::ui::components::{hint_spans, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{centered_rect, format_bytes};
+use crate::ui::theme::Tone;
 
 pub struct RestoreCompleteScreen;
 
@@ -45,6 +49,7 @@ impl RestoreCompleteScreen {
             chunks[0],
             header_title,
             Some("Your restore operation has finished"),
+            &state.theme,
         );
 
         // Content
@@ -64,7 +69,7 @@ impl RestoreCompleteScreen {
                 ProgressStatus::Completed => {
                     summary_lines.push(Line::from(vec![
                         Span::styled("✅ Restore completed successfully!", 
-                            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                            state.theme.style(Tone::Good).add_modifier(Modifier::BOLD))
                     ]));
                     summary_lines.push(Line::from(""));
                     
@@ -95,11 +100,11 @@ impl RestoreCompleteScreen {
                 ProgressStatus::Failed(error) => {
                     summary_lines.push(Line::from(vec![
                         Span::styled("❌ Restore failed!", 
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                            state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
                     ]));
                     summary_lines.push(Line::from(""));
                     summary_lines.push(Line::from(vec![
-                        Span::styled("Error: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Red)),
+                        Span::styled("Error: ", state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD)),
                         Span::raw(error),
                     ]));
                     summary_lines.push(Line::from(""));
@@ -138,10 +143,36 @@ impl RestoreCompleteScreen {
             .map(|p| matches!(p.status, ProgressStatus::Completed))
             .unwrap_or(false);
 
-        let actions_lines = if is_success {
+        let theme = &state.theme;
+        let actions_lines = if !state.service_results.is_empty() {
+            state.service_results
+                .iter()
+                .map(|result| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{} {}", result.status.icon(), theme.tag(result.status.tone())),
+                            theme.style(result.status.tone()),
+                        ),
+                        Span::styled(format!("[{}] ", result.category), state.theme.muted()),
+                        Span::styled(result.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(format!(" - {}", result.detail)),
+                    ])
+                })
+                .collect()
+        } else if state.restore_rolled_back {
+            vec![
+                Line::from(vec![
+                    Span::styled("↩ Restore rolled back", state.theme.accent().add_modifier(Modifier::BOLD))
+                ]),
+                Line::from(""),
+                Line::from("• Overwritten files were put back from the pre-restore snapshot"),
+                Line::from("• Files the restore created were removed"),
+                Line::from("• Restart applications that picked up the restored configuration"),
+            ]
+        } else if is_success {
             let mut lines = vec![
                 Line::from(vec![
-                    Span::styled("Next Steps:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))
+                    Span::styled("Next Steps:", state.theme.accent().add_modifier(Modifier::BOLD))
                 ]),
                 Line::from(""),
                 Line::from("• Your files have been restored successfully"),
@@ -156,16 +187,29 @@ impl RestoreCompleteScreen {
                 match archive.mode {
                     crate::core::types::BackupMode::Complete => {
                         lines.push(Line::from(vec![
-                            Span::styled("🔑 Complete Mode Restore:", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                            Span::styled("🔑 Complete Mode Restore:", state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
                         ]));
                         lines.push(Line::from("• SSH keys and credentials have been restored"));
                         lines.push(Line::from("• Verify SSH agent and GPG agent are working"));
-                        lines.push(Line::from("• Check file permissions on sensitive files"));
+                        if state.permission_fixes.is_empty() {
+                            lines.push(Line::from("• Permissions on keys and credentials were already safe"));
+                        } else {
+                            lines.push(Line::from(format!(
+                                "• Tightened permissions on {} sensitive paths:",
+                                state.permission_fixes.len()
+                            )));
+                            for fix in &state.permission_fixes {
+                                lines.push(Line::from(Span::styled(
+                                    format!("    {}{} {}", theme.tag(fix.status.tone()), fix.name, fix.detail),
+                                    theme.style(fix.status.tone()),
+                                )));
+                            }
+                        }
                         lines.push(Line::from("• Test authentication to services and repositories"));
                     }
                     crate::core::types::BackupMode::Secure => {
                         lines.push(Line::from(vec![
-                            Span::styled("🔰 Secure Mode Restore:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                            Span::styled("🔰 Secure Mode Restore:", state.theme.style(Tone::Good).add_modifier(Modifier::BOLD))
                         ]));
                         lines.push(Line::from("• Configuration files have been restored"));
                         lines.push(Line::from("• You may need to re-setup credentials manually"));
@@ -178,7 +222,7 @@ impl RestoreCompleteScreen {
         } else {
             vec![
                 Line::from(vec![
-                    Span::styled("What to do next:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow))
+                    Span::styled("What to do next:", state.theme.style(Tone::Caution).add_modifier(Modifier::BOLD))
                 ]),
                 Line::from(""),
                 Line::from("• Review the error message above"),
@@ -196,21 +240,169 @@ impl RestoreCompleteScreen {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(if is_success { "Success" } else { "Troubleshooting" })
+                    .title(if !state.service_results.is_empty() {
+                        "System State"
+                    } else if is_success {
+                        "Success"
+                    } else {
+                        "Troubleshooting"
+                    })
                     .title_alignment(Alignment::Center)
-                    .style(Style::default().fg(if is_success { Color::Green } else { Color::Yellow })),
+                    .style(state.theme.style(if is_success { Tone::Good } else { Tone::Caution })),
             )
             .alignment(Alignment::Left)
             .wrap(Wrap { trim: true });
 
-        frame.render_widget(actions_paragraph, content_chunks[1]);
+        let actions_area = if state.restored_credentials.is_empty() {
+            content_chunks[1]
+        } else {
+            let split = split_panels(content_chunks[1], 50);
+            self.render_credential_checks(frame, split[1], state);
+            split[0]
+        };
+
+        frame.render_widget(actions_paragraph, actions_area);
 
         // Footer
-        let shortcuts = [
-            ("Enter", "Return to Main Menu"),
-            ("Q", "Quit Application"),
+        let hints = state.keymap.hints(KeyContext::RestoreComplete, |action| match action {
+            Action::RollBack => state.pre_restore_snapshot.is_some(),
+            Action::ReenableServices | Action::ImportDesktop | Action::ReinstallPackages | Action::RestoreVolumes | Action::ReplayDatabases => {
+                is_success && !state.restore_rolled_back
+            }
+            Action::ValidateCredentials => !state.restored_credentials.is_empty() && !state.restore_rolled_back,
+            Action::SaveReport | Action::SaveHtmlReport => state.completion_report.is_some(),
+            _ => true,
+        });
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+
+        if state.confirm_restore_rollback {
+            self.render_rollback_confirm(frame, state);
+        } else if let Some(databases) = &state.database_replay {
+            self.render_replay_confirm(frame, state, databases);
+        }
+    }
+
+    fn render_replay_confirm(&self, frame: &mut ratatui::Frame, state: &AppStateManager, databases: &[DatabaseConfig]) {
+        let area = centered_rect(60, 40, frame.area());
+        frame.render_widget(Clear, area);
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    format!("Replay {} database dump(s)?", databases.len()),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            Line::from(""),
         ];
+        lines.extend(databases.iter().map(|database| {
+            Line::from(format!("  {} ({}) into {}", database.name, database.engine.as_str(), database.target()))
+        }));
+        lines.push(Line::from(""));
+        lines.push(Line::from("Tables in the dumps replace the ones there now; a SQLite file is kept next to its replacement."));
+        lines.push(Line::from(""));
+        lines.push(Line::from(hint_spans(&state.keymap.hints(KeyContext::DatabaseReplay, |_| true), &state.theme)));
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Confirm Replay")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.style(Tone::Caution)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+
+    fn render_rollback_confirm(&self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let Some(snapshot) = &state.pre_restore_snapshot else {
+            return;
+        };
+
+        let area = centered_rect(60, 30, frame.area());
+        frame.render_widget(Clear, area);
+
+        let created = snapshot.entries.iter().filter(|entry| !entry.existed).count();
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    format!("Undo the restore from {}?", snapshot.archive),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            Line::from(""),
+            Line::from(format!(
+                "{} overwritten paths are put back from the snapshot taken at {}.",
+                snapshot.entries.len() - created,
+                snapshot.taken.format("%H:%M:%S"),
+            )),
+            Line::from(format!("{} paths created by the restore are deleted.", created)),
+            Line::from(""),
+            Line::from(hint_spans(&state.keymap.hints(KeyContext::RestoreRollback, |_| true), &state.theme)),
+        ];
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Confirm Rollback")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.style(Tone::Caution)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+
+    fn render_credential_checks(&self, frame: &mut ratatui::Frame, area: ratatui::layout::Rect, state: &AppStateManager) {
+        let theme = &state.theme;
+        let lines: Vec<Line> = if state.credential_checks.is_empty() {
+            vec![
+                Line::from(""),
+                Line::from("Restored credentials can be checked automatically:"),
+                Line::from("• Fix key and directory permissions"),
+                Line::from("• Confirm private keys load and list fingerprints"),
+                Line::from("• Check known_hosts and the running agent"),
+                Line::from("• List GPG secret keys and verify the trustdb"),
+                Line::from(""),
+                Line::from(vec![
+                    Span::raw("Press "),
+                    Span::styled(
+                        state.keymap.key_label(KeyContext::RestoreComplete, Action::ValidateCredentials).unwrap_or_default(),
+                        state.theme.style(Tone::Caution),
+                    ),
+                    Span::raw(" to run the checks"),
+                ]),
+            ]
+        } else {
+            state.credential_checks
+                .iter()
+                .map(|check| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{} {}", check.status.icon(), theme.tag(check.status.tone())),
+                            theme.style(check.status.tone()),
+                        ),
+                        Span::styled(format!("[{}] ", check.category), state.theme.muted()),
+                        Span::styled(check.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(format!(" - {}", check.detail)),
+                    ])
+                })
+                .collect()
+        };
+
+        let checks = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Credential Checks")
+                    .title_alignment(Alignment::Center),
+            )
+            .wrap(Wrap { trim: true });
 
-        render_footer(frame, chunks[2], &shortcuts, None);
+        frame.render_widget(checks, area);
     }
 }
\ No newline at end of file
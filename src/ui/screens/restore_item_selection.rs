@@ -1,19 +1,32 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
+use crate::backend::live_changes::{Change, LiveChanges};
+use crate::backend::restore_plan::{PlannedChange, RestorePlan};
+use crate::core::diff::DiffLine;
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
-use crate::ui::components::{render_header, render_footer, render_restore_item_list, render_summary_panel};
-use crate::ui::terminal::format_bytes;
+use crate::ui::components::{hint_spans, render_header, render_footer, render_restore_item_list, render_summary_panel};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::{centered_rect, format_bytes};
+use crate::ui::theme::Tone;
 
-pub struct RestoreItemSelectionScreen;
+pub struct RestoreItemSelectionScreen {
+    /// List rows that fit on screen, as of the last render
+    visible_rows: usize,
+}
 
 impl RestoreItemSelectionScreen {
     pub fn new() -> Self {
-        Self
+        Self { visible_rows: 10 }
+    }
+
+    pub fn visible_rows(&self) -> usize {
+        self.visible_rows.max(1)
     }
 
     pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
@@ -38,25 +51,24 @@ impl RestoreItemSelectionScreen {
             frame,
             chunks[0],
             "Select Items to Restore",
-            Some(&format!("From archive: {} | Use Space to toggle, A/N to select/deselect all", archive_name)),
+            Some(&format!("From archive: {} | Use Space to toggle, A/N to select/deselect all, / to filter", archive_name)),
+            &state.theme,
         );
 
         // Main content
-        let content_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(70), // Item list
-                Constraint::Percentage(30), // Summary and details
-            ])
-            .split(chunks[1]);
+        let content_chunks = split_panels(chunks[1], 70);
 
         // Item list
+        self.visible_rows = content_chunks[0].height.saturating_sub(2) as usize;
         render_restore_item_list(
             frame,
             content_chunks[0],
             &state.restore_items,
+            &state.visible_restore_items(),
             state.selected_item_index,
             state.scroll_offset,
+            &state.item_filter,
+            &state.theme,
         );
 
         // Right panel
@@ -76,11 +88,14 @@ impl RestoreItemSelectionScreen {
             ("Total Size", format_bytes(total_size)),
             ("Conflicts", conflicts.to_string()),
             ("Available Items", state.restore_items.len().to_string()),
+            ("Owners/ACLs", state.restore_metadata.as_str().to_string()),
+            ("Dry Run", if state.restore_dry_run { "on" } else { "off" }.to_string()),
         ];
 
         render_summary_panel(frame, right_chunks[0], "Restore Summary", &summary_stats);
 
         // Legend
+        let theme = &state.theme;
         let legend_lines = vec![
             Line::from(vec![
                 Span::styled("Legend:", Style::default().add_modifier(Modifier::BOLD))
@@ -88,15 +103,15 @@ impl RestoreItemSelectionScreen {
             Line::from(""),
             Line::from(vec![
                 Span::raw("☑ "),
-                Span::styled("Selected for restore", Style::default().fg(Color::Green)),
+                Span::styled("Selected for restore", theme.style(Tone::Good)),
             ]),
             Line::from(vec![
                 Span::raw("☐ "),
-                Span::styled("Not selected", Style::default().fg(Color::Gray)),
+                Span::styled("Not selected", state.theme.muted()),
             ]),
             Line::from(vec![
-                Span::raw("⚠️ "),
-                Span::styled("File conflict detected", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("⚠️ {}", theme.tag(Tone::Caution))),
+                Span::styled("File conflict detected", theme.style(Tone::Caution)),
             ]),
         ];
 
@@ -112,7 +127,7 @@ impl RestoreItemSelectionScreen {
         frame.render_widget(legend_paragraph, right_chunks[1]);
 
         // Item details
-        if let Some(item) = state.restore_items.get(state.selected_item_index) {
+        if let Some(item) = state.highlighted_restore_item() {
             let mut details_lines = vec![
                 Line::from(vec![
                     Span::styled("Selected Item:", Style::default().add_modifier(Modifier::BOLD))
@@ -139,7 +154,10 @@ impl RestoreItemSelectionScreen {
             if item.conflicts {
                 details_lines.push(Line::from(""));
                 details_lines.push(Line::from(vec![
-                    Span::styled("⚠️ Conflict Detected:", Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow))
+                    Span::styled(
+                        format!("⚠️ {}Conflict Detected:", theme.tag(Tone::Caution)),
+                        theme.style(Tone::Caution).add_modifier(Modifier::BOLD),
+                    )
                 ]));
                 details_lines.push(Line::from("A file already exists at the restore location."));
                 details_lines.push(Line::from("Restoring will overwrite the existing file."));
@@ -153,7 +171,7 @@ impl RestoreItemSelectionScreen {
             } else {
                 details_lines.push(Line::from(""));
                 details_lines.push(Line::from(vec![
-                    Span::styled("✓ No Conflicts:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                    Span::styled("✓ No Conflicts:", theme.style(Tone::Good).add_modifier(Modifier::BOLD))
                 ]));
                 details_lines.push(Line::from("Safe to restore without overwriting files."));
             }
@@ -171,20 +189,15 @@ impl RestoreItemSelectionScreen {
         }
 
         // Footer
-        let mut shortcuts = vec![
-            ("↑↓", "Navigate"),
-            ("Space", "Toggle"),
-            ("A", "Select All"),
-            ("N", "Select None"),
-        ];
-
-        if state.is_restore_ready() {
-            shortcuts.push(("Enter", "Start Restore"));
-        } else {
-            shortcuts.push(("Enter", "Start Restore (disabled)"));
-        }
-
-        shortcuts.push(("Esc", "Back"));
+        let highlighted_conflicts = state.highlighted_restore_item()
+            .map(|item| item.conflicts)
+            .unwrap_or(false);
+        let ready = state.is_restore_ready();
+        let hints = state.keymap.hints(KeyContext::RestoreItemSelection, |action| match action {
+            Action::Diff => highlighted_conflicts,
+            Action::PushToHost | Action::Confirm => ready,
+            _ => true,
+        });
 
         let conflict_message = if conflicts > 0 {
             Some(format!("{} file conflicts detected - review before proceeding", conflicts))
@@ -200,6 +213,357 @@ impl RestoreItemSelectionScreen {
             state.status_message.as_deref()
         };
 
-        render_footer(frame, chunks[2], &shortcuts, status);
+        if state.item_filter_input {
+            let hints = state.keymap.hints(KeyContext::ItemFilter, |_| true);
+            render_footer(frame, chunks[2], &hints, Some(&format!("Filter: {}▏", state.item_filter)), &state.theme);
+        } else {
+            render_footer(frame, chunks[2], &hints, status, &state.theme);
+        }
+
+        if state.confirm_privileged_restore {
+            self.render_privileged_confirm(frame, state);
+        }
+
+        if let Some(diff) = &state.restore_diff {
+            self.render_diff(frame, chunks[1], state, diff);
+        }
+
+        if let Some(changes) = &state.live_changes {
+            self.render_live_changes(frame, chunks[1], state, changes);
+        }
+
+        if let Some(plan) = &state.restore_plan {
+            self.render_restore_plan(frame, chunks[1], state, plan);
+        }
+
+        if let Some(host) = &state.remote_restore_host {
+            self.render_remote_restore(frame, state, host);
+        }
+
+        if let Some(dest) = &state.extract_item_dest {
+            self.render_extract_item(frame, state, dest);
+        }
+    }
+
+    fn render_extract_item(&self, frame: &mut ratatui::Frame, state: &AppStateManager, dest: &str) {
+        let area = centered_rect(60, 40, frame.area());
+        frame.render_widget(Clear, area);
+
+        let name = state.restore_items
+            .get(state.selected_item_index)
+            .map(|item| item.name.as_str())
+            .unwrap_or_default();
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("Extract {} from the archive", name),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+
+        match &state.extract_item_result {
+            Some(Ok(message)) => {
+                lines.push(Line::from(Span::styled(format!("✓ {}", message), state.theme.style(Tone::Good))));
+                lines.push(Line::from(""));
+                lines.push(Line::from("Press any key to continue"));
+            }
+            Some(Err(error)) => {
+                lines.push(Line::from(Span::styled(format!("✗ {}", error), state.theme.style(Tone::Danger))));
+                lines.push(Line::from(""));
+                lines.push(Line::from("Press any key to continue"));
+            }
+            None => {
+                lines.push(Line::from(vec![
+                    Span::styled("To: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(dest),
+                    Span::styled("▏", state.theme.highlight()),
+                ]));
+                lines.push(Line::from(Span::styled(
+                    "A path that doesn't exist yet; relative paths are under your home",
+                    state.theme.muted(),
+                )));
+                lines.push(Line::from(""));
+                lines.push(Line::from("Only this item is written, with setuid/setgid bits cleared."));
+                lines.push(Line::from(""));
+                lines.push(Line::from(hint_spans(&state.keymap.hints(KeyContext::ExtractItem, |_| true), &state.theme)));
+            }
+        }
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Extract Item")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.accent()),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+
+    fn render_remote_restore(&self, frame: &mut ratatui::Frame, state: &AppStateManager, host: &str) {
+        let area = centered_rect(60, 40, frame.area());
+        frame.render_widget(Clear, area);
+
+        let selected = state.get_selected_restore_items().len();
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("Restore {} selected items onto another machine over SSH", selected),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+
+        match &state.remote_restore_result {
+            Some(Ok(message)) => {
+                lines.push(Line::from(Span::styled(format!("✓ {}", message), state.theme.style(Tone::Good))));
+                lines.push(Line::from(""));
+                lines.push(Line::from("Press any key to continue"));
+            }
+            Some(Err(error)) => {
+                lines.push(Line::from(Span::styled(format!("✗ {}", error), state.theme.style(Tone::Danger))));
+                lines.push(Line::from(""));
+                lines.push(Line::from("Press any key to continue"));
+            }
+            None => {
+                lines.push(Line::from(vec![
+                    Span::styled("Host: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(host),
+                    Span::styled("▏", state.theme.highlight()),
+                ]));
+                lines.push(Line::from(Span::styled(
+                    "user@host, an ~/.ssh/config alias or ssh://user@host:port",
+                    state.theme.muted(),
+                )));
+                lines.push(Line::from(""));
+                lines.push(Line::from("Files land in the remote user's home, overwriting what is there."));
+                lines.push(Line::from("Needs key-based SSH login and the host key already in known_hosts."));
+                lines.push(Line::from(""));
+                lines.push(Line::from(hint_spans(&state.keymap.hints(KeyContext::RemoteRestore, |_| true), &state.theme)));
+            }
+        }
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Push to Host")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.accent()),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
+    }
+
+    fn render_diff(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppStateManager,
+        diff: &Result<Vec<DiffLine>, String>,
+    ) {
+        frame.render_widget(Clear, area);
+
+        let path = state.restore_items
+            .get(state.selected_item_index)
+            .map(|item| item.restore_path.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut lines = vec![
+            Line::from(Span::styled(format!("--- {} (on disk)", path), state.theme.style(Tone::Danger))),
+            Line::from(Span::styled(format!("+++ {} (archived)", path), state.theme.style(Tone::Good))),
+            Line::from(""),
+        ];
+
+        match diff {
+            Ok(diff) if diff.is_empty() => {
+                lines.push(Line::from(Span::styled(
+                    "The archived file is identical to the one on disk.",
+                    state.theme.style(Tone::Good),
+                )));
+            }
+            Ok(diff) => {
+                lines.extend(diff.iter().map(|line| match line {
+                    DiffLine::Same(text) => Line::from(format!(" {}", text)),
+                    DiffLine::Removed(text) => Line::from(Span::styled(
+                        format!("-{}", text),
+                        state.theme.style(Tone::Danger),
+                    )),
+                    DiffLine::Added(text) => Line::from(Span::styled(
+                        format!("+{}", text),
+                        state.theme.style(Tone::Good),
+                    )),
+                    DiffLine::Skipped(count) => Line::from(Span::styled(
+                        format!("@@ {} unchanged lines @@", count),
+                        state.theme.accent(),
+                    )),
+                }));
+            }
+            Err(e) => {
+                lines.push(Line::from(Span::styled(
+                    format!("Cannot show diff: {}", e),
+                    state.theme.style(Tone::Caution),
+                )));
+            }
+        }
+
+        let keys: Vec<String> = state.keymap
+            .hints(KeyContext::RestoreDiff, |_| true)
+            .iter()
+            .map(|hint| format!("{} {}", hint.keys, hint.description))
+            .collect();
+        let diff_view = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Diff ({})", keys.join(", ")))
+                    .title_alignment(Alignment::Center),
+            )
+            .scroll((state.restore_diff_scroll, 0));
+
+        frame.render_widget(diff_view, area);
+    }
+
+    fn render_live_changes(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppStateManager,
+        changes: &Result<LiveChanges, String>,
+    ) {
+        frame.render_widget(Clear, area);
+
+        let lines = match changes {
+            Ok(changes) => {
+                let mut lines = vec![Line::from(changes.summary()), Line::from("")];
+                if changes.changed.is_empty() {
+                    lines.push(Line::from(Span::styled(
+                        "Nothing under the archived items has changed since the backup.",
+                        state.theme.style(Tone::Good),
+                    )));
+                }
+                lines.extend(changes.changed.iter().map(|changed| {
+                    let tone = match changed.change {
+                        Change::Modified => Tone::Caution,
+                        Change::Deleted => Tone::Danger,
+                        Change::New => Tone::Good,
+                    };
+                    Line::from(vec![
+                        Span::styled(format!("{} {}", changed.change.marker(), changed.path), state.theme.style(tone)),
+                        Span::styled(format!("  {}", changed.detail), state.theme.muted()),
+                    ])
+                }));
+                lines
+            }
+            Err(e) => vec![Line::from(Span::styled(
+                format!("Cannot compare with disk: {}", e),
+                state.theme.style(Tone::Caution),
+            ))],
+        };
+
+        let keys: Vec<String> = state.keymap
+            .hints(KeyContext::LiveChanges, |_| true)
+            .iter()
+            .map(|hint| format!("{} {}", hint.keys, hint.description))
+            .collect();
+        let view = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Changes since backup ({})", keys.join(", ")))
+                    .title_alignment(Alignment::Center),
+            )
+            .scroll((state.live_changes_scroll, 0));
+
+        frame.render_widget(view, area);
+    }
+
+    fn render_restore_plan(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppStateManager,
+        plan: &Result<RestorePlan, String>,
+    ) {
+        frame.render_widget(Clear, area);
+
+        let lines = match plan {
+            Ok(plan) => {
+                let mut lines = vec![
+                    Line::from(plan.summary()),
+                    Line::from(format!("{} directories already exist. Nothing has been written.", plan.existing_dirs)),
+                    Line::from(""),
+                ];
+                lines.extend(plan.paths.iter().map(|path| {
+                    let tone = match path.change {
+                        PlannedChange::Overwrite { .. } => Tone::Caution,
+                        PlannedChange::CreateFile | PlannedChange::CreateDir => Tone::Good,
+                    };
+                    Line::from(Span::styled(path.describe(), state.theme.style(tone)))
+                }));
+                lines
+            }
+            Err(e) => vec![Line::from(Span::styled(
+                format!("Cannot simulate the restore: {}", e),
+                state.theme.style(Tone::Caution),
+            ))],
+        };
+
+        let keys: Vec<String> = state.keymap
+            .hints(KeyContext::RestoreDryRun, |action| action != Action::Confirm || plan.is_ok())
+            .iter()
+            .map(|hint| format!("{} {}", hint.keys, hint.description))
+            .collect();
+        let view = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Dry run ({})", keys.join(", ")))
+                    .title_alignment(Alignment::Center),
+            )
+            .scroll((state.restore_plan_scroll, 0));
+
+        frame.render_widget(view, area);
+    }
+
+    fn render_privileged_confirm(&self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let area = centered_rect(70, 60, frame.area());
+        frame.render_widget(Clear, area);
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("The selected items contain privileged files:",
+                    state.theme.style(Tone::Danger).add_modifier(Modifier::BOLD))
+            ]),
+            Line::from(""),
+        ];
+
+        for entry in state.selected_privileged_entries() {
+            lines.push(Line::from(vec![
+                Span::raw("• "),
+                Span::raw(entry.path.clone()),
+                Span::styled(format!(" [{}]", entry.describe()), state.theme.style(Tone::Caution)),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from("Restoring these bits lets the files run with elevated privileges."));
+        lines.push(Line::from("Only keep them if you trust the archive. Each file is recorded in the audit log."));
+        lines.push(Line::from(""));
+        lines.push(Line::from(hint_spans(&state.keymap.hints(KeyContext::PrivilegedRestore, |_| true), &state.theme)));
+
+        let dialog = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("⚠️  Privileged Files")
+                    .title_alignment(Alignment::Center)
+                    .style(state.theme.style(Tone::Danger)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(dialog, area);
     }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use crossterm::event::KeyEvent;
 use ratatui::layout::{Constraint, Direction, Layout};
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::security::SecurePassword;
 use crate::core::state::AppStateManager;
 use crate::ui::components::{render_header, render_footer};
@@ -40,23 +41,21 @@ impl RestorePasswordScreen {
             frame,
             chunks[0],
             "Archive Password Required",
-            Some(&format!("Enter password to unlock archive: {}", archive_name)),
+            Some(&format!("Unlock {} with its passphrase, or your GPG key's passphrase or card PIN", archive_name)),
+            &state.theme,
         );
 
         // Password input (centered)
         let password_area = centered_rect(50, 40, chunks[1]);
-        self.password_input.render(frame, password_area);
+        self.password_input.render(frame, password_area, &state.theme);
 
         // Footer
-        let shortcuts = [
-            ("Enter", "Unlock Archive"),
-            ("Esc", "Back"),
-        ];
+        let hints = state.keymap.hints(KeyContext::RestorePassword, |_| true);
 
-        render_footer(frame, chunks[2], &shortcuts, None);
+        render_footer(frame, chunks[2], &hints, None, &state.theme);
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> Option<SecurePassword> {
-        self.password_input.handle_key(key)
+    pub fn handle_key(&mut self, action: Option<Action>, key: KeyEvent) -> Option<SecurePassword> {
+        self.password_input.handle_key(action, key)
     }
 }
\ No newline at end of file
@@ -1,8 +1,16 @@
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Gauge, Paragraph},
+};
 
+use crate::core::keymap::{Action, KeyContext};
 use crate::core::state::AppStateManager;
-use crate::core::types::ProgressStatus;
-use crate::ui::components::{render_header, render_footer, render_progress_bar};
+use crate::core::types::{ProgressStatus, RestoreProgress};
+use crate::ui::components::{render_header, render_footer, render_progress_bar, ProgressCounts};
+use crate::ui::terminal::{format_bytes, format_duration};
+use crate::ui::theme::Theme;
 
 pub struct RestoreProgressScreen;
 
@@ -34,10 +42,16 @@ impl RestoreProgressScreen {
             chunks[0],
             "Restore in Progress",
             Some(&format!("Restoring from archive: {}", archive_name)),
+            &state.theme,
         );
 
         // Progress content
-        if let Some(progress) = &state.restore_progress {
+        let downloading = state.restore_progress
+            .as_ref()
+            .filter(|progress| progress.status == ProgressStatus::Downloading);
+        if let Some(progress) = downloading {
+            render_download(frame, chunks[1], progress, &state.theme);
+        } else if let Some(progress) = &state.restore_progress {
             let percentage = if progress.total_items > 0 {
                 (progress.items_completed as f64 / progress.total_items as f64) * 100.0
             } else {
@@ -50,8 +64,14 @@ impl RestoreProgressScreen {
                 &format!("Restore Progress - {}", progress.status.as_str()),
                 percentage,
                 &progress.current_item,
-                progress.items_completed,
-                progress.total_items,
+                &ProgressCounts {
+                    items_completed: progress.items_completed,
+                    total_items: progress.total_items,
+                    bytes_processed: progress.bytes_processed,
+                    total_bytes: progress.total_bytes,
+                    bytes_per_second: progress.bytes_per_second(),
+                },
+                &state.theme,
             );
         } else {
             // Fallback if no progress data
@@ -61,15 +81,18 @@ impl RestoreProgressScreen {
                 "Initializing Restore...",
                 0.0,
                 "Preparing...",
-                0,
-                1,
+                &ProgressCounts { total_items: 1, ..ProgressCounts::default() },
+                &state.theme,
             );
         }
 
         // Footer
-        let shortcuts = [
-            ("Ctrl+C", "Cancel"),
-        ];
+        let mut hints = if downloading.is_some() {
+            state.keymap.hints(KeyContext::RestoreProgress, |_| true)
+        } else {
+            Vec::new()
+        };
+        hints.extend(state.keymap.hint(KeyContext::Global, Action::Quit));
 
         let status = if let Some(progress) = &state.restore_progress {
             match &progress.status {
@@ -80,6 +103,54 @@ impl RestoreProgressScreen {
             None
         };
 
-        render_footer(frame, chunks[2], &shortcuts, status);
+        render_footer(frame, chunks[2], &hints, status, &state.theme);
     }
+}
+
+/// Transfer of a remote archive, before anything is extracted
+fn render_download(frame: &mut ratatui::Frame, area: Rect, progress: &RestoreProgress, theme: &Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Step 1 of 2: Downloading archive")
+        .title_alignment(Alignment::Center);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Gauge
+            Constraint::Min(0),    // Speed and ETA
+        ])
+        .split(inner);
+
+    let ratio = if progress.total_bytes > 0 {
+        (progress.bytes_processed as f64 / progress.total_bytes as f64).min(1.0)
+    } else {
+        0.0
+    };
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL))
+        .gauge_style(theme.accent())
+        .ratio(ratio)
+        .label(format!(
+            "{} / {}",
+            format_bytes(progress.bytes_processed),
+            format_bytes(progress.total_bytes)
+        ));
+    frame.render_widget(gauge, chunks[0]);
+
+    let (speed, eta) = match (progress.bytes_per_second(), progress.eta()) {
+        (Some(rate), Some(eta)) => (format!("{}/s", format_bytes(rate as u64)), format_duration(eta)),
+        _ => ("measuring…".to_string(), "measuring…".to_string()),
+    };
+    let lines = vec![
+        Line::from(format!("Speed: {}", speed)),
+        Line::from(format!("Time left: {}", eta)),
+        Line::from(""),
+        Line::from("Files are restored once the whole archive is here").style(
+            theme.muted().add_modifier(Modifier::ITALIC),
+        ),
+    ];
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[1]);
 }
\ No newline at end of file
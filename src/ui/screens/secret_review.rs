@@ -0,0 +1,103 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
+use crate::ui::theme::Tone;
+
+pub struct SecretReviewScreen;
+
+impl SecretReviewScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        let findings = &state.secret_findings;
+        let left_out = findings.iter().filter(|(_, excluded)| *excluded).count();
+
+        // Header
+        let subtitle = format!(
+            "{} files look like they hold credentials | {} left out of this backup",
+            findings.len(),
+            left_out,
+        );
+        render_header(frame, chunks[0], "Possible Secrets", Some(&subtitle), &state.theme);
+        render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
+
+        let content_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),    // Findings
+                Constraint::Length(4), // Explanation
+            ])
+            .split(chunks[1]);
+
+        // Findings
+        let visible_height = content_chunks[0].height.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = findings
+            .iter()
+            .enumerate()
+            .skip(state.scroll_offset)
+            .take(visible_height)
+            .map(|(i, (finding, excluded))| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else if *excluded {
+                    Style::default()
+                } else {
+                    state.theme.style(Tone::Caution)
+                };
+                let checkbox = if *excluded { "[x]" } else { "[ ]" };
+                let location = match finding.line {
+                    Some(line) => format!("{}:{}", finding.path.display(), line),
+                    None => finding.path.display().to_string(),
+                };
+                ListItem::new(format!("{} {} - {}", checkbox, location, finding.reason)).style(style)
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Flagged Files")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[0]);
+
+        // Explanation
+        let (tone, text) = if left_out == findings.len() {
+            (Tone::Good, "Ticked files are left out of this backup only. Add them to the exclusions, or to secret_scan.ignore if they are safe, to stop seeing them here.")
+        } else {
+            (Tone::Caution, "Unticked files go into the archive as they are, credentials included. Keep them only if the archive is stored somewhere as safe as this machine.")
+        };
+        let explanation = Paragraph::new(Line::from(Span::styled(
+            format!("{}{}", state.theme.tag(tone), text),
+            state.theme.style(tone),
+        )))
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(explanation, content_chunks[1]);
+
+        // Footer
+        let hints = state.keymap.hints(KeyContext::SecretReview, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
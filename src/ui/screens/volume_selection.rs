@@ -0,0 +1,151 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::core::keymap::KeyContext;
+use crate::core::state::AppStateManager;
+use crate::ui::components::{render_backup_steps, render_header, render_footer};
+use crate::ui::layout::split_panels;
+use crate::ui::terminal::format_bytes;
+use crate::ui::theme::Tone;
+
+pub struct VolumeSelectionScreen;
+
+impl VolumeSelectionScreen {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, state: &AppStateManager) {
+        let size = frame.area();
+        let restoring = state.volume_item.is_none();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),  // Header
+                Constraint::Min(0),     // Content
+                Constraint::Length(3),  // Footer
+            ])
+            .split(size);
+
+        // Header
+        if restoring {
+            render_header(
+                frame,
+                chunks[0],
+                "Restore Container Volumes",
+                Some("Volumes that don't exist here are created before their files are copied in"),
+                &state.theme,
+            );
+        } else {
+            render_header(
+                frame,
+                chunks[0],
+                "Container Volumes",
+                Some("The ticked volumes are copied into every backup of this item"),
+                &state.theme,
+            );
+            render_backup_steps(frame, chunks[0], state.current_state.backup_step(), &state.backup_mode, &state.theme);
+        }
+
+        let content_chunks = split_panels(chunks[1], 55);
+
+        // Volumes
+        let items: Vec<ListItem> = state.volume_choices
+            .iter()
+            .enumerate()
+            .map(|(i, (volume, selected))| {
+                let style = if i == state.selected_item_index {
+                    state.theme.selection()
+                } else {
+                    Style::default()
+                };
+                let checkbox = if *selected { "[x]" } else { "[ ]" };
+                let users = match volume.containers.len() {
+                    0 => "unused".to_string(),
+                    1 => "1 container".to_string(),
+                    n => format!("{} containers", n),
+                };
+                ListItem::new(format!("{} {}  ({})", checkbox, volume.name, users)).style(style)
+            })
+            .collect();
+
+        let ticked = state.volume_choices.iter().filter(|(_, selected)| *selected).count();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Volumes ({} selected)", ticked))
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(list, content_chunks[0]);
+
+        // Details
+        let label = |text: &'static str| Span::styled(text, Style::default().add_modifier(Modifier::BOLD));
+        let mut lines = match state.volume_choices.get(state.selected_item_index) {
+            Some((volume, _)) => {
+                let mut lines = vec![Line::from(vec![label("Driver: "), Span::raw(&volume.driver)])];
+                if let Some(bytes) = volume.archived {
+                    lines.push(Line::from(vec![label("In archive: "), Span::raw(format_bytes(bytes))]));
+                }
+                lines.push(Line::from(""));
+                if volume.containers.is_empty() {
+                    lines.push(Line::from("No container uses this volume"));
+                }
+                for container in &volume.containers {
+                    let status = if container.running {
+                        Span::styled(format!("{}running", state.theme.tag(Tone::Good)), state.theme.style(Tone::Good))
+                    } else {
+                        Span::raw("stopped")
+                    };
+                    lines.push(Line::from(vec![
+                        label("Container: "),
+                        Span::raw(format!("{} at {} ", container.name, container.destination)),
+                        status,
+                    ]));
+                }
+
+                // Files written under a running service can be overwritten
+                // again by it, or break it
+                if restoring && volume.containers.iter().any(|container| container.running) {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        format!("{}Stop the running containers before restoring this volume", state.theme.tag(Tone::Caution)),
+                        state.theme.style(Tone::Caution),
+                    )));
+                }
+                lines
+            }
+            None => Vec::new(),
+        };
+
+        if !restoring {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                label("Pause containers: "),
+                Span::raw(if state.volume_pause {
+                    "yes, running containers are paused while their volume is copied"
+                } else {
+                    "no, volumes are copied while their containers run"
+                }),
+            ]));
+        }
+
+        let details = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Details")
+                .title_alignment(Alignment::Center),
+        );
+        frame.render_widget(details, content_chunks[1]);
+
+        // Footer
+        let context = if restoring { KeyContext::VolumeRestore } else { KeyContext::VolumeSelection };
+        let hints = state.keymap.hints(context, |_| true);
+
+        render_footer(frame, chunks[2], &hints, state.status_message.as_deref(), &state.theme);
+    }
+}
@@ -10,11 +10,24 @@ use ratatui::{
 };
 use std::{
     io::{self, Stdout},
-    time::Duration,
+    time::{Duration, Instant},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// How often the event loop ticks when no input arrives
+pub const TICK_RATE: Duration = Duration::from_millis(100);
+
+/// What the event loop receives: terminal input, or a tick every
+/// `TICK_RATE` so spinners and live figures move between keypresses
+#[derive(Debug, Clone)]
+pub enum TerminalEvent {
+    Input(Event),
+    Tick,
+}
 
 pub struct Terminal {
     terminal: RatatuiTerminal<CrosstermBackend<Stdout>>,
+    last_tick: Instant,
 }
 
 impl Terminal {
@@ -29,7 +42,7 @@ impl Terminal {
         let terminal = RatatuiTerminal::new(backend)
             .context("Failed to create terminal")?;
         
-        Ok(Self { terminal })
+        Ok(Self { terminal, last_tick: Instant::now() })
     }
 
     pub fn draw<F>(&mut self, f: F) -> Result<()>
@@ -42,13 +55,16 @@ impl Terminal {
         Ok(())
     }
 
-    pub async fn next_event(&mut self) -> Result<Option<Event>> {
-        // Check for events with timeout to allow for periodic updates
-        if event::poll(Duration::from_millis(100))? {
-            Ok(Some(event::read().context("Failed to read event")?))
-        } else {
-            Ok(None) // No event available
+    /// Wait for input until the next tick is due. A tick comes every
+    /// `TICK_RATE` however much input arrives in between.
+    pub async fn next_event(&mut self) -> Result<TerminalEvent> {
+        if let Some(wait) = input_wait(self.last_tick.elapsed()) {
+            if event::poll(wait)? {
+                return Ok(TerminalEvent::Input(event::read().context("Failed to read event")?));
+            }
         }
+        self.last_tick = Instant::now();
+        Ok(TerminalEvent::Tick)
     }
 
     pub fn cleanup(&mut self) -> Result<()> {
@@ -78,6 +94,33 @@ impl Drop for Terminal {
     }
 }
 
+/// How long input may be waited for `since_tick` after the last tick, or
+/// `None` when the next tick is due
+fn input_wait(since_tick: Duration) -> Option<Duration> {
+    TICK_RATE.checked_sub(since_tick).filter(|wait| !wait.is_zero())
+}
+
+/// Whether the terminal can be expected to draw Unicode symbols: not on
+/// the Linux console, whose fonts lack them, nor under a locale that
+/// isn't UTF-8. With no locale set at all, Unicode is assumed.
+pub fn supports_unicode() -> bool {
+    unicode_environment(|name| std::env::var(name).ok())
+}
+
+fn unicode_environment(var: impl Fn(&str) -> Option<String>) -> bool {
+    if var("TERM").as_deref() == Some("linux") {
+        return false;
+    }
+    // The first of these that is set decides the character encoding
+    match ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|name| var(name).filter(|value| !value.is_empty())) {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => true,
+    }
+}
+
 /// Helper function to center a rectangle within another rectangle
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
     use ratatui::layout::{Constraint, Direction, Layout};
@@ -135,15 +178,55 @@ pub fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
-/// Helper function to truncate text to fit within a specific width
-pub fn truncate_text(text: &str, max_width: usize) -> String {
-    if text.len() <= max_width {
-        text.to_string()
-    } else if max_width <= 3 {
-        "...".to_string()
+/// Helper function to format how long ago something happened
+pub fn format_age(age: chrono::Duration) -> String {
+    if age.num_days() > 0 {
+        format!("{}d {}h ago", age.num_days(), age.num_hours() % 24)
+    } else if age.num_hours() > 0 {
+        format!("{}h {}m ago", age.num_hours(), age.num_minutes() % 60)
+    } else if age.num_minutes() > 0 {
+        format!("{}m ago", age.num_minutes())
     } else {
-        format!("{}...", &text[..max_width - 3])
+        "just now".to_string()
+    }
+}
+
+/// Columns `text` takes in the terminal; CJK characters and most emoji
+/// take two
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Helper function to truncate text to fit within a specific width, in
+/// terminal columns
+pub fn truncate_text(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    if max_width <= 3 {
+        return "...".to_string();
     }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width > max_width - 3 {
+            break;
+        }
+        truncated.push(c);
+        width += char_width;
+    }
+    truncated.push_str("...");
+    truncated
+}
+
+/// Truncate or pad `text` to exactly `width` columns, for table cells;
+/// `format!("{:<20}")` pads by characters and misaligns wide ones
+pub fn pad_text(text: &str, width: usize) -> String {
+    let truncated = truncate_text(text, width);
+    let padding = width.saturating_sub(display_width(&truncated));
+    format!("{}{}", truncated, " ".repeat(padding))
 }
 
 /// Helper function to create a progress bar string
@@ -162,6 +245,15 @@ pub fn create_progress_bar(percentage: f64, width: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_ticks_keep_their_rate_through_input() {
+        assert_eq!(input_wait(Duration::ZERO), Some(TICK_RATE));
+        // Input partway through waits only for the rest of the interval
+        assert_eq!(input_wait(Duration::from_millis(40)), Some(Duration::from_millis(60)));
+        assert_eq!(input_wait(TICK_RATE), None);
+        assert_eq!(input_wait(Duration::from_millis(250)), None);
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(format_bytes(512), "512 B");
@@ -184,6 +276,26 @@ mod tests {
         assert_eq!(truncate_text("hello world", 8), "hello...");
         assert_eq!(truncate_text("hi", 2), "hi");
         assert_eq!(truncate_text("hello", 3), "...");
+
+        // Two columns per character, and never split inside one
+        assert_eq!(truncate_text("写真/旅行/京都.jpg", 10), "写真/旅...");
+        assert_eq!(display_width(&truncate_text("写真/旅行/京都.jpg", 10)), 10);
+        assert_eq!(truncate_text("📁 backups", 20), "📁 backups");
+        assert_eq!(pad_text("東京", 6), "東京  ");
+        assert_eq!(display_width(&pad_text("サーバー-01.local", 12)), 12);
+    }
+
+    #[test]
+    fn test_unicode_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        };
+        assert!(unicode_environment(env(&[("LANG", "en_US.UTF-8"), ("TERM", "xterm-256color")])));
+        assert!(unicode_environment(env(&[])));
+        assert!(!unicode_environment(env(&[("LANG", "en_US.UTF-8"), ("TERM", "linux")])));
+        assert!(!unicode_environment(env(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")])));
+        // An empty variable doesn't count as set
+        assert!(unicode_environment(env(&[("LC_ALL", ""), ("LC_CTYPE", "de_DE.utf8")])));
     }
 
     #[test]
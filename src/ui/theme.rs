@@ -0,0 +1,311 @@
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+use crate::core::config::UiConfig;
+use crate::core::types::SecurityLevel;
+use crate::ui::terminal::supports_unicode;
+
+/// Colors the UI is drawn in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Green, yellow and red on a dark terminal
+    #[default]
+    #[serde(alias = "dark")]
+    Standard,
+    /// Darker shades that stay readable on a light terminal
+    Light,
+    /// Bright colors, bold headings and an inverted selection
+    HighContrast,
+    /// Blue, yellow and vermillion, which stay apart with red-green
+    /// color blindness
+    Colorblind,
+}
+
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "standard" | "dark" => Ok(Palette::Standard),
+            "light" => Ok(Palette::Light),
+            "high-contrast" => Ok(Palette::HighContrast),
+            "colorblind" => Ok(Palette::Colorblind),
+            other => Err(format!(
+                "unknown theme '{}'; use dark, light, high-contrast or colorblind",
+                other
+            )),
+        }
+    }
+}
+
+/// What a colored status means, independent of the color that shows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Good,
+    Caution,
+    Danger,
+}
+
+/// Small pictures beside list entries and titles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Locked,
+    Warning,
+    Missing,
+    Folder,
+}
+
+/// How the UI looks: the palette, whether text tags such as `[!]` go
+/// beside the colors so nothing relies on color alone, and whether
+/// symbols are drawn in plain ASCII
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub status_tags: bool,
+    pub ascii: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self { palette: Palette::Standard, status_tags: true, ascii: false }
+    }
+}
+
+impl Theme {
+    /// The config's `ui` section; without an `ascii` setting, ASCII is
+    /// used where the terminal doesn't look able to draw Unicode
+    pub fn from_config(ui: Option<&UiConfig>) -> Self {
+        let ascii = ui.and_then(|ui| ui.ascii).unwrap_or_else(|| !supports_unicode());
+        match ui {
+            Some(ui) => Self { palette: ui.palette, status_tags: ui.status_tags, ascii },
+            None => Self { ascii, ..Self::default() },
+        }
+    }
+
+    /// This theme with the `--theme` palette, if given, in place of the
+    /// config's, and ASCII symbols when `--ascii` was given
+    pub fn overridden_by(self, palette: Option<Palette>, ascii: bool) -> Self {
+        Self { palette: palette.unwrap_or(self.palette), ascii: self.ascii || ascii, ..self }
+    }
+
+    pub fn color(&self, tone: Tone) -> Color {
+        match (self.palette, tone) {
+            (Palette::Standard, Tone::Good) => Color::Green,
+            (Palette::Standard, Tone::Caution) => Color::Yellow,
+            (Palette::Standard, Tone::Danger) => Color::Red,
+            (Palette::Light, Tone::Good) => Color::Rgb(0, 128, 0),
+            (Palette::Light, Tone::Caution) => Color::Rgb(170, 85, 0),
+            (Palette::Light, Tone::Danger) => Color::Rgb(190, 0, 0),
+            (Palette::HighContrast, Tone::Good) => Color::LightGreen,
+            (Palette::HighContrast, Tone::Caution) => Color::LightYellow,
+            (Palette::HighContrast, Tone::Danger) => Color::LightRed,
+            (Palette::Colorblind, Tone::Good) => Color::Rgb(86, 180, 233),
+            (Palette::Colorblind, Tone::Caution) => Color::Rgb(240, 228, 66),
+            (Palette::Colorblind, Tone::Danger) => Color::Rgb(213, 94, 0),
+        }
+    }
+
+    pub fn style(&self, tone: Tone) -> Style {
+        Style::default().fg(self.color(tone))
+    }
+
+    /// Headings, titles and other text that leads the eye
+    pub fn accent(&self) -> Style {
+        match self.palette {
+            Palette::Standard | Palette::Colorblind => Style::default().fg(Color::Cyan),
+            Palette::Light => Style::default().fg(Color::Blue),
+            Palette::HighContrast => Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Keys to press and the text cursor
+    pub fn highlight(&self) -> Style {
+        match self.palette {
+            Palette::Standard | Palette::Colorblind => Style::default().fg(Color::Yellow),
+            Palette::Light => Style::default().fg(Color::Magenta),
+            Palette::HighContrast => Style::default().fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Secondary text such as hints, labels and placeholders
+    pub fn muted(&self) -> Style {
+        match self.palette {
+            Palette::Standard | Palette::Colorblind => Style::default().fg(Color::Gray),
+            Palette::Light => Style::default().fg(Color::DarkGray),
+            Palette::HighContrast => Style::default().fg(Color::White),
+        }
+    }
+
+    /// The highlighted row of a list or menu
+    pub fn selection(&self) -> Style {
+        match self.palette {
+            Palette::HighContrast => Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD),
+            _ => Style::default().bg(Color::Blue).fg(Color::White),
+        }
+    }
+
+    /// Background of dialogs drawn over a screen
+    pub fn surface(&self) -> Style {
+        match self.palette {
+            Palette::Light => Style::default().bg(Color::White).fg(Color::Black),
+            _ => Style::default().bg(Color::Black).fg(Color::White),
+        }
+    }
+
+    /// `[ok] `, `[!] ` or `[!!] `, or nothing when tags are off
+    pub fn tag(&self, tone: Tone) -> &'static str {
+        if !self.status_tags {
+            return "";
+        }
+        match tone {
+            Tone::Good => "[ok] ",
+            Tone::Caution => "[!] ",
+            Tone::Danger => "[!!] ",
+        }
+    }
+
+    /// `[HIGH] ` or `[MED] ` for items that need care; low items are
+    /// left untagged so the tagged ones stand out
+    pub fn security_tag(&self, level: &SecurityLevel) -> &'static str {
+        match level {
+            _ if !self.status_tags => "",
+            SecurityLevel::High => "[HIGH] ",
+            SecurityLevel::Medium => "[MED] ",
+            SecurityLevel::Low => "",
+        }
+    }
+
+    /// Checkbox of something with `selected` of its `total` parts ticked
+    pub fn checkbox(&self, selected: usize, total: usize) -> &'static str {
+        match (selected, self.ascii) {
+            (0, false) => "☐",
+            (0, true) => "[ ]",
+            (n, false) if n == total => "☑",
+            (n, true) if n == total => "[x]",
+            (_, false) => "◩",
+            (_, true) => "[-]",
+        }
+    }
+
+    pub fn icon(&self, icon: Icon) -> &'static str {
+        match (icon, self.ascii) {
+            (Icon::Locked, false) => "🔒",
+            (Icon::Locked, true) => "#",
+            (Icon::Warning, false) => "⚠️",
+            (Icon::Warning, true) => "!",
+            (Icon::Missing, false) => "❌",
+            (Icon::Missing, true) => "x",
+            (Icon::Folder, false) => "📁",
+            (Icon::Folder, true) => "+",
+        }
+    }
+
+    /// Marker of a tree group that is open or folded
+    pub fn fold(&self, expanded: bool) -> &'static str {
+        match (expanded, self.ascii) {
+            (true, false) => "▾",
+            (false, false) => "▸",
+            (true, true) => "-",
+            (false, true) => "+",
+        }
+    }
+
+    /// In ASCII mode, swap whatever symbols were drawn into `buffer` for
+    /// ASCII stand-ins; see [`to_ascii`]
+    pub fn apply_ascii(&self, buffer: &mut Buffer) {
+        if self.ascii {
+            to_ascii(buffer);
+        }
+    }
+}
+
+/// Swap the symbols drawn into `buffer` for ASCII stand-ins, so borders,
+/// icons and arrows still read on terminals without Unicode fonts.
+/// Letters in any script are left alone.
+pub fn to_ascii(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if let Some(symbol) = cell.symbol().chars().next().and_then(ascii_symbol) {
+            cell.set_symbol(symbol);
+        }
+    }
+}
+
+/// ASCII stand-in for a symbol starting with `c`; none for ASCII and
+/// letters in any script
+fn ascii_symbol(c: char) -> Option<&'static str> {
+    if c.is_ascii() || c.is_alphanumeric() {
+        return None;
+    }
+    Some(match c {
+        '─' | '━' | '═' | '╌' | '┄' => "-",
+        '│' | '┃' | '║' | '╎' | '┆' => "|",
+        '\u{2500}'..='\u{257f}' => "+",
+        '▏' => "_",
+        '\u{2580}'..='\u{259f}' => "#",
+        '\u{2800}'..='\u{28ff}' => "*",
+        '↑' | '▲' => "^",
+        '↓' | '▼' | '▾' => "v",
+        '←' | '↩' | '◀' => "<",
+        '→' | '▸' | '▶' => ">",
+        '•' | '·' => "*",
+        '…' => ".",
+        '✓' | '✔' | '☑' => "x",
+        '✗' | '✘' | '❌' => "X",
+        '☐' => "_",
+        '◩' => "-",
+        '○' | '◯' => "o",
+        '⚠' => "!",
+        'ℹ' => "i",
+        '📁' | '📂' => "+",
+        // Pictographs and dingbats that lead labels, such as 🔒 or ⚙
+        '\u{2600}'..='\u{27bf}' | '\u{1f000}'..='\u{1faff}' => "*",
+        _ => "?",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn test_colorblind_palette_and_tags() {
+        let ui: UiConfig = serde_json::from_str(r#"{"palette": "colorblind"}"#).unwrap();
+        let theme = Theme::from_config(Some(&ui));
+        assert!(theme.status_tags);
+        assert_ne!(theme.color(Tone::Good), Color::Green);
+        assert_ne!(theme.color(Tone::Danger), Color::Red);
+        assert_eq!(theme.security_tag(&SecurityLevel::High), "[HIGH] ");
+        assert_eq!(theme.tag(Tone::Caution), "[!] ");
+
+        let plain = Theme { palette: Palette::Standard, status_tags: false, ascii: false };
+        assert_eq!(plain.color(Tone::Danger), Color::Red);
+        assert_eq!(plain.tag(Tone::Danger), "");
+        assert_eq!(plain.security_tag(&SecurityLevel::High), "");
+    }
+
+    #[test]
+    fn test_theme_overrides_and_ascii() {
+        let ui: UiConfig = serde_json::from_str(r#"{"palette": "dark", "ascii": false}"#).unwrap();
+        let theme = Theme::from_config(Some(&ui));
+        assert_eq!(theme.palette, Palette::Standard);
+        assert!(!theme.ascii);
+
+        let theme = theme.overridden_by("high-contrast".parse().ok(), true);
+        assert_eq!(theme.palette, Palette::HighContrast);
+        assert_ne!(theme.selection(), Theme::default().selection());
+        assert!("sepia".parse::<Palette>().is_err());
+
+        assert_eq!(theme.checkbox(1, 2), "[-]");
+        assert_eq!(Theme::default().checkbox(2, 2), "☑");
+        assert_eq!(theme.icon(Icon::Locked), "#");
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 9, 1));
+        buffer.set_string(0, 0, "┌─ é ❌📊", Style::default());
+        theme.apply_ascii(&mut buffer);
+        let drawn: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        assert_eq!(drawn, "+- é X * ");
+    }
+}
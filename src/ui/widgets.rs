@@ -1,13 +1,24 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
 use zeroize::Zeroize;
 
+use crate::core::keymap::{Action, KeyContext, Keymap};
 use crate::core::security::{SecurePassword, PasswordStrength, validate_password_strength};
+use crate::ui::components::hint_spans;
+use crate::ui::terminal::display_width;
+use crate::ui::theme::{Icon, Theme, Tone};
+
+/// Pause in typing after which the password's strength is recalculated
+const STRENGTH_DEBOUNCE: Duration = Duration::from_millis(150);
 
 /// Password input widget with secure handling
 pub struct PasswordInput {
@@ -15,6 +26,10 @@ pub struct PasswordInput {
     cursor_position: usize,
     show_strength: bool,
     strength: Option<PasswordStrength>,
+    /// When to recalculate the strength, once typing has paused
+    strength_due: Option<Instant>,
+    /// Strength being calculated on a blocking thread
+    strength_pending: Option<oneshot::Receiver<PasswordStrength>>,
     confirm_mode: bool,
     confirm_input: String,
     confirm_cursor: usize,
@@ -34,6 +49,8 @@ impl PasswordInput {
             cursor_position: 0,
             show_strength,
             strength: None,
+            strength_due: None,
+            strength_pending: None,
             confirm_mode,
             confirm_input: String::new(),
             confirm_cursor: 0,
@@ -41,19 +58,54 @@ impl PasswordInput {
         }
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> Option<SecurePassword> {
+    /// Whether nothing has been typed in either field
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty() && self.confirm_input.is_empty()
+    }
+
+    /// Apply a key press; `action` is what the screen's bindings make of
+    /// it, anything unbound edits the active field
+    pub fn handle_key(&mut self, action: Option<Action>, key: KeyEvent) -> Option<SecurePassword> {
+        match action {
+            Some(Action::NextField) => {
+                if self.confirm_mode {
+                    self.active_field = match self.active_field {
+                        PasswordField::Password => PasswordField::Confirm,
+                        PasswordField::Confirm => PasswordField::Password,
+                    };
+                }
+                return None;
+            }
+            Some(Action::Confirm) => {
+                if self.confirm_mode {
+                    if self.input == self.confirm_input && !self.input.is_empty() {
+                        let password = SecurePassword::new(self.input.clone());
+                        self.clear();
+                        return Some(password);
+                    }
+                } else if !self.input.is_empty() {
+                    let password = SecurePassword::new(self.input.clone());
+                    self.clear();
+                    return Some(password);
+                }
+                return None;
+            }
+            Some(_) => return None,
+            None => {}
+        }
+
         match key.code {
             KeyCode::Char(c) => {
                 match self.active_field {
                     PasswordField::Password => {
-                        self.input.insert(self.cursor_position, c);
+                        self.input.insert(byte_index(&self.input, self.cursor_position), c);
                         self.cursor_position += 1;
                         if self.show_strength {
-                            self.update_strength();
+                            self.schedule_strength();
                         }
                     }
                     PasswordField::Confirm => {
-                        self.confirm_input.insert(self.confirm_cursor, c);
+                        self.confirm_input.insert(byte_index(&self.confirm_input, self.confirm_cursor), c);
                         self.confirm_cursor += 1;
                     }
                 }
@@ -63,16 +115,16 @@ impl PasswordInput {
                     PasswordField::Password => {
                         if self.cursor_position > 0 {
                             self.cursor_position -= 1;
-                            self.input.remove(self.cursor_position);
+                            self.input.remove(byte_index(&self.input, self.cursor_position));
                             if self.show_strength {
-                                self.update_strength();
+                                self.schedule_strength();
                             }
                         }
                     }
                     PasswordField::Confirm => {
                         if self.confirm_cursor > 0 {
                             self.confirm_cursor -= 1;
-                            self.confirm_input.remove(self.confirm_cursor);
+                            self.confirm_input.remove(byte_index(&self.confirm_input, self.confirm_cursor));
                         }
                     }
                 }
@@ -90,40 +142,19 @@ impl PasswordInput {
             KeyCode::Right => {
                 match self.active_field {
                     PasswordField::Password => {
-                        self.cursor_position = (self.cursor_position + 1).min(self.input.len());
+                        self.cursor_position = (self.cursor_position + 1).min(self.input.chars().count());
                     }
                     PasswordField::Confirm => {
-                        self.confirm_cursor = (self.confirm_cursor + 1).min(self.confirm_input.len());
+                        self.confirm_cursor = (self.confirm_cursor + 1).min(self.confirm_input.chars().count());
                     }
                 }
             }
-            KeyCode::Tab => {
-                if self.confirm_mode {
-                    self.active_field = match self.active_field {
-                        PasswordField::Password => PasswordField::Confirm,
-                        PasswordField::Confirm => PasswordField::Password,
-                    };
-                }
-            }
-            KeyCode::Enter => {
-                if self.confirm_mode {
-                    if self.input == self.confirm_input && !self.input.is_empty() {
-                        let password = SecurePassword::new(self.input.clone());
-                        self.clear();
-                        return Some(password);
-                    }
-                } else if !self.input.is_empty() {
-                    let password = SecurePassword::new(self.input.clone());
-                    self.clear();
-                    return Some(password);
-                }
-            }
             _ => {}
         }
         None
     }
 
-    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect) {
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, theme: &Theme) {
         // Clear the background
         frame.render_widget(Clear, area);
 
@@ -131,7 +162,7 @@ impl PasswordInput {
             .borders(Borders::ALL)
             .title("Enter Password")
             .title_alignment(Alignment::Center)
-            .style(Style::default().bg(Color::Black));
+            .style(theme.surface());
 
         let inner_area = block.inner(area);
         frame.render_widget(block, area);
@@ -158,11 +189,11 @@ impl PasswordInput {
         let mut chunk_index = 0;
 
         // Password field
-        let password_display = "*".repeat(self.input.len());
+        let password_display = "*".repeat(self.input.chars().count());
         let password_style = if self.active_field == PasswordField::Password {
-            Style::default().fg(Color::Yellow)
+            theme.highlight()
         } else {
-            Style::default().fg(Color::Gray)
+            theme.muted()
         };
 
         let password_block = Block::default()
@@ -178,11 +209,11 @@ impl PasswordInput {
 
         // Confirm field (if in confirm mode)
         if self.confirm_mode {
-            let confirm_display = "*".repeat(self.confirm_input.len());
+            let confirm_display = "*".repeat(self.confirm_input.chars().count());
             let confirm_style = if self.active_field == PasswordField::Confirm {
-                Style::default().fg(Color::Yellow)
+                theme.highlight()
             } else {
-                Style::default().fg(Color::Gray)
+                theme.muted()
             };
 
             let confirm_block = Block::default()
@@ -200,11 +231,10 @@ impl PasswordInput {
         // Strength indicator (if enabled and available)
         if self.show_strength {
             if let Some(ref strength) = self.strength {
-            let strength_color = match strength.score {
-                80.. => Color::Green,
-                60..80 => Color::Yellow,
-                40..60 => Color::Rgb(255, 165, 0), // Orange
-                _ => Color::Red,
+            let strength_tone = match strength.score {
+                80.. => Tone::Good,
+                40..80 => Tone::Caution,
+                _ => Tone::Danger,
             };
 
             let strength_text = format!("Strength: {}% - {}", strength.score, 
@@ -213,7 +243,7 @@ impl PasswordInput {
             let strength_block = Block::default()
                 .borders(Borders::ALL)
                 .title("Password Strength")
-                .style(Style::default().fg(strength_color));
+                .style(theme.style(strength_tone));
 
             let strength_paragraph = Paragraph::new(strength_text)
                 .block(strength_block)
@@ -233,7 +263,7 @@ impl PasswordInput {
             instructions.push(Line::from("Use Tab to switch between fields"));
             if self.input != self.confirm_input {
                 instructions.push(Line::from(vec![
-                    Span::styled("Passwords do not match!", Style::default().fg(Color::Red))
+                    Span::styled("Passwords do not match!", theme.style(Tone::Danger))
                 ]));
             }
         }
@@ -247,12 +277,41 @@ impl PasswordInput {
         frame.render_widget(instructions_paragraph, chunks[chunk_index]);
     }
 
-    fn update_strength(&mut self) {
-        if !self.input.is_empty() {
-            let password = SecurePassword::new(self.input.clone());
-            self.strength = Some(validate_password_strength(&password));
-        } else {
+    /// Recalculate the strength once typing pauses; a calculation already
+    /// running is for an outdated password and its result is dropped
+    fn schedule_strength(&mut self) {
+        self.strength_pending = None;
+        if self.input.is_empty() {
             self.strength = None;
+            self.strength_due = None;
+        } else {
+            self.strength_due = Some(Instant::now() + STRENGTH_DEBOUNCE);
+        }
+    }
+
+    /// Start the strength calculation once it is due and collect its
+    /// result, so typing never waits on it; called once per frame
+    pub fn poll_strength(&mut self) {
+        if let Some(rx) = self.strength_pending.as_mut() {
+            match rx.try_recv() {
+                Ok(strength) => {
+                    self.strength = Some(strength);
+                    self.strength_pending = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => self.strength_pending = None,
+            }
+        }
+
+        if self.strength_due.is_some_and(|due| Instant::now() >= due) {
+            self.strength_due = None;
+            // The copy is zeroed when the calculation drops it
+            let password = SecurePassword::new(self.input.clone());
+            let (tx, rx) = oneshot::channel();
+            tokio::task::spawn_blocking(move || {
+                let _ = tx.send(validate_password_strength(&password));
+            });
+            self.strength_pending = Some(rx);
         }
     }
 
@@ -264,6 +323,8 @@ impl PasswordInput {
         self.cursor_position = 0;
         self.confirm_cursor = 0;
         self.strength = None;
+        self.strength_due = None;
+        self.strength_pending = None;
         self.active_field = PasswordField::Password;
     }
 }
@@ -274,6 +335,12 @@ impl Drop for PasswordInput {
     }
 }
 
+/// Byte offset of the character at `cursor`, which counts characters so
+/// non-ASCII passwords can be edited
+fn byte_index(text: &str, cursor: usize) -> usize {
+    text.char_indices().nth(cursor).map(|(index, _)| index).unwrap_or(text.len())
+}
+
 /// Menu widget for selection screens
 pub struct Menu {
     items: Vec<MenuItem>,
@@ -281,16 +348,16 @@ pub struct Menu {
 }
 
 pub struct MenuItem {
-    pub key: char,
+    pub action: Action,
     pub label: String,
     pub description: String,
     pub enabled: bool,
 }
 
 impl MenuItem {
-    pub fn new(key: char, label: String, description: String) -> Self {
+    pub fn new(action: Action, label: String, description: String) -> Self {
         Self {
-            key,
+            action,
             label,
             description,
             enabled: true,
@@ -311,34 +378,31 @@ impl Menu {
         }
     }
 
-    pub fn handle_key(&mut self, key: KeyEvent) -> Option<char> {
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
+    /// Move the highlight or pick an item. Returns the picked item's
+    /// action, or any other action that isn't a disabled item's.
+    pub fn handle_action(&mut self, action: Action) -> Option<Action> {
+        match action {
+            Action::Up => {
                 self.move_selection_up();
+                None
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            Action::Down => {
                 self.move_selection_down();
+                None
             }
-            KeyCode::Enter => {
-                if let Some(item) = self.items.get(self.selected_index) {
-                    if item.enabled {
-                        return Some(item.key);
-                    }
-                }
-            }
-            KeyCode::Char(c) => {
-                if let Some(item) = self.items.iter().find(|item| item.key == c) {
-                    if item.enabled {
-                        return Some(c);
-                    }
-                }
-            }
-            _ => {}
+            Action::Confirm => self.items
+                .get(self.selected_index)
+                .filter(|item| item.enabled)
+                .map(|item| item.action),
+            action => match self.items.iter().find(|item| item.action == action) {
+                Some(item) if !item.enabled => None,
+                _ => Some(action),
+            },
         }
-        None
     }
 
-    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, title: &str) {
+    /// Items are listed with the first key bound to them in `context`
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, title: &str, keymap: &Keymap, context: KeyContext, theme: &Theme) {
         let block = Block::default()
             .borders(Borders::ALL)
             .title(title)
@@ -353,9 +417,9 @@ impl Menu {
             .map(|(i, item)| {
                 let is_selected = i == self.selected_index;
                 let style = if !item.enabled {
-                    Style::default().fg(Color::DarkGray)
+                    theme.muted()
                 } else if is_selected {
-                    Style::default().bg(Color::Blue).fg(Color::White)
+                    theme.selection()
                 } else {
                     Style::default()
                 };
@@ -364,7 +428,10 @@ impl Menu {
                 
                 Line::from(vec![
                     Span::raw(prefix),
-                    Span::styled(format!("{}. ", item.key), Style::default().fg(Color::Yellow)),
+                    Span::styled(
+                        format!("{}. ", keymap.key_label(context, item.action).unwrap_or_default()),
+                        theme.highlight(),
+                    ),
                     Span::styled(&item.label, style.add_modifier(Modifier::BOLD)),
                     Span::raw(" - "),
                     Span::styled(&item.description, style),
@@ -396,6 +463,172 @@ impl Menu {
     }
 }
 
+/// Entry of the directory shown by [`FileBrowser`]
+#[derive(Debug, Clone, PartialEq)]
+struct BrowserEntry {
+    name: String,
+    is_dir: bool,
+}
+
+/// Directory browser for picking a file or directory below `root`, which
+/// it never goes above
+#[derive(Debug)]
+pub struct FileBrowser {
+    root: PathBuf,
+    dir: PathBuf,
+    entries: Vec<BrowserEntry>,
+    selected: usize,
+    show_hidden: bool,
+    error: Option<String>,
+}
+
+impl FileBrowser {
+    pub fn new(root: PathBuf) -> Self {
+        let mut browser = Self {
+            dir: root.clone(),
+            root,
+            entries: Vec::new(),
+            selected: 0,
+            show_hidden: false,
+            error: None,
+        };
+        browser.read_dir();
+        browser
+    }
+
+    /// Move around or pick an entry; returns the picked path, relative to
+    /// the root
+    pub fn handle_action(&mut self, action: Action) -> Option<PathBuf> {
+        let count = self.entries.len();
+        match action {
+            Action::Up if count > 0 => self.selected = (self.selected + count - 1) % count,
+            Action::Down if count > 0 => self.selected = (self.selected + 1) % count,
+            Action::PageUp => self.selected = self.selected.saturating_sub(10),
+            Action::PageDown => self.selected = (self.selected + 10).min(count.saturating_sub(1)),
+            Action::Right => self.open(),
+            Action::Confirm => match self.entries.get(self.selected) {
+                Some(entry) if entry.is_dir => self.open(),
+                Some(_) => return self.picked(),
+                None => {}
+            },
+            Action::Toggle => return self.picked(),
+            Action::Left => self.leave(),
+            Action::Hidden => {
+                self.show_hidden = !self.show_hidden;
+                let name = self.entries.get(self.selected).map(|entry| entry.name.clone());
+                self.read_dir();
+                self.select(name.as_deref());
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// The highlighted entry, relative to the root
+    fn picked(&self) -> Option<PathBuf> {
+        let entry = self.entries.get(self.selected)?;
+        self.dir.join(&entry.name).strip_prefix(&self.root).ok().map(Path::to_path_buf)
+    }
+
+    fn open(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected).filter(|entry| entry.is_dir) {
+            self.dir = self.dir.join(&entry.name);
+            self.read_dir();
+            self.selected = 0;
+        }
+    }
+
+    /// Go up to the parent directory, highlighting the one just left
+    fn leave(&mut self) {
+        if self.dir == self.root {
+            return;
+        }
+        let left = self.dir.file_name().map(|name| name.to_string_lossy().to_string());
+        self.dir.pop();
+        self.read_dir();
+        self.select(left.as_deref());
+    }
+
+    fn select(&mut self, name: Option<&str>) {
+        self.selected = name
+            .and_then(|name| self.entries.iter().position(|entry| entry.name == name))
+            .unwrap_or(0)
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    /// List the directory, subdirectories first, each alphabetically
+    fn read_dir(&mut self) {
+        self.error = None;
+        self.entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| BrowserEntry {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    // Links count as what they point to
+                    is_dir: entry.path().is_dir(),
+                })
+                .filter(|entry| self.show_hidden || !entry.name.starts_with('.'))
+                .collect(),
+            Err(e) => {
+                self.error = Some(format!("Cannot read {}: {}", self.dir.display(), e));
+                Vec::new()
+            }
+        };
+        self.entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, keymap: &Keymap, theme: &Theme) {
+        frame.render_widget(Clear, area);
+
+        let relative = self.dir.strip_prefix(&self.root).unwrap_or(&self.dir);
+        let title = format!(
+            "Add Path - ~/{}{}",
+            relative.display(),
+            if self.show_hidden { " (hidden shown)" } else { "" }
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_alignment(Alignment::Center);
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(2)])
+            .split(inner_area);
+
+        let height = chunks[0].height as usize;
+        let offset = self.selected.saturating_sub(height.saturating_sub(1));
+        let lines: Vec<Line> = match &self.error {
+            Some(error) => vec![Line::from(Span::styled(error.clone(), theme.style(Tone::Danger)))],
+            None if self.entries.is_empty() => vec![Line::from(Span::styled("Empty directory", theme.muted()))],
+            None => self.entries
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(height)
+                .map(|(i, entry)| {
+                    let text = if entry.is_dir {
+                        format!("{} {}/", theme.icon(Icon::Folder), entry.name)
+                    } else {
+                        format!("{} {}", " ".repeat(display_width(theme.icon(Icon::Folder))), entry.name)
+                    };
+                    let style = if i == self.selected { theme.selection() } else { Style::default() };
+                    Line::from(Span::styled(text, style))
+                })
+                .collect(),
+        };
+        frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+        let hints = keymap.hints(KeyContext::FileBrowser, |_| true);
+        frame.render_widget(
+            Paragraph::new(Line::from(hint_spans(&hints, theme))).wrap(Wrap { trim: true }),
+            chunks[1],
+        );
+    }
+}
+
 /// Loading spinner widget
 pub struct LoadingSpinner {
     frames: Vec<&'static str>,
@@ -414,12 +647,17 @@ impl LoadingSpinner {
         self.current_frame = (self.current_frame + 1) % self.frames.len();
     }
 
-    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, message: &str) {
-        let spinner_text = format!("{} {}", self.frames[self.current_frame], message);
+    pub fn render(&self, frame: &mut ratatui::Frame, area: Rect, message: &str, theme: &Theme) {
+        let spinner_frame = if theme.ascii {
+            ["|", "/", "-", "\\"][self.current_frame % 4]
+        } else {
+            self.frames[self.current_frame]
+        };
+        let spinner_text = format!("{} {}", spinner_frame, message);
         
         let spinner = Paragraph::new(spinner_text)
             .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD));
+            .style(theme.accent().add_modifier(Modifier::BOLD));
 
         frame.render_widget(spinner, area);
     }
@@ -429,4 +667,102 @@ impl Default for LoadingSpinner {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    #[tokio::test]
+    async fn test_strength_waits_for_typing_to_pause() {
+        let mut input = PasswordInput::new(true, true);
+        for c in "correct horse".chars() {
+            input.handle_key(None, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            input.poll_strength();
+        }
+        assert!(input.strength.is_none() && input.strength_pending.is_none());
+
+        tokio::time::sleep(STRENGTH_DEBOUNCE).await;
+        input.poll_strength();
+        assert!(input.strength_pending.is_some());
+
+        for _ in 0..100 {
+            input.poll_strength();
+            if input.strength.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(input.strength.as_ref().is_some_and(|strength| strength.score > 0));
+
+        // Clearing the field drops the shown strength straight away
+        for _ in 0.."correct horse".len() {
+            input.handle_key(None, KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
+        }
+        assert!(input.strength.is_none());
+    }
+
+    #[test]
+    fn test_passwords_with_non_ascii_characters_can_be_edited() {
+        let mut input = PasswordInput::new(false, true);
+        let press = |input: &mut PasswordInput, code: KeyCode| input.handle_key(None, KeyEvent::new(code, KeyModifiers::NONE));
+        let type_text = |input: &mut PasswordInput, text: &str| {
+            for c in text.chars() {
+                press(input, KeyCode::Char(c));
+            }
+        };
+
+        // The cursor counts characters, so editing mid-word never lands
+        // inside a multi-byte one
+        type_text(&mut input, "pässwört");
+        for _ in 0..3 {
+            press(&mut input, KeyCode::Left);
+        }
+        press(&mut input, KeyCode::Backspace);
+        type_text(&mut input, "ü");
+        for _ in 0..10 {
+            press(&mut input, KeyCode::Right);
+        }
+        type_text(&mut input, "😀");
+        assert_eq!(input.input, "pässüört😀");
+
+        input.handle_key(Some(Action::NextField), KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        type_text(&mut input, "pässüört😀");
+        let password = input.handle_key(Some(Action::Confirm), KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(password.unwrap().as_bytes(), "pässüört😀".as_bytes());
+    }
+
+    #[test]
+    fn test_file_browser() {
+        let root = std::env::temp_dir().join(format!("file-browser-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects/app")).unwrap();
+        fs::create_dir_all(root.join(".config")).unwrap();
+        fs::write(root.join("notes.txt"), "todo\n").unwrap();
+        fs::write(root.join("Projects/app/main.rs"), "fn main() {}\n").unwrap();
+
+        let mut browser = FileBrowser::new(root.clone());
+        let names = |browser: &FileBrowser| browser.entries.iter().map(|entry| entry.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&browser), vec!["Projects", "notes.txt"]);
+        // Showing hidden files keeps the highlight where it was
+        browser.handle_action(Action::Hidden);
+        assert_eq!(names(&browser), vec![".config", "Projects", "notes.txt"]);
+        assert_eq!(browser.selected, 1);
+
+        // Enter opens directories and picks files
+        browser.handle_action(Action::Confirm);
+        browser.handle_action(Action::Confirm);
+        assert_eq!(browser.handle_action(Action::Confirm), Some(PathBuf::from("Projects/app/main.rs")));
+
+        // Going up highlights the directory left, and stops at the root
+        browser.handle_action(Action::Left);
+        assert_eq!(browser.handle_action(Action::Toggle), Some(PathBuf::from("Projects/app")));
+        for _ in 0..3 {
+            browser.handle_action(Action::Left);
+        }
+        assert_eq!(browser.dir, root);
+        assert_eq!(browser.handle_action(Action::Toggle), Some(PathBuf::from("Projects")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}